@@ -60,6 +60,7 @@ fn build_libmupdf() {
     let target_features = features_var.split(',').collect::<Vec<_>>();
 
     let profile = match &*env::var("PROFILE").unwrap_or("debug".to_owned()) {
+        _ if cfg!(feature = "memento") => "memento",
         "bench" | "release" => "release",
         _ => "debug",
     };
@@ -87,6 +88,12 @@ fn build_libmupdf() {
     build.define("FZ_ENABLE_HTML", Some("0"));
     #[cfg(not(feature = "epub"))]
     build.define("FZ_ENABLE_EPUB", Some("0"));
+    #[cfg(not(feature = "mobi"))]
+    build.define("FZ_ENABLE_MOBI", Some("0"));
+    #[cfg(not(feature = "fb2"))]
+    build.define("FZ_ENABLE_FB2", Some("0"));
+    #[cfg(not(feature = "txt"))]
+    build.define("FZ_ENABLE_TXT", Some("0"));
     #[cfg(not(feature = "js"))]
     build.define("FZ_ENABLE_JS", Some("0"));
 
@@ -296,6 +303,15 @@ fn build_libmupdf() {
         if cfg!(not(feature = "epub")) {
             cl_env.push("/DFZ_ENABLE_EPUB#0".to_string());
         }
+        if cfg!(not(feature = "mobi")) {
+            cl_env.push("/DFZ_ENABLE_MOBI#0".to_string());
+        }
+        if cfg!(not(feature = "fb2")) {
+            cl_env.push("/DFZ_ENABLE_FB2#0".to_string());
+        }
+        if cfg!(not(feature = "txt")) {
+            cl_env.push("/DFZ_ENABLE_TXT#0".to_string());
+        }
         if cfg!(not(feature = "js")) {
             cl_env.push("/DFZ_ENABLE_JS#0".to_string());
         }
@@ -509,6 +525,21 @@ impl bindgen::callbacks::ParseCallbacks for Callback {
     }
 }
 
+/// Where to find the FreeType headers, for the wrapper functions using FreeType APIs MuPDF doesn't
+/// wrap.
+fn freetype_include_paths() -> Vec<PathBuf> {
+    #[cfg(any(feature = "sys-lib", feature = "sys-lib-freetype"))]
+    {
+        pkg_config::probe_library("freetype2")
+            .unwrap()
+            .include_paths
+    }
+    #[cfg(not(any(feature = "sys-lib", feature = "sys-lib-freetype")))]
+    {
+        vec![PathBuf::from("./mupdf/thirdparty/freetype/include")]
+    }
+}
+
 fn main() {
     if fs::read_dir("mupdf").map_or(true, |d| d.count() == 0) {
         println!("The `mupdf` directory is empty, did you forget to pull the submodules?");
@@ -521,8 +552,15 @@ fn main() {
 
     build_libmupdf();
 
+    let freetype_include = freetype_include_paths();
+
     let mut build = cc::Build::new();
-    build.file("wrapper.c").include("./mupdf/include");
+    build
+        .file("wrapper.c")
+        .include("./mupdf/include")
+        .includes(&freetype_include);
+    #[cfg(feature = "memento")]
+    build.define("MEMENTO", None);
     if cfg!(target_os = "android") {
         build.flag("-DHAVE_ANDROID").flag_if_supported("-std=c99");
     }
@@ -534,6 +572,12 @@ fn main() {
 
     let bindings = bindgen::Builder::default()
         .clang_arg("-I./mupdf/include")
+        .clang_args(
+            freetype_include
+                .iter()
+                .map(|p| format!("-I{}", p.display())),
+        )
+        .clang_args(cfg!(feature = "memento").then_some("-DMEMENTO"))
         .header("wrapper.h")
         .header("wrapper.c")
         .allowlist_function("fz_.*")
@@ -1,5 +1,7 @@
 #include <stdint.h>
 #include <stdbool.h>
+#include <stdio.h>
+#include <stdlib.h>
 #include <string.h>
 #include <assert.h>
 #ifdef _WIN32
@@ -10,6 +12,10 @@
 
 #include "wrapper.h"
 
+#include <ft2build.h>
+#include FT_FREETYPE_H
+#include FT_MULTIPLE_MASTERS_H
+
 #ifdef HAVE_ANDROID
 #include "androidfonts.c"
 #endif
@@ -59,6 +65,8 @@ typedef struct mupdf_error
 static void mupdf_save_error(fz_context *ctx, mupdf_error_t **errptr)
 {
     assert(errptr != NULL);
+    // Report warnings leading up to the error first, so they can be attached to it
+    fz_flush_warnings(ctx);
     int type = fz_caught(ctx);
     const char *message = fz_caught_message(ctx);
     mupdf_error_t *err = malloc(sizeof(mupdf_error_t));
@@ -70,7 +78,7 @@ static void mupdf_save_error(fz_context *ctx, mupdf_error_t **errptr)
 static mupdf_error_t *mupdf_new_error_from_str(const char *message)
 {
     mupdf_error_t *err = malloc(sizeof(mupdf_error_t));
-    err->type = FZ_ERROR_GENERIC;
+    err->type = FZ_ERROR_ARGUMENT;
     err->message = strdup(message);
     return err;
 }
@@ -115,7 +123,7 @@ void mupdf_drop_base_context(fz_context *ctx)
     ctx = NULL;
 }
 
-fz_context *mupdf_new_base_context()
+fz_context *mupdf_new_base_context(const fz_alloc_context *alloc, size_t max_store)
 {
     int i;
     for (i = 0; i < FZ_LOCK_MAX; i++)
@@ -126,7 +134,11 @@ fz_context *mupdf_new_base_context()
         (void)pthread_mutex_init(&mutexes[i], NULL);
 #endif
     }
-    fz_context *ctx = fz_new_context(NULL, &locks, FZ_STORE_DEFAULT);
+#ifdef MEMENTO
+    // Memento only sees allocations made through the default allocator
+    alloc = NULL;
+#endif
+    fz_context *ctx = fz_new_context(alloc, &locks, max_store);
     if (!ctx)
     {
         mupdf_drop_base_context(ctx);
@@ -138,7 +150,7 @@ fz_context *mupdf_new_base_context()
     fz_catch(ctx) {
         mupdf_drop_base_context(ctx);
     }
-    // Disable default warning & error printing
+    // Disable default warning & error printing, the Rust side installs its own callbacks
     fz_set_warning_callback(ctx, NULL, NULL);
     fz_set_error_callback(ctx, NULL, NULL);
 #ifdef HAVE_ANDROID
@@ -150,6 +162,52 @@ fz_context *mupdf_new_base_context()
     return ctx;
 }
 
+/* Store */
+// MuPDF keeps `fz_store` private, so add up the item sizes listed by its debug dump instead
+size_t mupdf_store_size(fz_context *ctx, mupdf_error_t **errptr)
+{
+    fz_output *out = NULL;
+    fz_buffer *buf = NULL;
+    size_t size = 0;
+    fz_var(out);
+    fz_var(buf);
+    fz_try(ctx)
+    {
+        const char *line;
+        buf = fz_new_buffer(ctx, 1024);
+        out = fz_new_output_with_buffer(ctx, buf);
+        fz_debug_store(ctx, out);
+        fz_close_output(ctx, out);
+        line = fz_string_from_buffer(ctx, buf);
+        while (line)
+        {
+            const char *item = line;
+            if (!strncmp(item, "STORE\t", 6))
+                item += 6;
+            // Every item appears once in the LRU list and once more in the hash table dump
+            if (!strncmp(item, "store[", 6))
+            {
+                const char *item_size = strstr(item, "[size=");
+                if (item_size)
+                    size += strtoul(item_size + 6, NULL, 10);
+            }
+            line = strchr(line, '\n');
+            if (line)
+                line++;
+        }
+    }
+    fz_always(ctx)
+    {
+        fz_drop_output(ctx, out);
+        fz_drop_buffer(ctx, buf);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+    return size;
+}
+
 /* Rect */
 fz_rect mupdf_adjust_rect_for_stroke(fz_context *ctx, fz_rect self, fz_stroke_state *stroke, fz_matrix ctm, mupdf_error_t **errptr)
 {
@@ -182,6 +240,25 @@ fz_pixmap *mupdf_new_pixmap(fz_context *ctx, fz_colorspace *cs, int x, int y, in
     return pixmap;
 }
 
+/* A pixmap with the colorspace, separations, origin and resolution of src */
+fz_pixmap *mupdf_new_pixmap_like(fz_context *ctx, fz_pixmap *src, int w, int h, bool alpha, mupdf_error_t **errptr)
+{
+    fz_pixmap *pixmap = NULL;
+    fz_try(ctx)
+    {
+        pixmap = fz_new_pixmap(ctx, src->colorspace, w, h, src->seps, alpha);
+        pixmap->x = src->x;
+        pixmap->y = src->y;
+        pixmap->xres = src->xres;
+        pixmap->yres = src->yres;
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+    return pixmap;
+}
+
 fz_pixmap *mupdf_clone_pixmap(fz_context *ctx, fz_pixmap *self, mupdf_error_t **errptr)
 {
     fz_pixmap *pixmap = NULL;
@@ -196,6 +273,36 @@ fz_pixmap *mupdf_clone_pixmap(fz_context *ctx, fz_pixmap *self, mupdf_error_t **
     return pixmap;
 }
 
+fz_pixmap *mupdf_scale_pixmap(fz_context *ctx, fz_pixmap *self, float x, float y, float w, float h, fz_irect clip, bool has_clip, mupdf_error_t **errptr)
+{
+    fz_pixmap *pixmap = NULL;
+    fz_try(ctx)
+    {
+        pixmap = fz_scale_pixmap(ctx, self, x, y, w, h, has_clip ? &clip : NULL);
+        if (!pixmap)
+            fz_throw(ctx, FZ_ERROR_ARGUMENT, "cannot scale pixmap to %g x %g", w, h);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+    return pixmap;
+}
+
+fz_pixmap *mupdf_new_pixmap_from_pixmap(fz_context *ctx, fz_pixmap *self, fz_irect rect, mupdf_error_t **errptr)
+{
+    fz_pixmap *pixmap = NULL;
+    fz_try(ctx)
+    {
+        pixmap = fz_new_pixmap_from_pixmap(ctx, self, &rect);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+    return pixmap;
+}
+
 void mupdf_clear_pixmap(fz_context *ctx, fz_pixmap *pixmap, mupdf_error_t **errptr)
 {
     fz_try(ctx)
@@ -232,6 +339,30 @@ void mupdf_invert_pixmap(fz_context *ctx, fz_pixmap *pixmap, mupdf_error_t **err
     }
 }
 
+void mupdf_invert_pixmap_rect(fz_context *ctx, fz_pixmap *pixmap, fz_irect rect, mupdf_error_t **errptr)
+{
+    fz_try(ctx)
+    {
+        fz_invert_pixmap_rect(ctx, pixmap, rect);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+}
+
+void mupdf_invert_pixmap_luminance(fz_context *ctx, fz_pixmap *pixmap, mupdf_error_t **errptr)
+{
+    fz_try(ctx)
+    {
+        fz_invert_pixmap_luminance(ctx, pixmap);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+}
+
 void mupdf_gamma_pixmap(fz_context *ctx, fz_pixmap *pixmap, float gamma, mupdf_error_t **errptr)
 {
     if (!fz_pixmap_colorspace(ctx, pixmap))
@@ -299,6 +430,32 @@ void mupdf_save_pixmap_as(fz_context *ctx, fz_pixmap *pixmap, const char *filena
     }
 }
 
+fz_buffer *mupdf_pixmap_get_jpeg_data(fz_context *ctx, fz_pixmap *pixmap, int quality, mupdf_error_t **errptr)
+{
+    fz_output *out = NULL;
+    fz_buffer *buf = NULL;
+    fz_var(out);
+    fz_var(buf);
+    fz_try(ctx)
+    {
+        buf = fz_new_buffer(ctx, 1024);
+        out = fz_new_output_with_buffer(ctx, buf);
+        fz_write_pixmap_as_jpeg(ctx, out, pixmap, quality, 0);
+        fz_close_output(ctx, out);
+    }
+    fz_always(ctx)
+    {
+        fz_drop_output(ctx, out);
+    }
+    fz_catch(ctx)
+    {
+        fz_drop_buffer(ctx, buf);
+        buf = NULL;
+        mupdf_save_error(ctx, errptr);
+    }
+    return buf;
+}
+
 fz_buffer *mupdf_pixmap_get_image_data(fz_context *ctx, fz_pixmap *pixmap, int format, mupdf_error_t **errptr)
 {
     fz_output *out = NULL;
@@ -343,6 +500,111 @@ fz_buffer *mupdf_pixmap_get_image_data(fz_context *ctx, fz_pixmap *pixmap, int f
     return buf;
 }
 
+/* Printer output */
+fz_buffer *mupdf_pixmap_to_ps(fz_context *ctx, fz_pixmap *pixmap, mupdf_error_t **errptr)
+{
+    fz_output *out = NULL;
+    fz_buffer *buf = NULL;
+    fz_var(out);
+    fz_var(buf);
+    fz_try(ctx)
+    {
+        buf = fz_new_buffer(ctx, 8192);
+        out = fz_new_output_with_buffer(ctx, buf);
+        fz_write_ps_file_header(ctx, out);
+        fz_write_pixmap_as_ps(ctx, out, pixmap);
+        fz_write_ps_file_trailer(ctx, out, 1);
+        fz_close_output(ctx, out);
+    }
+    fz_always(ctx)
+    {
+        fz_drop_output(ctx, out);
+    }
+    fz_catch(ctx)
+    {
+        fz_drop_buffer(ctx, buf);
+        buf = NULL;
+        mupdf_save_error(ctx, errptr);
+    }
+    return buf;
+}
+
+void mupdf_pcl_preset(fz_context *ctx, fz_pcl_options *opts, const char *preset, mupdf_error_t **errptr)
+{
+    fz_try(ctx)
+    {
+        fz_pcl_preset(ctx, opts, preset);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+}
+
+fz_buffer *mupdf_pixmap_to_pcl(fz_context *ctx, fz_pixmap *pixmap, fz_pcl_options *opts, bool mono, mupdf_error_t **errptr)
+{
+    fz_output *out = NULL;
+    fz_buffer *buf = NULL;
+    fz_bitmap *bitmap = NULL;
+    fz_var(out);
+    fz_var(buf);
+    fz_var(bitmap);
+    fz_try(ctx)
+    {
+        buf = fz_new_buffer(ctx, 8192);
+        out = fz_new_output_with_buffer(ctx, buf);
+        if (mono)
+        {
+            bitmap = fz_new_bitmap_from_pixmap(ctx, pixmap, NULL);
+            fz_write_bitmap_as_pcl(ctx, out, bitmap, opts);
+        }
+        else
+        {
+            fz_write_pixmap_as_pcl(ctx, out, pixmap, opts);
+        }
+        fz_close_output(ctx, out);
+    }
+    fz_always(ctx)
+    {
+        fz_drop_bitmap(ctx, bitmap);
+        fz_drop_output(ctx, out);
+    }
+    fz_catch(ctx)
+    {
+        fz_drop_buffer(ctx, buf);
+        buf = NULL;
+        mupdf_save_error(ctx, errptr);
+    }
+    return buf;
+}
+
+fz_buffer *mupdf_pixmap_to_pwg(fz_context *ctx, fz_pixmap *pixmap, const fz_pwg_options *opts, mupdf_error_t **errptr)
+{
+    fz_output *out = NULL;
+    fz_buffer *buf = NULL;
+    fz_var(out);
+    fz_var(buf);
+    fz_try(ctx)
+    {
+        buf = fz_new_buffer(ctx, 8192);
+        out = fz_new_output_with_buffer(ctx, buf);
+        fz_write_pwg_file_header(ctx, out);
+        fz_write_pixmap_as_pwg(ctx, out, pixmap, opts);
+        fz_close_output(ctx, out);
+    }
+    fz_always(ctx)
+    {
+        fz_drop_output(ctx, out);
+    }
+    fz_catch(ctx)
+    {
+        fz_drop_buffer(ctx, buf);
+        buf = NULL;
+        mupdf_save_error(ctx, errptr);
+    }
+    return buf;
+}
+
 /* Font */
 fz_font *mupdf_new_font(fz_context *ctx, const char *name, int index, mupdf_error_t **errptr)
 {
@@ -383,6 +645,42 @@ fz_font *mupdf_new_font_from_buffer(fz_context *ctx, const char *name, int index
     return font;
 }
 
+fz_font *mupdf_new_font_instance_from_buffer(fz_context *ctx, const char *name, int index, fz_buffer *buffer, const float *coords, int n, mupdf_error_t **errptr)
+{
+    fz_font *font = NULL;
+    FT_Fixed *fixed = NULL;
+    FT_Error error;
+    fz_var(font);
+    fz_var(fixed);
+    fz_try(ctx)
+    {
+        font = fz_new_font_from_buffer(ctx, name, buffer, index, 0);
+        fixed = fz_malloc_array(ctx, n, FT_Fixed);
+        for (int i = 0; i < n; i++)
+        {
+            fixed[i] = (FT_Fixed)(coords[i] * 65536.0f);
+        }
+        fz_lock(ctx, FZ_LOCK_FREETYPE);
+        error = FT_Set_Var_Design_Coordinates(fz_font_ft_face(ctx, font), n, fixed);
+        fz_unlock(ctx, FZ_LOCK_FREETYPE);
+        if (error)
+        {
+            fz_throw(ctx, FZ_ERROR_ARGUMENT, "cannot set variation coordinates (not a variable font?)");
+        }
+    }
+    fz_always(ctx)
+    {
+        fz_free(ctx, fixed);
+    }
+    fz_catch(ctx)
+    {
+        fz_drop_font(ctx, font);
+        font = NULL;
+        mupdf_save_error(ctx, errptr);
+    }
+    return font;
+}
+
 int mupdf_encode_character(fz_context *ctx, fz_font *font, int unicode, mupdf_error_t **errptr)
 {
     int glyph = 0;
@@ -743,57 +1041,176 @@ fz_pixmap *mupdf_page_to_pixmap(fz_context *ctx, fz_page *page, fz_matrix ctm, f
     return pixmap;
 }
 
-fz_buffer *mupdf_page_to_svg(fz_context *ctx, fz_page *page, fz_matrix ctm, fz_cookie *cookie, mupdf_error_t **errptr)
+fz_pixmap *mupdf_page_to_pixmap_with_overprint(fz_context *ctx, fz_page *page, fz_matrix ctm, fz_colorspace *cs, bool alpha, bool show_extras, mupdf_error_t **errptr)
 {
-    fz_rect mediabox = fz_bound_page(ctx, page);
-    fz_device *dev = NULL;
-    fz_buffer *buf = NULL;
-    fz_output *out = NULL;
-    fz_var(out);
-    fz_var(dev);
-    fz_var(buf);
-    fz_rect tbounds = mediabox;
-    tbounds = fz_transform_rect(tbounds, ctm);
+    fz_pixmap *pixmap = NULL;
+    fz_separations *seps = NULL;
+    fz_var(seps);
     fz_try(ctx)
     {
-        buf = fz_new_buffer(ctx, 1024);
-        out = fz_new_output_with_buffer(ctx, buf);
-        dev = fz_new_svg_device(ctx, out, tbounds.x1 - tbounds.x0, tbounds.y1 - tbounds.y0, FZ_SVG_TEXT_AS_PATH, 1);
-        fz_run_page(ctx, page, dev, ctm, cookie);
-        fz_close_device(ctx, dev);
+        /* Render spot colors through their process equivalents, so they overprint too */
+        seps = fz_page_separations(ctx, page);
+        if (seps)
+        {
+            int i, n = fz_count_separations(ctx, seps);
+            for (i = 0; i < n; i++)
+                fz_set_separation_behavior(ctx, seps, i, FZ_SEPARATION_COMPOSITE);
+        }
+        else if (fz_page_uses_overprint(ctx, page))
+        {
+            /* Separations, even empty ones, turn on the overprint simulation of the draw device */
+            seps = fz_new_separations(ctx, 0);
+        }
+        if (show_extras)
+        {
+            pixmap = fz_new_pixmap_from_page_with_separations(ctx, page, ctm, cs, seps, alpha);
+        }
+        else
+        {
+            pixmap = fz_new_pixmap_from_page_contents_with_separations(ctx, page, ctm, cs, seps, alpha);
+        }
     }
     fz_always(ctx)
     {
-        fz_drop_device(ctx, dev);
-        fz_drop_output(ctx, out);
+        fz_drop_separations(ctx, seps);
     }
     fz_catch(ctx)
     {
         mupdf_save_error(ctx, errptr);
     }
-    return buf;
+    return pixmap;
 }
 
-fz_stext_page *mupdf_page_to_text_page(fz_context *ctx, fz_page *page, int flags, mupdf_error_t **errptr)
+fz_pixmap *mupdf_page_to_pixmap_proofed(fz_context *ctx, fz_page *page, fz_matrix ctm, fz_colorspace *display_cs, fz_colorspace *proof_cs, fz_color_params params, bool alpha, bool show_extras, mupdf_error_t **errptr)
 {
-    fz_stext_page *text_page = NULL;
-    fz_stext_options opts = {0};
-    opts.flags = flags;
+    fz_pixmap *proof = NULL;
+    fz_pixmap *pixmap = NULL;
+    fz_var(proof);
     fz_try(ctx)
     {
-        text_page = fz_new_stext_page_from_page(ctx, page, &opts);
+        if (show_extras)
+        {
+            proof = fz_new_pixmap_from_page(ctx, page, ctm, proof_cs, alpha);
+        }
+        else
+        {
+            proof = fz_new_pixmap_from_page_contents(ctx, page, ctm, proof_cs, alpha);
+        }
+        pixmap = fz_convert_pixmap(ctx, proof, display_cs, proof_cs, NULL, params, 1);
+    }
+    fz_always(ctx)
+    {
+        fz_drop_pixmap(ctx, proof);
     }
     fz_catch(ctx)
     {
         mupdf_save_error(ctx, errptr);
     }
-    return text_page;
+    return pixmap;
 }
 
-fz_display_list *mupdf_page_to_display_list(fz_context *ctx, fz_page *page, bool annots, mupdf_error_t **errptr)
+static fz_device *mupdf_new_forwarding_device(fz_context *ctx, fz_device *target, int color_glyphs, int skip, bool fixed_default_colorspaces);
+
+fz_pixmap *mupdf_page_to_pixmap_with_default_colorspaces(fz_context *ctx, fz_page *page, fz_matrix ctm, fz_colorspace *cs, bool alpha, bool show_extras, fz_default_colorspaces *dcs, mupdf_error_t **errptr)
 {
-    fz_display_list *list = NULL;
-    fz_try(ctx)
+    fz_pixmap *pixmap = NULL;
+    fz_device *draw = NULL;
+    fz_device *dev = NULL;
+    fz_var(pixmap);
+    fz_var(draw);
+    fz_var(dev);
+    fz_try(ctx)
+    {
+        fz_irect bbox = fz_round_rect(fz_transform_rect(fz_bound_page(ctx, page), ctm));
+        pixmap = fz_new_pixmap_with_bbox(ctx, cs, bbox, NULL, alpha);
+        if (alpha)
+        {
+            fz_clear_pixmap(ctx, pixmap);
+        }
+        else
+        {
+            fz_clear_pixmap_with_value(ctx, pixmap, 0xFF);
+        }
+        draw = fz_new_draw_device(ctx, ctm, pixmap);
+        fz_set_default_colorspaces(ctx, draw, dcs);
+        /* Keep the interpreter from replacing them by those of the page */
+        dev = mupdf_new_forwarding_device(ctx, draw, 0, 0, true);
+        if (show_extras)
+        {
+            fz_run_page(ctx, page, dev, fz_identity, NULL);
+        }
+        else
+        {
+            fz_run_page_contents(ctx, page, dev, fz_identity, NULL);
+        }
+        fz_close_device(ctx, dev);
+        fz_close_device(ctx, draw);
+    }
+    fz_always(ctx)
+    {
+        fz_drop_device(ctx, dev);
+        fz_drop_device(ctx, draw);
+    }
+    fz_catch(ctx)
+    {
+        fz_drop_pixmap(ctx, pixmap);
+        pixmap = NULL;
+        mupdf_save_error(ctx, errptr);
+    }
+    return pixmap;
+}
+
+fz_buffer *mupdf_page_to_svg(fz_context *ctx, fz_page *page, fz_matrix ctm, int text_format, int reuse_images, fz_cookie *cookie, mupdf_error_t **errptr)
+{
+    fz_rect mediabox = fz_bound_page(ctx, page);
+    fz_device *dev = NULL;
+    fz_buffer *buf = NULL;
+    fz_output *out = NULL;
+    fz_var(out);
+    fz_var(dev);
+    fz_var(buf);
+    fz_rect tbounds = mediabox;
+    tbounds = fz_transform_rect(tbounds, ctm);
+    fz_try(ctx)
+    {
+        buf = fz_new_buffer(ctx, 1024);
+        out = fz_new_output_with_buffer(ctx, buf);
+        dev = fz_new_svg_device(ctx, out, tbounds.x1 - tbounds.x0, tbounds.y1 - tbounds.y0, text_format, reuse_images);
+        fz_run_page(ctx, page, dev, ctm, cookie);
+        fz_close_device(ctx, dev);
+    }
+    fz_always(ctx)
+    {
+        fz_drop_device(ctx, dev);
+        fz_drop_output(ctx, out);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+    return buf;
+}
+
+fz_stext_page *mupdf_page_to_text_page(fz_context *ctx, fz_page *page, int flags, mupdf_error_t **errptr)
+{
+    fz_stext_page *text_page = NULL;
+    fz_stext_options opts = {0};
+    opts.flags = flags;
+    fz_try(ctx)
+    {
+        text_page = fz_new_stext_page_from_page(ctx, page, &opts);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+    return text_page;
+}
+
+fz_display_list *mupdf_page_to_display_list(fz_context *ctx, fz_page *page, bool annots, mupdf_error_t **errptr)
+{
+    fz_display_list *list = NULL;
+    fz_try(ctx)
     {
         if (annots)
         {
@@ -859,6 +1276,178 @@ void mupdf_run_page_widgets(fz_context *ctx, fz_page *page, fz_device *device, f
     }
 }
 
+fz_image *mupdf_load_page_thumbnail(fz_context *ctx, fz_page *page, mupdf_error_t **errptr)
+{
+    fz_image *image = NULL;
+    fz_try(ctx)
+    {
+        pdf_page *pdf = pdf_page_from_fz_page(ctx, page);
+        if (pdf)
+        {
+            pdf_obj *thumb = pdf_dict_get(ctx, pdf->obj, PDF_NAME(Thumb));
+            if (pdf_is_stream(ctx, thumb))
+                image = pdf_load_image(ctx, pdf->doc, thumb);
+        }
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+    return image;
+}
+
+fz_matrix mupdf_page_pdf_transform(fz_context *ctx, fz_page *page, mupdf_error_t **errptr)
+{
+    fz_matrix ctm = fz_identity;
+    fz_try(ctx)
+    {
+        pdf_page *pdf = pdf_page_from_fz_page(ctx, page);
+        if (pdf)
+        {
+            pdf_page_transform(ctx, pdf, NULL, &ctm);
+        }
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+    return ctm;
+}
+
+float mupdf_page_user_unit(fz_context *ctx, fz_page *page, mupdf_error_t **errptr)
+{
+    float unit = 1;
+    fz_try(ctx)
+    {
+        pdf_page *pdf = pdf_page_from_fz_page(ctx, page);
+        if (pdf)
+        {
+            unit = pdf_dict_get_real_default(ctx, pdf->obj, PDF_NAME(UserUnit), 1);
+        }
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+    return unit > 0 ? unit : 1;
+}
+
+static void mupdf_digest_pdf_obj(fz_context *ctx, fz_md5 *md5, pdf_obj *obj, unsigned char *seen, int len, pdf_obj *page)
+{
+    pdf_obj *ref = obj;
+    unsigned char tag;
+    if (pdf_is_indirect(ctx, obj))
+    {
+        int num = pdf_to_num(ctx, obj);
+        fz_md5_update(md5, (const unsigned char *)&num, sizeof num);
+        if (num <= 0 || num >= len || seen[num])
+            return;
+        seen[num] = 1;
+        obj = pdf_resolve_indirect(ctx, obj);
+        // Other pages, e.g. link destinations, don't change how this one looks
+        if (obj != page && pdf_name_eq(ctx, pdf_dict_get(ctx, obj, PDF_NAME(Type)), PDF_NAME(Page)))
+            return;
+    }
+
+    if (pdf_is_dict(ctx, obj))
+    {
+        int n = pdf_dict_len(ctx, obj);
+        fz_md5_update(md5, (const unsigned char *)"<<", 2);
+        for (int i = 0; i < n; i++)
+        {
+            pdf_obj *key = pdf_dict_get_key(ctx, obj, i);
+            // The page tree and field hierarchy lead to everything else
+            if (pdf_name_eq(ctx, key, PDF_NAME(Parent)) || pdf_name_eq(ctx, key, PDF_NAME(P)))
+                continue;
+            mupdf_digest_pdf_obj(ctx, md5, key, seen, len, page);
+            mupdf_digest_pdf_obj(ctx, md5, pdf_dict_get_val(ctx, obj, i), seen, len, page);
+        }
+        // The data of every stream is read, an image may be replaced by one of the same size
+        if (pdf_is_stream(ctx, ref))
+        {
+            fz_buffer *buf = pdf_load_raw_stream(ctx, ref);
+            unsigned char *data;
+            size_t size = fz_buffer_storage(ctx, buf, &data);
+            fz_md5_update(md5, data, size);
+            fz_drop_buffer(ctx, buf);
+        }
+    }
+    else if (pdf_is_array(ctx, obj))
+    {
+        int n = pdf_array_len(ctx, obj);
+        fz_md5_update(md5, (const unsigned char *)"[", 1);
+        for (int i = 0; i < n; i++)
+            mupdf_digest_pdf_obj(ctx, md5, pdf_array_get(ctx, obj, i), seen, len, page);
+    }
+    else if (pdf_is_name(ctx, obj))
+    {
+        const char *name = pdf_to_name(ctx, obj);
+        fz_md5_update(md5, (const unsigned char *)"/", 1);
+        fz_md5_update(md5, (const unsigned char *)name, strlen(name) + 1);
+    }
+    else if (pdf_is_string(ctx, obj))
+    {
+        size_t size = pdf_to_str_len(ctx, obj);
+        fz_md5_update(md5, (const unsigned char *)"(", 1);
+        fz_md5_update(md5, (const unsigned char *)&size, sizeof size);
+        fz_md5_update(md5, (const unsigned char *)pdf_to_str_buf(ctx, obj), size);
+    }
+    else if (pdf_is_int(ctx, obj))
+    {
+        int64_t value = pdf_to_int64(ctx, obj);
+        fz_md5_update(md5, (const unsigned char *)"i", 1);
+        fz_md5_update(md5, (const unsigned char *)&value, sizeof value);
+    }
+    else if (pdf_is_real(ctx, obj))
+    {
+        float value = pdf_to_real(ctx, obj);
+        fz_md5_update(md5, (const unsigned char *)"r", 1);
+        fz_md5_update(md5, (const unsigned char *)&value, sizeof value);
+    }
+    else
+    {
+        tag = pdf_is_bool(ctx, obj) ? (pdf_to_bool(ctx, obj) ? 't' : 'f') : 'n';
+        fz_md5_update(md5, &tag, 1);
+    }
+}
+
+/* Digest of the objects a PDF page is drawn from, leaving out the page tree and other pages, to
+ * tell whether a rendering of the page is stale. Returns false for other kinds of documents. */
+bool mupdf_page_digest(fz_context *ctx, fz_page *page, unsigned char digest[16], mupdf_error_t **errptr)
+{
+    pdf_page *pdf = pdf_page_from_fz_page(ctx, page);
+    unsigned char *seen = NULL;
+    fz_md5 md5;
+    if (!pdf)
+        return false;
+    fz_var(seen);
+    fz_try(ctx)
+    {
+        int len = pdf_xref_len(ctx, pdf->doc);
+        pdf_obj *dict = pdf_resolve_indirect(ctx, pdf->obj);
+        seen = fz_calloc(ctx, len, 1);
+        fz_md5_init(&md5);
+        mupdf_digest_pdf_obj(ctx, &md5, pdf->obj, seen, len, dict);
+        // What the page inherits through the page tree
+        mupdf_digest_pdf_obj(ctx, &md5, pdf_dict_get_inheritable(ctx, dict, PDF_NAME(Resources)), seen, len, dict);
+        mupdf_digest_pdf_obj(ctx, &md5, pdf_dict_get_inheritable(ctx, dict, PDF_NAME(MediaBox)), seen, len, dict);
+        mupdf_digest_pdf_obj(ctx, &md5, pdf_dict_get_inheritable(ctx, dict, PDF_NAME(CropBox)), seen, len, dict);
+        mupdf_digest_pdf_obj(ctx, &md5, pdf_dict_get_inheritable(ctx, dict, PDF_NAME(Rotate)), seen, len, dict);
+        // Which optional content is shown
+        mupdf_digest_pdf_obj(ctx, &md5, pdf_dict_getp(ctx, pdf_trailer(ctx, pdf->doc), "Root/OCProperties"), seen, len, dict);
+        fz_md5_final(&md5, digest);
+    }
+    fz_always(ctx)
+    {
+        fz_free(ctx, seen);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+    return true;
+}
+
 fz_buffer *mupdf_page_to_html(fz_context *ctx, fz_page *page, mupdf_error_t **errptr)
 {
     fz_buffer *buf = NULL;
@@ -1137,6 +1726,96 @@ void mupdf_convert_color(fz_context *ctx, fz_colorspace *ss, const float *sv, fz
     }
 }
 
+fz_default_colorspaces *mupdf_new_default_colorspaces(fz_context *ctx, mupdf_error_t **errptr)
+{
+    fz_default_colorspaces *dcs = NULL;
+    fz_try(ctx)
+    {
+        dcs = fz_new_default_colorspaces(ctx);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+    return dcs;
+}
+
+static void mupdf_set_default_colorspace(fz_context *ctx, fz_default_colorspaces *dcs, fz_colorspace *cs, bool output_intent)
+{
+    if (output_intent)
+    {
+        fz_set_default_output_intent(ctx, dcs, cs);
+        return;
+    }
+    switch (fz_colorspace_type(ctx, cs))
+    {
+    case FZ_COLORSPACE_GRAY:
+        fz_set_default_gray(ctx, dcs, cs);
+        break;
+    case FZ_COLORSPACE_RGB:
+        fz_set_default_rgb(ctx, dcs, cs);
+        break;
+    case FZ_COLORSPACE_CMYK:
+        fz_set_default_cmyk(ctx, dcs, cs);
+        break;
+    default:
+        fz_throw(ctx, FZ_ERROR_ARGUMENT, "default colorspaces must be gray, RGB or CMYK");
+    }
+}
+
+void mupdf_default_colorspaces_set(fz_context *ctx, fz_default_colorspaces *dcs, fz_colorspace *cs, bool output_intent, mupdf_error_t **errptr)
+{
+    fz_try(ctx)
+    {
+        mupdf_set_default_colorspace(ctx, dcs, cs, output_intent);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+}
+
+void mupdf_default_colorspaces_load_icc(fz_context *ctx, fz_default_colorspaces *dcs, const unsigned char *data, size_t len, bool output_intent, mupdf_error_t **errptr)
+{
+    fz_buffer *buf = NULL;
+    fz_colorspace *cs = NULL;
+    fz_var(buf);
+    fz_var(cs);
+    fz_try(ctx)
+    {
+        buf = fz_new_buffer_from_copied_data(ctx, data, len);
+        cs = fz_new_icc_colorspace(ctx, FZ_COLORSPACE_NONE, 0, NULL, buf);
+        mupdf_set_default_colorspace(ctx, dcs, cs, output_intent);
+    }
+    fz_always(ctx)
+    {
+        fz_drop_colorspace(ctx, cs);
+        fz_drop_buffer(ctx, buf);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+}
+
+/* The ICC profile of a colorspace, or NULL for colorspaces without one */
+fz_buffer *mupdf_colorspace_icc_profile(fz_context *ctx, fz_colorspace *cs, mupdf_error_t **errptr)
+{
+    fz_buffer *buf = NULL;
+#if FZ_ENABLE_ICC
+    fz_try(ctx)
+    {
+        if (cs->flags & FZ_COLORSPACE_IS_ICC)
+            buf = fz_keep_buffer(ctx, cs->u.icc.buffer);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+#endif
+    return buf;
+}
+
 /* DisplayList */
 fz_display_list *mupdf_new_display_list(fz_context *ctx, fz_rect mediabox, mupdf_error_t **errptr)
 {
@@ -1194,6 +1873,50 @@ void mupdf_display_list_run(fz_context *ctx, fz_display_list *list, fz_device *d
     }
 }
 
+static void mupdf_decode_image(fz_context *ctx, fz_image *image)
+{
+    /* Decoding at full resolution leaves a tile in the store that any later scale can reuse */
+    fz_drop_pixmap(ctx, fz_get_pixmap_from_image(ctx, image, NULL, NULL, NULL, NULL));
+}
+
+static void mupdf_decode_fill_image(fz_context *ctx, fz_device *dev, fz_image *image, fz_matrix ctm, float alpha, fz_color_params color_params)
+{
+    mupdf_decode_image(ctx, image);
+}
+
+static void mupdf_decode_fill_image_mask(fz_context *ctx, fz_device *dev, fz_image *image, fz_matrix ctm, fz_colorspace *cs, const float *color, float alpha, fz_color_params color_params)
+{
+    mupdf_decode_image(ctx, image);
+}
+
+static void mupdf_decode_clip_image_mask(fz_context *ctx, fz_device *dev, fz_image *image, fz_matrix ctm, fz_rect scissor)
+{
+    mupdf_decode_image(ctx, image);
+}
+
+void mupdf_display_list_decode_images(fz_context *ctx, fz_display_list *list, mupdf_error_t **errptr)
+{
+    fz_device *dev = NULL;
+    fz_var(dev);
+    fz_try(ctx)
+    {
+        dev = fz_new_derived_device(ctx, fz_device);
+        dev->fill_image = mupdf_decode_fill_image;
+        dev->fill_image_mask = mupdf_decode_fill_image_mask;
+        dev->clip_image_mask = mupdf_decode_clip_image_mask;
+        fz_run_display_list(ctx, list, dev, fz_identity, fz_infinite_rect, NULL);
+        fz_close_device(ctx, dev);
+    }
+    fz_always(ctx)
+    {
+        fz_drop_device(ctx, dev);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+}
+
 fz_quad *mupdf_search_display_list(fz_context *ctx, fz_display_list *list, const char *needle, const int hit_max, int *hit_count, mupdf_error_t **errptr)
 {
     fz_quad *result = NULL;
@@ -1470,26 +2193,25 @@ pdf_obj *mupdf_pdf_new_name(fz_context *ctx, const char *name, mupdf_error_t **e
     return obj;
 }
 
-pdf_obj *mupdf_pdf_new_indirect(fz_context *ctx, pdf_document *pdf, int num, int gen, mupdf_error_t **errptr)
+/* The version written to the file header, e.g. 17 for PDF 1.7 */
+void mupdf_pdf_set_version(fz_context *ctx, pdf_document *pdf, int version, mupdf_error_t **errptr)
 {
-    pdf_obj *obj = NULL;
     fz_try(ctx)
     {
-        obj = pdf_new_indirect(ctx, pdf, num, gen);
+        pdf->version = version;
     }
     fz_catch(ctx)
     {
         mupdf_save_error(ctx, errptr);
     }
-    return obj;
 }
 
-pdf_obj *mupdf_pdf_new_array(fz_context *ctx, pdf_document *pdf, int capacity, mupdf_error_t **errptr)
+pdf_obj *mupdf_pdf_new_indirect(fz_context *ctx, pdf_document *pdf, int num, int gen, mupdf_error_t **errptr)
 {
     pdf_obj *obj = NULL;
     fz_try(ctx)
     {
-        obj = pdf_new_array(ctx, pdf, capacity);
+        obj = pdf_new_indirect(ctx, pdf, num, gen);
     }
     fz_catch(ctx)
     {
@@ -1498,21 +2220,51 @@ pdf_obj *mupdf_pdf_new_array(fz_context *ctx, pdf_document *pdf, int capacity, m
     return obj;
 }
 
-pdf_obj *mupdf_pdf_new_dict(fz_context *ctx, pdf_document *pdf, int capacity, mupdf_error_t **errptr)
+int mupdf_pdf_object_generation(fz_context *ctx, pdf_document *pdf, int num, mupdf_error_t **errptr)
 {
-    pdf_obj *obj = NULL;
+    int gen = 0;
     fz_try(ctx)
     {
-        obj = pdf_new_dict(ctx, pdf, capacity);
+        if (num <= 0 || num >= pdf_xref_len(ctx, pdf))
+            fz_throw(ctx, FZ_ERROR_ARGUMENT, "object number %d out of range", num);
+        gen = pdf_get_xref_entry_no_null(ctx, pdf, num)->gen;
     }
     fz_catch(ctx)
     {
         mupdf_save_error(ctx, errptr);
     }
-    return obj;
+    return gen;
 }
 
-pdf_obj *mupdf_pdf_obj_from_str(fz_context *ctx, pdf_document *pdf, const char *src, mupdf_error_t **errptr)
+pdf_obj *mupdf_pdf_new_array(fz_context *ctx, pdf_document *pdf, int capacity, mupdf_error_t **errptr)
+{
+    pdf_obj *obj = NULL;
+    fz_try(ctx)
+    {
+        obj = pdf_new_array(ctx, pdf, capacity);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+    return obj;
+}
+
+pdf_obj *mupdf_pdf_new_dict(fz_context *ctx, pdf_document *pdf, int capacity, mupdf_error_t **errptr)
+{
+    pdf_obj *obj = NULL;
+    fz_try(ctx)
+    {
+        obj = pdf_new_dict(ctx, pdf, capacity);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+    return obj;
+}
+
+pdf_obj *mupdf_pdf_obj_from_str(fz_context *ctx, pdf_document *pdf, const char *src, mupdf_error_t **errptr)
 {
     pdf_obj *obj = NULL;
     pdf_lexbuf lexbuf;
@@ -1787,6 +2539,60 @@ void mupdf_pdf_write_stream_buffer(fz_context *ctx, pdf_obj *obj, fz_buffer *buf
     }
 }
 
+/* Encode data with a chain of filters, the first of which is decoded first */
+fz_buffer *mupdf_encode_stream(fz_context *ctx, fz_buffer *data, const int *filters, int n, mupdf_error_t **errptr)
+{
+    fz_buffer *buf = NULL;
+    fz_output *outs[9] = { NULL };
+    int i;
+    if (n < 0 || n > 8)
+    {
+        *errptr = mupdf_new_error_from_str("too many filters");
+        return NULL;
+    }
+    fz_var(buf);
+    fz_try(ctx)
+    {
+        buf = fz_new_buffer(ctx, fz_buffer_storage(ctx, data, NULL) + 64);
+        outs[0] = fz_new_output_with_buffer(ctx, buf);
+        for (i = 0; i < n; i++)
+        {
+            switch (filters[i])
+            {
+            case 0:
+                outs[i + 1] = fz_new_deflate_output(ctx, outs[i], FZ_DEFLATE_DEFAULT, 0);
+                break;
+            case 1:
+                outs[i + 1] = fz_new_asciihex_output(ctx, outs[i]);
+                break;
+            case 2:
+                outs[i + 1] = fz_new_ascii85_output(ctx, outs[i]);
+                break;
+            case 3:
+                outs[i + 1] = fz_new_rle_output(ctx, outs[i]);
+                break;
+            default:
+                fz_throw(ctx, FZ_ERROR_ARGUMENT, "unknown filter %d", filters[i]);
+            }
+        }
+        fz_write_buffer(ctx, outs[n], data);
+        for (i = n; i >= 0; i--)
+            fz_close_output(ctx, outs[i]);
+    }
+    fz_always(ctx)
+    {
+        for (i = n; i >= 0; i--)
+            fz_drop_output(ctx, outs[i]);
+    }
+    fz_catch(ctx)
+    {
+        fz_drop_buffer(ctx, buf);
+        buf = NULL;
+        mupdf_save_error(ctx, errptr);
+    }
+    return buf;
+}
+
 int mupdf_pdf_array_len(fz_context *ctx, pdf_obj *obj, mupdf_error_t **errptr)
 {
     int len = 0;
@@ -1953,6 +2759,49 @@ fz_buffer *mupdf_buffer_from_base64(fz_context *ctx, const char *s, mupdf_error_
     return buf;
 }
 
+/* zlib compressed data, as used by PNG and TIFF */
+fz_buffer *mupdf_deflate(fz_context *ctx, const unsigned char *data, size_t len, mupdf_error_t **errptr)
+{
+    fz_buffer *buf = NULL;
+    fz_var(buf);
+    fz_try(ctx)
+    {
+        size_t compressed_len = fz_deflate_bound(ctx, len);
+        buf = fz_new_buffer(ctx, compressed_len);
+        fz_deflate(ctx, buf->data, &compressed_len, data, len, FZ_DEFLATE_DEFAULT);
+        buf->len = compressed_len;
+    }
+    fz_catch(ctx)
+    {
+        fz_drop_buffer(ctx, buf);
+        buf = NULL;
+        mupdf_save_error(ctx, errptr);
+    }
+    return buf;
+}
+
+/* XML */
+fz_xml *mupdf_parse_xml(fz_context *ctx, const unsigned char *data, size_t len, mupdf_error_t **errptr)
+{
+    fz_buffer *buf = NULL;
+    fz_xml *xml = NULL;
+    fz_var(buf);
+    fz_try(ctx)
+    {
+        buf = fz_new_buffer_from_copied_data(ctx, data, len);
+        xml = fz_parse_xml(ctx, buf, 1);
+    }
+    fz_always(ctx)
+    {
+        fz_drop_buffer(ctx, buf);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+    return xml;
+}
+
 /* Document */
 fz_document *mupdf_open_document(fz_context *ctx, const char *filename, mupdf_error_t **errptr)
 {
@@ -1968,6 +2817,37 @@ fz_document *mupdf_open_document(fz_context *ctx, const char *filename, mupdf_er
     return doc;
 }
 
+fz_document *mupdf_open_accelerated_document(fz_context *ctx, const char *filename, const char *accel, mupdf_error_t **errptr)
+{
+    fz_document *doc = NULL;
+    fz_try(ctx)
+    {
+        /* Ignore accelerators that are missing or older than the document */
+        if (fz_stat_mtime(accel) < fz_stat_mtime(filename) || !fz_file_exists(ctx, accel))
+        {
+            accel = NULL;
+        }
+        doc = fz_open_accelerated_document(ctx, filename, accel);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+    return doc;
+}
+
+void mupdf_save_accelerator(fz_context *ctx, fz_document *doc, const char *accel, mupdf_error_t **errptr)
+{
+    fz_try(ctx)
+    {
+        fz_save_accelerator(ctx, doc, accel);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+}
+
 fz_document *mupdf_open_document_from_bytes(fz_context *ctx, fz_buffer *bytes, const char *magic, mupdf_error_t **errptr)
 {
     if (!magic)
@@ -1993,6 +2873,53 @@ fz_document *mupdf_open_document_from_bytes(fz_context *ctx, fz_buffer *bytes, c
     return doc;
 }
 
+/* Wrap the image in a one page PDF, the page sized as if the image had a resolution of `dpi` */
+fz_document *mupdf_open_image_at_dpi(fz_context *ctx, fz_buffer *bytes, float dpi, mupdf_error_t **errptr)
+{
+    pdf_document *pdf = NULL;
+    fz_image *image = NULL;
+    pdf_obj *resources = NULL, *page_obj = NULL;
+    fz_buffer *contents = NULL;
+    fz_var(pdf);
+    fz_var(image);
+    fz_var(resources);
+    fz_var(page_obj);
+    fz_var(contents);
+    fz_try(ctx)
+    {
+        if (!(dpi > 0))
+        {
+            fz_throw(ctx, FZ_ERROR_ARGUMENT, "resolution must be positive");
+        }
+        image = fz_new_image_from_buffer(ctx, bytes);
+        image->xres = image->yres = (int)dpi;
+        float w = image->w * 72.0f / dpi;
+        float h = image->h * 72.0f / dpi;
+        pdf = pdf_create_document(ctx);
+        resources = pdf_new_dict(ctx, pdf, 1);
+        pdf_obj *xobjects = pdf_dict_put_dict(ctx, resources, PDF_NAME(XObject), 1);
+        pdf_dict_puts_drop(ctx, xobjects, "Im0", pdf_add_image(ctx, pdf, image));
+        contents = fz_new_buffer(ctx, 64);
+        fz_append_printf(ctx, contents, "q %g 0 0 %g 0 0 cm /Im0 Do Q", w, h);
+        page_obj = pdf_add_page(ctx, pdf, fz_make_rect(0, 0, w, h), 0, resources, contents);
+        pdf_insert_page(ctx, pdf, -1, page_obj);
+    }
+    fz_always(ctx)
+    {
+        fz_drop_buffer(ctx, contents);
+        pdf_drop_obj(ctx, page_obj);
+        pdf_drop_obj(ctx, resources);
+        fz_drop_image(ctx, image);
+    }
+    fz_catch(ctx)
+    {
+        pdf_drop_document(ctx, pdf);
+        pdf = NULL;
+        mupdf_save_error(ctx, errptr);
+    }
+    return pdf ? &pdf->super : NULL;
+}
+
 bool mupdf_recognize_document(fz_context *ctx, const char *magic, mupdf_error_t **errptr)
 {
     if (!magic)
@@ -2053,6 +2980,34 @@ int mupdf_document_page_count(fz_context *ctx, fz_document *doc, mupdf_error_t *
     return count;
 }
 
+int mupdf_document_chapter_count(fz_context *ctx, fz_document *doc, mupdf_error_t **errptr)
+{
+    int count = 0;
+    fz_try(ctx)
+    {
+        count = fz_count_chapters(ctx, doc);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+    return count;
+}
+
+int mupdf_document_chapter_page_count(fz_context *ctx, fz_document *doc, int chapter, mupdf_error_t **errptr)
+{
+    int count = 0;
+    fz_try(ctx)
+    {
+        count = fz_count_chapter_pages(ctx, doc, chapter);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+    return count;
+}
+
 char *mupdf_lookup_metadata(fz_context *ctx, fz_document *doc, const char *key, mupdf_error_t **errptr)
 {
     int len;
@@ -2122,6 +3077,20 @@ fz_page *mupdf_load_page(fz_context *ctx, fz_document *doc, int page_no, mupdf_e
     return page;
 }
 
+fz_page *mupdf_load_chapter_page(fz_context *ctx, fz_document *doc, int chapter, int page_no, mupdf_error_t **errptr)
+{
+    fz_page *page = NULL;
+    fz_try(ctx)
+    {
+        page = fz_load_chapter_page(ctx, doc, chapter, page_no);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+    return page;
+}
+
 static pdf_document *mupdf_convert_to_pdf_internal(fz_context *ctx, fz_document *doc, int fp, int tp, int rotate, fz_cookie *cookie)
 {
     pdf_document *pdfout = pdf_create_document(ctx);
@@ -2205,157 +3174,359 @@ fz_location mupdf_resolve_link(fz_context *ctx, fz_document *doc, const char *ur
     return loc;
 }
 
-fz_colorspace *mupdf_document_output_intent(fz_context *ctx, fz_document *doc, mupdf_error_t **errptr)
+fz_link_dest mupdf_resolve_link_dest(fz_context *ctx, fz_document *doc, const char *uri, mupdf_error_t **errptr)
 {
-    fz_colorspace *cs = NULL;
+    fz_link_dest dest = fz_make_link_dest_none();
     fz_try(ctx)
     {
-        cs = fz_document_output_intent(ctx, doc);
+        dest = fz_resolve_link_dest(ctx, doc, uri);
     }
     fz_catch(ctx)
     {
         mupdf_save_error(ctx, errptr);
     }
-    return cs;
+    return dest;
 }
 
-fz_outline *mupdf_load_outline(fz_context *ctx, fz_document *doc, mupdf_error_t **errptr)
+bool mupdf_has_embedded_file(fz_context *ctx, fz_document *doc, const char *name, mupdf_error_t **errptr)
 {
-    fz_outline *outline = NULL;
+    pdf_document *pdf = pdf_specifics(ctx, doc);
+    pdf_obj *needle = NULL;
+    bool found = false;
+    if (!pdf)
+    {
+        return false;
+    }
+    fz_var(needle);
     fz_try(ctx)
     {
-        outline = fz_load_outline(ctx, doc);
+        needle = pdf_new_text_string(ctx, name);
+        found = pdf_lookup_name(ctx, pdf, PDF_NAME(EmbeddedFiles), needle) != NULL;
+    }
+    fz_always(ctx)
+    {
+        pdf_drop_obj(ctx, needle);
     }
     fz_catch(ctx)
     {
         mupdf_save_error(ctx, errptr);
     }
-    return outline;
+    return found;
 }
 
-/* PdfDocument */
-pdf_document *mupdf_pdf_open_document_from_bytes(fz_context *ctx, fz_buffer *bytes, mupdf_error_t **errptr)
+fz_bookmark mupdf_make_bookmark(fz_context *ctx, fz_document *doc, fz_location loc, mupdf_error_t **errptr)
 {
-    pdf_document *pdf = NULL;
-    fz_stream *stream = NULL;
-    fz_var(stream);
+    fz_bookmark mark = 0;
     fz_try(ctx)
     {
-        stream = fz_open_buffer(ctx, bytes);
-        pdf = pdf_open_document_with_stream(ctx, stream);
-    }
-    fz_always(ctx)
-    {
-        fz_drop_stream(ctx, stream);
+        mark = fz_make_bookmark(ctx, doc, loc);
     }
     fz_catch(ctx)
     {
         mupdf_save_error(ctx, errptr);
     }
-    return pdf;
+    return mark;
 }
 
-pdf_obj *mupdf_pdf_add_object(fz_context *ctx, pdf_document *pdf, pdf_obj *obj, mupdf_error_t **errptr)
+fz_location mupdf_lookup_bookmark(fz_context *ctx, fz_document *doc, fz_bookmark mark, mupdf_error_t **errptr)
 {
-    pdf_obj *ind = NULL;
+    fz_location loc = { -1, -1 };
     fz_try(ctx)
     {
-        ind = pdf_add_object(ctx, pdf, obj);
+        loc = fz_lookup_bookmark(ctx, doc, mark);
     }
     fz_catch(ctx)
     {
         mupdf_save_error(ctx, errptr);
     }
-    return ind;
+    return loc;
 }
 
-pdf_obj *mupdf_pdf_create_object(fz_context *ctx, pdf_document *pdf, mupdf_error_t **errptr)
+fz_colorspace *mupdf_document_output_intent(fz_context *ctx, fz_document *doc, mupdf_error_t **errptr)
 {
-    pdf_obj *ind = NULL;
+    fz_colorspace *cs = NULL;
     fz_try(ctx)
     {
-        ind = pdf_new_indirect(ctx, pdf, pdf_create_object(ctx, pdf), 0);
+        cs = fz_document_output_intent(ctx, doc);
     }
     fz_catch(ctx)
     {
         mupdf_save_error(ctx, errptr);
     }
-    return ind;
+    return cs;
 }
 
-void mupdf_pdf_delete_object(fz_context *ctx, pdf_document *pdf, int num, mupdf_error_t **errptr)
+fz_outline *mupdf_load_outline(fz_context *ctx, fz_document *doc, mupdf_error_t **errptr)
 {
+    fz_outline *outline = NULL;
     fz_try(ctx)
     {
-        pdf_delete_object(ctx, pdf, num);
+        outline = fz_load_outline(ctx, doc);
     }
     fz_catch(ctx)
     {
         mupdf_save_error(ctx, errptr);
     }
+    return outline;
 }
 
-pdf_obj *mupdf_pdf_add_image(fz_context *ctx, pdf_document *pdf, fz_image *image, mupdf_error_t **errptr)
+/* Custom document handlers */
+typedef struct
 {
-    pdf_obj *ind = NULL;
+    void (*drop_document)(void *doc);
+    int (*count_pages)(void *doc);
+    void *(*load_page)(void *doc, int number, char **error);
+    fz_rect (*bound_page)(void *page);
+    int (*run_page)(void *page, fz_device *dev, fz_matrix ctm, char **error);
+    void (*drop_page)(void *page);
+    void *(*open)(void *handler, const unsigned char *data, size_t len, char **error);
+    void (*free_error)(char *error);
+} mupdf_custom_callbacks;
+
+typedef struct
+{
+    fz_document_handler super;
+    void *user;
+    const mupdf_custom_callbacks *cb;
+} mupdf_custom_handler;
+
+typedef struct
+{
+    fz_document super;
+    void *user;
+    const mupdf_custom_callbacks *cb;
+} mupdf_custom_document;
+
+typedef struct
+{
+    fz_page super;
+    void *user;
+    const mupdf_custom_callbacks *cb;
+} mupdf_custom_page;
+
+static void throw_custom_error(fz_context *ctx, const mupdf_custom_callbacks *cb, char *error)
+{
+    char message[256];
+    fz_strlcpy(message, error ? error : "custom document handler failed", sizeof message);
+    if (error)
+        cb->free_error(error);
+    fz_throw(ctx, FZ_ERROR_GENERIC, "%s", message);
+}
+
+static fz_rect bound_custom_page(fz_context *ctx, fz_page *page_, fz_box_type box)
+{
+    mupdf_custom_page *page = (mupdf_custom_page *)page_;
+    return page->cb->bound_page(page->user);
+}
+
+static void run_custom_page(fz_context *ctx, fz_page *page_, fz_device *dev, fz_matrix ctm, fz_cookie *cookie)
+{
+    mupdf_custom_page *page = (mupdf_custom_page *)page_;
+    char *error = NULL;
+    if (page->cb->run_page(page->user, dev, ctm, &error))
+        throw_custom_error(ctx, page->cb, error);
+}
+
+static void drop_custom_page(fz_context *ctx, fz_page *page_)
+{
+    mupdf_custom_page *page = (mupdf_custom_page *)page_;
+    page->cb->drop_page(page->user);
+}
+
+static int count_custom_pages(fz_context *ctx, fz_document *doc_, int chapter)
+{
+    mupdf_custom_document *doc = (mupdf_custom_document *)doc_;
+    return doc->cb->count_pages(doc->user);
+}
+
+static fz_page *load_custom_page(fz_context *ctx, fz_document *doc_, int chapter, int number)
+{
+    mupdf_custom_document *doc = (mupdf_custom_document *)doc_;
+    char *error = NULL;
+    void *user = doc->cb->load_page(doc->user, number, &error);
+    mupdf_custom_page *page;
+    if (!user)
+        throw_custom_error(ctx, doc->cb, error);
     fz_try(ctx)
     {
-        ind = pdf_add_image(ctx, pdf, image);
+        page = fz_new_derived_page(ctx, mupdf_custom_page, doc_);
     }
     fz_catch(ctx)
     {
-        mupdf_save_error(ctx, errptr);
+        doc->cb->drop_page(user);
+        fz_rethrow(ctx);
     }
-    return ind;
+    page->user = user;
+    page->cb = doc->cb;
+    page->super.bound_page = bound_custom_page;
+    page->super.run_page_contents = run_custom_page;
+    page->super.drop_page = drop_custom_page;
+    return &page->super;
 }
 
-pdf_obj *mupdf_pdf_add_font(fz_context *ctx, pdf_document *pdf, fz_font *font, mupdf_error_t **errptr)
+static void drop_custom_document(fz_context *ctx, fz_document *doc_)
 {
-    pdf_obj *ind = NULL;
+    mupdf_custom_document *doc = (mupdf_custom_document *)doc_;
+    doc->cb->drop_document(doc->user);
+}
+
+static fz_document *open_custom_document(fz_context *ctx, const fz_document_handler *handler_, fz_stream *stream,
+                                         fz_stream *accel, fz_archive *dir, void *state)
+{
+    const mupdf_custom_handler *handler = (const mupdf_custom_handler *)handler_;
+    mupdf_custom_document *doc;
+    fz_buffer *buf = NULL;
+    unsigned char *data;
+    size_t len;
+    char *error = NULL;
+    void *user;
+
+    if (!stream)
+        fz_throw(ctx, FZ_ERROR_UNSUPPORTED, "custom document handlers need a file");
+    buf = fz_read_all(ctx, stream, 0);
+    len = fz_buffer_storage(ctx, buf, &data);
+    user = handler->cb->open(handler->user, data, len, &error);
+    fz_drop_buffer(ctx, buf);
+    if (!user)
+        throw_custom_error(ctx, handler->cb, error);
+
     fz_try(ctx)
     {
-        ind = pdf_add_cid_font(ctx, pdf, font);
+        doc = fz_new_derived_document(ctx, mupdf_custom_document);
     }
     fz_catch(ctx)
     {
-        mupdf_save_error(ctx, errptr);
+        handler->cb->drop_document(user);
+        fz_rethrow(ctx);
     }
-    return ind;
+    doc->user = user;
+    doc->cb = handler->cb;
+    doc->super.drop_document = drop_custom_document;
+    doc->super.count_pages = count_custom_pages;
+    doc->super.load_page = load_custom_page;
+    return &doc->super;
 }
 
-pdf_obj *mupdf_pdf_add_cjk_font(fz_context *ctx, pdf_document *pdf, fz_font *font, int ordering, int wmode, bool serif, mupdf_error_t **errptr)
+/* The handler and the strings it points to must live as long as the context */
+void mupdf_register_custom_handler(fz_context *ctx, void *user, const mupdf_custom_callbacks *cb,
+                                   const char **extensions, const char **mimetypes, mupdf_error_t **errptr)
 {
-    pdf_obj *ind = NULL;
+    mupdf_custom_handler *handler = NULL;
+    fz_var(handler);
     fz_try(ctx)
     {
-        ind = pdf_add_cjk_font(ctx, pdf, font, ordering, wmode, serif);
+        handler = fz_malloc_struct(ctx, mupdf_custom_handler);
+        handler->user = user;
+        handler->cb = cb;
+        handler->super.open = open_custom_document;
+        handler->super.extensions = extensions;
+        handler->super.mimetypes = mimetypes;
+        fz_register_document_handler(ctx, &handler->super);
     }
     fz_catch(ctx)
     {
+        fz_free(ctx, handler);
         mupdf_save_error(ctx, errptr);
     }
-    return ind;
 }
 
-pdf_obj *mupdf_pdf_add_simple_font(fz_context *ctx, pdf_document *pdf, fz_font *font, int encoding, mupdf_error_t **errptr)
+/* Progressive loading */
+typedef struct
 {
-    pdf_obj *ind = NULL;
+    unsigned char *data;
+    size_t len;
+    size_t block_size;
+    unsigned char *have;
+    int64_t missing;
+} mupdf_progressive_state;
+
+static int next_progressive(fz_context *ctx, fz_stream *stm, size_t max)
+{
+    mupdf_progressive_state *state = stm->state;
+    size_t pos = (size_t)stm->pos;
+    size_t end;
+    if (pos >= state->len)
+        return EOF;
+    if (!state->have[pos / state->block_size])
+    {
+        state->missing = (int64_t)(pos - pos % state->block_size);
+        fz_throw(ctx, FZ_ERROR_TRYLATER, "data at offset %zu not yet available", pos);
+    }
+    /* Hand out every consecutive block received so far */
+    end = pos;
+    while (end < state->len && state->have[end / state->block_size])
+        end += state->block_size - end % state->block_size;
+    end = fz_minz(end, state->len);
+    stm->rp = state->data + pos;
+    stm->wp = state->data + end;
+    stm->pos = (int64_t)end;
+    return *stm->rp++;
+}
+
+static void seek_progressive(fz_context *ctx, fz_stream *stm, int64_t offset, int whence)
+{
+    mupdf_progressive_state *state = stm->state;
+    int64_t pos = offset;
+    if (whence == SEEK_CUR)
+        pos = stm->pos - (stm->wp - stm->rp) + offset;
+    else if (whence == SEEK_END)
+        pos = (int64_t)state->len + offset;
+    pos = fz_clampi64(pos, 0, (int64_t)state->len);
+    stm->rp = stm->wp = state->data + pos;
+    stm->pos = pos;
+}
+
+static void drop_progressive(fz_context *ctx, void *state_)
+{
+    mupdf_progressive_state *state = state_;
+    fz_free(ctx, state->data);
+    fz_free(ctx, state->have);
+    fz_free(ctx, state);
+}
+
+fz_stream *mupdf_open_progressive_stream(fz_context *ctx, size_t len, size_t block_size, mupdf_error_t **errptr)
+{
+    mupdf_progressive_state *state = NULL;
+    fz_stream *stm = NULL;
+    fz_var(state);
     fz_try(ctx)
     {
-        ind = pdf_add_simple_font(ctx, pdf, font, encoding);
+        state = fz_malloc_struct(ctx, mupdf_progressive_state);
+        state->len = len;
+        state->block_size = block_size;
+        state->missing = -1;
+        state->data = fz_malloc(ctx, len ? len : 1);
+        state->have = fz_calloc(ctx, len / block_size + 1, 1);
+        stm = fz_new_stream(ctx, state, next_progressive, drop_progressive);
+        state = NULL;
+        stm->seek = seek_progressive;
+        stm->progressive = 1;
     }
     fz_catch(ctx)
     {
+        if (state)
+        {
+            fz_free(ctx, state->data);
+            fz_free(ctx, state->have);
+            fz_free(ctx, state);
+        }
         mupdf_save_error(ctx, errptr);
     }
-    return ind;
+    return stm;
 }
 
-void mupdf_pdf_save_document(fz_context *ctx, pdf_document *pdf, const char *filename, pdf_write_options pwo, mupdf_error_t **errptr)
+/* Copy `n` bytes at `offset` and mark the blocks `first_block..end_block` as complete */
+void mupdf_progressive_stream_write(fz_context *ctx, fz_stream *stm, size_t offset, const unsigned char *data, size_t n,
+                                    size_t first_block, size_t end_block, mupdf_error_t **errptr)
 {
+    mupdf_progressive_state *state = stm->state;
     fz_try(ctx)
     {
-        pdf_save_document(ctx, pdf, filename, &pwo);
+        if (offset > state->len || n > state->len - offset)
+            fz_throw(ctx, FZ_ERROR_ARGUMENT, "write past the end of the stream");
+        memcpy(state->data + offset, data, n);
+        for (size_t i = first_block; i < end_block && i <= state->len / state->block_size; i++)
+            state->have[i] = 1;
     }
     fz_catch(ctx)
     {
@@ -2363,381 +3534,2100 @@ void mupdf_pdf_save_document(fz_context *ctx, pdf_document *pdf, const char *fil
     }
 }
 
-fz_buffer *mupdf_pdf_write_document(fz_context *ctx, pdf_document *pdf, pdf_write_options pwo, mupdf_error_t **errptr)
+/* The block offset MuPDF last failed to read, or -1 */
+int64_t mupdf_progressive_stream_missing(fz_stream *stm)
 {
-    fz_output *out = NULL;
-    fz_buffer *buf = NULL;
-    fz_var(out);
-    fz_var(buf);
+    mupdf_progressive_state *state = stm->state;
+    if (state->missing >= 0 && state->have[state->missing / state->block_size])
+        state->missing = -1;
+    return state->missing;
+}
+
+fz_document *mupdf_open_document_with_stream(fz_context *ctx, const char *magic, fz_stream *stm, mupdf_error_t **errptr)
+{
+    fz_document *doc = NULL;
     fz_try(ctx)
     {
-        buf = fz_new_buffer(ctx, 8192);
-        out = fz_new_output_with_buffer(ctx, buf);
-        pdf_write_document(ctx, pdf, out, &pwo);
-        fz_close_output(ctx, out);
+        doc = fz_open_document_with_stream(ctx, magic, stm);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+    return doc;
+}
+
+/* PdfDocument */
+pdf_document *mupdf_pdf_open_document_from_bytes(fz_context *ctx, fz_buffer *bytes, mupdf_error_t **errptr)
+{
+    pdf_document *pdf = NULL;
+    fz_stream *stream = NULL;
+    fz_var(stream);
+    fz_try(ctx)
+    {
+        stream = fz_open_buffer(ctx, bytes);
+        pdf = pdf_open_document_with_stream(ctx, stream);
     }
     fz_always(ctx)
     {
-        fz_drop_output(ctx, out);
+        fz_drop_stream(ctx, stream);
     }
     fz_catch(ctx)
     {
         mupdf_save_error(ctx, errptr);
     }
-    return buf;
+    return pdf;
 }
 
-void mupdf_pdf_enable_js(fz_context *ctx, pdf_document *pdf, mupdf_error_t **errptr)
+pdf_obj *mupdf_pdf_add_object(fz_context *ctx, pdf_document *pdf, pdf_obj *obj, mupdf_error_t **errptr)
 {
+    pdf_obj *ind = NULL;
     fz_try(ctx)
     {
-        pdf_enable_js(ctx, pdf);
+        ind = pdf_add_object(ctx, pdf, obj);
     }
     fz_catch(ctx)
     {
         mupdf_save_error(ctx, errptr);
     }
+    return ind;
 }
 
-void mupdf_pdf_disable_js(fz_context *ctx, pdf_document *pdf, mupdf_error_t **errptr)
+pdf_obj *mupdf_pdf_create_object(fz_context *ctx, pdf_document *pdf, mupdf_error_t **errptr)
 {
+    pdf_obj *ind = NULL;
     fz_try(ctx)
     {
-        pdf_disable_js(ctx, pdf);
+        ind = pdf_new_indirect(ctx, pdf, pdf_create_object(ctx, pdf), 0);
     }
     fz_catch(ctx)
     {
         mupdf_save_error(ctx, errptr);
     }
+    return ind;
 }
 
-bool mupdf_pdf_js_supported(fz_context *ctx, pdf_document *pdf, mupdf_error_t **errptr)
+void mupdf_pdf_delete_object(fz_context *ctx, pdf_document *pdf, int num, mupdf_error_t **errptr)
 {
-    bool supported = false;
     fz_try(ctx)
     {
-        supported = pdf_js_supported(ctx, pdf);
+        pdf_delete_object(ctx, pdf, num);
     }
     fz_catch(ctx)
     {
         mupdf_save_error(ctx, errptr);
     }
-    return supported;
 }
 
-void mupdf_pdf_calculate_form(fz_context *ctx, pdf_document *pdf, mupdf_error_t **errptr)
+pdf_obj *mupdf_pdf_add_image(fz_context *ctx, pdf_document *pdf, fz_image *image, mupdf_error_t **errptr)
 {
+    pdf_obj *ind = NULL;
     fz_try(ctx)
     {
-        if (pdf->recalculate)
-        {
-            pdf_calculate_form(ctx, pdf);
-        }
+        ind = pdf_add_image(ctx, pdf, image);
     }
     fz_catch(ctx)
     {
         mupdf_save_error(ctx, errptr);
     }
+    return ind;
 }
 
-pdf_obj *mupdf_pdf_trailer(fz_context *ctx, pdf_document *pdf, mupdf_error_t **errptr)
+pdf_obj *mupdf_pdf_add_font(fz_context *ctx, pdf_document *pdf, fz_font *font, mupdf_error_t **errptr)
 {
-    pdf_obj *obj = NULL;
+    pdf_obj *ind = NULL;
     fz_try(ctx)
     {
-        obj = pdf_trailer(ctx, pdf);
-        pdf_keep_obj(ctx, obj);
+        ind = pdf_add_cid_font(ctx, pdf, font);
     }
     fz_catch(ctx)
     {
         mupdf_save_error(ctx, errptr);
     }
-    return obj;
+    return ind;
 }
 
-pdf_obj *mupdf_pdf_catalog(fz_context *ctx, pdf_document *pdf, mupdf_error_t **errptr)
+pdf_obj *mupdf_pdf_add_cjk_font(fz_context *ctx, pdf_document *pdf, fz_font *font, int ordering, int wmode, bool serif, mupdf_error_t **errptr)
 {
-    pdf_obj *obj = NULL;
+    pdf_obj *ind = NULL;
     fz_try(ctx)
     {
-        obj = pdf_dict_get(ctx, pdf_trailer(ctx, pdf), PDF_NAME(Root));
-        pdf_keep_obj(ctx, obj);
+        ind = pdf_add_cjk_font(ctx, pdf, font, ordering, wmode, serif);
     }
     fz_catch(ctx)
     {
         mupdf_save_error(ctx, errptr);
     }
-    return obj;
+    return ind;
 }
 
-int mupdf_pdf_count_objects(fz_context *ctx, pdf_document *pdf, mupdf_error_t **errptr)
+pdf_obj *mupdf_pdf_add_simple_font(fz_context *ctx, pdf_document *pdf, fz_font *font, int encoding, mupdf_error_t **errptr)
 {
-    int count = 0;
+    pdf_obj *ind = NULL;
     fz_try(ctx)
     {
-        count = pdf_xref_len(ctx, pdf);
+        ind = pdf_add_simple_font(ctx, pdf, font, encoding);
     }
     fz_catch(ctx)
     {
         mupdf_save_error(ctx, errptr);
     }
-    return count;
+    return ind;
 }
 
-pdf_graft_map *mupdf_pdf_new_graft_map(fz_context *ctx, pdf_document *pdf, mupdf_error_t **errptr)
+static void add_font_resource(fz_context *ctx, pdf_obj *resources, const char *name, pdf_obj *font)
+{
+    pdf_obj *fonts = pdf_dict_get(ctx, resources, PDF_NAME(Font));
+    if (!fonts)
+        fonts = pdf_dict_put_dict(ctx, resources, PDF_NAME(Font), 4);
+    pdf_dict_puts(ctx, fonts, name, font);
+}
+
+void mupdf_pdf_page_add_font_resource(fz_context *ctx, pdf_page *page, const char *name, pdf_obj *font, mupdf_error_t **errptr)
 {
-    pdf_graft_map *map = NULL;
     fz_try(ctx)
     {
-        map = pdf_new_graft_map(ctx, pdf);
+        pdf_obj *resources = pdf_dict_get_inheritable(ctx, page->obj, PDF_NAME(Resources));
+        if (!resources)
+            resources = pdf_dict_put_dict(ctx, page->obj, PDF_NAME(Resources), 2);
+        add_font_resource(ctx, resources, name, font);
     }
     fz_catch(ctx)
     {
         mupdf_save_error(ctx, errptr);
     }
-    return map;
 }
 
-pdf_obj *mupdf_pdf_graft_object(fz_context *ctx, pdf_document *doc, pdf_obj *obj, mupdf_error_t **errptr)
+fz_device *mupdf_new_pdf_page_content_device(fz_context *ctx, pdf_page *page, pdf_obj *resources, fz_buffer *contents, mupdf_error_t **errptr)
 {
-    pdf_obj *graft_obj = NULL;
+    fz_device *device = NULL;
     fz_try(ctx)
     {
-        graft_obj = pdf_graft_object(ctx, doc, obj);
+        // Content is drawn in page space, as the page is rendered, and written in PDF space
+        fz_matrix page_ctm;
+        pdf_page_transform(ctx, page, NULL, &page_ctm);
+        device = pdf_new_pdf_device(ctx, page->doc, fz_invert_matrix(page_ctm), resources, contents);
     }
     fz_catch(ctx)
     {
         mupdf_save_error(ctx, errptr);
     }
-    return graft_obj;
+    return device;
 }
 
-pdf_obj *mupdf_pdf_graft_mapped_object(fz_context *ctx, pdf_graft_map *map, pdf_obj *obj, mupdf_error_t **errptr)
+/* Add the `n` content streams `contents` with their `resources` on top of the page, in order */
+void mupdf_pdf_page_append_content(fz_context *ctx, pdf_page *page, pdf_obj **resources, fz_buffer **contents, int n, mupdf_error_t **errptr)
 {
-    pdf_obj *graft_obj = NULL;
+    pdf_document *doc = page->doc;
+    pdf_obj *xobj = NULL, *arr = NULL;
+    fz_buffer *buf = NULL, *ops = NULL;
+    fz_var(xobj);
+    fz_var(arr);
+    fz_var(buf);
+    fz_var(ops);
+    fz_try(ctx)
+    {
+        fz_rect bbox = pdf_to_rect(ctx, pdf_dict_get_inheritable(ctx, page->obj, PDF_NAME(MediaBox)));
+        // The resources may be inherited from the page tree or shared with other pages, so the
+        // page gets its own copy of them and of their XObjects before any are added
+        pdf_obj *page_res = pdf_dict_get_inheritable(ctx, page->obj, PDF_NAME(Resources));
+        page_res = page_res ? pdf_copy_dict(ctx, page_res) : pdf_new_dict(ctx, doc, 2);
+        pdf_dict_put_drop(ctx, page->obj, PDF_NAME(Resources), page_res);
+        pdf_obj *xobjects = pdf_dict_get(ctx, page_res, PDF_NAME(XObject));
+        xobjects = xobjects ? pdf_copy_dict(ctx, xobjects) : pdf_new_dict(ctx, doc, n);
+        pdf_dict_put_drop(ctx, page_res, PDF_NAME(XObject), xobjects);
+
+        // Each content stream is wrapped in a form XObject, so its resources can't clash with
+        // the resources of the page, and they are all drawn by one content stream
+        ops = fz_new_buffer(ctx, 64);
+        fz_append_string(ctx, ops, "Q\nq\n");
+        char name[32];
+        int i = 0;
+        for (int k = 0; k < n; k++)
+        {
+            xobj = pdf_new_xobject(ctx, doc, bbox, fz_identity, resources[k], contents[k]);
+            do
+            {
+                fz_snprintf(name, sizeof name, "Content%d", i++);
+            } while (pdf_dict_gets(ctx, xobjects, name));
+            pdf_dict_puts(ctx, xobjects, name, xobj);
+            pdf_drop_obj(ctx, xobj);
+            xobj = NULL;
+            fz_append_printf(ctx, ops, "/%s Do\n", name);
+        }
+        fz_append_string(ctx, ops, "Q\n");
+
+        // Existing content is isolated in q/Q, it may leave the graphics state changed
+        arr = pdf_new_array(ctx, doc, 3);
+        buf = fz_new_buffer_from_copied_data(ctx, (const unsigned char *)"q\n", 2);
+        pdf_array_push_drop(ctx, arr, pdf_add_stream(ctx, doc, buf, NULL, 0));
+        pdf_obj *old = pdf_dict_get(ctx, page->obj, PDF_NAME(Contents));
+        if (pdf_is_array(ctx, old))
+        {
+            int len = pdf_array_len(ctx, old);
+            for (int k = 0; k < len; k++)
+            {
+                pdf_array_push(ctx, arr, pdf_array_get(ctx, old, k));
+            }
+        }
+        else if (old)
+        {
+            pdf_array_push(ctx, arr, old);
+        }
+        pdf_array_push_drop(ctx, arr, pdf_add_stream(ctx, doc, ops, NULL, 0));
+        pdf_dict_put(ctx, page->obj, PDF_NAME(Contents), arr);
+    }
+    fz_always(ctx)
+    {
+        fz_drop_buffer(ctx, ops);
+        fz_drop_buffer(ctx, buf);
+        pdf_drop_obj(ctx, arr);
+        pdf_drop_obj(ctx, xobj);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+}
+
+pdf_obj *mupdf_pdf_new_tiling_pattern(fz_context *ctx, pdf_document *doc, fz_display_list *list, fz_rect bbox, float xstep, float ystep, fz_matrix matrix, mupdf_error_t **errptr)
+{
+    pdf_obj *pattern = NULL, *res = NULL, *dict = NULL;
+    fz_buffer *buf = NULL;
+    fz_device *dev = NULL;
+    fz_var(res);
+    fz_var(dict);
+    fz_var(buf);
+    fz_var(dev);
     fz_try(ctx)
     {
-        graft_obj = pdf_graft_mapped_object(ctx, map, obj);
+        res = pdf_new_dict(ctx, doc, 4);
+        buf = fz_new_buffer(ctx, 256);
+        dev = pdf_new_pdf_device(ctx, doc, fz_identity, res, buf);
+        fz_run_display_list(ctx, list, dev, fz_identity, bbox, NULL);
+        fz_close_device(ctx, dev);
+
+        dict = pdf_new_dict(ctx, doc, 9);
+        pdf_dict_put(ctx, dict, PDF_NAME(Type), PDF_NAME(Pattern));
+        pdf_dict_put_int(ctx, dict, PDF_NAME(PatternType), 1);
+        pdf_dict_put_int(ctx, dict, PDF_NAME(PaintType), 1);
+        pdf_dict_put_int(ctx, dict, PDF_NAME(TilingType), 1);
+        pdf_dict_put_rect(ctx, dict, PDF_NAME(BBox), bbox);
+        pdf_dict_put_real(ctx, dict, PDF_NAME(XStep), xstep);
+        pdf_dict_put_real(ctx, dict, PDF_NAME(YStep), ystep);
+        pdf_dict_put_matrix(ctx, dict, PDF_NAME(Matrix), matrix);
+        pdf_dict_put(ctx, dict, PDF_NAME(Resources), res);
+        pattern = pdf_add_stream(ctx, doc, buf, dict, 0);
+    }
+    fz_always(ctx)
+    {
+        fz_drop_device(ctx, dev);
+        fz_drop_buffer(ctx, buf);
+        pdf_drop_obj(ctx, dict);
+        pdf_drop_obj(ctx, res);
     }
     fz_catch(ctx)
     {
         mupdf_save_error(ctx, errptr);
     }
-    return graft_obj;
+    return pattern;
 }
 
-pdf_page *mupdf_pdf_new_page(fz_context *ctx, pdf_document *pdf, int page_no, float width, float height, mupdf_error_t **errptr)
+void mupdf_pdf_add_form_font_resource(fz_context *ctx, pdf_document *pdf, const char *name, pdf_obj *font, mupdf_error_t **errptr)
 {
-    fz_rect mediabox = fz_unit_rect;
-    mediabox.x1 = width;
-    mediabox.y1 = height;
-    pdf_obj *resources = NULL, *page_obj = NULL;
-    fz_buffer *contents = NULL;
-    pdf_page *page = NULL;
-    fz_var(resources);
-    fz_var(page_obj);
-    fz_var(contents);
     fz_try(ctx)
     {
-        // create /Resources and /Contents objects
-        resources = pdf_add_object_drop(ctx, pdf, pdf_new_dict(ctx, pdf, 1));
-        page_obj = pdf_add_page(ctx, pdf, mediabox, 0, resources, contents);
-        pdf_insert_page(ctx, pdf, page_no, page_obj);
-        int n = page_no;
-        int page_count = pdf_count_pages(ctx, pdf);
-        while (n < 0)
-        {
-            n += page_count;
-        }
-        fz_page *fz_page = fz_load_page(ctx, &pdf->super, n);
-        page = pdf_page_from_fz_page(ctx, fz_page);
+        pdf_obj *root = pdf_dict_get(ctx, pdf_trailer(ctx, pdf), PDF_NAME(Root));
+        pdf_obj *acroform = pdf_dict_get(ctx, root, PDF_NAME(AcroForm));
+        if (!acroform)
+            acroform = pdf_dict_put_dict(ctx, root, PDF_NAME(AcroForm), 2);
+        pdf_obj *dr = pdf_dict_get(ctx, acroform, PDF_NAME(DR));
+        if (!dr)
+            dr = pdf_dict_put_dict(ctx, acroform, PDF_NAME(DR), 2);
+        add_font_resource(ctx, dr, name, font);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+}
+
+void mupdf_pdf_save_document(fz_context *ctx, pdf_document *pdf, const char *filename, pdf_write_options pwo, mupdf_error_t **errptr)
+{
+    fz_try(ctx)
+    {
+        pdf_save_document(ctx, pdf, filename, &pwo);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+}
+
+fz_buffer *mupdf_pdf_write_document(fz_context *ctx, pdf_document *pdf, pdf_write_options pwo, mupdf_error_t **errptr)
+{
+    fz_output *out = NULL;
+    fz_buffer *buf = NULL;
+    fz_var(out);
+    fz_var(buf);
+    fz_try(ctx)
+    {
+        buf = fz_new_buffer(ctx, 8192);
+        out = fz_new_output_with_buffer(ctx, buf);
+        pdf_write_document(ctx, pdf, out, &pwo);
+        fz_close_output(ctx, out);
     }
     fz_always(ctx)
     {
-        fz_drop_buffer(ctx, contents);
-        pdf_drop_obj(ctx, page_obj);
-        pdf_drop_obj(ctx, resources);
+        fz_drop_output(ctx, out);
     }
     fz_catch(ctx)
     {
         mupdf_save_error(ctx, errptr);
     }
-    return page;
+    return buf;
 }
 
-pdf_obj *mupdf_pdf_lookup_page_obj(fz_context *ctx, pdf_document *pdf, int page_no, mupdf_error_t **errptr)
+void mupdf_pdf_enable_js(fz_context *ctx, pdf_document *pdf, mupdf_error_t **errptr)
 {
-    pdf_obj *obj = NULL;
     fz_try(ctx)
     {
-        obj = pdf_lookup_page_obj(ctx, pdf, page_no);
-        pdf_keep_obj(ctx, obj);
+        pdf_enable_js(ctx, pdf);
     }
     fz_catch(ctx)
     {
         mupdf_save_error(ctx, errptr);
     }
-    return obj;
 }
 
-void mupdf_pdf_insert_page(fz_context *ctx, pdf_document *pdf, int page_no, pdf_obj *page, mupdf_error_t **errptr)
+void mupdf_pdf_disable_js(fz_context *ctx, pdf_document *pdf, mupdf_error_t **errptr)
 {
-    if (page_no < 0 || page_no > pdf_count_pages(ctx, pdf))
+    fz_try(ctx)
     {
-        *errptr = mupdf_new_error_from_str("page_no is not a valid page");
-        return;
+        pdf_disable_js(ctx, pdf);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
     }
+}
+
+bool mupdf_pdf_js_supported(fz_context *ctx, pdf_document *pdf, mupdf_error_t **errptr)
+{
+    bool supported = false;
     fz_try(ctx)
     {
-        pdf_insert_page(ctx, pdf, page_no, page);
+        supported = pdf_js_supported(ctx, pdf);
     }
     fz_catch(ctx)
     {
         mupdf_save_error(ctx, errptr);
     }
+    return supported;
 }
 
-void mupdf_pdf_delete_page(fz_context *ctx, pdf_document *pdf, int page_no, mupdf_error_t **errptr)
+/* Forwards console output and alerts of the JavaScript of a document to Rust */
+typedef struct
 {
-    if (page_no < 0 || page_no >= pdf_count_pages(ctx, pdf))
+    void (*write)(void *user, const char *message);
+    int (*alert)(void *user, const char *title, const char *message, int icon_type, int button_group_type);
+    void (*drop)(void *user);
+    void *user;
+} mupdf_js_handler;
+
+static void mupdf_js_console_drop(pdf_js_console *console, void *user)
+{
+    /* The handler is owned by the document event callback */
+}
+
+static void mupdf_js_console_nop(void *user)
+{
+}
+
+static void mupdf_js_console_write(void *user, const char *message)
+{
+    mupdf_js_handler *handler = user;
+    handler->write(handler->user, message);
+}
+
+static pdf_js_console mupdf_js_console = {
+    mupdf_js_console_drop,
+    mupdf_js_console_nop,
+    mupdf_js_console_nop,
+    mupdf_js_console_nop,
+    mupdf_js_console_write,
+};
+
+static void mupdf_js_event(fz_context *ctx, pdf_document *doc, pdf_doc_event *evt, void *data)
+{
+    mupdf_js_handler *handler = data;
+    if (evt->type == PDF_DOCUMENT_EVENT_ALERT)
     {
-        *errptr = mupdf_new_error_from_str("page_no is not a valid page");
-        return;
+        pdf_alert_event *alert = pdf_access_alert_event(ctx, evt);
+        alert->button_pressed = handler->alert(handler->user, alert->title, alert->message, alert->icon_type, alert->button_group_type);
+    }
+}
+
+static void mupdf_js_event_drop(fz_context *ctx, void *data)
+{
+    mupdf_js_handler *handler = data;
+    handler->drop(handler->user);
+    fz_free(ctx, handler);
+}
+
+void mupdf_pdf_set_js_handler(fz_context *ctx, pdf_document *pdf, void (*write)(void *, const char *), int (*alert)(void *, const char *, const char *, int, int), void (*drop)(void *), void *user, mupdf_error_t **errptr)
+{
+    mupdf_js_handler *handler = NULL;
+    fz_var(handler);
+    fz_try(ctx)
+    {
+        handler = fz_malloc_struct(ctx, mupdf_js_handler);
+        handler->write = write;
+        handler->alert = alert;
+        handler->drop = drop;
+        handler->user = user;
+        /* Replacing the callback frees the previous handler */
+        pdf_set_doc_event_callback(ctx, pdf, mupdf_js_event, mupdf_js_event_drop, handler);
+        handler = NULL;
+#if FZ_ENABLE_JS
+        pdf_js_set_console(ctx, pdf, &mupdf_js_console, pdf_get_doc_event_callback_data(ctx, pdf));
+#endif
+    }
+    fz_catch(ctx)
+    {
+        if (handler)
+        {
+            drop(user);
+            fz_free(ctx, handler);
+        }
+        mupdf_save_error(ctx, errptr);
+    }
+}
+
+void mupdf_pdf_calculate_form(fz_context *ctx, pdf_document *pdf, mupdf_error_t **errptr)
+{
+    fz_try(ctx)
+    {
+        if (pdf->recalculate)
+        {
+            pdf_calculate_form(ctx, pdf);
+        }
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+}
+
+pdf_obj *mupdf_pdf_trailer(fz_context *ctx, pdf_document *pdf, mupdf_error_t **errptr)
+{
+    pdf_obj *obj = NULL;
+    fz_try(ctx)
+    {
+        obj = pdf_trailer(ctx, pdf);
+        pdf_keep_obj(ctx, obj);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+    return obj;
+}
+
+pdf_obj *mupdf_pdf_catalog(fz_context *ctx, pdf_document *pdf, mupdf_error_t **errptr)
+{
+    pdf_obj *obj = NULL;
+    fz_try(ctx)
+    {
+        obj = pdf_dict_get(ctx, pdf_trailer(ctx, pdf), PDF_NAME(Root));
+        pdf_keep_obj(ctx, obj);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+    return obj;
+}
+
+int mupdf_pdf_count_objects(fz_context *ctx, pdf_document *pdf, mupdf_error_t **errptr)
+{
+    int count = 0;
+    fz_try(ctx)
+    {
+        count = pdf_xref_len(ctx, pdf);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+    return count;
+}
+
+pdf_graft_map *mupdf_pdf_new_graft_map(fz_context *ctx, pdf_document *pdf, mupdf_error_t **errptr)
+{
+    pdf_graft_map *map = NULL;
+    fz_try(ctx)
+    {
+        map = pdf_new_graft_map(ctx, pdf);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+    return map;
+}
+
+pdf_obj *mupdf_pdf_graft_object(fz_context *ctx, pdf_document *doc, pdf_obj *obj, mupdf_error_t **errptr)
+{
+    pdf_obj *graft_obj = NULL;
+    fz_try(ctx)
+    {
+        graft_obj = pdf_graft_object(ctx, doc, obj);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+    return graft_obj;
+}
+
+pdf_obj *mupdf_pdf_graft_mapped_object(fz_context *ctx, pdf_graft_map *map, pdf_obj *obj, mupdf_error_t **errptr)
+{
+    pdf_obj *graft_obj = NULL;
+    fz_try(ctx)
+    {
+        graft_obj = pdf_graft_mapped_object(ctx, map, obj);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+    return graft_obj;
+}
+
+pdf_page *mupdf_pdf_new_page(fz_context *ctx, pdf_document *pdf, int page_no, float width, float height, mupdf_error_t **errptr)
+{
+    fz_rect mediabox = fz_unit_rect;
+    mediabox.x1 = width;
+    mediabox.y1 = height;
+    pdf_obj *resources = NULL, *page_obj = NULL;
+    fz_buffer *contents = NULL;
+    pdf_page *page = NULL;
+    fz_var(resources);
+    fz_var(page_obj);
+    fz_var(contents);
+    fz_try(ctx)
+    {
+        // create /Resources and /Contents objects
+        resources = pdf_add_object_drop(ctx, pdf, pdf_new_dict(ctx, pdf, 1));
+        page_obj = pdf_add_page(ctx, pdf, mediabox, 0, resources, contents);
+        pdf_insert_page(ctx, pdf, page_no, page_obj);
+        int n = page_no;
+        int page_count = pdf_count_pages(ctx, pdf);
+        while (n < 0)
+        {
+            n += page_count;
+        }
+        fz_page *fz_page = fz_load_page(ctx, &pdf->super, n);
+        page = pdf_page_from_fz_page(ctx, fz_page);
+    }
+    fz_always(ctx)
+    {
+        fz_drop_buffer(ctx, contents);
+        pdf_drop_obj(ctx, page_obj);
+        pdf_drop_obj(ctx, resources);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+    return page;
+}
+
+pdf_obj *mupdf_pdf_lookup_page_obj(fz_context *ctx, pdf_document *pdf, int page_no, mupdf_error_t **errptr)
+{
+    pdf_obj *obj = NULL;
+    fz_try(ctx)
+    {
+        obj = pdf_lookup_page_obj(ctx, pdf, page_no);
+        pdf_keep_obj(ctx, obj);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+    return obj;
+}
+
+int mupdf_pdf_lookup_page_number(fz_context *ctx, pdf_document *pdf, pdf_obj *page, mupdf_error_t **errptr)
+{
+    int page_no = -1;
+    fz_try(ctx)
+    {
+        page_no = pdf_lookup_page_number(ctx, pdf, page);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+    return page_no;
+}
+
+void mupdf_pdf_insert_page(fz_context *ctx, pdf_document *pdf, int page_no, pdf_obj *page, mupdf_error_t **errptr)
+{
+    if (page_no < 0 || page_no > pdf_count_pages(ctx, pdf))
+    {
+        *errptr = mupdf_new_error_from_str("page_no is not a valid page");
+        return;
+    }
+    fz_try(ctx)
+    {
+        pdf_insert_page(ctx, pdf, page_no, page);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+}
+
+void mupdf_pdf_delete_page(fz_context *ctx, pdf_document *pdf, int page_no, mupdf_error_t **errptr)
+{
+    if (page_no < 0 || page_no >= pdf_count_pages(ctx, pdf))
+    {
+        *errptr = mupdf_new_error_from_str("page_no is not a valid page");
+        return;
+    }
+    fz_try(ctx)
+    {
+        pdf_delete_page(ctx, pdf, page_no);
+        if (pdf->rev_page_map)
+        {
+            pdf_drop_page_tree(ctx, pdf);
+        }
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+}
+
+typedef struct
+{
+    int num;
+    unsigned char digest[16];
+} mupdf_stream_digest;
+
+static int mupdf_cmp_stream_digest(const void *a_, const void *b_)
+{
+    const mupdf_stream_digest *a = a_, *b = b_;
+    int c = memcmp(a->digest, b->digest, 16);
+    return c ? c : a->num - b->num;
+}
+
+static int mupdf_same_stream_dict(fz_context *ctx, pdf_document *pdf, int a, int b)
+{
+    pdf_obj *da = NULL, *db = NULL;
+    int same = 0;
+    fz_var(da);
+    fz_var(db);
+    fz_try(ctx)
+    {
+        // /Length may be an indirect object per stream, the data already compared equal
+        da = pdf_copy_dict(ctx, pdf_load_object(ctx, pdf, a));
+        db = pdf_copy_dict(ctx, pdf_load_object(ctx, pdf, b));
+        pdf_dict_del(ctx, da, PDF_NAME(Length));
+        pdf_dict_del(ctx, db, PDF_NAME(Length));
+        same = !pdf_objcmp(ctx, da, db);
+    }
+    fz_always(ctx)
+    {
+        pdf_drop_obj(ctx, da);
+        pdf_drop_obj(ctx, db);
+    }
+    fz_catch(ctx)
+    {
+        fz_rethrow(ctx);
+    }
+    return same;
+}
+
+static void mupdf_remap_refs(fz_context *ctx, pdf_document *pdf, pdf_obj *obj, const int *map, int len)
+{
+    int i, n;
+    if (pdf_is_indirect(ctx, obj))
+        return;
+    if (pdf_is_dict(ctx, obj))
+    {
+        n = pdf_dict_len(ctx, obj);
+        for (i = 0; i < n; i++)
+        {
+            pdf_obj *val = pdf_dict_get_val(ctx, obj, i);
+            int num = pdf_is_indirect(ctx, val) ? pdf_to_num(ctx, val) : 0;
+            if (num > 0 && num < len && map[num] != num)
+                pdf_dict_put_drop(ctx, obj, pdf_dict_get_key(ctx, obj, i), pdf_new_indirect(ctx, pdf, map[num], 0));
+            else
+                mupdf_remap_refs(ctx, pdf, val, map, len);
+        }
+    }
+    else if (pdf_is_array(ctx, obj))
+    {
+        n = pdf_array_len(ctx, obj);
+        for (i = 0; i < n; i++)
+        {
+            pdf_obj *val = pdf_array_get(ctx, obj, i);
+            int num = pdf_is_indirect(ctx, val) ? pdf_to_num(ctx, val) : 0;
+            if (num > 0 && num < len && map[num] != num)
+                pdf_array_put_drop(ctx, obj, i, pdf_new_indirect(ctx, pdf, map[num], 0));
+            else
+                mupdf_remap_refs(ctx, pdf, val, map, len);
+        }
+    }
+}
+
+int mupdf_pdf_deduplicate_streams(fz_context *ctx, pdf_document *pdf, mupdf_error_t **errptr)
+{
+    int len = 0, count = 0, merged = 0;
+    int i, num;
+    int *map = NULL;
+    mupdf_stream_digest *digests = NULL;
+    fz_buffer *buf = NULL;
+    fz_var(map);
+    fz_var(digests);
+    fz_var(buf);
+    fz_var(merged);
+    fz_try(ctx)
+    {
+        len = pdf_xref_len(ctx, pdf);
+        map = fz_malloc_array(ctx, len, int);
+        digests = fz_malloc_array(ctx, len, mupdf_stream_digest);
+        for (num = 0; num < len; num++)
+        {
+            map[num] = num;
+            if (num == 0 || !pdf_obj_num_is_stream(ctx, pdf, num))
+                continue;
+            fz_md5 md5;
+            buf = pdf_load_raw_stream_number(ctx, pdf, num);
+            unsigned char *data;
+            size_t size = fz_buffer_storage(ctx, buf, &data);
+            fz_md5_init(&md5);
+            fz_md5_update(&md5, data, size);
+            fz_md5_final(&md5, digests[count].digest);
+            digests[count].num = num;
+            count++;
+            fz_drop_buffer(ctx, buf);
+            buf = NULL;
+        }
+
+        // Within each run of equal digests, map every stream onto the first one with the same
+        // dictionary
+        qsort(digests, count, sizeof(*digests), mupdf_cmp_stream_digest);
+        for (i = 1; i < count; i++)
+        {
+            int j;
+            for (j = i - 1; j >= 0 && !memcmp(digests[i].digest, digests[j].digest, 16); j--)
+            {
+                int canon = digests[j].num;
+                if (map[canon] == canon && mupdf_same_stream_dict(ctx, pdf, canon, digests[i].num))
+                {
+                    map[digests[i].num] = canon;
+                    merged++;
+                    break;
+                }
+            }
+        }
+
+        if (merged > 0)
+        {
+            mupdf_remap_refs(ctx, pdf, pdf_trailer(ctx, pdf), map, len);
+            for (num = 1; num < len; num++)
+            {
+                if (map[num] != num)
+                    continue;
+                pdf_obj *obj = pdf_load_object(ctx, pdf, num);
+                mupdf_remap_refs(ctx, pdf, obj, map, len);
+                pdf_drop_obj(ctx, obj);
+            }
+            for (num = 1; num < len; num++)
+            {
+                if (map[num] != num)
+                    pdf_delete_object(ctx, pdf, num);
+            }
+        }
+    }
+    fz_always(ctx)
+    {
+        fz_drop_buffer(ctx, buf);
+        fz_free(ctx, digests);
+        fz_free(ctx, map);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+    return merged;
+}
+
+/* Whether a 1 bit per component image has a single component, so its samples are a bitmap */
+static int mupdf_is_bilevel_image(fz_context *ctx, pdf_obj *dict)
+{
+    if (!pdf_name_eq(ctx, pdf_dict_get(ctx, dict, PDF_NAME(Subtype)), PDF_NAME(Image)))
+        return 0;
+    if (pdf_dict_get_int(ctx, dict, PDF_NAME(BitsPerComponent)) != 1 && !pdf_dict_get_bool(ctx, dict, PDF_NAME(ImageMask)))
+        return 0;
+    if (pdf_dict_get_bool(ctx, dict, PDF_NAME(ImageMask)))
+        return 1;
+    pdf_obj *cs = pdf_dict_get(ctx, dict, PDF_NAME(ColorSpace));
+    if (pdf_is_array(ctx, cs))
+        cs = pdf_array_get(ctx, cs, 0);
+    return pdf_name_eq(ctx, cs, PDF_NAME(DeviceGray)) || pdf_name_eq(ctx, cs, PDF_NAME(CalGray)) ||
+           pdf_name_eq(ctx, cs, PDF_NAME(Indexed)) || pdf_name_eq(ctx, cs, PDF_NAME(Separation));
+}
+
+static int mupdf_has_bilevel_filter(fz_context *ctx, pdf_obj *dict)
+{
+    pdf_obj *filter = pdf_dict_get(ctx, dict, PDF_NAME(Filter));
+    if (pdf_is_array(ctx, filter))
+        filter = pdf_array_get(ctx, filter, pdf_array_len(ctx, filter) - 1);
+    return pdf_name_eq(ctx, filter, PDF_NAME(CCITTFaxDecode)) || pdf_name_eq(ctx, filter, PDF_NAME(JBIG2Decode));
+}
+
+int mupdf_pdf_recompress_bilevel_images(fz_context *ctx, pdf_document *pdf, mupdf_error_t **errptr)
+{
+    int count = 0;
+    pdf_obj *obj = NULL;
+    fz_buffer *buf = NULL, *g4 = NULL;
+    fz_var(count);
+    fz_var(obj);
+    fz_var(buf);
+    fz_var(g4);
+    fz_try(ctx)
+    {
+        int len = pdf_xref_len(ctx, pdf);
+        for (int num = 1; num < len; num++)
+        {
+            if (!pdf_obj_num_is_stream(ctx, pdf, num))
+                continue;
+            obj = pdf_new_indirect(ctx, pdf, num, 0);
+            if (mupdf_is_bilevel_image(ctx, obj) && !mupdf_has_bilevel_filter(ctx, obj))
+            {
+                int w = pdf_dict_get_int(ctx, obj, PDF_NAME(Width));
+                int h = pdf_dict_get_int(ctx, obj, PDF_NAME(Height));
+                size_t stride = ((size_t)w + 7) / 8;
+                buf = pdf_load_stream_number(ctx, pdf, num);
+                unsigned char *data;
+                size_t size = fz_buffer_storage(ctx, buf, &data);
+                if (w > 0 && h > 0 && size >= stride * h)
+                {
+                    // Zero samples are black, which the encoder wants as set bits
+                    for (size_t i = 0; i < stride * h; i++)
+                        data[i] = ~data[i];
+                    g4 = fz_compress_ccitt_fax_g4(ctx, data, w, h, stride);
+                    fz_drop_buffer(ctx, buf);
+                    buf = pdf_load_raw_stream_number(ctx, pdf, num);
+                    if (fz_buffer_storage(ctx, g4, NULL) < fz_buffer_storage(ctx, buf, NULL))
+                    {
+                        pdf_update_stream(ctx, pdf, obj, g4, 1);
+                        pdf_dict_put(ctx, obj, PDF_NAME(Filter), PDF_NAME(CCITTFaxDecode));
+                        pdf_obj *parms = pdf_dict_put_dict(ctx, obj, PDF_NAME(DecodeParms), 3);
+                        pdf_dict_put_int(ctx, parms, PDF_NAME(K), -1);
+                        pdf_dict_put_int(ctx, parms, PDF_NAME(Columns), w);
+                        pdf_dict_put_int(ctx, parms, PDF_NAME(Rows), h);
+                        count++;
+                    }
+                    fz_drop_buffer(ctx, g4);
+                    g4 = NULL;
+                }
+                fz_drop_buffer(ctx, buf);
+                buf = NULL;
+            }
+            pdf_drop_obj(ctx, obj);
+            obj = NULL;
+        }
+    }
+    fz_always(ctx)
+    {
+        fz_drop_buffer(ctx, g4);
+        fz_drop_buffer(ctx, buf);
+        pdf_drop_obj(ctx, obj);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+    return count;
+}
+
+void mupdf_pdf_rearrange_pages(fz_context *ctx, pdf_document *pdf, int count, const int *pages, mupdf_error_t **errptr)
+{
+    fz_try(ctx)
+    {
+        pdf_rearrange_pages(ctx, pdf, count, pages, PDF_CLEAN_STRUCTURE_DROP);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+}
+
+/* Returns false if there is no field called `name` or it rejected the value */
+bool mupdf_pdf_set_field_value(fz_context *ctx, pdf_document *pdf, const char *name, const char *value, bool run_events, mupdf_error_t **errptr)
+{
+    bool accepted = false;
+    fz_try(ctx)
+    {
+        pdf_obj *fields = pdf_dict_getp(ctx, pdf_trailer(ctx, pdf), "Root/AcroForm/Fields");
+        pdf_obj *field = pdf_lookup_field(ctx, fields, name);
+        if (field)
+        {
+            accepted = pdf_set_field_value(ctx, pdf, field, value, !run_events) != 0;
+        }
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+    return accepted;
+}
+
+/* Device */
+fz_device *mupdf_new_draw_device(fz_context *ctx, fz_pixmap *pixmap, fz_irect clip, mupdf_error_t **errptr)
+{
+    fz_device *device = NULL;
+    fz_try(ctx)
+    {
+        if (fz_is_infinite_irect(clip))
+        {
+            device = fz_new_draw_device(ctx, fz_identity, pixmap);
+        }
+        else
+        {
+            device = fz_new_draw_device_with_bbox(ctx, fz_identity, pixmap, &clip);
+        }
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+    return device;
+}
+
+fz_device *mupdf_new_device_of_size(fz_context *ctx, int size, mupdf_error_t **errptr)
+{
+    fz_device *device = NULL;
+    fz_try(ctx)
+    {
+        device = fz_new_device_of_size(ctx, size);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+    return device;
+}
+
+fz_device *mupdf_new_display_list_device(fz_context *ctx, fz_display_list *list, mupdf_error_t **errptr)
+{
+    fz_device *device = NULL;
+    fz_try(ctx)
+    {
+        device = fz_new_list_device(ctx, list);
+        fz_keep_display_list(ctx, list);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+    return device;
+}
+
+fz_stext_page *mupdf_new_stext_page(fz_context *ctx, fz_rect mediabox, mupdf_error_t **errptr)
+{
+    fz_stext_page *text_page = NULL;
+    fz_try(ctx)
+    {
+        text_page = fz_new_stext_page(ctx, mediabox);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+    return text_page;
+}
+
+fz_device *mupdf_new_stext_device(fz_context *ctx, fz_stext_page *tp, int flags, mupdf_error_t **errptr)
+{
+    fz_device *device = NULL;
+    fz_try(ctx)
+    {
+        fz_stext_options opts = {0};
+        opts.flags = flags;
+        device = fz_new_stext_device(ctx, tp, &opts);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+    return device;
+}
+
+void mupdf_fill_path(fz_context *ctx, fz_device *device, fz_path *path, bool even_odd, fz_matrix ctm, fz_colorspace *cs, const float *color, float alpha, fz_color_params cp, mupdf_error_t **errptr)
+{
+    fz_try(ctx)
+    {
+        fz_fill_path(ctx, device, path, even_odd, ctm, cs, color, alpha, cp);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+}
+
+void mupdf_stroke_path(fz_context *ctx, fz_device *device, fz_path *path, fz_stroke_state *stroke, fz_matrix ctm, fz_colorspace *cs, const float *color, float alpha, fz_color_params cp, mupdf_error_t **errptr)
+{
+    fz_try(ctx)
+    {
+        fz_stroke_path(ctx, device, path, stroke, ctm, cs, color, alpha, cp);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+}
+
+void mupdf_clip_path(fz_context *ctx, fz_device *device, fz_path *path, bool even_odd, fz_matrix ctm, mupdf_error_t **errptr)
+{
+    fz_try(ctx)
+    {
+        fz_clip_path(ctx, device, path, even_odd, ctm, fz_infinite_rect);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+}
+
+void mupdf_clip_stroke_path(fz_context *ctx, fz_device *device, fz_path *path, fz_stroke_state *stroke, fz_matrix ctm, mupdf_error_t **errptr)
+{
+    fz_try(ctx)
+    {
+        fz_clip_stroke_path(ctx, device, path, stroke, ctm, fz_infinite_rect);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+}
+
+void mupdf_fill_text(fz_context *ctx, fz_device *device, fz_text *text, fz_matrix ctm, fz_colorspace *cs, const float *color, float alpha, fz_color_params cp, mupdf_error_t **errptr)
+{
+    fz_try(ctx)
+    {
+        fz_fill_text(ctx, device, text, ctm, cs, color, alpha, cp);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+}
+
+void mupdf_stroke_text(fz_context *ctx, fz_device *device, fz_text *text, fz_stroke_state *stroke, fz_matrix ctm, fz_colorspace *cs, const float *color, float alpha, fz_color_params cp, mupdf_error_t **errptr)
+{
+    fz_try(ctx)
+    {
+        fz_stroke_text(ctx, device, text, stroke, ctm, cs, color, alpha, cp);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+}
+
+void mupdf_clip_text(fz_context *ctx, fz_device *device, fz_text *text, fz_matrix ctm, mupdf_error_t **errptr)
+{
+    fz_try(ctx)
+    {
+        fz_clip_text(ctx, device, text, ctm, fz_infinite_rect);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+}
+
+void mupdf_clip_stroke_text(fz_context *ctx, fz_device *device, fz_text *text, fz_stroke_state *stroke, fz_matrix ctm, mupdf_error_t **errptr)
+{
+    fz_try(ctx)
+    {
+        fz_clip_stroke_text(ctx, device, text, stroke, ctm, fz_infinite_rect);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+}
+
+void mupdf_ignore_text(fz_context *ctx, fz_device *device, fz_text *text, fz_matrix ctm, mupdf_error_t **errptr)
+{
+    fz_try(ctx)
+    {
+        fz_ignore_text(ctx, device, text, ctm);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+}
+
+void mupdf_fill_shade(fz_context *ctx, fz_device *device, fz_shade *shade, fz_matrix ctm, float alpha, fz_color_params cp, mupdf_error_t **errptr)
+{
+    fz_try(ctx)
+    {
+        fz_fill_shade(ctx, device, shade, ctm, alpha, cp);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+}
+
+void mupdf_fill_image(fz_context *ctx, fz_device *device, fz_image *image, fz_matrix ctm, float alpha, fz_color_params cp, mupdf_error_t **errptr)
+{
+    fz_try(ctx)
+    {
+        fz_fill_image(ctx, device, image, ctm, alpha, cp);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+}
+
+void mupdf_fill_image_mask(fz_context *ctx, fz_device *device, fz_image *image, fz_matrix ctm, fz_colorspace *cs, const float *color, float alpha, fz_color_params cp, mupdf_error_t **errptr)
+{
+    fz_try(ctx)
+    {
+        fz_fill_image_mask(ctx, device, image, ctm, cs, color, alpha, cp);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+}
+
+void mupdf_clip_image_mask(fz_context *ctx, fz_device *device, fz_image *image, fz_matrix ctm, mupdf_error_t **errptr)
+{
+    fz_try(ctx)
+    {
+        fz_clip_image_mask(ctx, device, image, ctm, fz_infinite_rect);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+}
+
+void mupdf_pop_clip(fz_context *ctx, fz_device *device, mupdf_error_t **errptr)
+{
+    fz_try(ctx)
+    {
+        fz_pop_clip(ctx, device);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+}
+
+void mupdf_begin_layer(fz_context *ctx, fz_device *device, const char *name, mupdf_error_t **errptr)
+{
+    fz_try(ctx)
+    {
+        fz_begin_layer(ctx, device, name);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+}
+
+void mupdf_end_layer(fz_context *ctx, fz_device *device, mupdf_error_t **errptr)
+{
+    fz_try(ctx)
+    {
+        fz_end_layer(ctx, device);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+}
+
+void mupdf_begin_structure(fz_context *ctx, fz_device *device, fz_structure standard, const char *raw, int idx, mupdf_error_t **errptr)
+{
+    fz_try(ctx)
+    {
+        fz_begin_structure(ctx, device, standard, raw, idx);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+}
+
+void mupdf_end_structure(fz_context *ctx, fz_device *device, mupdf_error_t **errptr)
+{
+    fz_try(ctx)
+    {
+        fz_end_structure(ctx, device);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+}
+
+void mupdf_begin_metatext(fz_context *ctx, fz_device *device, fz_metatext meta, const char *text, mupdf_error_t **errptr)
+{
+    fz_try(ctx)
+    {
+        fz_begin_metatext(ctx, device, meta, text);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+}
+
+void mupdf_end_metatext(fz_context *ctx, fz_device *device, mupdf_error_t **errptr)
+{
+    fz_try(ctx)
+    {
+        fz_end_metatext(ctx, device);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+}
+
+void mupdf_begin_mask(fz_context *ctx, fz_device *device, fz_rect area, bool luminosity, fz_colorspace *cs, const float *color, fz_color_params cp, mupdf_error_t **errptr)
+{
+    fz_try(ctx)
+    {
+        fz_begin_mask(ctx, device, area, luminosity, cs, color, cp);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+}
+
+void mupdf_end_mask(fz_context *ctx, fz_device *device, fz_function *fn, mupdf_error_t **errptr)
+{
+    fz_try(ctx)
+    {
+        fz_end_mask_tr(ctx, device, fn);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+}
+
+void mupdf_begin_group(fz_context *ctx, fz_device *device, fz_rect area, fz_colorspace *cs, bool isolated, bool knockout, int blendmode, float alpha, mupdf_error_t **errptr)
+{
+    fz_try(ctx)
+    {
+        fz_begin_group(ctx, device, area, cs, isolated, knockout, blendmode, alpha);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+}
+
+void mupdf_end_group(fz_context *ctx, fz_device *device, mupdf_error_t **errptr)
+{
+    fz_try(ctx)
+    {
+        fz_end_group(ctx, device);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+}
+
+int mupdf_begin_tile(fz_context *ctx, fz_device *device, fz_rect area, fz_rect view, float xstep, float ystep, fz_matrix ctm, int id, mupdf_error_t **errptr)
+{
+    int i = 0;
+    fz_try(ctx)
+    {
+        i = fz_begin_tile_id(ctx, device, area, view, xstep, ystep, ctm, id);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+    return i;
+}
+
+void mupdf_end_tile(fz_context *ctx, fz_device *device, mupdf_error_t **errptr)
+{
+    fz_try(ctx)
+    {
+        fz_end_tile(ctx, device);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+}
+
+/* Color glyphs */
+
+typedef struct
+{
+    fz_device super;
+    fz_device *target;
+    int mode;
+    int skip;
+    bool fixed_default_colorspaces;
+} mupdf_color_glyph_device;
+
+enum
+{
+    MUPDF_COLOR_GLYPHS_RASTERIZE = 1,
+    MUPDF_COLOR_GLYPHS_SKIP = 2,
+};
+
+/* The kinds of content of SkipContent */
+enum
+{
+    MUPDF_SKIP_TEXT = 1,
+    MUPDF_SKIP_IMAGES = 2,
+    MUPDF_SKIP_SHADINGS = 4,
+    MUPDF_SKIP_PATHS = 8,
+};
+
+/* Render a glyph from its color version (CBDT, sbix or COLR) at about `size` pixels per em, or
+ * return NULL if it hasn't got one. `bbox` receives the placement of the image in glyph space. */
+static fz_pixmap *mupdf_render_color_glyph(fz_context *ctx, fz_font *font, int gid, float size, fz_rect *bbox)
+{
+    FT_Face face = fz_font_ft_face(ctx, font);
+    fz_pixmap *pix = NULL;
+    FT_Error error;
+    FT_Bitmap *bitmap;
+    int ppem;
+
+    if (!face || !FT_HAS_COLOR(face))
+        return NULL;
+
+    fz_lock(ctx, FZ_LOCK_FREETYPE);
+    if (FT_HAS_FIXED_SIZES(face))
+    {
+        /* Bitmap fonts only come in a few sizes, pick the smallest one that is large enough */
+        int best = 0;
+        for (int i = 1; i < face->num_fixed_sizes; i++)
+        {
+            int have = face->available_sizes[best].y_ppem >> 6;
+            int want = face->available_sizes[i].y_ppem >> 6;
+            if ((have < size && want > have) || (want >= size && want < have))
+                best = i;
+        }
+        error = FT_Select_Size(face, best);
+    }
+    else
+    {
+        error = FT_Set_Pixel_Sizes(face, 0, fz_clampi((int)(size + 0.5f), 1, 1024));
+    }
+    if (!error)
+        error = FT_Load_Glyph(face, gid, FT_LOAD_COLOR | FT_LOAD_RENDER);
+    bitmap = &face->glyph->bitmap;
+    if (error || bitmap->pixel_mode != FT_PIXEL_MODE_BGRA || bitmap->width == 0 || bitmap->rows == 0)
+    {
+        fz_unlock(ctx, FZ_LOCK_FREETYPE);
+        return NULL;
+    }
+
+    ppem = face->size->metrics.y_ppem;
+    bbox->x0 = (float)face->glyph->bitmap_left / ppem;
+    bbox->y1 = (float)face->glyph->bitmap_top / ppem;
+    bbox->x1 = bbox->x0 + (float)bitmap->width / ppem;
+    bbox->y0 = bbox->y1 - (float)bitmap->rows / ppem;
+
+    fz_try(ctx)
+    {
+        /* FreeType's BGRA bitmaps are premultiplied, like pixmaps with alpha */
+        pix = fz_new_pixmap(ctx, fz_device_bgr(ctx), bitmap->width, bitmap->rows, NULL, 1);
+        for (unsigned int y = 0; y < bitmap->rows; y++)
+        {
+            memcpy(pix->samples + y * pix->stride, bitmap->buffer + y * bitmap->pitch, bitmap->width * 4);
+        }
+    }
+    fz_always(ctx)
+    {
+        fz_unlock(ctx, FZ_LOCK_FREETYPE);
+    }
+    fz_catch(ctx)
+    {
+        fz_rethrow(ctx);
+    }
+    return pix;
+}
+
+static void mupdf_color_glyph_fill_text(fz_context *ctx, fz_device *dev_, const fz_text *text, fz_matrix ctm, fz_colorspace *cs, const float *color, float alpha, fz_color_params cp)
+{
+    mupdf_color_glyph_device *dev = (mupdf_color_glyph_device *)dev_;
+    fz_text *rest = NULL;
+    fz_pixmap *pix = NULL;
+    fz_image *image = NULL;
+    fz_var(rest);
+    fz_var(pix);
+    fz_var(image);
+    if (dev->skip & MUPDF_SKIP_TEXT)
+        return;
+    if (!dev->mode)
+    {
+        fz_fill_text(ctx, dev->target, text, ctm, cs, color, alpha, cp);
+        return;
+    }
+    fz_try(ctx)
+    {
+        rest = fz_new_text(ctx);
+        for (const fz_text_span *span = text->head; span; span = span->next)
+        {
+            for (int i = 0; i < span->len; i++)
+            {
+                const fz_text_item *item = &span->items[i];
+                fz_matrix trm = span->trm;
+                fz_rect bbox;
+                trm.e = item->x;
+                trm.f = item->y;
+                if (item->gid >= 0)
+                {
+                    fz_matrix m = fz_concat(trm, ctm);
+                    pix = mupdf_render_color_glyph(ctx, span->font, item->gid, fz_matrix_expansion(m), &bbox);
+                }
+                if (!pix)
+                {
+                    fz_show_glyph(ctx, rest, span->font, trm, item->gid, item->ucs, span->wmode, span->bidi_level, span->markup_dir, span->language);
+                    continue;
+                }
+                if (dev->mode == MUPDF_COLOR_GLYPHS_RASTERIZE)
+                {
+                    /* Images are drawn into the unit square with their first row at the top */
+                    fz_matrix place = fz_make_matrix(bbox.x1 - bbox.x0, 0, 0, bbox.y0 - bbox.y1, bbox.x0, bbox.y1);
+                    image = fz_new_image_from_pixmap(ctx, pix, NULL);
+                    fz_fill_image(ctx, dev->target, image, fz_concat(fz_concat(place, trm), ctm), alpha, cp);
+                    fz_drop_image(ctx, image);
+                    image = NULL;
+                }
+                fz_drop_pixmap(ctx, pix);
+                pix = NULL;
+            }
+        }
+        if (rest->head)
+            fz_fill_text(ctx, dev->target, rest, ctm, cs, color, alpha, cp);
+    }
+    fz_always(ctx)
+    {
+        fz_drop_image(ctx, image);
+        fz_drop_pixmap(ctx, pix);
+        fz_drop_text(ctx, rest);
+    }
+    fz_catch(ctx)
+    {
+        fz_rethrow(ctx);
+    }
+}
+
+static void mupdf_color_glyph_fill_path(fz_context *ctx, fz_device *dev, const fz_path *path, int even_odd, fz_matrix ctm, fz_colorspace *cs, const float *color, float alpha, fz_color_params cp)
+{
+    if (((mupdf_color_glyph_device *)dev)->skip & MUPDF_SKIP_PATHS)
+        return;
+    fz_fill_path(ctx, ((mupdf_color_glyph_device *)dev)->target, path, even_odd, ctm, cs, color, alpha, cp);
+}
+
+static void mupdf_color_glyph_stroke_path(fz_context *ctx, fz_device *dev, const fz_path *path, const fz_stroke_state *stroke, fz_matrix ctm, fz_colorspace *cs, const float *color, float alpha, fz_color_params cp)
+{
+    if (((mupdf_color_glyph_device *)dev)->skip & MUPDF_SKIP_PATHS)
+        return;
+    fz_stroke_path(ctx, ((mupdf_color_glyph_device *)dev)->target, path, stroke, ctm, cs, color, alpha, cp);
+}
+
+static void mupdf_color_glyph_clip_path(fz_context *ctx, fz_device *dev, const fz_path *path, int even_odd, fz_matrix ctm, fz_rect scissor)
+{
+    fz_clip_path(ctx, ((mupdf_color_glyph_device *)dev)->target, path, even_odd, ctm, scissor);
+}
+
+static void mupdf_color_glyph_clip_stroke_path(fz_context *ctx, fz_device *dev, const fz_path *path, const fz_stroke_state *stroke, fz_matrix ctm, fz_rect scissor)
+{
+    fz_clip_stroke_path(ctx, ((mupdf_color_glyph_device *)dev)->target, path, stroke, ctm, scissor);
+}
+
+static void mupdf_color_glyph_stroke_text(fz_context *ctx, fz_device *dev, const fz_text *text, const fz_stroke_state *stroke, fz_matrix ctm, fz_colorspace *cs, const float *color, float alpha, fz_color_params cp)
+{
+    if (((mupdf_color_glyph_device *)dev)->skip & MUPDF_SKIP_TEXT)
+        return;
+    fz_stroke_text(ctx, ((mupdf_color_glyph_device *)dev)->target, text, stroke, ctm, cs, color, alpha, cp);
+}
+
+static void mupdf_color_glyph_clip_text(fz_context *ctx, fz_device *dev, const fz_text *text, fz_matrix ctm, fz_rect scissor)
+{
+    fz_clip_text(ctx, ((mupdf_color_glyph_device *)dev)->target, text, ctm, scissor);
+}
+
+static void mupdf_color_glyph_clip_stroke_text(fz_context *ctx, fz_device *dev, const fz_text *text, const fz_stroke_state *stroke, fz_matrix ctm, fz_rect scissor)
+{
+    fz_clip_stroke_text(ctx, ((mupdf_color_glyph_device *)dev)->target, text, stroke, ctm, scissor);
+}
+
+static void mupdf_color_glyph_ignore_text(fz_context *ctx, fz_device *dev, const fz_text *text, fz_matrix ctm)
+{
+    fz_ignore_text(ctx, ((mupdf_color_glyph_device *)dev)->target, text, ctm);
+}
+
+static void mupdf_color_glyph_fill_shade(fz_context *ctx, fz_device *dev, fz_shade *shade, fz_matrix ctm, float alpha, fz_color_params cp)
+{
+    if (((mupdf_color_glyph_device *)dev)->skip & MUPDF_SKIP_SHADINGS)
+        return;
+    fz_fill_shade(ctx, ((mupdf_color_glyph_device *)dev)->target, shade, ctm, alpha, cp);
+}
+
+static void mupdf_color_glyph_fill_image(fz_context *ctx, fz_device *dev, fz_image *image, fz_matrix ctm, float alpha, fz_color_params cp)
+{
+    if (((mupdf_color_glyph_device *)dev)->skip & MUPDF_SKIP_IMAGES)
+        return;
+    fz_fill_image(ctx, ((mupdf_color_glyph_device *)dev)->target, image, ctm, alpha, cp);
+}
+
+static void mupdf_color_glyph_fill_image_mask(fz_context *ctx, fz_device *dev, fz_image *image, fz_matrix ctm, fz_colorspace *cs, const float *color, float alpha, fz_color_params cp)
+{
+    if (((mupdf_color_glyph_device *)dev)->skip & MUPDF_SKIP_IMAGES)
+        return;
+    fz_fill_image_mask(ctx, ((mupdf_color_glyph_device *)dev)->target, image, ctm, cs, color, alpha, cp);
+}
+
+static void mupdf_color_glyph_clip_image_mask(fz_context *ctx, fz_device *dev, fz_image *image, fz_matrix ctm, fz_rect scissor)
+{
+    fz_clip_image_mask(ctx, ((mupdf_color_glyph_device *)dev)->target, image, ctm, scissor);
+}
+
+static void mupdf_color_glyph_pop_clip(fz_context *ctx, fz_device *dev)
+{
+    fz_pop_clip(ctx, ((mupdf_color_glyph_device *)dev)->target);
+}
+
+static void mupdf_color_glyph_begin_mask(fz_context *ctx, fz_device *dev, fz_rect area, int luminosity, fz_colorspace *cs, const float *bc, fz_color_params cp)
+{
+    fz_begin_mask(ctx, ((mupdf_color_glyph_device *)dev)->target, area, luminosity, cs, bc, cp);
+}
+
+static void mupdf_color_glyph_end_mask(fz_context *ctx, fz_device *dev, fz_function *fn)
+{
+    fz_end_mask_tr(ctx, ((mupdf_color_glyph_device *)dev)->target, fn);
+}
+
+static void mupdf_color_glyph_begin_group(fz_context *ctx, fz_device *dev, fz_rect area, fz_colorspace *cs, int isolated, int knockout, int blendmode, float alpha)
+{
+    fz_begin_group(ctx, ((mupdf_color_glyph_device *)dev)->target, area, cs, isolated, knockout, blendmode, alpha);
+}
+
+static void mupdf_color_glyph_end_group(fz_context *ctx, fz_device *dev)
+{
+    fz_end_group(ctx, ((mupdf_color_glyph_device *)dev)->target);
+}
+
+static int mupdf_color_glyph_begin_tile(fz_context *ctx, fz_device *dev, fz_rect area, fz_rect view, float xstep, float ystep, fz_matrix ctm, int id)
+{
+    return fz_begin_tile_id(ctx, ((mupdf_color_glyph_device *)dev)->target, area, view, xstep, ystep, ctm, id);
+}
+
+static void mupdf_color_glyph_end_tile(fz_context *ctx, fz_device *dev)
+{
+    fz_end_tile(ctx, ((mupdf_color_glyph_device *)dev)->target);
+}
+
+static void mupdf_color_glyph_render_flags(fz_context *ctx, fz_device *dev, int set, int clear)
+{
+    fz_render_flags(ctx, ((mupdf_color_glyph_device *)dev)->target, set, clear);
+}
+
+static void mupdf_color_glyph_set_default_colorspaces(fz_context *ctx, fz_device *dev, fz_default_colorspaces *dcs)
+{
+    if (!((mupdf_color_glyph_device *)dev)->fixed_default_colorspaces)
+        fz_set_default_colorspaces(ctx, ((mupdf_color_glyph_device *)dev)->target, dcs);
+}
+
+static void mupdf_color_glyph_begin_layer(fz_context *ctx, fz_device *dev, const char *name)
+{
+    fz_begin_layer(ctx, ((mupdf_color_glyph_device *)dev)->target, name);
+}
+
+static void mupdf_color_glyph_end_layer(fz_context *ctx, fz_device *dev)
+{
+    fz_end_layer(ctx, ((mupdf_color_glyph_device *)dev)->target);
+}
+
+static void mupdf_color_glyph_begin_structure(fz_context *ctx, fz_device *dev, fz_structure standard, const char *raw, int idx)
+{
+    fz_begin_structure(ctx, ((mupdf_color_glyph_device *)dev)->target, standard, raw, idx);
+}
+
+static void mupdf_color_glyph_end_structure(fz_context *ctx, fz_device *dev)
+{
+    fz_end_structure(ctx, ((mupdf_color_glyph_device *)dev)->target);
+}
+
+static void mupdf_color_glyph_begin_metatext(fz_context *ctx, fz_device *dev, fz_metatext meta, const char *text)
+{
+    fz_begin_metatext(ctx, ((mupdf_color_glyph_device *)dev)->target, meta, text);
+}
+
+static void mupdf_color_glyph_end_metatext(fz_context *ctx, fz_device *dev)
+{
+    fz_end_metatext(ctx, ((mupdf_color_glyph_device *)dev)->target);
+}
+
+static void mupdf_color_glyph_drop_device(fz_context *ctx, fz_device *dev)
+{
+    fz_drop_device(ctx, ((mupdf_color_glyph_device *)dev)->target);
+}
+
+/* Wrap `target` in a device passing everything on, drawing color glyphs as images (mode 1) or
+ * leaving them out (mode 2), leaving out the kinds of content in `skip` and ignoring default
+ * colorspaces if `fixed_default_colorspaces`. Closing the wrapper leaves `target` open. */
+static fz_device *mupdf_new_forwarding_device(fz_context *ctx, fz_device *target, int color_glyphs, int skip, bool fixed_default_colorspaces)
+{
+    mupdf_color_glyph_device *dev = fz_new_derived_device(ctx, mupdf_color_glyph_device);
+    dev->super.fill_path = mupdf_color_glyph_fill_path;
+    dev->super.stroke_path = mupdf_color_glyph_stroke_path;
+    dev->super.clip_path = mupdf_color_glyph_clip_path;
+    dev->super.clip_stroke_path = mupdf_color_glyph_clip_stroke_path;
+    dev->super.fill_text = mupdf_color_glyph_fill_text;
+    dev->super.stroke_text = mupdf_color_glyph_stroke_text;
+    dev->super.clip_text = mupdf_color_glyph_clip_text;
+    dev->super.clip_stroke_text = mupdf_color_glyph_clip_stroke_text;
+    dev->super.ignore_text = mupdf_color_glyph_ignore_text;
+    dev->super.fill_shade = mupdf_color_glyph_fill_shade;
+    dev->super.fill_image = mupdf_color_glyph_fill_image;
+    dev->super.fill_image_mask = mupdf_color_glyph_fill_image_mask;
+    dev->super.clip_image_mask = mupdf_color_glyph_clip_image_mask;
+    dev->super.pop_clip = mupdf_color_glyph_pop_clip;
+    dev->super.begin_mask = mupdf_color_glyph_begin_mask;
+    dev->super.end_mask = mupdf_color_glyph_end_mask;
+    dev->super.begin_group = mupdf_color_glyph_begin_group;
+    dev->super.end_group = mupdf_color_glyph_end_group;
+    dev->super.begin_tile = mupdf_color_glyph_begin_tile;
+    dev->super.end_tile = mupdf_color_glyph_end_tile;
+    dev->super.render_flags = mupdf_color_glyph_render_flags;
+    dev->super.set_default_colorspaces = mupdf_color_glyph_set_default_colorspaces;
+    dev->super.begin_layer = mupdf_color_glyph_begin_layer;
+    dev->super.end_layer = mupdf_color_glyph_end_layer;
+    dev->super.begin_structure = mupdf_color_glyph_begin_structure;
+    dev->super.end_structure = mupdf_color_glyph_end_structure;
+    dev->super.begin_metatext = mupdf_color_glyph_begin_metatext;
+    dev->super.end_metatext = mupdf_color_glyph_end_metatext;
+    dev->super.drop_device = mupdf_color_glyph_drop_device;
+    dev->super.hints = target->hints;
+    dev->super.flags = target->flags;
+    dev->target = fz_keep_device(ctx, target);
+    dev->mode = color_glyphs;
+    dev->skip = skip;
+    dev->fixed_default_colorspaces = fixed_default_colorspaces;
+    return (fz_device *)dev;
+}
+
+/* Wrap `target` in a device drawing color glyphs as images (mode 1) or leaving them out (mode 2).
+ * Closing the wrapper leaves `target` open. */
+fz_device *mupdf_new_color_glyph_device(fz_context *ctx, fz_device *target, int mode, mupdf_error_t **errptr)
+{
+    fz_device *dev = NULL;
+    fz_try(ctx)
+    {
+        dev = mupdf_new_forwarding_device(ctx, target, mode, 0, false);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+    return dev;
+}
+
+/* Wrap `target` in a device leaving out the kinds of content in `skip`, a set of MUPDF_SKIP_*.
+ * Clipping is passed on as is. Closing the wrapper leaves `target` open. */
+fz_device *mupdf_new_skip_device(fz_context *ctx, fz_device *target, int skip, mupdf_error_t **errptr)
+{
+    fz_device *dev = NULL;
+    fz_try(ctx)
+    {
+        dev = mupdf_new_forwarding_device(ctx, target, 0, skip, false);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+    return dev;
+}
+
+/* PdfPage */
+pdf_annot *mupdf_pdf_create_annot(fz_context *ctx, pdf_page *page, int subtype, mupdf_error_t **errptr)
+{
+    pdf_annot *annot = NULL;
+    fz_try(ctx)
+    {
+        annot = pdf_create_annot(ctx, page, subtype);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+    return annot;
+}
+
+void mupdf_pdf_delete_annot(fz_context *ctx, pdf_page *page, pdf_annot *annot, mupdf_error_t **errptr)
+{
+    fz_try(ctx)
+    {
+        pdf_delete_annot(ctx, page, annot);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+}
+
+bool mupdf_pdf_update_page(fz_context *ctx, pdf_page *page, mupdf_error_t **errptr)
+{
+    bool updated = false;
+    fz_try(ctx)
+    {
+        updated = pdf_update_page(ctx, page);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+    return updated;
+}
+
+bool mupdf_pdf_page_event(fz_context *ctx, pdf_page *page, bool open, mupdf_error_t **errptr)
+{
+    bool changed = false;
+    fz_try(ctx)
+    {
+        if (open)
+            pdf_page_event_open(ctx, page);
+        else
+            pdf_page_event_close(ctx, page);
+        changed = pdf_update_page(ctx, page);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+    return changed;
+}
+
+bool mupdf_pdf_redact_page(fz_context *ctx, pdf_page *page, mupdf_error_t **errptr)
+{
+    bool redacted = false;
+    fz_try(ctx)
+    {
+        redacted = pdf_redact_page(ctx, page->doc, page, NULL);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+    return redacted;
+}
+
+void mupdf_pdf_filter_page_contents(fz_context *ctx, pdf_page *page, pdf_filter_options *filter, mupdf_error_t **errptr)
+{
+    fz_try(ctx)
+    {
+        pdf_filter_page_contents(ctx, page->doc, page, filter);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+}
+
+void mupdf_pdf_page_set_rotation(fz_context *ctx, pdf_page *page, int rotation, mupdf_error_t **errptr)
+{
+    if (rotation % 90)
+    {
+        *errptr = mupdf_new_error_from_str("rotation not multiple of 90");
+        return;
+    }
+    fz_try(ctx)
+    {
+        pdf_dict_put_int(ctx, page->obj, PDF_NAME(Rotate), (int64_t)rotation);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+}
+
+void mupdf_pdf_page_set_crop_box(fz_context *ctx, pdf_page *page, fz_rect rect, mupdf_error_t **errptr)
+{
+    fz_try(ctx)
+    {
+        fz_rect mediabox = pdf_bound_page(ctx, page, FZ_MEDIA_BOX);
+        pdf_obj *obj = pdf_dict_get_inheritable(ctx, page->obj, PDF_NAME(MediaBox));
+        if (obj)
+        {
+            mediabox = pdf_to_rect(ctx, obj);
+        }
+        fz_rect cropbox = fz_empty_rect;
+        cropbox.x0 = rect.x0;
+        cropbox.y0 = mediabox.y1 - rect.y1;
+        cropbox.x1 = rect.x1;
+        cropbox.y1 = mediabox.y1 - rect.y0;
+        pdf_dict_put_drop(ctx, page->obj, PDF_NAME(CropBox), pdf_new_rect(ctx, page->doc, cropbox));
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+}
+
+fz_point mupdf_pdf_page_crop_box_position(fz_context *ctx, pdf_page *page)
+{
+    fz_point pos = fz_make_point(0, 0);
+    pdf_obj *obj = pdf_dict_get_inheritable(ctx, page->obj, PDF_NAME(CropBox));
+    if (!obj)
+    {
+        return pos;
+    }
+    fz_rect cbox = pdf_to_rect(ctx, obj);
+    pos = fz_make_point(cbox.x0, cbox.y0);
+    return pos;
+}
+
+fz_rect mupdf_pdf_page_media_box(fz_context *ctx, pdf_page *page)
+{
+    fz_rect r = fz_empty_rect;
+    pdf_obj *obj = pdf_dict_get_inheritable(ctx, page->obj, PDF_NAME(MediaBox));
+    if (!obj)
+    {
+        return r;
+    }
+    r = pdf_to_rect(ctx, obj);
+    return r;
+}
+
+fz_matrix mupdf_pdf_page_transform(fz_context *ctx, pdf_page *page, mupdf_error_t **errptr)
+{
+    fz_matrix ctm = fz_identity;
+    fz_try(ctx)
+    {
+        pdf_page_transform(ctx, page, NULL, &ctm);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+    return ctm;
+}
+
+fz_matrix mupdf_pdf_page_obj_transform(fz_context *ctx, pdf_obj *page, mupdf_error_t **errptr)
+{
+    fz_matrix ctm = fz_identity;
+    fz_try(ctx)
+    {
+        pdf_page_obj_transform(ctx, page, NULL, &ctm);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+    return ctm;
+}
+
+/* PDFAnnotation */
+int mupdf_pdf_annot_type(fz_context *ctx, pdf_annot *annot, mupdf_error_t **errptr)
+{
+    int subtype = 0;
+    fz_try(ctx)
+    {
+        subtype = pdf_annot_type(ctx, annot);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+    return subtype;
+}
+
+const char *mupdf_pdf_annot_author(fz_context *ctx, pdf_annot *annot, mupdf_error_t **errptr)
+{
+    const char *author = NULL;
+    fz_try(ctx)
+    {
+        author = pdf_annot_author(ctx, annot);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+    return author;
+}
+
+void mupdf_pdf_set_annot_author(fz_context *ctx, pdf_annot *annot, const char *author, mupdf_error_t **errptr)
+{
+    fz_try(ctx)
+    {
+        pdf_set_annot_author(ctx, annot, author);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+}
+
+void mupdf_pdf_filter_annot_contents(fz_context *ctx, pdf_annot *annot, pdf_filter_options *filter, mupdf_error_t **errptr)
+{
+    fz_try(ctx)
+    {
+        pdf_filter_annot_contents(ctx, pdf_annot_page(ctx, annot)->doc, annot, filter);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
     }
+}
+
+fz_rect mupdf_pdf_annot_popup(fz_context *ctx, pdf_annot *annot, mupdf_error_t **errptr)
+{
+    fz_rect rect = fz_empty_rect;
     fz_try(ctx)
     {
-        pdf_delete_page(ctx, pdf, page_no);
-        if (pdf->rev_page_map)
-        {
-            pdf_drop_page_tree(ctx, pdf);
-        }
+        if (pdf_annot_has_popup(ctx, annot))
+            rect = pdf_annot_popup(ctx, annot);
     }
     fz_catch(ctx)
     {
         mupdf_save_error(ctx, errptr);
     }
+    return rect;
 }
 
-/* Device */
-fz_device *mupdf_new_draw_device(fz_context *ctx, fz_pixmap *pixmap, fz_irect clip, mupdf_error_t **errptr)
+void mupdf_pdf_set_annot_popup(fz_context *ctx, pdf_annot *annot, fz_rect rect, mupdf_error_t **errptr)
 {
-    fz_device *device = NULL;
     fz_try(ctx)
     {
-        if (fz_is_infinite_irect(clip))
-        {
-            device = fz_new_draw_device(ctx, fz_identity, pixmap);
-        }
-        else
-        {
-            device = fz_new_draw_device_with_bbox(ctx, fz_identity, pixmap, &clip);
-        }
+        pdf_set_annot_popup(ctx, annot, rect);
     }
     fz_catch(ctx)
     {
         mupdf_save_error(ctx, errptr);
     }
-    return device;
 }
 
-fz_device *mupdf_new_device_of_size(fz_context *ctx, int size, mupdf_error_t **errptr)
+bool mupdf_pdf_annot_is_open(fz_context *ctx, pdf_annot *annot, mupdf_error_t **errptr)
 {
-    fz_device *device = NULL;
+    bool open = false;
     fz_try(ctx)
     {
-        device = fz_new_device_of_size(ctx, size);
+        open = pdf_annot_is_open(ctx, annot) != 0;
     }
     fz_catch(ctx)
     {
         mupdf_save_error(ctx, errptr);
     }
-    return device;
+    return open;
 }
 
-fz_device *mupdf_new_display_list_device(fz_context *ctx, fz_display_list *list, mupdf_error_t **errptr)
+void mupdf_pdf_set_annot_is_open(fz_context *ctx, pdf_annot *annot, bool open, mupdf_error_t **errptr)
 {
-    fz_device *device = NULL;
     fz_try(ctx)
     {
-        device = fz_new_list_device(ctx, list);
-        fz_keep_display_list(ctx, list);
+        pdf_set_annot_is_open(ctx, annot, open);
     }
     fz_catch(ctx)
     {
         mupdf_save_error(ctx, errptr);
     }
-    return device;
 }
 
-fz_device *mupdf_new_stext_device(fz_context *ctx, fz_stext_page *tp, int flags, mupdf_error_t **errptr)
+void mupdf_pdf_set_annot_ink_list(fz_context *ctx, pdf_annot *annot, int n, const int *count, const fz_point *v, mupdf_error_t **errptr)
 {
-    fz_device *device = NULL;
     fz_try(ctx)
     {
-        fz_stext_options opts = {0};
-        opts.flags = flags;
-        device = fz_new_stext_device(ctx, tp, &opts);
+        pdf_set_annot_ink_list(ctx, annot, n, count, v);
     }
     fz_catch(ctx)
     {
         mupdf_save_error(ctx, errptr);
     }
-    return device;
 }
 
-void mupdf_fill_path(fz_context *ctx, fz_device *device, fz_path *path, bool even_odd, fz_matrix ctm, fz_colorspace *cs, const float *color, float alpha, fz_color_params cp, mupdf_error_t **errptr)
+int mupdf_pdf_annot_ink_list_count(fz_context *ctx, pdf_annot *annot, mupdf_error_t **errptr)
 {
+    int count = 0;
     fz_try(ctx)
     {
-        fz_fill_path(ctx, device, path, even_odd, ctm, cs, color, alpha, cp);
+        count = pdf_annot_ink_list_count(ctx, annot);
     }
     fz_catch(ctx)
     {
         mupdf_save_error(ctx, errptr);
     }
+    return count;
 }
 
-void mupdf_stroke_path(fz_context *ctx, fz_device *device, fz_path *path, fz_stroke_state *stroke, fz_matrix ctm, fz_colorspace *cs, const float *color, float alpha, fz_color_params cp, mupdf_error_t **errptr)
+int mupdf_pdf_annot_ink_list_stroke_count(fz_context *ctx, pdf_annot *annot, int i, mupdf_error_t **errptr)
 {
+    int count = 0;
     fz_try(ctx)
     {
-        fz_stroke_path(ctx, device, path, stroke, ctm, cs, color, alpha, cp);
+        count = pdf_annot_ink_list_stroke_count(ctx, annot, i);
     }
     fz_catch(ctx)
     {
         mupdf_save_error(ctx, errptr);
     }
+    return count;
 }
 
-void mupdf_clip_path(fz_context *ctx, fz_device *device, fz_path *path, bool even_odd, fz_matrix ctm, mupdf_error_t **errptr)
+fz_point mupdf_pdf_annot_ink_list_stroke_vertex(fz_context *ctx, pdf_annot *annot, int i, int k, mupdf_error_t **errptr)
 {
+    fz_point p = {0, 0};
     fz_try(ctx)
     {
-        fz_clip_path(ctx, device, path, even_odd, ctm, fz_infinite_rect);
+        p = pdf_annot_ink_list_stroke_vertex(ctx, annot, i, k);
     }
     fz_catch(ctx)
     {
         mupdf_save_error(ctx, errptr);
     }
+    return p;
 }
 
-void mupdf_clip_stroke_path(fz_context *ctx, fz_device *device, fz_path *path, fz_stroke_state *stroke, fz_matrix ctm, mupdf_error_t **errptr)
+float mupdf_pdf_annot_border_width(fz_context *ctx, pdf_annot *annot, mupdf_error_t **errptr)
 {
+    float width = 0;
     fz_try(ctx)
     {
-        fz_clip_stroke_path(ctx, device, path, stroke, ctm, fz_infinite_rect);
+        width = pdf_annot_border_width(ctx, annot);
     }
     fz_catch(ctx)
     {
         mupdf_save_error(ctx, errptr);
     }
+    return width;
 }
 
-void mupdf_fill_text(fz_context *ctx, fz_device *device, fz_text *text, fz_matrix ctm, fz_colorspace *cs, const float *color, float alpha, fz_color_params cp, mupdf_error_t **errptr)
+void mupdf_pdf_set_annot_border_width(fz_context *ctx, pdf_annot *annot, float width, mupdf_error_t **errptr)
 {
     fz_try(ctx)
     {
-        fz_fill_text(ctx, device, text, ctm, cs, color, alpha, cp);
+        pdf_set_annot_border_width(ctx, annot, width);
     }
     fz_catch(ctx)
     {
@@ -2745,23 +5635,25 @@ void mupdf_fill_text(fz_context *ctx, fz_device *device, fz_text *text, fz_matri
     }
 }
 
-void mupdf_stroke_text(fz_context *ctx, fz_device *device, fz_text *text, fz_stroke_state *stroke, fz_matrix ctm, fz_colorspace *cs, const float *color, float alpha, fz_color_params cp, mupdf_error_t **errptr)
+fz_rect mupdf_pdf_annot_rect(fz_context *ctx, pdf_annot *annot, mupdf_error_t **errptr)
 {
+    fz_rect rect = fz_empty_rect;
     fz_try(ctx)
     {
-        fz_stroke_text(ctx, device, text, stroke, ctm, cs, color, alpha, cp);
+        rect = pdf_annot_rect(ctx, annot);
     }
     fz_catch(ctx)
     {
         mupdf_save_error(ctx, errptr);
     }
+    return rect;
 }
 
-void mupdf_clip_text(fz_context *ctx, fz_device *device, fz_text *text, fz_matrix ctm, mupdf_error_t **errptr)
+void mupdf_pdf_set_annot_rect(fz_context *ctx, pdf_annot *annot, fz_rect rect, mupdf_error_t **errptr)
 {
     fz_try(ctx)
     {
-        fz_clip_text(ctx, device, text, ctm, fz_infinite_rect);
+        pdf_set_annot_rect(ctx, annot, rect);
     }
     fz_catch(ctx)
     {
@@ -2769,35 +5661,39 @@ void mupdf_clip_text(fz_context *ctx, fz_device *device, fz_text *text, fz_matri
     }
 }
 
-void mupdf_clip_stroke_text(fz_context *ctx, fz_device *device, fz_text *text, fz_stroke_state *stroke, fz_matrix ctm, mupdf_error_t **errptr)
+fz_rect mupdf_pdf_bound_annot(fz_context *ctx, pdf_annot *annot, mupdf_error_t **errptr)
 {
+    fz_rect rect = fz_empty_rect;
     fz_try(ctx)
     {
-        fz_clip_stroke_text(ctx, device, text, stroke, ctm, fz_infinite_rect);
+        rect = pdf_bound_annot(ctx, annot);
     }
     fz_catch(ctx)
     {
         mupdf_save_error(ctx, errptr);
     }
+    return rect;
 }
 
-void mupdf_ignore_text(fz_context *ctx, fz_device *device, fz_text *text, fz_matrix ctm, mupdf_error_t **errptr)
+fz_pixmap *mupdf_pdf_annot_to_pixmap(fz_context *ctx, pdf_annot *annot, fz_matrix ctm, fz_colorspace *cs, bool alpha, mupdf_error_t **errptr)
 {
+    fz_pixmap *pixmap = NULL;
     fz_try(ctx)
     {
-        fz_ignore_text(ctx, device, text, ctm);
+        pixmap = pdf_new_pixmap_from_annot(ctx, annot, ctm, cs, NULL, alpha);
     }
     fz_catch(ctx)
     {
         mupdf_save_error(ctx, errptr);
     }
+    return pixmap;
 }
 
-void mupdf_fill_shade(fz_context *ctx, fz_device *device, fz_shade *shade, fz_matrix ctm, float alpha, fz_color_params cp, mupdf_error_t **errptr)
+void mupdf_pdf_set_annot_stamp_image(fz_context *ctx, pdf_annot *annot, fz_image *image, mupdf_error_t **errptr)
 {
     fz_try(ctx)
     {
-        fz_fill_shade(ctx, device, shade, ctm, alpha, cp);
+        pdf_set_annot_stamp_image(ctx, annot, image);
     }
     fz_catch(ctx)
     {
@@ -2805,11 +5701,11 @@ void mupdf_fill_shade(fz_context *ctx, fz_device *device, fz_shade *shade, fz_ma
     }
 }
 
-void mupdf_fill_image(fz_context *ctx, fz_device *device, fz_image *image, fz_matrix ctm, float alpha, fz_color_params cp, mupdf_error_t **errptr)
+void mupdf_pdf_set_annot_appearance_from_display_list(fz_context *ctx, pdf_annot *annot, const char *appearance, const char *state, fz_matrix ctm, fz_display_list *list, mupdf_error_t **errptr)
 {
     fz_try(ctx)
     {
-        fz_fill_image(ctx, device, image, ctm, alpha, cp);
+        pdf_set_annot_appearance_from_display_list(ctx, annot, appearance, state, ctm, list);
     }
     fz_catch(ctx)
     {
@@ -2817,11 +5713,18 @@ void mupdf_fill_image(fz_context *ctx, fz_device *device, fz_image *image, fz_ma
     }
 }
 
-void mupdf_fill_image_mask(fz_context *ctx, fz_device *device, fz_image *image, fz_matrix ctm, fz_colorspace *cs, const float *color, float alpha, fz_color_params cp, mupdf_error_t **errptr)
+void mupdf_pdf_set_annot_appearance(fz_context *ctx, pdf_annot *annot, const char *appearance, const char *state, fz_matrix ctm, fz_rect bbox, pdf_obj *res, const unsigned char *contents, size_t len, mupdf_error_t **errptr)
 {
+    fz_buffer *buf = NULL;
+    fz_var(buf);
     fz_try(ctx)
     {
-        fz_fill_image_mask(ctx, device, image, ctm, cs, color, alpha, cp);
+        buf = fz_new_buffer_from_copied_data(ctx, contents, len);
+        pdf_set_annot_appearance(ctx, annot, appearance, state, ctm, bbox, res, buf);
+    }
+    fz_always(ctx)
+    {
+        fz_drop_buffer(ctx, buf);
     }
     fz_catch(ctx)
     {
@@ -2829,23 +5732,25 @@ void mupdf_fill_image_mask(fz_context *ctx, fz_device *device, fz_image *image,
     }
 }
 
-void mupdf_clip_image_mask(fz_context *ctx, fz_device *device, fz_image *image, fz_matrix ctm, mupdf_error_t **errptr)
+int mupdf_pdf_annot_default_appearance(fz_context *ctx, pdf_annot *annot, const char **font, float *size, float color[4], mupdf_error_t **errptr)
 {
+    int n = 0;
     fz_try(ctx)
     {
-        fz_clip_image_mask(ctx, device, image, ctm, fz_infinite_rect);
+        pdf_annot_default_appearance(ctx, annot, font, size, &n, color);
     }
     fz_catch(ctx)
     {
         mupdf_save_error(ctx, errptr);
     }
+    return n;
 }
 
-void mupdf_pop_clip(fz_context *ctx, fz_device *device, mupdf_error_t **errptr)
+void mupdf_pdf_set_annot_default_appearance(fz_context *ctx, pdf_annot *annot, const char *font, float size, int n, const float *color, mupdf_error_t **errptr)
 {
     fz_try(ctx)
     {
-        fz_pop_clip(ctx, device);
+        pdf_set_annot_default_appearance(ctx, annot, font, size, n, color);
     }
     fz_catch(ctx)
     {
@@ -2853,23 +5758,25 @@ void mupdf_pop_clip(fz_context *ctx, fz_device *device, mupdf_error_t **errptr)
     }
 }
 
-void mupdf_begin_layer(fz_context *ctx, fz_device *device, const char *name, mupdf_error_t **errptr)
+int mupdf_pdf_annot_quadding(fz_context *ctx, pdf_annot *annot, mupdf_error_t **errptr)
 {
+    int q = 0;
     fz_try(ctx)
     {
-        fz_begin_layer(ctx, device, name);
+        q = pdf_annot_quadding(ctx, annot);
     }
     fz_catch(ctx)
     {
         mupdf_save_error(ctx, errptr);
     }
+    return q;
 }
 
-void mupdf_end_layer(fz_context *ctx, fz_device *device, mupdf_error_t **errptr)
+void mupdf_pdf_set_annot_quadding(fz_context *ctx, pdf_annot *annot, int q, mupdf_error_t **errptr)
 {
     fz_try(ctx)
     {
-        fz_end_layer(ctx, device);
+        pdf_set_annot_quadding(ctx, annot, q);
     }
     fz_catch(ctx)
     {
@@ -2877,23 +5784,25 @@ void mupdf_end_layer(fz_context *ctx, fz_device *device, mupdf_error_t **errptr)
     }
 }
 
-void mupdf_begin_structure(fz_context *ctx, fz_device *device, fz_structure standard, const char *raw, int idx, mupdf_error_t **errptr)
+int mupdf_pdf_annot_callout_line(fz_context *ctx, pdf_annot *annot, fz_point line[3], mupdf_error_t **errptr)
 {
+    int n = 0;
     fz_try(ctx)
     {
-        fz_begin_structure(ctx, device, standard, raw, idx);
+        pdf_annot_callout_line(ctx, annot, line, &n);
     }
     fz_catch(ctx)
     {
         mupdf_save_error(ctx, errptr);
     }
+    return n;
 }
 
-void mupdf_end_structure(fz_context *ctx, fz_device *device, mupdf_error_t **errptr)
+void mupdf_pdf_set_annot_callout_line(fz_context *ctx, pdf_annot *annot, fz_point line[3], int n, mupdf_error_t **errptr)
 {
     fz_try(ctx)
     {
-        fz_end_structure(ctx, device);
+        pdf_set_annot_callout_line(ctx, annot, line, n);
     }
     fz_catch(ctx)
     {
@@ -2901,23 +5810,25 @@ void mupdf_end_structure(fz_context *ctx, fz_device *device, mupdf_error_t **err
     }
 }
 
-void mupdf_begin_metatext(fz_context *ctx, fz_device *device, fz_metatext meta, const char *text, mupdf_error_t **errptr)
+int mupdf_pdf_annot_color(fz_context *ctx, pdf_annot *annot, float color[4], mupdf_error_t **errptr)
 {
+    int n = 0;
     fz_try(ctx)
     {
-        fz_begin_metatext(ctx, device, meta, text);
+        pdf_annot_color(ctx, annot, &n, color);
     }
     fz_catch(ctx)
     {
         mupdf_save_error(ctx, errptr);
     }
+    return n;
 }
 
-void mupdf_end_metatext(fz_context *ctx, fz_device *device, mupdf_error_t **errptr)
+void mupdf_pdf_set_annot_color(fz_context *ctx, pdf_annot *annot, int n, const float *color, mupdf_error_t **errptr)
 {
     fz_try(ctx)
     {
-        fz_end_metatext(ctx, device);
+        pdf_set_annot_color(ctx, annot, n, color);
     }
     fz_catch(ctx)
     {
@@ -2925,11 +5836,11 @@ void mupdf_end_metatext(fz_context *ctx, fz_device *device, mupdf_error_t **errp
     }
 }
 
-void mupdf_begin_mask(fz_context *ctx, fz_device *device, fz_rect area, bool luminosity, fz_colorspace *cs, const float *color, fz_color_params cp, mupdf_error_t **errptr)
+void mupdf_pdf_annot_line(fz_context *ctx, pdf_annot *annot, fz_point line[2], mupdf_error_t **errptr)
 {
     fz_try(ctx)
     {
-        fz_begin_mask(ctx, device, area, luminosity, cs, color, cp);
+        pdf_annot_line(ctx, annot, &line[0], &line[1]);
     }
     fz_catch(ctx)
     {
@@ -2937,11 +5848,11 @@ void mupdf_begin_mask(fz_context *ctx, fz_device *device, fz_rect area, bool lum
     }
 }
 
-void mupdf_end_mask(fz_context *ctx, fz_device *device, fz_function *fn, mupdf_error_t **errptr)
+void mupdf_pdf_set_annot_line(fz_context *ctx, pdf_annot *annot, fz_point a, fz_point b, mupdf_error_t **errptr)
 {
     fz_try(ctx)
     {
-        fz_end_mask_tr(ctx, device, fn);
+        pdf_set_annot_line(ctx, annot, a, b);
     }
     fz_catch(ctx)
     {
@@ -2949,11 +5860,14 @@ void mupdf_end_mask(fz_context *ctx, fz_device *device, fz_function *fn, mupdf_e
     }
 }
 
-void mupdf_begin_group(fz_context *ctx, fz_device *device, fz_rect area, fz_colorspace *cs, bool isolated, bool knockout, int blendmode, float alpha, mupdf_error_t **errptr)
+void mupdf_pdf_annot_line_ending_styles(fz_context *ctx, pdf_annot *annot, int styles[2], mupdf_error_t **errptr)
 {
     fz_try(ctx)
     {
-        fz_begin_group(ctx, device, area, cs, isolated, knockout, blendmode, alpha);
+        enum pdf_line_ending start, end;
+        pdf_annot_line_ending_styles(ctx, annot, &start, &end);
+        styles[0] = start;
+        styles[1] = end;
     }
     fz_catch(ctx)
     {
@@ -2961,11 +5875,11 @@ void mupdf_begin_group(fz_context *ctx, fz_device *device, fz_rect area, fz_colo
     }
 }
 
-void mupdf_end_group(fz_context *ctx, fz_device *device, mupdf_error_t **errptr)
+void mupdf_pdf_set_annot_line_ending_styles(fz_context *ctx, pdf_annot *annot, int start, int end, mupdf_error_t **errptr)
 {
     fz_try(ctx)
     {
-        fz_end_group(ctx, device);
+        pdf_set_annot_line_ending_styles(ctx, annot, start, end);
     }
     fz_catch(ctx)
     {
@@ -2973,25 +5887,25 @@ void mupdf_end_group(fz_context *ctx, fz_device *device, mupdf_error_t **errptr)
     }
 }
 
-int mupdf_begin_tile(fz_context *ctx, fz_device *device, fz_rect area, fz_rect view, float xstep, float ystep, fz_matrix ctm, int id, mupdf_error_t **errptr)
+int mupdf_pdf_annot_interior_color(fz_context *ctx, pdf_annot *annot, float color[4], mupdf_error_t **errptr)
 {
-    int i = 0;
+    int n = 0;
     fz_try(ctx)
     {
-        i = fz_begin_tile_id(ctx, device, area, view, xstep, ystep, ctm, id);
+        pdf_annot_interior_color(ctx, annot, &n, color);
     }
     fz_catch(ctx)
     {
         mupdf_save_error(ctx, errptr);
     }
-    return i;
+    return n;
 }
 
-void mupdf_end_tile(fz_context *ctx, fz_device *device, mupdf_error_t **errptr)
+void mupdf_pdf_set_annot_interior_color(fz_context *ctx, pdf_annot *annot, int n, const float *color, mupdf_error_t **errptr)
 {
     fz_try(ctx)
     {
-        fz_end_tile(ctx, device);
+        pdf_set_annot_interior_color(ctx, annot, n, color);
     }
     fz_catch(ctx)
     {
@@ -2999,83 +5913,79 @@ void mupdf_end_tile(fz_context *ctx, fz_device *device, mupdf_error_t **errptr)
     }
 }
 
-/* PdfPage */
-pdf_annot *mupdf_pdf_create_annot(fz_context *ctx, pdf_page *page, int subtype, mupdf_error_t **errptr)
+int mupdf_pdf_annot_vertex_count(fz_context *ctx, pdf_annot *annot, mupdf_error_t **errptr)
 {
-    pdf_annot *annot = NULL;
+    int count = 0;
     fz_try(ctx)
     {
-        annot = pdf_create_annot(ctx, page, subtype);
+        count = pdf_annot_vertex_count(ctx, annot);
     }
     fz_catch(ctx)
     {
         mupdf_save_error(ctx, errptr);
     }
-    return annot;
+    return count;
 }
 
-void mupdf_pdf_delete_annot(fz_context *ctx, pdf_page *page, pdf_annot *annot, mupdf_error_t **errptr)
+fz_point mupdf_pdf_annot_vertex(fz_context *ctx, pdf_annot *annot, int i, mupdf_error_t **errptr)
 {
+    fz_point p = {0, 0};
     fz_try(ctx)
     {
-        pdf_delete_annot(ctx, page, annot);
+        p = pdf_annot_vertex(ctx, annot, i);
     }
     fz_catch(ctx)
     {
         mupdf_save_error(ctx, errptr);
     }
+    return p;
 }
 
-bool mupdf_pdf_update_page(fz_context *ctx, pdf_page *page, mupdf_error_t **errptr)
+void mupdf_pdf_set_annot_vertices(fz_context *ctx, pdf_annot *annot, int n, const fz_point *v, mupdf_error_t **errptr)
 {
-    bool updated = false;
     fz_try(ctx)
     {
-        updated = pdf_update_page(ctx, page);
+        pdf_set_annot_vertices(ctx, annot, n, v);
     }
     fz_catch(ctx)
     {
         mupdf_save_error(ctx, errptr);
     }
-    return updated;
 }
 
-bool mupdf_pdf_redact_page(fz_context *ctx, pdf_page *page, mupdf_error_t **errptr)
+int mupdf_pdf_annot_quad_point_count(fz_context *ctx, pdf_annot *annot, mupdf_error_t **errptr)
 {
-    bool redacted = false;
+    int count = 0;
     fz_try(ctx)
     {
-        redacted = pdf_redact_page(ctx, page->doc, page, NULL);
+        count = pdf_annot_quad_point_count(ctx, annot);
     }
     fz_catch(ctx)
     {
         mupdf_save_error(ctx, errptr);
     }
-    return redacted;
+    return count;
 }
 
-void mupdf_pdf_filter_page_contents(fz_context *ctx, pdf_page *page, pdf_filter_options *filter, mupdf_error_t **errptr)
+fz_quad mupdf_pdf_annot_quad_point(fz_context *ctx, pdf_annot *annot, int i, mupdf_error_t **errptr)
 {
+    fz_quad quad = fz_invalid_quad;
     fz_try(ctx)
     {
-        pdf_filter_page_contents(ctx, page->doc, page, filter);
+        quad = pdf_annot_quad_point(ctx, annot, i);
     }
     fz_catch(ctx)
     {
         mupdf_save_error(ctx, errptr);
     }
+    return quad;
 }
 
-void mupdf_pdf_page_set_rotation(fz_context *ctx, pdf_page *page, int rotation, mupdf_error_t **errptr)
+void mupdf_pdf_set_annot_quad_points(fz_context *ctx, pdf_annot *annot, int n, const fz_quad *quads, mupdf_error_t **errptr)
 {
-    if (rotation % 90)
-    {
-        *errptr = mupdf_new_error_from_str("rotation not multiple of 90");
-        return;
-    }
     fz_try(ctx)
     {
-        pdf_dict_put_int(ctx, page->obj, PDF_NAME(Rotate), (int64_t)rotation);
+        pdf_set_annot_quad_points(ctx, annot, n, quads);
     }
     fz_catch(ctx)
     {
@@ -3083,128 +5993,225 @@ void mupdf_pdf_page_set_rotation(fz_context *ctx, pdf_page *page, int rotation,
     }
 }
 
-void mupdf_pdf_page_set_crop_box(fz_context *ctx, pdf_page *page, fz_rect rect, mupdf_error_t **errptr)
+/* Run an event of a form widget, numbered as `WidgetEvent` in Rust, returning whether it
+ * changed the widgets of its page */
+bool mupdf_pdf_annot_event(fz_context *ctx, pdf_annot *annot, int event, mupdf_error_t **errptr)
 {
+    bool changed = false;
     fz_try(ctx)
     {
-        fz_rect mediabox = pdf_bound_page(ctx, page, FZ_MEDIA_BOX);
-        pdf_obj *obj = pdf_dict_get_inheritable(ctx, page->obj, PDF_NAME(MediaBox));
-        if (obj)
+        switch (event)
         {
-            mediabox = pdf_to_rect(ctx, obj);
+        case 0:
+            pdf_annot_event_enter(ctx, annot);
+            break;
+        case 1:
+            pdf_annot_event_exit(ctx, annot);
+            break;
+        case 2:
+            pdf_annot_event_down(ctx, annot);
+            break;
+        case 3:
+            pdf_annot_event_up(ctx, annot);
+            break;
+        case 4:
+            pdf_annot_event_focus(ctx, annot);
+            break;
+        case 5:
+            pdf_annot_event_blur(ctx, annot);
+            break;
+        default:
+            fz_throw(ctx, FZ_ERROR_ARGUMENT, "unknown widget event %d", event);
         }
-        fz_rect cropbox = fz_empty_rect;
-        cropbox.x0 = rect.x0;
-        cropbox.y0 = mediabox.y1 - rect.y1;
-        cropbox.x1 = rect.x1;
-        cropbox.y1 = mediabox.y1 - rect.y0;
-        pdf_dict_put_drop(ctx, page->obj, PDF_NAME(CropBox), pdf_new_rect(ctx, page->doc, cropbox));
+        changed = pdf_update_page(ctx, pdf_annot_page(ctx, annot));
     }
     fz_catch(ctx)
     {
         mupdf_save_error(ctx, errptr);
     }
+    return changed;
 }
 
-fz_point mupdf_pdf_page_crop_box_position(fz_context *ctx, pdf_page *page)
+/* A PKCS #7 signer creating signatures with a Rust callback */
+typedef struct
 {
-    fz_point pos = fz_make_point(0, 0);
-    pdf_obj *obj = pdf_dict_get_inheritable(ctx, page->obj, PDF_NAME(CropBox));
-    if (!obj)
+    pdf_pkcs7_signer base;
+    int refs;
+    char *name;
+    size_t max_size;
+    size_t (*sign)(void *user, const unsigned char *data, size_t len, unsigned char *out, size_t out_len);
+    void (*drop)(void *user);
+    void *user;
+} mupdf_signer;
+
+static pdf_pkcs7_signer *mupdf_signer_keep(fz_context *ctx, pdf_pkcs7_signer *base)
+{
+    mupdf_signer *signer = (mupdf_signer *)base;
+    signer->refs++;
+    return base;
+}
+
+static void mupdf_signer_drop(fz_context *ctx, pdf_pkcs7_signer *base)
+{
+    mupdf_signer *signer = (mupdf_signer *)base;
+    if (--signer->refs > 0)
+        return;
+    signer->drop(signer->user);
+    fz_free(ctx, signer->name);
+    fz_free(ctx, signer);
+}
+
+static pdf_pkcs7_distinguished_name *mupdf_signer_name(fz_context *ctx, pdf_pkcs7_signer *base)
+{
+    mupdf_signer *signer = (mupdf_signer *)base;
+    pdf_pkcs7_distinguished_name *dn = fz_malloc_struct(ctx, pdf_pkcs7_distinguished_name);
+    fz_try(ctx)
     {
-        return pos;
+        dn->cn = fz_strdup(ctx, signer->name);
     }
-    fz_rect cbox = pdf_to_rect(ctx, obj);
-    pos = fz_make_point(cbox.x0, cbox.y0);
-    return pos;
+    fz_catch(ctx)
+    {
+        fz_free(ctx, dn);
+        fz_rethrow(ctx);
+    }
+    return dn;
 }
 
-fz_rect mupdf_pdf_page_media_box(fz_context *ctx, pdf_page *page)
+static size_t mupdf_signer_max_digest_size(fz_context *ctx, pdf_pkcs7_signer *base)
 {
-    fz_rect r = fz_empty_rect;
-    pdf_obj *obj = pdf_dict_get_inheritable(ctx, page->obj, PDF_NAME(MediaBox));
-    if (!obj)
+    return ((mupdf_signer *)base)->max_size;
+}
+
+static int mupdf_signer_create_digest(fz_context *ctx, pdf_pkcs7_signer *base, fz_stream *in, unsigned char *digest, size_t digest_len)
+{
+    mupdf_signer *signer = (mupdf_signer *)base;
+    fz_buffer *buf = fz_read_all(ctx, in, 0);
+    size_t len = 0;
+    fz_try(ctx)
     {
-        return r;
+        unsigned char *data;
+        size_t n = fz_buffer_storage(ctx, buf, &data);
+        len = signer->sign(signer->user, data, n, digest, digest_len);
     }
-    r = pdf_to_rect(ctx, obj);
-    return r;
+    fz_always(ctx)
+    {
+        fz_drop_buffer(ctx, buf);
+    }
+    fz_catch(ctx)
+    {
+        fz_rethrow(ctx);
+    }
+    if (len == 0)
+        fz_throw(ctx, FZ_ERROR_GENERIC, "signer failed to create a signature");
+    return (int)len;
 }
 
-fz_matrix mupdf_pdf_page_transform(fz_context *ctx, pdf_page *page, mupdf_error_t **errptr)
+/* Sign a signature widget. The signature is created when the document is saved, the signer
+ * being dropped afterwards or on failure */
+void mupdf_pdf_sign_signature(fz_context *ctx, pdf_annot *widget, const char *name, size_t max_size, size_t (*sign)(void *, const unsigned char *, size_t, unsigned char *, size_t), void (*drop)(void *), void *user, const char *reason, const char *location, mupdf_error_t **errptr)
 {
-    fz_matrix ctm = fz_identity;
+    mupdf_signer *signer = NULL;
+    fz_var(signer);
     fz_try(ctx)
     {
-        pdf_page_transform(ctx, page, NULL, &ctm);
+        signer = fz_malloc_struct(ctx, mupdf_signer);
+        signer->base.keep = mupdf_signer_keep;
+        signer->base.drop = mupdf_signer_drop;
+        signer->base.get_signing_name = mupdf_signer_name;
+        signer->base.max_digest_size = mupdf_signer_max_digest_size;
+        signer->base.create_digest = mupdf_signer_create_digest;
+        signer->refs = 1;
+        signer->max_size = max_size;
+        signer->sign = sign;
+        signer->drop = drop;
+        signer->user = user;
+        signer->name = fz_strdup(ctx, name);
+        pdf_sign_signature(ctx, widget, &signer->base, PDF_SIGNATURE_DEFAULT_APPEARANCE, NULL, reason, location);
+    }
+    fz_always(ctx)
+    {
+        if (signer)
+            mupdf_signer_drop(ctx, &signer->base);
+        else
+            drop(user);
     }
     fz_catch(ctx)
     {
         mupdf_save_error(ctx, errptr);
     }
-    return ctm;
 }
 
-fz_matrix mupdf_pdf_page_obj_transform(fz_context *ctx, pdf_obj *page, mupdf_error_t **errptr)
+bool mupdf_pdf_signature_incremental_change_since_signing(fz_context *ctx, pdf_document *pdf, pdf_obj *field, mupdf_error_t **errptr)
 {
-    fz_matrix ctm = fz_identity;
+    bool changed = false;
     fz_try(ctx)
     {
-        pdf_page_obj_transform(ctx, page, NULL, &ctm);
+        changed = pdf_signature_incremental_change_since_signing(ctx, pdf, field) != 0;
     }
     fz_catch(ctx)
     {
         mupdf_save_error(ctx, errptr);
     }
-    return ctm;
+    return changed;
 }
 
-/* PDFAnnotation */
-int mupdf_pdf_annot_type(fz_context *ctx, pdf_annot *annot, mupdf_error_t **errptr)
+/* Regenerate the appearance of an annotation whose dictionary was changed directly */
+bool mupdf_pdf_update_annot(fz_context *ctx, pdf_annot *annot, mupdf_error_t **errptr)
 {
-    int subtype = 0;
+    bool changed = false;
     fz_try(ctx)
     {
-        subtype = pdf_annot_type(ctx, annot);
+        pdf_dirty_annot(ctx, annot);
+        changed = pdf_update_annot(ctx, annot) != 0;
     }
     fz_catch(ctx)
     {
         mupdf_save_error(ctx, errptr);
     }
-    return subtype;
+    return changed;
 }
 
-const char *mupdf_pdf_annot_author(fz_context *ctx, pdf_annot *annot, mupdf_error_t **errptr)
+/* Story */
+fz_story *mupdf_new_story(fz_context *ctx, const char *html, const char *css, float em, mupdf_error_t **errptr)
 {
-    const char *author = NULL;
+    fz_story *story = NULL;
+    fz_buffer *buf = NULL;
+    fz_var(buf);
     fz_try(ctx)
     {
-        author = pdf_annot_author(ctx, annot);
+        buf = fz_new_buffer_from_copied_data(ctx, (const unsigned char *)html, strlen(html));
+        story = fz_new_story(ctx, buf, css, em, NULL);
+    }
+    fz_always(ctx)
+    {
+        fz_drop_buffer(ctx, buf);
     }
     fz_catch(ctx)
     {
         mupdf_save_error(ctx, errptr);
     }
-    return author;
+    return story;
 }
 
-void mupdf_pdf_set_annot_author(fz_context *ctx, pdf_annot *annot, const char *author, mupdf_error_t **errptr)
+bool mupdf_place_story(fz_context *ctx, fz_story *story, fz_rect where, fz_rect *filled, mupdf_error_t **errptr)
 {
+    int more = 0;
     fz_try(ctx)
     {
-        pdf_set_annot_author(ctx, annot, author);
+        more = fz_place_story(ctx, story, where, filled);
     }
     fz_catch(ctx)
     {
         mupdf_save_error(ctx, errptr);
     }
+    return more != 0;
 }
 
-void mupdf_pdf_filter_annot_contents(fz_context *ctx, pdf_annot *annot, pdf_filter_options *filter, mupdf_error_t **errptr)
+void mupdf_draw_story(fz_context *ctx, fz_story *story, fz_device *device, fz_matrix ctm, mupdf_error_t **errptr)
 {
     fz_try(ctx)
     {
-        pdf_filter_annot_contents(ctx, pdf_annot_page(ctx, annot)->doc, annot, filter);
+        fz_draw_story(ctx, story, device, ctm);
     }
     fz_catch(ctx)
     {
@@ -3227,6 +6234,24 @@ fz_document_writer *mupdf_new_document_writer(fz_context *ctx, const char *filen
     return writer;
 }
 
+fz_document_writer *mupdf_new_pdf_writer_with_buffer(fz_context *ctx, fz_buffer *buf, const char *options, mupdf_error_t **errptr)
+{
+    fz_document_writer *writer = NULL;
+    fz_output *out = NULL;
+    fz_var(out);
+    fz_try(ctx)
+    {
+        out = fz_new_output_with_buffer(ctx, buf);
+        // The writer takes ownership of the output
+        writer = fz_new_pdf_writer_with_output(ctx, out, options);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+    return writer;
+}
+
 fz_device *mupdf_document_writer_begin_page(fz_context *ctx, fz_document_writer *writer, fz_rect mediabox, mupdf_error_t **errptr)
 {
     fz_device *device = NULL;
@@ -3253,6 +6278,18 @@ void mupdf_document_writer_end_page(fz_context *ctx, fz_document_writer *writer,
     }
 }
 
+void mupdf_close_document_writer(fz_context *ctx, fz_document_writer *writer, mupdf_error_t **errptr)
+{
+    fz_try(ctx)
+    {
+        fz_close_document_writer(ctx, writer);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+}
+
 /* Bitmap */
 fz_bitmap *mupdf_new_bitmap_from_pixmap(fz_context *ctx, fz_pixmap *pixmap, mupdf_error_t **errptr)
 {
@@ -3268,6 +6305,20 @@ fz_bitmap *mupdf_new_bitmap_from_pixmap(fz_context *ctx, fz_pixmap *pixmap, mupd
     return bitmap;
 }
 
+fz_buffer *mupdf_compress_ccitt_fax_g4(fz_context *ctx, fz_bitmap *bitmap, mupdf_error_t **errptr)
+{
+    fz_buffer *buf = NULL;
+    fz_try(ctx)
+    {
+        buf = fz_compress_ccitt_fax_g4(ctx, bitmap->samples, bitmap->w, bitmap->h, bitmap->stride);
+    }
+    fz_catch(ctx)
+    {
+        mupdf_save_error(ctx, errptr);
+    }
+    return buf;
+}
+
 int32_t mupdf_highlight_selection(fz_context *ctx, fz_stext_page *page, fz_point a, fz_point b, fz_quad *quads, int max_quads, mupdf_error_t **errptr)
 {
 	int count = 0;
@@ -0,0 +1,142 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::ffi::c_void;
+use std::ptr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use mupdf_sys::fz_alloc_context;
+use once_cell::sync::OnceCell;
+
+// Every allocation is prefixed with its size, since MuPDF doesn't pass it back to `free` and
+// `realloc`. 16 bytes keeps the returned pointer aligned like `malloc` would.
+const HEADER: usize = 16;
+
+static ALLOCATOR: OnceCell<&'static (dyn GlobalAlloc + Sync)> = OnceCell::new();
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static LIMIT: AtomicUsize = AtomicUsize::new(0);
+
+/// Memory allocated by MuPDF, in bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MemoryUsage {
+    /// Bytes currently allocated.
+    pub current: usize,
+    /// Highest value `current` has reached.
+    pub peak: usize,
+    /// Total number of allocations made so far.
+    pub allocations: usize,
+}
+
+pub(crate) fn set(alloc: &'static (dyn GlobalAlloc + Sync)) -> bool {
+    ALLOCATOR.set(alloc).is_ok()
+}
+
+pub(crate) fn alloc_context() -> fz_alloc_context {
+    ALLOCATOR.get_or_init(|| &System);
+    fz_alloc_context {
+        user: ptr::null_mut(),
+        malloc_: Some(fz_malloc_cb),
+        realloc_: Some(fz_realloc_cb),
+        free_: Some(fz_free_cb),
+    }
+}
+
+pub(crate) fn usage() -> MemoryUsage {
+    MemoryUsage {
+        current: CURRENT.load(Ordering::Relaxed),
+        peak: PEAK.load(Ordering::Relaxed),
+        allocations: ALLOCATIONS.load(Ordering::Relaxed),
+    }
+}
+
+pub(crate) fn set_limit(limit: Option<usize>) {
+    LIMIT.store(limit.unwrap_or(0), Ordering::Relaxed);
+}
+
+fn allocator() -> &'static (dyn GlobalAlloc + Sync) {
+    *ALLOCATOR.get_or_init(|| &System)
+}
+
+fn layout(size: usize) -> Option<Layout> {
+    Layout::from_size_align(size.checked_add(HEADER)?, HEADER).ok()
+}
+
+/// Reserve `grow` more bytes, failing if that would exceed the configured limit.
+fn reserve(grow: usize) -> bool {
+    let limit = LIMIT.load(Ordering::Relaxed);
+    let prev = CURRENT.fetch_add(grow, Ordering::Relaxed);
+    if limit != 0 && prev + grow > limit {
+        CURRENT.fetch_sub(grow, Ordering::Relaxed);
+        return false;
+    }
+    PEAK.fetch_max(prev + grow, Ordering::Relaxed);
+    true
+}
+
+unsafe extern "C" fn fz_malloc_cb(_user: *mut c_void, size: usize) -> *mut c_void {
+    let Some(layout) = layout(size) else {
+        return ptr::null_mut();
+    };
+    if !reserve(size) {
+        // MuPDF will try to evict items from the store and retry
+        return ptr::null_mut();
+    }
+    let base = unsafe { allocator().alloc(layout) };
+    if base.is_null() {
+        CURRENT.fetch_sub(size, Ordering::Relaxed);
+        return ptr::null_mut();
+    }
+    ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+    unsafe {
+        base.cast::<usize>().write(size);
+        base.add(HEADER).cast()
+    }
+}
+
+unsafe extern "C" fn fz_realloc_cb(
+    user: *mut c_void,
+    old: *mut c_void,
+    size: usize,
+) -> *mut c_void {
+    if old.is_null() {
+        return unsafe { fz_malloc_cb(user, size) };
+    }
+    if size == 0 {
+        unsafe { fz_free_cb(user, old) };
+        return ptr::null_mut();
+    }
+    let base = unsafe { old.cast::<u8>().sub(HEADER) };
+    let old_size = unsafe { base.cast::<usize>().read() };
+    let (Some(old_layout), Some(_)) = (layout(old_size), layout(size)) else {
+        return ptr::null_mut();
+    };
+    if size > old_size && !reserve(size - old_size) {
+        return ptr::null_mut();
+    }
+    let new_base = unsafe { allocator().realloc(base, old_layout, size + HEADER) };
+    if new_base.is_null() {
+        if size > old_size {
+            CURRENT.fetch_sub(size - old_size, Ordering::Relaxed);
+        }
+        return ptr::null_mut();
+    }
+    if size < old_size {
+        CURRENT.fetch_sub(old_size - size, Ordering::Relaxed);
+    }
+    unsafe {
+        new_base.cast::<usize>().write(size);
+        new_base.add(HEADER).cast()
+    }
+}
+
+unsafe extern "C" fn fz_free_cb(_user: *mut c_void, ptr: *mut c_void) {
+    if ptr.is_null() {
+        return;
+    }
+    let base = unsafe { ptr.cast::<u8>().sub(HEADER) };
+    let size = unsafe { base.cast::<usize>().read() };
+    if let Some(layout) = layout(size) {
+        unsafe { allocator().dealloc(base, layout) };
+        CURRENT.fetch_sub(size, Ordering::Relaxed);
+    }
+}
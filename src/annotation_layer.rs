@@ -0,0 +1,90 @@
+use crate::pdf::PdfPage;
+use crate::{Colorspace, Error, Matrix, Page, Pixmap};
+
+/// The annotations and form widgets of a page pre-rendered into a transparent pixmap, to be
+/// composited over a rendering of the page contents.
+///
+/// Showing or hiding annotations in a viewer then only needs the layer to be drawn or skipped,
+/// and editing annotations only needs the layer to be rendered again.
+#[derive(Debug)]
+pub struct AnnotationLayer {
+    ctm: Matrix,
+    colorspace: Colorspace,
+    pixmap: Pixmap,
+    dirty: bool,
+}
+
+impl AnnotationLayer {
+    /// Render the annotations of `page` with `ctm`, into a pixmap covering the same area as a
+    /// rendering of the whole page.
+    pub fn new(page: &Page, ctm: &Matrix, cs: &Colorspace) -> Result<Self, Error> {
+        Ok(Self {
+            ctm: ctm.clone(),
+            colorspace: cs.clone(),
+            pixmap: Self::render(page, ctm, cs)?,
+            dirty: false,
+        })
+    }
+
+    fn render(page: &Page, ctm: &Matrix, cs: &Colorspace) -> Result<Pixmap, Error> {
+        page.render_annotations_only(ctm, cs)
+    }
+
+    pub fn pixmap(&self) -> &Pixmap {
+        &self.pixmap
+    }
+
+    pub fn ctm(&self) -> &Matrix {
+        &self.ctm
+    }
+
+    /// Whether the annotations changed since the layer was last rendered.
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Flag the layer for re-rendering, e.g. after changing annotations of a non-PDF page.
+    pub fn mark_dirty(&mut self) {
+        self.dirty = true;
+    }
+
+    /// Render the layer again if it is dirty, returning whether it was.
+    pub fn refresh(&mut self, page: &Page) -> Result<bool, Error> {
+        if !self.dirty {
+            return Ok(false);
+        }
+        self.pixmap = Self::render(page, &self.ctm, &self.colorspace)?;
+        self.dirty = false;
+        Ok(true)
+    }
+
+    /// Regenerate the appearance streams of annotations changed since the last update and render
+    /// the layer again if there were any.
+    pub fn update(&mut self, page: &mut PdfPage) -> Result<bool, Error> {
+        if page.update()? {
+            self.dirty = true;
+        }
+        self.refresh(page)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::AnnotationLayer;
+    use crate::pdf::{PdfAnnotationType, PdfDocument, PdfPage};
+    use crate::{Colorspace, Matrix};
+
+    #[test]
+    fn test_annotation_layer() {
+        let doc = PdfDocument::open("tests/files/dummy.pdf").unwrap();
+        let mut page = PdfPage::try_from(doc.load_page(0).unwrap()).unwrap();
+        let mut layer =
+            AnnotationLayer::new(&page, &Matrix::IDENTITY, &Colorspace::device_rgb()).unwrap();
+        assert_eq!(layer.pixmap().width(), 595);
+        assert!(!layer.update(&mut page).unwrap());
+
+        page.create_annotation(PdfAnnotationType::Square).unwrap();
+        assert!(layer.update(&mut page).unwrap());
+        assert!(!layer.is_dirty());
+    }
+}
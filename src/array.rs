@@ -122,6 +122,13 @@ impl<T> Iterator for FzIter<T> {
     }
 }
 
+#[cfg(feature = "serde")]
+impl<T: serde::Serialize> serde::Serialize for FzArray<T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
 // SAFETY: This is esentially just a `Box<[T], fz_calloc>`. If that's safe to impl, then this is as
 // well. It's only not automatically derived to make us think for a second, not inherently unsafe.
 unsafe impl<T> Send for FzArray<T> where T: Send {}
@@ -0,0 +1,118 @@
+use std::panic;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use tokio::task;
+
+use crate::{
+    ContextPool, Cookie, Document, Error, Pixmap, PoolHandle, RenderOptions, TextPageOptions,
+};
+
+/// Aborts the blocking work through its cookie when the future awaiting it is dropped.
+struct AbortOnDrop(Option<Arc<Cookie>>);
+
+impl AbortOnDrop {
+    fn disarm(mut self) {
+        self.0 = None;
+    }
+}
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        if let Some(cookie) = &self.0 {
+            cookie.abort();
+        }
+    }
+}
+
+async fn spawn<T, F>(f: F) -> Result<T, Error>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, Error> + Send + 'static,
+{
+    match task::spawn_blocking(f).await {
+        Ok(res) => res,
+        Err(err) if err.is_panic() => panic::resume_unwind(err.into_panic()),
+        Err(_) => Err(Error::Aborted),
+    }
+}
+
+/// A document whose operations run on tokio's blocking thread pool.
+///
+/// Dropping a pending render future aborts the rendering, so requests that time out or whose
+/// client disconnects don't keep a thread busy.
+#[derive(Clone)]
+pub struct AsyncDocument {
+    handle: PoolHandle,
+}
+
+impl AsyncDocument {
+    pub async fn open(path: impl Into<PathBuf>) -> Result<Self, Error> {
+        let path = path.into();
+        spawn(move || ContextPool::open(path.as_path()))
+            .await
+            .map(|pool| Self {
+                handle: pool.handle(),
+            })
+    }
+
+    pub async fn from_bytes(bytes: Vec<u8>, magic: String) -> Result<Self, Error> {
+        spawn(move || Document::from_bytes(&bytes, &magic).map(ContextPool::new))
+            .await
+            .map(|pool| Self {
+                handle: pool.handle(),
+            })
+    }
+
+    pub async fn page_count(&self) -> Result<i32, Error> {
+        let handle = self.handle.clone();
+        spawn(move || handle.page_count()).await
+    }
+
+    pub async fn render_page(&self, page_no: i32, options: RenderOptions) -> Result<Pixmap, Error> {
+        let handle = self.handle.clone();
+        let cookie = Arc::new(Cookie::new()?);
+        let guard = AbortOnDrop(Some(cookie.clone()));
+        let res = spawn(move || {
+            handle
+                .display_list(page_no, options.annotations())?
+                .render_with_cookie(&options, &cookie)
+        })
+        .await;
+        guard.disarm();
+        res
+    }
+
+    /// Extract the plain text of a page.
+    pub async fn extract_text(&self, page_no: i32) -> Result<String, Error> {
+        let handle = self.handle.clone();
+        spawn(move || {
+            handle
+                .display_list(page_no, false)?
+                .to_text_page(TextPageOptions::empty())?
+                .to_text()
+        })
+        .await
+    }
+
+    /// The handle used to access the document from the blocking pool, e.g. to run other
+    /// operations with [`tokio::task::spawn_blocking`].
+    pub fn handle(&self) -> &PoolHandle {
+        &self.handle
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::AsyncDocument;
+    use crate::RenderOptions;
+
+    #[tokio::test]
+    async fn test_async_document() {
+        let doc = AsyncDocument::open("tests/files/dummy.pdf").await.unwrap();
+        assert_eq!(doc.page_count().await.unwrap(), 1);
+        let pixmap = doc.render_page(0, RenderOptions::new()).await.unwrap();
+        assert_eq!((pixmap.width(), pixmap.height()), (595, 842));
+        assert!(doc.extract_text(0).await.is_ok());
+    }
+}
@@ -3,7 +3,7 @@ use std::slice;
 
 use mupdf_sys::*;
 
-use crate::{context, Error, Pixmap};
+use crate::{context, Buffer, Error, Pixmap};
 
 /// Bitmaps have 1 bit per component.
 /// Only used for creating halftoned versions of contone buffers, and saving out.
@@ -19,6 +19,12 @@ impl Bitmap {
             .map(|inner| Self { inner })
     }
 
+    /// The samples compressed with CCITT Group 4, set bits being black.
+    pub(crate) fn to_ccitt_g4(&self) -> Result<Buffer, Error> {
+        unsafe { ffi_try!(mupdf_compress_ccitt_fax_g4(context(), self.inner)) }
+            .map(|inner| unsafe { Buffer::from_raw(inner) })
+    }
+
     /// Width of the region in pixels.
     pub fn width(&self) -> u32 {
         unsafe { (*self.inner).w as u32 }
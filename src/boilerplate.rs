@@ -0,0 +1,292 @@
+use std::collections::HashMap;
+
+use crate::text_page::TextBlockType;
+use crate::{Document, Error, Rect, TextBlock, TextPage, TextPageOptions};
+
+/// Share of the page height at the top and bottom where headers and footers are looked for.
+const MARGIN_ZONE: f32 = 0.12;
+
+/// How far apart, in points, repeated blocks may be on different pages.
+const POSITION_TOLERANCE: f32 = 4.0;
+
+/// What a block of a page is, as found by [`classify_blocks`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockRole {
+    Body,
+    /// Running text at the top of the page, e.g. a document or chapter title.
+    Header,
+    /// Running text at the bottom of the page, e.g. a copyright notice.
+    Footer,
+    /// A page number, at the top or the bottom of the page.
+    PageNumber,
+}
+
+fn block_text(block: &TextBlock) -> String {
+    block
+        .lines()
+        .map(|line| line.chars().filter_map(|c| c.char()).collect::<String>())
+        .collect::<Vec<_>>()
+        .join(" ")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+const NUMERALS: [(u32, &str); 13] = [
+    (1000, "m"),
+    (900, "cm"),
+    (500, "d"),
+    (400, "cd"),
+    (100, "c"),
+    (90, "xc"),
+    (50, "l"),
+    (40, "xl"),
+    (10, "x"),
+    (9, "ix"),
+    (5, "v"),
+    (4, "iv"),
+    (1, "i"),
+];
+
+/// Whether `s` is a lowercase roman numeral in canonical form, so that words like `mix` aren't.
+fn is_roman(s: &str) -> bool {
+    let mut value = 0;
+    let mut rest = s;
+    while !rest.is_empty() {
+        let Some(&(v, numeral)) = NUMERALS.iter().find(|(_, n)| rest.starts_with(n)) else {
+            return false;
+        };
+        value += v;
+        rest = &rest[numeral.len()..];
+    }
+    let mut canonical = String::new();
+    for (v, numeral) in NUMERALS {
+        while value >= v {
+            canonical.push_str(numeral);
+            value -= v;
+        }
+    }
+    !s.is_empty() && canonical == s
+}
+
+/// Whether `text` is e.g. `12`, `- 12 -`, `Page 12`, `p. xii`, `12 of 30` or `12/30`.
+fn is_page_number(text: &str) -> bool {
+    let text = text.to_lowercase();
+    let mut text = text.trim().trim_matches(['-', '–', '—', '|', ' ']);
+    for prefix in ["page", "pg.", "p."] {
+        if let Some(rest) = text.strip_prefix(prefix) {
+            text = rest.trim_start();
+            break;
+        }
+    }
+    let number = match text.split_once(" of ").or_else(|| text.split_once('/')) {
+        Some((number, total)) if total.trim().chars().all(|c| c.is_ascii_digit()) => number.trim(),
+        Some(_) => return false,
+        None => text,
+    };
+    (!number.is_empty() && number.len() <= 5 && number.chars().all(|c| c.is_ascii_digit()))
+        || is_roman(number)
+}
+
+/// The text with runs of digits replaced, so running headers with page numbers match.
+fn normalize(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        if c.is_ascii_digit() {
+            if !out.ends_with('#') {
+                out.push('#');
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Zone {
+    Top,
+    Bottom,
+}
+
+struct Candidate {
+    page: usize,
+    block: usize,
+    zone: Zone,
+    bounds: Rect,
+    text: String,
+}
+
+/// Classify the blocks of consecutive pages of a document into body text, headers, footers and
+/// page numbers, returning the roles of each page's blocks in the order of
+/// [`TextPage::blocks`].
+///
+/// Headers and footers are blocks near the top or bottom edge of the page that recur at the
+/// same position with the same text, digits aside, on at least three pages, or on both pages
+/// of a two page document. Page numbers are recognized by their form, e.g. `12`, `Page 12 of
+/// 30` or `xii`, on any page. The more pages are given, the more reliable the result.
+pub fn classify_blocks(pages: &[TextPage]) -> Vec<Vec<BlockRole>> {
+    let mut roles: Vec<Vec<BlockRole>> = pages
+        .iter()
+        .map(|page| vec![BlockRole::Body; page.blocks().count()])
+        .collect();
+
+    let mut candidates = Vec::new();
+    for (p, page) in pages.iter().enumerate() {
+        let bounds = page.bounds();
+        let zone_height = bounds.height() * MARGIN_ZONE;
+        for (b, block) in page.blocks().enumerate() {
+            if block.r#type() != TextBlockType::Text {
+                continue;
+            }
+            let rect = block.bounds();
+            let zone = if rect.y1 <= bounds.y0 + zone_height {
+                Zone::Top
+            } else if rect.y0 >= bounds.y1 - zone_height {
+                Zone::Bottom
+            } else {
+                continue;
+            };
+            let text = block_text(&block);
+            if text.is_empty() {
+                continue;
+            }
+            if is_page_number(&text) {
+                roles[p][b] = BlockRole::PageNumber;
+                continue;
+            }
+            candidates.push(Candidate {
+                page: p,
+                block: b,
+                zone,
+                bounds: rect,
+                text: normalize(&text),
+            });
+        }
+    }
+
+    let min_pages = pages.len().clamp(2, 3);
+    let mut by_text: HashMap<(Zone, &str), Vec<&Candidate>> = HashMap::new();
+    for candidate in &candidates {
+        by_text
+            .entry((candidate.zone, candidate.text.as_str()))
+            .or_default()
+            .push(candidate);
+    }
+    for ((zone, _), group) in by_text {
+        for candidate in &group {
+            let mut pages_seen: Vec<usize> = group
+                .iter()
+                .filter(|other| (other.bounds.y0 - candidate.bounds.y0).abs() <= POSITION_TOLERANCE)
+                .map(|other| other.page)
+                .collect();
+            pages_seen.dedup();
+            if pages_seen.len() >= min_pages {
+                roles[candidate.page][candidate.block] = match zone {
+                    Zone::Top => BlockRole::Header,
+                    Zone::Bottom => BlockRole::Footer,
+                };
+            }
+        }
+    }
+    roles
+}
+
+impl Document {
+    /// The plain text of every page without headers, footers and page numbers, as found by
+    /// [`classify_blocks`].
+    pub fn body_text(&self) -> Result<Vec<String>, Error> {
+        let pages = self
+            .pages()?
+            .map(|page| page?.to_text_page(TextPageOptions::empty()))
+            .collect::<Result<Vec<_>, Error>>()?;
+        let roles = classify_blocks(&pages);
+        Ok(pages
+            .iter()
+            .zip(roles)
+            .map(|(page, roles)| {
+                let mut text = String::new();
+                for (block, role) in page.blocks().zip(roles) {
+                    if role != BlockRole::Body || block.r#type() != TextBlockType::Text {
+                        continue;
+                    }
+                    for line in block.lines() {
+                        text.extend(line.chars().filter_map(|c| c.char()));
+                        text.push('\n');
+                    }
+                    text.push('\n');
+                }
+                text
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{classify_blocks, is_page_number, normalize, BlockRole};
+    use crate::pdf::{PageOptions, PdfDocument};
+    use crate::TextPageOptions;
+
+    #[test]
+    fn test_is_page_number() {
+        for text in ["12", "- 12 -", "Page 12", "page 3 of 30", "p. xii", "4/10"] {
+            assert!(is_page_number(text), "{}", text);
+        }
+        for text in ["2024 annual report", "Page layout", "of", "Mix", "iiii", ""] {
+            assert!(!is_page_number(text), "{}", text);
+        }
+        assert_eq!(normalize("Chapter 12, page 130"), "Chapter #, page #");
+    }
+
+    #[test]
+    fn test_classify_blocks() {
+        // Paragraph names without digits, so that they don't look alike to `normalize`
+        let html: String = (0..150u8)
+            .map(|i| {
+                format!(
+                    "<p>Paragraph {}{}</p>",
+                    (b'a' + i / 26) as char,
+                    (b'a' + i % 26) as char
+                )
+            })
+            .collect();
+        let doc = PdfDocument::from_html(
+            &html,
+            "",
+            PageOptions::new()
+                .set_header("<p>ACME Corporation annual report</p>")
+                .set_footer("<p>Page {page} of {pages}</p>"),
+        )
+        .unwrap();
+        assert!(doc.page_count().unwrap() >= 3);
+
+        let pages = doc
+            .pages()
+            .unwrap()
+            .map(|page| {
+                page.unwrap()
+                    .to_text_page(TextPageOptions::empty())
+                    .unwrap()
+            })
+            .collect::<Vec<_>>();
+        let roles = classify_blocks(&pages);
+        for roles in &roles {
+            assert_eq!(roles.iter().filter(|&&r| r == BlockRole::Header).count(), 1);
+            assert_eq!(
+                roles
+                    .iter()
+                    .filter(|&&r| r == BlockRole::PageNumber)
+                    .count(),
+                1
+            );
+            assert!(roles.contains(&BlockRole::Body));
+        }
+
+        let text = doc.body_text().unwrap();
+        assert!(text[0].contains("Paragraph aa"));
+        assert!(text
+            .iter()
+            .all(|t| !t.contains("ACME") && !t.contains("Page ")));
+    }
+}
@@ -0,0 +1,143 @@
+use std::time::{Duration, Instant};
+
+use crate::{Colorspace, Cookie, Device, DisplayList, Error, IRect, Matrix, Pixmap};
+
+/// Rows rendered between two checks of the time budget.
+const DEFAULT_BAND_HEIGHT: i32 = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenderStatus {
+    /// The budget ran out, call [`BudgetedRender::step`] again to continue.
+    Pending,
+    Complete,
+}
+
+/// Rendering of a display list that can be driven in time slices, so that a single threaded
+/// application can keep its event loop responsive while rendering a large page.
+///
+/// The pixmap is filled in horizontal bands from top to bottom. The budget is checked between
+/// bands, so a step may overrun it by the time needed to render one band.
+#[derive(Debug)]
+pub struct BudgetedRender {
+    list: DisplayList,
+    ctm: Matrix,
+    pixmap: Pixmap,
+    bbox: IRect,
+    next_row: i32,
+    band_height: i32,
+    cookie: Cookie,
+}
+
+impl BudgetedRender {
+    pub fn new(
+        list: DisplayList,
+        ctm: &Matrix,
+        cs: &Colorspace,
+        alpha: bool,
+    ) -> Result<Self, Error> {
+        let pixmap = list.blank_pixmap(ctm, cs, alpha)?;
+        let bbox = pixmap.rect();
+        Ok(Self {
+            list,
+            ctm: ctm.clone(),
+            pixmap,
+            bbox,
+            next_row: bbox.y0,
+            band_height: DEFAULT_BAND_HEIGHT,
+            cookie: Cookie::new()?,
+        })
+    }
+
+    /// Trade responsiveness for throughput: smaller bands keep steps closer to their budget.
+    pub fn set_band_height(&mut self, rows: u32) -> &mut Self {
+        self.band_height = rows.clamp(1, i32::MAX as u32) as i32;
+        self
+    }
+
+    /// Render for at most about `budget`, returning whether the pixmap is complete.
+    pub fn step(&mut self, budget: Duration) -> Result<RenderStatus, Error> {
+        let start = Instant::now();
+        while !self.is_complete() {
+            if self.cookie.is_aborted() {
+                return Err(Error::Aborted);
+            }
+            let band = IRect::new(
+                self.bbox.x0,
+                self.next_row,
+                self.bbox.x1,
+                self.next_row
+                    .saturating_add(self.band_height)
+                    .min(self.bbox.y1),
+            );
+            {
+                let device = Device::from_pixmap_with_clip(&self.pixmap, band)?;
+                self.list
+                    .run_with_cookie(&device, &self.ctm, band.into(), &self.cookie)?;
+            }
+            self.next_row = band.y1;
+            if start.elapsed() >= budget {
+                break;
+            }
+        }
+        Ok(if self.is_complete() {
+            RenderStatus::Complete
+        } else {
+            RenderStatus::Pending
+        })
+    }
+
+    /// Render everything that is left in one go.
+    pub fn finish(mut self) -> Result<Pixmap, Error> {
+        self.step(Duration::MAX)?;
+        Ok(self.pixmap)
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.next_row >= self.bbox.y1
+    }
+
+    /// Fraction of the rows rendered so far, from `0.0` to `1.0`.
+    pub fn progress(&self) -> f32 {
+        if self.bbox.height() <= 0 {
+            return 1.0;
+        }
+        (self.next_row - self.bbox.y0) as f32 / self.bbox.height() as f32
+    }
+
+    /// Stop rendering, the current and any later step fail with [`Error::Aborted`].
+    pub fn abort(&mut self) {
+        self.cookie.abort();
+    }
+
+    /// The pixmap rendered so far, rows past the progress still being blank.
+    pub fn pixmap(&self) -> &Pixmap {
+        &self.pixmap
+    }
+
+    pub fn into_pixmap(self) -> Pixmap {
+        self.pixmap
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use super::{BudgetedRender, RenderStatus};
+    use crate::{Colorspace, Document, Matrix};
+
+    #[test]
+    fn test_budgeted_render() {
+        let doc = Document::open("tests/files/dummy.pdf").unwrap();
+        let page = doc.load_page(0).unwrap();
+        let list = page.to_display_list(true).unwrap();
+        let mut render =
+            BudgetedRender::new(list, &Matrix::IDENTITY, &Colorspace::device_rgb(), false).unwrap();
+        render.set_band_height(100);
+        assert_eq!(render.step(Duration::ZERO).unwrap(), RenderStatus::Pending);
+        assert!(render.progress() > 0.0 && render.progress() < 1.0);
+
+        let pixmap = render.finish().unwrap();
+        assert_eq!((pixmap.width(), pixmap.height()), (595, 842));
+    }
+}
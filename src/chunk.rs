@@ -0,0 +1,200 @@
+use std::fmt;
+use std::sync::Arc;
+
+use crate::text_page::QuadGranularity;
+use crate::{Document, Error, Quad, TextPageOptions};
+
+/// Where a part of a [`TextChunk`] comes from: a line, or part of a line, on a page.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct ChunkSource {
+    pub page: i32,
+    pub quad: Quad,
+}
+
+/// A piece of document text sized for embedding, e.g. in a retrieval augmented generation
+/// pipeline.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct TextChunk {
+    pub text: String,
+    pub token_count: usize,
+    pub sources: Vec<ChunkSource>,
+}
+
+type TokenCounter = Arc<dyn Fn(&str) -> usize + Send + Sync>;
+
+/// Options for [`Document::text_chunks`].
+#[derive(Clone)]
+pub struct ChunkOptions {
+    target_tokens: usize,
+    overlap_tokens: usize,
+    count_tokens: TokenCounter,
+}
+
+impl fmt::Debug for ChunkOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ChunkOptions")
+            .field("target_tokens", &self.target_tokens)
+            .field("overlap_tokens", &self.overlap_tokens)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for ChunkOptions {
+    fn default() -> Self {
+        Self {
+            target_tokens: 256,
+            overlap_tokens: 32,
+            count_tokens: Arc::new(|_| 1),
+        }
+    }
+}
+
+impl ChunkOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Tokens per chunk to aim for, chunks ending at the first word reaching it.
+    pub fn set_target_tokens(&mut self, tokens: usize) -> &mut Self {
+        self.target_tokens = tokens.max(1);
+        self
+    }
+
+    /// Tokens at the end of a chunk repeated at the start of the next one.
+    pub fn set_overlap_tokens(&mut self, tokens: usize) -> &mut Self {
+        self.overlap_tokens = tokens;
+        self
+    }
+
+    /// Count the tokens of each word with the tokenizer of the embedding model, instead of
+    /// counting one token per word.
+    pub fn set_token_counter<F>(&mut self, count: F) -> &mut Self
+    where
+        F: Fn(&str) -> usize + Send + Sync + 'static,
+    {
+        self.count_tokens = Arc::new(count);
+        self
+    }
+}
+
+struct Word {
+    text: String,
+    page: i32,
+    line: usize,
+    quad: Quad,
+    tokens: usize,
+}
+
+impl Word {
+    fn new(text: String, page: i32, line: usize, quad: Quad, options: &ChunkOptions) -> Self {
+        Self {
+            tokens: (options.count_tokens)(&text),
+            text,
+            page,
+            line,
+            quad,
+        }
+    }
+}
+
+impl Document {
+    /// Split the text of the document into overlapping chunks of about the same number of tokens,
+    /// recording the lines each chunk was taken from.
+    pub fn text_chunks(&self, options: &ChunkOptions) -> Result<Vec<TextChunk>, Error> {
+        let mut words = Vec::new();
+        let mut line = 0;
+        for page_no in 0..self.page_count()? {
+            let text_page = self
+                .load_page(page_no)?
+                .to_text_page(TextPageOptions::empty())?;
+            for runs in text_page.line_runs(QuadGranularity::Word, f32::INFINITY) {
+                line += 1;
+                for run in runs {
+                    words.push(Word::new(run.text, page_no, line, run.quad, options));
+                }
+            }
+        }
+        Ok(chunk_words(&words, options))
+    }
+}
+
+fn chunk_words(words: &[Word], options: &ChunkOptions) -> Vec<TextChunk> {
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    while start < words.len() {
+        let mut end = start;
+        let mut tokens = 0;
+        while end < words.len() && tokens < options.target_tokens {
+            tokens += words[end].tokens;
+            end += 1;
+        }
+        chunks.push(make_chunk(&words[start..end], tokens));
+        if end == words.len() {
+            break;
+        }
+        // Step back over at most `overlap_tokens`, always moving forward
+        let mut next = end;
+        let mut overlap = 0;
+        while next > start + 1 && overlap + words[next - 1].tokens <= options.overlap_tokens {
+            overlap += words[next - 1].tokens;
+            next -= 1;
+        }
+        start = next;
+    }
+    chunks
+}
+
+fn make_chunk(words: &[Word], token_count: usize) -> TextChunk {
+    let mut text = String::new();
+    let mut sources: Vec<ChunkSource> = Vec::new();
+    let mut prev: Option<&Word> = None;
+    for word in words {
+        match prev {
+            Some(p) if p.page != word.page => text.push_str("\n\n"),
+            Some(p) if p.line != word.line => text.push('\n'),
+            Some(_) => text.push(' '),
+            None => {}
+        }
+        text.push_str(&word.text);
+        match (prev, sources.last_mut()) {
+            (Some(p), Some(source)) if p.line == word.line => {
+                source.quad.ur = word.quad.ur;
+                source.quad.lr = word.quad.lr;
+            }
+            _ => sources.push(ChunkSource {
+                page: word.page,
+                quad: word.quad.clone(),
+            }),
+        }
+        prev = Some(word);
+    }
+    TextChunk {
+        text,
+        token_count,
+        sources,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ChunkOptions;
+    use crate::Document;
+
+    #[test]
+    fn test_document_text_chunks() {
+        let doc = Document::open("tests/files/dummy.pdf").unwrap();
+        let mut options = ChunkOptions::new();
+        options.set_target_tokens(2).set_overlap_tokens(1);
+        let chunks = doc.text_chunks(&options).unwrap();
+        assert!(chunks.len() > 1);
+        assert!(chunks[0].text.starts_with("Dummy"));
+        assert!(chunks
+            .iter()
+            .all(|c| c.token_count <= 2 && !c.sources.is_empty()));
+        // The last word of a chunk starts the next one
+        let last = chunks[0].text.split_whitespace().last().unwrap();
+        assert!(chunks[1].text.starts_with(last));
+    }
+}
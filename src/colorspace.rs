@@ -1,40 +1,48 @@
 use std::cmp::PartialEq;
 use std::ffi::CStr;
 use std::fmt;
+use std::io::Read;
 use std::ptr;
 
 use mupdf_sys::*;
 
-use crate::{context, ColorParams, Error};
+use crate::{context, Buffer, ColorParams, Error};
 
 #[derive(Debug)]
 pub struct Colorspace {
     pub(crate) inner: *mut fz_colorspace,
 }
 
+// Colorspaces are immutable once created, reference counted under the allocation lock and
+// shared by all cloned contexts
+unsafe impl Send for Colorspace {}
+unsafe impl Sync for Colorspace {}
+
 impl Colorspace {
+    /// Take over a reference to `inner`.
     pub(crate) unsafe fn from_raw(inner: *mut fz_colorspace) -> Self {
         Self { inner }
     }
 
+    /// Keep a reference to `inner`, owned by something else.
+    pub(crate) unsafe fn from_raw_kept(inner: *mut fz_colorspace) -> Self {
+        Self::from_raw(fz_keep_colorspace(context(), inner))
+    }
+
     pub fn device_gray() -> Self {
-        let inner = unsafe { fz_device_gray(context()) };
-        Self { inner }
+        unsafe { Self::from_raw_kept(fz_device_gray(context())) }
     }
 
     pub fn device_rgb() -> Self {
-        let inner = unsafe { fz_device_rgb(context()) };
-        Self { inner }
+        unsafe { Self::from_raw_kept(fz_device_rgb(context())) }
     }
 
     pub fn device_bgr() -> Self {
-        let inner = unsafe { fz_device_bgr(context()) };
-        Self { inner }
+        unsafe { Self::from_raw_kept(fz_device_bgr(context())) }
     }
 
     pub fn device_cmyk() -> Self {
-        let inner = unsafe { fz_device_cmyk(context()) };
-        Self { inner }
+        unsafe { Self::from_raw_kept(fz_device_cmyk(context())) }
     }
 
     pub fn n(&self) -> u32 {
@@ -87,6 +95,19 @@ impl Colorspace {
         name_cstr.to_str().unwrap()
     }
 
+    /// The ICC profile of the colorspace, `None` for colorspaces without one such as indexed
+    /// and separation colorspaces, or when MuPDF is built without ICC support.
+    pub fn icc_profile(&self) -> Result<Option<Vec<u8>>, Error> {
+        let inner = unsafe { ffi_try!(mupdf_colorspace_icc_profile(context(), self.inner)) }?;
+        if inner.is_null() {
+            return Ok(None);
+        }
+        let mut buf = unsafe { Buffer::from_raw(inner) };
+        let mut profile = Vec::with_capacity(buf.len());
+        buf.read_to_end(&mut profile)?;
+        Ok(Some(profile))
+    }
+
     pub fn convert_color(
         &self,
         color: &[f32],
@@ -144,6 +165,18 @@ impl Colorspace {
     }
 }
 
+impl Clone for Colorspace {
+    fn clone(&self) -> Self {
+        unsafe { Self::from_raw_kept(self.inner) }
+    }
+}
+
+impl Drop for Colorspace {
+    fn drop(&mut self) {
+        unsafe { fz_drop_colorspace(context(), self.inner) }
+    }
+}
+
 impl PartialEq for Colorspace {
     fn eq(&self, other: &Self) -> bool {
         self.inner == other.inner
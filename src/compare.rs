@@ -0,0 +1,307 @@
+use crate::{Colorspace, Error, IRect, Matrix, Page, Pixmap, Rect};
+
+/// Options for [`diff_pages`].
+#[derive(Debug, Clone)]
+pub struct DiffOptions {
+    resolution: f32,
+    tolerance: u8,
+    merge_distance: u32,
+    compare_text: bool,
+}
+
+impl Default for DiffOptions {
+    fn default() -> Self {
+        Self {
+            resolution: 72.0,
+            tolerance: 0,
+            merge_distance: 8,
+            compare_text: true,
+        }
+    }
+}
+
+impl DiffOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Resolution in DPI the pages are rendered at, 72 by default.
+    pub fn resolution(&self) -> f32 {
+        self.resolution
+    }
+
+    pub fn set_resolution(&mut self, value: f32) -> &mut Self {
+        self.resolution = value;
+        self
+    }
+
+    /// How much a color component may differ before a pixel counts as changed, 0 by default.
+    /// A small tolerance hides anti-aliasing differences between renderers.
+    pub fn tolerance(&self) -> u8 {
+        self.tolerance
+    }
+
+    pub fn set_tolerance(&mut self, value: u8) -> &mut Self {
+        self.tolerance = value;
+        self
+    }
+
+    /// Distance in pixels within which changed pixels are merged into one region, 8 by default.
+    pub fn merge_distance(&self) -> u32 {
+        self.merge_distance
+    }
+
+    pub fn set_merge_distance(&mut self, value: u32) -> &mut Self {
+        self.merge_distance = value;
+        self
+    }
+
+    /// Whether to compare the text of the pages as well, true by default.
+    pub fn compare_text(&self) -> bool {
+        self.compare_text
+    }
+
+    pub fn set_compare_text(&mut self, value: bool) -> &mut Self {
+        self.compare_text = value;
+        self
+    }
+}
+
+/// A line of text only one of two compared pages has.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TextChange {
+    /// A line of the first page missing from the second.
+    Removed(String),
+    /// A line of the second page missing from the first.
+    Added(String),
+}
+
+/// The result of [`diff_pages`].
+#[derive(Debug)]
+pub struct DiffResult {
+    /// The first page faded to gray, with changed pixels in red.
+    pub diff: Pixmap,
+    pub changed_pixels: usize,
+    /// Bounding boxes of groups of changed pixels, in page coordinates of the first page.
+    pub regions: Vec<Rect>,
+    /// The changed lines of text, in reading order. Empty unless
+    /// [`DiffOptions::compare_text`] is set.
+    pub text_changes: Vec<TextChange>,
+}
+
+impl DiffResult {
+    /// Whether the pages look and read the same.
+    pub fn is_identical(&self) -> bool {
+        self.changed_pixels == 0 && self.text_changes.is_empty()
+    }
+}
+
+/// Compare two pages by rendering them, e.g. the output of a document pipeline against a known
+/// good version of it. Pages of different sizes are compared from their top left corners, with
+/// the area only one of them covers counting as changed.
+pub fn diff_pages(a: &Page, b: &Page, options: &DiffOptions) -> Result<DiffResult, Error> {
+    let scale = options.resolution / 72.0;
+    let ctm = Matrix::new_scale(scale, scale);
+    let rgb = Colorspace::device_rgb();
+    let pix_a = a.to_pixmap(&ctm, &rgb, false, false)?;
+    let pix_b = b.to_pixmap(&ctm, &rgb, false, false)?;
+
+    let width = pix_a.width().max(pix_b.width()) as usize;
+    let height = pix_a.height().max(pix_b.height()) as usize;
+    let mut diff = Pixmap::new_with_w_h(&rgb, width as i32, height as i32, false)?;
+    let mut changed = vec![false; width * height];
+    let mut changed_pixels = 0;
+    let pixel = |pix: &Pixmap, x: usize, y: usize| -> Option<[u8; 3]> {
+        if x >= pix.width() as usize || y >= pix.height() as usize {
+            return None;
+        }
+        let i = (y * pix.width() as usize + x) * 3;
+        let s = pix.samples();
+        Some([s[i], s[i + 1], s[i + 2]])
+    };
+    let samples = diff.samples_mut();
+    for y in 0..height {
+        for x in 0..width {
+            let (pa, pb) = (pixel(&pix_a, x, y), pixel(&pix_b, x, y));
+            let is_changed = match (pa, pb) {
+                (Some(pa), Some(pb)) => pa
+                    .iter()
+                    .zip(pb)
+                    .any(|(&ca, cb)| ca.abs_diff(cb) > options.tolerance),
+                _ => true,
+            };
+            let i = (y * width + x) * 3;
+            if is_changed {
+                changed[y * width + x] = true;
+                changed_pixels += 1;
+                samples[i..i + 3].copy_from_slice(&[255, 0, 0]);
+            } else {
+                let [r, g, b] = pa.unwrap_or([255; 3]);
+                let gray = (r as u32 * 77 + g as u32 * 150 + b as u32 * 29) >> 8;
+                // Fade towards white so the changes stand out
+                samples[i..i + 3].fill((191 + gray / 4) as u8);
+            }
+        }
+    }
+
+    let inverse = ctm.invert().unwrap_or_default();
+    let origin = pix_a.rect();
+    let regions = changed_regions(&changed, width, height, options.merge_distance.max(1))
+        .into_iter()
+        .map(|r| {
+            Rect::from(IRect::new(
+                r.x0 + origin.x0,
+                r.y0 + origin.y0,
+                r.x1 + origin.x0,
+                r.y1 + origin.y0,
+            ))
+            .transform(&inverse)
+        })
+        .collect();
+
+    let text_changes = if options.compare_text {
+        diff_lines(&a.to_text()?, &b.to_text()?)
+    } else {
+        Vec::new()
+    };
+
+    Ok(DiffResult {
+        diff,
+        changed_pixels,
+        regions,
+        text_changes,
+    })
+}
+
+/// Group changed pixels into cells of `cell` pixels and return the bounding boxes of the changed
+/// pixels of each group of touching changed cells.
+fn changed_regions(changed: &[bool], width: usize, height: usize, cell: u32) -> Vec<IRect> {
+    let cell = cell as usize;
+    let cols = width.div_ceil(cell);
+    let rows = height.div_ceil(cell);
+    let mut bounds: Vec<Option<IRect>> = vec![None; cols * rows];
+    for y in 0..height {
+        for x in 0..width {
+            if changed[y * width + x] {
+                let pixel = IRect::new(x as i32, y as i32, x as i32 + 1, y as i32 + 1);
+                bounds[(y / cell) * cols + x / cell]
+                    .get_or_insert(pixel)
+                    .r#union(pixel);
+            }
+        }
+    }
+
+    let mut regions = Vec::new();
+    let mut seen = vec![false; cols * rows];
+    for start in 0..cols * rows {
+        if seen[start] || bounds[start].is_none() {
+            continue;
+        }
+        let mut region = bounds[start].unwrap();
+        let mut stack = vec![start];
+        seen[start] = true;
+        while let Some(i) = stack.pop() {
+            region.r#union(bounds[i].unwrap());
+            let (row, col) = (i / cols, i % cols);
+            for (dr, dc) in [
+                (-1, -1),
+                (-1, 0),
+                (-1, 1),
+                (0, -1),
+                (0, 1),
+                (1, -1),
+                (1, 0),
+                (1, 1),
+            ] {
+                let (r, c) = (row as isize + dr, col as isize + dc);
+                if r < 0 || c < 0 || r >= rows as isize || c >= cols as isize {
+                    continue;
+                }
+                let j = r as usize * cols + c as usize;
+                if !seen[j] && bounds[j].is_some() {
+                    seen[j] = true;
+                    stack.push(j);
+                }
+            }
+        }
+        regions.push(region);
+    }
+    regions
+}
+
+/// The lines only one of `a` and `b` has, from their longest common subsequence.
+fn diff_lines(a: &str, b: &str) -> Vec<TextChange> {
+    let a: Vec<&str> = a.lines().map(str::trim).filter(|l| !l.is_empty()).collect();
+    let b: Vec<&str> = b.lines().map(str::trim).filter(|l| !l.is_empty()).collect();
+    // lcs[i][j] is the length of the longest common subsequence of a[i..] and b[j..]
+    let mut lcs = vec![vec![0u32; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+    let mut changes = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            i += 1;
+            j += 1;
+        } else if j == b.len() || (i < a.len() && lcs[i + 1][j] >= lcs[i][j + 1]) {
+            changes.push(TextChange::Removed(a[i].to_owned()));
+            i += 1;
+        } else {
+            changes.push(TextChange::Added(b[j].to_owned()));
+            j += 1;
+        }
+    }
+    changes
+}
+
+#[cfg(test)]
+mod test {
+    use super::{diff_lines, diff_pages, DiffOptions, TextChange};
+    use crate::pdf::PdfDocument;
+    use crate::Document;
+
+    #[test]
+    fn test_diff_lines() {
+        assert_eq!(
+            diff_lines("one\ntwo\nthree\n", "one\n2\nthree\nfour\n"),
+            [
+                TextChange::Removed("two".to_owned()),
+                TextChange::Added("2".to_owned()),
+                TextChange::Added("four".to_owned()),
+            ]
+        );
+        assert!(diff_lines("same\n", "same\n").is_empty());
+    }
+
+    #[test]
+    fn test_diff_pages() {
+        let doc = Document::open("tests/files/dummy.pdf").unwrap();
+        let page = doc.load_page(0).unwrap();
+        let result = diff_pages(&page, &page, &DiffOptions::new()).unwrap();
+        assert!(result.is_identical());
+        assert!(result.regions.is_empty());
+        assert_eq!(result.diff.width(), 595);
+
+        let mut blank = PdfDocument::new();
+        blank.new_page((595.0, 842.0)).unwrap();
+        let blank_page = blank.load_page(0).unwrap();
+        let result = diff_pages(&page, &blank_page, &DiffOptions::new()).unwrap();
+        assert!(result.changed_pixels > 0);
+        assert_eq!(
+            result.text_changes,
+            [TextChange::Removed("Dummy PDF file".to_owned())]
+        );
+        // The title is the only thing on the page
+        assert_eq!(result.regions.len(), 1);
+        let region = result.regions[0];
+        assert!(region.x0 >= 56.0 && region.x1 <= 117.0);
+        assert!(region.y0 >= 69.0 && region.y1 <= 88.0);
+    }
+}
@@ -1,16 +1,29 @@
+use std::alloc::GlobalAlloc;
 use std::cell::RefCell;
 use std::ffi::{CStr, CString};
 use std::ptr;
 use std::sync::Mutex;
 
 use mupdf_sys::*;
-use once_cell::sync::Lazy;
+use once_cell::sync::{Lazy, OnceCell};
 
+use crate::allocator::{self, MemoryUsage};
+use crate::document_handler::{self, DocumentHandler};
+use crate::message;
+use crate::stats::{self, ResourceStats};
 use crate::Error;
 
+static STORE_LIMIT: OnceCell<usize> = OnceCell::new();
+
 static BASE_CONTEXT: Lazy<Mutex<BaseContext>> = Lazy::new(|| {
+    let max_store = *STORE_LIMIT.get_or_init(|| FZ_STORE_DEFAULT as usize);
+    // MuPDF copies the allocator callbacks into the context
+    let alloc = allocator::alloc_context();
     let ctx = unsafe {
-        let base_ctx = mupdf_new_base_context();
+        let base_ctx = mupdf_new_base_context(&alloc, max_store);
+        // Cloned contexts inherit these
+        fz_set_warning_callback(base_ctx, Some(message::warning_callback), ptr::null_mut());
+        fz_set_error_callback(base_ctx, Some(message::error_callback), ptr::null_mut());
         #[cfg(all(not(target_os = "android"), feature = "system-fonts"))]
         {
             use crate::system_font;
@@ -59,6 +72,14 @@ impl Drop for RawContext {
     }
 }
 
+/// Current resource store (cache) usage, in bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StoreUsage {
+    pub size: usize,
+    /// The configured limit, `0` meaning unlimited.
+    pub max: usize,
+}
+
 #[derive(Debug)]
 pub struct Context {
     pub(crate) inner: *mut fz_context,
@@ -83,6 +104,99 @@ impl Context {
         })
     }
 
+    /// Get the context, limiting the resource store shared by all contexts to `bytes`.
+    ///
+    /// The store is created together with the first context, so this must be called before any
+    /// other use of the library. An error is returned if the store already exists with a different
+    /// limit.
+    pub fn new_with_store_limit(bytes: usize) -> Result<Self, Error> {
+        let limit = *STORE_LIMIT.get_or_init(|| bytes);
+        if limit != bytes {
+            return Err(Error::StoreLimitAlreadySet(limit));
+        }
+        Ok(Self::get())
+    }
+
+    /// Evict items from the store until it is at most `percent`% of its current size.
+    ///
+    /// Returns `false` if the store could not be shrunk that much because the remaining items are
+    /// in use.
+    pub fn shrink_store(&mut self, percent: u32) -> bool {
+        unsafe { fz_shrink_store(self.inner, percent) != 0 }
+    }
+
+    /// Evict every item that is not currently in use from the store.
+    pub fn empty_store(&mut self) {
+        unsafe { fz_empty_store(self.inner) }
+    }
+
+    /// This walks every item in the store, so avoid calling it on a hot path.
+    pub fn store_usage(&self) -> Result<StoreUsage, Error> {
+        let size = unsafe { ffi_try!(mupdf_store_size(self.inner)) }?;
+        // The store was created with this limit along with the first context
+        let max = STORE_LIMIT.get().copied().unwrap_or(0);
+        Ok(StoreUsage { size, max })
+    }
+
+    /// Route all of MuPDF's allocations through `alloc`, e.g. a jemalloc or mimalloc instance.
+    ///
+    /// Like the store limit, the allocator is fixed when the first context is created, so this
+    /// must be called before any other use of the library. Returns `false` if an allocator is
+    /// already in place.
+    ///
+    /// With the `memento` feature MuPDF always uses Memento's allocator and `alloc` is ignored.
+    pub fn set_allocator(alloc: &'static (dyn GlobalAlloc + Sync)) -> bool {
+        allocator::set(alloc)
+    }
+
+    /// Memory currently held by MuPDF, tracked separately from the Rust heap.
+    ///
+    /// Allocations are counted by the allocator this crate hands to MuPDF, which the `memento`
+    /// feature replaces, so with it every count stays at zero.
+    pub fn memory_usage(&self) -> MemoryUsage {
+        allocator::usage()
+    }
+
+    /// Cap the memory MuPDF may allocate, `None` meaning unlimited.
+    ///
+    /// The limit is process-wide: it applies to the allocations of every context on every thread,
+    /// and can be changed at any time. When an allocation would exceed the limit MuPDF first
+    /// evicts items from the store, and fails the operation with an out of memory error if that
+    /// isn't enough. Not enforced with the `memento` feature, see [`Context::memory_usage`].
+    pub fn set_memory_limit(bytes: Option<usize>) {
+        allocator::set_limit(bytes)
+    }
+
+    /// Counts of open documents, pages and pixmaps along with store and memory usage, e.g. to
+    /// spot leaks in a long-running server.
+    ///
+    /// The `memory` counts are zero with the `memento` feature, see [`Context::memory_usage`].
+    pub fn resource_stats(&self) -> Result<ResourceStats, Error> {
+        Ok(stats::snapshot(self.store_usage()?, self.memory_usage()))
+    }
+
+    /// Check the heap for corruption and print statistics and every block still allocated to
+    /// stderr.
+    #[cfg(feature = "memento")]
+    pub fn memento_report(&self) {
+        unsafe {
+            Memento_checkAllMemory();
+            Memento_stats();
+            Memento_listBlocks();
+        }
+    }
+
+    /// Make documents of a format implemented in Rust openable like the built-in formats,
+    /// recognized by the extensions and MIME types `handler` lists.
+    ///
+    /// Handlers are shared by all contexts and can't be unregistered.
+    pub fn register_document_handler<H: DocumentHandler>(
+        &mut self,
+        handler: H,
+    ) -> Result<(), Error> {
+        document_handler::register(Box::new(handler))
+    }
+
     pub fn enable_icc(&mut self) {
         unsafe {
             fz_enable_icc(self.inner);
@@ -188,4 +302,37 @@ mod test {
         assert!(ctx.use_document_css());
         assert!(ctx.user_css().is_none());
     }
+
+    #[test]
+    fn test_context_store() {
+        let ctx = Context::get();
+        let usage = ctx.store_usage().unwrap();
+        assert!(usage.max == 0 || usage.size <= usage.max);
+        assert!(Context::new_with_store_limit(usage.max).is_ok());
+        assert!(Context::new_with_store_limit(usage.max + 1).is_err());
+    }
+
+    // Memento bypasses the counting allocator
+    #[test]
+    #[cfg(not(feature = "memento"))]
+    fn test_context_memory_usage() {
+        let ctx = Context::get();
+        let usage = ctx.memory_usage();
+        assert!(usage.current > 0);
+        assert!(usage.peak >= usage.current);
+        assert!(usage.allocations > 0);
+        assert!(!Context::set_allocator(&std::alloc::System));
+    }
+
+    #[test]
+    fn test_context_resource_stats() {
+        use crate::{Colorspace, Pixmap};
+
+        let ctx = Context::get();
+        let _pixmap = Pixmap::new_with_w_h(&Colorspace::device_rgb(), 100, 100, false).unwrap();
+        let stats = ctx.resource_stats().unwrap();
+        assert!(stats.pixmaps >= 1);
+        assert!(stats.pixmap_bytes >= 100 * 100 * 3);
+        assert!(stats.peak_pixmap_bytes >= stats.pixmap_bytes);
+    }
 }
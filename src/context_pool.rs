@@ -0,0 +1,159 @@
+use std::borrow::Borrow;
+use std::ops::Range;
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+
+use crate::{Colorspace, Context, DisplayList, Document, Error, FilePath, Matrix, Pixmap};
+
+/// A document, owned or borrowed, used from several threads one at a time.
+pub(crate) struct SharedDocument<D> {
+    document: Mutex<D>,
+}
+
+// SAFETY: A document isn't tied to the context that opened it, any context cloned from the same
+// base context may use it as long as only one thread does so at a time, which the mutex ensures.
+unsafe impl<D: Borrow<Document>> Send for SharedDocument<D> {}
+unsafe impl<D: Borrow<Document>> Sync for SharedDocument<D> {}
+
+impl<D: Borrow<Document>> SharedDocument<D> {
+    pub(crate) fn new(document: D) -> Self {
+        Self {
+            document: Mutex::new(document),
+        }
+    }
+
+    pub(crate) fn with_document<R>(&self, f: impl FnOnce(&Document) -> R) -> R {
+        // Make sure this thread has its context before taking the lock
+        let _ = Context::get();
+        let guard = self
+            .document
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        f((*guard).borrow())
+    }
+}
+
+/// Renders the pages of a single document from multiple threads.
+///
+/// Each thread uses its own context cloned from the base context, all of them sharing the store
+/// and the locks guarding it. Access to the document itself is serialized: a page is only
+/// interpreted into a [`DisplayList`] under the lock, and rasterizing that list, which is where
+/// most of the time goes, then runs in parallel.
+pub struct ContextPool {
+    document: Arc<SharedDocument<Document>>,
+}
+
+impl ContextPool {
+    pub fn new(document: Document) -> Self {
+        Self {
+            document: Arc::new(SharedDocument::new(document)),
+        }
+    }
+
+    pub fn open<P: AsRef<FilePath> + ?Sized>(p: &P) -> Result<Self, Error> {
+        Document::open(p).map(Self::new)
+    }
+
+    /// A handle that can be moved to another thread.
+    pub fn handle(&self) -> PoolHandle {
+        PoolHandle {
+            document: self.document.clone(),
+        }
+    }
+}
+
+/// A `Send` handle on the document of a [`ContextPool`].
+#[derive(Clone)]
+pub struct PoolHandle {
+    document: Arc<SharedDocument<Document>>,
+}
+
+impl PoolHandle {
+    /// Run `f` with exclusive access to the document.
+    ///
+    /// Other handles block until `f` returns, so keep it short. Nothing tied to the document,
+    /// like the document itself or its pages, can be returned, as it would be used outside of
+    /// the lock:
+    ///
+    /// ```compile_fail
+    /// # use mupdf::ContextPool;
+    /// # let pool = ContextPool::open("tests/files/dummy.pdf").unwrap();
+    /// let doc = pool.handle().with_document(|doc| doc);
+    /// ```
+    ///
+    /// ```compile_fail
+    /// # use mupdf::ContextPool;
+    /// # let pool = ContextPool::open("tests/files/dummy.pdf").unwrap();
+    /// let page = pool.handle().with_document(|doc| doc.load_page(0));
+    /// ```
+    pub fn with_document<R>(&self, f: impl FnOnce(&Document) -> R) -> R
+    where
+        R: Send + 'static,
+    {
+        self.document.with_document(f)
+    }
+
+    pub fn page_count(&self) -> Result<i32, Error> {
+        self.with_document(|doc| doc.page_count())
+    }
+
+    /// Record a page into a display list, which can then be used from any thread.
+    pub fn display_list(&self, page_no: i32, annotations: bool) -> Result<DisplayList, Error> {
+        self.with_document(|doc| doc.load_page(page_no)?.to_display_list(annotations))
+    }
+
+    /// Interpret the pages in `range` on a background thread, see [`Document::prefetch`].
+    ///
+    /// The document is locked for one page at a time, so rendering from other handles carries on
+    /// in between.
+    pub fn prefetch(&self, range: Range<i32>) -> JoinHandle<Result<(), Error>> {
+        let handle = self.clone();
+        thread::spawn(move || {
+            for page_no in range {
+                handle.with_document(|doc| doc.prefetch(page_no..page_no + 1))?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Render a page, with annotations, holding the document lock only while the page is
+    /// interpreted.
+    pub fn render_page(
+        &self,
+        page_no: i32,
+        ctm: &Matrix,
+        cs: &Colorspace,
+        alpha: bool,
+    ) -> Result<Pixmap, Error> {
+        self.display_list(page_no, true)?.to_pixmap(ctm, cs, alpha)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::thread;
+
+    use super::ContextPool;
+    use crate::{Colorspace, Matrix};
+
+    #[test]
+    fn test_context_pool_render() {
+        let pool = ContextPool::open("tests/files/dummy.pdf").unwrap();
+        let threads: Vec<_> = (0..4)
+            .map(|_| {
+                let handle = pool.handle();
+                thread::spawn(move || {
+                    handle
+                        .render_page(0, &Matrix::IDENTITY, &Colorspace::device_rgb(), false)
+                        .unwrap()
+                })
+            })
+            .collect();
+        for t in threads {
+            let pixmap = t.join().unwrap();
+            assert_eq!((pixmap.width(), pixmap.height()), (595, 842));
+        }
+        assert_eq!(pool.handle().page_count().unwrap(), 1);
+        pool.handle().prefetch(0..2).join().unwrap().unwrap();
+    }
+}
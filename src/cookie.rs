@@ -1,3 +1,5 @@
+use std::sync::atomic::{AtomicI32, AtomicUsize, Ordering};
+
 use mupdf_sys::*;
 
 use crate::{context, Error};
@@ -5,54 +7,73 @@ use crate::{context, Error};
 /// Provide two-way communication between application and library.
 /// Intended for multi-threaded applications where one thread is rendering pages and
 /// another thread wants to read progress feedback or abort a job that takes a long time to finish.
-/// The communication is unsynchronized without locking.
+/// The fields are accessed atomically, as MuPDF only ever reads or writes them whole.
 #[derive(Debug)]
 pub struct Cookie {
     pub(crate) inner: *mut fz_cookie,
 }
 
 impl Cookie {
+    /// An `int` field of the cookie, shared with the rendering thread.
+    fn field(&self, field: impl FnOnce(*mut fz_cookie) -> *mut i32) -> &AtomicI32 {
+        // SAFETY: The cookie lives as long as `self` and `int` fields are aligned for atomics
+        unsafe { AtomicI32::from_ptr(field(self.inner)) }
+    }
+
     pub fn new() -> Result<Self, Error> {
         unsafe { ffi_try!(mupdf_new_cookie(context())) }.map(|inner| Self { inner })
     }
 
     /// Abort rendering
-    pub fn abort(&mut self) {
-        unsafe {
-            (*self.inner).abort = 1;
-        }
+    pub fn abort(&self) {
+        self.field(|c| unsafe { &raw mut (*c).abort })
+            .store(1, Ordering::Relaxed);
+    }
+
+    pub fn is_aborted(&self) -> bool {
+        self.field(|c| unsafe { &raw mut (*c).abort })
+            .load(Ordering::Relaxed)
+            != 0
     }
 
     /// Communicates rendering progress back to the application.
     /// Increments as a page is being rendered.
     pub fn progress(&self) -> i32 {
-        unsafe { (*self.inner).progress }
+        self.field(|c| unsafe { &raw mut (*c).progress })
+            .load(Ordering::Relaxed)
     }
 
     /// Communicates the known upper bound of rendering back to the application
     pub fn max_progress(&self) -> usize {
-        unsafe { (*self.inner).progress_max }
+        // SAFETY: See `field`
+        unsafe { AtomicUsize::from_ptr(&raw mut (*self.inner).progress_max) }
+            .load(Ordering::Relaxed)
     }
 
     /// count of errors during current rendering
     pub fn errors(&self) -> i32 {
-        unsafe { (*self.inner).errors }
+        self.field(|c| unsafe { &raw mut (*c).errors })
+            .load(Ordering::Relaxed)
     }
 
     /// Initially should be set to 0.
     /// Will be set to non-zero if a TRYLATER error is thrown during rendering
     pub fn incomplete(&self) -> bool {
-        unsafe { (*self.inner).incomplete > 0 }
+        self.field(|c| unsafe { &raw mut (*c).incomplete })
+            .load(Ordering::Relaxed)
+            > 0
     }
 
     pub fn set_incomplete(&mut self, value: bool) {
-        let val = if value { 1 } else { 0 };
-        unsafe {
-            (*self.inner).incomplete = val;
-        }
+        self.field(|c| unsafe { &raw mut (*c).incomplete })
+            .store(value.into(), Ordering::Relaxed);
     }
 }
 
+// The fields are meant to be accessed from other threads while rendering
+unsafe impl Send for Cookie {}
+unsafe impl Sync for Cookie {}
+
 impl Drop for Cookie {
     fn drop(&mut self) {
         if !self.inner.is_null() {
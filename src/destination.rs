@@ -1,5 +1,5 @@
 use crate::pdf::PdfObject;
-use crate::Error;
+use crate::{Error, Matrix, Point, Rect};
 
 #[derive(Debug, Clone)]
 pub struct Destination {
@@ -9,6 +9,7 @@ pub struct Destination {
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub enum DestinationKind {
     /// Display the page at a scale which just fits the whole page
     /// in the window both horizontally and vertically.
@@ -44,6 +45,84 @@ pub enum DestinationKind {
     FitBV { left: f32 },
 }
 
+impl DestinationKind {
+    /// Transform the coordinates of the destination by `m`.
+    pub(crate) fn transform(&self, m: &Matrix) -> Self {
+        let point = |x: f32, y: f32| Point::new(x, y).transform(m);
+        match *self {
+            DestinationKind::FitH { top } => DestinationKind::FitH {
+                top: point(0.0, top).y,
+            },
+            DestinationKind::FitBH { top } => DestinationKind::FitBH {
+                top: point(0.0, top).y,
+            },
+            DestinationKind::FitV { left } => DestinationKind::FitV {
+                left: point(left, 0.0).x,
+            },
+            DestinationKind::FitBV { left } => DestinationKind::FitBV {
+                left: point(left, 0.0).x,
+            },
+            DestinationKind::XYZ { left, top, zoom } => {
+                let p = point(left.unwrap_or(0.0), top.unwrap_or(0.0));
+                DestinationKind::XYZ {
+                    left: left.map(|_| p.x),
+                    top: top.map(|_| p.y),
+                    zoom,
+                }
+            }
+            DestinationKind::FitR {
+                left,
+                bottom,
+                right,
+                top,
+            } => {
+                let r = Rect::new(left, top.min(bottom), right, top.max(bottom)).transform(m);
+                DestinationKind::FitR {
+                    left: r.x0,
+                    bottom: r.y0,
+                    right: r.x1,
+                    top: r.y1,
+                }
+            }
+            DestinationKind::Fit | DestinationKind::FitB => self.clone(),
+        }
+    }
+
+    /// Decode the kind of an explicit destination, the array `[page /Kind ...]`.
+    pub(crate) fn decode(array: &PdfObject) -> Result<Option<Self>, Error> {
+        let Some(kind) = array.get_array(1)? else {
+            return Ok(None);
+        };
+        let number = |i| -> Result<Option<f32>, Error> {
+            match array.get_array(i)? {
+                Some(value) if value.is_number()? => Ok(Some(value.as_float()?)),
+                _ => Ok(None),
+            }
+        };
+        let value = |i| -> Result<f32, Error> { Ok(number(i)?.unwrap_or(0.0)) };
+        Ok(Some(match kind.as_name()? {
+            b"Fit" => DestinationKind::Fit,
+            b"FitH" => DestinationKind::FitH { top: value(2)? },
+            b"FitV" => DestinationKind::FitV { left: value(2)? },
+            b"XYZ" => DestinationKind::XYZ {
+                left: number(2)?,
+                top: number(3)?,
+                zoom: number(4)?,
+            },
+            b"FitR" => DestinationKind::FitR {
+                left: value(2)?,
+                bottom: value(3)?,
+                right: value(4)?,
+                top: value(5)?,
+            },
+            b"FitB" => DestinationKind::FitB,
+            b"FitBH" => DestinationKind::FitBH { top: value(2)? },
+            b"FitBV" => DestinationKind::FitBV { left: value(2)? },
+            _ => return Ok(None),
+        }))
+    }
+}
+
 impl Destination {
     pub(crate) fn new(page: PdfObject, kind: DestinationKind) -> Self {
         Self { page, kind }
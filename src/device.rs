@@ -6,14 +6,15 @@ use mupdf_sys::*;
 use num_enum::TryFromPrimitive;
 
 use crate::{
-    context, ColorParams, Colorspace, DisplayList, Error, IRect, Image, Matrix, Path, Pixmap, Rect,
-    Shade, StrokeState, Text, TextPage, TextPageOptions,
+    context, ColorGlyphRendering, ColorParams, Colorspace, DisplayList, Error, IRect, Image,
+    Matrix, Path, Pixmap, Rect, Shade, StrokeState, Text, TextPage, TextPageOptions,
 };
 
 mod native;
 pub use native::NativeDevice;
 
 #[derive(Debug, Clone, Copy, PartialEq, TryFromPrimitive)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 #[repr(u32)]
 pub enum BlendMode {
     /* PDF 1.4 -- standard separable */
@@ -56,6 +57,34 @@ bitflags! {
     }
 }
 
+bitflags! {
+    /// Hints to the interpreters running pages on a device.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct DeviceHints: u32 {
+        /// Draw images without smoothing them when scaling up.
+        const DONT_INTERPOLATE_IMAGES = FZ_DONT_INTERPOLATE_IMAGES as _;
+        /// Don't keep the resources used in the cache, for content drawn only once.
+        const NO_CACHE = FZ_NO_CACHE as _;
+        /// Don't decode images, for devices that don't look at their samples.
+        const DONT_DECODE_IMAGES = FZ_DONT_DECODE_IMAGES as _;
+    }
+}
+
+bitflags! {
+    /// Kinds of content a device can be made to ignore, see [`Device::with_skipped`].
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+    pub struct SkipContent: u32 {
+        /// Filled and stroked text.
+        const TEXT = 1 << 0;
+        /// Images and image masks.
+        const IMAGES = 1 << 1;
+        /// Shadings, such as gradients.
+        const SHADINGS = 1 << 2;
+        /// Filled and stroked paths.
+        const PATHS = 1 << 3;
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Clone, Copy, TryFromPrimitive)]
 #[repr(i32)]
 pub enum Structure {
@@ -151,10 +180,85 @@ pub enum Metatext {
     Title = fz_metatext_FZ_METATEXT_TITLE as _,
 }
 
+/// The colorspaces device gray, RGB and CMYK colors are interpreted in, and the output intent,
+/// usually set by the document and page being rendered.
 pub struct DefaultColorspaces {
     pub(crate) inner: *mut fz_default_colorspaces,
 }
 
+impl DefaultColorspaces {
+    /// The built-in defaults, without output intent.
+    pub fn new() -> Result<Self, Error> {
+        unsafe { ffi_try!(mupdf_new_default_colorspaces(context())) }.map(|inner| Self { inner })
+    }
+
+    pub fn gray(&self) -> Colorspace {
+        unsafe { Colorspace::from_raw_kept(fz_default_gray(context(), self.inner)) }
+    }
+
+    pub fn rgb(&self) -> Colorspace {
+        unsafe { Colorspace::from_raw_kept(fz_default_rgb(context(), self.inner)) }
+    }
+
+    pub fn cmyk(&self) -> Colorspace {
+        unsafe { Colorspace::from_raw_kept(fz_default_cmyk(context(), self.inner)) }
+    }
+
+    pub fn output_intent(&self) -> Option<Colorspace> {
+        let inner = unsafe { fz_default_output_intent(context(), self.inner) };
+        (!inner.is_null()).then(|| unsafe { Colorspace::from_raw_kept(inner) })
+    }
+
+    /// Replace the default of the kind of `cs`, which must be a gray, RGB or CMYK colorspace.
+    pub fn set(&mut self, cs: &Colorspace) -> Result<(), Error> {
+        unsafe {
+            ffi_try!(mupdf_default_colorspaces_set(
+                context(),
+                self.inner,
+                cs.inner,
+                false
+            ))
+        }
+    }
+
+    pub fn set_output_intent(&mut self, cs: &Colorspace) -> Result<(), Error> {
+        unsafe {
+            ffi_try!(mupdf_default_colorspaces_set(
+                context(),
+                self.inner,
+                cs.inner,
+                true
+            ))
+        }
+    }
+
+    /// Replace the default of the kind of the ICC profile `icc`, e.g. by a house RGB profile.
+    pub fn load_profile(&mut self, icc: &[u8]) -> Result<(), Error> {
+        unsafe {
+            ffi_try!(mupdf_default_colorspaces_load_icc(
+                context(),
+                self.inner,
+                icc.as_ptr(),
+                icc.len(),
+                false
+            ))
+        }
+    }
+
+    /// Set the output intent to the ICC profile `icc`.
+    pub fn load_output_intent(&mut self, icc: &[u8]) -> Result<(), Error> {
+        unsafe {
+            ffi_try!(mupdf_default_colorspaces_load_icc(
+                context(),
+                self.inner,
+                icc.as_ptr(),
+                icc.len(),
+                true
+            ))
+        }
+    }
+}
+
 impl Drop for DefaultColorspaces {
     fn drop(&mut self) {
         unsafe { fz_drop_default_colorspaces(context(), self.inner) }
@@ -199,6 +303,50 @@ impl Device {
         Self::from_pixmap_with_clip(pixmap, IRect::INF)
     }
 
+    pub fn enable_hints(&self, hints: DeviceHints) {
+        unsafe { fz_enable_device_hints(context(), self.dev, hints.bits() as _) }
+    }
+
+    pub fn disable_hints(&self, hints: DeviceHints) {
+        unsafe { fz_disable_device_hints(context(), self.dev, hints.bits() as _) }
+    }
+
+    /// Wrap this device so the kinds of content in `skip` are left out, e.g. to render only the
+    /// text of a page, or only what's behind it.
+    ///
+    /// Text and images used as clipping paths are still applied, so the rest of the content is
+    /// clipped as before.
+    pub fn with_skipped(&self, skip: SkipContent) -> Result<Self, Error> {
+        unsafe {
+            ffi_try!(mupdf_new_skip_device(
+                context(),
+                self.dev,
+                skip.bits() as i32
+            ))
+        }
+        .map(|dev| Self {
+            dev,
+            list: ptr::null_mut(),
+        })
+    }
+
+    /// Wrap this device so text glyphs with a color version are drawn as images or left out, as
+    /// chosen by `rendering`, instead of drawing their outline or nothing at all. Other content
+    /// and text used for clipping or stroking is passed on as is.
+    pub fn with_color_glyphs(&self, rendering: ColorGlyphRendering) -> Result<Self, Error> {
+        unsafe {
+            ffi_try!(mupdf_new_color_glyph_device(
+                context(),
+                self.dev,
+                rendering as i32
+            ))
+        }
+        .map(|dev| Self {
+            dev,
+            list: ptr::null_mut(),
+        })
+    }
+
     pub fn from_display_list(list: &DisplayList) -> Result<Self, Error> {
         unsafe { ffi_try!(mupdf_new_display_list_device(context(), list.inner)) }.map(|dev| Self {
             dev,
@@ -620,4 +768,31 @@ mod test {
         let list = DisplayList::new(Rect::new(0.0, 0.0, 100.0, 100.0)).unwrap();
         let _device = Device::from_display_list(&list).unwrap();
     }
+
+    #[test]
+    fn test_color_glyph_device_passes_other_text() {
+        use crate::{ColorGlyphRendering, Document, Matrix};
+
+        let doc = Document::open("tests/files/dummy.pdf").unwrap();
+        let page = doc.load_page(0).unwrap();
+        let cs = Colorspace::device_rgb();
+        let render = |rendering: Option<ColorGlyphRendering>| {
+            let mut pixmap = Pixmap::new_with_w_h(&cs, 200, 200, false).unwrap();
+            pixmap.clear_with(0xff).unwrap();
+            let device = Device::from_pixmap(&pixmap).unwrap();
+            match rendering {
+                Some(rendering) => {
+                    let filter = device.with_color_glyphs(rendering).unwrap();
+                    page.run(&filter, &Matrix::IDENTITY).unwrap();
+                }
+                None => page.run(&device, &Matrix::IDENTITY).unwrap(),
+            }
+            drop(device);
+            pixmap.samples().to_vec()
+        };
+        let plain = render(None);
+        assert!(plain.iter().any(|&b| b != 0xff));
+        assert_eq!(render(Some(ColorGlyphRendering::Skip)), plain);
+        assert_eq!(render(Some(ColorGlyphRendering::Rasterize)), plain);
+    }
 }
@@ -1,10 +1,13 @@
 use std::ffi::CString;
+use std::panic;
+use std::ptr;
+use std::thread;
 
 use mupdf_sys::*;
 
 use crate::{
-    array::FzArray, context, rust_vec_from_ffi_ptr, Colorspace, Cookie, Device, Error, Image,
-    Matrix, Pixmap, Quad, Rect, TextPage, TextPageOptions,
+    array::FzArray, context, rust_vec_from_ffi_ptr, Colorspace, Cookie, Device, Error, IRect,
+    Image, Matrix, Pixmap, Quad, Rect, RenderOptions, TextPage, TextPageOptions,
 };
 
 #[derive(Debug)]
@@ -40,6 +43,122 @@ impl DisplayList {
         .map(|inner| unsafe { Pixmap::from_raw(inner) })
     }
 
+    /// A cleared pixmap covering the bounds of the list transformed by `ctm`, white unless `alpha`
+    /// is set.
+    pub(crate) fn blank_pixmap(
+        &self,
+        ctm: &Matrix,
+        cs: &Colorspace,
+        alpha: bool,
+    ) -> Result<Pixmap, Error> {
+        let bbox: IRect =
+            unsafe { fz_round_rect(fz_transform_rect(self.bounds().into(), ctm.into())) }.into();
+        let mut pixmap = Pixmap::new_with_rect(cs, bbox, alpha)?;
+        if alpha {
+            pixmap.clear()?;
+        } else {
+            pixmap.clear_with(255)?;
+        }
+        Ok(pixmap)
+    }
+
+    /// Like [`DisplayList::to_pixmap`], returning [`Error::Aborted`] if rendering is stopped
+    /// through `cookie`.
+    pub fn to_pixmap_with_cookie(
+        &self,
+        ctm: &Matrix,
+        cs: &Colorspace,
+        alpha: bool,
+        cookie: &Cookie,
+    ) -> Result<Pixmap, Error> {
+        let pixmap = self.blank_pixmap(ctm, cs, alpha)?;
+        {
+            let device = Device::from_pixmap(&pixmap)?;
+            self.run_with_cookie(&device, ctm, Rect::INF, cookie)?;
+        }
+        if cookie.is_aborted() {
+            return Err(Error::Aborted);
+        }
+        Ok(pixmap)
+    }
+
+    /// Render the list as [`Page::render`](crate::Page::render) renders pages. Whether to
+    /// render annotations is decided when recording the list.
+    pub fn render(&self, options: &RenderOptions) -> Result<Pixmap, Error> {
+        let ctm = options.matrix();
+        let pixmap = options.blank_pixmap(self.bounds())?;
+        if pixmap.rect().is_empty() {
+            return Ok(pixmap);
+        }
+        options.with_aa_level(|| {
+            let target = Device::from_pixmap(&pixmap)?;
+            let device = target.with_skipped(options.skip())?;
+            self.run(&device, &ctm, Rect::INF)
+        })?;
+        Ok(pixmap)
+    }
+
+    /// Like [`DisplayList::render`], returning [`Error::Aborted`] if rendering is stopped
+    /// through `cookie`.
+    pub fn render_with_cookie(
+        &self,
+        options: &RenderOptions,
+        cookie: &Cookie,
+    ) -> Result<Pixmap, Error> {
+        let ctm = options.matrix();
+        let pixmap = options.blank_pixmap(self.bounds())?;
+        if pixmap.rect().is_empty() {
+            return Ok(pixmap);
+        }
+        options.with_aa_level(|| {
+            let target = Device::from_pixmap(&pixmap)?;
+            let device = target.with_skipped(options.skip())?;
+            self.run_with_cookie(&device, &ctm, Rect::INF, cookie)
+        })?;
+        if cookie.is_aborted() {
+            return Err(Error::Aborted);
+        }
+        Ok(pixmap)
+    }
+
+    /// Like [`DisplayList::to_pixmap`], splitting the pixmap into `bands` horizontal bands
+    /// rendered on as many threads, to cut the latency of rendering a single huge page.
+    pub fn to_pixmap_banded(
+        &self,
+        ctm: &Matrix,
+        cs: &Colorspace,
+        alpha: bool,
+        bands: usize,
+    ) -> Result<Pixmap, Error> {
+        let pixmap = self.blank_pixmap(ctm, cs, alpha)?;
+        let bbox = pixmap.rect();
+        let bands = bands.clamp(1, bbox.height().max(1) as usize) as i32;
+        let band_height = (bbox.height() + bands - 1) / bands;
+        let parts = (0..bands)
+            .map(|i| {
+                let y0 = bbox.y0 + i * band_height;
+                let rect = IRect::new(bbox.x0, y0, bbox.x1, (y0 + band_height).min(bbox.y1));
+                pixmap.sub_pixmap(rect).map(|part| (rect, part))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        // The bands don't overlap, and `pixmap` isn't accessed until they're all dropped
+        thread::scope(|s| {
+            let threads: Vec<_> = parts
+                .into_iter()
+                .map(|(rect, part)| {
+                    s.spawn(move || {
+                        let device = Device::from_pixmap(&part)?;
+                        self.run(&device, ctm, rect.into())
+                    })
+                })
+                .collect();
+            threads
+                .into_iter()
+                .try_for_each(|t| t.join().unwrap_or_else(|err| panic::resume_unwind(err)))
+        })?;
+        Ok(pixmap)
+    }
+
     pub fn to_text_page(&self, opts: TextPageOptions) -> Result<TextPage, Error> {
         unsafe {
             ffi_try!(mupdf_display_list_to_text_page(
@@ -87,6 +206,43 @@ impl DisplayList {
         }
     }
 
+    /// Decode every image drawn by the list into the resource store without rendering anything.
+    pub(crate) fn decode_images(&self) -> Result<(), Error> {
+        unsafe { ffi_try!(mupdf_display_list_decode_images(context(), self.inner)) }
+    }
+
+    /// Replay the list into each of `devices` in turn, e.g. a draw device, a text device and a
+    /// [native device](crate::NativeDevice), so a page parsed once feeds rendering, extraction
+    /// and analysis. Only the content within `area` in device space is replayed. Stops and
+    /// returns [`Error::Aborted`] when rendering is stopped through `cookie`.
+    ///
+    /// Devices are only closed when dropped, so drop a text device before reading its page.
+    pub fn run_devices(
+        &self,
+        devices: &[&Device],
+        ctm: &Matrix,
+        area: Rect,
+        cookie: Option<&Cookie>,
+    ) -> Result<(), Error> {
+        let cookie_ptr = cookie.map_or(ptr::null_mut(), |cookie| cookie.inner);
+        for device in devices {
+            unsafe {
+                ffi_try!(mupdf_display_list_run(
+                    context(),
+                    self.inner,
+                    device.dev,
+                    ctm.into(),
+                    area.into(),
+                    cookie_ptr
+                ))
+            }?;
+            if cookie.is_some_and(Cookie::is_aborted) {
+                return Err(Error::Aborted);
+            }
+        }
+        Ok(())
+    }
+
     pub fn is_empty(&self) -> bool {
         unsafe { fz_display_list_is_empty(context(), self.inner) > 0 }
     }
@@ -181,4 +337,78 @@ mod test {
         })
         .unwrap();
     }
+
+    #[test]
+    fn test_display_list_to_pixmap_banded() {
+        use crate::{Colorspace, Matrix};
+
+        let doc = Document::open("tests/files/dummy.pdf").unwrap();
+        let page0 = doc.load_page(0).unwrap();
+        let list = page0.to_display_list(false).unwrap();
+        let cs = Colorspace::device_rgb();
+        let single = list.to_pixmap(&Matrix::IDENTITY, &cs, false).unwrap();
+        let banded = list
+            .to_pixmap_banded(&Matrix::IDENTITY, &cs, false, 4)
+            .unwrap();
+        assert_eq!(banded.rect(), single.rect());
+        assert_eq!(banded.samples(), single.samples());
+    }
+
+    #[test]
+    fn test_display_list_render_skip() {
+        use crate::{Colorspace, Matrix, RenderOptions, SkipContent};
+
+        let doc = Document::open("tests/files/dummy.pdf").unwrap();
+        let page0 = doc.load_page(0).unwrap();
+        let list = page0.to_display_list(false).unwrap();
+        let mut options = RenderOptions::new();
+        let full = list.render(&options).unwrap();
+        let expected = list
+            .to_pixmap(&Matrix::IDENTITY, &Colorspace::device_rgb(), false)
+            .unwrap();
+        assert_eq!(full.samples(), expected.samples());
+
+        // The title is the only thing on the page
+        options.set_skip(SkipContent::TEXT);
+        let blank = list.render(&options).unwrap();
+        assert!(blank.samples().iter().all(|&v| v == 255));
+    }
+
+    #[test]
+    fn test_display_list_run_devices() {
+        use crate::{
+            Colorspace, Cookie, Device, Error, Matrix, Pixmap, RecordedCommands, Rect, TextPage,
+            TextPageOptions,
+        };
+
+        let doc = Document::open("tests/files/dummy.pdf").unwrap();
+        let page0 = doc.load_page(0).unwrap();
+        let list = page0.to_display_list(false).unwrap();
+        let expected = list
+            .to_pixmap(&Matrix::IDENTITY, &Colorspace::device_rgb(), false)
+            .unwrap();
+
+        let mut pixmap =
+            Pixmap::new_with_rect(&Colorspace::device_rgb(), expected.rect(), false).unwrap();
+        pixmap.clear_with(255).unwrap();
+        let text_page = TextPage::new(list.bounds()).unwrap();
+        let mut recorded = RecordedCommands::default();
+        {
+            let draw = Device::from_pixmap(&pixmap).unwrap();
+            let text = Device::from_text_page(&text_page, TextPageOptions::empty()).unwrap();
+            let native = Device::from_native(&mut recorded).unwrap();
+            list.run_devices(&[&draw, &text, &native], &Matrix::IDENTITY, Rect::INF, None)
+                .unwrap();
+        }
+        assert_eq!(pixmap.samples(), expected.samples());
+        assert_eq!(text_page.to_text().unwrap().trim(), "Dummy PDF file");
+        assert!(!recorded.commands.is_empty());
+
+        let cookie = Cookie::new().unwrap();
+        cookie.abort();
+        let mut aborted = RecordedCommands::default();
+        let device = Device::from_native(&mut aborted).unwrap();
+        let result = list.run_devices(&[&device], &Matrix::IDENTITY, Rect::INF, Some(&cookie));
+        assert!(matches!(result, Err(Error::Aborted)));
+    }
 }
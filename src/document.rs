@@ -1,11 +1,17 @@
 use std::ffi::{CStr, CString};
 use std::io::Write;
+use std::iter::FusedIterator;
+use std::ops::Range;
 use std::ptr;
 
 use mupdf_sys::*;
 
+use crate::link::parse_file_uri;
 use crate::pdf::PdfDocument;
-use crate::{context, Buffer, Colorspace, Cookie, Error, FilePath, Outline, Page};
+use crate::{
+    context, stats, with_message_source, Buffer, Colorspace, Cookie, Error, FilePath, LinkAction,
+    LinkDestination, Outline, Page,
+};
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum MetadataName {
@@ -41,11 +47,28 @@ impl MetadataName {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub struct Location {
     pub chapter: i32,
     pub page: i32,
 }
 
+impl From<Location> for fz_location {
+    fn from(location: Location) -> Self {
+        fz_location {
+            chapter: location.chapter,
+            page: location.page,
+        }
+    }
+}
+
+/// A reading position in a document that stays valid when a reflowable document is laid out
+/// again, e.g. with another font size, unlike a [`Location`]. Bookmarks can be stored, and
+/// looked up again once the document is reopened.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct Bookmark(pub i64);
+
 #[derive(Debug)]
 pub struct Document {
     pub(crate) inner: *mut fz_document,
@@ -53,13 +76,58 @@ pub struct Document {
 
 impl Document {
     pub(crate) unsafe fn from_raw(ptr: *mut fz_document) -> Self {
+        if !ptr.is_null() {
+            stats::DOCUMENTS.acquire();
+        }
         Self { inner: ptr }
     }
 
     pub fn open<P: AsRef<FilePath> + ?Sized>(p: &P) -> Result<Self, Error> {
         let c_name = CString::new(p.as_ref().as_bytes())?;
-        unsafe { ffi_try!(mupdf_open_document(context(), c_name.as_ptr())) }
-            .map(|inner| Self { inner })
+        with_message_source(&String::from_utf8_lossy(p.as_ref().as_bytes()), || unsafe {
+            ffi_try!(mupdf_open_document(context(), c_name.as_ptr()))
+        })
+        .map(|inner| unsafe { Self::from_raw(inner) })
+    }
+
+    /// Open a document with the accelerator file at `accel`, saved by
+    /// [`Document::save_accelerator`], so a large EPUB doesn't have to be laid out chapter by
+    /// chapter to count its pages. The accelerator is ignored if it doesn't exist or is older
+    /// than the document.
+    pub fn open_accelerated<P, A>(p: &P, accel: &A) -> Result<Self, Error>
+    where
+        P: AsRef<FilePath> + ?Sized,
+        A: AsRef<FilePath> + ?Sized,
+    {
+        let c_name = CString::new(p.as_ref().as_bytes())?;
+        let c_accel = CString::new(accel.as_ref().as_bytes())?;
+        unsafe {
+            ffi_try!(mupdf_open_accelerated_document(
+                context(),
+                c_name.as_ptr(),
+                c_accel.as_ptr()
+            ))
+        }
+        .map(|inner| unsafe { Self::from_raw(inner) })
+    }
+
+    /// Whether the document can save an accelerator file, which is the case for EPUB documents.
+    pub fn supports_accelerator(&self) -> bool {
+        unsafe { fz_document_supports_accelerator(context(), self.inner) != 0 }
+    }
+
+    /// Save the page counts of the current layout to the accelerator file `accel`, to be
+    /// reopened with [`Document::open_accelerated`]. Fails if the document doesn't
+    /// [support accelerators](Document::supports_accelerator).
+    pub fn save_accelerator<P: AsRef<FilePath> + ?Sized>(&self, accel: &P) -> Result<(), Error> {
+        let c_accel = CString::new(accel.as_ref().as_bytes())?;
+        unsafe {
+            ffi_try!(mupdf_save_accelerator(
+                context(),
+                self.inner,
+                c_accel.as_ptr()
+            ))
+        }
     }
 
     pub fn from_bytes(bytes: &[u8], magic: &str) -> Result<Self, Error> {
@@ -74,7 +142,29 @@ impl Document {
                 c_magic.as_ptr()
             ))
         }
-        .map(|inner| Self { inner })
+        .map(|inner| unsafe { Self::from_raw(inner) })
+    }
+
+    /// Open a JPEG, PNG, TIFF, JBIG2 or other image as a document, to render or convert it like
+    /// any other.
+    ///
+    /// Each frame becomes a page, so multi-page TIFFs give multi-page documents. Pages are sized
+    /// from the resolution stored in the image, an image at 72 DPI getting one point per pixel.
+    /// `mime` is a MIME type such as `image/png` or a file extension.
+    #[cfg(feature = "img")]
+    pub fn open_image(bytes: &[u8], mime: &str) -> Result<Self, Error> {
+        Self::from_bytes(bytes, mime)
+    }
+
+    /// Open an image as a one page PDF document sized as if the image had a resolution of `dpi`,
+    /// for images whose stored resolution is missing or wrong, as is common for scans and photos.
+    ///
+    /// The format is detected from the data. Only the first frame of multi-frame images is used.
+    pub fn open_image_at_dpi(bytes: &[u8], dpi: f32) -> Result<Self, Error> {
+        let mut buf = Buffer::with_capacity(bytes.len());
+        buf.write_all(bytes)?;
+        unsafe { ffi_try!(mupdf_open_image_at_dpi(context(), buf.inner, dpi)) }
+            .map(|inner| unsafe { Self::from_raw(inner) })
     }
 
     pub fn recognize(magic: &str) -> Result<bool, Error> {
@@ -101,6 +191,22 @@ impl Document {
         unsafe { ffi_try!(mupdf_document_page_count(context(), self.inner)) }
     }
 
+    /// The number of chapters. Only reflowable documents such as EPUB have more than one, the
+    /// pages of other documents are all in chapter 0.
+    pub fn chapter_count(&self) -> Result<i32, Error> {
+        unsafe { ffi_try!(mupdf_document_chapter_count(context(), self.inner)) }
+    }
+
+    pub fn chapter_page_count(&self, chapter: i32) -> Result<i32, Error> {
+        unsafe {
+            ffi_try!(mupdf_document_chapter_page_count(
+                context(),
+                self.inner,
+                chapter
+            ))
+        }
+    }
+
     pub fn metadata(&self, name: MetadataName) -> Result<String, Error> {
         let c_key = CString::new(name.to_str())?;
         let info_ptr =
@@ -128,6 +234,75 @@ impl Document {
         Ok(None)
     }
 
+    /// Resolve the internal link `uri` to the place it points at and how to show it, in the
+    /// same way for PDF, EPUB, XPS and other documents. `None` if the document doesn't have
+    /// the place.
+    pub fn resolve_link_destination(&self, uri: &str) -> Result<Option<LinkDestination>, Error> {
+        let c_uri = CString::new(uri)?;
+        let dest = unsafe {
+            ffi_try!(mupdf_resolve_link_dest(
+                context(),
+                self.inner,
+                c_uri.as_ptr()
+            ))
+        }?;
+        Ok(LinkDestination::from_raw(dest))
+    }
+
+    /// What following a link to `uri`, such as [`Link::uri`](crate::Link::uri), does.
+    ///
+    /// Links to other files that name a file embedded in a PDF document are reported as
+    /// [`LinkAction::EmbeddedFile`].
+    pub fn link_action(&self, uri: &str) -> Result<LinkAction, Error> {
+        let c_uri = CString::new(uri)?;
+        if unsafe { fz_is_external_link(context(), c_uri.as_ptr()) } == 0 {
+            return Ok(self
+                .resolve_link_destination(uri)?
+                .map_or(LinkAction::Unresolved, LinkAction::GoTo));
+        }
+        let Some((path, destination)) = parse_file_uri(uri) else {
+            return Ok(LinkAction::Uri(uri.to_owned()));
+        };
+        let c_path = CString::new(path.as_str())?;
+        if unsafe {
+            ffi_try!(mupdf_has_embedded_file(
+                context(),
+                self.inner,
+                c_path.as_ptr()
+            ))
+        }? {
+            return Ok(LinkAction::EmbeddedFile {
+                name: path,
+                destination,
+            });
+        }
+        Ok(LinkAction::File { path, destination })
+    }
+
+    /// A bookmark of `location` in the current layout.
+    pub fn make_bookmark(&self, location: Location) -> Result<Bookmark, Error> {
+        unsafe { ffi_try!(mupdf_make_bookmark(context(), self.inner, location.into())) }
+            .map(|mark| Bookmark(mark as i64))
+    }
+
+    /// Where `bookmark` is in the current layout, `None` if the document doesn't have it.
+    pub fn lookup_bookmark(&self, bookmark: Bookmark) -> Result<Option<Location>, Error> {
+        let loc = unsafe {
+            ffi_try!(mupdf_lookup_bookmark(
+                context(),
+                self.inner,
+                bookmark.0 as _
+            ))
+        }?;
+        if loc.page < 0 {
+            return Ok(None);
+        }
+        Ok(Some(Location {
+            chapter: loc.chapter,
+            page: loc.page,
+        }))
+    }
+
     pub fn is_reflowable(&self) -> Result<bool, Error> {
         unsafe { ffi_try!(mupdf_is_document_reflowable(context(), self.inner)) }
     }
@@ -213,20 +388,79 @@ impl Document {
             .and_then(|fz_page| unsafe { Page::from_raw(fz_page) })
     }
 
+    /// Load the page `page_no` of `chapter`, which for reflowable documents is much faster than
+    /// loading a page by its number in the whole document, as only its chapter is laid out.
+    pub fn load_chapter_page(&self, chapter: i32, page_no: i32) -> Result<Page, Error> {
+        unsafe {
+            ffi_try!(mupdf_load_chapter_page(
+                context(),
+                self.inner,
+                chapter,
+                page_no
+            ))
+        }
+        .and_then(|fz_page| unsafe { Page::from_raw(fz_page) })
+    }
+
+    pub fn load_location(&self, location: Location) -> Result<Page, Error> {
+        self.load_chapter_page(location.chapter, location.page)
+    }
+
+    /// Iterate over all pages. A page failing to load doesn't end the iteration, the following
+    /// pages are still loaded.
     pub fn pages(&self) -> Result<PageIter, Error> {
+        self.pages_range(0..i32::MAX)
+    }
+
+    /// Iterate over the pages in `range`, ignoring pages past the end of the document.
+    pub fn pages_range(&self, range: Range<i32>) -> Result<PageIter, Error> {
+        let end = range.end.min(self.page_count()?);
+        Ok(PageIter {
+            index: range.start.clamp(0, end.max(0)),
+            end,
+            chapter: None,
+            doc: self,
+        })
+    }
+
+    /// Iterate over the pages of `chapter`. See [`Document::load_chapter_page`].
+    pub fn chapter_pages(&self, chapter: i32) -> Result<PageIter, Error> {
         Ok(PageIter {
             index: 0,
-            total: self.page_count()?,
+            end: self.chapter_page_count(chapter)?,
+            chapter: Some(chapter),
             doc: self,
         })
     }
 
+    /// Interpret the pages in `range` ahead of displaying them, e.g. while a reader is idle on the
+    /// preceding page, so their objects are parsed, fonts loaded and images decoded by the time
+    /// they are needed.
+    ///
+    /// Nothing is rasterized, and whatever was loaded stays around only as long as the document's
+    /// caches and the resource store keep it, so a store too small for the decoded images defeats
+    /// the purpose.
+    /// Pages past the end of the document are ignored. See [`PoolHandle::prefetch`] to do this on
+    /// a background thread.
+    ///
+    /// [`PoolHandle::prefetch`]: crate::PoolHandle::prefetch
+    pub fn prefetch(&self, range: Range<i32>) -> Result<(), Error> {
+        let end = range.end.min(self.page_count()?);
+        for page_no in range.start.max(0)..end {
+            // Recording a display list loads fonts and images, decoding the images fills the store
+            self.load_page(page_no)?
+                .to_display_list(true)?
+                .decode_images()?;
+        }
+        Ok(())
+    }
+
     pub fn output_intent(&self) -> Result<Option<Colorspace>, Error> {
         let inner = unsafe { ffi_try!(mupdf_document_output_intent(context(), self.inner)) }?;
         if inner.is_null() {
             return Ok(None);
         }
-        Ok(Some(unsafe { Colorspace::from_raw(inner) }))
+        Ok(Some(unsafe { Colorspace::from_raw_kept(inner) }))
     }
 
     unsafe fn walk_outlines(&self, outline: *mut fz_outline) -> Vec<Outline> {
@@ -236,15 +470,17 @@ impl Document {
             let mut x = 0.0;
             let mut y = 0.0;
             let mut page = None;
+            let mut kind = None;
             let title = CStr::from_ptr((*next).title).to_string_lossy().into_owned();
             let uri = if !(*next).uri.is_null() {
                 if fz_is_external_link(context(), (*next).uri) > 0 {
                     Some(CStr::from_ptr((*next).uri).to_string_lossy().into_owned())
                 } else {
-                    page = Some(
-                        fz_resolve_link(context(), self.inner, (*next).uri, &mut x, &mut y).page
-                            as u32,
-                    );
+                    let dest = fz_resolve_link_dest(context(), self.inner, (*next).uri);
+                    page = Some(dest.loc.page as u32);
+                    x = dest.x;
+                    y = dest.y;
+                    kind = LinkDestination::from_raw(dest).map(|dest| dest.kind);
                     None
                 }
             } else {
@@ -262,6 +498,7 @@ impl Document {
                 down,
                 x,
                 y,
+                kind,
             });
             next = (*next).next;
         }
@@ -287,6 +524,7 @@ impl Drop for Document {
             unsafe {
                 fz_drop_document(context(), self.inner);
             }
+            stats::DOCUMENTS.release();
         }
     }
 }
@@ -300,23 +538,60 @@ impl Clone for Document {
 #[derive(Debug)]
 pub struct PageIter<'a> {
     index: i32,
-    total: i32,
+    end: i32,
+    chapter: Option<i32>,
     doc: &'a Document,
 }
 
+impl PageIter<'_> {
+    fn load(&self, page_no: i32) -> Result<Page, Error> {
+        match self.chapter {
+            Some(chapter) => self.doc.load_chapter_page(chapter, page_no),
+            None => self.doc.load_page(page_no),
+        }
+    }
+}
+
 impl Iterator for PageIter<'_> {
     type Item = Result<Page, Error>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.index >= self.total {
+        if self.index >= self.end {
             return None;
         }
-        let page = self.doc.load_page(self.index);
+        let page = self.load(self.index);
         self.index += 1;
         Some(page)
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = (self.end - self.index).max(0) as usize;
+        (len, Some(len))
+    }
+
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        self.index = i32::try_from(n)
+            .ok()
+            .and_then(|n| self.index.checked_add(n))
+            .map_or(self.end, |index| index.min(self.end));
+        self.next()
+    }
+}
+
+impl DoubleEndedIterator for PageIter<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.index >= self.end {
+            return None;
+        }
+        self.end -= 1;
+        Some(self.load(self.end))
+    }
 }
 
+impl ExactSizeIterator for PageIter<'_> {}
+
+impl FusedIterator for PageIter<'_> {}
+
 impl<'a> IntoIterator for &'a Document {
     type Item = Result<Page, Error>;
     type IntoIter = PageIter<'a>;
@@ -337,13 +612,19 @@ impl<'a> IntoIterator for &'a mut Document {
 
 #[cfg(test)]
 mod test {
-    use super::{Document, MetadataName, Page};
+    use super::{Document, Location, MetadataName, Page};
 
     #[test]
     fn test_recognize_document() {
         assert!(Document::recognize("test.pdf").unwrap());
         assert!(Document::recognize("application/pdf").unwrap());
         assert!(Document::recognize("text/html").unwrap());
+        #[cfg(feature = "txt")]
+        assert!(Document::recognize("test.txt").unwrap());
+        #[cfg(feature = "mobi")]
+        assert!(Document::recognize("test.mobi").unwrap());
+        #[cfg(feature = "fb2")]
+        assert!(Document::recognize("test.fb2").unwrap());
 
         assert!(!Document::recognize("test.doc").unwrap());
     }
@@ -354,6 +635,51 @@ mod test {
         assert!(!doc.is_pdf());
     }
 
+    #[test]
+    #[cfg(feature = "txt")]
+    fn test_document_open_txt() {
+        let doc = Document::from_bytes(b"Plain text\non two lines", "text/plain").unwrap();
+        assert!(doc.is_reflowable().unwrap());
+        let page = doc.load_page(0).unwrap();
+        let text_page = page.to_text_page(crate::TextPageOptions::empty()).unwrap();
+        assert!(text_page.to_text().unwrap().contains("Plain text"));
+    }
+
+    #[test]
+    #[cfg(feature = "fb2")]
+    fn test_document_open_fb2() {
+        let fb2 = br#"<?xml version="1.0" encoding="utf-8"?>
+<FictionBook xmlns="http://www.gribuser.ru/xml/fictionbook/2.0">
+<body><section><p>Hello from FictionBook</p></section></body>
+</FictionBook>"#;
+        let doc = Document::from_bytes(fb2, "application/x-fictionbook").unwrap();
+        assert!(doc.page_count().unwrap() >= 1);
+    }
+
+    #[test]
+    #[cfg(feature = "img")]
+    fn test_document_open_image() {
+        use crate::{Colorspace, ImageFormat, Pixmap};
+
+        let mut pixmap = Pixmap::new_with_w_h(&Colorspace::device_rgb(), 200, 100, false).unwrap();
+        pixmap.clear_with(128).unwrap();
+        pixmap.set_resolution(144, 144);
+        let mut png = Vec::new();
+        pixmap.write_to(&mut png, ImageFormat::PNG).unwrap();
+
+        let doc = Document::open_image(&png, "image/png").unwrap();
+        assert_eq!(doc.page_count().unwrap(), 1);
+        let bounds = doc.load_page(0).unwrap().bounds().unwrap();
+        assert_eq!((bounds.width(), bounds.height()), (100.0, 50.0));
+
+        let doc = Document::open_image_at_dpi(&png, 72.0).unwrap();
+        assert!(doc.is_pdf());
+        let bounds = doc.load_page(0).unwrap().bounds().unwrap();
+        assert_eq!((bounds.width(), bounds.height()), (200.0, 100.0));
+
+        assert!(Document::open_image_at_dpi(&png, 0.0).is_err());
+    }
+
     #[test]
     fn test_document_load_page() {
         let doc = Document::open("tests/files/dummy.pdf").unwrap();
@@ -407,6 +733,51 @@ mod test {
         assert_eq!(bounds.y1, 842.0);
     }
 
+    #[test]
+    fn test_document_pages_range() {
+        use crate::pdf::PdfDocument;
+
+        let mut doc = PdfDocument::new();
+        for width in [100.0, 200.0, 300.0, 400.0] {
+            doc.new_page((width, 100.0)).unwrap();
+        }
+        let width = |page: Result<Page, _>| page.unwrap().bounds().unwrap().x1;
+
+        let pages = doc.pages().unwrap();
+        assert_eq!(pages.len(), 4);
+        let widths: Vec<f32> = pages.rev().map(width).collect();
+        assert_eq!(widths, [400.0, 300.0, 200.0, 100.0]);
+
+        let mut pages = doc.pages_range(1..3).unwrap();
+        assert_eq!(pages.len(), 2);
+        assert_eq!(width(pages.next().unwrap()), 200.0);
+        assert_eq!(pages.len(), 1);
+        assert_eq!(width(pages.next().unwrap()), 300.0);
+        assert!(pages.next().is_none());
+
+        assert_eq!(doc.pages_range(2..10).unwrap().len(), 2);
+        assert_eq!(doc.pages_range(5..10).unwrap().len(), 0);
+        assert_eq!(width(doc.pages().unwrap().nth(3).unwrap()), 400.0);
+
+        assert_eq!(doc.chapter_count().unwrap(), 1);
+        let chapter: Vec<f32> = doc.chapter_pages(0).unwrap().map(width).collect();
+        assert_eq!(chapter, [100.0, 200.0, 300.0, 400.0]);
+    }
+
+    #[test]
+    fn test_document_prefetch() {
+        let doc = Document::open("tests/files/dummy.pdf").unwrap();
+        doc.prefetch(0..10).unwrap();
+        doc.prefetch(1..1).unwrap();
+
+        use crate::Context;
+
+        let doc = Document::open("tests/files/multiple-images.pdf").unwrap();
+        let before = Context::get().store_usage().unwrap().size;
+        doc.prefetch(0..1).unwrap();
+        assert!(Context::get().store_usage().unwrap().size > before);
+    }
+
     #[test]
     fn test_document_metadata() {
         let doc = Document::open("tests/files/dummy.pdf").unwrap();
@@ -447,4 +818,101 @@ mod test {
         assert_eq!(out1.x, 56.7);
         assert_eq!(out1.y, 68.70001);
     }
+
+    #[test]
+    fn test_document_accelerator() {
+        let accel_path = std::env::temp_dir().join("mupdf-rs-missing.accel");
+        let accel = accel_path.as_path();
+        let doc = Document::open_accelerated("tests/files/dummy.pdf", accel).unwrap();
+        assert_eq!(doc.page_count().unwrap(), 1);
+        // PDF documents have a cross reference table, which makes accelerators pointless
+        assert!(!doc.supports_accelerator());
+        assert!(doc.save_accelerator(accel).is_err());
+        // Saving may have created the file before failing
+        let _ = std::fs::remove_file(accel);
+    }
+
+    #[test]
+    fn test_document_accelerator_epub() {
+        use std::fs::File;
+        use std::time::{Duration, SystemTime};
+
+        let accel_path = std::env::temp_dir().join("mupdf-rs-dummy-epub.accel");
+        let accel = accel_path.as_path();
+        let _ = std::fs::remove_file(accel);
+
+        let doc = Document::open_accelerated("tests/files/dummy.epub", accel).unwrap();
+        assert!(doc.supports_accelerator());
+        let page_count = doc.page_count().unwrap();
+        assert!(page_count > 1);
+        doc.save_accelerator(accel).unwrap();
+
+        let doc = Document::open_accelerated("tests/files/dummy.epub", accel).unwrap();
+        assert_eq!(doc.page_count().unwrap(), page_count);
+
+        // An accelerator older than the document is ignored, whatever it holds
+        std::fs::write(accel, b"stale").unwrap();
+        File::options()
+            .write(true)
+            .open(accel)
+            .unwrap()
+            .set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(1))
+            .unwrap();
+        let doc = Document::open_accelerated("tests/files/dummy.epub", accel).unwrap();
+        assert_eq!(doc.page_count().unwrap(), page_count);
+        std::fs::remove_file(accel).unwrap();
+    }
+
+    #[test]
+    fn test_document_bookmarks() {
+        let html: String = (0..50)
+            .map(|i| format!("<p>Paragraph {} of some reflowable text.</p>", i))
+            .collect();
+        let mut doc = Document::from_bytes(html.as_bytes(), "text/html").unwrap();
+        doc.layout(200.0, 200.0, 11.0).unwrap();
+        let last = doc.page_count().unwrap() - 1;
+        let location = Location {
+            chapter: 0,
+            page: last,
+        };
+        let bookmark = doc.make_bookmark(location).unwrap();
+        assert_eq!(doc.lookup_bookmark(bookmark).unwrap(), Some(location));
+
+        // The position is found again with a larger font, and thus more pages
+        doc.layout(200.0, 200.0, 22.0).unwrap();
+        let found = doc.lookup_bookmark(bookmark).unwrap().unwrap();
+        assert!(found.page >= last);
+        assert!(found.page < doc.page_count().unwrap());
+    }
+
+    #[test]
+    fn test_document_link_action() {
+        use crate::{DestinationKind, LinkAction};
+
+        let doc = Document::open("tests/files/dummy.pdf").unwrap();
+        let dest = doc.resolve_link_destination("#page=1").unwrap().unwrap();
+        assert_eq!(dest.location.page, 0);
+        let dest = doc
+            .resolve_link_destination("#page=1&zoom=200,10,20")
+            .unwrap()
+            .unwrap();
+        assert!(matches!(dest.kind, DestinationKind::XYZ { .. }));
+        assert_eq!(dest.zoom(), Some(200.0));
+
+        assert!(matches!(
+            doc.link_action("#page=1").unwrap(),
+            LinkAction::GoTo(dest) if dest.location.page == 0
+        ));
+        assert_eq!(
+            doc.link_action("https://example.com").unwrap(),
+            LinkAction::Uri("https://example.com".to_owned())
+        );
+        assert_eq!(
+            doc.link_action("file:other.pdf#page=2").unwrap(),
+            LinkAction::File {
+                path: "other.pdf".to_owned(),
+                destination: Some("page=2".to_owned()),
+            }
+        );
+    }
 }
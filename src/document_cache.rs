@@ -0,0 +1,245 @@
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+use std::rc::Rc;
+use std::sync::Arc;
+
+use crate::{DisplayList, Document, Error, TextPage, TextPageOptions};
+
+/// Options for [`Document::with_cache`].
+#[derive(Debug, Clone)]
+pub struct CacheOptions {
+    capacity: usize,
+    annotations: bool,
+    text_options: TextPageOptions,
+}
+
+impl Default for CacheOptions {
+    fn default() -> Self {
+        Self {
+            capacity: 32,
+            annotations: true,
+            text_options: TextPageOptions::empty(),
+        }
+    }
+}
+
+impl CacheOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of pages to keep, the least recently used being evicted first.
+    pub fn set_capacity(&mut self, pages: usize) -> &mut Self {
+        self.capacity = pages;
+        self
+    }
+
+    /// Whether display lists include annotations and widgets, `true` by default.
+    pub fn set_annotations(&mut self, annotations: bool) -> &mut Self {
+        self.annotations = annotations;
+        self
+    }
+
+    pub fn set_text_options(&mut self, options: TextPageOptions) -> &mut Self {
+        self.text_options = options;
+        self
+    }
+}
+
+#[derive(Debug)]
+struct Entry {
+    /// What the page looked like when it was cached, see `Page::pdf_digest`
+    digest: Option<[u8; 16]>,
+    list: Arc<DisplayList>,
+    text: Option<Rc<TextPage>>,
+}
+
+/// A document remembering the display lists and text pages of recently used pages, so repainting
+/// a page doesn't interpret its contents again.
+///
+/// Looking up a cached page costs next to nothing, pages aren't checked for changes. Changes made
+/// through [`CachedDocument::document_mut`], like laying out a reflowable document again, drop
+/// the whole cache. Pages changed otherwise, e.g. by editing a PDF page loaded from
+/// [`CachedDocument::document`], must be dropped with [`CachedDocument::invalidate`], or checked
+/// with [`CachedDocument::revalidate`] when what changed isn't known.
+#[derive(Debug)]
+pub struct CachedDocument {
+    doc: Document,
+    options: CacheOptions,
+    entries: RefCell<HashMap<i32, Entry>>,
+    // Least recently used first
+    order: RefCell<VecDeque<i32>>,
+}
+
+impl Document {
+    pub fn with_cache(self, options: CacheOptions) -> CachedDocument {
+        CachedDocument {
+            doc: self,
+            options,
+            entries: RefCell::new(HashMap::new()),
+            order: RefCell::new(VecDeque::new()),
+        }
+    }
+}
+
+impl CachedDocument {
+    pub fn document(&self) -> &Document {
+        &self.doc
+    }
+
+    /// Mutable access to the document, dropping everything cached.
+    pub fn document_mut(&mut self) -> &mut Document {
+        self.invalidate_all();
+        &mut self.doc
+    }
+
+    pub fn into_inner(self) -> Document {
+        self.doc
+    }
+
+    fn touch(&self, page_no: i32) {
+        let mut order = self.order.borrow_mut();
+        if let Some(pos) = order.iter().position(|&n| n == page_no) {
+            order.remove(pos);
+        }
+        order.push_back(page_no);
+        let mut entries = self.entries.borrow_mut();
+        while order.len() > self.options.capacity {
+            if let Some(evicted) = order.pop_front() {
+                entries.remove(&evicted);
+            }
+        }
+    }
+
+    pub fn display_list(&self, page_no: i32) -> Result<Arc<DisplayList>, Error> {
+        let cached = self
+            .entries
+            .borrow()
+            .get(&page_no)
+            .map(|entry| entry.list.clone());
+        let list = match cached {
+            Some(list) => list,
+            None => {
+                let page = self.doc.load_page(page_no)?;
+                let digest = page.pdf_digest()?;
+                let list = Arc::new(page.to_display_list(self.options.annotations)?);
+                let entry = Entry {
+                    digest,
+                    list: list.clone(),
+                    text: None,
+                };
+                self.entries.borrow_mut().insert(page_no, entry);
+                list
+            }
+        };
+        self.touch(page_no);
+        Ok(list)
+    }
+
+    /// The structured text of a page, extracted from its cached display list.
+    pub fn text_page(&self, page_no: i32) -> Result<Rc<TextPage>, Error> {
+        let list = self.display_list(page_no)?;
+        let cached = self
+            .entries
+            .borrow()
+            .get(&page_no)
+            .and_then(|entry| entry.text.clone());
+        if let Some(text) = cached {
+            return Ok(text);
+        }
+        let text = Rc::new(list.to_text_page(self.options.text_options)?);
+        if let Some(entry) = self.entries.borrow_mut().get_mut(&page_no) {
+            entry.text = Some(text.clone());
+        }
+        Ok(text)
+    }
+
+    /// Drop a cached PDF page if the objects it is drawn from changed since it was cached,
+    /// returning whether it was dropped. Pages of other documents are never dropped. This digests the page and everything it uses, images
+    /// and fonts included, so it costs about as much as recording the page again.
+    pub fn revalidate(&self, page_no: i32) -> Result<bool, Error> {
+        let Some(cached) = self
+            .entries
+            .borrow()
+            .get(&page_no)
+            .map(|entry| entry.digest)
+        else {
+            return Ok(false);
+        };
+        let digest = self.doc.load_page(page_no)?.pdf_digest()?;
+        let stale = cached != digest;
+        if stale {
+            self.invalidate(page_no);
+        }
+        Ok(stale)
+    }
+
+    pub fn invalidate(&self, page_no: i32) {
+        self.entries.borrow_mut().remove(&page_no);
+        self.order.borrow_mut().retain(|&n| n != page_no);
+    }
+
+    pub fn invalidate_all(&self) {
+        self.entries.borrow_mut().clear();
+        self.order.borrow_mut().clear();
+    }
+
+    /// Number of pages currently cached.
+    pub fn len(&self) -> usize {
+        self.entries.borrow().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.borrow().is_empty()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use super::CacheOptions;
+    use crate::Document;
+
+    #[test]
+    fn test_cached_document() {
+        let doc = Document::open("tests/files/dummy.pdf").unwrap();
+        let mut options = CacheOptions::new();
+        options.set_capacity(1);
+        let mut cached = doc.with_cache(options);
+
+        let list = cached.display_list(0).unwrap();
+        assert!(Arc::ptr_eq(&list, &cached.display_list(0).unwrap()));
+        let text = cached.text_page(0).unwrap();
+        assert!(text.to_text().unwrap().contains("Dummy"));
+        assert_eq!(cached.len(), 1);
+
+        cached.invalidate(0);
+        assert!(cached.is_empty());
+        assert!(!Arc::ptr_eq(&list, &cached.display_list(0).unwrap()));
+
+        cached.document_mut();
+        assert!(cached.is_empty());
+    }
+
+    #[test]
+    fn test_cached_document_modified() {
+        use crate::pdf::PdfPage;
+
+        let doc = Document::open("tests/files/dummy.pdf").unwrap();
+        let cached = doc.with_cache(CacheOptions::new());
+        let list = cached.display_list(0).unwrap();
+        assert!(Arc::ptr_eq(&list, &cached.display_list(0).unwrap()));
+
+        let mut page = PdfPage::try_from(cached.document().load_page(0).unwrap()).unwrap();
+        page.set_rotation(90).unwrap();
+        // Changes aren't looked for until asked to
+        assert!(Arc::ptr_eq(&list, &cached.display_list(0).unwrap()));
+        assert!(cached.revalidate(0).unwrap());
+        assert!(!cached.revalidate(0).unwrap());
+        let rotated = cached.display_list(0).unwrap();
+        assert!(!Arc::ptr_eq(&list, &rotated));
+        assert!(!cached.revalidate(0).unwrap());
+        assert!(Arc::ptr_eq(&rotated, &cached.display_list(0).unwrap()));
+    }
+}
@@ -0,0 +1,269 @@
+use std::ffi::{c_char, c_int, c_void, CString};
+use std::mem::ManuallyDrop;
+use std::panic::{self, AssertUnwindSafe};
+use std::{ptr, slice};
+
+use mupdf_sys::*;
+
+use crate::{context, Device, Error, Matrix, Rect};
+
+/// A document format implemented in Rust, see [`Context::register_document_handler`].
+///
+/// [`Context::register_document_handler`]: crate::Context::register_document_handler
+pub trait DocumentHandler: Send + Sync + 'static {
+    /// File extensions, without the leading dot, recognized as this format.
+    fn extensions(&self) -> &[&str];
+
+    /// MIME types recognized as this format.
+    fn mime_types(&self) -> &[&str] {
+        &[]
+    }
+
+    fn open(&self, data: Vec<u8>) -> Result<Box<dyn CustomDocument>, Error>;
+}
+
+/// A document opened by a [`DocumentHandler`].
+pub trait CustomDocument: Send {
+    fn page_count(&self) -> i32;
+
+    fn load_page(&self, page_no: i32) -> Result<Box<dyn CustomPage>, Error>;
+}
+
+/// A page of a [`CustomDocument`].
+pub trait CustomPage: Send {
+    fn bounds(&self) -> Rect;
+
+    /// Draw the page onto `device`.
+    fn run(&self, device: &Device, ctm: &Matrix) -> Result<(), Error>;
+}
+
+fn set_error(error: *mut *mut c_char, message: String) {
+    let message = CString::new(message.replace('\0', " ")).unwrap_or_default();
+    unsafe { *error = message.into_raw() };
+}
+
+/// Run a callback from MuPDF, reporting errors and panics through `error`.
+fn guard<T>(error: *mut *mut c_char, f: impl FnOnce() -> Result<T, Error>) -> Option<T> {
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(value)) => Some(value),
+        Ok(Err(err)) => {
+            set_error(error, err.to_string());
+            None
+        }
+        Err(_) => {
+            set_error(error, "panic in custom document handler".to_owned());
+            None
+        }
+    }
+}
+
+unsafe extern "C" fn free_error(error: *mut c_char) {
+    drop(unsafe { CString::from_raw(error) });
+}
+
+unsafe extern "C" fn open(
+    handler: *mut c_void,
+    data: *const u8,
+    len: usize,
+    error: *mut *mut c_char,
+) -> *mut c_void {
+    let handler = unsafe { &*(handler as *const Box<dyn DocumentHandler>) };
+    let data = unsafe { slice::from_raw_parts(data, len) }.to_vec();
+    guard(error, || handler.open(data)).map_or(ptr::null_mut(), |doc| {
+        Box::into_raw(Box::new(doc)) as *mut c_void
+    })
+}
+
+unsafe extern "C" fn drop_document(doc: *mut c_void) {
+    let doc = unsafe { Box::from_raw(doc as *mut Box<dyn CustomDocument>) };
+    let _ = panic::catch_unwind(AssertUnwindSafe(|| drop(doc)));
+}
+
+unsafe extern "C" fn count_pages(doc: *mut c_void) -> c_int {
+    let doc = unsafe { &*(doc as *const Box<dyn CustomDocument>) };
+    panic::catch_unwind(AssertUnwindSafe(|| doc.page_count())).unwrap_or(0)
+}
+
+unsafe extern "C" fn load_page(
+    doc: *mut c_void,
+    number: c_int,
+    error: *mut *mut c_char,
+) -> *mut c_void {
+    let doc = unsafe { &*(doc as *const Box<dyn CustomDocument>) };
+    guard(error, || doc.load_page(number)).map_or(ptr::null_mut(), |page| {
+        Box::into_raw(Box::new(page)) as *mut c_void
+    })
+}
+
+unsafe extern "C" fn bound_page(page: *mut c_void) -> fz_rect {
+    let page = unsafe { &*(page as *const Box<dyn CustomPage>) };
+    panic::catch_unwind(AssertUnwindSafe(|| page.bounds()))
+        .unwrap_or_default()
+        .into()
+}
+
+unsafe extern "C" fn run_page(
+    page: *mut c_void,
+    dev: *mut fz_device,
+    ctm: fz_matrix,
+    error: *mut *mut c_char,
+) -> c_int {
+    let page = unsafe { &*(page as *const Box<dyn CustomPage>) };
+    // MuPDF owns the device
+    let device = ManuallyDrop::new(unsafe { Device::from_raw(dev, ptr::null_mut()) });
+    let ctm = Matrix::from(ctm);
+    match guard(error, || page.run(&device, &ctm)) {
+        Some(()) => 0,
+        None => 1,
+    }
+}
+
+unsafe extern "C" fn drop_page(page: *mut c_void) {
+    let page = unsafe { Box::from_raw(page as *mut Box<dyn CustomPage>) };
+    let _ = panic::catch_unwind(AssertUnwindSafe(|| drop(page)));
+}
+
+static CALLBACKS: mupdf_custom_callbacks = mupdf_custom_callbacks {
+    drop_document: Some(drop_document),
+    count_pages: Some(count_pages),
+    load_page: Some(load_page),
+    bound_page: Some(bound_page),
+    run_page: Some(run_page),
+    drop_page: Some(drop_page),
+    open: Some(open),
+    free_error: Some(free_error),
+};
+
+/// A null terminated array of C strings, to be freed with [`free_strings`].
+fn new_strings(strings: &[&str]) -> Result<*mut *const c_char, Error> {
+    let strings = strings
+        .iter()
+        .map(|s| CString::new(*s))
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut ptrs: Vec<_> = strings
+        .into_iter()
+        .map(|s| s.into_raw() as *const c_char)
+        .collect();
+    ptrs.push(ptr::null());
+    Ok(Box::into_raw(ptrs.into_boxed_slice()) as *mut *const c_char)
+}
+
+unsafe fn free_strings(strings: *mut *const c_char) {
+    let mut len = 0;
+    loop {
+        let s = unsafe { *strings.add(len) };
+        if s.is_null() {
+            break;
+        }
+        drop(unsafe { CString::from_raw(s as *mut c_char) });
+        len += 1;
+    }
+    drop(unsafe { Box::from_raw(ptr::slice_from_raw_parts_mut(strings, len + 1)) });
+}
+
+pub(crate) fn register(handler: Box<dyn DocumentHandler>) -> Result<(), Error> {
+    let extensions = new_strings(handler.extensions())?;
+    let mime_types = match new_strings(handler.mime_types()) {
+        Ok(mime_types) => mime_types,
+        Err(err) => {
+            unsafe { free_strings(extensions) };
+            return Err(err);
+        }
+    };
+    let user = Box::into_raw(Box::new(handler));
+    let result = unsafe {
+        ffi_try!(mupdf_register_custom_handler(
+            context(),
+            user as *mut c_void,
+            &CALLBACKS,
+            extensions,
+            mime_types
+        ))
+    };
+    // Handlers can't be unregistered, so once registered these are never freed
+    if result.is_err() {
+        unsafe {
+            drop(Box::from_raw(user));
+            free_strings(extensions);
+            free_strings(mime_types);
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::{CustomDocument, CustomPage, DocumentHandler};
+    use crate::{Colorspace, Context, Device, Document, Error, Matrix, Rect};
+
+    struct Blank;
+
+    impl DocumentHandler for Blank {
+        fn extensions(&self) -> &[&str] {
+            &["blank"]
+        }
+
+        fn open(&self, data: Vec<u8>) -> Result<Box<dyn CustomDocument>, Error> {
+            Ok(Box::new(BlankDocument(data.len() as i32)))
+        }
+    }
+
+    struct BlankDocument(i32);
+
+    impl CustomDocument for BlankDocument {
+        fn page_count(&self) -> i32 {
+            self.0
+        }
+
+        fn load_page(&self, page_no: i32) -> Result<Box<dyn CustomPage>, Error> {
+            Ok(Box::new(BlankPage(page_no)))
+        }
+    }
+
+    struct BlankPage(i32);
+
+    impl CustomPage for BlankPage {
+        fn bounds(&self) -> Rect {
+            Rect::new(0.0, 0.0, 100.0, 100.0 * (self.0 + 1) as f32)
+        }
+
+        fn run(&self, _device: &Device, _ctm: &Matrix) -> Result<(), Error> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_custom_document_handler() {
+        Context::get().register_document_handler(Blank).unwrap();
+        let doc = Document::from_bytes(b"abc", "test.blank").unwrap();
+        assert_eq!(doc.page_count().unwrap(), 3);
+        let page = doc.load_page(1).unwrap();
+        assert_eq!(page.bounds().unwrap(), Rect::new(0.0, 0.0, 100.0, 200.0));
+        let pixmap = page
+            .to_pixmap(&Matrix::IDENTITY, &Colorspace::device_rgb(), false, true)
+            .unwrap();
+        assert_eq!(pixmap.height(), 200);
+    }
+
+    struct Invalid;
+
+    impl DocumentHandler for Invalid {
+        fn extensions(&self) -> &[&str] {
+            &["invalid"]
+        }
+
+        fn mime_types(&self) -> &[&str] {
+            &["application/in\0valid"]
+        }
+
+        fn open(&self, _data: Vec<u8>) -> Result<Box<dyn CustomDocument>, Error> {
+            unreachable!()
+        }
+    }
+
+    #[test]
+    fn test_custom_document_handler_invalid() {
+        assert!(Context::get().register_document_handler(Invalid).is_err());
+        assert!(Document::from_bytes(b"abc", "test.invalid").is_err());
+    }
+}
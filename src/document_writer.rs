@@ -3,7 +3,7 @@ use std::ptr;
 
 use mupdf_sys::*;
 
-use crate::{context, Device, Error, Rect};
+use crate::{context, Buffer, Device, Error, Rect};
 
 #[derive(Debug)]
 pub struct DocumentWriter {
@@ -26,6 +26,19 @@ impl DocumentWriter {
         .map(|inner| Self { inner })
     }
 
+    /// A PDF writer appending to `buf`, which holds the whole file once the writer is closed.
+    pub(crate) fn pdf_to_buffer(buf: &Buffer, options: &str) -> Result<Self, Error> {
+        let c_options = CString::new(options)?;
+        unsafe {
+            ffi_try!(mupdf_new_pdf_writer_with_buffer(
+                context(),
+                buf.inner,
+                c_options.as_ptr()
+            ))
+        }
+        .map(|inner| Self { inner })
+    }
+
     pub fn begin_page(&mut self, media_box: Rect) -> Result<Device, Error> {
         unsafe {
             ffi_try!(mupdf_document_writer_begin_page(
@@ -45,6 +58,11 @@ impl DocumentWriter {
         device.dev = ptr::null_mut();
         unsafe { ffi_try!(mupdf_document_writer_end_page(context(), self.inner)) }
     }
+
+    /// Finish writing the document, reporting errors that dropping the writer would ignore.
+    pub fn close(self) -> Result<(), Error> {
+        unsafe { ffi_try!(mupdf_close_document_writer(context(), self.inner)) }
+    }
 }
 
 impl Drop for DocumentWriter {
@@ -5,11 +5,65 @@ use std::num::TryFromIntError;
 use std::ptr::NonNull;
 
 use mupdf_sys::*;
+use num_enum::TryFromPrimitive;
+
+/// Category of an error raised by MuPDF.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, TryFromPrimitive)]
+#[repr(i32)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    Generic = FZ_ERROR_GENERIC as i32,
+    /// Out of memory or a failed system call.
+    System = FZ_ERROR_SYSTEM as i32,
+    /// Unclassified error from a third-party library.
+    Library = FZ_ERROR_LIBRARY as i32,
+    /// Invalid or out-of-range arguments.
+    Argument = FZ_ERROR_ARGUMENT as i32,
+    /// A resource or other hard limit was hit.
+    Limit = FZ_ERROR_LIMIT as i32,
+    Unsupported = FZ_ERROR_UNSUPPORTED as i32,
+    /// Unrecoverable syntax or format error in the input.
+    Format = FZ_ERROR_FORMAT as i32,
+    /// Syntax error in the input.
+    Syntax = FZ_ERROR_SYNTAX as i32,
+    /// More data is needed when loading progressively.
+    TryLater = FZ_ERROR_TRYLATER as i32,
+    /// The operation was cancelled through a [`Cookie`](crate::Cookie).
+    Abort = FZ_ERROR_ABORT as i32,
+    /// The document was found to be broken while repairing it.
+    Repaired = FZ_ERROR_REPAIRED as i32,
+}
 
 #[derive(Debug, Clone)]
 pub struct MuPdfError {
     pub code: i32,
+    pub kind: ErrorKind,
     pub message: String,
+    /// Warnings MuPDF emitted on this thread during the failing call, oldest first, which often
+    /// explain what went wrong before the error was raised.
+    pub warnings: Vec<String>,
+}
+
+impl MuPdfError {
+    /// Whether the input document is corrupt or malformed.
+    pub fn is_corrupt_input(&self) -> bool {
+        matches!(
+            self.kind,
+            ErrorKind::Format | ErrorKind::Syntax | ErrorKind::Repaired
+        )
+    }
+
+    pub fn is_out_of_memory(&self) -> bool {
+        // MuPDF reports allocation failures as system errors such as "malloc (16 bytes) failed"
+        self.kind == ErrorKind::System
+            && ["malloc", "calloc", "realloc"]
+                .iter()
+                .any(|f| self.message.starts_with(f))
+    }
+
+    pub fn is_aborted(&self) -> bool {
+        self.kind == ErrorKind::Abort
+    }
 }
 
 impl fmt::Display for MuPdfError {
@@ -18,7 +72,11 @@ impl fmt::Display for MuPdfError {
             f,
             "MuPDF error, code: {}, message: {}",
             self.code, &self.message
-        )
+        )?;
+        for warning in &self.warnings {
+            write!(f, "\n  warning: {}", warning)?;
+        }
+        Ok(())
     }
 }
 
@@ -46,13 +104,19 @@ pub unsafe fn ffi_error(ptr: NonNull<mupdf_error_t>) -> MuPdfError {
 
     // SAFETY: Upheld by caller; if it's pointing to a valid instance then it can be dropped
     unsafe { mupdf_drop_error(ptr.as_ptr()) };
-    MuPdfError { code, message }
+    MuPdfError {
+        code,
+        kind: ErrorKind::try_from(code).unwrap_or(ErrorKind::Generic),
+        message,
+        warnings: crate::message::take_warnings(),
+    }
 }
 
 macro_rules! ffi_try {
     ($func:ident($($arg:expr),+)) => ({
         use std::ptr;
         let mut err = ptr::null_mut();
+        $crate::message::clear_warnings();
         // SAFETY: Upheld by the caller of the macro
         let res = $func($($arg),+, (&mut err) as *mut *mut ::mupdf_sys::mupdf_error_t);
         ::core::ptr::NonNull::new(err)
@@ -75,6 +139,24 @@ pub enum Error {
     IntConversion(TryFromIntError),
     InvalidUtf8,
     UnexpectedNullPtr,
+    StoreLimitAlreadySet(usize),
+    FontVariation(String),
+    InvalidFont(String),
+    /// The pixmap's format isn't supported by the operation.
+    InvalidPixmap(String),
+    /// Rendering was stopped through a [`Cookie`](crate::Cookie) before it completed.
+    Aborted,
+    /// A batch job manifest couldn't be parsed or one of its operations failed.
+    InvalidManifest(String),
+    /// Annotation data to import is malformed or can't be represented.
+    InvalidAnnotation(String),
+    /// Text index data to import is malformed.
+    InvalidTextIndex(String),
+    /// Groups or masks of a [`ContentBuilder`](crate::pdf::ContentBuilder) were ended out of
+    /// order or left open, or content was drawn inside them that can't be nested.
+    UnbalancedContent(String),
+    /// An argument is out of range or otherwise can't be used.
+    InvalidInput(String),
 }
 
 impl fmt::Display for Error {
@@ -91,12 +173,52 @@ impl fmt::Display for Error {
                 f,
                 "An FFI function call returned a null ptr when we expected a non-null ptr"
             ),
+            Error::StoreLimitAlreadySet(limit) => {
+                write!(f, "resource store already created with a limit of {} bytes", limit)
+            }
+            Error::FontVariation(ref msg) => write!(f, "font variation error: {}", msg),
+            Error::InvalidFont(ref msg) => write!(f, "invalid font: {}", msg),
+            Error::InvalidPixmap(ref msg) => write!(f, "invalid pixmap: {}", msg),
+            Error::Aborted => f.write_str("operation aborted"),
+            Error::InvalidManifest(ref msg) => write!(f, "invalid manifest: {}", msg),
+            Error::InvalidAnnotation(ref msg) => write!(f, "invalid annotation: {}", msg),
+            Error::InvalidTextIndex(ref msg) => write!(f, "invalid text index: {}", msg),
+            Error::UnbalancedContent(ref msg) => write!(f, "unbalanced content: {}", msg),
+            Error::InvalidInput(ref msg) => write!(f, "invalid input: {}", msg),
         }
     }
 }
 
 impl std::error::Error for Error {}
 
+impl Error {
+    /// The MuPDF error category, if this error was raised by MuPDF.
+    pub fn kind(&self) -> Option<ErrorKind> {
+        match self {
+            Error::MuPdf(err) => Some(err.kind),
+            _ => None,
+        }
+    }
+
+    /// Whether the input document is corrupt or malformed.
+    pub fn is_corrupt_input(&self) -> bool {
+        matches!(self, Error::MuPdf(err) if err.is_corrupt_input())
+    }
+
+    pub fn is_out_of_memory(&self) -> bool {
+        matches!(self, Error::MuPdf(err) if err.is_out_of_memory())
+    }
+
+    /// Whether the operation was cancelled through a [`Cookie`](crate::Cookie).
+    pub fn is_aborted(&self) -> bool {
+        match self {
+            Error::MuPdf(err) => err.is_aborted(),
+            Error::Aborted => true,
+            _ => false,
+        }
+    }
+}
+
 impl From<io::Error> for Error {
     fn from(err: io::Error) -> Self {
         Self::Io(err)
@@ -2,9 +2,11 @@ use std::ffi::{CStr, CString};
 use std::fmt;
 use std::str::FromStr;
 
+use bitflags::bitflags;
 use mupdf_sys::*;
 use num_enum::TryFromPrimitive;
 
+use crate::sfnt::find_table;
 use crate::{context, Buffer, Error, Matrix, Path};
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -48,6 +50,76 @@ impl FromStr for CjkFontOrdering {
     }
 }
 
+bitflags! {
+    /// Color glyph formats present in a font file.
+    ///
+    /// MuPDF only rasterizes glyph outlines. Glyphs that exist solely as color bitmaps (`CBDT`,
+    /// `sbix`) render blank, while `COLR` and `SVG` glyphs render as their monochrome base
+    /// outline, unless drawn through [`Device::with_color_glyphs`]. This is the same on every
+    /// platform, but whether text falls back to a font with outlines for emoji depends on the
+    /// fonts built in, see [`Font::has_emoji_fallback`].
+    ///
+    /// [`Device::with_color_glyphs`]: crate::Device::with_color_glyphs
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct ColorGlyphFormats: u32 {
+        const CBDT = 1 << 0;
+        const SBIX = 1 << 1;
+        const COLR = 1 << 2;
+        const SVG = 1 << 3;
+    }
+}
+
+impl ColorGlyphFormats {
+    pub fn from_bytes(font_data: &[u8]) -> Self {
+        Self::from_bytes_with_index(font_data, 0)
+    }
+
+    pub fn from_bytes_with_index(font_data: &[u8], index: u32) -> Self {
+        let mut formats = Self::empty();
+        for (tag, format) in [
+            (b"CBDT", Self::CBDT),
+            (b"sbix", Self::SBIX),
+            (b"COLR", Self::COLR),
+            (b"SVG ", Self::SVG),
+        ] {
+            formats.set(format, find_table(font_data, index, tag).is_some());
+        }
+        formats
+    }
+
+    /// Whether the font's glyphs only exist as color bitmaps, so nothing would be rendered.
+    pub fn is_bitmap_only(font_data: &[u8], index: u32) -> bool {
+        let formats = Self::from_bytes_with_index(font_data, index);
+        formats.intersects(Self::CBDT | Self::SBIX)
+            && [b"glyf", b"CFF ", b"CFF2"]
+                .iter()
+                .all(|tag| find_table(font_data, index, tag).is_none())
+    }
+}
+
+/// How to treat fonts with color glyphs when loading them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorGlyphPolicy {
+    /// Load every font; glyphs without outlines render blank.
+    #[default]
+    Allow,
+    /// Refuse fonts whose glyphs only exist as color bitmaps, so the caller can pick another
+    /// font (or rely on the emoji fallback) instead of silently rendering nothing.
+    RejectBitmapOnly,
+}
+
+/// How to draw glyphs that have a color version (`CBDT`, `sbix` or `COLR`), see
+/// [`Device::with_color_glyphs`].
+///
+/// [`Device::with_color_glyphs`]: crate::Device::with_color_glyphs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorGlyphRendering {
+    /// Draw the color version as an image, at about the resolution the glyph is drawn at.
+    Rasterize = 1,
+    /// Leave glyphs with a color version out.
+    Skip = 2,
+}
+
 #[derive(Debug)]
 pub struct Font {
     pub(crate) inner: *mut fz_font,
@@ -86,6 +158,60 @@ impl Font {
         .map(|inner| Self { inner })
     }
 
+    /// Load an instance of a variable font, with `coordinates` holding one value per axis in
+    /// design units, as built by [`FontVariations::coordinates`] or taken from a
+    /// [`NamedInstance`]. The coordinates are applied through FreeType, which clamps values
+    /// outside of an axis' range.
+    ///
+    /// [`FontVariations::coordinates`]: crate::FontVariations::coordinates
+    /// [`NamedInstance`]: crate::NamedInstance
+    pub fn from_bytes_with_variation(
+        name: &str,
+        index: i32,
+        font_data: &[u8],
+        coordinates: &[f32],
+    ) -> Result<Self, Error> {
+        let c_name = CString::new(name)?;
+        let buffer = Buffer::from_bytes(font_data)?;
+        unsafe {
+            ffi_try!(mupdf_new_font_instance_from_buffer(
+                context(),
+                c_name.as_ptr(),
+                index,
+                buffer.inner,
+                coordinates.as_ptr(),
+                coordinates.len().try_into()?
+            ))
+        }
+        .map(|inner| Self { inner })
+    }
+
+    /// Like [`Font::from_bytes_with_index`], applying `policy` to fonts with color glyphs.
+    pub fn from_bytes_with_policy(
+        name: &str,
+        index: i32,
+        font_data: &[u8],
+        policy: ColorGlyphPolicy,
+    ) -> Result<Self, Error> {
+        if policy == ColorGlyphPolicy::RejectBitmapOnly
+            && ColorGlyphFormats::is_bitmap_only(font_data, index.try_into()?)
+        {
+            return Err(Error::InvalidFont(
+                "font only contains color bitmap glyphs".to_owned(),
+            ));
+        }
+        Self::from_bytes_with_index(name, index, font_data)
+    }
+
+    /// Whether MuPDF was built with its emoji font, which characters missing from a font fall back
+    /// to. Without it (the default unless the `all-fonts` feature is enabled) emoji render as
+    /// blank boxes.
+    pub fn has_emoji_fallback() -> bool {
+        let mut len = 0;
+        let data = unsafe { fz_lookup_noto_emoji_font(context(), &mut len) };
+        !data.is_null() && len > 0
+    }
+
     pub fn name(&self) -> &str {
         let f_name = unsafe { fz_font_name(context(), self.inner) };
         let c_name = unsafe { CStr::from_ptr(f_name) };
@@ -189,6 +315,23 @@ mod test {
         assert_eq!(advance, 0.6);
     }
 
+    #[test]
+    fn test_color_glyph_formats() {
+        use super::{ColorGlyphFormats, ColorGlyphPolicy};
+
+        let mut data = 0x0001_0000u32.to_be_bytes().to_vec();
+        data.extend_from_slice(&[0, 1, 0, 16, 0, 0, 0, 0]);
+        data.extend_from_slice(b"CBDT");
+        data.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 28, 0, 0, 0, 0]);
+        assert_eq!(
+            ColorGlyphFormats::from_bytes(&data),
+            ColorGlyphFormats::CBDT
+        );
+        assert!(ColorGlyphFormats::is_bitmap_only(&data, 0));
+        let font = Font::from_bytes_with_policy("", 0, &data, ColorGlyphPolicy::RejectBitmapOnly);
+        assert!(font.is_err());
+    }
+
     #[test]
     fn test_outline_glyph() {
         let font = Font::new("Courier").expect("new font failed");
@@ -0,0 +1,255 @@
+use crate::sfnt::{find_table, is_font, Reader};
+use crate::Error;
+
+/// A design axis of a variable font, read from its `fvar` table.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VariationAxis {
+    /// Four character axis tag, e.g. `wght` or `wdth`.
+    pub tag: String,
+    pub min: f32,
+    pub default: f32,
+    pub max: f32,
+    /// Whether the font asks for the axis not to be shown in user interfaces.
+    pub hidden: bool,
+}
+
+/// A predefined instance of a variable font, e.g. "Bold Condensed".
+#[derive(Debug, Clone, PartialEq)]
+pub struct NamedInstance {
+    pub name: Option<String>,
+    /// One value per axis, in the order of [`FontVariations::axes`].
+    pub coordinates: Vec<f32>,
+}
+
+/// The axes and named instances of a variable (OpenType Font Variations) font.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FontVariations {
+    pub axes: Vec<VariationAxis>,
+    pub instances: Vec<NamedInstance>,
+}
+
+fn invalid() -> Error {
+    Error::FontVariation("invalid or truncated font data".to_owned())
+}
+
+/// Look up a string in the `name` table, preferring the Windows Unicode English entry.
+fn lookup_name(name_table: &[u8], name_id: u16) -> Option<String> {
+    let r = Reader(name_table);
+    let count = r.u16(2)? as usize;
+    let storage = r.u16(4)? as usize;
+    let mut fallback = None;
+    for i in 0..count {
+        let rec = 6 + 12 * i;
+        if r.u16(rec + 6)? != name_id {
+            continue;
+        }
+        let platform = r.u16(rec)?;
+        let encoding = r.u16(rec + 2)?;
+        let language = r.u16(rec + 4)?;
+        let len = r.u16(rec + 8)? as usize;
+        let offset = storage + r.u16(rec + 10)? as usize;
+        let raw = r.bytes(offset, len)?;
+        match (platform, encoding) {
+            (0, _) | (3, 1) | (3, 10) => {
+                let units: Vec<u16> = raw
+                    .chunks_exact(2)
+                    .map(|c| u16::from_be_bytes([c[0], c[1]]))
+                    .collect();
+                let name = String::from_utf16_lossy(&units);
+                if platform == 3 && language == 0x409 {
+                    return Some(name);
+                }
+                fallback.get_or_insert(name);
+            }
+            (1, 0) => {
+                fallback.get_or_insert_with(|| String::from_utf8_lossy(raw).into_owned());
+            }
+            _ => {}
+        }
+    }
+    fallback
+}
+
+impl FontVariations {
+    /// Read the variation axes of a font file, returning `None` if it isn't a variable font.
+    pub fn from_bytes(font_data: &[u8]) -> Result<Option<Self>, Error> {
+        Self::from_bytes_with_index(font_data, 0)
+    }
+
+    /// Read the variation axes of the font at `index` in a font collection (`.ttc`) file.
+    pub fn from_bytes_with_index(font_data: &[u8], index: u32) -> Result<Option<Self>, Error> {
+        if !is_font(font_data, index) {
+            return Err(invalid());
+        }
+        let Some(fvar) = find_table(font_data, index, b"fvar") else {
+            return Ok(None);
+        };
+        let names = find_table(font_data, index, b"name");
+        Self::parse(fvar, names).map(Some).ok_or_else(invalid)
+    }
+
+    fn parse(fvar: &[u8], names: Option<&[u8]>) -> Option<Self> {
+        let r = Reader(fvar);
+        let axes_offset = r.u16(4)? as usize;
+        let axis_count = r.u16(8)? as usize;
+        let axis_size = r.u16(10)? as usize;
+        let instance_count = r.u16(12)? as usize;
+        let instance_size = r.u16(14)? as usize;
+
+        let mut axes = Vec::with_capacity(axis_count);
+        for i in 0..axis_count {
+            let rec = axes_offset + axis_size * i;
+            axes.push(VariationAxis {
+                tag: String::from_utf8_lossy(r.bytes(rec, 4)?).into_owned(),
+                min: r.fixed(rec + 4)?,
+                default: r.fixed(rec + 8)?,
+                max: r.fixed(rec + 12)?,
+                hidden: r.u16(rec + 16)? & 1 != 0,
+            });
+        }
+
+        let instances_offset = axes_offset + axis_size * axis_count;
+        let mut instances = Vec::with_capacity(instance_count);
+        for i in 0..instance_count {
+            let rec = instances_offset + instance_size * i;
+            let name_id = r.u16(rec)?;
+            let coordinates = (0..axis_count)
+                .map(|a| r.fixed(rec + 4 + 4 * a))
+                .collect::<Option<_>>()?;
+            instances.push(NamedInstance {
+                name: names.and_then(|n| lookup_name(n, name_id)),
+                coordinates,
+            });
+        }
+
+        Some(Self { axes, instances })
+    }
+
+    pub fn instance(&self, name: &str) -> Option<&NamedInstance> {
+        self.instances
+            .iter()
+            .find(|inst| inst.name.as_deref() == Some(name))
+    }
+
+    /// Coordinates with every axis at its default value.
+    pub fn default_coordinates(&self) -> Vec<f32> {
+        self.axes.iter().map(|axis| axis.default).collect()
+    }
+
+    /// Build a full set of coordinates from `(tag, value)` pairs, leaving the other axes at their
+    /// default.
+    pub fn coordinates(&self, values: &[(&str, f32)]) -> Result<Vec<f32>, Error> {
+        let mut coords = self.default_coordinates();
+        for &(tag, value) in values {
+            let i = self
+                .axes
+                .iter()
+                .position(|axis| axis.tag == tag)
+                .ok_or_else(|| Error::FontVariation(format!("no such axis: {}", tag)))?;
+            coords[i] = value;
+        }
+        self.validate(&coords)?;
+        Ok(coords)
+    }
+
+    pub(crate) fn validate(&self, coords: &[f32]) -> Result<(), Error> {
+        if coords.len() != self.axes.len() {
+            return Err(Error::FontVariation(format!(
+                "expected {} coordinates, got {}",
+                self.axes.len(),
+                coords.len()
+            )));
+        }
+        for (axis, &value) in self.axes.iter().zip(coords) {
+            if value < axis.min || value > axis.max {
+                return Err(Error::FontVariation(format!(
+                    "{} value {} is outside of {}..={}",
+                    axis.tag, value, axis.min, axis.max
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn is_default(&self, coords: &[f32]) -> bool {
+        self.axes
+            .iter()
+            .zip(coords)
+            .all(|(axis, &value)| axis.default == value)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::FontVariations;
+
+    /// A minimal sfnt with a two axis `fvar` table and a single named instance.
+    fn variable_font() -> Vec<u8> {
+        let mut fvar = Vec::new();
+        for v in [1u16, 0, 16, 2, 2, 20, 1, 12] {
+            fvar.extend_from_slice(&v.to_be_bytes());
+        }
+        for (tag, min, def, max) in [(b"wght", 100i32, 400, 900), (b"wdth", 75, 100, 100)] {
+            fvar.extend_from_slice(tag);
+            for v in [min, def, max] {
+                fvar.extend_from_slice(&(v << 16).to_be_bytes());
+            }
+            fvar.extend_from_slice(&[0, 0, 0, 0]);
+        }
+        fvar.extend_from_slice(&[0, 0, 0, 0]);
+        fvar.extend_from_slice(&(700i32 << 16).to_be_bytes());
+        fvar.extend_from_slice(&(75i32 << 16).to_be_bytes());
+
+        let mut font = Vec::new();
+        font.extend_from_slice(&0x0001_0000u32.to_be_bytes());
+        font.extend_from_slice(&[0, 1, 0, 16, 0, 0, 0, 0]);
+        font.extend_from_slice(b"fvar");
+        font.extend_from_slice(&0u32.to_be_bytes());
+        font.extend_from_slice(&28u32.to_be_bytes());
+        font.extend_from_slice(&(fvar.len() as u32).to_be_bytes());
+        font.extend_from_slice(&fvar);
+        font
+    }
+
+    #[test]
+    fn test_font_variations() {
+        let vars = FontVariations::from_bytes(&variable_font())
+            .unwrap()
+            .unwrap();
+        assert_eq!(vars.axes.len(), 2);
+        assert_eq!(vars.axes[0].tag, "wght");
+        assert_eq!(vars.axes[0].default, 400.0);
+        assert_eq!(vars.axes[1].min, 75.0);
+        assert_eq!(vars.instances.len(), 1);
+        assert_eq!(vars.instances[0].coordinates, vec![700.0, 75.0]);
+        assert_eq!(vars.instances[0].name, None);
+
+        let coords = vars.coordinates(&[("wght", 400.0)]).unwrap();
+        assert_eq!(coords, vars.default_coordinates());
+        assert!(vars.is_default(&coords));
+        assert!(vars.coordinates(&[("wght", 1000.0)]).is_err());
+        assert!(vars.coordinates(&[("slnt", 0.0)]).is_err());
+    }
+
+    #[test]
+    fn test_embed_font_instance() {
+        use crate::pdf::PdfDocument;
+        use crate::Error;
+
+        let font = variable_font();
+        let mut doc = PdfDocument::new();
+        // Only the default instance can be embedded, the font file holds no other
+        assert!(matches!(
+            doc.embed_font_instance(&font, &[700.0, 75.0]),
+            Err(Error::FontVariation(_))
+        ));
+        assert!(matches!(
+            doc.embed_font_instance(&font, &[1000.0, 100.0]),
+            Err(Error::FontVariation(_))
+        ));
+        assert!(matches!(
+            doc.embed_font_instance(&font, &[400.0]),
+            Err(Error::FontVariation(_))
+        ));
+    }
+}
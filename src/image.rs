@@ -54,7 +54,7 @@ impl Image {
     }
 
     pub fn color_space(&self) -> Colorspace {
-        unsafe { Colorspace::from_raw((*self.inner).colorspace) }
+        unsafe { Colorspace::from_raw_kept((*self.inner).colorspace) }
     }
 
     pub fn resolution(&self) -> (i32, i32) {
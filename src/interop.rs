@@ -0,0 +1,220 @@
+//! `From` conversions between [`Matrix`], [`Point`], [`Rect`] and [`Size`] and the equivalent
+//! types of the `mint`, `euclid` and `kurbo` crates, each behind the feature of the same name.
+//!
+//! All of them use the same row vector convention as MuPDF: a point is transformed as
+//! `x' = a * x + c * y + e`, `y' = b * x + d * y + f`.
+
+#[cfg(feature = "mint")]
+mod mint_impls {
+    use crate::{Matrix, Point, Size};
+
+    impl From<mint::Point2<f32>> for Point {
+        fn from(p: mint::Point2<f32>) -> Self {
+            Point::new(p.x, p.y)
+        }
+    }
+
+    impl From<Point> for mint::Point2<f32> {
+        fn from(p: Point) -> Self {
+            mint::Point2 { x: p.x, y: p.y }
+        }
+    }
+
+    impl From<mint::Vector2<f32>> for Size {
+        fn from(v: mint::Vector2<f32>) -> Self {
+            Size::new(v.x, v.y)
+        }
+    }
+
+    impl From<Size> for mint::Vector2<f32> {
+        fn from(s: Size) -> Self {
+            mint::Vector2 {
+                x: s.width,
+                y: s.height,
+            }
+        }
+    }
+
+    impl From<mint::RowMatrix3x2<f32>> for Matrix {
+        fn from(m: mint::RowMatrix3x2<f32>) -> Self {
+            Matrix::new(m.x.x, m.x.y, m.y.x, m.y.y, m.z.x, m.z.y)
+        }
+    }
+
+    impl From<Matrix> for mint::RowMatrix3x2<f32> {
+        fn from(m: Matrix) -> Self {
+            mint::RowMatrix3x2 {
+                x: mint::Vector2 { x: m.a, y: m.b },
+                y: mint::Vector2 { x: m.c, y: m.d },
+                z: mint::Vector2 { x: m.e, y: m.f },
+            }
+        }
+    }
+}
+
+#[cfg(feature = "euclid")]
+mod euclid_impls {
+    use crate::{Matrix, Point, Rect, Size};
+
+    impl<U> From<euclid::Point2D<f32, U>> for Point {
+        fn from(p: euclid::Point2D<f32, U>) -> Self {
+            Point::new(p.x, p.y)
+        }
+    }
+
+    impl<U> From<Point> for euclid::Point2D<f32, U> {
+        fn from(p: Point) -> Self {
+            euclid::Point2D::new(p.x, p.y)
+        }
+    }
+
+    impl<U> From<euclid::Size2D<f32, U>> for Size {
+        fn from(s: euclid::Size2D<f32, U>) -> Self {
+            Size::new(s.width, s.height)
+        }
+    }
+
+    impl<U> From<Size> for euclid::Size2D<f32, U> {
+        fn from(s: Size) -> Self {
+            euclid::Size2D::new(s.width, s.height)
+        }
+    }
+
+    impl<U> From<euclid::Box2D<f32, U>> for Rect {
+        fn from(r: euclid::Box2D<f32, U>) -> Self {
+            Rect::new(r.min.x, r.min.y, r.max.x, r.max.y)
+        }
+    }
+
+    impl<U> From<Rect> for euclid::Box2D<f32, U> {
+        fn from(r: Rect) -> Self {
+            euclid::Box2D::new(
+                euclid::Point2D::new(r.x0, r.y0),
+                euclid::Point2D::new(r.x1, r.y1),
+            )
+        }
+    }
+
+    impl<U> From<euclid::Rect<f32, U>> for Rect {
+        fn from(r: euclid::Rect<f32, U>) -> Self {
+            r.to_box2d().into()
+        }
+    }
+
+    impl<U> From<Rect> for euclid::Rect<f32, U> {
+        fn from(r: Rect) -> Self {
+            euclid::Box2D::from(r).to_rect()
+        }
+    }
+
+    impl<Src, Dst> From<euclid::Transform2D<f32, Src, Dst>> for Matrix {
+        fn from(m: euclid::Transform2D<f32, Src, Dst>) -> Self {
+            Matrix::new(m.m11, m.m12, m.m21, m.m22, m.m31, m.m32)
+        }
+    }
+
+    impl<Src, Dst> From<Matrix> for euclid::Transform2D<f32, Src, Dst> {
+        fn from(m: Matrix) -> Self {
+            euclid::Transform2D::new(m.a, m.b, m.c, m.d, m.e, m.f)
+        }
+    }
+}
+
+#[cfg(feature = "kurbo")]
+mod kurbo_impls {
+    use crate::{Matrix, Point, Rect, Size};
+
+    impl From<kurbo::Point> for Point {
+        fn from(p: kurbo::Point) -> Self {
+            Point::new(p.x as f32, p.y as f32)
+        }
+    }
+
+    impl From<Point> for kurbo::Point {
+        fn from(p: Point) -> Self {
+            kurbo::Point::new(p.x.into(), p.y.into())
+        }
+    }
+
+    impl From<kurbo::Size> for Size {
+        fn from(s: kurbo::Size) -> Self {
+            Size::new(s.width as f32, s.height as f32)
+        }
+    }
+
+    impl From<Size> for kurbo::Size {
+        fn from(s: Size) -> Self {
+            kurbo::Size::new(s.width.into(), s.height.into())
+        }
+    }
+
+    impl From<kurbo::Rect> for Rect {
+        fn from(r: kurbo::Rect) -> Self {
+            Rect::new(r.x0 as f32, r.y0 as f32, r.x1 as f32, r.y1 as f32)
+        }
+    }
+
+    impl From<Rect> for kurbo::Rect {
+        fn from(r: Rect) -> Self {
+            kurbo::Rect::new(r.x0.into(), r.y0.into(), r.x1.into(), r.y1.into())
+        }
+    }
+
+    impl From<kurbo::Affine> for Matrix {
+        fn from(m: kurbo::Affine) -> Self {
+            let [a, b, c, d, e, f] = m.as_coeffs().map(|v| v as f32);
+            Matrix::new(a, b, c, d, e, f)
+        }
+    }
+
+    impl From<Matrix> for kurbo::Affine {
+        fn from(m: Matrix) -> Self {
+            kurbo::Affine::new([m.a, m.b, m.c, m.d, m.e, m.f].map(f64::from))
+        }
+    }
+}
+
+#[cfg(all(test, any(feature = "mint", feature = "euclid", feature = "kurbo")))]
+mod test {
+
+    #[test]
+    #[cfg(feature = "mint")]
+    fn test_mint() {
+        use crate::{Matrix, Point};
+
+        let m = Matrix::new(1.0, 2.0, 3.0, 4.0, 5.0, 6.0);
+        let mm: mint::RowMatrix3x2<f32> = m.clone().into();
+        assert_eq!(mm.z.x, 5.0);
+        assert_eq!(Matrix::from(mm), m);
+        let p: mint::Point2<f32> = Point::new(1.0, 2.0).into();
+        assert_eq!(Point::from(p), Point::new(1.0, 2.0));
+    }
+
+    #[test]
+    #[cfg(feature = "euclid")]
+    fn test_euclid() {
+        use crate::{Matrix, Point, Rect};
+
+        let m = Matrix::new_translate(10.0, 20.0);
+        let t: euclid::default::Transform2D<f32> = m.clone().into();
+        let p = t.transform_point(euclid::default::Point2D::new(1.0, 2.0));
+        assert_eq!(Point::from(p), Point::new(1.0, 2.0).transform(&m));
+        let r: euclid::default::Rect<f32> = Rect::new(1.0, 2.0, 4.0, 6.0).into();
+        assert_eq!(r.size, euclid::default::Size2D::new(3.0, 4.0));
+        assert_eq!(Rect::from(r), Rect::new(1.0, 2.0, 4.0, 6.0));
+    }
+
+    #[test]
+    #[cfg(feature = "kurbo")]
+    fn test_kurbo() {
+        use crate::{Matrix, Point, Rect};
+
+        let m = Matrix::new_scale(2.0, 3.0) * Matrix::new_translate(1.0, 1.0);
+        let affine: kurbo::Affine = m.clone().into();
+        let p = affine * kurbo::Point::new(1.0, 1.0);
+        assert_eq!(Point::from(p), Point::new(1.0, 1.0).transform(&m));
+        assert_eq!(Matrix::from(affine), m);
+        let r: kurbo::Rect = Rect::new(1.0, 2.0, 4.0, 6.0).into();
+        assert_eq!(r.area(), 12.0);
+    }
+}
@@ -0,0 +1,350 @@
+use crate::text_page::TextBlockType;
+use crate::{Point, Quad, Rect, TextPage};
+
+/// Characters starting an unordered list item when followed by whitespace.
+const BULLETS: &[char] = &[
+    '•', '◦', '▪', '▫', '‣', '⁃', '●', '○', '■', '□', '–', '—', '-', '*', '·',
+];
+
+/// Characters used as footnote labels besides digits.
+const NOTE_SYMBOLS: &[char] = &['*', '†', '‡', '§', '¶'];
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub enum LayoutBlockKind {
+    Paragraph,
+    /// An item of a bulleted or numbered list, `marker` being e.g. `•`, `3.` or `(b)`.
+    ListItem {
+        marker: String,
+        ordered: bool,
+    },
+    /// A note at the bottom of the page, `label` being e.g. `1` or `*`.
+    Footnote {
+        label: String,
+    },
+}
+
+/// A run of text with a role on the page.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct LayoutBlock {
+    pub kind: LayoutBlockKind,
+    /// The text, lines joined with spaces, without the list marker or footnote label.
+    pub text: String,
+    pub bounds: Rect,
+}
+
+/// A superscript footnote mark in the body text.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct FootnoteReference {
+    pub label: String,
+    pub quad: Quad,
+    /// Index of the block containing the mark.
+    pub block: usize,
+    /// Index of the footnote block with the same label, if any.
+    pub footnote: Option<usize>,
+}
+
+/// The result of [`TextPage::layout`].
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct PageLayout {
+    pub blocks: Vec<LayoutBlock>,
+    pub footnote_references: Vec<FootnoteReference>,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct CharInfo {
+    pub c: char,
+    pub size: f32,
+    pub origin: Point,
+    pub quad: Quad,
+    pub bold: bool,
+    pub italic: bool,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Line {
+    pub glyphs: Vec<CharInfo>,
+    pub bounds: Rect,
+}
+
+impl Line {
+    pub fn text(&self) -> String {
+        self.glyphs.iter().map(|g| g.c).collect()
+    }
+}
+
+/// The text blocks of a page as lines of glyphs.
+pub(crate) fn text_lines(page: &TextPage) -> Vec<Vec<Line>> {
+    page.blocks()
+        .filter(|block| block.r#type() == TextBlockType::Text)
+        .map(|block| {
+            block
+                .lines()
+                .map(|line| Line {
+                    glyphs: line
+                        .chars()
+                        .map(|ch| {
+                            let font = ch.font();
+                            CharInfo {
+                                c: ch.char().unwrap_or(char::REPLACEMENT_CHARACTER),
+                                size: ch.size(),
+                                origin: ch.origin(),
+                                quad: ch.quad(),
+                                bold: font.is_bold(),
+                                italic: font.is_italic(),
+                            }
+                        })
+                        .collect(),
+                    bounds: line.bounds(),
+                })
+                .filter(|line| line.glyphs.iter().any(|g| !g.c.is_whitespace()))
+                .collect::<Vec<_>>()
+        })
+        .filter(|lines| !lines.is_empty())
+        .collect()
+}
+
+/// The most common font size, rounded to half points.
+pub(crate) fn body_size(blocks: &[Vec<Line>]) -> f32 {
+    let mut counts: Vec<(i32, usize)> = Vec::new();
+    for g in blocks.iter().flatten().flat_map(|line| &line.glyphs) {
+        let key = (g.size * 2.0).round() as i32;
+        match counts.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, n)) => *n += 1,
+            None => counts.push((key, 1)),
+        }
+    }
+    counts
+        .into_iter()
+        .max_by_key(|&(key, n)| (n, -key))
+        .map_or(0.0, |(key, _)| key as f32 / 2.0)
+}
+
+/// Split a list marker off the start of `text`, returning it, whether it is ordered, and the rest.
+pub(crate) fn list_marker(text: &str) -> Option<(String, bool, &str)> {
+    let text = text.trim_start();
+    let (marker, rest) = text.split_once(char::is_whitespace)?;
+    let rest = rest.trim_start();
+    if rest.is_empty() {
+        return None;
+    }
+    let mut chars = marker.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        if BULLETS.contains(&c) {
+            return Some((marker.to_owned(), false, rest));
+        }
+    }
+    let inner = marker
+        .strip_prefix('(')
+        .and_then(|m| m.strip_suffix(')'))
+        .or_else(|| marker.strip_suffix('.'))
+        .or_else(|| marker.strip_suffix(')'))?;
+    let ordered = (!inner.is_empty()
+        && inner.len() <= 3
+        && inner.chars().all(|c| c.is_ascii_digit()))
+        || (inner.len() == 1 && inner.chars().all(|c| c.is_ascii_lowercase()))
+        || (!inner.is_empty() && inner.len() <= 5 && inner.chars().all(|c| "ivxlc".contains(c)));
+    ordered.then(|| (marker.to_owned(), true, rest))
+}
+
+fn is_note_label(s: &str) -> bool {
+    !s.is_empty()
+        && s.len() <= 3
+        && (s.chars().all(|c| c.is_ascii_digit()) || s.chars().all(|c| NOTE_SYMBOLS.contains(&c)))
+}
+
+/// A footnote label starting a line: small raised characters, or a label followed by a space.
+fn footnote_label(line: &Line, body: f32) -> Option<(String, String)> {
+    let text = line.text();
+    let text = text.trim_start();
+    let small: String = line
+        .glyphs
+        .iter()
+        .skip_while(|g| g.c.is_whitespace())
+        .take_while(|g| g.size < body * 0.85 && !g.c.is_whitespace())
+        .map(|g| g.c)
+        .collect();
+    if is_note_label(&small) && text.len() > small.len() {
+        let rest = text[small.len()..].trim_start().to_owned();
+        return Some((small, rest));
+    }
+    let (label, rest) = text.split_once(char::is_whitespace)?;
+    let label = label.trim_end_matches(['.', ')']);
+    is_note_label(label).then(|| (label.to_owned(), rest.trim_start().to_owned()))
+}
+
+fn union(rects: impl IntoIterator<Item = Rect>) -> Rect {
+    let mut rects = rects.into_iter();
+    let mut bounds = rects.next().unwrap_or_default();
+    for r in rects {
+        bounds.union(r);
+    }
+    bounds
+}
+
+fn join(lines: &[Line]) -> String {
+    lines
+        .iter()
+        .map(|line| line.text().split_whitespace().collect::<Vec<_>>().join(" "))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+impl TextPage {
+    /// Classify the text of the page into paragraphs, list items and footnotes, and find the
+    /// footnote marks referring to the footnotes.
+    ///
+    /// This relies on heuristics: bullet and numbering patterns for lists, and font size and
+    /// position for footnotes, which are looked for in the bottom third of the page and must
+    /// be set smaller than the body text.
+    pub fn layout(&self) -> PageLayout {
+        let blocks = text_lines(self);
+        let body = body_size(&blocks);
+        let page = self.bounds();
+        let footer_top = page.y0 + page.height() * 2.0 / 3.0;
+
+        let mut layout = PageLayout::default();
+        for lines in &blocks {
+            let bounds = union(lines.iter().map(|l| l.bounds));
+            let block_size = lines
+                .iter()
+                .flat_map(|l| &l.glyphs)
+                .map(|g| g.size)
+                .fold(0.0, f32::max);
+            if bounds.y0 >= footer_top && block_size < body * 0.95 {
+                if let Some((label, first)) = footnote_label(&lines[0], body) {
+                    let rest = join(&lines[1..]);
+                    layout.blocks.push(LayoutBlock {
+                        kind: LayoutBlockKind::Footnote { label },
+                        text: [first, rest].join(" ").trim().to_owned(),
+                        bounds,
+                    });
+                    continue;
+                }
+            }
+
+            // Lines up to the next list marker belong together
+            let mut start = 0;
+            while start < lines.len() {
+                let marker = list_marker(&lines[start].text())
+                    .map(|(m, ordered, rest)| (m, ordered, rest.to_owned()));
+                let end = (start + 1..lines.len())
+                    .find(|&i| list_marker(&lines[i].text()).is_some())
+                    .unwrap_or(lines.len());
+                let part = &lines[start..end];
+                let bounds = union(part.iter().map(|l| l.bounds));
+                let block = match marker {
+                    Some((marker, ordered, first)) => LayoutBlock {
+                        kind: LayoutBlockKind::ListItem { marker, ordered },
+                        text: [first, join(&part[1..])].join(" ").trim().to_owned(),
+                        bounds,
+                    },
+                    None => LayoutBlock {
+                        kind: LayoutBlockKind::Paragraph,
+                        text: join(part),
+                        bounds,
+                    },
+                };
+                let index = layout.blocks.len();
+                layout.blocks.push(block);
+                for line in part {
+                    find_references(line, body, index, &mut layout.footnote_references);
+                }
+                start = end;
+            }
+        }
+
+        for reference in &mut layout.footnote_references {
+            reference.footnote = layout.blocks.iter().position(|b| {
+                matches!(&b.kind, LayoutBlockKind::Footnote { label } if *label == reference.label)
+            });
+        }
+        layout
+    }
+}
+
+/// Runs of small raised note label characters after some text on a line.
+fn find_references(line: &Line, body: f32, block: usize, refs: &mut Vec<FootnoteReference>) {
+    let baseline = line
+        .glyphs
+        .iter()
+        .filter(|g| g.size >= body * 0.95)
+        .map(|g| g.origin.y)
+        .fold(f32::NAN, f32::max);
+    if baseline.is_nan() {
+        return;
+    }
+    let is_mark = |g: &CharInfo| {
+        g.size < body * 0.85
+            && g.origin.y < baseline - body * 0.2
+            && (g.c.is_ascii_digit() || NOTE_SYMBOLS.contains(&g.c))
+    };
+    let mut i = 0;
+    while i < line.glyphs.len() {
+        if i == 0 || !is_mark(&line.glyphs[i]) {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < line.glyphs.len() && is_mark(&line.glyphs[i]) {
+            i += 1;
+        }
+        let glyphs = &line.glyphs[start..i];
+        let label: String = glyphs.iter().map(|g| g.c).collect();
+        if is_note_label(&label) {
+            let mut quad = glyphs[0].quad.clone();
+            quad.ur = glyphs[glyphs.len() - 1].quad.ur;
+            quad.lr = glyphs[glyphs.len() - 1].quad.lr;
+            refs.push(FootnoteReference {
+                label,
+                quad,
+                block,
+                footnote: None,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{is_note_label, list_marker, LayoutBlockKind};
+    use crate::{Document, TextPageOptions};
+
+    #[test]
+    fn test_list_marker() {
+        assert_eq!(
+            list_marker("• first item"),
+            Some(("•".to_owned(), false, "first item"))
+        );
+        assert_eq!(
+            list_marker("12. twelfth"),
+            Some(("12.".to_owned(), true, "twelfth"))
+        );
+        assert_eq!(
+            list_marker("(b) second"),
+            Some(("(b)".to_owned(), true, "second"))
+        );
+        assert_eq!(
+            list_marker("iv) fourth"),
+            Some(("iv)".to_owned(), true, "fourth"))
+        );
+        assert_eq!(list_marker("2024 was a year"), None);
+        assert_eq!(list_marker("Hello world"), None);
+        assert!(is_note_label("12") && is_note_label("†") && !is_note_label("a"));
+    }
+
+    #[test]
+    fn test_text_page_layout() {
+        let doc = Document::open("tests/files/dummy.pdf").unwrap();
+        let page0 = doc.load_page(0).unwrap();
+        let text_page = page0.to_text_page(TextPageOptions::empty()).unwrap();
+        let layout = text_page.layout();
+        assert_eq!(layout.blocks.len(), 1);
+        assert_eq!(layout.blocks[0].kind, LayoutBlockKind::Paragraph);
+        assert_eq!(layout.blocks[0].text, "Dummy PDF file");
+        assert!(layout.footnote_references.is_empty());
+    }
+}
@@ -2,16 +2,33 @@
 
 /// Error types
 #[rustfmt::skip] #[macro_use] pub mod error;
+/// Allocator hook used for all of MuPDF's memory
+pub mod allocator;
+/// Annotations pre-rendered into a separate layer
+pub mod annotation_layer;
+/// Documents driven from async code
+#[cfg(feature = "tokio")]
+pub mod async_document;
 /// Bitmaps used for creating halftoned versions of contone buffers, and saving out
 pub mod bitmap;
+/// Header, footer and page number detection
+pub mod boilerplate;
+/// Rendering in time slices for single threaded event loops
+pub mod budgeted_render;
 /// Dynamically allocated array of bytes
 pub mod buffer;
+/// Splitting document text into chunks for embedding
+pub mod chunk;
 /// Color params
 pub mod color_params;
 /// Colorspace
 pub mod colorspace;
+/// Visual and text comparison of pages
+pub mod compare;
 /// Context
 pub mod context;
+/// Sharing a document between threads
+pub mod context_pool;
 /// Provide two-way communication between application and library
 pub mod cookie;
 /// Destination
@@ -22,24 +39,44 @@ pub mod device;
 pub mod display_list;
 /// Common document operation interface
 pub mod document;
+/// Caching of display lists and text pages
+pub mod document_cache;
+/// Document formats implemented in Rust
+pub mod document_handler;
 /// Easy creation of new documents
 pub mod document_writer;
 /// File paths
 pub mod file_path;
 /// Font
 pub mod font;
+/// Variable font axes and named instances
+pub mod font_variation;
 /// Glyph
 pub mod glyph;
 /// Image
 pub mod image;
+/// Conversions to and from the geometry types of other crates
+mod interop;
+/// Layout analysis of structured text
+pub mod layout;
 /// Hyperlink
 pub mod link;
+/// Batch jobs described in JSON
+#[cfg(feature = "manifest")]
+pub mod manifest;
+/// Markdown output of structured text
+mod markdown;
 /// Matrix operations
 pub mod matrix;
+/// Forwarding of MuPDF warnings and errors
+pub mod message;
 /// Outline
 pub mod outline;
 /// Document page
 pub mod page;
+/// Parallel page rendering
+#[cfg(feature = "rayon")]
+mod parallel;
 /// Path type
 pub mod path;
 /// PDF interface
@@ -48,66 +85,143 @@ pub mod pdf;
 pub mod pixmap;
 /// Point type
 pub mod point;
+/// PostScript, PCL and PWG raster output for printers
+pub mod printer;
+/// Opening documents while their data is still arriving
+pub mod progressive;
 /// A representation for a region defined by 4 points
 pub mod quad;
+/// Recording drawing operations as typed commands
+pub mod record;
 /// Rectangle types
 pub mod rect;
+/// Typography settings for reflowable documents
+pub mod reflow;
+/// Page rendering options
+pub mod render;
+/// Clean-up and compression of scanned pages
+pub mod scan;
+/// Searching the text of whole documents
+pub mod search;
 /// Separations
 pub mod separations;
+/// Minimal OpenType table directory reader, for font metadata MuPDF doesn't expose
+mod sfnt;
 /// Shadings
 pub mod shade;
 /// Size type
 pub mod size;
+/// Counters of live handles and memory, for diagnostics
+pub mod stats;
 /// Stroke state
 pub mod stroke_state;
+/// SVG output options
+pub mod svg;
 
 /// System font loading
 #[cfg(feature = "system-fonts")]
 pub mod system_font;
+/// Table detection in structured text
+pub mod table;
 /// Text objects
 pub mod text;
+/// Persistent word indexes of document text
+pub mod text_index;
 /// Text page
 pub mod text_page;
+/// Streaming the text spans a page draws
+pub mod text_stream;
+/// Page thumbnails
+mod thumbnail;
+/// Multi-page TIFF output
+pub mod tiff;
+/// Partial re-rendering of pages from a recorded display list
+pub mod tile;
+/// XML parsing
+mod xml;
 
 /// Contains a special [`array::FzArray`] type which wraps an allocation from the `fz_calloc`
 /// allocation fn that mupdf uses internally. Ideally this will eventually be replaced with
 /// `Box<[_], A>` once the allocator api is stabilized.
 pub mod array;
 
+pub use allocator::MemoryUsage;
+pub use annotation_layer::AnnotationLayer;
 use array::FzArray;
+#[cfg(feature = "tokio")]
+pub use async_document::AsyncDocument;
 pub use bitmap::Bitmap;
+pub use boilerplate::{classify_blocks, BlockRole};
+pub use budgeted_render::{BudgetedRender, RenderStatus};
 pub use buffer::Buffer;
+pub use chunk::{ChunkOptions, ChunkSource, TextChunk};
 pub use color_params::{ColorParams, RenderingIntent};
 pub use colorspace::Colorspace;
+pub use compare::{diff_pages, DiffOptions, DiffResult, TextChange};
 pub(crate) use context::context;
-pub use context::Context;
+pub use context::{Context, StoreUsage};
+pub use context_pool::{ContextPool, PoolHandle};
 pub use cookie::Cookie;
 pub use destination::{Destination, DestinationKind};
-pub use device::{BlendMode, Device, Function, NativeDevice};
+pub use device::{
+    BlendMode, DefaultColorspaces, Device, DeviceHints, Function, NativeDevice, SkipContent,
+};
 pub use display_list::DisplayList;
-pub use document::{Document, MetadataName};
+pub use document::{Bookmark, Document, Location, MetadataName};
+pub use document_cache::{CacheOptions, CachedDocument};
+pub use document_handler::{CustomDocument, CustomPage, DocumentHandler};
 pub use document_writer::DocumentWriter;
 pub(crate) use error::ffi_error;
-pub use error::Error;
+pub use error::{Error, ErrorKind, MuPdfError};
 pub use file_path::FilePath;
-pub use font::{CjkFontOrdering, Font, SimpleFontEncoding, WriteMode};
+pub use font::{
+    CjkFontOrdering, ColorGlyphFormats, ColorGlyphPolicy, ColorGlyphRendering, Font,
+    SimpleFontEncoding, WriteMode,
+};
+pub use font_variation::{FontVariations, NamedInstance, VariationAxis};
 pub use glyph::Glyph;
 pub use image::Image;
-pub use link::Link;
+pub use layout::{FootnoteReference, LayoutBlock, LayoutBlockKind, PageLayout};
+pub use link::{Link, LinkAction, LinkDestination};
+#[cfg(feature = "manifest")]
+pub use manifest::{execute_manifest, execute_manifest_with_signer, ManifestReport};
 pub use matrix::Matrix;
+pub use message::{
+    clear_message_handler, set_message_handler, with_message_source, Message, MessageLevel,
+};
 pub use outline::Outline;
 pub use page::Page;
 pub use path::{Path, PathWalker};
 pub use pixmap::{ImageFormat, Pixmap};
 pub use point::Point;
+pub use printer::{PclOptions, PsOptions, PwgOptions};
+pub use progressive::ProgressiveDocument;
 pub use quad::Quad;
+pub use record::{
+    DrawColor, DrawCommand, PathSegment, RecordedCommands, RecordedGlyph, RecordedImage,
+    RecordedTextSpan, StrokeStyle,
+};
 pub use rect::{IRect, Rect};
+pub use reflow::ReflowStyle;
+pub use render::RenderOptions;
+pub use scan::{BackgroundOptions, MrcOptions};
+pub use search::{SearchHit, SearchIter, SearchOptions};
 pub use separations::Separations;
 pub use shade::Shade;
 pub use size::Size;
+pub use stats::ResourceStats;
 pub use stroke_state::{LineCap, LineJoin, StrokeState};
+pub use svg::{SvgOptions, SvgText, SvgUnits};
+pub use table::{Table, TableCell};
 pub use text::{Text, TextItem, TextSpan};
-pub use text_page::{TextBlock, TextChar, TextLine, TextPage, TextPageOptions};
+pub use text_index::{IndexedWord, PageWords, TextIndex};
+pub use text_page::{
+    CharPosition, QuadGranularity, StyledSpan, TextBlock, TextChar, TextCharFlags, TextLine,
+    TextPage, TextPageOptions, TextRenderMode, TextRun,
+};
+pub use text_stream::StreamedSpan;
+pub use tiff::{TiffCompression, TiffWriter};
+pub use tile::TileInfo;
 
 use core::{marker::PhantomData, ptr::NonNull};
 use zerocopy::{FromBytes, IntoBytes};
@@ -131,7 +245,7 @@ trait FFIWrapper {
 }
 
 macro_rules! unsafe_impl_ffi_wrapper {
-    ($struct:ident, $ffi_type:ident, $ffi_drop_fn:ident) => {
+    ($struct:ident, $ffi_type:ident, $ffi_drop_fn:ident $(, $counter:path)?) => {
         impl $crate::FFIWrapper for $struct {
             type FFIType = $ffi_type;
             fn as_ref(&self) -> &Self::FFIType {
@@ -151,6 +265,7 @@ macro_rules! unsafe_impl_ffi_wrapper {
                 let ptr = <Self as $crate::FFIWrapper>::as_ptr(&*self) as *mut _;
                 // SAFETY: Guaranteed by caller
                 unsafe { $ffi_drop_fn($crate::context(), ptr) }
+                $($counter.release();)?
             }
         }
     };
@@ -1,9 +1,12 @@
 use std::fmt;
 
-use crate::Rect;
+use mupdf_sys::*;
+
+use crate::{DestinationKind, Location, Rect};
 
 /// A list of interactive links on a page.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub struct Link {
     pub bounds: Rect,
     pub page: u32,
@@ -19,3 +22,152 @@ impl fmt::Display for Link {
         )
     }
 }
+
+/// A place in a document, and how to show it.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct LinkDestination {
+    pub location: Location,
+    /// How to show the page, with coordinates in the page space of [`Page::bounds`] rather than
+    /// in PDF user space.
+    ///
+    /// [`Page::bounds`]: crate::Page::bounds
+    pub kind: DestinationKind,
+}
+
+impl LinkDestination {
+    pub(crate) fn from_raw(dest: fz_link_dest) -> Option<Self> {
+        if dest.loc.page < 0 {
+            return None;
+        }
+        // Coordinates the destination leaves unspecified are NaN
+        let coord = |v: f32| (!v.is_nan()).then_some(v);
+        let value = |v: f32| coord(v).unwrap_or(0.0);
+        let kind = match dest.type_ {
+            fz_link_dest_type_FZ_LINK_DEST_FIT_B => DestinationKind::FitB,
+            fz_link_dest_type_FZ_LINK_DEST_FIT_H => DestinationKind::FitH { top: value(dest.y) },
+            fz_link_dest_type_FZ_LINK_DEST_FIT_BH => DestinationKind::FitBH { top: value(dest.y) },
+            fz_link_dest_type_FZ_LINK_DEST_FIT_V => DestinationKind::FitV {
+                left: value(dest.x),
+            },
+            fz_link_dest_type_FZ_LINK_DEST_FIT_BV => DestinationKind::FitBV {
+                left: value(dest.x),
+            },
+            fz_link_dest_type_FZ_LINK_DEST_FIT_R => DestinationKind::FitR {
+                left: dest.x,
+                bottom: dest.y + dest.h,
+                right: dest.x + dest.w,
+                top: dest.y,
+            },
+            fz_link_dest_type_FZ_LINK_DEST_XYZ => DestinationKind::XYZ {
+                left: coord(dest.x),
+                top: coord(dest.y),
+                zoom: coord(dest.zoom).filter(|&zoom| zoom > 0.0),
+            },
+            _ => DestinationKind::Fit,
+        };
+        Some(Self {
+            location: Location {
+                chapter: dest.loc.chapter,
+                page: dest.loc.page,
+            },
+            kind,
+        })
+    }
+
+    /// The area of the page to show, for [`DestinationKind::FitR`] destinations.
+    pub fn rect(&self) -> Option<Rect> {
+        match self.kind {
+            DestinationKind::FitR {
+                left,
+                bottom,
+                right,
+                top,
+            } => Some(Rect::new(left, top, right, bottom)),
+            _ => None,
+        }
+    }
+
+    /// The zoom factor in percent, for [`DestinationKind::XYZ`] destinations that set one.
+    pub fn zoom(&self) -> Option<f32> {
+        match self.kind {
+            DestinationKind::XYZ { zoom, .. } => zoom,
+            _ => None,
+        }
+    }
+}
+
+/// What following a link does.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub enum LinkAction {
+    /// Go to a place in the same document.
+    GoTo(LinkDestination),
+    /// Open a web page, write a mail, and the like.
+    Uri(String),
+    /// Open or launch another file. `destination` is the URI fragment naming the place to go
+    /// to in it, such as `page=3` or `nameddest=intro`.
+    File {
+        path: String,
+        destination: Option<String>,
+    },
+    /// Open a file embedded in the document under `name`.
+    EmbeddedFile {
+        name: String,
+        destination: Option<String>,
+    },
+    /// An internal link to a place the document doesn't have.
+    Unresolved,
+}
+
+/// Split a `file:` URI into its path and fragment.
+pub(crate) fn parse_file_uri(uri: &str) -> Option<(String, Option<String>)> {
+    let rest = uri.strip_prefix("file:")?;
+    let rest = rest.strip_prefix("//").unwrap_or(rest);
+    let (path, fragment) = match rest.split_once('#') {
+        Some((path, fragment)) => (path, Some(fragment.to_owned())),
+        None => (rest, None),
+    };
+    Some((percent_decode(path), fragment))
+}
+
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                out.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                out.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+#[cfg(test)]
+mod test {
+    use super::parse_file_uri;
+
+    #[test]
+    fn test_parse_file_uri() {
+        assert_eq!(
+            parse_file_uri("file:other%20doc.pdf#page=3"),
+            Some(("other doc.pdf".to_owned(), Some("page=3".to_owned())))
+        );
+        assert_eq!(
+            parse_file_uri("file:///tmp/a.pdf"),
+            Some(("/tmp/a.pdf".to_owned(), None))
+        );
+        assert_eq!(parse_file_uri("https://example.com"), None);
+    }
+}
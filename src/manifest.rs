@@ -0,0 +1,480 @@
+use std::collections::BTreeMap;
+
+use serde::Deserialize;
+
+use crate::pdf::{
+    PdfAnnotation, PdfDocument, PdfObject, PdfPage, PdfSigner, PdfWriteOptions, SignatureOptions,
+};
+use crate::{Error, Font, SimpleFontEncoding};
+
+/// The version of the manifest schema understood by [`execute_manifest`].
+pub const MANIFEST_VERSION: u32 = 1;
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Manifest {
+    version: u32,
+    operations: Vec<Operation>,
+}
+
+fn default_font_size() -> f32 {
+    48.0
+}
+
+fn default_opacity() -> f32 {
+    0.3
+}
+
+fn default_rotation() -> f32 {
+    45.0
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case", deny_unknown_fields)]
+enum Operation {
+    Open {
+        path: String,
+        #[serde(default)]
+        password: Option<String>,
+    },
+    Subset {
+        pages: Vec<i32>,
+    },
+    Watermark {
+        text: String,
+        #[serde(default = "default_font_size")]
+        font_size: f32,
+        #[serde(default = "default_opacity")]
+        opacity: f32,
+        #[serde(default = "default_rotation")]
+        rotation: f32,
+    },
+    FillFields {
+        fields: BTreeMap<String, String>,
+    },
+    Sign {
+        field: String,
+        signer: String,
+        #[serde(default)]
+        reason: Option<String>,
+        #[serde(default)]
+        location: Option<String>,
+    },
+    Save {
+        path: String,
+        #[serde(default)]
+        garbage: bool,
+        #[serde(default)]
+        compress: bool,
+    },
+}
+
+impl Operation {
+    fn name(&self) -> &'static str {
+        match self {
+            Operation::Open { .. } => "open",
+            Operation::Subset { .. } => "subset",
+            Operation::Watermark { .. } => "watermark",
+            Operation::FillFields { .. } => "fill_fields",
+            Operation::Sign { .. } => "sign",
+            Operation::Save { .. } => "save",
+        }
+    }
+}
+
+/// What [`execute_manifest`] did.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ManifestReport {
+    /// Number of operations run, which is all of them when the manifest succeeds.
+    pub executed: usize,
+    /// The files written by `save` operations.
+    pub saved: Vec<String>,
+    /// Names of the fields of `fill_fields` operations that don't exist or rejected the value.
+    pub unfilled_fields: Vec<String>,
+}
+
+fn invalid(msg: impl Into<String>) -> Error {
+    Error::InvalidManifest(msg.into())
+}
+
+/// Run the operations of a JSON manifest, in order, on one document at a time.
+///
+/// This gives programs written in other languages a single stable entry point to drive the
+/// crate with. A manifest looks like this, page numbers being 0-based:
+///
+/// ```json
+/// {
+///   "version": 1,
+///   "operations": [
+///     { "op": "open", "path": "in.pdf", "password": "secret" },
+///     { "op": "subset", "pages": [0, 2, 3] },
+///     { "op": "fill_fields", "fields": { "name": "Jane Doe", "address.city": "Ghent" } },
+///     { "op": "watermark", "text": "DRAFT", "font_size": 48, "opacity": 0.3, "rotation": 45 },
+///     { "op": "sign", "field": "approval", "signer": "legal", "reason": "Approved" },
+///     { "op": "save", "path": "out.pdf", "garbage": true, "compress": true }
+///   ]
+/// }
+/// ```
+///
+/// `open` replaces the current document, every other operation needs one to be open. `sign`
+/// needs a signer, see [`execute_manifest_with_signer`], and fails here. Operations before a
+/// failing one have taken effect, including saves.
+pub fn execute_manifest(json: &str) -> Result<ManifestReport, Error> {
+    execute(json, &mut |_, _, _| {
+        Err(invalid("signing needs execute_manifest_with_signer"))
+    })
+}
+
+/// As [`execute_manifest`], signing the signature fields of `sign` operations with signers
+/// created by `signer`, which is passed the `signer` of the operation, e.g. to pick a key.
+///
+/// As with [`PdfAnnotation::sign`], the signatures are created when the document is saved, so a
+/// `save` has to follow each `sign` before the document changes further.
+pub fn execute_manifest_with_signer<S, F>(
+    json: &str,
+    mut signer: F,
+) -> Result<ManifestReport, Error>
+where
+    S: PdfSigner + 'static,
+    F: FnMut(&str) -> Result<S, Error>,
+{
+    execute(json, &mut |name, widget, options| {
+        widget.sign(signer(name)?, options)
+    })
+}
+
+type Sign<'a> = dyn FnMut(&str, &mut PdfAnnotation, &SignatureOptions) -> Result<(), Error> + 'a;
+
+fn execute(json: &str, sign: &mut Sign) -> Result<ManifestReport, Error> {
+    let manifest: Manifest = serde_json::from_str(json)
+        .map_err(|err| invalid(format!("malformed manifest: {}", err)))?;
+    if manifest.version != MANIFEST_VERSION {
+        return Err(invalid(format!(
+            "unsupported manifest version {}, expected {}",
+            manifest.version, MANIFEST_VERSION
+        )));
+    }
+
+    let mut report = ManifestReport::default();
+    let mut doc: Option<PdfDocument> = None;
+    for (index, operation) in manifest.operations.iter().enumerate() {
+        run(operation, &mut doc, &mut report, sign).map_err(|err| {
+            invalid(format!(
+                "operation {} ({}) failed: {}",
+                index,
+                operation.name(),
+                err
+            ))
+        })?;
+        report.executed += 1;
+    }
+    Ok(report)
+}
+
+fn run(
+    operation: &Operation,
+    doc: &mut Option<PdfDocument>,
+    report: &mut ManifestReport,
+    sign: &mut Sign,
+) -> Result<(), Error> {
+    if let Operation::Open { path, password } = operation {
+        let mut opened = PdfDocument::open(path)?;
+        if opened.needs_password()? {
+            let password = password.as_deref().unwrap_or_default();
+            if !opened.authenticate(password)? {
+                return Err(invalid("wrong password"));
+            }
+        }
+        *doc = Some(opened);
+        return Ok(());
+    }
+
+    let doc = doc.as_mut().ok_or_else(|| invalid("no document is open"))?;
+    match operation {
+        Operation::Open { .. } => unreachable!(),
+        Operation::Subset { pages } => {
+            let count = doc.page_count()?;
+            if let Some(page) = pages.iter().find(|&&p| p < 0 || p >= count) {
+                return Err(invalid(format!(
+                    "page {} out of range, the document has {} pages",
+                    page, count
+                )));
+            }
+            doc.rearrange_pages(pages)
+        }
+        Operation::Watermark {
+            text,
+            font_size,
+            opacity,
+            rotation,
+        } => watermark(doc, text, *font_size, *opacity, *rotation),
+        Operation::FillFields { fields } => {
+            for (name, value) in fields {
+                if !doc.set_field_value(name, value)? {
+                    report.unfilled_fields.push(name.clone());
+                }
+            }
+            Ok(())
+        }
+        Operation::Sign {
+            field,
+            signer,
+            reason,
+            location,
+        } => {
+            let mut options = SignatureOptions::new();
+            if let Some(reason) = reason {
+                options.set_reason(reason);
+            }
+            if let Some(location) = location {
+                options.set_location(location);
+            }
+            // Widgets point back at their page, keep it loaded while signing
+            for page_no in 0..doc.page_count()? {
+                let page = PdfPage::try_from(doc.load_page(page_no)?)?;
+                for mut widget in page.widgets() {
+                    if widget.field_name()? == *field {
+                        return sign(signer.as_str(), &mut widget, &options);
+                    }
+                }
+            }
+            Err(invalid(format!("no field named {}", field)))
+        }
+        Operation::Save {
+            path,
+            garbage,
+            compress,
+        } => {
+            let mut options = PdfWriteOptions::default();
+            options.set_garbage(*garbage).set_compress(*compress);
+            doc.save_with_options(path, options)?;
+            report.saved.push(path.clone());
+            Ok(())
+        }
+    }
+}
+
+/// Escape `text` as a PDF string in the WinAnsi encoding of simple Latin fonts.
+fn pdf_string(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() + 2);
+    out.push('(');
+    for c in text.chars() {
+        match c {
+            '(' | ')' | '\\' => {
+                out.push('\\');
+                out.push(c);
+            }
+            ' '..='~' => out.push(c),
+            '\u{a0}'..='\u{ff}' => out.push_str(&format!("\\{:03o}", c as u32)),
+            _ => out.push('?'),
+        }
+    }
+    out.push(')');
+    out
+}
+
+/// Put a copy of the dictionary `parent` has or inherits under `key` into `parent` itself, or a new
+/// one if there is none, so adding to it can't affect other pages sharing the original.
+fn own_dict(doc: &mut PdfDocument, parent: &mut PdfObject, key: &str) -> Result<PdfObject, Error> {
+    let dict = match parent.get_dict_inheritable(key)? {
+        Some(dict) => match dict.resolve()? {
+            Some(dict) => dict.try_clone()?,
+            None => doc.new_dict()?,
+        },
+        None => doc.new_dict()?,
+    };
+    // `dict_put` takes its own reference, so what's added to `dict` later ends up in `parent`
+    parent.dict_put(key, unsafe { PdfObject::from_raw_keep_ref(dict.inner) })?;
+    Ok(dict)
+}
+
+/// Draw `text` across the middle of every page, on top of the existing content.
+fn watermark(
+    doc: &mut PdfDocument,
+    text: &str,
+    font_size: f32,
+    opacity: f32,
+    rotation: f32,
+) -> Result<(), Error> {
+    let font = Font::new("Helvetica")?;
+    let width: f32 = text
+        .chars()
+        .map(|c| {
+            let glyph = font.encode_character(c as i32)?;
+            font.advance_glyph(glyph)
+        })
+        .sum::<Result<f32, Error>>()?
+        * font_size;
+    let font_ref = doc.add_simple_font(&font, SimpleFontEncoding::Latin)?;
+    let gs = doc.new_object_from_str(&format!(
+        "<< /Type /ExtGState /CA {0} /ca {0} >>",
+        opacity.clamp(0.0, 1.0)
+    ))?;
+    let gs = doc.add_object(&gs)?;
+
+    let (sin, cos) = rotation.to_radians().sin_cos();
+    let wrap_start = doc.new_dict()?;
+    let mut wrap_start = doc.add_object(&wrap_start)?;
+    wrap_start.write_stream_string("q\n")?;
+    let wrap_end = doc.new_dict()?;
+    let mut wrap_end = doc.add_object(&wrap_end)?;
+    wrap_end.write_stream_string("Q\n")?;
+
+    for page_no in 0..doc.page_count()? {
+        let page = PdfPage::try_from(doc.load_page(page_no)?)?;
+        let media_box = page.media_box()?;
+        let mut page_obj = page.object();
+
+        let mut resources = own_dict(doc, &mut page_obj, "Resources")?;
+        own_dict(doc, &mut resources, "Font")?
+            .dict_put("MuPdfRsWatermarkFont", font_ref.try_clone()?)?;
+        own_dict(doc, &mut resources, "ExtGState")?
+            .dict_put("MuPdfRsWatermarkGs", gs.try_clone()?)?;
+
+        let (cx, cy) = (
+            (media_box.x0 + media_box.x1) / 2.0,
+            (media_box.y0 + media_box.y1) / 2.0,
+        );
+        let contents = doc.new_dict()?;
+        let mut contents = doc.add_object(&contents)?;
+        contents.write_stream_string(&format!(
+            "q /MuPdfRsWatermarkGs gs 0.5 g BT /MuPdfRsWatermarkFont {} Tf \
+             {} {} {} {} {} {} Tm {} {} Td {} Tj ET Q\n",
+            font_size,
+            cos,
+            sin,
+            -sin,
+            cos,
+            cx,
+            cy,
+            -width / 2.0,
+            -font_size / 3.0,
+            pdf_string(text)
+        ))?;
+
+        // Wrap the existing content in q/Q so its graphics state doesn't leak into ours
+        let mut array = doc.new_array()?;
+        array.array_push(wrap_start.try_clone()?)?;
+        match page_obj.get_dict("Contents")? {
+            Some(old) if old.is_array()? => {
+                for i in 0..old.len()? as i32 {
+                    if let Some(item) = old.get_array(i)? {
+                        array.array_push(item)?;
+                    }
+                }
+            }
+            Some(old) => array.array_push(old)?,
+            None => {}
+        }
+        array.array_push(wrap_end.try_clone()?)?;
+        array.array_push(contents)?;
+        page_obj.dict_put("Contents", array)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{execute_manifest, execute_manifest_with_signer, pdf_string};
+    use crate::pdf::{FieldKind, PdfDocument, PdfSigner};
+    use crate::{Document, Error, Rect, TextPageOptions};
+
+    #[test]
+    fn test_pdf_string() {
+        assert_eq!(pdf_string("a(b)\\"), "(a\\(b\\)\\\\)");
+        assert_eq!(pdf_string("café€"), "(caf\\351?)");
+    }
+
+    #[test]
+    fn test_execute_manifest() {
+        let out = std::env::temp_dir().join("mupdf-rs-manifest-test.pdf");
+        let manifest = serde_json::json!({
+            "version": 1,
+            "operations": [
+                { "op": "open", "path": "tests/files/dummy.pdf" },
+                { "op": "subset", "pages": [0, 0] },
+                { "op": "fill_fields", "fields": { "missing": "value" } },
+                { "op": "watermark", "text": "CONFIDENTIAL" },
+                { "op": "save", "path": out.to_str().unwrap(), "garbage": true },
+            ]
+        });
+        let report = execute_manifest(&manifest.to_string()).unwrap();
+        assert_eq!(report.executed, 5);
+        assert_eq!(report.unfilled_fields, vec!["missing".to_owned()]);
+
+        let doc = Document::open(&out).unwrap();
+        assert_eq!(doc.page_count().unwrap(), 2);
+        let text = doc
+            .load_page(1)
+            .unwrap()
+            .to_text_page(TextPageOptions::empty())
+            .unwrap()
+            .to_text()
+            .unwrap();
+        assert!(text.contains("Dummy PDF file"));
+        assert!(text.contains("CONFIDENTIAL"));
+        std::fs::remove_file(&out).unwrap();
+
+        let err =
+            execute_manifest(r#"{"version": 1, "operations": [{"op": "save", "path": "x.pdf"}]}"#)
+                .unwrap_err();
+        assert!(err.to_string().contains("no document is open"));
+        assert!(execute_manifest(r#"{"version": 2, "operations": []}"#).is_err());
+        assert!(execute_manifest(r#"{"version": 1, "operations": [{"op": "rotate"}]}"#).is_err());
+    }
+
+    struct FakeSigner(String);
+
+    impl PdfSigner for FakeSigner {
+        fn name(&self) -> String {
+            self.0.clone()
+        }
+
+        fn max_signature_size(&self) -> usize {
+            1024
+        }
+
+        fn sign(&mut self, _data: &[u8]) -> Result<Vec<u8>, Error> {
+            Ok(b"fake signature".to_vec())
+        }
+    }
+
+    #[test]
+    fn test_execute_manifest_sign() {
+        let dir = std::env::temp_dir();
+        let unsigned = dir.join("mupdf-rs-manifest-unsigned.pdf");
+        let signed = dir.join("mupdf-rs-manifest-signed.pdf");
+        let mut doc = PdfDocument::new();
+        let mut page = doc.new_page((300.0, 400.0)).unwrap();
+        page.create_field(
+            FieldKind::Signature,
+            "approval",
+            Rect::new(10.0, 10.0, 210.0, 60.0),
+        )
+        .unwrap();
+        doc.save(unsigned.to_str().unwrap()).unwrap();
+
+        let manifest = serde_json::json!({
+            "version": 1,
+            "operations": [
+                { "op": "open", "path": unsigned.to_str().unwrap() },
+                { "op": "sign", "field": "approval", "signer": "Jane Doe", "reason": "Approved" },
+                { "op": "save", "path": signed.to_str().unwrap() },
+            ]
+        })
+        .to_string();
+        let err = execute_manifest(&manifest).unwrap_err();
+        assert!(err.to_string().contains("operation 1 (sign)"));
+
+        let report =
+            execute_manifest_with_signer(&manifest, |name| Ok(FakeSigner(name.to_owned())))
+                .unwrap();
+        assert_eq!(report.executed, 3);
+        let doc = PdfDocument::open(signed.to_str().unwrap()).unwrap();
+        let signature = &doc.signatures().unwrap()[0];
+        assert!(signature.is_signed().unwrap());
+        assert_eq!(signature.reason().unwrap().as_deref(), Some("Approved"));
+        std::fs::remove_file(&unsigned).unwrap();
+        std::fs::remove_file(&signed).unwrap();
+    }
+}
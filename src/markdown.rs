@@ -0,0 +1,362 @@
+use std::collections::HashMap;
+
+use crate::layout::{body_size, list_marker, text_lines, CharInfo, Line};
+use crate::table::{find_tables, FoundTable};
+use crate::TextPage;
+
+/// How much larger than the body text a line must be to be a heading.
+const HEADING_RATIO: f32 = 1.2;
+
+/// Blocks with more lines than this are paragraphs, whatever their font size.
+const MAX_HEADING_LINES: usize = 3;
+
+#[derive(Debug, Clone, PartialEq)]
+struct Word {
+    text: String,
+    bold: bool,
+    italic: bool,
+}
+
+/// Split glyphs into words, a word being bold or italic if all of its glyphs are.
+fn words<'a>(glyphs: impl IntoIterator<Item = &'a CharInfo>) -> Vec<Word> {
+    let mut words = Vec::new();
+    let mut current: Option<Word> = None;
+    for g in glyphs {
+        if g.c.is_whitespace() {
+            words.extend(current.take());
+            continue;
+        }
+        match &mut current {
+            Some(word) => {
+                word.text.push(g.c);
+                word.bold &= g.bold;
+                word.italic &= g.italic;
+            }
+            None => {
+                current = Some(Word {
+                    text: g.c.to_string(),
+                    bold: g.bold,
+                    italic: g.italic,
+                })
+            }
+        }
+    }
+    words.extend(current);
+    words
+}
+
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '|') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Escape what would make a paragraph start a heading, list or rule.
+fn escape_start(text: String) -> String {
+    if text.starts_with(['#', '-', '+', '=']) {
+        return format!("\\{}", text);
+    }
+    let digits = text.chars().take_while(|c| c.is_ascii_digit()).count();
+    if digits > 0 && text[digits..].starts_with(['.', ')']) {
+        return format!("{}\\{}", &text[..digits], &text[digits..]);
+    }
+    text
+}
+
+/// Which styles are emphasis, as opposed to the style most of the page is set in.
+#[derive(Debug, Clone, Copy)]
+struct Emphasis {
+    bold: bool,
+    italic: bool,
+}
+
+impl Emphasis {
+    const NONE: Self = Self {
+        bold: false,
+        italic: false,
+    };
+}
+
+/// Join words with spaces, wrapping runs of bold and italic words in emphasis markers.
+fn render(words: &[Word], emphasis: Emphasis) -> String {
+    let mut out = String::new();
+    let mut start = 0;
+    while start < words.len() {
+        let style = |w: &Word| (w.bold && emphasis.bold, w.italic && emphasis.italic);
+        let (bold, italic) = style(&words[start]);
+        let end = (start..words.len())
+            .find(|&i| style(&words[i]) != (bold, italic))
+            .unwrap_or(words.len());
+        let mark = match (bold, italic) {
+            (true, true) => "***",
+            (true, false) => "**",
+            (false, true) => "_",
+            (false, false) => "",
+        };
+        let text = words[start..end]
+            .iter()
+            .map(|w| escape(&w.text))
+            .collect::<Vec<_>>()
+            .join(" ");
+        if !out.is_empty() {
+            out.push(' ');
+        }
+        out.push_str(mark);
+        out.push_str(&text);
+        out.push_str(mark);
+        start = end;
+    }
+    out
+}
+
+fn line_size(line: &Line) -> f32 {
+    line.glyphs
+        .iter()
+        .filter(|g| !g.c.is_whitespace())
+        .map(|g| g.size)
+        .fold(0.0, f32::max)
+}
+
+fn render_table(found: &FoundTable, emphasis: Emphasis) -> String {
+    let table = &found.table;
+    let mut out = String::new();
+    for (i, row) in found.glyphs.chunks(table.cols.max(1)).enumerate() {
+        out.push('|');
+        for cell in row {
+            let words: Vec<Word> = cell
+                .iter()
+                .flat_map(|line| words(line.iter().copied()))
+                .collect();
+            out.push(' ');
+            out.push_str(&render(&words, emphasis));
+            out.push_str(" |");
+        }
+        if i == 0 {
+            out.push_str("\n|");
+            out.push_str(&" --- |".repeat(table.cols));
+        }
+        if i + 1 < table.rows {
+            out.push('\n');
+        }
+    }
+    out
+}
+
+enum Element {
+    Block(String),
+    ListItem(String),
+}
+
+impl TextPage {
+    /// Convert the text of the page to Markdown, for feeding it to search indexes or language
+    /// models.
+    ///
+    /// Headings, lists, emphasis and tables are recovered heuristically: headings are short
+    /// blocks set larger than the body text, their level following the font size, lists are
+    /// recognized by their bullets and numbering, emphasis comes from bold and italic fonts,
+    /// and tables are found as by [`TextPage::tables`]. Other blocks become paragraphs.
+    pub fn to_markdown(&self) -> String {
+        let blocks = text_lines(self);
+        let body = body_size(&blocks);
+        let glyphs: Vec<&CharInfo> = blocks
+            .iter()
+            .flatten()
+            .flat_map(|l| &l.glyphs)
+            .filter(|g| !g.c.is_whitespace())
+            .collect();
+        // Nothing is emphasized on a page set in bold
+        let emphasis = Emphasis {
+            bold: glyphs.iter().filter(|g| g.bold).count() * 2 <= glyphs.len(),
+            italic: glyphs.iter().filter(|g| g.italic).count() * 2 <= glyphs.len(),
+        };
+
+        let mut heading_sizes: Vec<i32> = blocks
+            .iter()
+            .flatten()
+            .map(line_size)
+            .filter(|&size| size >= body * HEADING_RATIO)
+            .map(|size| (size * 2.0).round() as i32)
+            .collect();
+        heading_sizes.sort_unstable_by(|a, b| b.cmp(a));
+        heading_sizes.dedup();
+
+        let tables = find_tables(self, &blocks, body);
+        let mut table_of = HashMap::new();
+        for (t, table) in tables.iter().enumerate() {
+            for &line in &table.lines {
+                table_of.insert(line, t);
+            }
+        }
+
+        let mut elements = Vec::new();
+        let mut emitted = vec![false; tables.len()];
+        for (b, lines) in blocks.iter().enumerate() {
+            let mut start = 0;
+            while start < lines.len() {
+                if let Some(&t) = table_of.get(&(b, start)) {
+                    if !emitted[t] {
+                        elements.push(Element::Block(render_table(&tables[t], emphasis)));
+                        emitted[t] = true;
+                    }
+                    start += 1;
+                    continue;
+                }
+                let end = (start..lines.len())
+                    .find(|&l| table_of.contains_key(&(b, l)))
+                    .unwrap_or(lines.len());
+                text_elements(
+                    &lines[start..end],
+                    body,
+                    &heading_sizes,
+                    emphasis,
+                    &mut elements,
+                );
+                start = end;
+            }
+        }
+
+        let mut out = String::new();
+        for (i, element) in elements.iter().enumerate() {
+            let text = match element {
+                Element::Block(text) | Element::ListItem(text) => text,
+            };
+            if i > 0 {
+                let list = matches!(
+                    (&elements[i - 1], element),
+                    (Element::ListItem(_), Element::ListItem(_))
+                );
+                out.push_str(if list { "\n" } else { "\n\n" });
+            }
+            out.push_str(text);
+        }
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        out
+    }
+}
+
+/// Turn lines of one block into a heading, or list items and paragraphs.
+fn text_elements(
+    lines: &[Line],
+    body: f32,
+    heading_sizes: &[i32],
+    emphasis: Emphasis,
+    out: &mut Vec<Element>,
+) {
+    if lines.len() <= MAX_HEADING_LINES
+        && lines.iter().all(|l| line_size(l) >= body * HEADING_RATIO)
+    {
+        let size = lines.iter().map(line_size).fold(0.0, f32::max);
+        let key = (size * 2.0).round() as i32;
+        let level = heading_sizes.iter().position(|&s| s == key).unwrap_or(0) + 1;
+        let words = words(lines.iter().flat_map(|l| &l.glyphs));
+        out.push(Element::Block(format!(
+            "{} {}",
+            "#".repeat(level.min(6)),
+            render(&words, Emphasis::NONE)
+        )));
+        return;
+    }
+
+    // Lines up to the next list marker belong together, as in `TextPage::layout`
+    let mut start = 0;
+    while start < lines.len() {
+        let marker = list_marker(&lines[start].text()).map(|(m, ordered, _)| (m, ordered));
+        let end = (start + 1..lines.len())
+            .find(|&i| list_marker(&lines[i].text()).is_some())
+            .unwrap_or(lines.len());
+        let words = words(lines[start..end].iter().flat_map(|l| &l.glyphs));
+        match marker {
+            Some((marker, ordered)) => {
+                // The marker is the first word
+                let text = render(&words[1..], emphasis);
+                let number = marker.trim_end_matches(['.', ')']);
+                let item = if ordered && number.chars().all(|c| c.is_ascii_digit()) {
+                    format!("{}. {}", number, text)
+                } else if ordered {
+                    format!("- {} {}", escape(&marker), text)
+                } else {
+                    format!("- {}", text)
+                };
+                out.push(Element::ListItem(item));
+            }
+            None => out.push(Element::Block(escape_start(render(&words, emphasis)))),
+        }
+        start = end;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{escape_start, render, Emphasis, Word};
+    use crate::pdf::{PageOptions, PdfDocument};
+    use crate::{Document, TextPageOptions};
+
+    fn word(text: &str, bold: bool, italic: bool) -> Word {
+        Word {
+            text: text.to_owned(),
+            bold,
+            italic,
+        }
+    }
+
+    #[test]
+    fn test_render_emphasis() {
+        let words = [
+            word("plain", false, false),
+            word("very", true, false),
+            word("bold", true, false),
+            word("a_b", false, true),
+            word("both", true, true),
+        ];
+        let all = Emphasis {
+            bold: true,
+            italic: true,
+        };
+        assert_eq!(
+            render(&words, all),
+            "plain **very bold** _a\\_b_ ***both***"
+        );
+        let italic = Emphasis {
+            bold: false,
+            italic: true,
+        };
+        assert_eq!(render(&words, italic), "plain very bold _a\\_b both_");
+        assert_eq!(render(&words, Emphasis::NONE), "plain very bold a\\_b both");
+        assert_eq!(escape_start("# not".to_owned()), "\\# not");
+        assert_eq!(escape_start("2024. A year".to_owned()), "2024\\. A year");
+    }
+
+    #[test]
+    fn test_text_page_to_markdown() {
+        let doc = Document::open("tests/files/dummy.pdf").unwrap();
+        let page = doc.load_page(0).unwrap();
+        let text_page = page.to_text_page(TextPageOptions::empty()).unwrap();
+        assert_eq!(text_page.to_markdown(), "Dummy PDF file\n");
+
+        let html = "<h1>Annual report</h1>\
+            <p>Revenue <b>grew</b> this year.</p>\
+            <table>\
+            <tr><td>Item</td><td>Amount</td></tr>\
+            <tr><td><i>Sales</i></td><td>120</td></tr>\
+            <tr><td>Costs</td><td>80</td></tr>\
+            </table>";
+        let doc =
+            PdfDocument::from_html(html, "td { padding: 0 3em }", &PageOptions::new()).unwrap();
+        let markdown = doc
+            .load_page(0)
+            .unwrap()
+            .to_text_page(TextPageOptions::empty())
+            .unwrap()
+            .to_markdown();
+        assert!(markdown.starts_with("# Annual report\n\n"));
+        assert!(markdown.contains("Revenue **grew** this year."));
+        assert!(markdown.contains("| Item | Amount |\n| --- | --- |\n| _Sales_ | 120 |"));
+    }
+}
@@ -1,9 +1,11 @@
 use std::f32::consts::PI;
+use std::ops::{Mul, MulAssign};
 
 use mupdf_sys::*;
 
 /// A row-major 3x3 matrix used for representing transformations of coordinates
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub struct Matrix {
     pub a: f32,
     pub b: f32,
@@ -143,6 +145,27 @@ impl Matrix {
     pub fn expansion(&self) -> f32 {
         (self.a * self.d - self.b * self.c).abs().sqrt()
     }
+
+    /// The angle in degrees the matrix rotates the x axis by, clockwise in page coordinates.
+    /// Together with [`Matrix::expansion`] this decomposes rotations with uniform scaling.
+    pub fn rotation(&self) -> f32 {
+        self.b.atan2(self.a).to_degrees()
+    }
+
+    /// Whether the matrix maps axis aligned rectangles to axis aligned rectangles, i.e. rotates
+    /// by a multiple of 90 degrees at most.
+    pub fn is_rectilinear(&self) -> bool {
+        (self.b.abs() < f32::EPSILON && self.c.abs() < f32::EPSILON)
+            || (self.a.abs() < f32::EPSILON && self.d.abs() < f32::EPSILON)
+    }
+
+    /// The inverse transformation, `None` if the matrix is degenerate, e.g. scales by zero.
+    pub fn invert(&self) -> Option<Matrix> {
+        let mut inverse = fz_matrix::from(Matrix::IDENTITY);
+        // Returns 1 for degenerate matrices
+        let degenerate = unsafe { fz_try_invert_matrix(&mut inverse, self.into()) };
+        (degenerate == 0).then(|| inverse.into())
+    }
 }
 
 impl Default for Matrix {
@@ -158,6 +181,22 @@ impl Default for Matrix {
     }
 }
 
+/// `a * b` applies `a`, then `b`, like [`Matrix::concat`].
+impl Mul for Matrix {
+    type Output = Matrix;
+
+    fn mul(mut self, rhs: Matrix) -> Matrix {
+        self.concat(rhs);
+        self
+    }
+}
+
+impl MulAssign for Matrix {
+    fn mul_assign(&mut self, rhs: Matrix) {
+        self.concat(rhs);
+    }
+}
+
 impl From<fz_matrix> for Matrix {
     fn from(m: fz_matrix) -> Self {
         let fz_matrix { a, b, c, d, e, f } = m;
@@ -0,0 +1,181 @@
+use std::cell::RefCell;
+use std::ffi::{c_char, c_void, CStr};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, RwLock};
+
+/// Severity of a message reported by MuPDF.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageLevel {
+    Warning,
+    Error,
+}
+
+/// A warning or error reported by MuPDF.
+#[derive(Debug, Clone, Copy)]
+pub struct Message<'a> {
+    pub level: MessageLevel,
+    pub text: &'a str,
+    /// What the reporting thread was working on, as set by [`with_message_source`], e.g. the
+    /// path of the document being opened.
+    pub source: Option<&'a str>,
+}
+
+type Handler = Arc<dyn Fn(&Message) + Send + Sync>;
+
+static HANDLER: RwLock<Option<Handler>> = RwLock::new(None);
+
+/// Keep at most this many warnings to attach to an error
+const MAX_WARNINGS: usize = 16;
+
+thread_local! {
+    static WARNINGS: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+    static SOURCE: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+pub(crate) fn clear_warnings() {
+    WARNINGS.with(|w| w.borrow_mut().clear());
+}
+
+pub(crate) fn take_warnings() -> Vec<String> {
+    WARNINGS.with(|w| w.take())
+}
+
+/// Send MuPDF warnings and errors to `handler` instead of the default destination.
+///
+/// By default messages are forwarded to the `log` or `tracing` crate under the `mupdf` target
+/// when the corresponding feature is enabled, and dropped otherwise.
+///
+/// The handler may be called from any thread, and may itself set or clear the handler.
+pub fn set_message_handler<F>(handler: F)
+where
+    F: Fn(&Message) + Send + Sync + 'static,
+{
+    *HANDLER.write().unwrap() = Some(Arc::new(handler));
+}
+
+/// Restore the default message destination.
+pub fn clear_message_handler() {
+    *HANDLER.write().unwrap() = None;
+}
+
+/// Tag the messages reported on this thread while `f` runs with `source`, so a handler serving
+/// several documents can tell them apart. Calls can be nested, the innermost source wins.
+pub fn with_message_source<R>(source: &str, f: impl FnOnce() -> R) -> R {
+    struct Restore(Option<String>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            SOURCE.with(|s| *s.borrow_mut() = self.0.take());
+        }
+    }
+
+    let _restore = Restore(SOURCE.with(|s| s.replace(Some(source.to_owned()))));
+    f()
+}
+
+fn dispatch(message: &Message) {
+    // Don't hold the lock while the handler runs, it may want to replace itself
+    let handler = HANDLER.read().unwrap().clone();
+    if let Some(handler) = handler {
+        handler(message);
+        return;
+    }
+    #[cfg(feature = "log")]
+    {
+        let source = message
+            .source
+            .map(|s| format!("{}: ", s))
+            .unwrap_or_default();
+        match message.level {
+            MessageLevel::Warning => log::warn!(target: "mupdf", "{}{}", source, message.text),
+            MessageLevel::Error => log::error!(target: "mupdf", "{}{}", source, message.text),
+        }
+    }
+    #[cfg(feature = "tracing")]
+    match message.level {
+        MessageLevel::Warning => {
+            tracing::warn!(target: "mupdf", source = message.source, "{}", message.text)
+        }
+        MessageLevel::Error => {
+            tracing::error!(target: "mupdf", source = message.source, "{}", message.text)
+        }
+    }
+}
+
+unsafe fn report(level: MessageLevel, message: *const c_char) {
+    if message.is_null() {
+        return;
+    }
+    let message = unsafe { CStr::from_ptr(message) }.to_string_lossy();
+    if level == MessageLevel::Warning {
+        WARNINGS.with(|w| {
+            let mut w = w.borrow_mut();
+            if w.len() < MAX_WARNINGS {
+                w.push(message.to_string());
+            }
+        });
+    }
+    // Unwinding into MuPDF would abort the process
+    let _ = panic::catch_unwind(AssertUnwindSafe(|| {
+        SOURCE.with(|source| {
+            dispatch(&Message {
+                level,
+                text: &message,
+                source: source.borrow().as_deref(),
+            })
+        })
+    }));
+}
+
+pub(crate) unsafe extern "C" fn warning_callback(_user: *mut c_void, message: *const c_char) {
+    unsafe { report(MessageLevel::Warning, message) }
+}
+
+pub(crate) unsafe extern "C" fn error_callback(_user: *mut c_void, message: *const c_char) {
+    unsafe { report(MessageLevel::Error, message) }
+}
+
+#[cfg(test)]
+mod test {
+    use std::ffi::CString;
+    use std::sync::{Arc, Mutex};
+
+    use mupdf_sys::{fz_flush_warnings, fz_warn};
+
+    use super::{set_message_handler, with_message_source, MessageLevel, HANDLER};
+    use crate::context;
+
+    /// Put back whatever handler was installed before the test, even if it fails
+    struct RestoreHandler(Option<super::Handler>);
+
+    impl Drop for RestoreHandler {
+        fn drop(&mut self) {
+            *HANDLER.write().unwrap() = self.0.take();
+        }
+    }
+
+    #[test]
+    fn test_message_handler() {
+        let _restore = RestoreHandler(HANDLER.read().unwrap().clone());
+        let messages = Arc::new(Mutex::new(Vec::new()));
+        let sink = messages.clone();
+        set_message_handler(move |msg| {
+            sink.lock().unwrap().push((
+                msg.level,
+                msg.text.to_owned(),
+                msg.source.map(str::to_owned),
+            ))
+        });
+        let fmt = CString::new("%s").unwrap();
+        let msg = CString::new("test warning").unwrap();
+        with_message_source("test.pdf", || unsafe {
+            fz_warn(context(), fmt.as_ptr(), msg.as_ptr());
+            fz_flush_warnings(context());
+        });
+        assert!(messages.lock().unwrap().contains(&(
+            MessageLevel::Warning,
+            "test warning".to_owned(),
+            Some("test.pdf".to_owned())
+        )));
+    }
+}
@@ -1,5 +1,9 @@
+use crate::DestinationKind;
+
 /// a tree of the outline of a document (also known as table of contents).
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[non_exhaustive]
 pub struct Outline {
     pub title: String,
     pub uri: Option<String>,
@@ -7,4 +11,23 @@ pub struct Outline {
     pub down: Vec<Outline>,
     pub x: f32,
     pub y: f32,
+    /// How to show the page, in the page space of [`Page::bounds`](crate::Page::bounds). When
+    /// writing outlines, `None` shows `x`, `y` at the top left of the window.
+    pub kind: Option<DestinationKind>,
+}
+
+impl Outline {
+    /// An entry titled `title` going nowhere, without children. Set the public fields to
+    /// choose its destination and children.
+    pub fn new(title: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            uri: None,
+            page: None,
+            down: Vec::new(),
+            x: 0.0,
+            y: 0.0,
+            kind: None,
+        }
+    }
 }
@@ -1,14 +1,19 @@
 use std::ffi::{c_int, CStr, CString};
-use std::io::Read;
+use std::io::{self, Read, Write};
+use std::mem;
 use std::ptr::{self, NonNull};
+use std::sync::Arc;
 
 use mupdf_sys::*;
 
 use crate::array::FzArray;
+use crate::printer::{self, PclOptions, PsOptions, PwgOptions};
+use crate::text_page::TextBlockType;
 use crate::{
-    context, rust_vec_from_ffi_ptr, unsafe_impl_ffi_wrapper, Buffer, Colorspace, Cookie, Device,
-    DisplayList, Error, FFIWrapper, Link, Matrix, Pixmap, Quad, Rect, Separations, TextPage,
-    TextPageOptions,
+    context, rust_vec_from_ffi_ptr, stats, unsafe_impl_ffi_wrapper, Buffer, ColorParams,
+    Colorspace, Cookie, DefaultColorspaces, Device, DisplayList, Error, FFIWrapper, IRect, Link,
+    Matrix, Pixmap, Quad, Rect, RenderOptions, RenderingIntent, Separations, Size, TextPage,
+    TextPageOptions, TileInfo,
 };
 
 #[derive(Debug)]
@@ -18,7 +23,7 @@ pub struct Page {
     pub(crate) doc: *mut fz_document,
 }
 
-unsafe_impl_ffi_wrapper!(Page, fz_page, fz_drop_page);
+unsafe_impl_ffi_wrapper!(Page, fz_page, fz_drop_page, stats::PAGES);
 
 impl Page {
     /// # Safety
@@ -36,6 +41,7 @@ impl Page {
     ///
     /// * `nonnull` must point to a valid, well-aligned instance of [`fz_page`]
     pub(crate) unsafe fn from_non_null(nonnull: NonNull<fz_page>) -> Self {
+        stats::PAGES.acquire();
         Self {
             inner: nonnull,
             doc: (*nonnull.as_ptr()).doc,
@@ -66,12 +72,243 @@ impl Page {
         .map(|inner| unsafe { Pixmap::from_raw(inner) })
     }
 
+    /// Like [`Page::to_pixmap`], rendering only the part of the page inside `clip`, in device
+    /// coordinates, e.g. the visible part of a zoomed in page in a viewer. Content outside
+    /// `clip` is skipped instead of drawn and thrown away.
+    ///
+    /// The pixmap covers the intersection of `clip` and the page, and is empty if they don't
+    /// overlap.
+    pub fn to_pixmap_clipped(
+        &self,
+        ctm: &Matrix,
+        clip: IRect,
+        cs: &Colorspace,
+        alpha: bool,
+        show_extras: bool,
+    ) -> Result<Pixmap, Error> {
+        let mut area: IRect =
+            unsafe { fz_round_rect(fz_transform_rect(self.bounds()?.into(), ctm.into())) }.into();
+        area.intersect(clip);
+        let mut pixmap = Pixmap::new_with_rect(cs, area, alpha)?;
+        if alpha {
+            pixmap.clear()?;
+        } else {
+            pixmap.clear_with(255)?;
+        }
+        if !area.is_empty() {
+            let device = Device::from_pixmap(&pixmap)?;
+            if show_extras {
+                self.run(&device, ctm)?;
+            } else {
+                self.run_contents(&device, ctm)?;
+            }
+        }
+        Ok(pixmap)
+    }
+
+    /// Render the page with the resolution, rotation, colorspace, area and content of
+    /// `options`, e.g. at 150 dpi with `RenderOptions::new().set_dpi(150.0)`.
+    pub fn render(&self, options: &RenderOptions) -> Result<Pixmap, Error> {
+        let ctm = options.matrix();
+        let pixmap = options.blank_pixmap(self.bounds()?)?;
+        if pixmap.rect().is_empty() {
+            return Ok(pixmap);
+        }
+        options.with_aa_level(|| {
+            let target = Device::from_pixmap(&pixmap)?;
+            let device = target.with_skipped(options.skip())?;
+            if options.annotations() {
+                self.run(&device, &ctm)
+            } else {
+                self.run_contents(&device, &ctm)
+            }
+        })?;
+        Ok(pixmap)
+    }
+
+    /// The size of a unit of PDF user space in 1/72 inch, see
+    /// [`PdfPage::user_unit`](crate::pdf::PdfPage::user_unit). 1 for documents other than PDF.
+    pub fn user_unit(&self) -> Result<f32, Error> {
+        unsafe { ffi_try!(mupdf_page_user_unit(context(), self.as_ptr() as *mut _)) }
+    }
+
+    /// A digest of the objects a PDF page is drawn from, which changes whenever the page is
+    /// modified. `None` for documents other than PDF.
+    pub(crate) fn pdf_digest(&self) -> Result<Option<[u8; 16]>, Error> {
+        let mut digest = [0; 16];
+        let is_pdf = unsafe {
+            ffi_try!(mupdf_page_digest(
+                context(),
+                self.as_ptr() as *mut _,
+                digest.as_mut_ptr()
+            ))
+        }?;
+        Ok(is_pdf.then_some(digest))
+    }
+
+    /// The size of the page as printed, in 1/72 inch, taking /UserUnit into account. This is
+    /// the size of [`Page::bounds`], which MuPDF scales by the user unit.
+    pub fn physical_size(&self) -> Result<Size, Error> {
+        Ok(self.bounds()?.size())
+    }
+
+    /// The transform from PDF user space, with the origin at the bottom left of the MediaBox
+    /// and y going up, to the space of [`Page::bounds`], with the origin at the top left of the
+    /// page as shown. It rotates by /Rotate and scales by /UserUnit, so page space is in
+    /// 1/72 inch whatever the user unit. The identity for documents other than PDF.
+    pub fn pdf_to_page(&self) -> Result<Matrix, Error> {
+        unsafe { ffi_try!(mupdf_page_pdf_transform(context(), self.as_ptr() as *mut _)) }
+            .map(Into::into)
+    }
+
+    /// The transform from PDF user space to the pixels of the pixmap [`Page::render`] renders
+    /// with `options`, with (0, 0) at its top left corner. Use it to place things found in the
+    /// content of the page, such as annotation rectangles, over a rendering.
+    pub fn page_to_device(&self, options: &RenderOptions) -> Result<Matrix, Error> {
+        let area = options.area(self.bounds()?);
+        Ok(self.pdf_to_page()?
+            * options.matrix()
+            * Matrix::new_translate(-area.x0 as f32, -area.y0 as f32))
+    }
+
+    /// The transform from the pixels of a rendering with `options` to PDF user space, e.g. to
+    /// find where in the page a click on the rendering went. The inverse of
+    /// [`Page::page_to_device`]. Fails with [`Error::InvalidInput`] for a zoom of 0, which
+    /// renders nothing.
+    pub fn device_to_page(&self, options: &RenderOptions) -> Result<Matrix, Error> {
+        self.page_to_device(options)?.invert().ok_or_else(|| {
+            Error::InvalidInput("render options with a zoom of 0 can't be inverted".to_owned())
+        })
+    }
+
+    /// Render the contents of the page without its annotations and form widgets, to composite
+    /// them over it from [`Page::render_annotations_only`] or [`PdfAnnotation::to_pixmap`].
+    ///
+    /// [`PdfAnnotation::to_pixmap`]: crate::pdf::PdfAnnotation::to_pixmap
+    pub fn render_contents_only(
+        &self,
+        ctm: &Matrix,
+        cs: &Colorspace,
+        alpha: bool,
+    ) -> Result<Pixmap, Error> {
+        self.to_pixmap(ctm, cs, alpha, false)
+    }
+
+    /// Render the annotations and form widgets of the page into a transparent pixmap covering
+    /// the same area as [`Page::render_contents_only`].
+    pub fn render_annotations_only(&self, ctm: &Matrix, cs: &Colorspace) -> Result<Pixmap, Error> {
+        let bbox: IRect =
+            unsafe { fz_round_rect(fz_transform_rect(self.bounds()?.into(), ctm.into())) }.into();
+        let mut pixmap = Pixmap::new_with_rect(cs, bbox, true)?;
+        pixmap.clear()?;
+        {
+            let device = Device::from_pixmap(&pixmap)?;
+            self.run_annotations(&device, ctm)?;
+            self.run_widgets(&device, ctm)?;
+        }
+        Ok(pixmap)
+    }
+
+    /// Whether the page sets overprint in any of its graphics states.
+    pub fn uses_overprint(&self) -> bool {
+        unsafe { fz_page_uses_overprint(context(), self.as_ptr() as *mut _) != 0 }
+    }
+
+    /// Like [`Page::to_pixmap`], simulating overprint when `overprint` is set, so that inks set
+    /// to overprint mix with the inks below them instead of knocking them out, as they would on
+    /// a press. Spot colors are mixed through their process equivalents.
+    ///
+    /// Simulation renders through a CMYK buffer and is slower, and only has an effect on pages
+    /// that [use overprint](Page::uses_overprint) or spot colors.
+    pub fn to_pixmap_with_overprint(
+        &self,
+        ctm: &Matrix,
+        cs: &Colorspace,
+        alpha: bool,
+        show_extras: bool,
+        overprint: bool,
+    ) -> Result<Pixmap, Error> {
+        if !overprint {
+            return self.to_pixmap(ctm, cs, alpha, show_extras);
+        }
+        unsafe {
+            ffi_try!(mupdf_page_to_pixmap_with_overprint(
+                context(),
+                self.as_ptr() as *mut _,
+                ctm.into(),
+                cs.inner,
+                alpha,
+                show_extras
+            ))
+        }
+        .map(|inner| unsafe { Pixmap::from_raw(inner) })
+    }
+
+    /// Soft proof the page: render it in the colorspace of an output device such as a printer,
+    /// `proof`, and convert the result to the colorspace of the screen, `display`, to preview
+    /// on screen how the page will print. Colors the output device can't reproduce come out as
+    /// it would print them.
+    ///
+    /// `intent` is the rendering intent used to map the output device colors to the display.
+    /// [`RenderingIntent::AbsoluteColorimetric`] also simulates the color of the paper.
+    pub fn to_pixmap_proofed(
+        &self,
+        ctm: &Matrix,
+        display: &Colorspace,
+        proof: &Colorspace,
+        intent: RenderingIntent,
+        alpha: bool,
+        show_extras: bool,
+    ) -> Result<Pixmap, Error> {
+        let params = ColorParams::new(intent, true, false, false);
+        unsafe {
+            ffi_try!(mupdf_page_to_pixmap_proofed(
+                context(),
+                self.as_ptr() as *mut _,
+                ctm.into(),
+                display.inner,
+                proof.inner,
+                params.into(),
+                alpha,
+                show_extras
+            ))
+        }
+        .map(|inner| unsafe { Pixmap::from_raw(inner) })
+    }
+
+    /// Like [`Page::to_pixmap`], interpreting device gray, RGB and CMYK colors in the
+    /// colorspaces of `defaults` instead of those the document and page set, such as an output
+    /// intent or `/DefaultRGB` resource.
+    pub fn to_pixmap_with_default_colorspaces(
+        &self,
+        ctm: &Matrix,
+        cs: &Colorspace,
+        alpha: bool,
+        show_extras: bool,
+        defaults: &DefaultColorspaces,
+    ) -> Result<Pixmap, Error> {
+        unsafe {
+            ffi_try!(mupdf_page_to_pixmap_with_default_colorspaces(
+                context(),
+                self.as_ptr() as *mut _,
+                ctm.into(),
+                cs.inner,
+                alpha,
+                show_extras,
+                defaults.inner
+            ))
+        }
+        .map(|inner| unsafe { Pixmap::from_raw(inner) })
+    }
+
     pub fn to_svg(&self, ctm: &Matrix) -> Result<String, Error> {
         let inner = unsafe {
             ffi_try!(mupdf_page_to_svg(
                 context(),
                 self.as_ptr() as *mut _,
                 ctm.into(),
+                FZ_SVG_TEXT_AS_PATH as c_int,
+                1,
                 ptr::null_mut()
             ))
         }?;
@@ -87,6 +324,8 @@ impl Page {
                 context(),
                 self.as_ptr() as *mut _,
                 ctm.into(),
+                FZ_SVG_TEXT_AS_PATH as c_int,
+                1,
                 cookie.inner
             ))
         }?;
@@ -118,6 +357,74 @@ impl Page {
         .map(|inner| unsafe { DisplayList::from_raw(inner) })
     }
 
+    /// A digest of the text and coarse layout of the page, equal for pages that look the same even
+    /// if they were produced differently, e.g. to find duplicate pages across documents.
+    ///
+    /// Whitespace is normalized and positions are snapped to a 32x32 grid over the page, so small
+    /// shifts and font changes don't change the hash. Images are hashed by their decoded pixels.
+    pub fn content_hash(&self) -> Result<[u8; 16], Error> {
+        const GRID: f32 = 32.0;
+        let bounds = self.bounds()?;
+        let cell = |v: f32, origin: f32, size: f32| {
+            if size <= 0.0 {
+                return 0;
+            }
+            ((v - origin) / size * GRID).floor().clamp(0.0, GRID) as u8
+        };
+        let cells = |r: Rect| {
+            [
+                cell(r.x0, bounds.x0, bounds.width()),
+                cell(r.y0, bounds.y0, bounds.height()),
+            ]
+        };
+
+        let text_page = self.to_text_page(TextPageOptions::PRESERVE_IMAGES)?;
+        let mut md5: fz_md5 = unsafe { mem::zeroed() };
+        unsafe { fz_md5_init(&mut md5) };
+        let mut update =
+            |data: &[u8]| unsafe { fz_md5_update(&mut md5, data.as_ptr(), data.len()) };
+        for block in text_page.blocks() {
+            if block.r#type() == TextBlockType::Image {
+                update(b"I");
+                update(&cells(block.bounds()));
+                if let Some(image) = block.image() {
+                    let pixmap = image.to_pixmap()?;
+                    update(&pixmap.width().to_le_bytes());
+                    update(&pixmap.height().to_le_bytes());
+                    update(pixmap.samples());
+                }
+                continue;
+            }
+            for line in block.lines() {
+                let text: String = line.chars().filter_map(|c| c.char()).collect();
+                let words: Vec<&str> = text.split_whitespace().collect();
+                if words.is_empty() {
+                    continue;
+                }
+                update(b"T");
+                update(&cells(line.bounds()));
+                update(words.join(" ").as_bytes());
+                update(b"\n");
+            }
+        }
+        let mut digest = [0; 16];
+        unsafe { fz_md5_final(&mut md5, digest.as_mut_ptr()) };
+        Ok(digest)
+    }
+
+    /// Render the `viewport` area, in device pixels at `scale`, recording the page (with
+    /// annotations) into a display list that [`TileInfo::render_patch`] reuses.
+    pub fn render_tile(
+        &self,
+        scale: f32,
+        viewport: IRect,
+        cs: &Colorspace,
+        alpha: bool,
+    ) -> Result<(Pixmap, TileInfo), Error> {
+        let list = Arc::new(self.to_display_list(true)?);
+        TileInfo::render(list, scale, viewport, cs, alpha)
+    }
+
     pub fn run(&self, device: &Device, ctm: &Matrix) -> Result<(), Error> {
         unsafe {
             ffi_try!(mupdf_run_page(
@@ -280,6 +587,35 @@ impl Page {
         Ok(out)
     }
 
+    fn to_print_pixmap(&self, resolution: i32, cs: &Colorspace) -> Result<Pixmap, Error> {
+        let scale = resolution as f32 / 72.0;
+        let mut pixmap = self.to_pixmap(&Matrix::new_scale(scale, scale), cs, false, true)?;
+        pixmap.set_resolution(resolution, resolution);
+        Ok(pixmap)
+    }
+
+    /// Rasterize the page and write it as a single page PostScript document.
+    pub fn write_ps<W: Write>(&self, w: &mut W, options: &PsOptions) -> Result<u64, Error> {
+        let pixmap = self.to_print_pixmap(options.resolution(), &options.colorspace())?;
+        let mut buf = printer::pixmap_to_ps(&pixmap)?;
+        Ok(io::copy(&mut buf, w)?)
+    }
+
+    /// Rasterize the page and write it as PCL, using the printer features and job options
+    /// (duplex, tray, ...) from `options`.
+    pub fn write_pcl<W: Write>(&self, w: &mut W, options: &PclOptions) -> Result<u64, Error> {
+        let pixmap = self.to_print_pixmap(options.resolution(), &options.colorspace())?;
+        let mut buf = printer::pixmap_to_pcl(&pixmap, options)?;
+        Ok(io::copy(&mut buf, w)?)
+    }
+
+    /// Rasterize the page and write it as a PWG raster stream, e.g. for use in a CUPS filter.
+    pub fn write_pwg<W: Write>(&self, w: &mut W, options: &PwgOptions) -> Result<u64, Error> {
+        let pixmap = self.to_print_pixmap(options.resolution(), &options.colorspace())?;
+        let mut buf = printer::pixmap_to_pwg(&pixmap, options)?;
+        Ok(io::copy(&mut buf, w)?)
+    }
+
     pub fn links(&self) -> Result<LinkIter, Error> {
         unsafe { ffi_try!(mupdf_load_links(context(), self.as_ptr() as *mut _)) }.map(|next| {
             LinkIter {
@@ -502,6 +838,93 @@ mod test {
             .unwrap();
     }
 
+    #[test]
+    fn test_page_content_hash() {
+        use crate::pdf::PdfDocument;
+
+        let doc = Document::open("tests/files/dummy.pdf").unwrap();
+        let hash = doc.load_page(0).unwrap().content_hash().unwrap();
+        assert_eq!(hash, doc.load_page(0).unwrap().content_hash().unwrap());
+
+        let mut blank = PdfDocument::new();
+        let page = blank.new_page((595.0, 842.0)).unwrap();
+        assert_ne!(hash, page.content_hash().unwrap());
+    }
+
+    #[test]
+    fn test_page_content_hash_image() {
+        use crate::pdf::PdfDocument;
+        use crate::{ColorParams, Colorspace, Image, Matrix, Pixmap};
+
+        let image_hash = |value: i32| {
+            let mut pixmap = Pixmap::new(&Colorspace::device_rgb(), 0, 0, 4, 4, false).unwrap();
+            pixmap.clear_with(value).unwrap();
+            let image = Image::from_pixmap(&pixmap).unwrap();
+
+            let mut doc = PdfDocument::new();
+            let mut page = doc.new_page((100.0, 100.0)).unwrap();
+            let builder = page.content_builder().unwrap();
+            builder
+                .device()
+                .fill_image(
+                    &image,
+                    &Matrix::new(50.0, 0.0, 0.0, 50.0, 10.0, 10.0),
+                    1.0,
+                    ColorParams::default(),
+                )
+                .unwrap();
+            builder.finish().unwrap();
+            page.content_hash().unwrap()
+        };
+        assert_eq!(image_hash(0), image_hash(0));
+        assert_ne!(image_hash(0), image_hash(255));
+    }
+
+    #[test]
+    fn test_page_render_patch() {
+        use crate::{Colorspace, IRect};
+
+        let doc = Document::open("tests/files/dummy.pdf").unwrap();
+        let page0 = doc.load_page(0).unwrap();
+        let cs = Colorspace::device_rgb();
+
+        let viewport = IRect::new(0, 0, 100, 50);
+        let (pixmap, tile) = page0.render_tile(1.0, viewport, &cs, false).unwrap();
+        assert_eq!((pixmap.width(), pixmap.height()), (100, 50));
+
+        let zoomed = tile.viewport_at(2.0);
+        assert_eq!(zoomed, IRect::new(0, 0, 200, 100));
+        let (pixmap, tile) = tile.render_patch(2.0, zoomed).unwrap();
+        assert_eq!((pixmap.width(), pixmap.height()), (200, 100));
+        assert_eq!(tile.scale(), 2.0);
+    }
+
+    #[test]
+    fn test_page_write_printer_formats() {
+        use crate::printer::{PclOptions, PsOptions, PwgOptions};
+
+        let doc = Document::open("tests/files/dummy.pdf").unwrap();
+        let page0 = doc.load_page(0).unwrap();
+
+        let mut ps = Vec::new();
+        let mut options = PsOptions::default();
+        options.set_resolution(72);
+        page0.write_ps(&mut ps, &options).unwrap();
+        assert!(ps.starts_with(b"%!PS"));
+
+        let mut pcl = Vec::new();
+        let mut options = PclOptions::default();
+        options.set_resolution(72).set_duplex(true);
+        page0.write_pcl(&mut pcl, &options).unwrap();
+        assert!(!pcl.is_empty());
+
+        let mut pwg = Vec::new();
+        let mut options = PwgOptions::default();
+        options.set_resolution(72);
+        page0.write_pwg(&mut pwg, &options).unwrap();
+        assert!(pwg.starts_with(b"RaS2"));
+    }
+
     #[test]
     fn test_page_links() {
         use crate::Link;
@@ -521,6 +944,201 @@ mod test {
         assert_eq!(seps.len(), 0);
     }
 
+    #[test]
+    fn test_page_to_pixmap_with_overprint() {
+        use crate::pdf::PdfDocument;
+        use crate::Colorspace;
+
+        let mut doc = PdfDocument::new();
+        doc.new_page((100.0, 100.0)).unwrap();
+        let resources = doc
+            .new_object_from_str(
+                "<< /ExtGState << /GS1 << /Type /ExtGState /OP true /op true /OPM 1 >> >> >>",
+            )
+            .unwrap();
+        let contents = doc.new_dict().unwrap();
+        let mut contents = doc.add_object(&contents).unwrap();
+        contents
+            .write_stream_string("1 0 0 0 k 0 0 60 100 re f /GS1 gs 0 1 0 0 k 40 0 60 100 re f")
+            .unwrap();
+        let mut page_obj = doc.find_page(0).unwrap();
+        page_obj.dict_put("Resources", resources).unwrap();
+        page_obj.dict_put("Contents", contents).unwrap();
+
+        let page = doc.load_page(0).unwrap();
+        assert!(page.uses_overprint());
+        let rgb = Colorspace::device_rgb();
+        let red_at_overlap = |overprint: bool| {
+            let pixmap = page
+                .to_pixmap_with_overprint(&Matrix::IDENTITY, &rgb, false, false, overprint)
+                .unwrap();
+            pixmap.samples()[(50 * 100 + 50) * 3]
+        };
+        // Magenta knocks out the cyan below it, unless it overprints it into blue
+        assert!(red_at_overlap(false) > 128);
+        assert!(red_at_overlap(true) < 128);
+    }
+
+    #[test]
+    fn test_page_to_pixmap_proofed() {
+        use crate::{Colorspace, RenderingIntent};
+
+        let doc = Document::open("tests/files/dummy.pdf").unwrap();
+        let page = doc.load_page(0).unwrap();
+        let pixmap = page
+            .to_pixmap_proofed(
+                &Matrix::IDENTITY,
+                &Colorspace::device_rgb(),
+                &Colorspace::device_cmyk(),
+                RenderingIntent::RelativeColorimetric,
+                false,
+                true,
+            )
+            .unwrap();
+        assert_eq!(pixmap.n(), 3);
+        assert_eq!((pixmap.width(), pixmap.height()), (595, 842));
+        // Paper white stays white
+        assert_eq!(&pixmap.samples()[..3], &[255, 255, 255]);
+    }
+
+    #[test]
+    fn test_page_to_pixmap_with_default_colorspaces() {
+        use crate::{Colorspace, DefaultColorspaces};
+
+        let mut defaults = DefaultColorspaces::new().unwrap();
+        assert_eq!(defaults.rgb(), Colorspace::device_rgb());
+        assert!(defaults.output_intent().is_none());
+        assert!(defaults.set(&Colorspace::device_bgr()).is_err());
+
+        let doc = Document::open("tests/files/dummy.pdf").unwrap();
+        let page = doc.load_page(0).unwrap();
+        let rgb = Colorspace::device_rgb();
+        let expected = page
+            .to_pixmap(&Matrix::IDENTITY, &rgb, false, true)
+            .unwrap();
+        let pixmap = page
+            .to_pixmap_with_default_colorspaces(&Matrix::IDENTITY, &rgb, false, true, &defaults)
+            .unwrap();
+        assert_eq!(pixmap.md5(), expected.md5());
+
+        defaults
+            .set_output_intent(&Colorspace::device_cmyk())
+            .unwrap();
+        assert_eq!(defaults.output_intent(), Some(Colorspace::device_cmyk()));
+    }
+
+    #[test]
+    fn test_page_to_pixmap_clipped() {
+        use crate::{Colorspace, IRect};
+
+        let doc = Document::open("tests/files/dummy.pdf").unwrap();
+        let page = doc.load_page(0).unwrap();
+        let ctm = Matrix::new_scale(2.0, 2.0);
+        let rgb = Colorspace::device_rgb();
+        let full = page.to_pixmap(&ctm, &rgb, false, true).unwrap();
+        let clip = IRect::new(100, 130, 240, 180);
+        let part = page
+            .to_pixmap_clipped(&ctm, clip, &rgb, false, true)
+            .unwrap();
+        assert_eq!(part.rect(), clip);
+        let stride = full.width() as usize * 3;
+        for y in 0..clip.height() as usize {
+            let row = (clip.y0 as usize + y) * stride + clip.x0 as usize * 3;
+            let part_row = y * part.width() as usize * 3;
+            assert_eq!(
+                &part.samples()[part_row..part_row + part.width() as usize * 3],
+                &full.samples()[row..row + part.width() as usize * 3]
+            );
+        }
+
+        // Clips are limited to the page
+        let edge = page
+            .to_pixmap_clipped(&ctm, IRect::new(1100, -10, 1300, 10), &rgb, false, true)
+            .unwrap();
+        assert_eq!(edge.rect(), IRect::new(1100, 0, 1190, 10));
+        let outside = page
+            .to_pixmap_clipped(&ctm, IRect::new(-100, -100, -10, -10), &rgb, false, true)
+            .unwrap();
+        assert!(outside.rect().is_empty());
+    }
+
+    #[test]
+    fn test_page_render() {
+        use crate::{Colorspace, IRect, RenderOptions};
+
+        let doc = Document::open("tests/files/dummy.pdf").unwrap();
+        let page = doc.load_page(0).unwrap();
+        let mut options = RenderOptions::new();
+        options.set_dpi(144.0);
+        let pixmap = page.render(&options).unwrap();
+        let expected = page
+            .to_pixmap(
+                &Matrix::new_scale(2.0, 2.0),
+                &Colorspace::device_rgb(),
+                false,
+                true,
+            )
+            .unwrap();
+        assert_eq!(pixmap.rect(), expected.rect());
+        assert_eq!(pixmap.samples(), expected.samples());
+
+        options
+            .set_rotation(90.0)
+            .set_colorspace(&Colorspace::device_gray());
+        let rotated = page.render(&options).unwrap();
+        assert_eq!((rotated.width(), rotated.height()), (1684, 1190));
+        assert_eq!(rotated.n(), 1);
+
+        // Without anti-aliasing the text is only black and white
+        let mut options = RenderOptions::new();
+        options
+            .set_clip(IRect::new(50, 60, 120, 95))
+            .set_aa_level(0);
+        let part = page.render(&options).unwrap();
+        assert_eq!(part.rect(), IRect::new(50, 60, 120, 95));
+        assert!(part.samples().iter().any(|&v| v == 0));
+        assert!(part.samples().iter().all(|&v| v == 0 || v == 255));
+    }
+
+    #[test]
+    fn test_page_to_device() {
+        use crate::{IRect, Point, RenderOptions};
+
+        let doc = Document::open("tests/files/dummy.pdf").unwrap();
+        let page = doc.load_page(0).unwrap();
+        let flip = page.pdf_to_page().unwrap();
+        assert_eq!(
+            Point::new(10.0, 800.0).transform(&flip),
+            Point::new(10.0, 42.0)
+        );
+
+        let mut options = RenderOptions::new();
+        options.set_dpi(144.0);
+        let to_device = page.page_to_device(&options).unwrap();
+        assert_eq!(
+            Point::new(0.0, 842.0).transform(&to_device),
+            Point::new(0.0, 0.0)
+        );
+        assert_eq!(
+            Point::new(595.0, 0.0).transform(&to_device),
+            Point::new(1190.0, 1684.0)
+        );
+
+        // Pixels of rotated and clipped renderings count from the top left of the pixmap
+        options
+            .set_rotation(90.0)
+            .set_clip(IRect::new(-1000, 100, 0, 500));
+        let to_device = page.page_to_device(&options).unwrap();
+        let p = Point::new(0.0, 842.0).transform(&to_device);
+        assert_eq!(p, Point::new(1000.0, -100.0));
+        let to_page = page.device_to_page(&options).unwrap();
+        let back = p.transform(&to_page);
+        assert!((back.x - 0.0).abs() < 0.01 && (back.y - 842.0).abs() < 0.01);
+
+        options.set_zoom(0.0);
+        assert!(page.device_to_page(&options).is_err());
+    }
+
     #[test]
     fn test_page_search() {
         use crate::{Point, Quad};
@@ -0,0 +1,48 @@
+use std::ops::Range;
+
+use rayon::prelude::*;
+
+use crate::context_pool::SharedDocument;
+use crate::{Document, Error, Pixmap, RenderOptions};
+
+impl Document {
+    /// Render the pages in `range` on the rayon thread pool, yielding the pixmaps in page order.
+    ///
+    /// Pages are interpreted into display lists one at a time, as a document can't be used by
+    /// several threads at once, while rasterizing them runs in parallel.
+    pub fn render_pages_parallel<'a>(
+        &'a self,
+        range: Range<i32>,
+        options: &'a RenderOptions,
+    ) -> impl IndexedParallelIterator<Item = Result<Pixmap, Error>> + 'a {
+        let shared = SharedDocument::new(self);
+        range.into_par_iter().map(move |page_no| {
+            shared
+                .with_document(|doc| {
+                    doc.load_page(page_no)?
+                        .to_display_list(options.annotations())
+                })?
+                .render(options)
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rayon::prelude::*;
+
+    use crate::{Document, RenderOptions};
+
+    #[test]
+    fn test_render_pages_parallel() {
+        let doc = Document::open("tests/files/dummy.pdf").unwrap();
+        let mut options = RenderOptions::new();
+        options.set_zoom(0.5);
+        let pixmaps: Vec<_> = doc
+            .render_pages_parallel(0..doc.page_count().unwrap(), &options)
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(pixmaps.len(), 1);
+        assert_eq!(pixmaps[0].width(), 298);
+    }
+}
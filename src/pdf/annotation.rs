@@ -1,14 +1,19 @@
 use std::convert::TryFrom;
 use std::ffi::{CStr, CString};
+use std::ptr;
 
 use mupdf_sys::*;
 use num_enum::TryFromPrimitive;
 
-use crate::pdf::PdfFilterOptions;
-use crate::{context, Error};
+use crate::pdf::{PdfFilterOptions, PdfObject};
+use crate::{
+    context, ColorParams, Colorspace, Device, DisplayList, Error, Image, Matrix, Path, Pixmap,
+    Point, Quad, Rect,
+};
 
 #[derive(Debug, Clone, Copy, PartialEq, TryFromPrimitive)]
 #[repr(i32)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub enum PdfAnnotationType {
     Text = 0,
     Link = 1,
@@ -41,6 +46,7 @@ pub enum PdfAnnotationType {
 
 #[derive(Debug, Clone, Copy, PartialEq, TryFromPrimitive)]
 #[repr(i32)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub enum LineEndingStyle {
     None = 0,
     Square = 1,
@@ -54,6 +60,143 @@ pub enum LineEndingStyle {
     Slash = 9,
 }
 
+/// How an annotation relates to the annotation it refers to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplyType {
+    /// A comment on the other annotation, shown as part of its thread.
+    Reply,
+    /// Grouped with the other annotation, which represents them both.
+    Group,
+}
+
+/// The state of an annotation in a review, set by a state annotation replying to it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReviewState {
+    /// The `Review` state model.
+    Accepted,
+    Rejected,
+    Cancelled,
+    Completed,
+    /// No review state, after one was set before.
+    None,
+    /// The `Marked` state model, a check mark independent of the review.
+    Marked,
+    Unmarked,
+}
+
+impl ReviewState {
+    const NAMES: [(ReviewState, &'static str); 7] = [
+        (ReviewState::Accepted, "Accepted"),
+        (ReviewState::Rejected, "Rejected"),
+        (ReviewState::Cancelled, "Cancelled"),
+        (ReviewState::Completed, "Completed"),
+        (ReviewState::None, "None"),
+        (ReviewState::Marked, "Marked"),
+        (ReviewState::Unmarked, "Unmarked"),
+    ];
+
+    pub fn name(self) -> &'static str {
+        Self::NAMES.iter().find(|(s, _)| *s == self).unwrap().1
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::NAMES
+            .iter()
+            .find(|(_, n)| *n == name)
+            .map(|(s, _)| *s)
+    }
+
+    /// The state model the state belongs to, `Review` or `Marked`.
+    pub fn model(self) -> &'static str {
+        match self {
+            ReviewState::Marked | ReviewState::Unmarked => "Marked",
+            _ => "Review",
+        }
+    }
+}
+
+/// The kinds of annotations marking up text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MarkupKind {
+    Highlight,
+    Underline,
+    StrikeOut,
+    Squiggly,
+}
+
+impl From<MarkupKind> for PdfAnnotationType {
+    fn from(kind: MarkupKind) -> Self {
+        match kind {
+            MarkupKind::Highlight => PdfAnnotationType::Highlight,
+            MarkupKind::Underline => PdfAnnotationType::Underline,
+            MarkupKind::StrikeOut => PdfAnnotationType::StrikeOut,
+            MarkupKind::Squiggly => PdfAnnotationType::Squiggly,
+        }
+    }
+}
+
+/// Alignment of the text of free text annotations and text fields.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, TryFromPrimitive)]
+#[repr(i32)]
+pub enum TextAlignment {
+    Left = 0,
+    Center = 1,
+    Right = 2,
+}
+
+/// The font, size and color of the text of free text annotations and form fields.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DefaultAppearance {
+    /// Name of a base 14 font resource, `Helv`, `TiRo`, `Cour`, `Symb` or `ZaDb`.
+    pub font: String,
+    pub size: f32,
+    /// Gray, RGB or CMYK color components.
+    pub color: Vec<f32>,
+}
+
+impl Default for DefaultAppearance {
+    fn default() -> Self {
+        Self {
+            font: "Helv".to_owned(),
+            size: 12.0,
+            color: vec![0.0],
+        }
+    }
+}
+
+/// Which of the appearances of an annotation to set, viewers showing them depending on the
+/// mouse.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AppearanceKind {
+    /// Shown when the mouse isn't over the annotation, and when printing.
+    Normal,
+    /// Shown while the mouse is over the annotation.
+    Rollover,
+    /// Shown while the mouse button is pressed on the annotation.
+    Down,
+}
+
+impl AppearanceKind {
+    fn name(self) -> &'static str {
+        match self {
+            AppearanceKind::Normal => "N",
+            AppearanceKind::Rollover => "R",
+            AppearanceKind::Down => "D",
+        }
+    }
+}
+
+/// An annotation with its replies and review states, as found by
+/// [`PdfPage::annotation_threads`](crate::pdf::PdfPage::annotation_threads).
+#[derive(Debug)]
+pub struct AnnotationThread {
+    pub annotation: PdfAnnotation,
+    /// Replies in page order, each with replies of its own.
+    pub replies: Vec<AnnotationThread>,
+    /// The latest review state set by each author, authors being empty when unknown.
+    pub review_states: Vec<(String, ReviewState)>,
+}
+
 #[derive(Debug)]
 pub struct PdfAnnotation {
     pub(crate) inner: *mut pdf_annot,
@@ -98,6 +241,632 @@ impl PdfAnnotation {
         }
     }
 
+    /// The color of the annotation: of the icon, border, line or highlight depending on its
+    /// type, as gray, RGB or CMYK components. Empty when transparent.
+    pub fn color(&self) -> Result<Vec<f32>, Error> {
+        let mut color = [0.0; 4];
+        let n = unsafe {
+            ffi_try!(mupdf_pdf_annot_color(
+                context(),
+                self.inner,
+                color.as_mut_ptr()
+            ))
+        }?;
+        Ok(color[..n as usize].to_vec())
+    }
+
+    pub fn set_color(&mut self, color: &[f32]) -> Result<(), Error> {
+        if !matches!(color.len(), 0 | 1 | 3 | 4) {
+            return Err(Error::InvalidAnnotation(format!(
+                "{} color components",
+                color.len()
+            )));
+        }
+        unsafe {
+            ffi_try!(mupdf_pdf_set_annot_color(
+                context(),
+                self.inner,
+                color.len() as i32,
+                color.as_ptr()
+            ))
+        }
+    }
+
+    /// The color a square, circle, polygon or the line endings of a line annotation are filled
+    /// with. Empty when not filled.
+    pub fn interior_color(&self) -> Result<Vec<f32>, Error> {
+        let mut color = [0.0; 4];
+        let n = unsafe {
+            ffi_try!(mupdf_pdf_annot_interior_color(
+                context(),
+                self.inner,
+                color.as_mut_ptr()
+            ))
+        }?;
+        Ok(color[..n as usize].to_vec())
+    }
+
+    pub fn set_interior_color(&mut self, color: &[f32]) -> Result<(), Error> {
+        if !matches!(color.len(), 0 | 1 | 3 | 4) {
+            return Err(Error::InvalidAnnotation(format!(
+                "{} color components",
+                color.len()
+            )));
+        }
+        unsafe {
+            ffi_try!(mupdf_pdf_set_annot_interior_color(
+                context(),
+                self.inner,
+                color.len() as i32,
+                color.as_ptr()
+            ))
+        }
+    }
+
+    /// The quads of the text marked up by a text markup or redaction annotation, in page
+    /// coordinates.
+    pub fn quad_points(&self) -> Result<Vec<Quad>, Error> {
+        let count = unsafe { ffi_try!(mupdf_pdf_annot_quad_point_count(context(), self.inner)) }?;
+        (0..count)
+            .map(|i| {
+                unsafe { ffi_try!(mupdf_pdf_annot_quad_point(context(), self.inner, i)) }
+                    .map(Quad::from)
+            })
+            .collect()
+    }
+
+    pub fn set_quad_points(&mut self, quads: &[Quad]) -> Result<(), Error> {
+        let quads: Vec<fz_quad> = quads.iter().map(|q| q.clone().into()).collect();
+        unsafe {
+            ffi_try!(mupdf_pdf_set_annot_quad_points(
+                context(),
+                self.inner,
+                i32::try_from(quads.len())?,
+                quads.as_ptr()
+            ))
+        }
+    }
+
+    /// The end points of a line annotation, in page coordinates.
+    pub fn line(&self) -> Result<[Point; 2], Error> {
+        let mut line = [fz_point { x: 0.0, y: 0.0 }; 2];
+        unsafe {
+            ffi_try!(mupdf_pdf_annot_line(
+                context(),
+                self.inner,
+                line.as_mut_ptr()
+            ))
+        }?;
+        Ok(line.map(Point::from))
+    }
+
+    pub fn set_line(&mut self, start: Point, end: Point) -> Result<(), Error> {
+        unsafe {
+            ffi_try!(mupdf_pdf_set_annot_line(
+                context(),
+                self.inner,
+                start.into(),
+                end.into()
+            ))
+        }
+    }
+
+    /// The shapes drawn at the start and end of a line or polyline annotation.
+    pub fn line_ending_styles(&self) -> Result<[LineEndingStyle; 2], Error> {
+        let mut styles = [0; 2];
+        unsafe {
+            ffi_try!(mupdf_pdf_annot_line_ending_styles(
+                context(),
+                self.inner,
+                styles.as_mut_ptr()
+            ))
+        }?;
+        Ok(styles.map(|s| LineEndingStyle::try_from(s).unwrap_or(LineEndingStyle::None)))
+    }
+
+    pub fn set_line_ending_styles(
+        &mut self,
+        start: LineEndingStyle,
+        end: LineEndingStyle,
+    ) -> Result<(), Error> {
+        unsafe {
+            ffi_try!(mupdf_pdf_set_annot_line_ending_styles(
+                context(),
+                self.inner,
+                start as i32,
+                end as i32
+            ))
+        }
+    }
+
+    /// The vertices of a polygon or polyline annotation, in page coordinates.
+    pub fn vertices(&self) -> Result<Vec<Point>, Error> {
+        let count = unsafe { ffi_try!(mupdf_pdf_annot_vertex_count(context(), self.inner)) }?;
+        (0..count)
+            .map(|i| {
+                unsafe { ffi_try!(mupdf_pdf_annot_vertex(context(), self.inner, i)) }
+                    .map(Point::from)
+            })
+            .collect()
+    }
+
+    pub fn set_vertices(&mut self, vertices: &[Point]) -> Result<(), Error> {
+        let points: Vec<fz_point> = vertices.iter().map(|&p| p.into()).collect();
+        unsafe {
+            ffi_try!(mupdf_pdf_set_annot_vertices(
+                context(),
+                self.inner,
+                i32::try_from(points.len())?,
+                points.as_ptr()
+            ))
+        }
+    }
+
+    /// The font, size and color of the text of a free text annotation.
+    pub fn default_appearance(&self) -> Result<DefaultAppearance, Error> {
+        let mut font = ptr::null();
+        let mut size = 0.0;
+        let mut color = [0.0; 4];
+        let n = unsafe {
+            ffi_try!(mupdf_pdf_annot_default_appearance(
+                context(),
+                self.inner,
+                &mut font,
+                &mut size,
+                color.as_mut_ptr()
+            ))
+        }?;
+        let font = if font.is_null() {
+            String::new()
+        } else {
+            unsafe { CStr::from_ptr(font) }
+                .to_string_lossy()
+                .into_owned()
+        };
+        Ok(DefaultAppearance {
+            font,
+            size,
+            color: color[..n as usize].to_vec(),
+        })
+    }
+
+    pub fn set_default_appearance(&mut self, da: &DefaultAppearance) -> Result<(), Error> {
+        if !matches!(da.color.len(), 0 | 1 | 3 | 4) {
+            return Err(Error::InvalidAnnotation(format!(
+                "{} color components",
+                da.color.len()
+            )));
+        }
+        let font = CString::new(da.font.as_str())?;
+        unsafe {
+            ffi_try!(mupdf_pdf_set_annot_default_appearance(
+                context(),
+                self.inner,
+                font.as_ptr(),
+                da.size,
+                da.color.len() as i32,
+                da.color.as_ptr()
+            ))
+        }
+    }
+
+    pub fn alignment(&self) -> Result<TextAlignment, Error> {
+        unsafe { ffi_try!(mupdf_pdf_annot_quadding(context(), self.inner)) }
+            .map(|q| TextAlignment::try_from(q).unwrap_or(TextAlignment::Left))
+    }
+
+    pub fn set_alignment(&mut self, alignment: TextAlignment) -> Result<(), Error> {
+        unsafe {
+            ffi_try!(mupdf_pdf_set_annot_quadding(
+                context(),
+                self.inner,
+                alignment as i32
+            ))
+        }
+    }
+
+    /// The callout line of a free text annotation, from the point it points at to the text box,
+    /// in page coordinates.
+    pub fn callout_line(&self) -> Result<Vec<Point>, Error> {
+        let mut line = [fz_point { x: 0.0, y: 0.0 }; 3];
+        let n = unsafe {
+            ffi_try!(mupdf_pdf_annot_callout_line(
+                context(),
+                self.inner,
+                line.as_mut_ptr()
+            ))
+        }?;
+        Ok(line[..n as usize].iter().map(|&p| p.into()).collect())
+    }
+
+    /// Set the callout line of a free text annotation: the point it points at, an optional
+    /// knee point and the point on the text box, or no points to remove it.
+    pub fn set_callout_line(&mut self, line: &[Point]) -> Result<(), Error> {
+        if !matches!(line.len(), 0 | 2 | 3) {
+            return Err(Error::InvalidAnnotation(format!(
+                "callout line with {} points",
+                line.len()
+            )));
+        }
+        let mut points = [fz_point { x: 0.0, y: 0.0 }; 3];
+        for (point, &p) in points.iter_mut().zip(line) {
+            *point = p.into();
+        }
+        unsafe {
+            ffi_try!(mupdf_pdf_set_annot_callout_line(
+                context(),
+                self.inner,
+                points.as_mut_ptr(),
+                line.len() as i32
+            ))
+        }?;
+        let mut obj = self.object();
+        if line.is_empty() {
+            obj.dict_delete("IT")
+        } else {
+            obj.dict_put("IT", PdfObject::new_name("FreeTextCallout")?)
+        }
+    }
+
+    /// The rich text of the annotation, an XHTML fragment shown instead of its contents by
+    /// viewers that support it.
+    pub fn rich_contents(&self) -> Result<Option<String>, Error> {
+        self.text_entry("RC")
+    }
+
+    /// Set the rich text of the annotation along with its plain text contents, for viewers
+    /// without rich text support. The appearance MuPDF generates shows the plain text.
+    pub fn set_rich_contents(&mut self, plain: &str, rich: &str) -> Result<(), Error> {
+        self.set_contents(plain)?;
+        self.object().dict_put("RC", PdfObject::new_string(rich)?)
+    }
+
+    /// The default style of the rich text, a CSS declaration like `font: 12pt Helvetica`.
+    pub fn default_style(&self) -> Result<Option<String>, Error> {
+        self.text_entry("DS")
+    }
+
+    pub fn set_default_style(&mut self, style: &str) -> Result<(), Error> {
+        self.object().dict_put("DS", PdfObject::new_string(style)?)
+    }
+
+    fn text_entry(&self, key: &str) -> Result<Option<String>, Error> {
+        match self.object().get_dict(key)? {
+            Some(value) if value.is_string()? => Ok(Some(value.as_string()?.to_owned())),
+            _ => Ok(None),
+        }
+    }
+
+    /// The text of the annotation, or of the comment it represents.
+    pub fn contents(&self) -> Result<Option<String>, Error> {
+        self.text_entry("Contents")
+    }
+
+    pub fn set_contents(&mut self, contents: &str) -> Result<(), Error> {
+        self.object()
+            .dict_put("Contents", PdfObject::new_string(contents)?)
+    }
+
+    /// The object number of the annotation, which identifies it within its document.
+    pub(crate) fn object_number(&self) -> Result<i32, Error> {
+        self.object().as_indirect()
+    }
+
+    /// The dictionary of the annotation this one replies to, if any.
+    pub fn in_reply_to(&self) -> Result<Option<PdfObject>, Error> {
+        match self.object().get_dict("IRT")? {
+            Some(irt) if irt.is_dict()? => Ok(Some(irt)),
+            _ => Ok(None),
+        }
+    }
+
+    /// Whether this annotation replies to `parent`.
+    pub fn is_reply_to(&self, parent: &PdfAnnotation) -> Result<bool, Error> {
+        match self.object().get_dict("IRT")? {
+            Some(irt) if irt.is_indirect()? => Ok(irt.as_indirect()? == parent.object_number()?),
+            _ => Ok(false),
+        }
+    }
+
+    /// How this annotation relates to the one it replies to, `None` if it isn't a reply.
+    pub fn reply_type(&self) -> Result<Option<ReplyType>, Error> {
+        let obj = self.object();
+        if obj.get_dict("IRT")?.is_none() {
+            return Ok(None);
+        }
+        Ok(Some(match obj.get_dict("RT")? {
+            Some(rt) if rt.is_name()? && rt.as_name()? == b"Group" => ReplyType::Group,
+            _ => ReplyType::Reply,
+        }))
+    }
+
+    /// Make this annotation a reply to `parent`, which must be on the same page.
+    pub fn set_in_reply_to(
+        &mut self,
+        parent: &PdfAnnotation,
+        reply_type: ReplyType,
+    ) -> Result<(), Error> {
+        let mut obj = self.object();
+        obj.dict_put("IRT", parent.object())?;
+        match reply_type {
+            ReplyType::Reply => obj.dict_delete("RT"),
+            ReplyType::Group => obj.dict_put("RT", PdfObject::new_name("Group")?),
+        }
+    }
+
+    /// The review state this annotation sets on the one it replies to, if it is a state
+    /// annotation.
+    pub fn state(&self) -> Result<Option<ReviewState>, Error> {
+        match self.object().get_dict("State")? {
+            Some(state) if state.is_string()? => Ok(ReviewState::from_name(state.as_string()?)),
+            _ => Ok(None),
+        }
+    }
+
+    /// The rectangle of the popup window showing the contents of the annotation, if it has
+    /// one.
+    pub fn popup(&self) -> Result<Option<Rect>, Error> {
+        let rect: Rect = unsafe { ffi_try!(mupdf_pdf_annot_popup(context(), self.inner)) }?.into();
+        Ok(if rect.is_empty() { None } else { Some(rect) })
+    }
+
+    /// Set the rectangle of the popup window, creating it if needed.
+    pub fn set_popup(&mut self, rect: Rect) -> Result<(), Error> {
+        unsafe {
+            ffi_try!(mupdf_pdf_set_annot_popup(
+                context(),
+                self.inner,
+                rect.into()
+            ))
+        }
+    }
+
+    /// Whether the popup window, or the note of a text annotation, is shown open.
+    pub fn is_open(&self) -> Result<bool, Error> {
+        unsafe { ffi_try!(mupdf_pdf_annot_is_open(context(), self.inner)) }
+    }
+
+    pub fn set_open(&mut self, open: bool) -> Result<(), Error> {
+        unsafe { ffi_try!(mupdf_pdf_set_annot_is_open(context(), self.inner, open)) }
+    }
+
+    /// The strokes of an ink annotation, in page coordinates.
+    pub fn ink_list(&self) -> Result<Vec<Vec<Point>>, Error> {
+        let count = unsafe { ffi_try!(mupdf_pdf_annot_ink_list_count(context(), self.inner)) }?;
+        (0..count)
+            .map(|i| {
+                let len = unsafe {
+                    ffi_try!(mupdf_pdf_annot_ink_list_stroke_count(
+                        context(),
+                        self.inner,
+                        i
+                    ))
+                }?;
+                (0..len)
+                    .map(|k| {
+                        unsafe {
+                            ffi_try!(mupdf_pdf_annot_ink_list_stroke_vertex(
+                                context(),
+                                self.inner,
+                                i,
+                                k
+                            ))
+                        }
+                        .map(Point::from)
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Replace the strokes of an ink annotation, given in page coordinates. The rectangle of
+    /// the annotation grows to fit them when its appearance is updated.
+    pub fn set_ink_list(&mut self, strokes: &[Vec<Point>]) -> Result<(), Error> {
+        let counts = strokes
+            .iter()
+            .map(|stroke| i32::try_from(stroke.len()))
+            .collect::<Result<Vec<_>, _>>()?;
+        let points: Vec<fz_point> = strokes.iter().flatten().map(|&p| p.into()).collect();
+        unsafe {
+            ffi_try!(mupdf_pdf_set_annot_ink_list(
+                context(),
+                self.inner,
+                i32::try_from(counts.len())?,
+                counts.as_ptr(),
+                points.as_ptr()
+            ))
+        }
+    }
+
+    /// The width of the border, or the line width of ink, line and shape annotations.
+    pub fn border_width(&self) -> Result<f32, Error> {
+        unsafe { ffi_try!(mupdf_pdf_annot_border_width(context(), self.inner)) }
+    }
+
+    pub fn set_border_width(&mut self, width: f32) -> Result<(), Error> {
+        unsafe {
+            ffi_try!(mupdf_pdf_set_annot_border_width(
+                context(),
+                self.inner,
+                width
+            ))
+        }
+    }
+
+    /// The rectangle of the annotation, in page coordinates.
+    pub fn rect(&self) -> Result<Rect, Error> {
+        unsafe { ffi_try!(mupdf_pdf_annot_rect(context(), self.inner)) }.map(Rect::from)
+    }
+
+    pub fn set_rect(&mut self, rect: Rect) -> Result<(), Error> {
+        unsafe { ffi_try!(mupdf_pdf_set_annot_rect(context(), self.inner, rect.into())) }
+    }
+
+    /// The area the annotation covers, in page coordinates.
+    pub fn bounds(&self) -> Result<Rect, Error> {
+        unsafe { ffi_try!(mupdf_pdf_bound_annot(context(), self.inner)) }.map(Rect::from)
+    }
+
+    /// Render the annotation alone, into a pixmap covering its [bounds](PdfAnnotation::bounds)
+    /// transformed by `ctm`, to redraw a single changed annotation or widget without rendering
+    /// the whole page. Call [`PdfPage::update`](crate::pdf::PdfPage::update) first to
+    /// regenerate the appearance of changed annotations.
+    pub fn to_pixmap(&self, ctm: &Matrix, cs: &Colorspace, alpha: bool) -> Result<Pixmap, Error> {
+        unsafe {
+            ffi_try!(mupdf_pdf_annot_to_pixmap(
+                context(),
+                self.inner,
+                ctm.into(),
+                cs.inner,
+                alpha
+            ))
+        }
+        .map(|inner| unsafe { Pixmap::from_raw(inner) })
+    }
+
+    /// Use `image` as the appearance of a stamp annotation, scaled to fit its rectangle, instead
+    /// of the text of its icon.
+    pub fn set_stamp_image(&mut self, image: &Image) -> Result<(), Error> {
+        unsafe {
+            ffi_try!(mupdf_pdf_set_annot_stamp_image(
+                context(),
+                self.inner,
+                image.inner
+            ))
+        }
+    }
+
+    /// Use the drawing recorded in `list` as the appearance of a stamp annotation, scaled to fit
+    /// `rect` in page coordinates while keeping its aspect ratio. The rectangle of the
+    /// annotation becomes that of the drawing.
+    pub fn set_stamp_display_list(&mut self, list: &DisplayList, rect: Rect) -> Result<(), Error> {
+        let bounds = list.bounds();
+        if bounds.is_empty() || rect.is_empty() {
+            return Err(Error::InvalidAnnotation(
+                "empty stamp appearance".to_owned(),
+            ));
+        }
+        let scale = (rect.width() / bounds.width()).min(rect.height() / bounds.height());
+        let mut ctm = Matrix::new_translate(-bounds.x0, -bounds.y0);
+        ctm.concat(Matrix::new_scale(scale, scale));
+        ctm.concat(Matrix::new_translate(
+            rect.x0 + (rect.width() - bounds.width() * scale) / 2.0,
+            rect.y0 + (rect.height() - bounds.height() * scale) / 2.0,
+        ));
+        self.set_appearance(AppearanceKind::Normal, None, list, &ctm)
+    }
+
+    /// Set an appearance of the annotation to the drawing recorded in `list`, transformed by
+    /// `ctm` to page coordinates. The rectangle of the annotation becomes that of the drawing.
+    ///
+    /// `state` selects the appearance for one state of the annotation, e.g. `On` or `Off` for
+    /// check boxes, `None` setting the appearance for annotations without states. Changing the
+    /// properties of the annotation afterwards replaces the normal appearance by a generated
+    /// one.
+    pub fn set_appearance(
+        &mut self,
+        kind: AppearanceKind,
+        state: Option<&str>,
+        list: &DisplayList,
+        ctm: &Matrix,
+    ) -> Result<(), Error> {
+        let kind = CString::new(kind.name())?;
+        let state = state.map(CString::new).transpose()?;
+        unsafe {
+            ffi_try!(mupdf_pdf_set_annot_appearance_from_display_list(
+                context(),
+                self.inner,
+                kind.as_ptr(),
+                state.as_ref().map_or(ptr::null(), |s| s.as_ptr()),
+                ctm.into(),
+                list.inner
+            ))
+        }
+    }
+
+    /// Set an appearance of the annotation to a PDF content stream, as by
+    /// [`PdfAnnotation::set_appearance`].
+    ///
+    /// `contents` draws into `bbox`, which `ctm` transforms to page coordinates, using the fonts,
+    /// images and other resources in the `resources` dictionary.
+    pub fn set_appearance_stream(
+        &mut self,
+        kind: AppearanceKind,
+        state: Option<&str>,
+        ctm: &Matrix,
+        bbox: Rect,
+        resources: &PdfObject,
+        contents: &[u8],
+    ) -> Result<(), Error> {
+        let kind = CString::new(kind.name())?;
+        let state = state.map(CString::new).transpose()?;
+        unsafe {
+            ffi_try!(mupdf_pdf_set_annot_appearance(
+                context(),
+                self.inner,
+                kind.as_ptr(),
+                state.as_ref().map_or(ptr::null(), |s| s.as_ptr()),
+                ctm.into(),
+                bbox.into(),
+                resources.inner,
+                contents.as_ptr(),
+                contents.len()
+            ))
+        }
+    }
+
+    /// The names of the states the annotation has appearances for, e.g. `On` and `Off`.
+    pub fn appearance_states(&self, kind: AppearanceKind) -> Result<Vec<String>, Error> {
+        let Some(ap) = self.object().get_dict("AP")? else {
+            return Ok(Vec::new());
+        };
+        match ap.get_dict(kind.name())? {
+            Some(states) if states.is_dict()? && !states.is_stream()? => (0..states.dict_len()?
+                as i32)
+                .map(|i| {
+                    let key = states.get_dict_key(i)?.ok_or(Error::UnexpectedNullPtr)?;
+                    Ok(String::from_utf8_lossy(key.as_name()?).into_owned())
+                })
+                .collect(),
+            _ => Ok(Vec::new()),
+        }
+    }
+
+    /// Use `path`, filled with the RGB `color`, as the appearance of a stamp annotation, scaled
+    /// to fit `rect` as by [`PdfAnnotation::set_stamp_display_list`].
+    pub fn set_stamp_path(
+        &mut self,
+        path: &Path,
+        color: [f32; 3],
+        rect: Rect,
+    ) -> Result<(), Error> {
+        let list = DisplayList::new(path.bounds(&Default::default(), &Matrix::IDENTITY)?)?;
+        {
+            let device = Device::from_display_list(&list)?;
+            device.fill_path(
+                path,
+                false,
+                &Matrix::IDENTITY,
+                &Colorspace::device_rgb(),
+                &color,
+                1.0,
+                ColorParams::default(),
+            )?;
+        }
+        self.set_stamp_display_list(&list, rect)
+    }
+
+    /// The annotation dictionary.
+    pub fn object(&self) -> PdfObject {
+        unsafe { PdfObject::from_raw_keep_ref(pdf_annot_obj(context(), self.inner)) }
+    }
+
+    /// Regenerate the appearance stream after the annotation dictionary was changed through
+    /// [`PdfAnnotation::object`], returning whether it changed.
+    pub fn update(&mut self) -> Result<bool, Error> {
+        unsafe { ffi_try!(mupdf_pdf_update_annot(context(), self.inner)) }
+    }
+
     pub fn filter(&mut self, mut opt: PdfFilterOptions) -> Result<(), Error> {
         unsafe {
             ffi_try!(mupdf_pdf_filter_annot_contents(
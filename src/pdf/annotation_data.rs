@@ -0,0 +1,636 @@
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+use crate::pdf::{LineEndingStyle, PdfAnnotationType, PdfDocument, PdfObject, PdfPage};
+use crate::{Error, Point, Quad, Rect};
+
+/// PDF names of the annotation subtypes.
+const SUBTYPES: [(PdfAnnotationType, &str); 26] = [
+    (PdfAnnotationType::Text, "Text"),
+    (PdfAnnotationType::Link, "Link"),
+    (PdfAnnotationType::FreeText, "FreeText"),
+    (PdfAnnotationType::Line, "Line"),
+    (PdfAnnotationType::Square, "Square"),
+    (PdfAnnotationType::Circle, "Circle"),
+    (PdfAnnotationType::Polygon, "Polygon"),
+    (PdfAnnotationType::PloyLine, "PolyLine"),
+    (PdfAnnotationType::Highlight, "Highlight"),
+    (PdfAnnotationType::Underline, "Underline"),
+    (PdfAnnotationType::Squiggly, "Squiggly"),
+    (PdfAnnotationType::StrikeOut, "StrikeOut"),
+    (PdfAnnotationType::Redact, "Redact"),
+    (PdfAnnotationType::Stamp, "Stamp"),
+    (PdfAnnotationType::Caret, "Caret"),
+    (PdfAnnotationType::Ink, "Ink"),
+    (PdfAnnotationType::Popup, "Popup"),
+    (PdfAnnotationType::FileAttachment, "FileAttachment"),
+    (PdfAnnotationType::Sound, "Sound"),
+    (PdfAnnotationType::Movie, "Movie"),
+    (PdfAnnotationType::Widget, "Widget"),
+    (PdfAnnotationType::Screen, "Screen"),
+    (PdfAnnotationType::PrinterMark, "PrinterMark"),
+    (PdfAnnotationType::TrapNet, "TrapNet"),
+    (PdfAnnotationType::Watermark, "Watermark"),
+    (PdfAnnotationType::ThreeD, "3D"),
+];
+
+const LINE_ENDINGS: [(LineEndingStyle, &str); 10] = [
+    (LineEndingStyle::None, "None"),
+    (LineEndingStyle::Square, "Square"),
+    (LineEndingStyle::Circle, "Circle"),
+    (LineEndingStyle::Diamond, "Diamond"),
+    (LineEndingStyle::OpenArrow, "OpenArrow"),
+    (LineEndingStyle::ClosedArrow, "ClosedArrow"),
+    (LineEndingStyle::Butt, "Butt"),
+    (LineEndingStyle::ROpenArrow, "ROpenArrow"),
+    (LineEndingStyle::RClosedArrow, "RClosedArrow"),
+    (LineEndingStyle::Slash, "Slash"),
+];
+
+impl PdfAnnotationType {
+    /// The name of the subtype in PDF, e.g. `Highlight`.
+    pub fn pdf_name(self) -> Option<&'static str> {
+        SUBTYPES.iter().find(|(t, _)| *t == self).map(|(_, n)| *n)
+    }
+
+    /// The subtype with the PDF name `name`.
+    pub fn from_pdf_name(name: &str) -> Option<Self> {
+        SUBTYPES.iter().find(|(_, n)| *n == name).map(|(t, _)| *t)
+    }
+}
+
+impl LineEndingStyle {
+    pub fn pdf_name(self) -> &'static str {
+        LINE_ENDINGS.iter().find(|(s, _)| *s == self).unwrap().1
+    }
+
+    pub fn from_pdf_name(name: &str) -> Option<Self> {
+        LINE_ENDINGS
+            .iter()
+            .find(|(_, n)| *n == name)
+            .map(|(s, _)| *s)
+    }
+}
+
+/// The popup window showing the text of an annotation.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct PopupData {
+    pub rect: Rect,
+    pub open: bool,
+}
+
+/// The properties of an annotation, detached from its document so they can be stored or sent
+/// elsewhere and imported again with [`PdfDocument::import_annotations`].
+///
+/// Coordinates are in PDF user space, with the origin in the bottom left corner of the page
+/// and no page rotation applied, as in the annotation dictionary. Dates are PDF date strings,
+/// e.g. `D:20240131120000Z`.
+///
+/// With the `serde` feature annotations can be serialized, e.g. to JSON for syncing them with
+/// a database. Replies are annotations of their own, linked through `in_reply_to`. Missing
+/// fields take their default value when deserializing:
+///
+/// ```json
+/// {
+///   "page": 0,
+///   "subtype": "Highlight",
+///   "name": "hl-1",
+///   "rect": { "x0": 56.0, "y0": 750.0, "x1": 116.0, "y1": 775.0 },
+///   "quad_points": [{
+///     "ul": { "x": 56.0, "y": 775.0 }, "ur": { "x": 116.0, "y": 775.0 },
+///     "ll": { "x": 56.0, "y": 750.0 }, "lr": { "x": 116.0, "y": 750.0 }
+///   }],
+///   "color": [1.0, 1.0, 0.0],
+///   "author": "Reviewer",
+///   "modified": "D:20240131120000Z",
+///   "contents": "Check this"
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct AnnotationData {
+    /// Index of the page, starting at 0.
+    pub page: i32,
+    pub subtype: PdfAnnotationType,
+    /// Unique name of the annotation on its page, which replies and imports refer to it by.
+    pub name: String,
+    pub rect: Rect,
+    pub contents: Option<String>,
+    pub author: Option<String>,
+    pub subject: Option<String>,
+    pub modified: Option<String>,
+    pub created: Option<String>,
+    /// Annotation flags, e.g. 4 for printing.
+    pub flags: i32,
+    /// Color components, gray, RGB or CMYK depending on their number.
+    pub color: Vec<f32>,
+    pub interior_color: Vec<f32>,
+    pub opacity: Option<f32>,
+    pub border_width: Option<f32>,
+    /// Icon of text, stamp and file attachment annotations, e.g. `Comment` or `Approved`.
+    pub icon: Option<String>,
+    /// The quads of text markup annotations.
+    pub quad_points: Vec<Quad>,
+    /// The strokes of ink annotations.
+    pub ink_list: Vec<Vec<Point>>,
+    /// The vertices of polygon and polyline annotations.
+    pub vertices: Vec<Point>,
+    /// The end points of line annotations.
+    pub line: Option<[Point; 2]>,
+    pub line_endings: Option<[LineEndingStyle; 2]>,
+    /// Default appearance string of free text annotations, e.g. `/Helv 12 Tf 0 g`.
+    pub default_appearance: Option<String>,
+    /// Text alignment of free text annotations: 0 for left, 1 for centered, 2 for right.
+    pub justification: Option<i32>,
+    /// Name of the annotation this one replies to.
+    pub in_reply_to: Option<String>,
+    /// `R` for a reply, `Group` for an annotation grouped with the one it refers to.
+    pub reply_type: Option<String>,
+    /// Review state, e.g. `Accepted`, of text annotations replying to another annotation.
+    pub state: Option<String>,
+    /// `Review` or `Marked`.
+    pub state_model: Option<String>,
+    pub popup: Option<PopupData>,
+}
+
+impl Default for AnnotationData {
+    fn default() -> Self {
+        Self {
+            page: 0,
+            subtype: PdfAnnotationType::Text,
+            name: String::new(),
+            rect: Rect::default(),
+            contents: None,
+            author: None,
+            subject: None,
+            modified: None,
+            created: None,
+            flags: 4,
+            color: Vec::new(),
+            interior_color: Vec::new(),
+            opacity: None,
+            border_width: None,
+            icon: None,
+            quad_points: Vec::new(),
+            ink_list: Vec::new(),
+            vertices: Vec::new(),
+            line: None,
+            line_endings: None,
+            default_appearance: None,
+            justification: None,
+            in_reply_to: None,
+            reply_type: None,
+            state: None,
+            state_model: None,
+            popup: None,
+        }
+    }
+}
+
+pub(crate) fn invalid(msg: impl Into<String>) -> Error {
+    Error::InvalidAnnotation(msg.into())
+}
+
+fn text(obj: &PdfObject, key: &str) -> Result<Option<String>, Error> {
+    match obj.get_dict(key)? {
+        Some(value) if value.is_string()? => Ok(Some(value.as_string()?.to_owned())),
+        _ => Ok(None),
+    }
+}
+
+fn name(obj: &PdfObject, key: &str) -> Result<Option<String>, Error> {
+    match obj.get_dict(key)? {
+        Some(value) if value.is_name()? => {
+            Ok(Some(String::from_utf8_lossy(value.as_name()?).into_owned()))
+        }
+        _ => Ok(None),
+    }
+}
+
+fn number(obj: &PdfObject, key: &str) -> Result<Option<f32>, Error> {
+    match obj.get_dict(key)? {
+        Some(value) if value.is_number()? => Ok(Some(value.as_float()?)),
+        _ => Ok(None),
+    }
+}
+
+fn array_numbers(array: &PdfObject) -> Result<Vec<f32>, Error> {
+    if !array.is_array()? {
+        return Ok(Vec::new());
+    }
+    (0..array.len()? as i32)
+        .map(|i| match array.get_array(i)? {
+            Some(value) => value.as_float(),
+            None => Ok(0.0),
+        })
+        .collect()
+}
+
+fn numbers(obj: &PdfObject, key: &str) -> Result<Vec<f32>, Error> {
+    match obj.get_dict(key)? {
+        Some(value) => array_numbers(&value),
+        None => Ok(Vec::new()),
+    }
+}
+
+fn rect(values: &[f32]) -> Rect {
+    match *values {
+        [x0, y0, x1, y1] => Rect::new(x0.min(x1), y0.min(y1), x0.max(x1), y0.max(y1)),
+        _ => Rect::default(),
+    }
+}
+
+fn points(values: &[f32]) -> Vec<Point> {
+    values
+        .chunks_exact(2)
+        .map(|p| Point::new(p[0], p[1]))
+        .collect()
+}
+
+/// The name of an annotation, made up from its position for those without one.
+pub(crate) fn annotation_name(obj: &PdfObject, page: i32, index: usize) -> Result<String, Error> {
+    Ok(text(obj, "NM")?.unwrap_or_else(|| format!("mupdf-{}-{}", page, index)))
+}
+
+pub(crate) fn read_annotation(
+    obj: &PdfObject,
+    page: i32,
+    name: String,
+    names: &HashMap<i32, String>,
+) -> Result<AnnotationData, Error> {
+    let subtype = name_or_default(obj, "Subtype")?;
+    let subtype = PdfAnnotationType::from_pdf_name(&subtype).unwrap_or(PdfAnnotationType::Unknown);
+    let border_width = match obj.get_dict("BS")? {
+        Some(bs) if bs.is_dict()? => number(&bs, "W")?,
+        _ => numbers(obj, "Border")?.get(2).copied(),
+    };
+    let line = match *numbers(obj, "L")? {
+        [x1, y1, x2, y2] => Some([Point::new(x1, y1), Point::new(x2, y2)]),
+        _ => None,
+    };
+    let line_endings = match obj.get_dict("LE")? {
+        Some(le) if le.is_array()? && le.len()? == 2 => {
+            let style = |i| -> Result<LineEndingStyle, Error> {
+                let style = match le.get_array(i)? {
+                    Some(name) if name.is_name()? => {
+                        LineEndingStyle::from_pdf_name(&String::from_utf8_lossy(name.as_name()?))
+                    }
+                    _ => None,
+                };
+                Ok(style.unwrap_or(LineEndingStyle::None))
+            };
+            Some([style(0)?, style(1)?])
+        }
+        _ => None,
+    };
+    let ink_list = match obj.get_dict("InkList")? {
+        Some(list) if list.is_array()? => (0..list.len()? as i32)
+            .map(|i| match list.get_array(i)? {
+                Some(stroke) => Ok(points(&array_numbers(&stroke)?)),
+                None => Ok(Vec::new()),
+            })
+            .collect::<Result<_, Error>>()?,
+        _ => Vec::new(),
+    };
+    let in_reply_to = match obj.get_dict("IRT")? {
+        Some(irt) if irt.is_indirect()? => names.get(&irt.as_indirect()?).cloned(),
+        Some(irt) if irt.is_dict()? => text(&irt, "NM")?,
+        _ => None,
+    };
+    let popup = match obj.get_dict("Popup")? {
+        Some(popup) if popup.is_dict()? => Some(PopupData {
+            rect: rect(&numbers(&popup, "Rect")?),
+            open: match popup.get_dict("Open")? {
+                Some(open) => open.as_bool()?,
+                None => false,
+            },
+        }),
+        _ => None,
+    };
+
+    Ok(AnnotationData {
+        page,
+        subtype,
+        name,
+        rect: rect(&numbers(obj, "Rect")?),
+        contents: text(obj, "Contents")?,
+        author: text(obj, "T")?,
+        subject: text(obj, "Subj")?,
+        modified: text(obj, "M")?,
+        created: text(obj, "CreationDate")?,
+        flags: number(obj, "F")?.map_or(0, |f| f as i32),
+        color: numbers(obj, "C")?,
+        interior_color: numbers(obj, "IC")?,
+        opacity: number(obj, "CA")?,
+        border_width,
+        icon: name(obj, "Name")?,
+        quad_points: numbers(obj, "QuadPoints")?
+            .chunks_exact(8)
+            .map(|q| {
+                Quad::new(
+                    Point::new(q[0], q[1]),
+                    Point::new(q[2], q[3]),
+                    Point::new(q[4], q[5]),
+                    Point::new(q[6], q[7]),
+                )
+            })
+            .collect(),
+        ink_list,
+        vertices: points(&numbers(obj, "Vertices")?),
+        line,
+        line_endings,
+        default_appearance: text(obj, "DA")?,
+        justification: number(obj, "Q")?.map(|q| q as i32),
+        in_reply_to,
+        reply_type: name(obj, "RT")?,
+        state: text(obj, "State")?,
+        state_model: text(obj, "StateModel")?,
+        popup,
+    })
+}
+
+pub(crate) fn name_or_default(obj: &PdfObject, key: &str) -> Result<String, Error> {
+    Ok(name(obj, key)?.unwrap_or_default())
+}
+
+impl PdfDocument {
+    /// The properties of the annotations of all pages, except links, form fields and popups,
+    /// which are part of the annotation they belong to.
+    pub fn annotations_data(&self) -> Result<Vec<AnnotationData>, Error> {
+        let mut annotations = Vec::new();
+        for page_no in 0..self.page_count()? {
+            let page = PdfPage::try_from(self.load_page(page_no)?)?;
+            let objects: Vec<PdfObject> = page.annotations().map(|a| a.object()).collect();
+            // Replies refer to their parent through an indirect reference
+            let mut names = HashMap::new();
+            for (index, obj) in objects.iter().enumerate() {
+                if obj.is_indirect()? {
+                    names.insert(obj.as_indirect()?, annotation_name(obj, page_no, index)?);
+                }
+            }
+            for (index, obj) in objects.iter().enumerate() {
+                let subtype = name_or_default(obj, "Subtype")?;
+                if matches!(subtype.as_str(), "Link" | "Widget" | "Popup") {
+                    continue;
+                }
+                let name = annotation_name(obj, page_no, index)?;
+                annotations.push(read_annotation(obj, page_no, name, &names)?);
+            }
+        }
+        Ok(annotations)
+    }
+
+    /// Add annotations, replacing those on the same page with the same name, and return how
+    /// many were added.
+    ///
+    /// Replies are linked to the annotation they reply to by name, which must be on the same
+    /// page.
+    pub fn import_annotations(&mut self, annotations: &[AnnotationData]) -> Result<usize, Error> {
+        let mut replies = Vec::new();
+        for data in annotations {
+            let subtype = data.subtype;
+            if matches!(
+                subtype,
+                PdfAnnotationType::Unknown
+                    | PdfAnnotationType::Link
+                    | PdfAnnotationType::Widget
+                    | PdfAnnotationType::Popup
+            ) {
+                return Err(invalid(format!(
+                    "can't import {:?} annotations",
+                    data.subtype
+                )));
+            }
+            let mut page = PdfPage::try_from(self.load_page(data.page)?)?;
+            if !data.name.is_empty() {
+                let existing: Vec<_> = page
+                    .annotations()
+                    .filter(|a| text(&a.object(), "NM").ok().flatten().as_ref() == Some(&data.name))
+                    .collect();
+                for annot in existing {
+                    page.delete_annotation(&annot)?;
+                }
+            }
+
+            let mut annot = page.create_annotation(subtype)?;
+            let mut obj = annot.object();
+            self.write_annotation(&mut obj, data)?;
+            if let Some(popup_data) = &data.popup {
+                let popup = page.create_annotation(PdfAnnotationType::Popup)?;
+                let mut popup_obj = popup.object();
+                popup_obj.dict_put("Rect", self.number_array(&rect_numbers(popup_data.rect))?)?;
+                popup_obj.dict_put("Open", PdfObject::new_bool(popup_data.open))?;
+                popup_obj.dict_put("Parent", obj.try_clone()?)?;
+                obj.dict_put("Popup", popup_obj)?;
+            }
+            annot.update()?;
+            if let Some(target) = &data.in_reply_to {
+                replies.push((data.page, obj, target.clone()));
+            }
+        }
+
+        for (page_no, mut obj, target) in replies {
+            let page = PdfPage::try_from(self.load_page(page_no)?)?;
+            let parent = page
+                .annotations()
+                .map(|a| a.object())
+                .find(|o| text(o, "NM").ok().flatten().as_ref() == Some(&target));
+            if let Some(parent) = parent {
+                obj.dict_put("IRT", parent)?;
+            }
+        }
+        Ok(annotations.len())
+    }
+
+    pub(crate) fn number_array(&self, values: &[f32]) -> Result<PdfObject, Error> {
+        let mut array = self.new_array()?;
+        for &v in values {
+            array.array_push(PdfObject::new_real(v)?)?;
+        }
+        Ok(array)
+    }
+
+    pub(crate) fn write_annotation(
+        &self,
+        obj: &mut PdfObject,
+        data: &AnnotationData,
+    ) -> Result<(), Error> {
+        obj.dict_put("Rect", self.number_array(&rect_numbers(data.rect))?)?;
+        obj.dict_put("F", PdfObject::new_int(data.flags)?)?;
+        let texts = [
+            ("NM", Some(&data.name).filter(|n| !n.is_empty())),
+            ("Contents", data.contents.as_ref()),
+            ("T", data.author.as_ref()),
+            ("Subj", data.subject.as_ref()),
+            ("M", data.modified.as_ref()),
+            ("CreationDate", data.created.as_ref()),
+            ("DA", data.default_appearance.as_ref()),
+            ("State", data.state.as_ref()),
+            ("StateModel", data.state_model.as_ref()),
+        ];
+        for (key, value) in texts {
+            if let Some(value) = value {
+                obj.dict_put(key, PdfObject::new_string(value)?)?;
+            }
+        }
+        for (key, value) in [("Name", &data.icon), ("RT", &data.reply_type)] {
+            if let Some(value) = value {
+                obj.dict_put(key, PdfObject::new_name(value)?)?;
+            }
+        }
+        for (key, value) in [("C", &data.color), ("IC", &data.interior_color)] {
+            if !value.is_empty() {
+                obj.dict_put(key, self.number_array(value)?)?;
+            }
+        }
+        if let Some(opacity) = data.opacity {
+            obj.dict_put("CA", PdfObject::new_real(opacity)?)?;
+        }
+        if let Some(width) = data.border_width {
+            let mut bs = self.new_dict()?;
+            bs.dict_put("W", PdfObject::new_real(width)?)?;
+            obj.dict_put("BS", bs)?;
+        }
+        if let Some(q) = data.justification {
+            obj.dict_put("Q", PdfObject::new_int(q)?)?;
+        }
+        if !data.quad_points.is_empty() {
+            let values: Vec<f32> = data
+                .quad_points
+                .iter()
+                .flat_map(|q| [q.ul, q.ur, q.ll, q.lr])
+                .flat_map(|p| [p.x, p.y])
+                .collect();
+            obj.dict_put("QuadPoints", self.number_array(&values)?)?;
+        }
+        if !data.ink_list.is_empty() {
+            let mut list = self.new_array()?;
+            for stroke in &data.ink_list {
+                let values: Vec<f32> = stroke.iter().flat_map(|p| [p.x, p.y]).collect();
+                list.array_push(self.number_array(&values)?)?;
+            }
+            obj.dict_put("InkList", list)?;
+        }
+        if !data.vertices.is_empty() {
+            let values: Vec<f32> = data.vertices.iter().flat_map(|p| [p.x, p.y]).collect();
+            obj.dict_put("Vertices", self.number_array(&values)?)?;
+        }
+        if let Some([a, b]) = data.line {
+            obj.dict_put("L", self.number_array(&[a.x, a.y, b.x, b.y])?)?;
+        }
+        if let Some(endings) = data.line_endings {
+            let mut le = self.new_array()?;
+            for style in endings {
+                le.array_push(PdfObject::new_name(style.pdf_name())?)?;
+            }
+            obj.dict_put("LE", le)?;
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn rect_numbers(rect: Rect) -> [f32; 4] {
+    [rect.x0, rect.y0, rect.x1, rect.y1]
+}
+
+#[cfg(test)]
+mod test {
+    use super::AnnotationData;
+    use crate::pdf::{PdfAnnotationType, PdfDocument};
+    use crate::{Point, Quad, Rect};
+
+    #[test]
+    fn test_annotations_data_round_trip() {
+        let mut doc = PdfDocument::open("tests/files/dummy.pdf").unwrap();
+        assert!(doc.annotations_data().unwrap().is_empty());
+
+        let highlight = AnnotationData {
+            subtype: PdfAnnotationType::Highlight,
+            name: "hl-1".to_owned(),
+            rect: Rect::new(56.0, 750.0, 116.0, 775.0),
+            contents: Some("Check this".to_owned()),
+            author: Some("Reviewer".to_owned()),
+            color: vec![1.0, 1.0, 0.0],
+            quad_points: vec![Quad::new(
+                Point::new(56.0, 775.0),
+                Point::new(116.0, 775.0),
+                Point::new(56.0, 750.0),
+                Point::new(116.0, 750.0),
+            )],
+            ..Default::default()
+        };
+        let reply = AnnotationData {
+            name: "reply-1".to_owned(),
+            rect: Rect::new(0.0, 0.0, 20.0, 20.0),
+            contents: Some("Done".to_owned()),
+            in_reply_to: Some("hl-1".to_owned()),
+            reply_type: Some("R".to_owned()),
+            ..Default::default()
+        };
+        let ink = AnnotationData {
+            subtype: PdfAnnotationType::Ink,
+            name: "ink-1".to_owned(),
+            rect: Rect::new(100.0, 100.0, 200.0, 200.0),
+            ink_list: vec![vec![Point::new(110.0, 110.0), Point::new(190.0, 190.0)]],
+            border_width: Some(2.0),
+            ..Default::default()
+        };
+        let imported = [highlight.clone(), reply.clone(), ink.clone()];
+        assert_eq!(doc.import_annotations(&imported).unwrap(), 3);
+        // Importing again replaces instead of duplicating
+        doc.import_annotations(&imported).unwrap();
+
+        let data = doc.annotations_data().unwrap();
+        assert_eq!(data.len(), 3);
+        let find = |name: &str| data.iter().find(|a| a.name == name).unwrap();
+        let found = find("hl-1");
+        assert_eq!(found.subtype, PdfAnnotationType::Highlight);
+        assert_eq!(found.contents, highlight.contents);
+        assert_eq!(found.author, highlight.author);
+        assert_eq!(found.color, highlight.color);
+        assert_eq!(found.quad_points, highlight.quad_points);
+        assert_eq!(find("reply-1").in_reply_to.as_deref(), Some("hl-1"));
+        assert_eq!(find("ink-1").ink_list, ink.ink_list);
+        assert_eq!(find("ink-1").border_width, Some(2.0));
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_annotations_data_json() {
+        let json = r#"[
+            {
+                "subtype": "Highlight",
+                "name": "hl-1",
+                "rect": { "x0": 56.0, "y0": 750.0, "x1": 116.0, "y1": 775.0 },
+                "color": [1.0, 1.0, 0.0],
+                "author": "Reviewer",
+                "modified": "D:20240131120000Z",
+                "contents": "Check this"
+            },
+            {
+                "name": "reply-1",
+                "rect": { "x0": 0.0, "y0": 0.0, "x1": 20.0, "y1": 20.0 },
+                "contents": "Done",
+                "in_reply_to": "hl-1"
+            }
+        ]"#;
+        let annotations: Vec<AnnotationData> = serde_json::from_str(json).unwrap();
+        assert_eq!(annotations[1].subtype, PdfAnnotationType::Text);
+        assert_eq!(annotations[1].flags, 4);
+
+        let mut doc = PdfDocument::open("tests/files/dummy.pdf").unwrap();
+        doc.import_annotations(&annotations).unwrap();
+        let exported = serde_json::to_string(&doc.annotations_data().unwrap()).unwrap();
+
+        let mut copy = PdfDocument::open("tests/files/dummy.pdf").unwrap();
+        let annotations: Vec<AnnotationData> = serde_json::from_str(&exported).unwrap();
+        copy.import_annotations(&annotations).unwrap();
+        let data = copy.annotations_data().unwrap();
+        assert_eq!(data.len(), 2);
+        assert_eq!(data[0].author.as_deref(), Some("Reviewer"));
+        assert_eq!(data[0].modified.as_deref(), Some("D:20240131120000Z"));
+        assert_eq!(data[1].in_reply_to.as_deref(), Some("hl-1"));
+    }
+}
@@ -0,0 +1,164 @@
+use crate::pdf::{PdfDocument, PdfObject};
+use crate::{Error, Point, Rect};
+
+/// A piece of an article on one page, such as a column.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArticleBead {
+    pub page_no: i32,
+    /// The area of the bead in page coordinates.
+    pub rect: Rect,
+}
+
+/// An article thread, the beads of a story that flows across columns and pages in reading
+/// order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Article {
+    pub title: Option<String>,
+    pub author: Option<String>,
+    pub beads: Vec<ArticleBead>,
+}
+
+impl Article {
+    /// The index of the bead on page `page_no` containing `point`, to continue reading at the
+    /// bead after it.
+    pub fn bead_at(&self, page_no: i32, point: Point) -> Option<usize> {
+        self.beads
+            .iter()
+            .position(|bead| bead.page_no == page_no && bead.rect.contains(point.x, point.y))
+    }
+}
+
+/// The bounding box of `rect` transformed by the page matrix of `page`.
+fn page_rect(page: &PdfObject, rect: &PdfObject) -> Result<Rect, Error> {
+    let mut values = [0.0; 4];
+    for (i, value) in values.iter_mut().enumerate() {
+        if let Some(number) = rect.get_array(i as i32)? {
+            *value = number.as_float()?;
+        }
+    }
+    let ctm = page.page_ctm()?;
+    let [x0, y0, x1, y1] = values;
+    let corners =
+        [(x0, y0), (x0, y1), (x1, y0), (x1, y1)].map(|(x, y)| Point::new(x, y).transform(&ctm));
+    let xs = corners.map(|p| p.x);
+    let ys = corners.map(|p| p.y);
+    Ok(Rect::new(
+        xs.into_iter().fold(f32::INFINITY, f32::min),
+        ys.into_iter().fold(f32::INFINITY, f32::min),
+        xs.into_iter().fold(f32::NEG_INFINITY, f32::max),
+        ys.into_iter().fold(f32::NEG_INFINITY, f32::max),
+    ))
+}
+
+impl PdfDocument {
+    /// The article threads of the document, from the `/Threads` array of the catalog.
+    pub fn articles(&self) -> Result<Vec<Article>, Error> {
+        let mut articles = Vec::new();
+        let Some(threads) = self.catalog()?.get_dict("Threads")? else {
+            return Ok(articles);
+        };
+        for i in 0..threads.len()? as i32 {
+            let Some(thread) = threads.get_array(i)? else {
+                continue;
+            };
+            let mut beads = Vec::new();
+            let first = thread.get_dict("F")?;
+            let first_num = match &first {
+                Some(first) if first.is_indirect()? => Some(first.as_indirect()?),
+                _ => None,
+            };
+            let mut bead = first;
+            // The beads form a ring, stop when it comes back to the first one
+            while let Some(current) = bead.take() {
+                if let (Some(page), Some(rect)) = (current.get_dict("P")?, current.get_dict("R")?) {
+                    if let Some(page_no) = self.page_number(&page)? {
+                        beads.push(ArticleBead {
+                            page_no,
+                            rect: page_rect(&page, &rect)?,
+                        });
+                    }
+                }
+                bead = match current.get_dict("N")? {
+                    Some(next) if next.is_indirect()? && Some(next.as_indirect()?) != first_num => {
+                        Some(next)
+                    }
+                    _ => None,
+                };
+                // Guard against broken rings
+                if beads.len() > 10000 {
+                    break;
+                }
+            }
+            let info = thread.get_dict("I")?;
+            let text = |key: &str| -> Result<Option<String>, Error> {
+                match &info {
+                    Some(info) => info.path_string(key),
+                    None => Ok(None),
+                }
+            };
+            articles.push(Article {
+                title: text("Title")?,
+                author: text("Author")?,
+                beads,
+            });
+        }
+        Ok(articles)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ArticleBead;
+    use crate::pdf::PdfDocument;
+    use crate::{Point, Rect};
+
+    #[test]
+    fn test_articles() {
+        let mut doc = PdfDocument::new();
+        doc.new_page((300.0, 400.0)).unwrap();
+        let page = doc.find_page(0).unwrap();
+        let mut thread = doc
+            .add_object(
+                &doc.new_object_from_str("<< /Type /Thread /I << /Title (Story) >> >>")
+                    .unwrap(),
+            )
+            .unwrap();
+        let mut beads = Vec::new();
+        for rect in ["[0 200 150 400]", "[150 200 300 400]"] {
+            let mut bead = doc
+                .new_object_from_str(&format!("<< /Type /Bead /R {} >>", rect))
+                .unwrap();
+            bead.dict_put("P", page.clone()).unwrap();
+            bead.dict_put("T", thread.clone()).unwrap();
+            beads.push(doc.add_object(&bead).unwrap());
+        }
+        beads[0].dict_put("N", beads[1].clone()).unwrap();
+        beads[0].dict_put("V", beads[1].clone()).unwrap();
+        beads[1].dict_put("N", beads[0].clone()).unwrap();
+        beads[1].dict_put("V", beads[0].clone()).unwrap();
+        thread.dict_put("F", beads[0].clone()).unwrap();
+        let mut threads = doc.new_array().unwrap();
+        threads.array_push(thread).unwrap();
+        doc.catalog().unwrap().dict_put("Threads", threads).unwrap();
+
+        let articles = doc.articles().unwrap();
+        assert_eq!(articles.len(), 1);
+        assert_eq!(articles[0].title.as_deref(), Some("Story"));
+        assert_eq!(articles[0].author, None);
+        assert_eq!(
+            articles[0].beads,
+            [
+                ArticleBead {
+                    page_no: 0,
+                    rect: Rect::new(0.0, 0.0, 150.0, 200.0),
+                },
+                ArticleBead {
+                    page_no: 0,
+                    rect: Rect::new(150.0, 0.0, 300.0, 200.0),
+                },
+            ]
+        );
+        assert_eq!(articles[0].bead_at(0, Point::new(200.0, 100.0)), Some(1));
+        assert_eq!(articles[0].bead_at(0, Point::new(200.0, 300.0)), None);
+    }
+}
@@ -0,0 +1,73 @@
+use mupdf_sys::*;
+
+use crate::pdf::{PdfDocument, PdfObject};
+use crate::{context, Bitmap, Error};
+
+impl PdfDocument {
+    /// Add a black and white image compressed with CCITT Group 4, which for scans is several
+    /// times smaller than the deflate compression [`PdfDocument::add_image`] uses.
+    pub fn add_bilevel_image(&mut self, bitmap: &Bitmap) -> Result<PdfObject, Error> {
+        let (w, h) = (bitmap.width(), bitmap.height());
+        let dict = self.new_object_from_str(&format!(
+            "<< /Type /XObject /Subtype /Image /Width {w} /Height {h} /ColorSpace /DeviceGray \
+             /BitsPerComponent 1 /Filter /CCITTFaxDecode \
+             /DecodeParms << /K -1 /Columns {w} /Rows {h} >> >>"
+        ))?;
+        let mut image = self.add_object(&dict)?;
+        image.write_raw_stream_buffer(&bitmap.to_ccitt_g4()?)?;
+        Ok(image)
+    }
+
+    /// Recompress the black and white images and image masks of the document with CCITT
+    /// Group 4 wherever that makes them smaller, returning how many were recompressed.
+    ///
+    /// The decoded samples are unchanged. Images already compressed with CCITT fax or JBIG2 are
+    /// left alone; MuPDF has no JBIG2 encoder, so JBIG2's better ratios aren't available here.
+    pub fn recompress_bilevel_images(&mut self) -> Result<usize, Error> {
+        let count =
+            unsafe { ffi_try!(mupdf_pdf_recompress_bilevel_images(context(), self.inner)) }?;
+        Ok(count as usize)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::pdf::PdfDocument;
+    use crate::{Bitmap, Buffer, Colorspace, Pixmap};
+
+    #[test]
+    fn test_pdf_recompress_bilevel_images() {
+        let mut doc = PdfDocument::new();
+        let samples: Vec<u8> = (0..64 * 8)
+            .map(|i| match (i / 8) % 16 {
+                0..=5 => 0x00,
+                6 => 0xf0,
+                7 => 0x0f,
+                _ => 0xff,
+            })
+            .collect();
+        let dict = doc
+            .new_object_from_str(
+                "<< /Subtype /Image /Width 64 /Height 64 /ColorSpace /DeviceGray \
+                 /BitsPerComponent 1 >>",
+            )
+            .unwrap();
+        let mut image = doc.add_object(&dict).unwrap();
+        image
+            .write_stream_buffer(&Buffer::from_bytes(&samples).unwrap())
+            .unwrap();
+
+        assert_eq!(doc.recompress_bilevel_images().unwrap(), 1);
+        let image = image.resolve().unwrap().unwrap();
+        let filter = image.get_dict("Filter").unwrap().unwrap();
+        assert_eq!(filter.as_name().unwrap(), b"CCITTFaxDecode");
+        assert_eq!(image.read_stream().unwrap(), samples);
+        assert_eq!(doc.recompress_bilevel_images().unwrap(), 0);
+
+        let mut pixmap = Pixmap::new_with_w_h(&Colorspace::device_gray(), 16, 8, false).unwrap();
+        pixmap.clear_with(255).unwrap();
+        let bitmap = Bitmap::from_pixmap(&pixmap).unwrap();
+        let image = doc.add_bilevel_image(&bitmap).unwrap();
+        assert_eq!(image.read_stream().unwrap(), vec![0xff; 16]);
+    }
+}
@@ -0,0 +1,313 @@
+use std::fmt;
+
+use crate::pdf::der::{self, Element};
+use crate::pdf::signature::Cms;
+use crate::pdf::Signature;
+use crate::Error;
+
+const OID_COMMON_NAME: &[u8] = &[0x55, 0x04, 0x03];
+const OID_COUNTRY: &[u8] = &[0x55, 0x04, 0x06];
+const OID_ORGANIZATION: &[u8] = &[0x55, 0x04, 0x0a];
+const OID_ORGANIZATIONAL_UNIT: &[u8] = &[0x55, 0x04, 0x0b];
+const OID_EMAIL: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x09, 0x01];
+
+const UTC_TIME: u8 = 0x17;
+const GENERALIZED_TIME: u8 = 0x18;
+const BMP_STRING: u8 = 0x1e;
+
+/// The parts of an X.509 name that identify a signer.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DistinguishedName {
+    pub common_name: Option<String>,
+    pub organization: Option<String>,
+    pub organizational_unit: Option<String>,
+    pub email: Option<String>,
+    pub country: Option<String>,
+}
+
+impl fmt::Display for DistinguishedName {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let parts = [
+            ("CN", &self.common_name),
+            ("O", &self.organization),
+            ("OU", &self.organizational_unit),
+            ("E", &self.email),
+            ("C", &self.country),
+        ];
+        let mut first = true;
+        for (key, value) in parts {
+            if let Some(value) = value {
+                if !first {
+                    write!(f, ", ")?;
+                }
+                write!(f, "{}={}", key, value)?;
+                first = false;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The certificate of the signer of a signature, from [`Signature::signer_info`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignerInfo {
+    pub subject: DistinguishedName,
+    pub issuer: DistinguishedName,
+    /// The serial number of the certificate, big endian.
+    pub serial: Vec<u8>,
+    /// Start of the validity of the certificate, as `YYYY-MM-DDTHH:MM:SSZ`.
+    pub not_before: String,
+    /// End of the validity of the certificate, as `YYYY-MM-DDTHH:MM:SSZ`.
+    pub not_after: String,
+    /// The DER encoded certificates the signature comes with, the signer's first.
+    pub certificates: Vec<Vec<u8>>,
+}
+
+fn decode_string(value: &Element) -> String {
+    if value.tag == BMP_STRING {
+        let units: Vec<u16> = value
+            .contents
+            .chunks_exact(2)
+            .map(|c| u16::from_be_bytes([c[0], c[1]]))
+            .collect();
+        String::from_utf16_lossy(&units)
+    } else {
+        // UTF8String, PrintableString, IA5String and the like
+        String::from_utf8_lossy(value.contents).into_owned()
+    }
+}
+
+fn parse_name(name: &Element) -> Option<DistinguishedName> {
+    let mut dn = DistinguishedName::default();
+    for rdn in name.children()? {
+        for attribute in rdn.children()? {
+            let [oid, value] = attribute.children()?[..] else {
+                return None;
+            };
+            let field = match oid.contents {
+                OID_COMMON_NAME => &mut dn.common_name,
+                OID_COUNTRY => &mut dn.country,
+                OID_ORGANIZATION => &mut dn.organization,
+                OID_ORGANIZATIONAL_UNIT => &mut dn.organizational_unit,
+                OID_EMAIL => &mut dn.email,
+                _ => continue,
+            };
+            field.get_or_insert_with(|| decode_string(&value));
+        }
+    }
+    Some(dn)
+}
+
+fn parse_time(time: &Element) -> Option<String> {
+    let text = std::str::from_utf8(time.contents).ok()?;
+    let digits = text.strip_suffix('Z')?;
+    let (year, rest) = match time.tag {
+        UTC_TIME => {
+            let yy: u32 = digits.get(..2)?.parse().ok()?;
+            (if yy < 50 { 2000 + yy } else { 1900 + yy }, &digits[2..])
+        }
+        GENERALIZED_TIME => (digits.get(..4)?.parse().ok()?, &digits[4..]),
+        _ => return None,
+    };
+    if rest.len() < 10 || !rest.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    Some(format!(
+        "{:04}-{}-{}T{}:{}:{}Z",
+        year,
+        &rest[0..2],
+        &rest[2..4],
+        &rest[4..6],
+        &rest[6..8],
+        &rest[8..10]
+    ))
+}
+
+/// The parts of a certificate needed to identify it and its subject.
+struct Certificate<'a> {
+    serial: Element<'a>,
+    issuer: Element<'a>,
+    validity: Element<'a>,
+    subject: Element<'a>,
+}
+
+impl<'a> Certificate<'a> {
+    fn parse(certificate: &Element<'a>) -> Option<Self> {
+        let tbs = *certificate.children()?.first()?;
+        let mut fields = tbs.children()?.into_iter();
+        let mut serial = fields.next()?;
+        // Skip the explicit version
+        if serial.tag == der::context(0) {
+            serial = fields.next()?;
+        }
+        let _algorithm = fields.next()?;
+        Some(Self {
+            serial,
+            issuer: fields.next()?,
+            validity: fields.next()?,
+            subject: fields.next()?,
+        })
+    }
+
+    /// Whether this is the certificate of a signer identified by issuer and serial number.
+    fn matches(&self, signer_id: &Element) -> bool {
+        match signer_id.children().as_deref() {
+            Some([issuer, serial]) if signer_id.tag == der::SEQUENCE => {
+                issuer.raw == self.issuer.raw && serial.contents == self.serial.contents
+            }
+            _ => false,
+        }
+    }
+}
+
+fn signer_info(cms: &[u8]) -> Option<SignerInfo> {
+    let cms = Cms::parse(cms)?;
+    let mut certificates = cms.certificates();
+    let signer_id = cms.signer_id()?;
+    // Signers identified by subject key identifier are assumed to come first
+    let index = certificates
+        .iter()
+        .position(|c| Certificate::parse(c).is_some_and(|c| c.matches(&signer_id)))
+        .unwrap_or(0);
+    if certificates.is_empty() {
+        return None;
+    }
+    let signer = certificates.remove(index);
+    certificates.insert(0, signer);
+
+    let certificate = Certificate::parse(&signer)?;
+    let [not_before, not_after] = certificate.validity.children()?[..] else {
+        return None;
+    };
+    Some(SignerInfo {
+        subject: parse_name(&certificate.subject)?,
+        issuer: parse_name(&certificate.issuer)?,
+        serial: certificate.serial.contents.to_vec(),
+        not_before: parse_time(&not_before)?,
+        not_after: parse_time(&not_after)?,
+        certificates: certificates.iter().map(|c| c.raw.to_vec()).collect(),
+    })
+}
+
+impl Signature {
+    /// The certificate of the signer, `None` for unsigned fields and signatures without
+    /// certificates. The certificate is not validated.
+    pub fn signer_info(&self) -> Result<Option<SignerInfo>, Error> {
+        let contents = self.contents()?;
+        if contents.is_empty() {
+            return Ok(None);
+        }
+        match signer_info(&contents) {
+            Some(info) => Ok(Some(info)),
+            None if Cms::parse(&contents).is_some() => Ok(None),
+            None => Err(Error::InvalidAnnotation(
+                "malformed CMS signature".to_owned(),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{signer_info, DistinguishedName};
+    use crate::pdf::der::{self, encode, encode_children};
+
+    fn name(common_name: &str, organization: &str) -> Vec<u8> {
+        let attribute = |oid: &[u8], tag: u8, value: &str| {
+            encode(
+                der::SET,
+                &encode_children(
+                    der::SEQUENCE,
+                    &[&encode(der::OID, oid), &encode(tag, value.as_bytes())],
+                ),
+            )
+        };
+        encode_children(
+            der::SEQUENCE,
+            &[
+                &attribute(super::OID_COUNTRY, 0x13, "NL"),
+                &attribute(super::OID_ORGANIZATION, 0x0c, organization),
+                &attribute(super::OID_COMMON_NAME, 0x0c, common_name),
+            ],
+        )
+    }
+
+    fn certificate(subject: &[u8], issuer: &[u8], serial: &[u8]) -> Vec<u8> {
+        let validity = encode_children(
+            der::SEQUENCE,
+            &[
+                &encode(super::UTC_TIME, b"240131120000Z"),
+                &encode(super::GENERALIZED_TIME, b"20500131120000Z"),
+            ],
+        );
+        let tbs = encode_children(
+            der::SEQUENCE,
+            &[
+                &encode(der::context(0), &encode(der::INTEGER, &[2])),
+                &encode(der::INTEGER, serial),
+                &encode(der::SEQUENCE, &[]),
+                issuer,
+                &validity,
+                subject,
+            ],
+        );
+        encode_children(der::SEQUENCE, &[&tbs, &encode(der::SEQUENCE, &[])])
+    }
+
+    #[test]
+    fn test_signer_info() {
+        let root = name("Root CA", "Trust Inc");
+        let signer = name("Jane Doe", "Acme");
+        let ca_certificate = certificate(&root, &root, &[1]);
+        let signer_certificate = certificate(&signer, &root, &[0x01, 0x02]);
+        let signer_id = encode_children(
+            der::SEQUENCE,
+            &[&root, &encode(der::INTEGER, &[0x01, 0x02])],
+        );
+        let signer_info_der = encode_children(
+            der::SEQUENCE,
+            &[
+                &encode(der::INTEGER, &[1]),
+                &signer_id,
+                &encode(der::SEQUENCE, &[]),
+                &encode(der::OCTET_STRING, b"signature value"),
+            ],
+        );
+        let signed_data = encode_children(
+            der::SEQUENCE,
+            &[
+                &encode(der::INTEGER, &[1]),
+                &encode(der::SET, &[]),
+                &encode(der::SEQUENCE, &[]),
+                &encode_children(der::context(0), &[&ca_certificate, &signer_certificate]),
+                &encode(der::SET, &signer_info_der),
+            ],
+        );
+        let mut cms = encode_children(
+            der::SEQUENCE,
+            &[
+                &encode(der::OID, &[0x2a]),
+                &encode(der::context(0), &signed_data),
+            ],
+        );
+        // Signatures are padded to the room reserved for them
+        cms.resize(cms.len() + 100, 0);
+
+        let info = signer_info(&cms).unwrap();
+        assert_eq!(
+            info.subject,
+            DistinguishedName {
+                common_name: Some("Jane Doe".to_owned()),
+                organization: Some("Acme".to_owned()),
+                country: Some("NL".to_owned()),
+                ..Default::default()
+            }
+        );
+        assert_eq!(info.subject.to_string(), "CN=Jane Doe, O=Acme, C=NL");
+        assert_eq!(info.issuer.common_name.as_deref(), Some("Root CA"));
+        assert_eq!(info.serial, [0x01, 0x02]);
+        assert_eq!(info.not_before, "2024-01-31T12:00:00Z");
+        assert_eq!(info.not_after, "2050-01-31T12:00:00Z");
+        assert_eq!(info.certificates, [signer_certificate, ca_certificate]);
+    }
+}
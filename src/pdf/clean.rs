@@ -0,0 +1,515 @@
+use crate::pdf::validation::{dict_objects, name_of};
+use crate::pdf::{Encryption, PdfDocument, PdfObject, PdfWriteOptions};
+use crate::Error;
+
+/// What [`PdfDocument::clean`] does, after the options of `mutool clean`. Everything is off by
+/// default, which rewrites the document as it is, repairing a broken cross reference table.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CleanOptions {
+    write: PdfWriteOptions,
+    strip_javascript: bool,
+    strip_embedded_files: bool,
+    strip_metadata: bool,
+    remove_encryption: bool,
+}
+
+impl CleanOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// How thoroughly unused objects are dropped: 1 drops them, 2 also compacts the cross
+    /// reference table, 3 also merges duplicate objects and 4 also merges duplicate streams.
+    pub fn garbage_level(&self) -> i32 {
+        self.write.garbage_level()
+    }
+
+    pub fn set_garbage_level(&mut self, value: i32) -> &mut Self {
+        self.write.set_garbage_level(value);
+        self
+    }
+
+    /// Decompress all streams.
+    pub fn decompress(&self) -> bool {
+        self.write.decompress()
+    }
+
+    pub fn set_decompress(&mut self, value: bool) -> &mut Self {
+        self.write.set_decompress(value);
+        self
+    }
+
+    /// Compress all streams that aren't.
+    pub fn compress(&self) -> bool {
+        self.write.compress()
+    }
+
+    pub fn set_compress(&mut self, value: bool) -> &mut Self {
+        self.write.set_compress(value);
+        self
+    }
+
+    /// Compress font programs that aren't.
+    pub fn compress_fonts(&self) -> bool {
+        self.write.compress_fonts()
+    }
+
+    pub fn set_compress_fonts(&mut self, value: bool) -> &mut Self {
+        self.write.set_compress_fonts(value);
+        self
+    }
+
+    /// Compress images that aren't.
+    pub fn compress_images(&self) -> bool {
+        self.write.compress_images()
+    }
+
+    pub fn set_compress_images(&mut self, value: bool) -> &mut Self {
+        self.write.set_compress_images(value);
+        self
+    }
+
+    /// Pretty-print objects with indentation.
+    pub fn pretty(&self) -> bool {
+        self.write.pretty()
+    }
+
+    pub fn set_pretty(&mut self, value: bool) -> &mut Self {
+        self.write.set_pretty(value);
+        self
+    }
+
+    /// Hex encode binary streams.
+    pub fn ascii(&self) -> bool {
+        self.write.ascii()
+    }
+
+    pub fn set_ascii(&mut self, value: bool) -> &mut Self {
+        self.write.set_ascii(value);
+        self
+    }
+
+    /// Rewrite the content streams of pages, forms and annotations with normalized syntax.
+    pub fn clean_content(&self) -> bool {
+        self.write.clean()
+    }
+
+    pub fn set_clean_content(&mut self, value: bool) -> &mut Self {
+        self.write.set_clean(value);
+        self
+    }
+
+    /// Rewrite content streams, dropping operators that are invalid or have no effect.
+    pub fn sanitize(&self) -> bool {
+        self.write.sanitize()
+    }
+
+    pub fn set_sanitize(&mut self, value: bool) -> &mut Self {
+        self.write.set_sanitize(value);
+        self
+    }
+
+    /// Write the document unencrypted. Otherwise an encrypted document keeps its encryption,
+    /// and the cleaned document needs to be authenticated again unless its user password is
+    /// empty.
+    pub fn remove_encryption(&self) -> bool {
+        self.remove_encryption
+    }
+
+    pub fn set_remove_encryption(&mut self, value: bool) -> &mut Self {
+        self.remove_encryption = value;
+        self
+    }
+
+    /// Remove document level JavaScript and the JavaScript actions of pages, annotations and
+    /// form fields.
+    pub fn strip_javascript(&self) -> bool {
+        self.strip_javascript
+    }
+
+    pub fn set_strip_javascript(&mut self, value: bool) -> &mut Self {
+        self.strip_javascript = value;
+        self
+    }
+
+    /// Remove embedded files and file attachment annotations.
+    pub fn strip_embedded_files(&self) -> bool {
+        self.strip_embedded_files
+    }
+
+    pub fn set_strip_embedded_files(&mut self, value: bool) -> &mut Self {
+        self.strip_embedded_files = value;
+        self
+    }
+
+    /// Remove the document information dictionary and all XMP metadata streams.
+    pub fn strip_metadata(&self) -> bool {
+        self.strip_metadata
+    }
+
+    pub fn set_strip_metadata(&mut self, value: bool) -> &mut Self {
+        self.strip_metadata = value;
+        self
+    }
+}
+
+/// What [`PdfDocument::scrub`] removes. Everything is removed by default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScrubOptions {
+    info: bool,
+    xmp: bool,
+    javascript: bool,
+    thumbnails: bool,
+    piece_info: bool,
+    annotation_authors: bool,
+}
+
+impl Default for ScrubOptions {
+    fn default() -> Self {
+        Self {
+            info: true,
+            xmp: true,
+            javascript: true,
+            thumbnails: true,
+            piece_info: true,
+            annotation_authors: true,
+        }
+    }
+}
+
+impl ScrubOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The document information dictionary with its title, author, producer and dates.
+    pub fn info(&self) -> bool {
+        self.info
+    }
+
+    pub fn set_info(&mut self, value: bool) -> &mut Self {
+        self.info = value;
+        self
+    }
+
+    /// The XMP metadata streams of the document, its pages, images and other objects.
+    pub fn xmp(&self) -> bool {
+        self.xmp
+    }
+
+    pub fn set_xmp(&mut self, value: bool) -> &mut Self {
+        self.xmp = value;
+        self
+    }
+
+    /// Document level JavaScript and the JavaScript actions of pages, annotations and form
+    /// fields.
+    pub fn javascript(&self) -> bool {
+        self.javascript
+    }
+
+    pub fn set_javascript(&mut self, value: bool) -> &mut Self {
+        self.javascript = value;
+        self
+    }
+
+    /// The thumbnail images of pages.
+    pub fn thumbnails(&self) -> bool {
+        self.thumbnails
+    }
+
+    pub fn set_thumbnails(&mut self, value: bool) -> &mut Self {
+        self.thumbnails = value;
+        self
+    }
+
+    /// The private data authoring applications keep in the document, its pages and forms.
+    pub fn piece_info(&self) -> bool {
+        self.piece_info
+    }
+
+    pub fn set_piece_info(&mut self, value: bool) -> &mut Self {
+        self.piece_info = value;
+        self
+    }
+
+    /// The author and the creation and modification dates of annotations other than form
+    /// widgets, whose title is the name of their field.
+    pub fn annotation_authors(&self) -> bool {
+        self.annotation_authors
+    }
+
+    pub fn set_annotation_authors(&mut self, value: bool) -> &mut Self {
+        self.annotation_authors = value;
+        self
+    }
+}
+
+/// Whether an action dictionary runs JavaScript.
+fn is_javascript(action: &PdfObject) -> Result<bool, Error> {
+    Ok(action.is_dict()? && name_of(action, "S")?.as_deref() == Some(b"JavaScript"))
+}
+
+impl PdfDocument {
+    /// Rewrite the document like `mutool clean` does, and reopen the result in place of the
+    /// document. The cleaned document keeps none of the state of the document before it, such
+    /// as JavaScript handlers or authentication, see [`CleanOptions::remove_encryption`]. Save
+    /// it to keep the result.
+    pub fn clean(&mut self, options: &CleanOptions) -> Result<(), Error> {
+        if options.strip_javascript {
+            self.strip_javascript()?;
+        }
+        if options.strip_embedded_files {
+            self.strip_embedded_files()?;
+        }
+        if options.strip_metadata {
+            self.strip_metadata()?;
+        }
+        let mut write = options.write;
+        write.set_encryption(if options.remove_encryption {
+            Encryption::None
+        } else {
+            Encryption::Keep
+        });
+        let mut cleaned = Vec::new();
+        self.write_to_with_options(&mut cleaned, write)?;
+        *self = PdfDocument::from_bytes(&cleaned)?;
+        Ok(())
+    }
+
+    /// Remove metadata and data that tracks how and by whom the document was made, before
+    /// publishing it. The removed data is gone from the saved document once it is saved without
+    /// incremental updates and with garbage collection.
+    pub fn scrub(&mut self, options: &ScrubOptions) -> Result<(), Error> {
+        if options.info {
+            self.trailer()?.dict_delete("Info")?;
+        }
+        if options.javascript {
+            self.strip_javascript()?;
+        }
+        for (_, mut obj) in dict_objects(self)? {
+            if options.xmp {
+                obj.dict_delete("Metadata")?;
+            }
+            if options.thumbnails {
+                obj.dict_delete("Thumb")?;
+            }
+            if options.piece_info {
+                obj.dict_delete("PieceInfo")?;
+            }
+        }
+        if options.annotation_authors {
+            // Annotations are found through the pages, as their Type entry is optional
+            for page in 0..self.page_count()? {
+                let Some(annots) = self.find_page(page)?.get_dict("Annots")? else {
+                    continue;
+                };
+                for i in 0..annots.len()? as i32 {
+                    let Some(mut annot) = annots.get_array(i)? else {
+                        continue;
+                    };
+                    if !annot.is_dict()?
+                        || name_of(&annot, "Subtype")?.as_deref() == Some(b"Widget")
+                    {
+                        continue;
+                    }
+                    for key in ["T", "M", "CreationDate"] {
+                        annot.dict_delete(key)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Remove document level JavaScript and the JavaScript actions of pages, annotations and
+    /// form fields.
+    pub fn strip_javascript(&mut self) -> Result<(), Error> {
+        if let Some(mut names) = self.catalog()?.get_dict("Names")? {
+            names.dict_delete("JavaScript")?;
+        }
+        for (_, mut obj) in dict_objects(self)? {
+            for key in ["A", "OpenAction"] {
+                if let Some(action) = obj.get_dict(key)? {
+                    if is_javascript(&action)? {
+                        obj.dict_delete(key)?;
+                    }
+                }
+            }
+            if let Some(mut actions) = obj.get_dict("AA")? {
+                let mut events = Vec::new();
+                for i in 0..actions.dict_len()? as i32 {
+                    if let (Some(event), Some(action)) =
+                        (actions.get_dict_key(i)?, actions.get_dict_val(i)?)
+                    {
+                        if is_javascript(&action)? {
+                            events.push(String::from_utf8_lossy(event.as_name()?).into_owned());
+                        }
+                    }
+                }
+                for event in events {
+                    actions.dict_delete(event.as_str())?;
+                }
+                if actions.dict_len()? == 0 {
+                    obj.dict_delete("AA")?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn strip_embedded_files(&mut self) -> Result<(), Error> {
+        let mut catalog = self.catalog()?;
+        catalog.dict_delete("AF")?;
+        if let Some(mut names) = catalog.get_dict("Names")? {
+            names.dict_delete("EmbeddedFiles")?;
+        }
+        for i in 0..self.page_count()? {
+            let Some(mut annots) = self.find_page(i)?.get_dict("Annots")? else {
+                continue;
+            };
+            for j in (0..annots.len()? as i32).rev() {
+                if let Some(annot) = annots.get_array(j)? {
+                    if name_of(&annot, "Subtype")?.as_deref() == Some(b"FileAttachment") {
+                        annots.array_delete(j)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn strip_metadata(&mut self) -> Result<(), Error> {
+        self.trailer()?.dict_delete("Info")?;
+        for (_, mut obj) in dict_objects(self)? {
+            obj.dict_delete("Metadata")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{CleanOptions, ScrubOptions};
+    use crate::pdf::PdfDocument;
+
+    #[test]
+    fn test_clean() {
+        let mut doc = PdfDocument::open("tests/files/dummy.pdf").unwrap();
+        let unused = doc.new_object_from_str("<< /Unused true >>").unwrap();
+        doc.add_object(&unused).unwrap();
+        let action = doc
+            .new_object_from_str("<< /S /JavaScript /JS (app.alert(1)) >>")
+            .unwrap();
+        doc.catalog()
+            .unwrap()
+            .dict_put("OpenAction", action)
+            .unwrap();
+        let mut page = doc.find_page(0).unwrap();
+        page.dict_put(
+            "AA",
+            doc.new_object_from_str(
+                "<< /O << /S /JavaScript /JS (1) >> /C << /S /Named /N /NextPage >> >>",
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        doc.trailer()
+            .unwrap()
+            .dict_put("Info", doc.new_object_from_str("<< /Title (x) >>").unwrap())
+            .unwrap();
+        let count = doc.count_objects().unwrap();
+
+        let mut options = CleanOptions::new();
+        options
+            .set_garbage_level(2)
+            .set_pretty(true)
+            .set_strip_javascript(true)
+            .set_strip_metadata(true);
+        doc.clean(&options).unwrap();
+
+        assert!(doc.count_objects().unwrap() < count);
+        assert_eq!(doc.page_count().unwrap(), 1);
+        assert!(doc
+            .catalog()
+            .unwrap()
+            .get_dict("OpenAction")
+            .unwrap()
+            .is_none());
+        assert!(doc.trailer().unwrap().get_dict("Info").unwrap().is_none());
+        let actions = doc.find_page(0).unwrap().get_dict("AA").unwrap().unwrap();
+        assert!(actions.get_dict("O").unwrap().is_none());
+        assert!(actions.get_dict("C").unwrap().is_some());
+    }
+
+    #[test]
+    fn test_clean_encrypted() {
+        let mut doc = PdfDocument::open("tests/files/dummy-encrypted.pdf").unwrap();
+        assert!(doc.authenticate("123456").unwrap());
+        doc.clean(&CleanOptions::new()).unwrap();
+        assert!(doc.is_encrypted().unwrap());
+        assert!(doc.authenticate("123456").unwrap());
+        assert_eq!(doc.page_count().unwrap(), 1);
+
+        let mut options = CleanOptions::new();
+        options.set_remove_encryption(true);
+        doc.clean(&options).unwrap();
+        assert!(!doc.is_encrypted().unwrap());
+        assert!(!doc.needs_password().unwrap());
+        assert_eq!(doc.page_count().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_scrub() {
+        let mut doc = PdfDocument::open("tests/files/dummy.pdf").unwrap();
+        let annot = doc
+            .new_object_from_str(
+                "<< /Subtype /Text /Rect [0 0 10 10] /T (Jane Doe) \
+                 /M (D:20240131120000Z) /Contents (Note) >>",
+            )
+            .unwrap();
+        let annot = doc.add_object(&annot).unwrap();
+        let mut annots = doc.new_array().unwrap();
+        annots.array_push(annot.clone()).unwrap();
+        let mut page = doc.find_page(0).unwrap();
+        page.dict_put("Annots", annots).unwrap();
+        let thumb = doc.add_object(&doc.new_dict().unwrap()).unwrap();
+        page.dict_put("Thumb", thumb).unwrap();
+        page.dict_put(
+            "PieceInfo",
+            doc.new_object_from_str("<< /App << /Private (data) >> >>")
+                .unwrap(),
+        )
+        .unwrap();
+        let metadata = doc.add_object(&doc.new_dict().unwrap()).unwrap();
+        doc.catalog()
+            .unwrap()
+            .dict_put("Metadata", metadata)
+            .unwrap();
+        doc.trailer()
+            .unwrap()
+            .dict_put(
+                "Info",
+                doc.new_object_from_str("<< /Author (x) >>").unwrap(),
+            )
+            .unwrap();
+
+        let mut options = ScrubOptions::new();
+        options.set_thumbnails(false);
+        doc.scrub(&options).unwrap();
+
+        let page = doc.find_page(0).unwrap();
+        assert!(page.get_dict("Thumb").unwrap().is_some());
+        assert!(page.get_dict("PieceInfo").unwrap().is_none());
+        assert!(doc
+            .catalog()
+            .unwrap()
+            .get_dict("Metadata")
+            .unwrap()
+            .is_none());
+        assert!(doc.trailer().unwrap().get_dict("Info").unwrap().is_none());
+        assert!(annot.get_dict("T").unwrap().is_none());
+        assert!(annot.get_dict("M").unwrap().is_none());
+        assert!(annot.get_dict("Contents").unwrap().is_some());
+    }
+}
@@ -0,0 +1,865 @@
+use std::ffi::c_int;
+use std::fmt::Write;
+use std::{mem, ptr};
+
+use mupdf_sys::*;
+
+use crate::pdf::{PdfObject, PdfPage};
+use crate::{
+    context, BlendMode, Buffer, ColorParams, Colorspace, Device, DisplayList, Error, FFIWrapper,
+    Matrix, Path, PathWalker, Rect, StrokeState,
+};
+
+/// The color paths are filled or stroked with by a [`ContentBuilder`].
+#[derive(Debug, Clone)]
+pub struct Paint {
+    colorspace: Colorspace,
+    color: Vec<f32>,
+    alpha: f32,
+    blend_mode: BlendMode,
+}
+
+impl Paint {
+    /// Paint with `color` in `colorspace`, opaque and with the normal blend mode.
+    pub fn new(colorspace: &Colorspace, color: &[f32]) -> Self {
+        Self {
+            colorspace: colorspace.clone(),
+            color: color.to_vec(),
+            alpha: 1.0,
+            blend_mode: BlendMode::Normal,
+        }
+    }
+
+    pub fn gray(gray: f32) -> Self {
+        Self::new(&Colorspace::device_gray(), &[gray])
+    }
+
+    pub fn rgb(r: f32, g: f32, b: f32) -> Self {
+        Self::new(&Colorspace::device_rgb(), &[r, g, b])
+    }
+
+    pub fn cmyk(c: f32, m: f32, y: f32, k: f32) -> Self {
+        Self::new(&Colorspace::device_cmyk(), &[c, m, y, k])
+    }
+
+    pub fn colorspace(&self) -> &Colorspace {
+        &self.colorspace
+    }
+
+    pub fn color(&self) -> &[f32] {
+        &self.color
+    }
+
+    /// The opacity, from 0 (invisible) to 1 (opaque, the default).
+    pub fn alpha(&self) -> f32 {
+        self.alpha
+    }
+
+    pub fn set_alpha(&mut self, value: f32) -> &mut Self {
+        self.alpha = value.clamp(0.0, 1.0);
+        self
+    }
+
+    /// How the paint is combined with the content below it, [`BlendMode::Normal`] by default.
+    pub fn blend_mode(&self) -> BlendMode {
+        self.blend_mode
+    }
+
+    pub fn set_blend_mode(&mut self, value: BlendMode) -> &mut Self {
+        self.blend_mode = value;
+        self
+    }
+}
+
+/// Options of a transparency group of a [`ContentBuilder`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct GroupOptions {
+    isolated: bool,
+    knockout: bool,
+    blend_mode: BlendMode,
+    alpha: f32,
+}
+
+impl Default for GroupOptions {
+    fn default() -> Self {
+        Self {
+            isolated: false,
+            knockout: false,
+            blend_mode: BlendMode::Normal,
+            alpha: 1.0,
+        }
+    }
+}
+
+impl GroupOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether the group is composited on a transparent backdrop instead of the content below
+    /// it, false by default. Blend modes inside an isolated group don't affect the page.
+    pub fn isolated(&self) -> bool {
+        self.isolated
+    }
+
+    pub fn set_isolated(&mut self, value: bool) -> &mut Self {
+        self.isolated = value;
+        self
+    }
+
+    /// Whether content of the group replaces the content of the group below it instead of
+    /// being composited with it, false by default.
+    pub fn knockout(&self) -> bool {
+        self.knockout
+    }
+
+    pub fn set_knockout(&mut self, value: bool) -> &mut Self {
+        self.knockout = value;
+        self
+    }
+
+    /// How the group is combined with the content below it, [`BlendMode::Normal`] by default.
+    pub fn blend_mode(&self) -> BlendMode {
+        self.blend_mode
+    }
+
+    pub fn set_blend_mode(&mut self, value: BlendMode) -> &mut Self {
+        self.blend_mode = value;
+        self
+    }
+
+    /// The opacity of the group as a whole, 1 by default.
+    pub fn alpha(&self) -> f32 {
+        self.alpha
+    }
+
+    pub fn set_alpha(&mut self, value: f32) -> &mut Self {
+        self.alpha = value.clamp(0.0, 1.0);
+        self
+    }
+}
+
+/// Content repeated to fill paths with by a [`ContentBuilder`], such as the hatching of a cut in
+/// a technical drawing.
+///
+/// The pattern is written to the page as a PDF tiling pattern, so viewers repeat it however far
+/// they zoom in.
+#[derive(Debug)]
+pub struct TilingPattern {
+    list: DisplayList,
+    cell: Rect,
+    xstep: f32,
+    ystep: f32,
+    matrix: Matrix,
+}
+
+impl TilingPattern {
+    /// Repeat the content of `list` inside `cell` every `xstep` to the right and every `ystep`
+    /// down. Content outside `cell` is cut off.
+    pub fn new(list: DisplayList, cell: Rect, xstep: f32, ystep: f32) -> Self {
+        Self {
+            list,
+            cell,
+            xstep,
+            ystep,
+            matrix: Matrix::IDENTITY,
+        }
+    }
+
+    /// Repeat the content `f` draws inside `cell` side by side.
+    pub fn from_fn(
+        cell: Rect,
+        f: impl FnOnce(&Device) -> Result<(), Error>,
+    ) -> Result<Self, Error> {
+        let list = DisplayList::new(cell)?;
+        {
+            let device = Device::from_display_list(&list)?;
+            f(&device)?;
+        }
+        Ok(Self::new(list, cell, cell.width(), cell.height()))
+    }
+
+    pub fn cell(&self) -> Rect {
+        self.cell
+    }
+
+    pub fn xstep(&self) -> f32 {
+        self.xstep
+    }
+
+    pub fn ystep(&self) -> f32 {
+        self.ystep
+    }
+
+    /// The transform from the space of the pattern to page space, the identity by default.
+    /// Patterns are laid out on the page rather than on the paths they fill, so adjoining
+    /// fills line up.
+    pub fn matrix(&self) -> &Matrix {
+        &self.matrix
+    }
+
+    pub fn set_matrix(&mut self, value: Matrix) -> &mut Self {
+        self.matrix = value;
+        self
+    }
+}
+
+/// Writes a path as PDF path construction operators.
+struct PathOps(String);
+
+impl PathWalker for PathOps {
+    fn move_to(&mut self, x: f32, y: f32) {
+        let _ = writeln!(self.0, "{} {} m", x, y);
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        let _ = writeln!(self.0, "{} {} l", x, y);
+    }
+
+    fn curve_to(&mut self, cx1: f32, cy1: f32, cx2: f32, cy2: f32, ex: f32, ey: f32) {
+        let _ = writeln!(self.0, "{} {} {} {} {} {} c", cx1, cy1, cx2, cy2, ex, ey);
+    }
+
+    fn close(&mut self) {
+        self.0.push_str("h\n");
+    }
+}
+
+/// A content stream and its resources, added to the page as a form XObject.
+#[derive(Debug)]
+struct Segment {
+    resources: PdfObject,
+    contents: Buffer,
+}
+
+/// A [`TilingPattern`] written to the document, with the list it was drawn from kept alive so
+/// it is recognized by its address.
+#[derive(Debug)]
+struct PatternObject {
+    list: DisplayList,
+    cell: Rect,
+    xstep: f32,
+    ystep: f32,
+    matrix: Matrix,
+    object: PdfObject,
+}
+
+impl PatternObject {
+    fn is(&self, pattern: &TilingPattern) -> bool {
+        self.list.inner == pattern.list.inner
+            && self.cell == pattern.cell
+            && self.xstep == pattern.xstep
+            && self.ystep == pattern.ystep
+            && self.matrix == pattern.matrix
+    }
+}
+
+/// What a [`ContentBuilder`] is in the middle of.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Nesting {
+    Group,
+    /// Drawing a soft mask.
+    Mask,
+    /// Drawing content masked by a soft mask.
+    Masked,
+}
+
+/// Draws new content on top of a PDF page, returned by [`PdfPage::content_builder`].
+///
+/// Coordinates are in page space, as the page is rendered with [`Matrix::IDENTITY`], whatever
+/// the rotation and boxes of the page. The content is only added to the page by
+/// [`ContentBuilder::finish`], it is thrown away if the builder is dropped.
+#[derive(Debug)]
+pub struct ContentBuilder<'a> {
+    page: &'a mut PdfPage,
+    device: Device,
+    /// The segment `device` draws to.
+    segment: Segment,
+    /// Segments drawn before, in order.
+    segments: Vec<Segment>,
+    /// Patterns filled with so far, each written once.
+    patterns: Vec<PatternObject>,
+    bounds: Rect,
+    nesting: Vec<Nesting>,
+}
+
+impl ContentBuilder<'_> {
+    /// The device drawing on the page, for content other than paths, such as text and images.
+    pub fn device(&self) -> &Device {
+        &self.device
+    }
+
+    pub fn fill_path(
+        &mut self,
+        path: &Path,
+        even_odd: bool,
+        ctm: &Matrix,
+        paint: &Paint,
+    ) -> Result<(), Error> {
+        self.with_blend_mode(paint.blend_mode, |device| {
+            device.fill_path(
+                path,
+                even_odd,
+                ctm,
+                &paint.colorspace,
+                &paint.color,
+                paint.alpha,
+                ColorParams::default(),
+            )
+        })
+    }
+
+    /// Stroke `path` with the width, dashes, caps and joins of `stroke`.
+    pub fn stroke_path(
+        &mut self,
+        path: &Path,
+        stroke: &StrokeState,
+        ctm: &Matrix,
+        paint: &Paint,
+    ) -> Result<(), Error> {
+        self.with_blend_mode(paint.blend_mode, |device| {
+            device.stroke_path(
+                path,
+                stroke,
+                ctm,
+                &paint.colorspace,
+                &paint.color,
+                paint.alpha,
+                ColorParams::default(),
+            )
+        })
+    }
+
+    /// Fill `path` with `pattern`. Filling with the same pattern again reuses the pattern
+    /// written to the document.
+    ///
+    /// The pattern is painted opaque with the normal blend mode, as there is no [`Paint`] to
+    /// choose otherwise, and pattern fills can't be nested in groups or soft masks.
+    pub fn fill_path_with_pattern(
+        &mut self,
+        path: &Path,
+        even_odd: bool,
+        ctm: &Matrix,
+        pattern: &TilingPattern,
+    ) -> Result<(), Error> {
+        if !self.nesting.is_empty() {
+            return Err(Error::UnbalancedContent(
+                "pattern fills can't be nested in groups or soft masks".to_owned(),
+            ));
+        }
+        let page_to_pdf = self.page.pdf_to_page()?.invert().ok_or_else(|| {
+            Error::InvalidInput("the transform of the page can't be inverted".to_owned())
+        })?;
+        let index = match self.patterns.iter().position(|p| p.is(pattern)) {
+            Some(index) => index,
+            None => {
+                let object = unsafe {
+                    ffi_try!(mupdf_pdf_new_tiling_pattern(
+                        context(),
+                        (*self.page.as_ptr()).doc,
+                        pattern.list.inner,
+                        pattern.cell.into(),
+                        pattern.xstep,
+                        pattern.ystep,
+                        (pattern.matrix.clone() * page_to_pdf.clone()).into()
+                    ))
+                }
+                .map(|inner| unsafe { PdfObject::from_raw(inner) })?;
+                unsafe { fz_keep_display_list(context(), pattern.list.inner) };
+                self.patterns.push(PatternObject {
+                    list: unsafe { DisplayList::from_raw(pattern.list.inner) },
+                    cell: pattern.cell,
+                    xstep: pattern.xstep,
+                    ystep: pattern.ystep,
+                    matrix: pattern.matrix.clone(),
+                    object,
+                });
+                self.patterns.len() - 1
+            }
+        };
+        let name = format!("P{}", index);
+        let mut patterns = new_dict(self.page)?;
+        patterns.dict_put(name.as_str(), self.patterns[index].object.clone())?;
+        let mut resources = new_dict(self.page)?;
+        resources.dict_put("Pattern", patterns)?;
+
+        // The pattern fill is written by hand, so the content drawn so far is ended and drawing
+        // goes on in a new segment after it
+        let mut path = path.try_clone()?;
+        path.transform(&(ctm.clone() * page_to_pdf))?;
+        let mut ops = PathOps(format!("/Pattern cs /{} scn\n", name));
+        path.walk(&mut ops)?;
+        ops.0.push_str(if even_odd { "f*\n" } else { "f\n" });
+        let contents = Buffer::from_bytes(ops.0.as_bytes())?;
+
+        let (device, segment) = begin_segment(self.page)?;
+        // Closing the device writes out what's left of the content
+        drop(mem::replace(&mut self.device, device));
+        let drawn = mem::replace(&mut self.segment, segment);
+        self.segments.push(drawn);
+        self.segments.push(Segment {
+            resources,
+            contents,
+        });
+        Ok(())
+    }
+
+    /// Draw with `f`, in a group blending with the content below it with `blend_mode` unless
+    /// that's the normal blend mode.
+    fn with_blend_mode(
+        &mut self,
+        blend_mode: BlendMode,
+        f: impl FnOnce(&Device) -> Result<(), Error>,
+    ) -> Result<(), Error> {
+        if blend_mode == BlendMode::Normal {
+            return f(&self.device);
+        }
+        self.device.begin_group(
+            self.bounds,
+            &Colorspace::device_rgb(),
+            false,
+            false,
+            blend_mode,
+            1.0,
+        )?;
+        let result = f(&self.device);
+        self.device.end_group()?;
+        result
+    }
+
+    /// Start a transparency group within `area`. Content drawn until
+    /// [`ContentBuilder::end_group`] is composited on its own first, and then combined with the
+    /// content below it as a whole, with the blend mode and opacity of `options`.
+    ///
+    /// Unlike [`Paint::set_blend_mode`] this blends overlapping content of the group once, e.g.
+    /// for a "DRAFT" watermark multiplied with the page.
+    pub fn begin_group(&mut self, area: Rect, options: &GroupOptions) -> Result<(), Error> {
+        self.device.begin_group(
+            area,
+            &Colorspace::device_rgb(),
+            options.isolated,
+            options.knockout,
+            options.blend_mode,
+            options.alpha,
+        )?;
+        self.nesting.push(Nesting::Group);
+        Ok(())
+    }
+
+    pub fn end_group(&mut self) -> Result<(), Error> {
+        self.leave(Nesting::Group, "no group to end")?;
+        self.device.end_group()
+    }
+
+    /// Start drawing a soft mask within `area`, which content drawn after
+    /// [`ContentBuilder::end_soft_mask`] is masked by until [`ContentBuilder::pop_soft_mask`].
+    ///
+    /// With `luminosity` the mask shows content where it is light and hides it where it is
+    /// dark, otherwise it shows content where it is opaque. Content outside `area` is hidden.
+    pub fn begin_soft_mask(&mut self, area: Rect, luminosity: bool) -> Result<(), Error> {
+        self.device.begin_mask(
+            area,
+            luminosity,
+            &Colorspace::device_gray(),
+            &[0.0],
+            ColorParams::default(),
+        )?;
+        self.nesting.push(Nesting::Mask);
+        Ok(())
+    }
+
+    /// Stop drawing the mask, content drawn from now on is masked.
+    pub fn end_soft_mask(&mut self) -> Result<(), Error> {
+        self.leave(Nesting::Mask, "no soft mask being drawn")?;
+        self.device.end_mask(None)?;
+        self.nesting.push(Nesting::Masked);
+        Ok(())
+    }
+
+    /// Stop masking content with the mask.
+    pub fn pop_soft_mask(&mut self) -> Result<(), Error> {
+        self.leave(Nesting::Masked, "no soft mask to pop")?;
+        self.device.pop_clip()
+    }
+
+    fn leave(&mut self, nesting: Nesting, msg: &str) -> Result<(), Error> {
+        if self.nesting.last() != Some(&nesting) {
+            return Err(Error::UnbalancedContent(msg.to_owned()));
+        }
+        self.nesting.pop();
+        Ok(())
+    }
+
+    /// Add the content drawn to the page. Groups and soft masks must have been ended.
+    pub fn finish(self) -> Result<(), Error> {
+        if !self.nesting.is_empty() {
+            return Err(Error::UnbalancedContent(format!(
+                "{} groups or soft masks left open",
+                self.nesting.len()
+            )));
+        }
+        let Self {
+            page,
+            device,
+            segment,
+            mut segments,
+            ..
+        } = self;
+        drop(device);
+        segments.push(segment);
+        let (mut resources, mut contents): (Vec<_>, Vec<_>) = segments
+            .iter()
+            .filter(|s| !s.contents.is_empty())
+            .map(|s| (s.resources.inner, s.contents.inner))
+            .unzip();
+        if contents.is_empty() {
+            return Ok(());
+        }
+        unsafe {
+            ffi_try!(mupdf_pdf_page_append_content(
+                context(),
+                page.as_mut_ptr(),
+                resources.as_mut_ptr(),
+                contents.as_mut_ptr(),
+                contents.len() as c_int
+            ))
+        }
+    }
+}
+
+fn new_dict(page: &PdfPage) -> Result<PdfObject, Error> {
+    unsafe { ffi_try!(mupdf_pdf_new_dict(context(), (*page.as_ptr()).doc, 0)) }
+        .map(|inner| unsafe { PdfObject::from_raw(inner) })
+}
+
+/// A device drawing on `page`, and the segment it draws to.
+fn begin_segment(page: &mut PdfPage) -> Result<(Device, Segment), Error> {
+    let resources = new_dict(page)?;
+    let contents = Buffer::new();
+    let device = unsafe {
+        ffi_try!(mupdf_new_pdf_page_content_device(
+            context(),
+            page.as_mut_ptr(),
+            resources.inner,
+            contents.inner
+        ))
+    }
+    .map(|dev| unsafe { Device::from_raw(dev, ptr::null_mut()) })?;
+    Ok((
+        device,
+        Segment {
+            resources,
+            contents,
+        },
+    ))
+}
+
+impl PdfPage {
+    /// Start drawing new content on top of the page.
+    pub fn content_builder(&mut self) -> Result<ContentBuilder<'_>, Error> {
+        let bounds = self.bounds()?;
+        let (device, segment) = begin_segment(self)?;
+        Ok(ContentBuilder {
+            page: self,
+            device,
+            segment,
+            segments: Vec::new(),
+            patterns: Vec::new(),
+            bounds,
+            nesting: Vec::new(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{GroupOptions, Paint, TilingPattern};
+    use crate::pdf::PdfDocument;
+    use crate::{
+        BlendMode, ColorParams, Colorspace, Error, LineCap, LineJoin, Matrix, Path, Rect,
+        StrokeState,
+    };
+
+    #[test]
+    fn test_content_builder_paths() {
+        let mut doc = PdfDocument::new();
+        let mut page = doc.new_page((100.0, 100.0)).unwrap();
+
+        let mut square = Path::new().unwrap();
+        square.rect(10.0, 10.0, 50.0, 50.0).unwrap();
+        let mut line = Path::new().unwrap();
+        line.move_to(0.0, 80.0).unwrap();
+        line.line_to(100.0, 80.0).unwrap();
+        let stroke = StrokeState::new(
+            LineCap::Butt,
+            LineCap::Butt,
+            LineCap::Butt,
+            LineJoin::Round,
+            4.0,
+            10.0,
+            0.0,
+            &[10.0, 10.0],
+        )
+        .unwrap();
+
+        let mut builder = page.content_builder().unwrap();
+        builder
+            .fill_path(
+                &square,
+                false,
+                &Matrix::IDENTITY,
+                &Paint::rgb(1.0, 0.0, 0.0),
+            )
+            .unwrap();
+        let mut overlay = Paint::gray(0.5);
+        overlay.set_alpha(0.5).set_blend_mode(BlendMode::Multiply);
+        builder
+            .fill_path(&square, false, &Matrix::new_translate(20.0, 0.0), &overlay)
+            .unwrap();
+        builder
+            .stroke_path(&line, &stroke, &Matrix::IDENTITY, &Paint::gray(0.0))
+            .unwrap();
+        builder.finish().unwrap();
+
+        let pixmap = page
+            .to_pixmap(&Matrix::IDENTITY, &Colorspace::device_rgb(), false, false)
+            .unwrap();
+        let n = pixmap.n() as usize;
+        let stride = pixmap.stride() as usize;
+        let pixel = |x: usize, y: usize| &pixmap.samples()[y * stride + x * n..][..3];
+        assert_eq!(pixel(15, 15), [255, 0, 0]);
+        // Red multiplied with half transparent gray
+        let blended = pixel(40, 15);
+        assert!(blended[0] > 150 && blended[0] < 255, "{:?}", blended);
+        assert_eq!(blended[1], 0);
+        assert_eq!(pixel(5, 80), [0, 0, 0]);
+        assert_eq!(pixel(15, 80), [255, 255, 255]);
+        assert_eq!(pixel(5, 5), [255, 255, 255]);
+
+        // Drawing again keeps what was drawn before
+        let mut builder = page.content_builder().unwrap();
+        builder
+            .fill_path(
+                &square,
+                false,
+                &Matrix::new_translate(0.0, 40.0),
+                &Paint::gray(0.0),
+            )
+            .unwrap();
+        builder.finish().unwrap();
+        let xobjects = page
+            .object()
+            .get_dict_inheritable("Resources")
+            .unwrap()
+            .unwrap()
+            .get_dict("XObject")
+            .unwrap()
+            .unwrap();
+        assert!(xobjects.get_dict("Content0").unwrap().is_some());
+        assert!(xobjects.get_dict("Content1").unwrap().is_some());
+    }
+
+    #[test]
+    fn test_content_builder_groups_and_masks() {
+        let mut doc = PdfDocument::new();
+        let mut page = doc.new_page((100.0, 100.0)).unwrap();
+        let mut full = Path::new().unwrap();
+        full.rect(0.0, 0.0, 100.0, 100.0).unwrap();
+        let mut left = Path::new().unwrap();
+        left.rect(0.0, 0.0, 50.0, 50.0).unwrap();
+        let area = Rect::new(0.0, 0.0, 100.0, 100.0);
+
+        let mut builder = page.content_builder().unwrap();
+        assert!(matches!(
+            builder.end_group(),
+            Err(Error::UnbalancedContent(_))
+        ));
+        builder
+            .fill_path(&left, false, &Matrix::IDENTITY, &Paint::rgb(1.0, 0.0, 0.0))
+            .unwrap();
+
+        // Two overlapping fills multiplied with the page once, as a whole
+        let mut options = GroupOptions::new();
+        options.set_blend_mode(BlendMode::Multiply);
+        builder.begin_group(area, &options).unwrap();
+        let gray = Paint::gray(0.5);
+        builder
+            .fill_path(&left, false, &Matrix::IDENTITY, &gray)
+            .unwrap();
+        builder
+            .fill_path(&left, false, &Matrix::new_translate(25.0, 0.0), &gray)
+            .unwrap();
+        builder.end_group().unwrap();
+
+        // Only the left half of the bottom is masked in
+        builder.begin_soft_mask(area, true).unwrap();
+        builder
+            .fill_path(
+                &left,
+                false,
+                &Matrix::new_translate(0.0, 50.0),
+                &Paint::gray(1.0),
+            )
+            .unwrap();
+        builder.end_soft_mask().unwrap();
+        builder
+            .fill_path(&full, false, &Matrix::IDENTITY, &Paint::rgb(0.0, 0.0, 1.0))
+            .unwrap();
+        builder.pop_soft_mask().unwrap();
+        builder.finish().unwrap();
+
+        let pixmap = page
+            .to_pixmap(&Matrix::IDENTITY, &Colorspace::device_rgb(), false, false)
+            .unwrap();
+        let n = pixmap.n() as usize;
+        let stride = pixmap.stride() as usize;
+        let pixel = |x: usize, y: usize| pixmap.samples()[y * stride + x * n..][..3].to_vec();
+        let red = pixel(10, 25);
+        assert!((120..136).contains(&red[0]) && red[1] == 0, "{:?}", red);
+        // The overlap of the fills isn't darker
+        assert_eq!(pixel(40, 25), red);
+        assert!((120..136).contains(&pixel(60, 25)[1]));
+        assert_eq!(pixel(25, 75), [0, 0, 255]);
+        assert_eq!(pixel(75, 75), [255, 255, 255]);
+
+        let mut builder = page.content_builder().unwrap();
+        builder.begin_soft_mask(area, true).unwrap();
+        assert!(matches!(
+            builder.pop_soft_mask(),
+            Err(Error::UnbalancedContent(_))
+        ));
+        builder.end_soft_mask().unwrap();
+        builder.begin_group(area, &GroupOptions::new()).unwrap();
+        assert!(matches!(builder.finish(), Err(Error::UnbalancedContent(_))));
+    }
+
+    #[test]
+    fn test_content_builder_patterns() {
+        let mut doc = PdfDocument::new();
+        let mut page = doc.new_page((100.0, 100.0)).unwrap();
+
+        // Vertical stripes, 5 wide
+        let mut stripe = Path::new().unwrap();
+        stripe.rect(0.0, 0.0, 5.0, 10.0).unwrap();
+        let pattern = TilingPattern::from_fn(Rect::new(0.0, 0.0, 10.0, 10.0), |device| {
+            device.fill_path(
+                &stripe,
+                false,
+                &Matrix::IDENTITY,
+                &Colorspace::device_gray(),
+                &[0.0],
+                1.0,
+                ColorParams::default(),
+            )
+        })
+        .unwrap();
+        assert_eq!(pattern.xstep(), 10.0);
+
+        let mut square = Path::new().unwrap();
+        square.rect(0.0, 0.0, 50.0, 50.0).unwrap();
+        let mut builder = page.content_builder().unwrap();
+        builder
+            .fill_path_with_pattern(&square, false, &Matrix::IDENTITY, &pattern)
+            .unwrap();
+        builder
+            .fill_path_with_pattern(&square, false, &Matrix::new_translate(0.0, 50.0), &pattern)
+            .unwrap();
+        builder
+            .fill_path(
+                &square,
+                false,
+                &Matrix::new_translate(50.0, 50.0),
+                &Paint::rgb(1.0, 0.0, 0.0),
+            )
+            .unwrap();
+        let area = Rect::new(0.0, 0.0, 100.0, 100.0);
+        builder.begin_group(area, &GroupOptions::new()).unwrap();
+        assert!(matches!(
+            builder.fill_path_with_pattern(&square, false, &Matrix::IDENTITY, &pattern),
+            Err(Error::UnbalancedContent(_))
+        ));
+        builder.end_group().unwrap();
+        builder.finish().unwrap();
+
+        let pixmap = page
+            .to_pixmap(&Matrix::IDENTITY, &Colorspace::device_rgb(), false, false)
+            .unwrap();
+        let n = pixmap.n() as usize;
+        let stride = pixmap.stride() as usize;
+        let pixel = |x: usize, y: usize| &pixmap.samples()[y * stride + x * n..][..3];
+        assert_eq!(pixel(2, 20), [0, 0, 0]);
+        assert_eq!(pixel(7, 20), [255, 255, 255]);
+        assert_eq!(pixel(42, 45), [0, 0, 0]);
+        assert_eq!(pixel(2, 70), [0, 0, 0]);
+        assert_eq!(pixel(62, 20), [255, 255, 255]);
+        assert_eq!(pixel(70, 70), [255, 0, 0]);
+
+        let xobjects = page
+            .object()
+            .get_dict_inheritable("Resources")
+            .unwrap()
+            .unwrap()
+            .get_dict("XObject")
+            .unwrap()
+            .unwrap();
+        // Both fills use the same pattern object
+        let mut pattern_nums = Vec::new();
+        for i in 0..xobjects.dict_len().unwrap() as i32 {
+            let xobject = xobjects.get_dict_val(i).unwrap().unwrap();
+            let resources = xobject.get_dict("Resources").unwrap().unwrap();
+            if let Some(patterns) = resources.get_dict("Pattern").unwrap() {
+                let pattern = patterns.get_dict_val(0).unwrap().unwrap();
+                pattern_nums.push(pattern.as_indirect().unwrap());
+            }
+        }
+        assert_eq!(pattern_nums.len(), 2);
+        assert_eq!(pattern_nums[0], pattern_nums[1]);
+
+        // All segments are drawn by the last content stream, in order
+        let contents = page.object().get_dict("Contents").unwrap().unwrap();
+        let last = contents
+            .get_array(contents.len().unwrap() as i32 - 1)
+            .unwrap()
+            .unwrap();
+        let ops = String::from_utf8(last.read_stream().unwrap()).unwrap();
+        assert_eq!(ops, "Q\nq\n/Content0 Do\n/Content1 Do\n/Content2 Do\nQ\n");
+    }
+
+    #[test]
+    fn test_content_builder_inherited_resources() {
+        let mut doc = PdfDocument::new();
+        let mut page = doc.new_page((100.0, 100.0)).unwrap();
+        let other = doc.new_page((100.0, 100.0)).unwrap();
+
+        // Both pages inherit the same resources from the page tree
+        let shared = doc.new_dict().unwrap();
+        let shared = doc.add_object(&shared).unwrap();
+        for mut obj in [page.object(), other.object()] {
+            obj.dict_delete("Resources").unwrap();
+            let mut parent = obj.get_dict("Parent").unwrap().unwrap();
+            parent
+                .dict_put("Resources", shared.try_clone().unwrap())
+                .unwrap();
+        }
+
+        let mut square = Path::new().unwrap();
+        square.rect(10.0, 10.0, 50.0, 50.0).unwrap();
+        let mut builder = page.content_builder().unwrap();
+        builder
+            .fill_path(&square, false, &Matrix::IDENTITY, &Paint::gray(0.0))
+            .unwrap();
+        builder.finish().unwrap();
+
+        let resources = page.object().get_dict("Resources").unwrap().unwrap();
+        let xobjects = resources.get_dict("XObject").unwrap().unwrap();
+        assert!(xobjects.get_dict("Content0").unwrap().is_some());
+        assert!(shared.get_dict("XObject").unwrap().is_none());
+        let resources = other
+            .object()
+            .get_dict_inheritable("Resources")
+            .unwrap()
+            .unwrap();
+        assert!(resources.get_dict("XObject").unwrap().is_none());
+    }
+}
@@ -0,0 +1,181 @@
+// Just enough DER to find the parts of CMS signatures and X.509 certificates this crate reads
+// or amends. Parsing accepts the indefinite lengths of BER too, as some signers produce them.
+
+pub(crate) const SEQUENCE: u8 = 0x30;
+pub(crate) const SET: u8 = 0x31;
+pub(crate) const INTEGER: u8 = 0x02;
+pub(crate) const OCTET_STRING: u8 = 0x04;
+pub(crate) const OID: u8 = 0x06;
+/// How deep elements of indefinite length may nest, parsing them recurses.
+const MAX_INDEFINITE_DEPTH: usize = 64;
+
+/// Context specific, constructed tag `[n]`.
+pub(crate) const fn context(n: u8) -> u8 {
+    0xa0 | n
+}
+
+/// An element of DER, its tag and contents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Element<'a> {
+    pub tag: u8,
+    pub contents: &'a [u8],
+    /// The whole element, tag and length included.
+    pub raw: &'a [u8],
+}
+
+impl<'a> Element<'a> {
+    /// The first element of `data`, and what follows it.
+    pub fn parse(data: &'a [u8]) -> Option<(Self, &'a [u8])> {
+        Self::parse_nested(data, 0)
+    }
+
+    /// Like [`Element::parse`], inside `depth` elements of indefinite length.
+    fn parse_nested(data: &'a [u8], depth: usize) -> Option<(Self, &'a [u8])> {
+        let (&tag, rest) = data.split_first()?;
+        // Multi byte tags don't occur in what this crate reads
+        if tag & 0x1f == 0x1f {
+            return None;
+        }
+        let (&first, mut rest) = rest.split_first()?;
+        if first == 0x80 {
+            return Self::parse_indefinite(data, tag, rest, depth);
+        }
+        let len = if first & 0x80 == 0 {
+            first as usize
+        } else {
+            let n = (first & 0x7f) as usize;
+            if n == 0 || n > 4 || rest.len() < n {
+                return None;
+            }
+            let len = rest[..n].iter().fold(0, |len, &b| len << 8 | b as usize);
+            rest = &rest[n..];
+            len
+        };
+        if rest.len() < len {
+            return None;
+        }
+        let header = data.len() - rest.len();
+        Some((
+            Self {
+                tag,
+                contents: &rest[..len],
+                raw: &data[..header + len],
+            },
+            &rest[len..],
+        ))
+    }
+
+    /// An element of indefinite length, whose contents end at an end-of-contents marker.
+    fn parse_indefinite(
+        data: &'a [u8],
+        tag: u8,
+        contents: &'a [u8],
+        depth: usize,
+    ) -> Option<(Self, &'a [u8])> {
+        // Only constructed elements may have an indefinite length
+        if tag & 0x20 == 0 || depth >= MAX_INDEFINITE_DEPTH {
+            return None;
+        }
+        let mut rest = contents;
+        while !rest.starts_with(&[0, 0]) {
+            let (_, next) = Element::parse_nested(rest, depth + 1)?;
+            rest = next;
+        }
+        let len = contents.len() - rest.len();
+        let header = data.len() - contents.len();
+        Some((
+            Self {
+                tag,
+                contents: &contents[..len],
+                raw: &data[..header + len + 2],
+            },
+            &rest[2..],
+        ))
+    }
+
+    /// The elements a constructed element consists of.
+    pub fn children(&self) -> Option<Vec<Element<'a>>> {
+        let mut children = Vec::new();
+        let mut rest = self.contents;
+        while !rest.is_empty() {
+            let (child, next) = Element::parse(rest)?;
+            children.push(child);
+            rest = next;
+        }
+        Some(children)
+    }
+}
+
+/// Encode an element from its tag and contents.
+pub(crate) fn encode(tag: u8, contents: &[u8]) -> Vec<u8> {
+    let mut out = vec![tag];
+    let len = contents.len();
+    if len < 0x80 {
+        out.push(len as u8);
+    } else {
+        let bytes = len.to_be_bytes();
+        let skip = bytes.iter().take_while(|&&b| b == 0).count();
+        out.push(0x80 | (bytes.len() - skip) as u8);
+        out.extend_from_slice(&bytes[skip..]);
+    }
+    out.extend_from_slice(contents);
+    out
+}
+
+/// Encode a constructed element from its children.
+pub(crate) fn encode_children(tag: u8, children: &[&[u8]]) -> Vec<u8> {
+    encode(tag, &children.concat())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{encode, encode_children, Element, OCTET_STRING, SEQUENCE};
+
+    #[test]
+    fn test_der_round_trip() {
+        let long = vec![7; 300];
+        let data = encode_children(
+            SEQUENCE,
+            &[&encode(OCTET_STRING, &[1]), &encode(OCTET_STRING, &long)],
+        );
+        assert_eq!(&data[..4], [SEQUENCE, 0x82, 0x01, 0x33]);
+        let (seq, rest) = Element::parse(&data).unwrap();
+        assert!(rest.is_empty());
+        assert_eq!(seq.raw, &data[..]);
+        let children = seq.children().unwrap();
+        assert_eq!(children[0].contents, [1]);
+        assert_eq!(children[1].contents, &long[..]);
+        assert!(Element::parse(&data[..10]).is_none());
+    }
+
+    #[test]
+    fn test_ber_indefinite_length() {
+        let data = b"\x30\x80\x04\x01\x07\x30\x80\x04\x00\x00\x00\x00\x00\x09";
+        let (seq, rest) = Element::parse(data).unwrap();
+        assert_eq!(rest, [9]);
+        assert_eq!(seq.raw, &data[..13]);
+        let children = seq.children().unwrap();
+        assert_eq!(children.len(), 2);
+        assert_eq!(children[0].contents, [7]);
+        assert_eq!(children[1].contents, [OCTET_STRING, 0]);
+        assert!(children[1].children().unwrap()[0].contents.is_empty());
+        assert!(Element::parse(&data[..8]).is_none());
+        assert!(Element::parse(&[OCTET_STRING, 0x80, 0, 0]).is_none());
+    }
+
+    #[test]
+    fn test_ber_indefinite_length_nesting() {
+        let nested = |levels: usize| {
+            let mut data = [SEQUENCE, 0x80].repeat(levels);
+            data.extend(std::iter::repeat(0).take(2 * levels));
+            data
+        };
+        let data = nested(10);
+        let (seq, rest) = Element::parse(&data).unwrap();
+        assert!(rest.is_empty());
+        assert_eq!(seq.raw, &data[..]);
+        // Too deep to be a signature, and it would overflow the stack otherwise
+        assert!(Element::parse(&nested(65)).is_none());
+        assert!(Element::parse(&nested(1_000_000)).is_none());
+    }
+}
@@ -1,5 +1,8 @@
+use std::collections::hash_map::RandomState;
 use std::convert::TryFrom;
 use std::ffi::{CStr, CString};
+use std::fmt;
+use std::hash::{BuildHasher, Hasher};
 use std::io::{self, Write};
 use std::ops::{Deref, DerefMut};
 use std::ptr::{self, NonNull};
@@ -8,10 +11,10 @@ use bitflags::bitflags;
 use mupdf_sys::*;
 use num_enum::TryFromPrimitive;
 
-use crate::pdf::{PdfGraftMap, PdfObject, PdfPage};
+use crate::pdf::{FontRef, PdfGraftMap, PdfObject, PdfPage, PrintLimits};
 use crate::{
-    context, Buffer, CjkFontOrdering, Destination, DestinationKind, Document, Error, Font, Image,
-    Outline, Point, SimpleFontEncoding, Size, WriteMode,
+    context, Buffer, CjkFontOrdering, Destination, DestinationKind, Document, Error, Font,
+    FontVariations, Image, Outline, Point, SimpleFontEncoding, Size, WriteMode,
 };
 
 bitflags! {
@@ -225,9 +228,31 @@ impl PdfWriteOptions {
     }
 }
 
+impl fmt::Debug for PdfWriteOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // The passwords are left out
+        f.debug_struct("PdfWriteOptions")
+            .field("incremental", &self.incremental())
+            .field("pretty", &self.pretty())
+            .field("ascii", &self.ascii())
+            .field("compress", &self.compress())
+            .field("compress_images", &self.compress_images())
+            .field("compress_fonts", &self.compress_fonts())
+            .field("decompress", &self.decompress())
+            .field("garbage_level", &self.garbage_level())
+            .field("linear", &self.linear())
+            .field("clean", &self.clean())
+            .field("sanitize", &self.sanitize())
+            .field("appearance", &self.appearance())
+            .field("encryption", &self.encryption())
+            .field("permissions", &self.permissions())
+            .finish_non_exhaustive()
+    }
+}
+
 #[derive(Debug)]
 pub struct PdfDocument {
-    inner: *mut pdf_document,
+    pub(crate) inner: *mut pdf_document,
     doc: Document,
 }
 
@@ -343,6 +368,69 @@ impl PdfDocument {
             .map(|inner| unsafe { PdfObject::from_raw(inner) })
     }
 
+    /// Embed a TrueType, OpenType or CFF font file, so it can be used for inserted text, form
+    /// field appearances and the like.
+    pub fn embed_font(&mut self, font_data: &[u8]) -> Result<FontRef, Error> {
+        self.embed_font_with_index(font_data, 0)
+    }
+
+    /// Embed the font at `index` in a font collection (`.ttc`) file.
+    pub fn embed_font_with_index(
+        &mut self,
+        font_data: &[u8],
+        index: i32,
+    ) -> Result<FontRef, Error> {
+        let font = Font::from_bytes_with_index("", index, font_data)?;
+        self.embed(font)
+    }
+
+    /// Embed an instance of a variable font, with `coordinates` holding one value per axis as
+    /// built by [`FontVariations::coordinates`] or taken from a [`NamedInstance`].
+    ///
+    /// MuPDF can only embed font files as they are and has no instancer to bake other axis values
+    /// into a static font, so only the default instance is supported; an error is returned for any
+    /// other instance instead of silently embedding the default one.
+    ///
+    /// [`NamedInstance`]: crate::NamedInstance
+    pub fn embed_font_instance(
+        &mut self,
+        font_data: &[u8],
+        coordinates: &[f32],
+    ) -> Result<FontRef, Error> {
+        if let Some(variations) = FontVariations::from_bytes(font_data)? {
+            variations.validate(coordinates)?;
+            if !variations.is_default(coordinates) {
+                return Err(Error::FontVariation(
+                    "embedding non-default instances of variable fonts is not supported".to_owned(),
+                ));
+            }
+        }
+        self.embed_font(font_data)
+    }
+
+    /// Embed an already loaded font. Embedding the same font twice returns the same object.
+    ///
+    /// The font file is embedded as it is, so a font loaded with
+    /// [`Font::from_bytes_with_variation`] is embedded as the default instance.
+    pub fn embed(&mut self, font: Font) -> Result<FontRef, Error> {
+        let obj = self.add_font(&font)?;
+        Ok(FontRef::new(font, obj))
+    }
+
+    /// Register `font` in the AcroForm default resources under `name`, so it can be referenced
+    /// from the default appearance (`/DA`) of form fields.
+    pub fn add_form_font(&mut self, name: &str, font: &FontRef) -> Result<(), Error> {
+        let c_name = CString::new(name)?;
+        unsafe {
+            ffi_try!(mupdf_pdf_add_form_font_resource(
+                context(),
+                self.inner,
+                c_name.as_ptr(),
+                font.object().inner
+            ))
+        }
+    }
+
     pub fn add_cjk_font(
         &mut self,
         font: &Font,
@@ -424,16 +512,129 @@ impl PdfDocument {
             .map(|inner| unsafe { PdfObject::from_raw(inner) })
     }
 
+    /// Whether the document is encrypted, having an `Encrypt` entry in its trailer.
+    pub fn is_encrypted(&self) -> Result<bool, Error> {
+        Ok(self.trailer()?.get_dict("Encrypt")?.is_some())
+    }
+
+    /// Render object `num` like `mutool show` does, see [`PdfObject::to_string_pretty`].
+    pub fn dump_object(&self, num: i32, limits: &PrintLimits) -> Result<String, Error> {
+        let gen = unsafe { ffi_try!(mupdf_pdf_object_generation(context(), self.inner, num)) }?;
+        let obj = self.new_indirect(num, gen)?;
+        Ok(format!(
+            "{} {} obj\n{}\nendobj",
+            num,
+            gen,
+            obj.to_string_pretty(limits)?
+        ))
+    }
+
+    /// Follow a path of keys and indices from the trailer, see [`PdfObject::resolve_path`].
+    pub fn resolve_path(&self, path: &str) -> Result<Option<PdfObject>, Error> {
+        self.trailer()?.resolve_path(path)
+    }
+
     pub fn catalog(&self) -> Result<PdfObject, Error> {
         unsafe { ffi_try!(mupdf_pdf_catalog(context(), self.inner)) }
             .map(|inner| unsafe { PdfObject::from_raw(inner) })
     }
 
+    /// The PDF version of the document as major and minor version, the later of the version in
+    /// the file header and the `/Version` of the catalog.
+    pub fn version(&self) -> Result<(i32, i32), Error> {
+        let header = unsafe { (*self.inner).version };
+        let mut version = (header / 10, header % 10);
+        if let Some(name) = self.catalog()?.get_dict("Version")? {
+            let name = String::from_utf8_lossy(name.as_name()?).into_owned();
+            if let Some((major, minor)) = name.split_once('.') {
+                if let (Ok(major), Ok(minor)) = (major.parse(), minor.parse()) {
+                    version = version.max((major, minor));
+                }
+            }
+        }
+        Ok(version)
+    }
+
+    /// Set the PDF version written to the file header, and to the `/Version` of the catalog
+    /// that incremental saves rely on. Fails unless the version is one of 1.0 to 1.9 or 2.0 to
+    /// 2.9.
+    pub fn set_version(&mut self, major: i32, minor: i32) -> Result<(), Error> {
+        if !(1..=2).contains(&major) || !(0..=9).contains(&minor) {
+            return Err(Error::InvalidInput(format!(
+                "PDF version {}.{} out of range",
+                major, minor
+            )));
+        }
+        unsafe {
+            ffi_try!(mupdf_pdf_set_version(
+                context(),
+                self.inner,
+                major * 10 + minor
+            ))
+        }?;
+        self.catalog()?.dict_put(
+            "Version",
+            PdfObject::new_name(&format!("{}.{}", major, minor))?,
+        )
+    }
+
+    /// The permanent and changing parts of the file identifier in the trailer, if any.
+    pub fn file_id(&self) -> Result<Option<(Vec<u8>, Vec<u8>)>, Error> {
+        let Some(id) = self.trailer()?.get_dict("ID")? else {
+            return Ok(None);
+        };
+        match (id.get_array(0)?, id.get_array(1)?) {
+            (Some(permanent), Some(changing)) => Ok(Some((
+                permanent.as_bytes()?.to_vec(),
+                changing.as_bytes()?.to_vec(),
+            ))),
+            _ => Ok(None),
+        }
+    }
+
+    /// Give the document a new random file identifier, as for a new document rather than a
+    /// revision of the old one.
+    pub fn regenerate_file_id(&mut self) -> Result<(), Error> {
+        let id: String = (0..2)
+            .map(|_| format!("{:016x}", RandomState::new().build_hasher().finish()))
+            .collect();
+        let id = self.new_object_from_str(&format!("[<{0}> <{0}>]", id))?;
+        self.trailer()?.dict_put("ID", id)
+    }
+
+    /// Whether viewers are asked to render the XFA form of the document instead of its pages.
+    pub fn needs_rendering(&self) -> Result<bool, Error> {
+        match self.catalog()?.get_dict("NeedsRendering")? {
+            Some(value) => value.as_bool(),
+            None => Ok(false),
+        }
+    }
+
+    pub fn set_needs_rendering(&mut self, value: bool) -> Result<(), Error> {
+        let mut catalog = self.catalog()?;
+        if value {
+            catalog.dict_put("NeedsRendering", PdfObject::new_bool(true))
+        } else {
+            catalog.dict_delete("NeedsRendering")
+        }
+    }
+
     pub fn count_objects(&self) -> Result<u32, Error> {
         unsafe { ffi_try!(mupdf_pdf_count_objects(context(), self.inner)) }
             .map(|count| count as u32)
     }
 
+    /// Merge streams with identical data and dictionaries, such as the page contents, images and
+    /// form XObjects repeated on every page of a mail-merge document, into a single shared object.
+    ///
+    /// References to the duplicates are redirected and the duplicates deleted. Returns the number
+    /// of streams that were merged away. Saving with a garbage level of 3 or more does the same for
+    /// the remaining non-stream objects.
+    pub fn deduplicate_streams(&mut self) -> Result<usize, Error> {
+        let merged = unsafe { ffi_try!(mupdf_pdf_deduplicate_streams(context(), self.inner)) }?;
+        Ok(merged as usize)
+    }
+
     pub fn has_acro_form(&self) -> Result<bool, Error> {
         let trailer = self.trailer()?;
         if let Some(root) = trailer.get_dict("Root")? {
@@ -496,6 +697,19 @@ impl PdfDocument {
             .map(|inner| unsafe { PdfObject::from_raw(inner) })
     }
 
+    /// The number of the page whose dictionary is `page`, the reverse of
+    /// [`PdfDocument::find_page`].
+    pub fn page_number(&self, page: &PdfObject) -> Result<Option<i32>, Error> {
+        let page_no = unsafe {
+            ffi_try!(mupdf_pdf_lookup_page_number(
+                context(),
+                self.inner,
+                page.inner
+            ))
+        }?;
+        Ok((page_no >= 0).then_some(page_no))
+    }
+
     pub fn new_page_at<T: Into<Size>>(&mut self, page_no: i32, size: T) -> Result<PdfPage, Error> {
         let size = size.into();
         let inner = unsafe {
@@ -530,6 +744,37 @@ impl PdfDocument {
         unsafe { ffi_try!(mupdf_pdf_delete_page(context(), self.inner, page_no)) }
     }
 
+    /// Keep only the pages listed in `pages`, in that order. A page may be listed more than once.
+    pub fn rearrange_pages(&mut self, pages: &[i32]) -> Result<(), Error> {
+        let count = i32::try_from(pages.len())?;
+        unsafe {
+            ffi_try!(mupdf_pdf_rearrange_pages(
+                context(),
+                self.inner,
+                count,
+                pages.as_ptr()
+            ))
+        }
+    }
+
+    /// Set the value of the form field with the fully qualified name `name`, e.g.
+    /// `address.city`, without running its JavaScript actions.
+    ///
+    /// Returns `false` if there is no such field or the field rejected the value.
+    pub fn set_field_value(&mut self, name: &str, value: &str) -> Result<bool, Error> {
+        let c_name = CString::new(name)?;
+        let c_value = CString::new(value)?;
+        unsafe {
+            ffi_try!(mupdf_pdf_set_field_value(
+                context(),
+                self.inner,
+                c_name.as_ptr(),
+                c_value.as_ptr(),
+                false
+            ))
+        }
+    }
+
     pub fn set_outlines(&mut self, toc: &[Outline]) -> Result<(), Error> {
         self.delete_outlines()?;
 
@@ -565,12 +810,17 @@ impl PdfDocument {
                     let page = self.find_page(page as i32)?;
 
                     let matrix = page.page_ctm()?;
-                    let fz_point = Point::new(outline.x, outline.y);
-                    let Point { x, y } = fz_point.transform(&matrix);
-                    let dest_kind = DestinationKind::XYZ {
-                        left: Some(x),
-                        top: Some(y),
-                        zoom: None,
+                    let dest_kind = match &outline.kind {
+                        Some(kind) => kind.transform(&matrix),
+                        None => {
+                            let fz_point = Point::new(outline.x, outline.y);
+                            let Point { x, y } = fz_point.transform(&matrix);
+                            DestinationKind::XYZ {
+                                left: Some(x),
+                                top: Some(y),
+                                zoom: None,
+                            }
+                        }
                     };
                     let dest = Destination::new(page, dest_kind);
 
@@ -836,9 +1086,137 @@ mod test {
         assert_eq!(bounds.y1, 842.0);
     }
 
+    #[test]
+    fn test_pdf_document_embed_font() {
+        use crate::{Font, Size};
+
+        let mut pdf = PdfDocument::new();
+        let mut page = pdf.new_page(Size::A4).unwrap();
+        assert!(pdf.embed_font(b"not a font").is_err());
+
+        let font = pdf.embed(Font::new("Times-Roman").unwrap()).unwrap();
+        assert!(font.object().is_indirect().unwrap());
+        assert_eq!(font.encode("AB").unwrap().len(), 4);
+        assert!(font.text_width("AB", 12.0).unwrap() > 0.0);
+
+        page.add_font_resource("F1", &font).unwrap();
+        pdf.add_form_font("F1", &font).unwrap();
+        let resources = page
+            .object()
+            .get_dict_inheritable("Resources")
+            .unwrap()
+            .unwrap();
+        let fonts = resources.get_dict("Font").unwrap().unwrap();
+        assert!(fonts.get_dict("F1").unwrap().is_some());
+    }
+
+    #[test]
+    fn test_pdf_document_deduplicate_streams() {
+        use crate::Size;
+
+        let mut pdf = PdfDocument::new();
+        let mut contents = Vec::new();
+        for _ in 0..3 {
+            let page = pdf.new_page(Size::A4).unwrap();
+            let dict = pdf.new_dict().unwrap();
+            let mut stream = pdf.add_object(&dict).unwrap();
+            stream.write_stream_string("0 0 m 100 100 l S").unwrap();
+            page.object()
+                .dict_put("Contents", stream.try_clone().unwrap())
+                .unwrap();
+            contents.push(stream);
+        }
+        assert_eq!(pdf.deduplicate_streams().unwrap(), 2);
+        assert_eq!(pdf.deduplicate_streams().unwrap(), 0);
+
+        let first = contents[0].as_indirect().unwrap();
+        for i in 0..3 {
+            let page = pdf.find_page(i).unwrap();
+            let contents = page.get_dict("Contents").unwrap().unwrap();
+            assert_eq!(contents.as_indirect().unwrap(), first);
+        }
+    }
+
+    #[test]
+    fn test_pdf_document_error_kind() {
+        use crate::ErrorKind;
+
+        let mut pdf = PdfDocument::open("tests/files/dummy.pdf").unwrap();
+        let err = pdf.delete_page(5).unwrap_err();
+        assert_eq!(err.kind(), Some(ErrorKind::Argument));
+        assert!(!err.is_corrupt_input());
+        assert!(!err.is_aborted());
+    }
+
     #[test]
     fn test_pdf_document_find_page() {
         let doc = PdfDocument::open("tests/files/dummy.pdf").unwrap();
         let _page = doc.find_page(0).unwrap();
     }
+
+    #[test]
+    fn test_pdf_document_version_and_id() {
+        let mut doc = PdfDocument::open("tests/files/dummy.pdf").unwrap();
+        let (major, minor) = doc.version().unwrap();
+        assert_eq!(major, 1);
+        doc.set_version(1, 7).unwrap();
+        assert_eq!(doc.version().unwrap(), (1, 7));
+        assert!(matches!(
+            doc.set_version(3, 0),
+            Err(crate::Error::InvalidInput(_))
+        ));
+        assert!(minor <= 7);
+
+        doc.regenerate_file_id().unwrap();
+        let (permanent, changing) = doc.file_id().unwrap().unwrap();
+        assert_eq!(permanent.len(), 16);
+        assert_eq!(permanent, changing);
+        doc.regenerate_file_id().unwrap();
+        assert_ne!(doc.file_id().unwrap().unwrap().0, permanent);
+
+        let mut buf = Vec::new();
+        doc.write_to(&mut buf).unwrap();
+        assert!(buf.starts_with(b"%PDF-1.7"));
+
+        assert!(!doc.needs_rendering().unwrap());
+        doc.set_needs_rendering(true).unwrap();
+        assert!(doc.needs_rendering().unwrap());
+    }
+
+    #[test]
+    fn test_pdf_document_outline_kinds() {
+        use crate::{DestinationKind, Outline};
+
+        let mut doc = PdfDocument::new();
+        doc.new_page((595.0, 842.0)).unwrap();
+        let entry = |title: &str, kind| {
+            let mut outline = Outline::new(title);
+            outline.page = Some(0);
+            outline.kind = kind;
+            outline
+        };
+        let fit_r = DestinationKind::FitR {
+            left: 10.0,
+            bottom: 70.0,
+            right: 110.0,
+            top: 20.0,
+        };
+        doc.set_outlines(&[
+            entry("Fit", Some(DestinationKind::Fit)),
+            entry("FitR", Some(fit_r.clone())),
+            entry("FitH", Some(DestinationKind::FitH { top: 100.0 })),
+        ])
+        .unwrap();
+
+        let outlines = doc.outlines().unwrap();
+        let kinds: Vec<_> = outlines.iter().map(|o| o.kind.clone()).collect();
+        assert_eq!(
+            kinds,
+            [
+                Some(DestinationKind::Fit),
+                Some(fit_r),
+                Some(DestinationKind::FitH { top: 100.0 }),
+            ]
+        );
+    }
 }
@@ -0,0 +1,675 @@
+use std::collections::HashSet;
+
+use bitflags::bitflags;
+use mupdf_sys::*;
+
+use crate::pdf::{
+    DefaultAppearance, PdfAnnotation, PdfAnnotationType, PdfDocument, PdfObject, PdfPage,
+};
+use crate::{Error, Rect};
+
+bitflags! {
+    /// The `Ff` flags of a form field. Which apply depends on the type of the field.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct FieldFlags: u32 {
+        const READ_ONLY = PDF_FIELD_IS_READ_ONLY as _;
+        const REQUIRED = PDF_FIELD_IS_REQUIRED as _;
+        const NO_EXPORT = PDF_FIELD_IS_NO_EXPORT as _;
+        const MULTILINE = PDF_TX_FIELD_IS_MULTILINE as _;
+        const PASSWORD = PDF_TX_FIELD_IS_PASSWORD as _;
+        const FILE_SELECT = PDF_TX_FIELD_IS_FILE_SELECT as _;
+        const DO_NOT_SPELL_CHECK = PDF_TX_FIELD_IS_DO_NOT_SPELL_CHECK as _;
+        const DO_NOT_SCROLL = PDF_TX_FIELD_IS_DO_NOT_SCROLL as _;
+        const COMB = PDF_TX_FIELD_IS_COMB as _;
+        const RICH_TEXT = PDF_TX_FIELD_IS_RICH_TEXT as _;
+        const NO_TOGGLE_TO_OFF = PDF_BTN_FIELD_IS_NO_TOGGLE_TO_OFF as _;
+        const RADIO = PDF_BTN_FIELD_IS_RADIO as _;
+        const PUSHBUTTON = PDF_BTN_FIELD_IS_PUSHBUTTON as _;
+        const RADIOS_IN_UNISON = PDF_BTN_FIELD_IS_RADIOS_IN_UNISON as _;
+        const COMBO = PDF_CH_FIELD_IS_COMBO as _;
+        const EDIT = PDF_CH_FIELD_IS_EDIT as _;
+        const SORT = PDF_CH_FIELD_IS_SORT as _;
+        const MULTI_SELECT = PDF_CH_FIELD_IS_MULTI_SELECT as _;
+        const COMMIT_ON_SEL_CHANGE = PDF_CH_FIELD_IS_COMMIT_ON_SEL_CHANGE as _;
+    }
+}
+
+/// The kinds of form fields [`PdfPage::create_field`] creates. Radio buttons come in groups,
+/// created by [`PdfPage::create_radio_group`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldKind {
+    Text,
+    CheckBox,
+    ComboBox,
+    ListBox,
+    PushButton,
+    Signature,
+}
+
+/// The PDF field type and initial flags of a kind of field.
+fn field_type(kind: FieldKind) -> (&'static str, FieldFlags) {
+    match kind {
+        FieldKind::Text => ("Tx", FieldFlags::empty()),
+        FieldKind::CheckBox => ("Btn", FieldFlags::empty()),
+        FieldKind::ComboBox => ("Ch", FieldFlags::COMBO),
+        FieldKind::ListBox => ("Ch", FieldFlags::empty()),
+        FieldKind::PushButton => ("Btn", FieldFlags::PUSHBUTTON),
+        FieldKind::Signature => ("Sig", FieldFlags::empty()),
+    }
+}
+
+/// The `Fields` array of the form of the document, created as needed.
+fn form_fields(doc: &PdfDocument) -> Result<PdfObject, Error> {
+    let mut catalog = doc.catalog()?;
+    let mut form = match catalog.get_dict("AcroForm")? {
+        Some(form) => form,
+        None => {
+            catalog.dict_put("AcroForm", doc.new_dict()?)?;
+            catalog
+                .get_dict("AcroForm")?
+                .ok_or(Error::UnexpectedNullPtr)?
+        }
+    };
+    match form.get_dict("Fields")? {
+        Some(fields) => Ok(fields),
+        None => {
+            form.dict_put("Fields", doc.new_array()?)?;
+            form.get_dict("Fields")?.ok_or(Error::UnexpectedNullPtr)
+        }
+    }
+}
+
+/// Add a field to the form, failing if there is one by that name already.
+fn add_to_form(doc: &PdfDocument, field: PdfObject, name: &str) -> Result<(), Error> {
+    let mut fields = form_fields(doc)?;
+    for i in 0..fields.len()? as i32 {
+        let Some(existing) = fields.get_array(i)? else {
+            continue;
+        };
+        match existing.get_dict("T")? {
+            Some(t) if t.is_string()? && t.as_string()? == name => {
+                return Err(Error::InvalidAnnotation(format!(
+                    "a field named {} exists",
+                    name
+                )))
+            }
+            _ => {}
+        }
+    }
+    fields.array_push(field)
+}
+
+/// Let MuPDF synthesize the on and off appearances of a check box or radio button. MuPDF
+/// names the on state after the first non-`Off` state it finds, so both are seeded empty.
+fn update_toggle_appearances(widget: &mut PdfAnnotation, on_state: &str) -> Result<(), Error> {
+    let mut obj = widget.object();
+    let doc = obj.document().ok_or(Error::InvalidPdfDocument)?;
+    let mut states = doc.new_dict()?;
+    states.dict_put(on_state, doc.new_dict()?)?;
+    states.dict_put("Off", doc.new_dict()?)?;
+    let mut ap = doc.new_dict()?;
+    ap.dict_put("N", states)?;
+    obj.dict_put("AP", ap)?;
+    obj.dict_put("AS", PdfObject::new_name("Off")?)?;
+    widget.update()?;
+    Ok(())
+}
+
+impl PdfPage {
+    fn create_widget(&mut self, rect: Rect) -> Result<PdfAnnotation, Error> {
+        let mut widget = self.create_annotation(PdfAnnotationType::Widget)?;
+        widget.set_rect(rect)?;
+        Ok(widget)
+    }
+
+    /// Add a form field named `name` with a single widget covering `rect`, returning the
+    /// widget.
+    ///
+    /// Text and choice fields and push buttons get the default appearance of
+    /// [`DefaultAppearance::default`]. Check boxes are on in the `Yes` state, and push buttons
+    /// show their name as caption. Appearances are synthesized by MuPDF.
+    pub fn create_field(
+        &mut self,
+        kind: FieldKind,
+        name: &str,
+        rect: Rect,
+    ) -> Result<PdfAnnotation, Error> {
+        let doc = self.object().document().ok_or(Error::InvalidPdfDocument)?;
+        let mut widget = self.create_widget(rect)?;
+        let mut obj = widget.object();
+        if let Err(e) = add_to_form(&doc, obj.clone(), name) {
+            self.delete_annotation(&widget)?;
+            return Err(e);
+        }
+        let (ft, flags) = field_type(kind);
+        obj.dict_put("FT", PdfObject::new_name(ft)?)?;
+        obj.dict_put("T", PdfObject::new_string(name)?)?;
+        if !flags.is_empty() {
+            widget.set_field_flags(flags)?;
+        }
+        match kind {
+            FieldKind::Text | FieldKind::ComboBox | FieldKind::ListBox => {
+                widget.set_default_appearance(&DefaultAppearance::default())?;
+                if ft == "Ch" {
+                    obj.dict_put("Opt", doc.new_array()?)?;
+                }
+                widget.update()?;
+            }
+            FieldKind::CheckBox => {
+                obj.dict_put("V", PdfObject::new_name("Off")?)?;
+                update_toggle_appearances(&mut widget, "Yes")?;
+            }
+            FieldKind::PushButton => {
+                widget.set_default_appearance(&DefaultAppearance::default())?;
+                let mut mk = doc.new_dict()?;
+                mk.dict_put("CA", PdfObject::new_string(name)?)?;
+                obj.dict_put("MK", mk)?;
+                widget.update()?;
+            }
+            FieldKind::Signature => {
+                if let Some(mut form) = doc.catalog()?.get_dict("AcroForm")? {
+                    // SignaturesExist
+                    let sig_flags = match form.get_dict("SigFlags")? {
+                        Some(f) => f.as_int()?,
+                        None => 0,
+                    };
+                    form.dict_put("SigFlags", PdfObject::new_int(sig_flags | 1)?)?;
+                }
+                widget.update()?;
+            }
+        }
+        Ok(widget)
+    }
+
+    /// Add a group of radio buttons named `name`, one widget per `(rect, export value)` pair,
+    /// of which at most one is on. Returns the widgets, all off.
+    pub fn create_radio_group(
+        &mut self,
+        name: &str,
+        buttons: &[(Rect, &str)],
+    ) -> Result<Vec<PdfAnnotation>, Error> {
+        let mut doc = self.object().document().ok_or(Error::InvalidPdfDocument)?;
+        let mut parent = doc.new_dict()?;
+        parent.dict_put("FT", PdfObject::new_name("Btn")?)?;
+        parent.dict_put("T", PdfObject::new_string(name)?)?;
+        let flags = FieldFlags::RADIO | FieldFlags::NO_TOGGLE_TO_OFF;
+        parent.dict_put("Ff", PdfObject::new_int(flags.bits() as i32)?)?;
+        parent.dict_put("V", PdfObject::new_name("Off")?)?;
+        parent.dict_put("Kids", doc.new_array()?)?;
+        let parent = doc.add_object(&parent)?;
+        add_to_form(&doc, parent.clone(), name)?;
+
+        let mut kids = parent.get_dict("Kids")?.ok_or(Error::UnexpectedNullPtr)?;
+        let mut widgets = Vec::with_capacity(buttons.len());
+        for &(rect, export_value) in buttons {
+            let mut widget = self.create_widget(rect)?;
+            widget.object().dict_put("Parent", parent.clone())?;
+            kids.array_push(widget.object())?;
+            update_toggle_appearances(&mut widget, export_value)?;
+            widgets.push(widget);
+        }
+        Ok(widgets)
+    }
+}
+
+impl PdfAnnotation {
+    pub fn field_flags(&self) -> Result<FieldFlags, Error> {
+        match self.object().get_dict_inheritable("Ff")? {
+            Some(flags) => Ok(FieldFlags::from_bits_retain(flags.as_int()? as u32)),
+            None => Ok(FieldFlags::empty()),
+        }
+    }
+
+    /// Set the flags of the field of a widget. Changing the type of a field, e.g. from combo
+    /// box to list box, does not regenerate its appearance.
+    pub fn set_field_flags(&mut self, flags: FieldFlags) -> Result<(), Error> {
+        self.field()?
+            .dict_put("Ff", PdfObject::new_int(flags.bits() as i32)?)
+    }
+}
+
+/// Deepest field tree walked, guarding against overly deep trees in broken files.
+const MAX_FIELD_DEPTH: usize = 64;
+
+/// A field of a form, with the fields below it.
+#[derive(Debug, Clone)]
+pub struct FormField {
+    /// The partial name of the field, empty for fields without one.
+    pub name: String,
+    /// The fully qualified name: the partial names from the top of the tree down to this
+    /// field, joined by periods.
+    pub full_name: String,
+    /// The field dictionary.
+    pub object: PdfObject,
+    /// The fields below this one.
+    pub kids: Vec<FormField>,
+    /// The widget dictionaries of the field. A field with a single widget is usually merged
+    /// with it, having itself as widget.
+    pub widgets: Vec<PdfObject>,
+}
+
+fn partial_name(field: &PdfObject) -> Result<String, Error> {
+    match field.get_dict("T")? {
+        Some(t) if t.is_string()? => Ok(t.as_string()?.to_owned()),
+        _ => Ok(String::new()),
+    }
+}
+
+fn join_name(parent: &str, name: &str) -> String {
+    match (parent.is_empty(), name.is_empty()) {
+        (true, _) => name.to_owned(),
+        (false, true) => parent.to_owned(),
+        (false, false) => format!("{}.{}", parent, name),
+    }
+}
+
+fn is_widget(obj: &PdfObject) -> Result<bool, Error> {
+    match obj.get_dict("Subtype")? {
+        Some(subtype) => Ok(subtype.as_name()? == b"Widget"),
+        None => Ok(false),
+    }
+}
+
+/// Whether `obj` is seen for the first time, recording indirect objects in `visited`.
+/// Guards against cycles in broken files.
+fn first_visit(obj: &PdfObject, visited: &mut HashSet<i32>) -> Result<bool, Error> {
+    if obj.is_indirect()? {
+        Ok(visited.insert(obj.as_indirect()?))
+    } else {
+        Ok(true)
+    }
+}
+
+fn load_fields(
+    array: &PdfObject,
+    parent: &str,
+    depth: usize,
+    visited: &mut HashSet<i32>,
+) -> Result<Vec<FormField>, Error> {
+    let mut fields = Vec::new();
+    if depth > MAX_FIELD_DEPTH {
+        return Ok(fields);
+    }
+    for i in 0..array.len()? as i32 {
+        let Some(object) = array.get_array(i)? else {
+            continue;
+        };
+        if !object.is_dict()? || !first_visit(&object, visited)? {
+            continue;
+        }
+        let name = partial_name(&object)?;
+        let full_name = join_name(parent, &name);
+        let mut kids = Vec::new();
+        let mut widgets = Vec::new();
+        if let Some(kid_array) = object.get_dict("Kids")? {
+            for j in 0..kid_array.len()? as i32 {
+                let Some(kid) = kid_array.get_array(j)? else {
+                    continue;
+                };
+                // Kids without a name of their own are widgets of this field
+                if kid.get_dict("T")?.is_none() && is_widget(&kid)? {
+                    widgets.push(kid);
+                }
+            }
+            kids = load_fields(&kid_array, &full_name, depth + 1, visited)?
+                .into_iter()
+                .filter(|kid| !kid.name.is_empty() || !kid.kids.is_empty())
+                .collect();
+        }
+        if is_widget(&object)? {
+            widgets.push(object.clone());
+        }
+        fields.push(FormField {
+            name,
+            full_name,
+            object,
+            kids,
+            widgets,
+        });
+    }
+    Ok(fields)
+}
+
+impl FormField {
+    fn find(fields: &[FormField], full_name: &str) -> Option<FormField> {
+        for field in fields {
+            if field.full_name == full_name {
+                return Some(field.clone());
+            }
+            let below = field.full_name.is_empty()
+                || full_name
+                    .strip_prefix(field.full_name.as_str())
+                    .is_some_and(|rest| rest.starts_with('.'));
+            if below {
+                if let Some(found) = Self::find(&field.kids, full_name) {
+                    return Some(found);
+                }
+            }
+        }
+        None
+    }
+}
+
+impl PdfDocument {
+    /// The field tree of the form of the document, empty without a form.
+    pub fn form_fields(&self) -> Result<Vec<FormField>, Error> {
+        match self.catalog()?.get_dict("AcroForm")? {
+            Some(form) => match form.get_dict("Fields")? {
+                Some(fields) => load_fields(&fields, "", 0, &mut HashSet::new()),
+                None => Ok(Vec::new()),
+            },
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// The field with the fully qualified name `full_name`, e.g. `address.city`.
+    pub fn find_field(&self, full_name: &str) -> Result<Option<FormField>, Error> {
+        Ok(FormField::find(&self.form_fields()?, full_name))
+    }
+
+    /// Change the partial name of the field with the fully qualified name `full_name`, which
+    /// renames the fields below it too. Fails if a sibling has the new name already.
+    pub fn rename_field(&mut self, full_name: &str, name: &str) -> Result<(), Error> {
+        if name.is_empty() || name.contains('.') {
+            return Err(Error::InvalidAnnotation(format!(
+                "invalid field name {:?}",
+                name
+            )));
+        }
+        let mut field = self
+            .find_field(full_name)?
+            .ok_or_else(|| Error::InvalidAnnotation(format!("no field named {}", full_name)))?;
+        let parent = match full_name.rfind('.') {
+            Some(i) => &full_name[..i],
+            None => "",
+        };
+        let siblings = match parent {
+            "" => self.form_fields()?,
+            parent => self.find_field(parent)?.map(|p| p.kids).unwrap_or_default(),
+        };
+        if siblings.iter().any(|s| s.name == name) {
+            return Err(Error::InvalidAnnotation(format!(
+                "a field named {} exists",
+                join_name(parent, name)
+            )));
+        }
+        field.object.dict_put("T", PdfObject::new_string(name)?)
+    }
+}
+
+impl PdfAnnotation {
+    /// The fully qualified name of the field of a widget.
+    pub fn field_name(&self) -> Result<String, Error> {
+        let mut names = Vec::new();
+        let mut visited = HashSet::new();
+        let mut field = Some(self.object());
+        while let Some(obj) = field {
+            if !first_visit(&obj, &mut visited)? {
+                break;
+            }
+            let name = partial_name(&obj)?;
+            if !name.is_empty() {
+                names.push(name);
+            }
+            field = obj.get_dict("Parent")?;
+        }
+        names.reverse();
+        Ok(names.join("."))
+    }
+}
+
+/// The order keyboard navigation visits the widgets of a page in, the `Tabs` entry of the
+/// page.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TabOrder {
+    /// By rows from top to bottom, each row from left to right.
+    Row,
+    /// By columns from left to right, each column from top to bottom.
+    Column,
+    /// In the order of the structure tree.
+    Structure,
+    /// In the order of the annotations of the page.
+    Annotations,
+    /// In the order of the widgets of the page.
+    Widgets,
+}
+
+impl TabOrder {
+    fn name(self) -> &'static str {
+        match self {
+            TabOrder::Row => "R",
+            TabOrder::Column => "C",
+            TabOrder::Structure => "S",
+            TabOrder::Annotations => "A",
+            TabOrder::Widgets => "W",
+        }
+    }
+
+    fn from_name(name: &[u8]) -> Option<Self> {
+        match name {
+            b"R" => Some(TabOrder::Row),
+            b"C" => Some(TabOrder::Column),
+            b"S" => Some(TabOrder::Structure),
+            b"A" => Some(TabOrder::Annotations),
+            b"W" => Some(TabOrder::Widgets),
+            _ => None,
+        }
+    }
+}
+
+impl PdfPage {
+    /// The tab order of the page, `None` if unspecified.
+    pub fn tab_order(&self) -> Result<Option<TabOrder>, Error> {
+        match self.object().get_dict("Tabs")? {
+            Some(tabs) if tabs.is_name()? => Ok(TabOrder::from_name(tabs.as_name()?)),
+            _ => Ok(None),
+        }
+    }
+
+    pub fn set_tab_order(&mut self, order: Option<TabOrder>) -> Result<(), Error> {
+        match order {
+            Some(order) => self
+                .object()
+                .dict_put("Tabs", PdfObject::new_name(order.name())?),
+            None => self.object().dict_delete("Tabs"),
+        }
+    }
+
+    /// The widgets of the page in the order keyboard navigation visits them. Pages in row or
+    /// column order sort their widgets by position, others keep the order of the annotations
+    /// of the page, which is usually also the order of the structure tree.
+    pub fn widgets_in_tab_order(&self) -> Result<Vec<PdfAnnotation>, Error> {
+        let mut widgets = self
+            .widgets()
+            .map(|w| Ok((w.rect()?, w)))
+            .collect::<Result<Vec<_>, Error>>()?;
+        match self.tab_order()? {
+            Some(TabOrder::Row) => {
+                widgets.sort_by(|(a, _), (b, _)| a.y0.total_cmp(&b.y0).then(a.x0.total_cmp(&b.x0)))
+            }
+            Some(TabOrder::Column) => {
+                widgets.sort_by(|(a, _), (b, _)| a.x0.total_cmp(&b.x0).then(a.y0.total_cmp(&b.y0)))
+            }
+            _ => {}
+        }
+        Ok(widgets.into_iter().map(|(_, w)| w).collect())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{FieldFlags, FieldKind, TabOrder};
+    use crate::pdf::{AppearanceKind, ChoiceOption, PdfDocument, PdfPage};
+    use crate::Rect;
+
+    #[test]
+    fn test_create_fields() {
+        let mut doc = PdfDocument::new();
+        let mut page = doc.new_page((300.0, 400.0)).unwrap();
+        let mut name = page
+            .create_field(FieldKind::Text, "name", Rect::new(10.0, 10.0, 200.0, 30.0))
+            .unwrap();
+        name.set_field_flags(FieldFlags::REQUIRED | FieldFlags::MULTILINE)
+            .unwrap();
+        assert_eq!(
+            name.field_flags().unwrap(),
+            FieldFlags::REQUIRED | FieldFlags::MULTILINE
+        );
+        assert!(page
+            .create_field(FieldKind::Text, "name", Rect::new(10.0, 40.0, 200.0, 60.0))
+            .is_err());
+
+        let agree = page
+            .create_field(
+                FieldKind::CheckBox,
+                "agree",
+                Rect::new(10.0, 40.0, 24.0, 54.0),
+            )
+            .unwrap();
+        let mut states = agree.appearance_states(AppearanceKind::Normal).unwrap();
+        states.sort();
+        assert_eq!(states, ["Off", "Yes"]);
+
+        let mut fruit = page
+            .create_field(
+                FieldKind::ComboBox,
+                "fruit",
+                Rect::new(10.0, 60.0, 200.0, 80.0),
+            )
+            .unwrap();
+        assert!(fruit.is_combo_box().unwrap());
+        fruit
+            .set_choice_options(&[ChoiceOption::from("Apple"), ChoiceOption::from("Pear")])
+            .unwrap();
+        fruit.set_choice_values(&["Pear"]).unwrap();
+
+        page.create_field(
+            FieldKind::PushButton,
+            "Submit",
+            Rect::new(10.0, 90.0, 80.0, 110.0),
+        )
+        .unwrap();
+        page.create_field(
+            FieldKind::Signature,
+            "signature",
+            Rect::new(10.0, 120.0, 200.0, 160.0),
+        )
+        .unwrap();
+        let radios = page
+            .create_radio_group(
+                "size",
+                &[
+                    (Rect::new(10.0, 170.0, 24.0, 184.0), "S"),
+                    (Rect::new(30.0, 170.0, 44.0, 184.0), "M"),
+                ],
+            )
+            .unwrap();
+        assert_eq!(radios.len(), 2);
+        assert_eq!(
+            radios[1].field_flags().unwrap(),
+            FieldFlags::RADIO | FieldFlags::NO_TOGGLE_TO_OFF
+        );
+        let mut states = radios[1].appearance_states(AppearanceKind::Normal).unwrap();
+        states.sort();
+        assert_eq!(states, ["M", "Off"]);
+        assert_eq!(page.widgets().count(), 7);
+        assert!(doc.has_acro_form().unwrap());
+
+        let mut buf = Vec::new();
+        doc.write_to(&mut buf).unwrap();
+        let doc = PdfDocument::from_bytes(&buf).unwrap();
+        let page = PdfPage::try_from(doc.load_page(0).unwrap()).unwrap();
+        assert_eq!(page.widgets().count(), 7);
+    }
+
+    #[test]
+    fn test_field_tree() {
+        let mut doc = PdfDocument::new();
+        let mut page = doc.new_page((300.0, 400.0)).unwrap();
+        page.create_field(FieldKind::Text, "zip", Rect::new(10.0, 50.0, 100.0, 70.0))
+            .unwrap();
+        page.create_field(FieldKind::Text, "name", Rect::new(10.0, 10.0, 100.0, 30.0))
+            .unwrap();
+        page.create_radio_group(
+            "size",
+            &[
+                (Rect::new(150.0, 10.0, 164.0, 24.0), "S"),
+                (Rect::new(170.0, 10.0, 184.0, 24.0), "M"),
+            ],
+        )
+        .unwrap();
+
+        // Move the zip code into an address field
+        let mut zip = doc.find_field("zip").unwrap().unwrap().object;
+        let address = doc
+            .new_object_from_str(&format!(
+                "<< /T (address) /Kids [{} 0 R] >>",
+                zip.as_indirect().unwrap()
+            ))
+            .unwrap();
+        let address = doc.add_object(&address).unwrap();
+        let mut fields = doc
+            .catalog()
+            .unwrap()
+            .get_dict("AcroForm")
+            .unwrap()
+            .unwrap()
+            .get_dict("Fields")
+            .unwrap()
+            .unwrap();
+        fields.array_put(0, address.clone()).unwrap();
+        zip.dict_put("Parent", address).unwrap();
+
+        let tree = doc.form_fields().unwrap();
+        let names: Vec<_> = tree.iter().map(|f| f.full_name.as_str()).collect();
+        assert_eq!(names, ["address", "name", "size"]);
+        assert_eq!(tree[0].kids[0].full_name, "address.zip");
+        assert_eq!(tree[0].kids[0].widgets.len(), 1);
+        assert!(tree[2].kids.is_empty());
+        assert_eq!(tree[2].widgets.len(), 2);
+
+        doc.rename_field("address.zip", "postcode").unwrap();
+        assert!(doc.find_field("address.zip").unwrap().is_none());
+        assert!(doc.find_field("address.postcode").unwrap().is_some());
+        assert!(doc.rename_field("name", "size").is_err());
+
+        let mut page = PdfPage::try_from(doc.load_page(0).unwrap()).unwrap();
+        let names = |page: &PdfPage| -> Vec<String> {
+            page.widgets_in_tab_order()
+                .unwrap()
+                .iter()
+                .map(|w| w.field_name().unwrap())
+                .collect()
+        };
+        assert_eq!(page.tab_order().unwrap(), None);
+        assert_eq!(names(&page), ["address.postcode", "name", "size", "size"]);
+        page.set_tab_order(Some(TabOrder::Row)).unwrap();
+        assert_eq!(page.tab_order().unwrap(), Some(TabOrder::Row));
+        assert_eq!(names(&page), ["name", "size", "size", "address.postcode"]);
+        page.set_tab_order(Some(TabOrder::Column)).unwrap();
+        assert_eq!(names(&page), ["name", "address.postcode", "size", "size"]);
+    }
+
+    #[test]
+    fn test_field_tree_cycle() {
+        let mut doc = PdfDocument::new();
+        let mut page = doc.new_page((300.0, 400.0)).unwrap();
+        let radios = page
+            .create_radio_group("size", &[(Rect::new(10.0, 10.0, 24.0, 24.0), "S")])
+            .unwrap();
+
+        // A broken file whose field is its own kid and parent
+        let mut size = doc.find_field("size").unwrap().unwrap().object;
+        size.get_dict("Kids")
+            .unwrap()
+            .unwrap()
+            .array_push(size.clone())
+            .unwrap();
+        size.dict_put("Parent", size.clone()).unwrap();
+
+        let tree = doc.form_fields().unwrap();
+        assert_eq!(tree.len(), 1);
+        assert!(tree[0].kids.is_empty());
+        assert_eq!(radios[0].field_name().unwrap(), "size");
+    }
+}
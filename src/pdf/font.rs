@@ -0,0 +1,53 @@
+use crate::pdf::PdfObject;
+use crate::{Error, Font};
+
+/// A font embedded into a PDF document as a Type0 font with an `Identity-H` encoded
+/// CIDFontType2 (or CIDFontType0C) descendant, glyph widths and a ToUnicode map.
+///
+/// Text shown with this font in a content stream must be encoded with [`FontRef::encode`].
+#[derive(Debug)]
+pub struct FontRef {
+    font: Font,
+    obj: PdfObject,
+}
+
+impl FontRef {
+    pub(crate) fn new(font: Font, obj: PdfObject) -> Self {
+        Self { font, obj }
+    }
+
+    pub fn font(&self) -> &Font {
+        &self.font
+    }
+
+    /// The indirect reference to the Type0 font dictionary.
+    pub fn object(&self) -> &PdfObject {
+        &self.obj
+    }
+
+    pub fn into_object(self) -> PdfObject {
+        self.obj
+    }
+
+    /// Encode `text` as big-endian glyph ids, ready to be shown with `Tj` as a hex string.
+    ///
+    /// Characters missing from the font are mapped to glyph 0 (`.notdef`).
+    pub fn encode(&self, text: &str) -> Result<Vec<u8>, Error> {
+        let mut bytes = Vec::with_capacity(text.len() * 2);
+        for c in text.chars() {
+            let gid = self.font.encode_character(c as i32)?;
+            bytes.extend_from_slice(&(gid as u16).to_be_bytes());
+        }
+        Ok(bytes)
+    }
+
+    /// The advance width of `text` when set at `size` points.
+    pub fn text_width(&self, text: &str, size: f32) -> Result<f32, Error> {
+        let mut width = 0.0;
+        for c in text.chars() {
+            let gid = self.font.encode_character(c as i32)?;
+            width += self.font.advance_glyph(gid)?;
+        }
+        Ok(width * size)
+    }
+}
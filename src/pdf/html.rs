@@ -0,0 +1,275 @@
+use std::ffi::CString;
+use std::io::{self, Read};
+
+use mupdf_sys::*;
+
+use crate::pdf::PdfDocument;
+use crate::{context, Buffer, Device, DocumentWriter, Error, Matrix, Rect, Size};
+
+/// Page layout for [`PdfDocument::from_html`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct PageOptions {
+    size: Size,
+    margins: [f32; 4],
+    em: f32,
+    header: Option<String>,
+    footer: Option<String>,
+}
+
+impl Default for PageOptions {
+    fn default() -> Self {
+        Self {
+            size: Size::A4,
+            margins: [36.0; 4],
+            em: 12.0,
+            header: None,
+            footer: None,
+        }
+    }
+}
+
+impl PageOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Page size in points, A4 by default.
+    pub fn set_size<T: Into<Size>>(&mut self, size: T) -> &mut Self {
+        self.size = size.into();
+        self
+    }
+
+    /// Margins in points, half an inch on all sides by default. Headers and footers are laid out
+    /// in the top and bottom margins.
+    pub fn set_margins(&mut self, top: f32, right: f32, bottom: f32, left: f32) -> &mut Self {
+        self.margins = [top, right, bottom, left];
+        self
+    }
+
+    /// Default font size in points, `12.0` by default.
+    pub fn set_em(&mut self, em: f32) -> &mut Self {
+        self.em = em;
+        self
+    }
+
+    /// HTML laid out in the top margin of every page, with `{page}` replaced by the page number
+    /// and `{pages}` by the page count.
+    pub fn set_header(&mut self, html: &str) -> &mut Self {
+        self.header = Some(html.to_owned());
+        self
+    }
+
+    /// HTML laid out in the bottom margin of every page, see [`PageOptions::set_header`].
+    pub fn set_footer(&mut self, html: &str) -> &mut Self {
+        self.footer = Some(html.to_owned());
+        self
+    }
+
+    pub fn size(&self) -> Size {
+        self.size
+    }
+
+    /// Top, right, bottom and left margins.
+    pub fn margins(&self) -> [f32; 4] {
+        self.margins
+    }
+
+    pub fn em(&self) -> f32 {
+        self.em
+    }
+
+    pub fn header(&self) -> Option<&str> {
+        self.header.as_deref()
+    }
+
+    pub fn footer(&self) -> Option<&str> {
+        self.footer.as_deref()
+    }
+
+    fn content_rect(&self) -> Rect {
+        let [top, right, bottom, left] = self.margins;
+        Rect::new(
+            left,
+            top,
+            self.size.width - right,
+            self.size.height - bottom,
+        )
+    }
+}
+
+fn too_large() -> Error {
+    Error::Io(io::Error::new(
+        io::ErrorKind::InvalidInput,
+        "content too large to fit on a page",
+    ))
+}
+
+/// HTML content being laid out into rectangles.
+struct Story {
+    inner: *mut fz_story,
+}
+
+impl Story {
+    fn new(html: &str, css: &str, em: f32) -> Result<Self, Error> {
+        let c_html = CString::new(html)?;
+        let c_css = CString::new(css)?;
+        unsafe {
+            ffi_try!(mupdf_new_story(
+                context(),
+                c_html.as_ptr(),
+                c_css.as_ptr(),
+                em
+            ))
+        }
+        .map(|inner| Self { inner })
+    }
+
+    /// Lay out as much as fits into `rect`, returning the area used and whether content is left.
+    fn place(&mut self, rect: Rect) -> Result<(Rect, bool), Error> {
+        let mut filled: fz_rect = Rect::default().into();
+        let more = unsafe {
+            ffi_try!(mupdf_place_story(
+                context(),
+                self.inner,
+                rect.into(),
+                &mut filled
+            ))
+        }?;
+        Ok((filled.into(), more))
+    }
+
+    fn draw(&mut self, device: &Device) -> Result<(), Error> {
+        unsafe {
+            ffi_try!(mupdf_draw_story(
+                context(),
+                self.inner,
+                device.dev,
+                Matrix::IDENTITY.into()
+            ))
+        }
+    }
+
+    /// Number of pages the content takes when laid out into `rect` on each.
+    fn count_pages(mut self, rect: Rect) -> Result<usize, Error> {
+        let mut pages = 1;
+        loop {
+            match self.place(rect)? {
+                (_, false) => return Ok(pages),
+                (filled, true) if filled.is_empty() => return Err(too_large()),
+                _ => pages += 1,
+            }
+        }
+    }
+}
+
+impl Drop for Story {
+    fn drop(&mut self) {
+        unsafe { fz_drop_story(context(), self.inner) }
+    }
+}
+
+impl PdfDocument {
+    /// Lay out `html`, styled with `css` on top of the default style sheet, over as many pages
+    /// as it takes and write them into a new PDF document.
+    ///
+    /// This uses MuPDF's HTML engine, which supports a good part of CSS 2 but no scripting. Use
+    /// CSS `page-break-before` and `page-break-after` to control pagination.
+    pub fn from_html(html: &str, css: &str, options: &PageOptions) -> Result<Self, Error> {
+        let rect = options.content_rect();
+        if rect.is_empty() {
+            return Err(Error::Io(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "margins leave no room for content",
+            )));
+        }
+        let has_decorations = options.header.is_some() || options.footer.is_some();
+        let pages = if has_decorations {
+            Story::new(html, css, options.em)?.count_pages(rect)?
+        } else {
+            0
+        };
+
+        let mut buf = Buffer::new();
+        {
+            let mut writer = DocumentWriter::pdf_to_buffer(&buf, "")?;
+            let mediabox = Rect::new(0.0, 0.0, options.size.width, options.size.height);
+            let [top, right, _, left] = options.margins;
+            let header_rect = Rect::new(left, 0.0, mediabox.x1 - right, top);
+            let footer_rect = Rect::new(left, rect.y1, mediabox.x1 - right, mediabox.y1);
+
+            let mut story = Story::new(html, css, options.em)?;
+            let mut page = 0;
+            loop {
+                page += 1;
+                let device = writer.begin_page(mediabox)?;
+                let (filled, more) = story.place(rect)?;
+                story.draw(&device)?;
+                for (decoration, area) in [
+                    (&options.header, header_rect),
+                    (&options.footer, footer_rect),
+                ] {
+                    let Some(template) = decoration else {
+                        continue;
+                    };
+                    if area.is_empty() {
+                        continue;
+                    }
+                    let html = template
+                        .replace("{page}", &page.to_string())
+                        .replace("{pages}", &pages.to_string());
+                    let mut decoration = Story::new(&html, css, options.em)?;
+                    decoration.place(area)?;
+                    decoration.draw(&device)?;
+                }
+                writer.end_page(device)?;
+                if !more {
+                    break;
+                }
+                if filled.is_empty() {
+                    return Err(too_large());
+                }
+            }
+            writer.close()?;
+        }
+
+        let mut data = Vec::with_capacity(buf.len());
+        buf.read_to_end(&mut data)?;
+        Self::from_bytes(&data)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::PageOptions;
+    use crate::pdf::PdfDocument;
+    use crate::{Size, TextPageOptions};
+
+    #[test]
+    fn test_pdf_from_html() {
+        let paragraphs: String = (0..120)
+            .map(|i| format!("<p>Paragraph number {}</p>", i))
+            .collect();
+        let html = format!("<h1>Report</h1>{}", paragraphs);
+        let doc = PdfDocument::from_html(
+            &html,
+            "p { margin: 0.5em 0 }",
+            PageOptions::new()
+                .set_size(Size::LETTER)
+                .set_footer("<p>Page {page} of {pages}</p>"),
+        )
+        .unwrap();
+        let pages = doc.page_count().unwrap();
+        assert!(pages > 1);
+
+        let page = doc.load_page(0).unwrap();
+        let bounds = page.bounds().unwrap();
+        assert_eq!((bounds.width(), bounds.height()), (612.0, 792.0));
+        let text = page
+            .to_text_page(TextPageOptions::empty())
+            .unwrap()
+            .to_text()
+            .unwrap();
+        assert!(text.contains("Report"));
+        assert!(text.contains(&format!("Page 1 of {}", pages)));
+    }
+}
@@ -0,0 +1,200 @@
+use crate::Point;
+
+/// A position reported by a stylus, pen tablet or touch screen.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StylusSample {
+    pub point: Point,
+    /// Normalized pressure from 0 to 1, 0 meaning the pen was lifted. Devices without pressure
+    /// sensing report 1 while the pen is down.
+    pub pressure: f32,
+}
+
+impl StylusSample {
+    pub fn new(point: Point, pressure: f32) -> Self {
+        Self { point, pressure }
+    }
+}
+
+/// How [`ink_list_from_samples`] cleans up stylus input.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InkSmoothing {
+    window: usize,
+    tolerance: f32,
+}
+
+impl Default for InkSmoothing {
+    fn default() -> Self {
+        Self {
+            window: 5,
+            tolerance: 0.5,
+        }
+    }
+}
+
+impl InkSmoothing {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of samples each point is averaged over to remove jitter, 1 to keep the points.
+    pub fn window(&self) -> usize {
+        self.window
+    }
+
+    pub fn set_window(&mut self, window: usize) -> &mut Self {
+        self.window = window.max(1);
+        self
+    }
+
+    /// How far, in points, the simplified stroke may stray from the smoothed one. Dropping
+    /// points that add nothing keeps files small, 0 keeps all points.
+    pub fn tolerance(&self) -> f32 {
+        self.tolerance
+    }
+
+    pub fn set_tolerance(&mut self, tolerance: f32) -> &mut Self {
+        self.tolerance = tolerance.max(0.0);
+        self
+    }
+}
+
+/// Average each point with its neighbours, weighted by pressure so that the light touches at
+/// the ends of a stroke pull less. The end points stay where they are.
+fn smooth(samples: &[StylusSample], window: usize) -> Vec<Point> {
+    let half = window / 2;
+    (0..samples.len())
+        .map(|i| {
+            if i == 0 || i + 1 == samples.len() || half == 0 {
+                return samples[i].point;
+            }
+            // Shrink the window near the ends so it stays centered
+            let reach = half.min(i).min(samples.len() - 1 - i);
+            let neighbours = &samples[i - reach..=i + reach];
+            let weight: f32 = neighbours.iter().map(|s| s.pressure).sum();
+            let (x, y) = neighbours.iter().fold((0.0, 0.0), |(x, y), s| {
+                (x + s.point.x * s.pressure, y + s.point.y * s.pressure)
+            });
+            Point::new(x / weight, y / weight)
+        })
+        .collect()
+}
+
+fn distance_to_segment(p: Point, a: Point, b: Point) -> f32 {
+    let (dx, dy) = (b.x - a.x, b.y - a.y);
+    let len2 = dx * dx + dy * dy;
+    let t = if len2 == 0.0 {
+        0.0
+    } else {
+        (((p.x - a.x) * dx + (p.y - a.y) * dy) / len2).clamp(0.0, 1.0)
+    };
+    let (x, y) = (a.x + t * dx, a.y + t * dy);
+    ((p.x - x).powi(2) + (p.y - y).powi(2)).sqrt()
+}
+
+/// Ramer-Douglas-Peucker simplification.
+fn simplify(points: &[Point], tolerance: f32, out: &mut Vec<Point>) {
+    let (first, last) = (points[0], points[points.len() - 1]);
+    let farthest = (1..points.len() - 1)
+        .map(|i| (i, distance_to_segment(points[i], first, last)))
+        .fold((0, 0.0), |best, d| if d.1 > best.1 { d } else { best });
+    if farthest.1 > tolerance {
+        simplify(&points[..=farthest.0], tolerance, out);
+        out.pop();
+        simplify(&points[farthest.0..], tolerance, out);
+    } else {
+        out.push(first);
+        out.push(last);
+    }
+}
+
+/// Turn stylus samples into the strokes of an ink annotation, for
+/// [`PdfAnnotation::set_ink_list`](crate::pdf::PdfAnnotation::set_ink_list).
+///
+/// Samples without pressure end a stroke. Each stroke is smoothed and simplified as set by
+/// `smoothing`, a single sample becoming a dot.
+pub fn ink_list_from_samples(
+    samples: &[StylusSample],
+    smoothing: &InkSmoothing,
+) -> Vec<Vec<Point>> {
+    samples
+        .split(|s| s.pressure <= 0.0)
+        .filter(|stroke| !stroke.is_empty())
+        .map(|stroke| {
+            let points = smooth(stroke, smoothing.window);
+            if points.len() == 1 {
+                return vec![points[0], points[0]];
+            }
+            let mut out = Vec::new();
+            simplify(&points, smoothing.tolerance, &mut out);
+            out
+        })
+        .collect()
+}
+
+/// The line width for ink drawn with the given samples: `max_width` scaled by the average
+/// pressure, since an ink annotation has a single width.
+pub fn ink_width(samples: &[StylusSample], max_width: f32) -> f32 {
+    let pressures: Vec<f32> = samples
+        .iter()
+        .map(|s| s.pressure.min(1.0))
+        .filter(|&p| p > 0.0)
+        .collect();
+    if pressures.is_empty() {
+        return max_width;
+    }
+    max_width * pressures.iter().sum::<f32>() / pressures.len() as f32
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ink_list_from_samples, ink_width, InkSmoothing, StylusSample};
+    use crate::pdf::{PdfAnnotationType, PdfDocument, PdfPage};
+    use crate::Point;
+
+    #[test]
+    fn test_ink_list_from_samples() {
+        let mut samples: Vec<StylusSample> = (0..=20)
+            .map(|i| {
+                // A straight line with jitter
+                let jitter = if i % 2 == 0 { 0.2 } else { -0.2 };
+                StylusSample::new(Point::new(i as f32 * 5.0, 100.0 + jitter), 0.5)
+            })
+            .collect();
+        samples.push(StylusSample::new(Point::new(0.0, 0.0), 0.0));
+        samples.push(StylusSample::new(Point::new(50.0, 50.0), 1.0));
+
+        let strokes = ink_list_from_samples(&samples, &InkSmoothing::default());
+        assert_eq!(strokes.len(), 2);
+        assert_eq!(strokes[0].len(), 2);
+        assert_eq!(strokes[0][0], Point::new(0.0, 100.2));
+        assert_eq!(strokes[0][1], Point::new(100.0, 100.2));
+        assert_eq!(
+            strokes[1],
+            vec![Point::new(50.0, 50.0), Point::new(50.0, 50.0)]
+        );
+
+        let mut exact = InkSmoothing::new();
+        exact.set_window(1).set_tolerance(0.0);
+        assert_eq!(ink_list_from_samples(&samples, &exact)[0].len(), 21);
+        assert_eq!(ink_width(&samples, 4.0), 4.0 * (21.0 * 0.5 + 1.0) / 22.0);
+    }
+
+    #[test]
+    fn test_annotation_ink_list() {
+        let doc = PdfDocument::open("tests/files/dummy.pdf").unwrap();
+        let mut page = PdfPage::try_from(doc.load_page(0).unwrap()).unwrap();
+        let mut annot = page.create_annotation(PdfAnnotationType::Ink).unwrap();
+        let strokes = vec![
+            vec![Point::new(10.0, 10.0), Point::new(50.0, 60.0)],
+            vec![
+                Point::new(70.0, 10.0),
+                Point::new(80.0, 20.0),
+                Point::new(90.0, 10.0),
+            ],
+        ];
+        annot.set_ink_list(&strokes).unwrap();
+        annot.set_border_width(3.0).unwrap();
+        assert_eq!(annot.ink_list().unwrap(), strokes);
+        assert_eq!(annot.border_width().unwrap(), 3.0);
+    }
+}
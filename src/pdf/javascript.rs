@@ -0,0 +1,320 @@
+use std::collections::HashSet;
+
+use crate::pdf::validation::name_of;
+use crate::pdf::{PdfDocument, PdfObject};
+use crate::Error;
+
+/// A named script from the `/JavaScript` name tree, run when the document opens.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DocumentScript {
+    pub name: String,
+    pub source: String,
+}
+
+/// The page events a script can be attached to, keys of the `/AA` dictionary of a page.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PageScriptEvent {
+    /// The page is opened.
+    Open,
+    /// The page is closed.
+    Close,
+}
+
+impl PageScriptEvent {
+    fn key(self) -> &'static str {
+        match self {
+            PageScriptEvent::Open => "O",
+            PageScriptEvent::Close => "C",
+        }
+    }
+}
+
+/// The source of a JavaScript action, `None` for other actions.
+fn script_source(action: &PdfObject) -> Result<Option<String>, Error> {
+    if !action.is_dict()? || name_of(action, "S")?.as_deref() != Some(b"JavaScript") {
+        return Ok(None);
+    }
+    let Some(js) = action.get_dict("JS")? else {
+        return Ok(None);
+    };
+    if js.is_stream()? {
+        let data = js.read_stream()?;
+        match data.strip_prefix(&[0xfe, 0xff]) {
+            Some(utf16) => {
+                let units: Vec<u16> = utf16
+                    .chunks_exact(2)
+                    .map(|c| u16::from_be_bytes([c[0], c[1]]))
+                    .collect();
+                Ok(Some(String::from_utf16_lossy(&units)))
+            }
+            None => Ok(Some(String::from_utf8_lossy(&data).into_owned())),
+        }
+    } else if js.is_string()? {
+        Ok(Some(js.as_string()?.to_owned()))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Collect the entries of the name tree node `node` and its kids, the key and value objects.
+fn name_tree_entries(
+    node: &PdfObject,
+    visited: &mut HashSet<i32>,
+    entries: &mut Vec<(PdfObject, PdfObject)>,
+) -> Result<(), Error> {
+    // Guard against cyclic trees
+    if node.is_indirect()? && !visited.insert(node.as_indirect()?) {
+        return Ok(());
+    }
+    if let Some(names) = node.get_dict("Names")? {
+        for i in (0..names.len()? as i32 - 1).step_by(2) {
+            if let (Some(key), Some(value)) = (names.get_array(i)?, names.get_array(i + 1)?) {
+                entries.push((key, value));
+            }
+        }
+    }
+    if let Some(kids) = node.get_dict("Kids")? {
+        for i in 0..kids.len()? as i32 {
+            if let Some(kid) = kids.get_array(i)? {
+                name_tree_entries(&kid, visited, entries)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Whether the key of a name tree entry is `name`, comparing the decoded text.
+fn is_named(key: &PdfObject, name: &str) -> Result<bool, Error> {
+    Ok(key.is_string()? && key.as_string()? == name)
+}
+
+/// Remove the entries with the key `name`, returning whether there were any.
+fn remove_named(entries: &mut Vec<(PdfObject, PdfObject)>, name: &str) -> Result<bool, Error> {
+    let len = entries.len();
+    let mut kept = Vec::with_capacity(len);
+    for (key, value) in entries.drain(..) {
+        if !is_named(&key, name)? {
+            kept.push((key, value));
+        }
+    }
+    *entries = kept;
+    Ok(entries.len() != len)
+}
+
+impl PdfDocument {
+    fn javascript_entries(&self) -> Result<Vec<(PdfObject, PdfObject)>, Error> {
+        let mut entries = Vec::new();
+        if let Some(tree) = self.resolve_path("Root/Names/JavaScript")? {
+            name_tree_entries(&tree, &mut HashSet::new(), &mut entries)?;
+        }
+        Ok(entries)
+    }
+
+    /// Replace the `/JavaScript` name tree by a single node holding `entries`.
+    fn set_javascript_entries(
+        &mut self,
+        entries: Vec<(PdfObject, PdfObject)>,
+    ) -> Result<(), Error> {
+        let mut catalog = self.catalog()?;
+        let mut names = match catalog.get_dict("Names")? {
+            Some(names) => names,
+            None if entries.is_empty() => return Ok(()),
+            None => {
+                catalog.dict_put("Names", self.new_dict()?)?;
+                catalog.get_dict("Names")?.ok_or(Error::UnexpectedNullPtr)?
+            }
+        };
+        if entries.is_empty() {
+            return names.dict_delete("JavaScript");
+        }
+        // Keys of name trees are sorted by their bytes
+        let mut entries = entries
+            .into_iter()
+            .map(|(key, value)| Ok((key.as_bytes()?.to_vec(), key, value)))
+            .collect::<Result<Vec<_>, Error>>()?;
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        let mut array = self.new_array()?;
+        for (_, key, value) in entries {
+            array.array_push(key)?;
+            array.array_push(value)?;
+        }
+        let mut tree = self.new_dict()?;
+        tree.dict_put("Names", array)?;
+        names.dict_put("JavaScript", self.add_object(&tree)?)
+    }
+
+    /// The document level scripts, in the order of the name tree.
+    pub fn document_scripts(&self) -> Result<Vec<DocumentScript>, Error> {
+        let mut scripts = Vec::new();
+        for (key, action) in self.javascript_entries()? {
+            if let Some(source) = script_source(&action)? {
+                let name = if key.is_string()? {
+                    key.as_string()?.to_owned()
+                } else {
+                    String::new()
+                };
+                scripts.push(DocumentScript { name, source });
+            }
+        }
+        Ok(scripts)
+    }
+
+    /// Add a document level script, replacing any script of the same name.
+    pub fn add_document_script(&mut self, name: &str, source: &str) -> Result<(), Error> {
+        let mut entries = self.javascript_entries()?;
+        remove_named(&mut entries, name)?;
+        let mut action = self.new_dict()?;
+        action.dict_put("S", PdfObject::new_name("JavaScript")?)?;
+        action.dict_put("JS", PdfObject::new_string(source)?)?;
+        entries.push((PdfObject::new_string(name)?, self.add_object(&action)?));
+        self.set_javascript_entries(entries)
+    }
+
+    /// Remove the document level script `name`, returning whether there was one.
+    pub fn remove_document_script(&mut self, name: &str) -> Result<bool, Error> {
+        let mut entries = self.javascript_entries()?;
+        if !remove_named(&mut entries, name)? {
+            return Ok(false);
+        }
+        self.set_javascript_entries(entries)?;
+        Ok(true)
+    }
+
+    /// The script run on `event` of page `page_no`, `None` if it runs none or another action.
+    pub fn page_script(
+        &self,
+        page_no: i32,
+        event: PageScriptEvent,
+    ) -> Result<Option<String>, Error> {
+        let page = self.find_page(page_no)?;
+        let Some(actions) = page.get_dict("AA")? else {
+            return Ok(None);
+        };
+        match actions.get_dict(event.key())? {
+            Some(action) => script_source(&action),
+            None => Ok(None),
+        }
+    }
+
+    /// Set the script run on `event` of page `page_no`, or remove the action with `None`.
+    pub fn set_page_script(
+        &mut self,
+        page_no: i32,
+        event: PageScriptEvent,
+        source: Option<&str>,
+    ) -> Result<(), Error> {
+        let mut page = self.find_page(page_no)?;
+        let mut actions = match page.get_dict("AA")? {
+            Some(actions) => actions,
+            None if source.is_none() => return Ok(()),
+            None => {
+                page.dict_put("AA", self.new_dict()?)?;
+                page.get_dict("AA")?.ok_or(Error::UnexpectedNullPtr)?
+            }
+        };
+        match source {
+            Some(source) => {
+                let mut action = self.new_dict()?;
+                action.dict_put("S", PdfObject::new_name("JavaScript")?)?;
+                action.dict_put("JS", PdfObject::new_string(source)?)?;
+                actions.dict_put(event.key(), action)
+            }
+            None => {
+                actions.dict_delete(event.key())?;
+                if actions.dict_len()? == 0 {
+                    page.dict_delete("AA")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{DocumentScript, PageScriptEvent};
+    use crate::pdf::PdfDocument;
+
+    #[test]
+    fn test_document_scripts() {
+        let mut doc = PdfDocument::open("tests/files/dummy.pdf").unwrap();
+        assert!(doc.document_scripts().unwrap().is_empty());
+
+        doc.add_document_script("setup", "var x = 1;").unwrap();
+        doc.add_document_script("init", "app.alert('hi');").unwrap();
+        doc.add_document_script("setup", "var x = 2;").unwrap();
+        assert_eq!(
+            doc.document_scripts().unwrap(),
+            [
+                DocumentScript {
+                    name: "init".to_owned(),
+                    source: "app.alert('hi');".to_owned(),
+                },
+                DocumentScript {
+                    name: "setup".to_owned(),
+                    source: "var x = 2;".to_owned(),
+                },
+            ]
+        );
+
+        assert!(doc.remove_document_script("init").unwrap());
+        assert!(!doc.remove_document_script("init").unwrap());
+        assert_eq!(doc.document_scripts().unwrap().len(), 1);
+        doc.strip_javascript().unwrap();
+        assert!(doc.document_scripts().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_document_scripts_utf16_keys() {
+        let mut doc = PdfDocument::open("tests/files/dummy.pdf").unwrap();
+        doc.add_document_script("init", "var x = 1;").unwrap();
+        // A key in UTF-16 and a kid pointing back at the root
+        let mut tree = doc.resolve_path("Root/Names/JavaScript").unwrap().unwrap();
+        let action = doc
+            .new_object_from_str("<< /S /JavaScript /JS (var y = 1;) >>")
+            .unwrap();
+        let mut names = tree.get_dict("Names").unwrap().unwrap();
+        names
+            .array_push(
+                doc.new_object_from_str("<FEFF0073006500740075 0070>")
+                    .unwrap(),
+            )
+            .unwrap();
+        names.array_push(action).unwrap();
+        let mut kids = doc.new_array().unwrap();
+        kids.array_push(tree.clone()).unwrap();
+        tree.dict_put("Kids", kids).unwrap();
+
+        let scripts = doc.document_scripts().unwrap();
+        assert_eq!(scripts.len(), 2);
+        assert_eq!(scripts[1].name, "setup");
+        doc.add_document_script("setup", "var y = 2;").unwrap();
+        assert_eq!(doc.document_scripts().unwrap().len(), 2);
+        assert!(doc.remove_document_script("setup").unwrap());
+        assert_eq!(
+            doc.document_scripts().unwrap(),
+            [DocumentScript {
+                name: "init".to_owned(),
+                source: "var x = 1;".to_owned(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_page_scripts() {
+        let mut doc = PdfDocument::open("tests/files/dummy.pdf").unwrap();
+        doc.set_page_script(0, PageScriptEvent::Open, Some("console.println(1);"))
+            .unwrap();
+        assert_eq!(
+            doc.page_script(0, PageScriptEvent::Open)
+                .unwrap()
+                .as_deref(),
+            Some("console.println(1);")
+        );
+        assert_eq!(doc.page_script(0, PageScriptEvent::Close).unwrap(), None);
+
+        doc.set_page_script(0, PageScriptEvent::Open, None).unwrap();
+        assert_eq!(doc.page_script(0, PageScriptEvent::Open).unwrap(), None);
+        assert!(doc.find_page(0).unwrap().get_dict("AA").unwrap().is_none());
+    }
+}
@@ -0,0 +1,196 @@
+use std::ffi::{c_char, c_int, c_void, CStr, CString};
+
+use mupdf_sys::*;
+use num_enum::TryFromPrimitive;
+
+use crate::pdf::PdfDocument;
+use crate::{context, Error};
+
+/// The icon of an alert shown by the JavaScript of a document.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, TryFromPrimitive)]
+#[repr(i32)]
+pub enum AlertIcon {
+    Error = 0,
+    Warning = 1,
+    Question = 2,
+    Status = 3,
+}
+
+/// The buttons an alert offers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, TryFromPrimitive)]
+#[repr(i32)]
+pub enum AlertButtons {
+    Ok = 0,
+    OkCancel = 1,
+    YesNo = 2,
+    YesNoCancel = 3,
+}
+
+/// The button pressed to close an alert.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, TryFromPrimitive)]
+#[repr(i32)]
+pub enum AlertButton {
+    None = 0,
+    Ok = 1,
+    Cancel = 2,
+    No = 3,
+    Yes = 4,
+}
+
+/// An alert shown by `app.alert`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsAlert<'a> {
+    pub title: &'a str,
+    pub message: &'a str,
+    pub icon: AlertIcon,
+    pub buttons: AlertButtons,
+}
+
+/// Receives what the JavaScript of a document shows to the user, set with
+/// [`PdfDocument::set_js_handler`].
+pub trait JsHandler {
+    /// Output of `console.println`.
+    fn console(&mut self, _message: &str) {}
+
+    /// An alert to show, returning the button pressed. Without a user to ask, this presses OK.
+    fn alert(&mut self, _alert: &JsAlert<'_>) -> AlertButton {
+        AlertButton::Ok
+    }
+}
+
+type BoxedHandler = Box<dyn JsHandler>;
+
+fn str_from_ptr<'a>(ptr: *const c_char) -> std::borrow::Cow<'a, str> {
+    if ptr.is_null() {
+        return "".into();
+    }
+    unsafe { CStr::from_ptr(ptr) }.to_string_lossy()
+}
+
+unsafe extern "C" fn js_write(user: *mut c_void, message: *const c_char) {
+    let handler = unsafe { &mut *user.cast::<BoxedHandler>() };
+    handler.console(&str_from_ptr(message));
+}
+
+unsafe extern "C" fn js_alert(
+    user: *mut c_void,
+    title: *const c_char,
+    message: *const c_char,
+    icon: c_int,
+    buttons: c_int,
+) -> c_int {
+    let handler = unsafe { &mut *user.cast::<BoxedHandler>() };
+    let alert = JsAlert {
+        title: &str_from_ptr(title),
+        message: &str_from_ptr(message),
+        icon: AlertIcon::try_from(icon).unwrap_or(AlertIcon::Status),
+        buttons: AlertButtons::try_from(buttons).unwrap_or(AlertButtons::Ok),
+    };
+    handler.alert(&alert) as c_int
+}
+
+unsafe extern "C" fn js_drop(user: *mut c_void) {
+    drop(unsafe { Box::from_raw(user.cast::<BoxedHandler>()) });
+}
+
+impl PdfDocument {
+    /// Enable JavaScript and pass console output and alerts of the document's scripts to
+    /// `handler`, replacing the previous handler.
+    pub fn set_js_handler<H: JsHandler + 'static>(&mut self, handler: H) -> Result<(), Error> {
+        self.enable_js()?;
+        let user = Box::into_raw(Box::new(Box::new(handler) as BoxedHandler));
+        // The document owns the handler from here on, dropping it on failure too
+        unsafe {
+            ffi_try!(mupdf_pdf_set_js_handler(
+                context(),
+                self.inner,
+                Some(js_write),
+                Some(js_alert),
+                Some(js_drop),
+                user.cast()
+            ))
+        }
+    }
+
+    /// Set the value of the form field with the fully qualified `name`, running its keystroke,
+    /// validate and calculate actions as when a user types it, and the format actions
+    /// computing what it shows. Returns whether the value was accepted, scripts being able to
+    /// reject it.
+    ///
+    /// Without [`PdfDocument::enable_js`] or [`PdfDocument::set_js_handler`] no scripts run,
+    /// as with [`PdfDocument::set_field_value`].
+    pub fn set_field_value_with_events(&mut self, name: &str, value: &str) -> Result<bool, Error> {
+        let c_name = CString::new(name)?;
+        let c_value = CString::new(value)?;
+        unsafe {
+            ffi_try!(mupdf_pdf_set_field_value(
+                context(),
+                self.inner,
+                c_name.as_ptr(),
+                c_value.as_ptr(),
+                true
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use super::{AlertButton, JsAlert, JsHandler};
+    use crate::pdf::PdfDocument;
+
+    #[derive(Default)]
+    struct Recorder {
+        console: Rc<RefCell<String>>,
+        alerts: Rc<RefCell<Vec<String>>>,
+    }
+
+    impl JsHandler for Recorder {
+        fn console(&mut self, message: &str) {
+            self.console.borrow_mut().push_str(message);
+        }
+
+        fn alert(&mut self, alert: &JsAlert<'_>) -> AlertButton {
+            self.alerts.borrow_mut().push(alert.message.to_owned());
+            AlertButton::Yes
+        }
+    }
+
+    #[test]
+    fn test_js_handler() {
+        let mut doc = PdfDocument::open("tests/files/dummy.pdf").unwrap();
+        if !doc.is_js_supported().unwrap() {
+            return;
+        }
+        // A text field computing its value from a script
+        let field = doc
+            .new_object_from_str(
+                "<< /FT /Tx /T (total) /V (1) /AA << /C << /S /JavaScript /JS \
+                 (console.println\\('calculating'\\); \
+                 var b = app.alert\\('sure?', 1, 2\\); event.value = String\\(b\\);) >> >> >>",
+            )
+            .unwrap();
+        let field = doc.add_object(&field).unwrap();
+        let mut fields = doc.new_array().unwrap();
+        fields.array_push(field.clone()).unwrap();
+        let mut order = doc.new_array().unwrap();
+        order.array_push(field).unwrap();
+        let mut form = doc.new_dict().unwrap();
+        form.dict_put("Fields", fields).unwrap();
+        form.dict_put("CO", order).unwrap();
+        doc.catalog().unwrap().dict_put("AcroForm", form).unwrap();
+
+        let recorder = Recorder::default();
+        let (console, alerts) = (recorder.console.clone(), recorder.alerts.clone());
+        doc.set_js_handler(recorder).unwrap();
+        doc.calculate_form().unwrap();
+        assert_eq!(console.borrow().trim_end(), "calculating");
+        assert_eq!(*alerts.borrow(), ["sure?"]);
+
+        assert!(doc.set_field_value_with_events("total", "2").unwrap());
+        assert!(!doc.set_field_value_with_events("missing", "2").unwrap());
+    }
+}
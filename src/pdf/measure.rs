@@ -0,0 +1,227 @@
+use crate::pdf::{PdfAnnotation, PdfAnnotationType, PdfObject};
+use crate::{Error, Point};
+
+/// A scale for measuring distances and areas on a page, stored with line, polyline and polygon
+/// annotations so that viewers show their length or area in real world units.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MeasureScale {
+    /// The scale as shown to the user, e.g. `1 in = 10 ft`.
+    pub ratio: String,
+    /// The unit measurements are in, e.g. `ft`.
+    pub unit: String,
+    /// How many units one point on the page stands for.
+    pub units_per_point: f32,
+    /// Number of decimal places measurements are shown with.
+    pub precision: u32,
+}
+
+impl MeasureScale {
+    pub fn new(ratio: &str, unit: &str, units_per_point: f32) -> Self {
+        Self {
+            ratio: ratio.to_owned(),
+            unit: unit.to_owned(),
+            units_per_point,
+            precision: 2,
+        }
+    }
+
+    pub fn set_precision(&mut self, precision: u32) -> &mut Self {
+        self.precision = precision;
+        self
+    }
+}
+
+fn length(points: &[Point], closed: bool) -> f32 {
+    let segment = |a: &Point, b: &Point| ((b.x - a.x).powi(2) + (b.y - a.y).powi(2)).sqrt();
+    let open: f32 = points.windows(2).map(|w| segment(&w[0], &w[1])).sum();
+    match (closed, points.first(), points.last()) {
+        (true, Some(first), Some(last)) if points.len() > 2 => open + segment(last, first),
+        _ => open,
+    }
+}
+
+/// Area of a simple polygon, by the shoelace formula.
+fn area(points: &[Point]) -> f32 {
+    let n = points.len();
+    let twice: f32 = (0..n)
+        .map(|i| {
+            let (a, b) = (points[i], points[(i + 1) % n]);
+            a.x * b.y - b.x * a.y
+        })
+        .sum();
+    twice.abs() / 2.0
+}
+
+fn number_format(
+    obj: &PdfObject,
+    unit: &str,
+    factor: f32,
+    precision: u32,
+) -> Result<PdfObject, Error> {
+    let doc = obj.document().ok_or(Error::InvalidPdfDocument)?;
+    let mut format = doc.new_dict()?;
+    format.dict_put("Type", PdfObject::new_name("NumberFormat")?)?;
+    format.dict_put("U", PdfObject::new_string(unit)?)?;
+    format.dict_put("C", PdfObject::new_real(factor)?)?;
+    format.dict_put("F", PdfObject::new_name("D")?)?;
+    format.dict_put("D", PdfObject::new_int(10i32.saturating_pow(precision))?)?;
+    let mut array = doc.new_array()?;
+    array.array_push(format)?;
+    Ok(array)
+}
+
+/// The first number format of an array of them, as `(unit, factor, denominator)`.
+fn read_number_format(measure: &PdfObject, key: &str) -> Result<Option<(String, f32, i32)>, Error> {
+    let Some(format) = measure
+        .get_dict(key)?
+        .map(|a| a.get_array(0))
+        .transpose()?
+        .flatten()
+    else {
+        return Ok(None);
+    };
+    let unit = match format.get_dict("U")? {
+        Some(u) if u.is_string()? => u.as_string()?.to_owned(),
+        _ => String::new(),
+    };
+    let factor = match format.get_dict("C")? {
+        Some(c) => c.as_float()?,
+        None => 1.0,
+    };
+    let denominator = match format.get_dict("D")? {
+        Some(d) => d.as_int()?,
+        None => 100,
+    };
+    Ok(Some((unit, factor, denominator)))
+}
+
+impl PdfAnnotation {
+    /// The scale measurements of the annotation are shown in, if any.
+    pub fn measure(&self) -> Result<Option<MeasureScale>, Error> {
+        let Some(measure) = self.object().get_dict("Measure")? else {
+            return Ok(None);
+        };
+        let ratio = match measure.get_dict("R")? {
+            Some(r) if r.is_string()? => r.as_string()?.to_owned(),
+            _ => String::new(),
+        };
+        let Some((x_unit, x_factor, x_denominator)) = read_number_format(&measure, "X")? else {
+            return Ok(None);
+        };
+        // Distances may be converted once more from the units of the X axis
+        let (unit, factor, denominator) = match read_number_format(&measure, "D")? {
+            Some((unit, factor, denominator)) => (unit, x_factor * factor, denominator),
+            None => (x_unit, x_factor, x_denominator),
+        };
+        Ok(Some(MeasureScale {
+            ratio,
+            unit,
+            units_per_point: factor,
+            precision: (denominator.max(1) as f32).log10().round() as u32,
+        }))
+    }
+
+    /// Attach a scale to a line, polyline or polygon annotation, marking it as a dimension so
+    /// viewers show its length or area.
+    pub fn set_measure(&mut self, scale: &MeasureScale) -> Result<(), Error> {
+        let intent = match self.r#type()? {
+            PdfAnnotationType::Line => "LineDimension",
+            PdfAnnotationType::PloyLine => "PolyLineDimension",
+            PdfAnnotationType::Polygon => "PolygonDimension",
+            subtype => {
+                return Err(Error::InvalidAnnotation(format!(
+                    "{:?} annotations can't measure",
+                    subtype
+                )))
+            }
+        };
+        let mut obj = self.object();
+        let doc = obj.document().ok_or(Error::InvalidPdfDocument)?;
+        let mut measure = doc.new_dict()?;
+        measure.dict_put("Type", PdfObject::new_name("Measure")?)?;
+        measure.dict_put("Subtype", PdfObject::new_name("RL")?)?;
+        measure.dict_put("R", PdfObject::new_string(&scale.ratio)?)?;
+        let x = number_format(&obj, &scale.unit, scale.units_per_point, scale.precision)?;
+        measure.dict_put("X", x)?;
+        measure.dict_put("D", number_format(&obj, &scale.unit, 1.0, scale.precision)?)?;
+        let area_unit = format!("sq {}", scale.unit);
+        measure.dict_put("A", number_format(&obj, &area_unit, 1.0, scale.precision)?)?;
+        obj.dict_put("Measure", measure)?;
+        obj.dict_put("IT", PdfObject::new_name(intent)?)
+    }
+
+    /// The length of a line or polyline annotation, or the perimeter of a polygon annotation,
+    /// in the units of its scale. `None` without a scale.
+    pub fn measured_length(&self) -> Result<Option<f32>, Error> {
+        let Some(scale) = self.measure()? else {
+            return Ok(None);
+        };
+        let length = match self.r#type()? {
+            PdfAnnotationType::Line => length(&self.line()?, false),
+            PdfAnnotationType::PloyLine => length(&self.vertices()?, false),
+            PdfAnnotationType::Polygon => length(&self.vertices()?, true),
+            _ => return Ok(None),
+        };
+        Ok(Some(length * scale.units_per_point))
+    }
+
+    /// The area of a polygon annotation in square units of its scale. `None` without a scale.
+    pub fn measured_area(&self) -> Result<Option<f32>, Error> {
+        if self.r#type()? != PdfAnnotationType::Polygon {
+            return Ok(None);
+        }
+        let Some(scale) = self.measure()? else {
+            return Ok(None);
+        };
+        Ok(Some(
+            area(&self.vertices()?) * scale.units_per_point.powi(2),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::MeasureScale;
+    use crate::pdf::{LineEndingStyle, PdfAnnotationType, PdfDocument, PdfPage};
+    use crate::Point;
+
+    #[test]
+    fn test_annotation_measure() {
+        let doc = PdfDocument::open("tests/files/dummy.pdf").unwrap();
+        let mut page = PdfPage::try_from(doc.load_page(0).unwrap()).unwrap();
+        // One inch on the page is ten feet
+        let scale = MeasureScale::new("1 in = 10 ft", "ft", 10.0 / 72.0);
+
+        let mut line = page.create_annotation(PdfAnnotationType::Line).unwrap();
+        line.set_line(Point::new(100.0, 100.0), Point::new(244.0, 100.0))
+            .unwrap();
+        line.set_line_ending_styles(LineEndingStyle::OpenArrow, LineEndingStyle::ClosedArrow)
+            .unwrap();
+        assert_eq!(line.measured_length().unwrap(), None);
+        line.set_measure(&scale).unwrap();
+        assert_eq!(line.measure().unwrap(), Some(scale.clone()));
+        assert!((line.measured_length().unwrap().unwrap() - 20.0).abs() < 1e-3);
+        assert_eq!(
+            line.line_ending_styles().unwrap(),
+            [LineEndingStyle::OpenArrow, LineEndingStyle::ClosedArrow]
+        );
+
+        let mut polygon = page.create_annotation(PdfAnnotationType::Polygon).unwrap();
+        let square = [
+            Point::new(100.0, 200.0),
+            Point::new(172.0, 200.0),
+            Point::new(172.0, 272.0),
+            Point::new(100.0, 272.0),
+        ];
+        polygon.set_vertices(&square).unwrap();
+        polygon.set_interior_color(&[0.0, 0.0, 1.0]).unwrap();
+        polygon.set_measure(&scale).unwrap();
+        assert_eq!(polygon.vertices().unwrap(), square);
+        assert_eq!(polygon.interior_color().unwrap(), vec![0.0, 0.0, 1.0]);
+        assert!((polygon.measured_area().unwrap().unwrap() - 100.0).abs() < 1e-2);
+        assert!((polygon.measured_length().unwrap().unwrap() - 40.0).abs() < 1e-3);
+
+        let mut ink = page.create_annotation(PdfAnnotationType::Ink).unwrap();
+        assert!(ink.set_measure(&scale).is_err());
+    }
+}
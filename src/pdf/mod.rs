@@ -1,13 +1,61 @@
 pub mod annotation;
+pub mod annotation_data;
+pub mod article;
+#[cfg(feature = "bilevel")]
+mod bilevel;
+pub mod certificate;
+pub mod clean;
+pub mod content;
+mod der;
 pub mod document;
+pub mod field;
 pub mod filter;
+pub mod font;
 pub mod graft_map;
+pub mod html;
+pub mod ink;
+pub mod javascript;
+#[cfg(feature = "js")]
+pub mod js;
+pub mod measure;
 pub mod object;
 pub mod page;
+pub mod pdfa;
+pub mod signature;
+pub mod validation;
+pub mod viewer;
+pub mod widget;
+pub mod xfdf;
 
-pub use annotation::{LineEndingStyle, PdfAnnotation, PdfAnnotationType};
+pub use annotation::{
+    AnnotationThread, AppearanceKind, DefaultAppearance, LineEndingStyle, MarkupKind,
+    PdfAnnotation, PdfAnnotationType, ReplyType, ReviewState, TextAlignment,
+};
+pub use annotation_data::{AnnotationData, PopupData};
+pub use article::{Article, ArticleBead};
+pub use certificate::{DistinguishedName, SignerInfo};
+pub use clean::{CleanOptions, ScrubOptions};
+pub use content::{ContentBuilder, GroupOptions, Paint, TilingPattern};
 pub use document::{Encryption, PdfDocument, PdfWriteOptions, Permission};
+pub use field::{FieldFlags, FieldKind, FormField, TabOrder};
 pub use filter::PdfFilterOptions;
+pub use font::FontRef;
 pub use graft_map::PdfGraftMap;
-pub use object::PdfObject;
+pub use html::PageOptions;
+pub use ink::{ink_list_from_samples, ink_width, InkSmoothing, StylusSample};
+pub use javascript::{DocumentScript, PageScriptEvent};
+#[cfg(feature = "js")]
+pub use js::{AlertButton, AlertButtons, AlertIcon, JsAlert, JsHandler};
+pub use measure::MeasureScale;
+pub use object::{PdfObject, PrintLimits, StreamFilter};
 pub use page::PdfPage;
+pub use pdfa::PdfALevel;
+pub use signature::{
+    MdpPermissions, PdfSigner, Signature, SignatureOptions, TimestampedSigner, ValidationData,
+};
+pub use validation::{ValidationProfile, Violation};
+pub use viewer::{Duplex, PageLayout, PageMode, ViewerPreferences};
+pub use widget::{ChoiceOption, PageEvent, WidgetEvent};
+pub use xfdf::{
+    annotations_from_fdf, annotations_from_xfdf, annotations_to_fdf, annotations_to_xfdf,
+};
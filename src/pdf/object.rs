@@ -32,6 +32,73 @@ impl IntoPdfDictKey for PdfObject {
     }
 }
 
+/// How much of an object [`PdfObject::to_string_pretty`] shows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PrintLimits {
+    expand_depth: u32,
+    max_items: Option<usize>,
+    stream_data: bool,
+}
+
+impl PrintLimits {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// How many levels of indirect references are replaced by the objects they refer to.
+    /// References back to an object being shown are never expanded.
+    pub fn expand_depth(&self) -> u32 {
+        self.expand_depth
+    }
+
+    pub fn set_expand_depth(&mut self, value: u32) -> &mut Self {
+        self.expand_depth = value;
+        self
+    }
+
+    /// How many entries of an array or dictionary are shown before the rest is elided.
+    pub fn max_items(&self) -> Option<usize> {
+        self.max_items
+    }
+
+    pub fn set_max_items(&mut self, value: Option<usize>) -> &mut Self {
+        self.max_items = value;
+        self
+    }
+
+    /// Show the decoded data of streams after their dictionaries.
+    pub fn stream_data(&self) -> bool {
+        self.stream_data
+    }
+
+    pub fn set_stream_data(&mut self, value: bool) -> &mut Self {
+        self.stream_data = value;
+        self
+    }
+}
+
+/// A filter to compress or encode the data of a stream with, see [`PdfObject::set_stream`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(i32)]
+pub enum StreamFilter {
+    Flate = 0,
+    AsciiHex = 1,
+    Ascii85 = 2,
+    RunLength = 3,
+}
+
+impl StreamFilter {
+    /// The name of the filter in the `/Filter` entry of streams.
+    pub fn name(self) -> &'static str {
+        match self {
+            StreamFilter::Flate => "FlateDecode",
+            StreamFilter::AsciiHex => "ASCIIHexDecode",
+            StreamFilter::Ascii85 => "ASCII85Decode",
+            StreamFilter::RunLength => "RunLengthDecode",
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct PdfObject {
     pub(crate) inner: *mut pdf_obj,
@@ -229,6 +296,55 @@ impl PdfObject {
         self.write_raw_stream_buffer(&buf)
     }
 
+    /// Replace the data of a stream with `data` encoded with `filters`, which are listed in the
+    /// order they are decoded in like in the `/Filter` entry. The decode parameters of the old
+    /// filters are removed, and without filters the data is stored as it is.
+    pub fn set_stream(&mut self, data: &[u8], filters: &[StreamFilter]) -> Result<(), Error> {
+        let data = Buffer::from_bytes(data)?;
+        if filters.is_empty() {
+            return self.write_stream_buffer(&data);
+        }
+        let codes: Vec<i32> = filters.iter().map(|&f| f as i32).collect();
+        let encoded = unsafe {
+            ffi_try!(mupdf_encode_stream(
+                context(),
+                data.inner,
+                codes.as_ptr(),
+                codes.len() as i32
+            ))
+        }?;
+        let encoded = unsafe { Buffer::from_raw(encoded) };
+        self.write_raw_stream_buffer(&encoded)?;
+        let filter = match filters {
+            [filter] => PdfObject::new_name(filter.name())?,
+            filters => {
+                let doc = self.document().ok_or(Error::InvalidPdfDocument)?;
+                let mut array = doc.new_array()?;
+                for filter in filters {
+                    array.array_push(PdfObject::new_name(filter.name())?)?;
+                }
+                array
+            }
+        };
+        self.dict_put("Filter", filter)?;
+        self.dict_delete("DecodeParms")
+    }
+
+    /// The names of the filters of a stream, in the order they are decoded in.
+    pub fn filters(&self) -> Result<Vec<String>, Error> {
+        let name = |filter: &PdfObject| -> Result<String, Error> {
+            Ok(String::from_utf8_lossy(filter.as_name()?).into_owned())
+        };
+        match self.get_dict("Filter")? {
+            None => Ok(Vec::new()),
+            Some(filter) if filter.is_array()? => (0..filter.len()? as i32)
+                .filter_map(|i| filter.get_array(i).transpose())
+                .map(|f| name(&f?))
+                .collect(),
+            Some(filter) => Ok(vec![name(&filter)?]),
+        }
+    }
+
     pub fn get_array(&self, index: i32) -> Result<Option<Self>, Error> {
         let inner = unsafe { ffi_try!(mupdf_pdf_array_get(context(), self.inner, index)) }?;
         if inner.is_null() {
@@ -280,6 +396,70 @@ impl PdfObject {
         Ok(Some(Self { inner }))
     }
 
+    /// Follow a path of dictionary keys and array indices separated by slashes, such as
+    /// `Root/Pages/Kids/3/Annots/0/AP/N` from the trailer, resolving indirect objects on the way.
+    /// Returns `None` if any step of the path is missing.
+    pub fn resolve_path(&self, path: &str) -> Result<Option<Self>, Error> {
+        let mut obj = None;
+        for step in path.split('/').filter(|step| !step.is_empty()) {
+            let current = obj.as_ref().unwrap_or(self);
+            let next = if current.is_array()? {
+                match step.parse() {
+                    Ok(index) => current.get_array(index)?,
+                    Err(_) => None,
+                }
+            } else {
+                current.get_dict(step)?
+            };
+            match next {
+                Some(next) => obj = Some(next),
+                None => return Ok(None),
+            }
+        }
+        Ok(Some(obj.unwrap_or_else(|| self.clone())))
+    }
+
+    /// The integer at `path`, see [`PdfObject::resolve_path`]. Returns `None` if the path is
+    /// missing or leads to something else.
+    pub fn path_int(&self, path: &str) -> Result<Option<i32>, Error> {
+        match self.resolve_path(path)? {
+            Some(obj) if obj.is_int()? => Ok(Some(obj.as_int()?)),
+            _ => Ok(None),
+        }
+    }
+
+    /// The number at `path`, integer or real.
+    pub fn path_float(&self, path: &str) -> Result<Option<f32>, Error> {
+        match self.resolve_path(path)? {
+            Some(obj) if obj.is_number()? => Ok(Some(obj.as_float()?)),
+            _ => Ok(None),
+        }
+    }
+
+    pub fn path_bool(&self, path: &str) -> Result<Option<bool>, Error> {
+        match self.resolve_path(path)? {
+            Some(obj) if obj.is_bool()? => Ok(Some(obj.as_bool()?)),
+            _ => Ok(None),
+        }
+    }
+
+    pub fn path_name(&self, path: &str) -> Result<Option<String>, Error> {
+        match self.resolve_path(path)? {
+            Some(obj) if obj.is_name()? => {
+                Ok(Some(String::from_utf8_lossy(obj.as_name()?).into_owned()))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// The text string at `path`, decoded from PDFDocEncoding or UTF-16.
+    pub fn path_string(&self, path: &str) -> Result<Option<String>, Error> {
+        match self.resolve_path(path)? {
+            Some(obj) if obj.is_string()? => Ok(Some(obj.as_string()?.to_owned())),
+            _ => Ok(None),
+        }
+    }
+
     #[allow(clippy::len_without_is_empty)]
     pub fn len(&self) -> Result<usize, Error> {
         unsafe { ffi_try!(mupdf_pdf_array_len(context(), self.inner)) }.map(|size| size as usize)
@@ -321,7 +501,7 @@ impl PdfObject {
         unsafe { ffi_try!(mupdf_pdf_dict_delete(context(), self.inner, key_obj.inner)) }
     }
 
-    fn print(&self, tight: bool, ascii: bool) -> Result<String, Error> {
+    pub(crate) fn print(&self, tight: bool, ascii: bool) -> Result<String, Error> {
         let ptr =
             unsafe { ffi_try!(mupdf_pdf_obj_to_string(context(), self.inner, tight, ascii)) }?;
         let c_str = unsafe { CStr::from_ptr(ptr) };
@@ -330,6 +510,25 @@ impl PdfObject {
         Ok(s)
     }
 
+    /// Render the object in PDF syntax with one dictionary entry per line, like
+    /// `mutool show` does. An indirect object is shown as the object it refers to.
+    pub fn to_string_pretty(&self, limits: &PrintLimits) -> Result<String, Error> {
+        let mut out = String::new();
+        let top_indirect = self.is_indirect()?;
+        let mut printer = Printer {
+            out: &mut out,
+            limits,
+            expanded: Vec::new(),
+            top_indirect,
+        };
+        if top_indirect {
+            printer.indirect(self, 0)?;
+        } else {
+            printer.object(self, 0)?;
+        }
+        Ok(out)
+    }
+
     pub fn document(&self) -> Option<PdfDocument> {
         unsafe {
             let ptr = mupdf_pdf_get_bound_document(context(), self.inner);
@@ -376,6 +575,120 @@ impl Clone for PdfObject {
     }
 }
 
+/// Renders objects for [`PdfObject::to_string_pretty`].
+struct Printer<'a> {
+    out: &'a mut String,
+    limits: &'a PrintLimits,
+    /// The indirect objects being shown, outermost first.
+    expanded: Vec<i32>,
+    /// Whether the object at the top is indirect, which doesn't count as an expansion.
+    top_indirect: bool,
+}
+
+impl Printer<'_> {
+    fn indent(&mut self, indent: usize) {
+        self.out.extend(std::iter::repeat(' ').take(indent));
+    }
+
+    /// Whether `obj` is an indirect reference that is replaced by its object.
+    fn expands(&self, obj: &PdfObject) -> Result<bool, Error> {
+        if !obj.is_indirect()? {
+            return Ok(false);
+        }
+        let depth = self.expanded.len() - self.top_indirect as usize;
+        Ok(depth < self.limits.expand_depth as usize
+            && !self.expanded.contains(&obj.as_indirect()?))
+    }
+
+    fn indirect(&mut self, obj: &PdfObject, indent: usize) -> Result<(), Error> {
+        self.expanded.push(obj.as_indirect()?);
+        match obj.resolve()? {
+            Some(resolved) => self.object(&resolved, indent)?,
+            None => self.out.push_str("null"),
+        }
+        if self.limits.stream_data && obj.is_stream()? {
+            let data = obj.read_stream()?;
+            self.out.push_str("\nstream\n");
+            self.out.push_str(&String::from_utf8_lossy(&data));
+            if !data.ends_with(b"\n") {
+                self.out.push('\n');
+            }
+            self.out.push_str("endstream");
+        }
+        self.expanded.pop();
+        Ok(())
+    }
+
+    fn object(&mut self, obj: &PdfObject, indent: usize) -> Result<(), Error> {
+        if self.expands(obj)? {
+            return self.indirect(obj, indent);
+        }
+        if obj.is_indirect()? {
+            self.out.push_str(&obj.print(true, false)?);
+        } else if obj.is_array()? {
+            let len = obj.len()?;
+            let shown = self.limits.max_items.map_or(len, |max| max.min(len));
+            let mut items = Vec::with_capacity(shown);
+            let mut inline = true;
+            for i in 0..shown as i32 {
+                let item = obj.get_array(i)?.ok_or(Error::UnexpectedNullPtr)?;
+                inline &= if item.is_indirect()? {
+                    !self.expands(&item)?
+                } else {
+                    !item.is_array()? && !item.is_dict()?
+                };
+                items.push(item);
+            }
+            if inline {
+                self.out.push('[');
+                for item in items {
+                    self.out.push(' ');
+                    self.object(&item, indent)?;
+                }
+                if shown < len {
+                    self.out.push_str(" ...");
+                }
+                self.out.push_str(" ]");
+            } else {
+                self.out.push_str("[\n");
+                for item in items {
+                    self.indent(indent + 2);
+                    self.object(&item, indent + 2)?;
+                    self.out.push('\n');
+                }
+                if shown < len {
+                    self.indent(indent + 2);
+                    self.out.push_str("...\n");
+                }
+                self.indent(indent);
+                self.out.push(']');
+            }
+        } else if obj.is_dict()? {
+            let len = obj.dict_len()?;
+            let shown = self.limits.max_items.map_or(len, |max| max.min(len));
+            self.out.push_str("<<\n");
+            for i in 0..shown as i32 {
+                let key = obj.get_dict_key(i)?.ok_or(Error::UnexpectedNullPtr)?;
+                let value = obj.get_dict_val(i)?.ok_or(Error::UnexpectedNullPtr)?;
+                self.indent(indent + 2);
+                self.out.push_str(&key.print(true, false)?);
+                self.out.push(' ');
+                self.object(&value, indent + 2)?;
+                self.out.push('\n');
+            }
+            if shown < len {
+                self.indent(indent + 2);
+                self.out.push_str("...\n");
+            }
+            self.indent(indent);
+            self.out.push_str(">>");
+        } else {
+            self.out.push_str(&obj.print(true, false)?);
+        }
+        Ok(())
+    }
+}
+
 impl fmt::Display for PdfObject {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let s = self.print(true, false).unwrap();
@@ -420,3 +733,111 @@ impl TryFrom<String> for PdfObject {
         PdfObject::new_string(&s)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::{PrintLimits, StreamFilter};
+    use crate::pdf::PdfDocument;
+
+    #[test]
+    fn test_set_stream() {
+        let mut doc = PdfDocument::new();
+        let mut stream = doc.add_object(&doc.new_dict().unwrap()).unwrap();
+        let data = b"0 0 m 100 100 l S ".repeat(20);
+        stream
+            .set_stream(&data, &[StreamFilter::Ascii85, StreamFilter::Flate])
+            .unwrap();
+        assert_eq!(stream.filters().unwrap(), ["ASCII85Decode", "FlateDecode"]);
+        assert!(stream.read_raw_stream().unwrap().len() < data.len());
+        assert_eq!(stream.read_stream().unwrap(), data);
+
+        stream.set_stream(b"q Q", &[]).unwrap();
+        assert!(stream.filters().unwrap().is_empty());
+        assert_eq!(stream.read_raw_stream().unwrap(), b"q Q");
+    }
+
+    #[test]
+    fn test_to_string_pretty() {
+        let mut doc = PdfDocument::new();
+        let font = doc
+            .new_object_from_str("<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica >>")
+            .unwrap();
+        let font = doc.add_object(&font).unwrap();
+        let mut fonts = doc.new_dict().unwrap();
+        fonts.dict_put("F1", font.clone()).unwrap();
+        let mut resources = doc
+            .new_object_from_str("<< /ProcSet [/PDF /Text] >>")
+            .unwrap();
+        resources.dict_put("Font", fonts).unwrap();
+        let mut resources = doc.add_object(&resources).unwrap();
+        resources.dict_put("Self", resources.clone()).unwrap();
+        let num = font.as_indirect().unwrap();
+
+        let mut limits = PrintLimits::new();
+        assert_eq!(
+            resources.to_string_pretty(&limits).unwrap(),
+            format!(
+                "<<\n  /ProcSet [ /PDF /Text ]\n  /Font <<\n    /F1 {} 0 R\n  >>\n  \
+                 /Self {} 0 R\n>>",
+                num,
+                resources.as_indirect().unwrap()
+            )
+        );
+        limits.set_expand_depth(1).set_max_items(Some(2));
+        let expanded = resources.to_string_pretty(&limits).unwrap();
+        assert!(expanded.contains("/F1 <<\n      /Type /Font\n      /Subtype /Type1\n      ...\n"));
+        assert!(expanded.ends_with("  ...\n>>"));
+
+        let mut stream = doc.add_object(&doc.new_dict().unwrap()).unwrap();
+        stream.write_stream_string("BT ET").unwrap();
+        let shown = doc
+            .dump_object(
+                stream.as_indirect().unwrap(),
+                PrintLimits::new().set_stream_data(true),
+            )
+            .unwrap();
+        assert!(shown.starts_with(&format!("{} 0 obj\n<<\n", stream.as_indirect().unwrap())));
+        assert!(shown.ends_with(">>\nstream\nBT ET\nendstream\nendobj"));
+        assert!(doc.dump_object(-1, &PrintLimits::new()).is_err());
+
+        // Expansions count from a direct object at the top too
+        let descriptor = doc.add_object(&doc.new_dict().unwrap()).unwrap();
+        let mut font = font;
+        font.dict_put("FontDescriptor", descriptor.clone()).unwrap();
+        let mut fonts = doc.new_dict().unwrap();
+        fonts.dict_put("F1", font).unwrap();
+        let shown = fonts
+            .to_string_pretty(PrintLimits::new().set_expand_depth(1))
+            .unwrap();
+        assert!(shown.contains("/Type /Font"));
+        assert!(shown.contains(&format!(
+            "/FontDescriptor {} 0 R",
+            descriptor.as_indirect().unwrap()
+        )));
+    }
+
+    #[test]
+    fn test_resolve_path() {
+        let doc = PdfDocument::open("tests/files/dummy.pdf").unwrap();
+        let trailer = doc.trailer().unwrap();
+        assert_eq!(
+            trailer.path_name("Root/Type").unwrap().as_deref(),
+            Some("Catalog")
+        );
+        assert_eq!(trailer.path_int("Root/Pages/Count").unwrap(), Some(1));
+        assert_eq!(
+            trailer
+                .path_float("/Root/Pages/Kids/0/MediaBox/2/")
+                .unwrap(),
+            Some(595.0)
+        );
+        let page = doc.resolve_path("Root/Pages/Kids/0").unwrap().unwrap();
+        assert_eq!(page.path_name("Type").unwrap().as_deref(), Some("Page"));
+        assert!(trailer.resolve_path("Root/Pages/Kids/1").unwrap().is_none());
+        assert!(trailer
+            .resolve_path("Root/Pages/Kids/first")
+            .unwrap()
+            .is_none());
+        assert_eq!(trailer.path_int("Root/Type").unwrap(), None);
+    }
+}
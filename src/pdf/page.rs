@@ -1,4 +1,6 @@
 use std::{
+    collections::HashMap,
+    ffi::CString,
     mem::ManuallyDrop,
     ops::{Deref, DerefMut},
     ptr::NonNull,
@@ -6,8 +8,17 @@ use std::{
 
 use mupdf_sys::*;
 
-use crate::pdf::{PdfAnnotation, PdfAnnotationType, PdfFilterOptions, PdfObject};
-use crate::{context, unsafe_impl_ffi_wrapper, Error, FFIWrapper, Matrix, Page, Rect};
+use crate::pdf::{
+    AnnotationThread, DefaultAppearance, FontRef, MarkupKind, PdfAnnotation, PdfAnnotationType,
+    PdfFilterOptions, PdfObject, ReviewState,
+};
+use crate::{
+    context, stats, unsafe_impl_ffi_wrapper, Error, FFIWrapper, Matrix, Page, Point, Quad, Rect,
+    TextPageOptions,
+};
+
+/// Most occurrences of a search term [`PdfPage::add_search_markup`] marks up.
+const MAX_SEARCH_HITS: u32 = 1000;
 
 #[derive(Debug)]
 pub struct PdfPage {
@@ -26,7 +37,7 @@ pub struct PdfPage {
     page: ManuallyDrop<Page>,
 }
 
-unsafe_impl_ffi_wrapper!(PdfPage, pdf_page, pdf_drop_page);
+unsafe_impl_ffi_wrapper!(PdfPage, pdf_page, pdf_drop_page, stats::PAGES);
 
 impl PdfPage {
     /// # Safety
@@ -67,7 +78,164 @@ impl PdfPage {
 
     pub fn annotations(&self) -> AnnotationIter {
         let next = unsafe { pdf_first_annot(context(), self.as_ptr() as *mut _) };
-        AnnotationIter { next }
+        AnnotationIter {
+            next,
+            widgets: false,
+        }
+    }
+
+    /// The form widgets of the page, which [`PdfPage::annotations`] leaves out.
+    pub fn widgets(&self) -> AnnotationIter {
+        let next = unsafe { pdf_first_widget(context(), self.as_ptr() as *mut _) };
+        AnnotationIter {
+            next,
+            widgets: true,
+        }
+    }
+
+    /// Mark up the text covered by `quads`, e.g. the results of [`Page::search`] or
+    /// [`TextPage::selection_quads`](crate::TextPage::selection_quads), with a single
+    /// annotation in the given color, its appearance generated.
+    pub fn add_text_markup(
+        &mut self,
+        quads: &[Quad],
+        kind: MarkupKind,
+        color: &[f32],
+    ) -> Result<PdfAnnotation, Error> {
+        if quads.is_empty() {
+            return Err(Error::InvalidAnnotation("no quads to mark up".to_owned()));
+        }
+        let mut annot = self.create_annotation(kind.into())?;
+        annot.set_quad_points(quads)?;
+        annot.set_color(color)?;
+        annot.update()?;
+        Ok(annot)
+    }
+
+    /// Mark up every occurrence of `needle` on the page, returning `None` if there is none.
+    pub fn add_search_markup(
+        &mut self,
+        needle: &str,
+        kind: MarkupKind,
+        color: &[f32],
+    ) -> Result<Option<PdfAnnotation>, Error> {
+        let quads = self.search(needle, MAX_SEARCH_HITS)?;
+        if quads.is_empty() {
+            return Ok(None);
+        }
+        self.add_text_markup(&quads, kind, color).map(Some)
+    }
+
+    /// Mark up the text selected by dragging from `a` to `b`, in page coordinates, returning
+    /// `None` if no text is selected.
+    pub fn add_selection_markup(
+        &mut self,
+        a: Point,
+        b: Point,
+        kind: MarkupKind,
+        color: &[f32],
+    ) -> Result<Option<PdfAnnotation>, Error> {
+        let text_page = self.to_text_page(TextPageOptions::empty())?;
+        let quads = text_page.selection_quads(a, b)?;
+        if quads.is_empty() {
+            return Ok(None);
+        }
+        self.add_text_markup(&quads, kind, color).map(Some)
+    }
+
+    /// Add a free text annotation showing `text` in `rect`, in page coordinates, with a border
+    /// of `border_width`, 0 for none.
+    pub fn create_free_text(
+        &mut self,
+        rect: Rect,
+        text: &str,
+        appearance: &DefaultAppearance,
+        border_width: f32,
+    ) -> Result<PdfAnnotation, Error> {
+        let mut annot = self.create_annotation(PdfAnnotationType::FreeText)?;
+        annot.set_rect(rect)?;
+        annot.set_contents(text)?;
+        annot.set_default_appearance(appearance)?;
+        annot.set_border_width(border_width)?;
+        Ok(annot)
+    }
+
+    /// The annotations of the page arranged in comment threads: annotations that don't reply to
+    /// another one, with their replies nested below them and the review states set on them.
+    /// Popups aren't included, they belong to the annotation they show.
+    pub fn annotation_threads(&self) -> Result<Vec<AnnotationThread>, Error> {
+        let mut annotations = Vec::new();
+        let mut numbers = HashMap::new();
+        for annot in self.annotations() {
+            if annot.r#type()? == PdfAnnotationType::Popup {
+                continue;
+            }
+            numbers.insert(annot.object_number()?, annotations.len());
+            annotations.push(annot);
+        }
+
+        let mut replies: Vec<Vec<usize>> = vec![Vec::new(); annotations.len()];
+        let mut states: Vec<Vec<(String, ReviewState)>> = vec![Vec::new(); annotations.len()];
+        let mut roots = Vec::new();
+        for (i, annot) in annotations.iter().enumerate() {
+            let parent = match annot.object().get_dict("IRT")? {
+                Some(irt) if irt.is_indirect()? => numbers.get(&irt.as_indirect()?).copied(),
+                _ => None,
+            };
+            match (parent, annot.state()?) {
+                (Some(parent), Some(state)) => {
+                    let author = annot.author()?.unwrap_or_default().to_owned();
+                    let states = &mut states[parent];
+                    states.retain(|(a, s)| *a != author || s.model() != state.model());
+                    states.push((author, state));
+                }
+                (Some(parent), None) if parent != i => replies[parent].push(i),
+                _ => roots.push(i),
+            }
+        }
+
+        fn build(
+            i: usize,
+            annotations: &mut [Option<PdfAnnotation>],
+            replies: &[Vec<usize>],
+            states: &mut [Vec<(String, ReviewState)>],
+        ) -> Option<AnnotationThread> {
+            // Taking the annotation out also breaks reply cycles
+            let annotation = annotations[i].take()?;
+            Some(AnnotationThread {
+                annotation,
+                replies: replies[i]
+                    .iter()
+                    .filter_map(|&r| build(r, annotations, replies, states))
+                    .collect(),
+                review_states: std::mem::take(&mut states[i]),
+            })
+        }
+        let mut annotations: Vec<_> = annotations.into_iter().map(Some).collect();
+        Ok(roots
+            .into_iter()
+            .filter_map(|i| build(i, &mut annotations, &replies, &mut states))
+            .collect())
+    }
+
+    /// Set the review state of `annotation` on behalf of `author` by adding a hidden state
+    /// annotation replying to it, as Acrobat does.
+    pub fn set_review_state(
+        &mut self,
+        annotation: &PdfAnnotation,
+        state: ReviewState,
+        author: &str,
+    ) -> Result<PdfAnnotation, Error> {
+        let mut state_annot = self.create_annotation(PdfAnnotationType::Text)?;
+        state_annot.set_author(author)?;
+        let mut obj = state_annot.object();
+        obj.dict_put("IRT", annotation.object())?;
+        obj.dict_put("State", PdfObject::new_string(state.name())?)?;
+        obj.dict_put("StateModel", PdfObject::new_string(state.model())?)?;
+        // Hidden, no zoom and no rotate
+        obj.dict_put("F", PdfObject::new_int(2 | 8 | 16)?)?;
+        obj.dict_put("Contents", PdfObject::new_string(state.name())?)?;
+        Ok(state_annot)
     }
 
     pub fn update(&mut self) -> Result<bool, Error> {
@@ -107,6 +275,26 @@ impl PdfPage {
         Ok(rect.into())
     }
 
+    /// The size of a unit of user space in 1/72 inch, set by large-format documents such as
+    /// engineering drawings to go beyond the 200 inch limit of page sizes. 1 by default.
+    pub fn user_unit(&self) -> Result<f32, Error> {
+        match self.object().get_dict("UserUnit")? {
+            Some(unit) if unit.is_number()? && unit.as_float()? > 0.0 => unit.as_float(),
+            _ => Ok(1.0),
+        }
+    }
+
+    /// Set the size of a unit of user space, e.g. 10 to draw a 500 inch wide poster on a
+    /// 3600 unit wide MediaBox. Units that aren't positive reset it to 1.
+    pub fn set_user_unit(&mut self, unit: f32) -> Result<(), Error> {
+        let mut obj = self.object();
+        if unit > 0.0 && unit.is_finite() && unit != 1.0 {
+            obj.dict_put("UserUnit", PdfObject::new_real(unit)?)
+        } else {
+            obj.dict_delete("UserUnit")
+        }
+    }
+
     pub fn crop_box(&self) -> Result<Rect, Error> {
         let bounds = self.bounds()?;
         let pos = unsafe { mupdf_pdf_page_crop_box_position(context(), self.as_ptr() as *mut _) };
@@ -142,6 +330,19 @@ impl PdfPage {
             ))
         }
     }
+
+    /// Make `font` available to the page contents as `/<name>`.
+    pub fn add_font_resource(&mut self, name: &str, font: &FontRef) -> Result<(), Error> {
+        let c_name = CString::new(name)?;
+        unsafe {
+            ffi_try!(mupdf_pdf_page_add_font_resource(
+                context(),
+                self.as_mut_ptr(),
+                c_name.as_ptr(),
+                font.object().inner
+            ))
+        }
+    }
 }
 
 impl Deref for PdfPage {
@@ -161,6 +362,7 @@ impl DerefMut for PdfPage {
 #[derive(Debug)]
 pub struct AnnotationIter {
     next: *mut pdf_annot,
+    widgets: bool,
 }
 
 impl Iterator for AnnotationIter {
@@ -172,8 +374,13 @@ impl Iterator for AnnotationIter {
         }
         let node = self.next;
         unsafe {
-            self.next = pdf_next_annot(context(), node);
-            Some(PdfAnnotation::from_raw(node))
+            self.next = if self.widgets {
+                pdf_next_widget(context(), node)
+            } else {
+                pdf_next_annot(context(), node)
+            };
+            // The page owns its annotations, `PdfAnnotation` drops a reference of its own
+            Some(PdfAnnotation::from_raw(pdf_keep_annot(context(), node)))
         }
     }
 }
@@ -187,6 +394,9 @@ impl TryFrom<Page> for PdfPage {
         // ...god please give me linear types so that I can check this sort of transformation at
         // compile-time
         std::mem::forget(value);
+        // ...and since `value` is never dropped, release its handle count here, as `from_raw`
+        // acquires one for the new wrapper.
+        stats::PAGES.release();
         NonNull::new(pdf_page)
             .ok_or(Error::UnexpectedNullPtr)
             .map(|inner| unsafe { PdfPage::from_raw(inner) })
@@ -195,8 +405,11 @@ impl TryFrom<Page> for PdfPage {
 
 #[cfg(test)]
 mod test {
-    use crate::pdf::{PdfAnnotation, PdfDocument, PdfPage};
-    use crate::{Matrix, Rect};
+    use crate::pdf::{
+        AppearanceKind, DefaultAppearance, MarkupKind, PdfAnnotation, PdfAnnotationType,
+        PdfDocument, PdfPage, ReplyType, ReviewState, TextAlignment,
+    };
+    use crate::{Colorspace, DisplayList, Image, Matrix, Path, Pixmap, Point, Rect};
 
     #[test]
     fn test_page_properties() {
@@ -230,6 +443,28 @@ mod test {
         assert_eq!(crop_box, Rect::new(100.0, 100.0, 400.0, 400.0));
     }
 
+    #[test]
+    fn test_page_user_unit() {
+        use crate::Size;
+
+        let mut doc = PdfDocument::new();
+        let mut page = doc.new_page((100.0, 200.0)).unwrap();
+        assert_eq!(page.user_unit().unwrap(), 1.0);
+        page.set_user_unit(10.0).unwrap();
+        assert_eq!(page.user_unit().unwrap(), 10.0);
+
+        let page = doc.load_page(0).unwrap();
+        assert_eq!(page.user_unit().unwrap(), 10.0);
+        assert_eq!(page.physical_size().unwrap(), Size::new(1000.0, 2000.0));
+        let ctm = page.pdf_to_page().unwrap();
+        assert_eq!((ctm.a, ctm.d), (10.0, -10.0));
+
+        let mut page = PdfPage::try_from(page).unwrap();
+        page.set_user_unit(-1.0).unwrap();
+        assert_eq!(page.user_unit().unwrap(), 1.0);
+        assert!(page.object().get_dict("UserUnit").unwrap().is_none());
+    }
+
     #[test]
     fn test_page_annotations() {
         let doc = PdfDocument::open("tests/files/dummy.pdf").unwrap();
@@ -237,4 +472,208 @@ mod test {
         let annots: Vec<PdfAnnotation> = page0.annotations().collect();
         assert_eq!(annots.len(), 0);
     }
+
+    #[test]
+    fn test_page_custom_stamps() {
+        let doc = PdfDocument::open("tests/files/dummy.pdf").unwrap();
+        let mut page0 = PdfPage::try_from(doc.load_page(0).unwrap()).unwrap();
+
+        let mut path = Path::new().unwrap();
+        path.move_to(0.0, 0.0).unwrap();
+        path.line_to(100.0, 0.0).unwrap();
+        path.line_to(50.0, 50.0).unwrap();
+        path.close().unwrap();
+        let target = Rect::new(100.0, 100.0, 300.0, 300.0);
+        let mut stamp = page0.create_annotation(PdfAnnotationType::Stamp).unwrap();
+        stamp
+            .set_stamp_path(&path, [1.0, 0.0, 0.0], target)
+            .unwrap();
+        let bounds = stamp.bounds().unwrap();
+        assert!((bounds.width() - 200.0).abs() < 2.0);
+        assert!(bounds.y0 >= target.y0 - 1.0 && bounds.y1 <= target.y1 + 1.0);
+
+        let mut pixmap = Pixmap::new_with_w_h(&Colorspace::device_rgb(), 20, 10, false).unwrap();
+        pixmap.clear_with(128).unwrap();
+        let mut stamp = page0.create_annotation(PdfAnnotationType::Stamp).unwrap();
+        stamp
+            .set_stamp_image(&Image::from_pixmap(&pixmap).unwrap())
+            .unwrap();
+        page0.update().unwrap();
+    }
+
+    #[test]
+    fn test_page_annotation_appearances() {
+        let doc = PdfDocument::open("tests/files/dummy.pdf").unwrap();
+        let mut page0 = PdfPage::try_from(doc.load_page(0).unwrap()).unwrap();
+        let mut annot = page0.create_annotation(PdfAnnotationType::Square).unwrap();
+        let resources = doc.new_dict().unwrap();
+        let bbox = Rect::new(0.0, 0.0, 50.0, 50.0);
+        for (state, color) in [("On", "0 1 0"), ("Off", "1 0 0")] {
+            annot
+                .set_appearance_stream(
+                    AppearanceKind::Normal,
+                    Some(state),
+                    &Matrix::new_translate(100.0, 100.0),
+                    bbox,
+                    &resources,
+                    format!("{} rg 0 0 50 50 re f", color).as_bytes(),
+                )
+                .unwrap();
+        }
+        let mut states = annot.appearance_states(AppearanceKind::Normal).unwrap();
+        states.sort();
+        assert_eq!(states, vec!["Off".to_owned(), "On".to_owned()]);
+
+        let list = DisplayList::new(bbox).unwrap();
+        annot
+            .set_appearance(AppearanceKind::Down, None, &list, &Matrix::IDENTITY)
+            .unwrap();
+        assert!(annot
+            .appearance_states(AppearanceKind::Down)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_page_render_layers() {
+        let doc = PdfDocument::open("tests/files/dummy.pdf").unwrap();
+        let mut page0 = PdfPage::try_from(doc.load_page(0).unwrap()).unwrap();
+        let mut annot = page0.create_annotation(PdfAnnotationType::Square).unwrap();
+        annot
+            .set_rect(Rect::new(300.0, 400.0, 400.0, 450.0))
+            .unwrap();
+        annot.set_interior_color(&[1.0, 0.0, 0.0]).unwrap();
+        page0.update().unwrap();
+
+        let rgb = Colorspace::device_rgb();
+        let contents = page0
+            .render_contents_only(&Matrix::IDENTITY, &rgb, false)
+            .unwrap();
+        let annots = page0
+            .render_annotations_only(&Matrix::IDENTITY, &rgb)
+            .unwrap();
+        assert_eq!(annots.rect(), contents.rect());
+        let alpha_at = |x: usize, y: usize| annots.samples()[(y * 595 + x) * 4 + 3];
+        assert_eq!(alpha_at(10, 10), 0);
+        assert_eq!(alpha_at(350, 425), 255);
+        // The square isn't part of the contents
+        let i = (425 * 595 + 350) * 3;
+        assert_eq!(&contents.samples()[i..i + 3], &[255, 255, 255]);
+
+        let single = annot.to_pixmap(&Matrix::IDENTITY, &rgb, true).unwrap();
+        let bounds = annot.bounds().unwrap();
+        assert_eq!(single.x(), bounds.x0.floor() as i32);
+        assert!(single.width() >= 100 && single.width() <= 102);
+    }
+
+    #[test]
+    fn test_page_text_markup() {
+        let doc = PdfDocument::open("tests/files/dummy.pdf").unwrap();
+        let mut page0 = PdfPage::try_from(doc.load_page(0).unwrap()).unwrap();
+        let highlight = page0
+            .add_search_markup("Dummy", MarkupKind::Highlight, &[1.0, 1.0, 0.0])
+            .unwrap()
+            .unwrap();
+        assert_eq!(highlight.r#type().unwrap(), PdfAnnotationType::Highlight);
+        assert_eq!(highlight.quad_points().unwrap().len(), 1);
+        assert_eq!(highlight.color().unwrap(), vec![1.0, 1.0, 0.0]);
+        assert!(page0
+            .add_search_markup("missing", MarkupKind::Underline, &[0.0])
+            .unwrap()
+            .is_none());
+
+        let words = page0.search("PDF file", 1).unwrap();
+        let bounds = Rect::from(words[0].clone());
+        let strike = page0
+            .add_selection_markup(
+                Point::new(bounds.x0 + 1.0, (bounds.y0 + bounds.y1) / 2.0),
+                Point::new(bounds.x1 - 1.0, (bounds.y0 + bounds.y1) / 2.0),
+                MarkupKind::StrikeOut,
+                &[1.0, 0.0, 0.0],
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(strike.r#type().unwrap(), PdfAnnotationType::StrikeOut);
+        assert_eq!(page0.annotations().count(), 2);
+    }
+
+    #[test]
+    fn test_page_free_text() {
+        let doc = PdfDocument::open("tests/files/dummy.pdf").unwrap();
+        let mut page0 = PdfPage::try_from(doc.load_page(0).unwrap()).unwrap();
+        let appearance = DefaultAppearance {
+            font: "Cour".to_owned(),
+            size: 14.0,
+            color: vec![1.0, 0.0, 0.0],
+        };
+        let mut annot = page0
+            .create_free_text(
+                Rect::new(200.0, 200.0, 400.0, 250.0),
+                "Note",
+                &appearance,
+                1.0,
+            )
+            .unwrap();
+        annot.set_alignment(TextAlignment::Center).unwrap();
+        let line = vec![Point::new(100.0, 100.0), Point::new(200.0, 225.0)];
+        annot.set_callout_line(&line).unwrap();
+        annot
+            .set_rich_contents("Note", "<body><p><b>Note</b></p></body>")
+            .unwrap();
+        page0.update().unwrap();
+
+        assert_eq!(annot.default_appearance().unwrap(), appearance);
+        assert_eq!(annot.alignment().unwrap(), TextAlignment::Center);
+        assert_eq!(annot.callout_line().unwrap(), line);
+        assert_eq!(annot.contents().unwrap().as_deref(), Some("Note"));
+        assert!(annot
+            .rich_contents()
+            .unwrap()
+            .unwrap()
+            .contains("<b>Note</b>"));
+        annot.set_callout_line(&[]).unwrap();
+        assert!(annot.callout_line().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_page_annotation_threads() {
+        let doc = PdfDocument::open("tests/files/dummy.pdf").unwrap();
+        let mut page0 = PdfPage::try_from(doc.load_page(0).unwrap()).unwrap();
+        let mut comment = page0.create_annotation(PdfAnnotationType::Text).unwrap();
+        comment.set_contents("Typo here").unwrap();
+        comment
+            .set_popup(Rect::new(200.0, 100.0, 400.0, 200.0))
+            .unwrap();
+        let mut reply = page0.create_annotation(PdfAnnotationType::Text).unwrap();
+        reply.set_contents("Fixed").unwrap();
+        reply.set_in_reply_to(&comment, ReplyType::Reply).unwrap();
+        page0
+            .set_review_state(&comment, ReviewState::Rejected, "Alice")
+            .unwrap();
+        page0
+            .set_review_state(&comment, ReviewState::Accepted, "Alice")
+            .unwrap();
+
+        assert!(reply.is_reply_to(&comment).unwrap());
+        assert_eq!(reply.reply_type().unwrap(), Some(ReplyType::Reply));
+        assert_eq!(comment.reply_type().unwrap(), None);
+        assert!(comment.popup().unwrap().is_some());
+
+        let threads = page0.annotation_threads().unwrap();
+        assert_eq!(threads.len(), 1);
+        let thread = &threads[0];
+        assert_eq!(
+            thread.annotation.contents().unwrap().as_deref(),
+            Some("Typo here")
+        );
+        assert_eq!(thread.replies.len(), 1);
+        assert_eq!(
+            thread.replies[0].annotation.contents().unwrap().as_deref(),
+            Some("Fixed")
+        );
+        assert_eq!(
+            thread.review_states,
+            vec![("Alice".to_owned(), ReviewState::Accepted)]
+        );
+    }
 }
@@ -0,0 +1,707 @@
+use std::collections::HashMap;
+use std::ops::Range;
+
+use crate::pdf::validation::{
+    dict_objects, font_name, graphics_states, is_font, is_font_embedded, is_forbidden_action,
+    is_printed_annotation, name_of, uses_lzw, ANNOT_HIDING, ANNOT_PRINT,
+};
+use crate::pdf::{
+    Encryption, PdfDocument, PdfObject, PdfWriteOptions, ValidationProfile, Violation,
+};
+use crate::xml::XmlDocument;
+use crate::{Buffer, Colorspace, Error, Font, SimpleFontEncoding};
+
+/// A level of conformance to PDF/A, the ISO 19005 standard for archiving documents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PdfALevel {
+    /// PDF/A-1b, based on PDF 1.4 and without transparency.
+    A1b,
+    /// PDF/A-2b, based on PDF 1.7, allowing transparency and embedded PDF/A files.
+    A2b,
+    /// PDF/A-3b, which also allows embedding files of any kind.
+    A3b,
+}
+
+impl PdfALevel {
+    /// The part of ISO 19005 the level is defined in.
+    pub fn part(self) -> u8 {
+        match self {
+            PdfALevel::A1b => 1,
+            PdfALevel::A2b => 2,
+            PdfALevel::A3b => 3,
+        }
+    }
+
+    /// The conformance level within its part.
+    pub fn conformance(self) -> char {
+        'B'
+    }
+}
+
+const BASE14_FONTS: [&str; 12] = [
+    "Courier",
+    "Courier-Bold",
+    "Courier-BoldOblique",
+    "Courier-Oblique",
+    "Helvetica",
+    "Helvetica-Bold",
+    "Helvetica-BoldOblique",
+    "Helvetica-Oblique",
+    "Times-Bold",
+    "Times-BoldItalic",
+    "Times-Italic",
+    "Times-Roman",
+];
+
+const OUTPUT_CONDITION: &str = "sRGB IEC61966-2.1";
+
+/// Make a graphics state, annotation or transparency group opaque, as PDF/A-1 requires.
+fn remove_transparency(obj: &mut PdfObject) -> Result<(), Error> {
+    if name_of(obj, "Subtype")?.as_deref() == Some(b"Image") {
+        return Ok(());
+    }
+    if let Some(group) = obj.get_dict("Group")? {
+        if name_of(&group, "S")?.as_deref() == Some(b"Transparency") {
+            obj.dict_delete("Group")?;
+        }
+    }
+    for key in ["CA", "ca"] {
+        // The MK dictionary of widgets has a caption string named CA
+        if let Some(alpha) = obj.get_dict(key)? {
+            if alpha.is_number()? && alpha.as_float()? != 1.0 {
+                obj.dict_put(key, PdfObject::new_real(1.0)?)?;
+            }
+        }
+    }
+    if let Some(blend_mode) = name_of(obj, "BM")? {
+        if blend_mode != b"Normal" && blend_mode != b"Compatible" {
+            obj.dict_put("BM", PdfObject::new_name("Normal")?)?;
+        }
+    }
+    if obj.get_dict("SMask")?.is_some() && name_of(obj, "SMask")?.as_deref() != Some(b"None") {
+        obj.dict_put("SMask", PdfObject::new_name("None")?)?;
+    }
+    Ok(())
+}
+
+/// Convert a date like `D:20240131120000+01'00'` to one like `2024-01-31T12:00:00+01:00`.
+fn xmp_date(date: &str) -> Option<String> {
+    let date = date.strip_prefix("D:").unwrap_or(date);
+    let digits = date.bytes().take_while(u8::is_ascii_digit).count();
+    if digits < 4 || digits % 2 != 0 {
+        return None;
+    }
+    let (digits, zone) = date.split_at(digits);
+    let mut xmp = digits[..4].to_owned();
+    for (i, separator) in ["-", "-", "T", ":", ":"].into_iter().enumerate() {
+        match digits.get(4 + 2 * i..6 + 2 * i) {
+            Some(part) => {
+                xmp.push_str(separator);
+                xmp.push_str(part);
+            }
+            None => break,
+        }
+    }
+    // Times need minutes and a time zone in XMP
+    if digits.len() > 8 {
+        if digits.len() == 10 {
+            xmp.push_str(":00");
+        }
+        let zone = zone.replace('\'', "");
+        match zone.as_bytes().first() {
+            Some(b'+' | b'-') if zone.len() == 5 => {
+                xmp.push_str(&zone[..3]);
+                xmp.push(':');
+                xmp.push_str(&zone[3..]);
+            }
+            Some(b'+' | b'-') if zone.len() == 3 => {
+                xmp.push_str(&zone);
+                xmp.push_str(":00");
+            }
+            _ => xmp.push('Z'),
+        }
+    }
+    Some(xmp)
+}
+
+const RDF_NS: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#";
+const PDFAID_NS: &str = "http://www.aiim.org/pdfa/ns/id/";
+const DC_NS: &str = "http://purl.org/dc/elements/1.1/";
+const PDF_NS: &str = "http://ns.adobe.com/pdf/1.3/";
+const XMP_NS: &str = "http://ns.adobe.com/xap/1.0/";
+
+/// A start or end tag of well formed XML.
+struct Tag<'a> {
+    /// Where the tag starts and ends in the XML.
+    span: Range<usize>,
+    /// The qualified name, with its namespace prefix.
+    name: &'a str,
+    closing: bool,
+    empty: bool,
+    /// The names and values of the attributes, with their spans including the whitespace
+    /// before them.
+    attributes: Vec<(&'a str, &'a str, Range<usize>)>,
+}
+
+/// The tags of well formed XML, skipping comments, processing instructions and CDATA.
+fn xml_tags(xml: &str) -> Option<Vec<Tag<'_>>> {
+    let is_space = |c: char| c.is_ascii_whitespace();
+    let mut tags = Vec::new();
+    let mut pos = 0;
+    while let Some(offset) = xml[pos..].find('<') {
+        let start = pos + offset;
+        let rest = &xml[start..];
+        let skip_to = |end: &str| rest.find(end).map(|i| start + i + end.len());
+        if rest.starts_with("<!--") {
+            pos = skip_to("-->")?;
+            continue;
+        }
+        if rest.starts_with("<?") {
+            pos = skip_to("?>")?;
+            continue;
+        }
+        if rest.starts_with("<![CDATA[") {
+            pos = skip_to("]]>")?;
+            continue;
+        }
+        if rest.starts_with("<!") {
+            pos = skip_to(">")?;
+            continue;
+        }
+        let closing = rest.starts_with("</");
+        let name_start = start + if closing { 2 } else { 1 };
+        let name_len = xml[name_start..].find(|c: char| is_space(c) || c == '/' || c == '>')?;
+        let name = &xml[name_start..name_start + name_len];
+        pos = name_start + name_len;
+        let mut attributes = Vec::new();
+        let empty = loop {
+            let attribute_start = pos;
+            pos += xml[pos..].find(|c: char| !is_space(c))?;
+            if xml[pos..].starts_with('>') {
+                break false;
+            }
+            if xml[pos..].starts_with("/>") {
+                pos += 1;
+                break true;
+            }
+            let eq = pos + xml[pos..].find('=')?;
+            let attribute = xml[pos..eq].trim_end();
+            pos = eq + 1 + xml[eq + 1..].find(|c: char| !is_space(c))?;
+            let quote = xml[pos..].chars().next()?;
+            let value_start = pos + 1;
+            let value_end = value_start + xml[value_start..].find(quote)?;
+            pos = value_end + 1;
+            attributes.push((
+                attribute,
+                &xml[value_start..value_end],
+                attribute_start..pos,
+            ));
+        };
+        pos += 1;
+        tags.push(Tag {
+            span: start..pos,
+            name,
+            closing,
+            empty,
+            attributes,
+        });
+    }
+    Some(tags)
+}
+
+fn local_name(name: &str) -> &str {
+    name.rsplit(':').next().unwrap_or(name)
+}
+
+/// Add the `rdf:Description` elements `descriptions` to XMP metadata, removing the
+/// properties `replaced`, given by namespace and local name, which they set anew. `None` if
+/// the metadata has no `rdf:RDF` element.
+fn merge_xmp(xmp: &str, descriptions: &str, replaced: &[(&str, &str)]) -> Option<String> {
+    let tags = xml_tags(xmp)?;
+    let mut namespaces = HashMap::new();
+    for tag in &tags {
+        for &(name, value, _) in &tag.attributes {
+            if let Some(prefix) = name.strip_prefix("xmlns:") {
+                namespaces.insert(prefix, value);
+            }
+        }
+    }
+    let is_replaced = |name: &str| {
+        let Some((prefix, local)) = name.split_once(':') else {
+            return false;
+        };
+        namespaces
+            .get(prefix)
+            .is_some_and(|&ns| replaced.contains(&(ns, local)))
+    };
+
+    let mut removed: Vec<Range<usize>> = Vec::new();
+    // Open elements, with whether they are removed
+    let mut open: Vec<(usize, bool)> = Vec::new();
+    let mut insert_at = None;
+    for tag in &tags {
+        let in_removed = open.iter().any(|&(_, remove)| remove);
+        if tag.closing {
+            let (start, remove) = open.pop()?;
+            if remove && !open.iter().any(|&(_, remove)| remove) {
+                removed.push(start..tag.span.end);
+            }
+            if local_name(tag.name) == "RDF" {
+                insert_at = Some(tag.span.start);
+            }
+            continue;
+        }
+        let remove = !in_removed && is_replaced(tag.name);
+        if !in_removed && !remove && local_name(tag.name) == "Description" {
+            // Properties can be attributes of descriptions too
+            for (name, _, span) in &tag.attributes {
+                if is_replaced(name) {
+                    removed.push(span.clone());
+                }
+            }
+        }
+        if tag.empty {
+            if remove {
+                removed.push(tag.span.clone());
+            }
+        } else {
+            open.push((tag.span.start, remove));
+        }
+    }
+
+    // The removed spans are in order and none contains the end of the RDF element
+    let mut insert_at = Some(insert_at?);
+    let mut merged = String::with_capacity(xmp.len() + descriptions.len());
+    let mut pos = 0;
+    for span in removed {
+        if let Some(at) = insert_at.filter(|&at| at <= span.start) {
+            merged.push_str(&xmp[pos..at]);
+            merged.push_str(descriptions);
+            pos = at;
+            insert_at = None;
+        }
+        merged.push_str(&xmp[pos..span.start]);
+        pos = span.end;
+    }
+    if let Some(at) = insert_at {
+        merged.push_str(&xmp[pos..at]);
+        merged.push_str(descriptions);
+        pos = at;
+    }
+    merged.push_str(&xmp[pos..]);
+    Some(merged)
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+impl PdfDocument {
+    /// Convert the document to PDF/A `level` as far as possible, returning the violations that
+    /// remain as reported by [`PdfDocument::validate`].
+    ///
+    /// This removes encryption, which reopens the document like [`PdfDocument::clean`] does,
+    /// adds an sRGB output intent, declares the conformance level in the XMP metadata, adds a
+    /// file identifier, embeds the standard 14 fonts used with `WinAnsiEncoding`, removes
+    /// JavaScript and other forbidden actions, decompresses LZW streams, turns off image
+    /// interpolation and makes annotations printable. For PDF/A-1, graphics states, annotations
+    /// and transparency groups are made opaque. Other fonts that aren't embedded, soft masks of
+    /// images and embedded files are left to the caller.
+    pub fn convert_to_pdfa(&mut self, level: PdfALevel) -> Result<Vec<Violation>, Error> {
+        let profile = ValidationProfile::PdfA(level);
+        if self.is_encrypted()? {
+            let mut write = PdfWriteOptions::default();
+            write.set_encryption(Encryption::None);
+            let mut decrypted = Vec::new();
+            self.write_to_with_options(&mut decrypted, write)?;
+            *self = PdfDocument::from_bytes(&decrypted)?;
+        }
+        self.add_pdfa_output_intent()?;
+        self.set_pdfa_metadata(level)?;
+        if self.file_id()?.is_none() {
+            self.regenerate_file_id()?;
+        }
+        if let Some(mut names) = self.catalog()?.get_dict("Names")? {
+            names.dict_delete("JavaScript")?;
+        }
+
+        for (_, mut obj) in dict_objects(self)? {
+            obj.dict_delete("AA")?;
+            for key in ["A", "OpenAction"] {
+                if let Some(action) = obj.get_dict(key)? {
+                    if is_forbidden_action(&action, profile)? {
+                        obj.dict_delete(key)?;
+                    }
+                }
+            }
+            if level == PdfALevel::A1b {
+                remove_transparency(&mut obj)?;
+                for mut gstate in graphics_states(&obj)? {
+                    remove_transparency(&mut gstate)?;
+                }
+            }
+            if obj.is_stream()? && uses_lzw(&obj)? {
+                let data = obj.read_stream()?;
+                obj.write_stream_buffer(&Buffer::from_bytes(&data)?)?;
+            }
+            obj.dict_delete("Interpolate")?;
+            if is_printed_annotation(&obj)? {
+                let flags = match obj.get_dict("F")? {
+                    Some(flags) => flags.as_int()?,
+                    None => 0,
+                };
+                let fixed = (flags | ANNOT_PRINT) & !ANNOT_HIDING;
+                if fixed != flags {
+                    obj.dict_put("F", PdfObject::new_int(fixed)?)?;
+                }
+            }
+            if is_font(&obj)? && !is_font_embedded(&obj)? {
+                self.embed_base14_font(&mut obj)?;
+            }
+        }
+        self.validate(profile)
+    }
+
+    /// Add an sRGB output intent unless the document has a PDF/A one or no ICC profile is
+    /// available.
+    fn add_pdfa_output_intent(&mut self) -> Result<(), Error> {
+        let mut catalog = self.catalog()?;
+        let mut intents = match catalog.get_dict("OutputIntents")? {
+            Some(intents) if intents.is_array()? => intents,
+            _ => {
+                catalog.dict_put("OutputIntents", self.new_array()?)?;
+                catalog
+                    .get_dict("OutputIntents")?
+                    .ok_or(Error::UnexpectedNullPtr)?
+            }
+        };
+        for i in 0..intents.len()? as i32 {
+            if let Some(intent) = intents.get_array(i)? {
+                if name_of(&intent, "S")?.as_deref() == Some(b"GTS_PDFA1") {
+                    return Ok(());
+                }
+            }
+        }
+        let Some(icc) = Colorspace::device_rgb().icc_profile()? else {
+            return Ok(());
+        };
+        let mut profile = self.add_object(&self.new_object_from_str("<< /N 3 >>")?)?;
+        profile.write_stream_buffer(&Buffer::from_bytes(&icc)?)?;
+        let mut intent = self.new_object_from_str(&format!(
+            "<< /Type /OutputIntent /S /GTS_PDFA1 /OutputConditionIdentifier ({0}) \
+             /Info ({0}) /RegistryName (http://www.color.org) >>",
+            OUTPUT_CONDITION
+        ))?;
+        intent.dict_put("DestOutputProfile", profile)?;
+        intents.array_push(intent)
+    }
+
+    /// Declare `level` in the XMP metadata and repeat the document information dictionary in
+    /// it, which PDF/A requires to agree. Other properties of existing metadata are kept.
+    fn set_pdfa_metadata(&mut self, level: PdfALevel) -> Result<(), Error> {
+        let info = self.trailer()?.get_dict("Info")?;
+        let entry = |key: &str| -> Result<Option<String>, Error> {
+            match info.as_ref().map(|info| info.get_dict(key)).transpose()? {
+                Some(Some(value)) if value.is_string()? => Ok(Some(escape_xml(value.as_string()?))),
+                _ => Ok(None),
+            }
+        };
+
+        let mut replaced = vec![(PDFAID_NS, "part"), (PDFAID_NS, "conformance")];
+        let mut descriptions = format!(
+            "<rdf:Description rdf:about=\"\" xmlns:pdfaid=\"{}\">\n\
+             <pdfaid:part>{}</pdfaid:part>\n\
+             <pdfaid:conformance>{}</pdfaid:conformance>\n\
+             </rdf:Description>\n",
+            PDFAID_NS,
+            level.part(),
+            level.conformance()
+        );
+        descriptions.push_str(&format!(
+            "<rdf:Description rdf:about=\"\" xmlns:dc=\"{}\">\n",
+            DC_NS
+        ));
+        if let Some(title) = entry("Title")? {
+            replaced.push((DC_NS, "title"));
+            descriptions.push_str(&format!(
+                "<dc:title><rdf:Alt><rdf:li xml:lang=\"x-default\">{}</rdf:li></rdf:Alt></dc:title>\n",
+                title
+            ));
+        }
+        if let Some(author) = entry("Author")? {
+            replaced.push((DC_NS, "creator"));
+            descriptions.push_str(&format!(
+                "<dc:creator><rdf:Seq><rdf:li>{}</rdf:li></rdf:Seq></dc:creator>\n",
+                author
+            ));
+        }
+        if let Some(subject) = entry("Subject")? {
+            replaced.push((DC_NS, "description"));
+            descriptions.push_str(&format!(
+                "<dc:description><rdf:Alt><rdf:li xml:lang=\"x-default\">{}</rdf:li></rdf:Alt></dc:description>\n",
+                subject
+            ));
+        }
+        descriptions.push_str("</rdf:Description>\n");
+        descriptions.push_str(&format!(
+            "<rdf:Description rdf:about=\"\" xmlns:pdf=\"{}\">\n",
+            PDF_NS
+        ));
+        if let Some(keywords) = entry("Keywords")? {
+            replaced.push((PDF_NS, "Keywords"));
+            descriptions.push_str(&format!("<pdf:Keywords>{}</pdf:Keywords>\n", keywords));
+        }
+        if let Some(producer) = entry("Producer")? {
+            replaced.push((PDF_NS, "Producer"));
+            descriptions.push_str(&format!("<pdf:Producer>{}</pdf:Producer>\n", producer));
+        }
+        descriptions.push_str("</rdf:Description>\n");
+        descriptions.push_str(&format!(
+            "<rdf:Description rdf:about=\"\" xmlns:xmp=\"{}\">\n",
+            XMP_NS
+        ));
+        if let Some(creator) = entry("Creator")? {
+            replaced.push((XMP_NS, "CreatorTool"));
+            descriptions.push_str(&format!("<xmp:CreatorTool>{}</xmp:CreatorTool>\n", creator));
+        }
+        for (key, property) in [("CreationDate", "CreateDate"), ("ModDate", "ModifyDate")] {
+            if let Some(date) = entry(key)?.as_deref().and_then(xmp_date) {
+                replaced.push((XMP_NS, property));
+                descriptions.push_str(&format!("<xmp:{0}>{1}</xmp:{0}>\n", property, date));
+            }
+        }
+        descriptions.push_str("</rdf:Description>\n");
+
+        let existing = match self.catalog()?.get_dict("Metadata")? {
+            Some(metadata) if metadata.is_stream()? => metadata.read_stream().ok(),
+            _ => None,
+        };
+        let xmp = existing
+            .and_then(|xmp| String::from_utf8(xmp).ok())
+            // The merge below relies on the metadata being well formed
+            .filter(|xmp| XmlDocument::parse(xmp.as_bytes()).is_ok())
+            .and_then(|xmp| merge_xmp(&xmp, &descriptions, &replaced))
+            .unwrap_or_else(|| {
+                format!(
+                    "<?xpacket begin=\"\u{feff}\" id=\"W5M0MpCehiHzreSzNTczkc9d\"?>\n\
+                     <x:xmpmeta xmlns:x=\"adobe:ns:meta/\">\n\
+                     <rdf:RDF xmlns:rdf=\"{}\">\n{}</rdf:RDF>\n</x:xmpmeta>\n\
+                     <?xpacket end=\"w\"?>",
+                    RDF_NS, descriptions
+                )
+            });
+
+        let mut metadata =
+            self.add_object(&self.new_object_from_str("<< /Type /Metadata /Subtype /XML >>")?)?;
+        metadata.write_stream_buffer(&Buffer::from_bytes(xmp.as_bytes())?)?;
+        self.catalog()?.dict_put("Metadata", metadata)
+    }
+
+    /// Embed a font in place if it is one of the standard 14 fonts with `WinAnsiEncoding`.
+    fn embed_base14_font(&mut self, font: &mut PdfObject) -> Result<(), Error> {
+        let name = font_name(font)?;
+        let is_replaceable = name_of(font, "Subtype")?.as_deref() == Some(b"Type1")
+            && name_of(font, "Encoding")?.as_deref() == Some(b"WinAnsiEncoding")
+            && BASE14_FONTS.contains(&name.as_str());
+        if !is_replaceable {
+            return Ok(());
+        }
+        let embedded = self.add_simple_font(&Font::new(&name)?, SimpleFontEncoding::Latin)?;
+        for i in 0..embedded.dict_len()? as i32 {
+            if let (Some(key), Some(value)) = (embedded.get_dict_key(i)?, embedded.get_dict_val(i)?)
+            {
+                let key = String::from_utf8_lossy(key.as_name()?).into_owned();
+                font.dict_put(key.as_str(), value)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{merge_xmp, xmp_date, PdfALevel, DC_NS, PDFAID_NS};
+    use crate::pdf::PdfDocument;
+
+    #[test]
+    fn test_xmp_date() {
+        assert_eq!(
+            xmp_date("D:20240131120000+01'00'").as_deref(),
+            Some("2024-01-31T12:00:00+01:00")
+        );
+        assert_eq!(
+            xmp_date("D:202401311200Z").as_deref(),
+            Some("2024-01-31T12:00Z")
+        );
+        assert_eq!(xmp_date("D:2024").as_deref(), Some("2024"));
+        assert_eq!(xmp_date("yesterday"), None);
+    }
+
+    #[test]
+    fn test_merge_xmp() {
+        let xmp = "<?xpacket begin=\"\"?><x:xmpmeta xmlns:x=\"adobe:ns:meta/\">\
+                   <rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\">\
+                   <rdf:Description rdf:about=\"\" xmlns:a=\"http://www.aiim.org/pdfa/ns/id/\" \
+                   a:part=\"3\" xmlns:d=\"http://purl.org/dc/elements/1.1/\">\
+                   <d:title><rdf:Alt><rdf:li>Old</rdf:li></rdf:Alt></d:title>\
+                   <d:format>application/pdf</d:format><!-- <d:title/> --></rdf:Description>\
+                   </rdf:RDF></x:xmpmeta><?xpacket end=\"w\"?>";
+        let merged = merge_xmp(xmp, "<new/>", &[(PDFAID_NS, "part"), (DC_NS, "title")]).unwrap();
+        assert_eq!(
+            merged,
+            "<?xpacket begin=\"\"?><x:xmpmeta xmlns:x=\"adobe:ns:meta/\">\
+             <rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\">\
+             <rdf:Description rdf:about=\"\" xmlns:a=\"http://www.aiim.org/pdfa/ns/id/\" \
+             xmlns:d=\"http://purl.org/dc/elements/1.1/\">\
+             <d:format>application/pdf</d:format><!-- <d:title/> --></rdf:Description>\
+             <new/></rdf:RDF></x:xmpmeta><?xpacket end=\"w\"?>"
+        );
+        assert!(merge_xmp("<a><b/></a>", "<new/>", &[]).is_none());
+    }
+
+    #[test]
+    fn test_convert_to_pdfa() {
+        let mut doc = PdfDocument::new();
+        doc.new_page((300.0, 400.0)).unwrap();
+        let font = doc
+            .new_object_from_str(
+                "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica \
+                 /Encoding /WinAnsiEncoding >>",
+            )
+            .unwrap();
+        let font = doc.add_object(&font).unwrap();
+        let other = doc
+            .new_object_from_str("<< /Type /Font /Subtype /TrueType /BaseFont /Arial >>")
+            .unwrap();
+        let other = doc.add_object(&other).unwrap();
+        let gstate = doc
+            .new_object_from_str("<< /Type /ExtGState /ca 0.5 /BM /Multiply >>")
+            .unwrap();
+        let mut resources = doc.new_dict().unwrap();
+        let mut fonts = doc.new_dict().unwrap();
+        fonts.dict_put("F1", font.clone()).unwrap();
+        fonts.dict_put("F2", other.clone()).unwrap();
+        let mut gstates = doc.new_dict().unwrap();
+        gstates.dict_put("GS1", gstate).unwrap();
+        resources.dict_put("Font", fonts).unwrap();
+        resources.dict_put("ExtGState", gstates).unwrap();
+        let mut page_obj = doc.find_page(0).unwrap();
+        page_obj.dict_put("Resources", resources).unwrap();
+        page_obj
+            .dict_put(
+                "Group",
+                doc.new_object_from_str("<< /S /Transparency >>").unwrap(),
+            )
+            .unwrap();
+        let action = doc
+            .new_object_from_str("<< /S /JavaScript /JS (app.alert(1)) >>")
+            .unwrap();
+        doc.catalog()
+            .unwrap()
+            .dict_put("OpenAction", action)
+            .unwrap();
+
+        let violations = doc.convert_to_pdfa(PdfALevel::A1b).unwrap();
+        assert_eq!(violations.len(), 1, "{:?}", violations);
+        assert_eq!(violations[0].rule, "font-not-embedded");
+        assert_eq!(violations[0].object, Some(other.as_indirect().unwrap()));
+
+        let catalog = doc.catalog().unwrap();
+        assert!(catalog.get_dict("OpenAction").unwrap().is_none());
+        let intent = catalog
+            .get_dict("OutputIntents")
+            .unwrap()
+            .unwrap()
+            .get_array(0)
+            .unwrap()
+            .unwrap();
+        assert!(intent.get_dict("DestOutputProfile").unwrap().is_some());
+        let xmp = catalog
+            .get_dict("Metadata")
+            .unwrap()
+            .unwrap()
+            .read_stream()
+            .unwrap();
+        let xmp = String::from_utf8(xmp).unwrap();
+        assert!(xmp.contains("<pdfaid:part>1</pdfaid:part>"));
+
+        let page_obj = doc.find_page(0).unwrap();
+        assert!(page_obj.get_dict("Group").unwrap().is_none());
+        let gstate = page_obj
+            .get_dict("Resources")
+            .unwrap()
+            .unwrap()
+            .get_dict("ExtGState")
+            .unwrap()
+            .unwrap()
+            .get_dict("GS1")
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            gstate.get_dict("ca").unwrap().unwrap().as_float().unwrap(),
+            1.0
+        );
+        assert_eq!(
+            gstate.get_dict("BM").unwrap().unwrap().as_name().unwrap(),
+            b"Normal"
+        );
+        let descriptor = font.get_dict("FontDescriptor").unwrap().unwrap();
+        assert!(
+            descriptor.get_dict("FontFile3").unwrap().is_some()
+                || descriptor.get_dict("FontFile").unwrap().is_some()
+        );
+    }
+
+    #[test]
+    fn test_convert_encrypted_to_pdfa() {
+        let mut doc = PdfDocument::open("tests/files/dummy-encrypted.pdf").unwrap();
+        assert!(doc.authenticate("123456").unwrap());
+        assert!(doc.is_encrypted().unwrap());
+        let violations = doc.convert_to_pdfa(PdfALevel::A2b).unwrap();
+        assert!(!doc.is_encrypted().unwrap());
+        assert!(violations.iter().all(|v| v.rule != "encryption"));
+        assert_eq!(doc.page_count().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_convert_to_pdfa_keeps_xmp() {
+        let mut doc = PdfDocument::new();
+        doc.new_page((300.0, 400.0)).unwrap();
+        let mut metadata = doc
+            .add_object(
+                &doc.new_object_from_str("<< /Type /Metadata /Subtype /XML >>")
+                    .unwrap(),
+            )
+            .unwrap();
+        let xmp = "<x:xmpmeta xmlns:x=\"adobe:ns:meta/\">\
+                   <rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\">\
+                   <rdf:Description rdf:about=\"\" xmlns:pdfaid=\"http://www.aiim.org/pdfa/ns/id/\" \
+                   xmlns:xmpRights=\"http://ns.adobe.com/xap/1.0/rights/\">\
+                   <pdfaid:part>3</pdfaid:part><xmpRights:Marked>True</xmpRights:Marked>\
+                   </rdf:Description></rdf:RDF></x:xmpmeta>";
+        metadata
+            .write_stream_buffer(&crate::Buffer::from_bytes(xmp.as_bytes()).unwrap())
+            .unwrap();
+        doc.catalog()
+            .unwrap()
+            .dict_put("Metadata", metadata)
+            .unwrap();
+
+        doc.convert_to_pdfa(PdfALevel::A2b).unwrap();
+        let xmp = doc
+            .catalog()
+            .unwrap()
+            .get_dict("Metadata")
+            .unwrap()
+            .unwrap()
+            .read_stream()
+            .unwrap();
+        let xmp = String::from_utf8(xmp).unwrap();
+        assert!(xmp.contains("<xmpRights:Marked>True</xmpRights:Marked>"));
+        assert!(xmp.contains("<pdfaid:part>2</pdfaid:part>"));
+        assert!(!xmp.contains("<pdfaid:part>3</pdfaid:part>"));
+    }
+}
@@ -0,0 +1,753 @@
+use std::ffi::{c_uchar, c_void, CString};
+use std::ptr;
+
+use mupdf_sys::*;
+use num_enum::TryFromPrimitive;
+
+use crate::pdf::der::{self, Element};
+use crate::pdf::{FormField, PdfAnnotation, PdfDocument, PdfObject};
+use crate::{context, Buffer, Error};
+
+/// Creates the signatures of [`PdfAnnotation::sign`], typically with a private key held in a
+/// file, a smart card or a signing service.
+pub trait PdfSigner {
+    /// The name of the signer, shown in the appearance of the signature.
+    fn name(&self) -> String;
+
+    /// The most bytes [`PdfSigner::sign`] returns, the room reserved for the signature in the
+    /// file.
+    fn max_signature_size(&self) -> usize {
+        16384
+    }
+
+    /// A detached CMS (PKCS #7) signature of `data`, the bytes of the file the signature covers.
+    /// An error, or more bytes than [`PdfSigner::max_signature_size`], fails saving the
+    /// document.
+    fn sign(&mut self, data: &[u8]) -> Result<Vec<u8>, Error>;
+}
+
+/// What a certification signature allows to change in the document without invalidating it,
+/// the DocMDP permissions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, TryFromPrimitive)]
+#[repr(i32)]
+pub enum MdpPermissions {
+    /// No changes at all.
+    NoChanges = 1,
+    /// Filling in forms, instantiating page templates and signing.
+    FormFilling = 2,
+    /// As [`MdpPermissions::FormFilling`], and adding, changing and deleting annotations.
+    Annotations = 3,
+}
+
+/// Options for [`PdfAnnotation::sign`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SignatureOptions {
+    reason: Option<String>,
+    location: Option<String>,
+    certification: Option<MdpPermissions>,
+    pades: bool,
+}
+
+impl SignatureOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn reason(&self) -> Option<&str> {
+        self.reason.as_deref()
+    }
+
+    pub fn set_reason(&mut self, reason: &str) -> &mut Self {
+        self.reason = Some(reason.to_owned());
+        self
+    }
+
+    pub fn location(&self) -> Option<&str> {
+        self.location.as_deref()
+    }
+
+    pub fn set_location(&mut self, location: &str) -> &mut Self {
+        self.location = Some(location.to_owned());
+        self
+    }
+
+    /// Whether the signature certifies the document, `None` for an approval signature.
+    pub fn certification(&self) -> Option<MdpPermissions> {
+        self.certification
+    }
+
+    /// Make the signature certify the document, allowing the given changes afterwards. Only
+    /// the first signature of a document can certify it.
+    pub fn set_certification(&mut self, permissions: Option<MdpPermissions>) -> &mut Self {
+        self.certification = permissions;
+        self
+    }
+
+    /// Whether the signature is marked as a PAdES signature, `ETSI.CAdES.detached`, rather
+    /// than a plain PKCS #7 one. The signer has to create a CAdES signature then.
+    pub fn pades(&self) -> bool {
+        self.pades
+    }
+
+    pub fn set_pades(&mut self, pades: bool) -> &mut Self {
+        self.pades = pades;
+        self
+    }
+}
+
+/// The DER of the OID of the `signatureTimeStampToken` attribute, 1.2.840.113549.1.9.16.2.14.
+const TIMESTAMP_TOKEN_OID: &[u8] = &[
+    0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x09, 0x10, 0x02, 0x0e,
+];
+
+/// A CMS signature taken apart down to its first signer.
+pub(crate) struct Cms<'a> {
+    content_type: Element<'a>,
+    signed_data: Vec<Element<'a>>,
+    /// Index of the signer infos in `signed_data`.
+    signer_infos: usize,
+    signer: Vec<Element<'a>>,
+}
+
+impl<'a> Cms<'a> {
+    pub(crate) fn parse(cms: &'a [u8]) -> Option<Self> {
+        let (content_info, _) = Element::parse(cms)?;
+        let [content_type, content] = content_info.children()?[..] else {
+            return None;
+        };
+        let (signed_data, _) = Element::parse(content.contents)?;
+        let signed_data = signed_data.children()?;
+        // The signer infos are last, after the optional certificates and CRLs
+        let signer_infos = signed_data.len().checked_sub(1)?;
+        if signed_data[signer_infos].tag != der::SET {
+            return None;
+        }
+        let signer = signed_data[signer_infos].children()?.first()?.children()?;
+        Some(Self {
+            content_type,
+            signed_data,
+            signer_infos,
+            signer,
+        })
+    }
+
+    /// The certificates the signature comes with, in no particular order.
+    pub(crate) fn certificates(&self) -> Vec<Element<'a>> {
+        self.signed_data
+            .iter()
+            .find(|e| e.tag == der::context(0))
+            .and_then(|certificates| certificates.children())
+            .unwrap_or_default()
+    }
+
+    /// Who the first signer is, the issuer and serial number of its certificate or the
+    /// subject key identifier.
+    pub(crate) fn signer_id(&self) -> Option<Element<'a>> {
+        self.signer.get(1).copied()
+    }
+
+    /// The signature value of the first signer.
+    fn signature_value(&self) -> Option<&'a [u8]> {
+        self.signer
+            .iter()
+            .rev()
+            .find(|e| e.tag == der::OCTET_STRING)
+            .map(|e| e.contents)
+    }
+
+    /// The signature with an unsigned attribute added to the first signer.
+    fn with_unsigned_attribute(&self, oid: &[u8], value: &[u8]) -> Option<Vec<u8>> {
+        let attribute = der::encode_children(
+            der::SEQUENCE,
+            &[&der::encode(der::OID, oid), &der::encode(der::SET, value)],
+        );
+        let mut signer: Vec<Vec<u8>> = self.signer.iter().map(|e| e.raw.to_vec()).collect();
+        match self.signer.last() {
+            Some(last) if last.tag == der::context(1) => {
+                let attributes = [last.contents, &attribute].concat();
+                *signer.last_mut()? = der::encode(der::context(1), &attributes);
+            }
+            _ => signer.push(der::encode(der::context(1), &attribute)),
+        }
+        let signer: Vec<&[u8]> = signer.iter().map(Vec::as_slice).collect();
+        let signer = der::encode_children(der::SEQUENCE, &signer);
+
+        let others = self.signed_data[self.signer_infos].children()?;
+        let mut signer_infos = signer;
+        for other in &others[1..] {
+            signer_infos.extend_from_slice(other.raw);
+        }
+        let signer_infos = der::encode(der::SET, &signer_infos);
+
+        let mut signed_data: Vec<&[u8]> = self.signed_data.iter().map(|e| e.raw).collect();
+        signed_data[self.signer_infos] = &signer_infos;
+        let signed_data = der::encode_children(der::SEQUENCE, &signed_data);
+        Some(der::encode_children(
+            der::SEQUENCE,
+            &[
+                self.content_type.raw,
+                &der::encode(der::context(0), &signed_data),
+            ],
+        ))
+    }
+}
+
+/// A [`PdfSigner`] adding a timestamp from a time stamping authority to the signatures of
+/// another signer, as PAdES B-T requires.
+///
+/// The authority is called with the signature value and returns a `TimeStampToken` as of
+/// RFC 3161, for a request over a digest of that value.
+pub struct TimestampedSigner<S, F> {
+    signer: S,
+    authority: F,
+    token_size: usize,
+}
+
+impl<S, F> TimestampedSigner<S, F>
+where
+    S: PdfSigner,
+    F: FnMut(&[u8]) -> Result<Vec<u8>, Error>,
+{
+    pub fn new(signer: S, authority: F) -> Self {
+        Self {
+            signer,
+            authority,
+            token_size: 8192,
+        }
+    }
+
+    /// Room reserved for the timestamp token besides the signature, 8 KiB by default.
+    pub fn set_token_size(&mut self, size: usize) -> &mut Self {
+        self.token_size = size;
+        self
+    }
+}
+
+impl<S, F> PdfSigner for TimestampedSigner<S, F>
+where
+    S: PdfSigner,
+    F: FnMut(&[u8]) -> Result<Vec<u8>, Error>,
+{
+    fn name(&self) -> String {
+        self.signer.name()
+    }
+
+    fn max_signature_size(&self) -> usize {
+        self.signer.max_signature_size() + self.token_size
+    }
+
+    fn sign(&mut self, data: &[u8]) -> Result<Vec<u8>, Error> {
+        let signature = self.signer.sign(data)?;
+        let invalid = || Error::InvalidAnnotation("malformed CMS signature".to_owned());
+        let cms = Cms::parse(&signature).ok_or_else(invalid)?;
+        let token = (self.authority)(cms.signature_value().ok_or_else(invalid)?)?;
+        cms.with_unsigned_attribute(TIMESTAMP_TOKEN_OID, &token)
+            .ok_or_else(invalid)
+    }
+}
+
+/// Certificates and revocation information for validating signatures long after signing,
+/// stored in the document security store (DSS) as PAdES B-LT requires.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ValidationData {
+    /// DER encoded X.509 certificates.
+    pub certificates: Vec<Vec<u8>>,
+    /// DER encoded OCSP responses.
+    pub ocsp_responses: Vec<Vec<u8>>,
+    /// DER encoded certificate revocation lists.
+    pub crls: Vec<Vec<u8>>,
+}
+
+type BoxedSigner = Box<dyn PdfSigner>;
+
+unsafe extern "C" fn signer_sign(
+    user: *mut c_void,
+    data: *const c_uchar,
+    len: usize,
+    out: *mut c_uchar,
+    out_len: usize,
+) -> usize {
+    let signer = unsafe { &mut *user.cast::<BoxedSigner>() };
+    let data = unsafe { std::slice::from_raw_parts(data, len) };
+    match signer.sign(data) {
+        Ok(signature) if signature.len() <= out_len => {
+            unsafe { ptr::copy_nonoverlapping(signature.as_ptr(), out, signature.len()) };
+            signature.len()
+        }
+        _ => 0,
+    }
+}
+
+unsafe extern "C" fn signer_drop(user: *mut c_void) {
+    drop(unsafe { Box::from_raw(user.cast::<BoxedSigner>()) });
+}
+
+fn string_entry(dict: &PdfObject, key: &str) -> Result<Option<String>, Error> {
+    match dict.get_dict(key)? {
+        Some(s) if s.is_string()? => Ok(Some(s.as_string()?.to_owned())),
+        _ => Ok(None),
+    }
+}
+
+/// The DocMDP permissions of a signature dictionary, if it certifies the document.
+fn docmdp_permissions(value: &PdfObject) -> Result<Option<MdpPermissions>, Error> {
+    let Some(references) = value.get_dict("Reference")? else {
+        return Ok(None);
+    };
+    for i in 0..references.len()? as i32 {
+        let Some(reference) = references.get_array(i)? else {
+            continue;
+        };
+        match reference.get_dict("TransformMethod")? {
+            Some(method) if method.as_name()? == b"DocMDP" => {}
+            _ => continue,
+        }
+        let p = match reference
+            .get_dict("TransformParams")?
+            .map(|params| params.get_dict("P"))
+            .transpose()?
+            .flatten()
+        {
+            Some(p) => p.as_int()?,
+            None => MdpPermissions::FormFilling as i32,
+        };
+        return Ok(Some(
+            MdpPermissions::try_from(p).unwrap_or(MdpPermissions::NoChanges),
+        ));
+    }
+    Ok(None)
+}
+
+/// A signature field of a form.
+#[derive(Debug, Clone)]
+pub struct Signature {
+    name: String,
+    field: PdfObject,
+}
+
+impl Signature {
+    /// The fully qualified name of the signature field.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn object(&self) -> &PdfObject {
+        &self.field
+    }
+
+    /// The signature dictionary, `None` for an unsigned field.
+    fn value(&self) -> Result<Option<PdfObject>, Error> {
+        match self.field.get_dict("V")? {
+            Some(v) if v.is_dict()? => Ok(Some(v)),
+            _ => Ok(None),
+        }
+    }
+
+    pub fn is_signed(&self) -> Result<bool, Error> {
+        Ok(self.value()?.is_some())
+    }
+
+    pub fn reason(&self) -> Result<Option<String>, Error> {
+        self.value()?
+            .map_or(Ok(None), |v| string_entry(&v, "Reason"))
+    }
+
+    pub fn location(&self) -> Result<Option<String>, Error> {
+        self.value()?
+            .map_or(Ok(None), |v| string_entry(&v, "Location"))
+    }
+
+    /// The time of signing as a PDF date string, e.g. `D:20240131120000Z`.
+    pub fn signing_time(&self) -> Result<Option<String>, Error> {
+        self.value()?.map_or(Ok(None), |v| string_entry(&v, "M"))
+    }
+
+    /// The CMS (PKCS #7) signature, padded with zeros to the room reserved for it.
+    pub fn contents(&self) -> Result<Vec<u8>, Error> {
+        match self
+            .value()?
+            .map(|v| v.get_dict("Contents"))
+            .transpose()?
+            .flatten()
+        {
+            Some(contents) if contents.is_string()? => Ok(contents.as_bytes()?.to_vec()),
+            _ => Ok(Vec::new()),
+        }
+    }
+
+    /// The `(offset, length)` ranges of the file the signature covers.
+    pub fn byte_range(&self) -> Result<Vec<(usize, usize)>, Error> {
+        let Some(range) = self
+            .value()?
+            .map(|v| v.get_dict("ByteRange"))
+            .transpose()?
+            .flatten()
+        else {
+            return Ok(Vec::new());
+        };
+        let mut ranges = Vec::new();
+        for i in (0..range.len()? as i32 - 1).step_by(2) {
+            match (range.get_array(i)?, range.get_array(i + 1)?) {
+                (Some(offset), Some(len)) => ranges.push((
+                    offset.as_int()?.max(0) as usize,
+                    len.as_int()?.max(0) as usize,
+                )),
+                _ => break,
+            }
+        }
+        Ok(ranges)
+    }
+
+    /// The changes the signature allows after signing if it certifies the document, `None`
+    /// for approval signatures.
+    pub fn certification(&self) -> Result<Option<MdpPermissions>, Error> {
+        match self.value()? {
+            Some(value) => docmdp_permissions(&value),
+            None => Ok(None),
+        }
+    }
+
+    /// Whether the document was changed by incremental updates after signing. Whether those
+    /// changes are allowed depends on [`Signature::certification`].
+    pub fn is_modified_since_signing(&self) -> Result<bool, Error> {
+        let doc = self.field.document().ok_or(Error::InvalidPdfDocument)?;
+        unsafe {
+            ffi_try!(mupdf_pdf_signature_incremental_change_since_signing(
+                context(),
+                doc.inner,
+                self.field.inner
+            ))
+        }
+    }
+}
+
+fn collect_signatures(fields: Vec<FormField>, out: &mut Vec<Signature>) -> Result<(), Error> {
+    for field in fields {
+        let is_signature = match field.object.get_dict_inheritable("FT")? {
+            Some(ft) => ft.as_name()? == b"Sig",
+            None => false,
+        };
+        if is_signature && field.kids.is_empty() {
+            out.push(Signature {
+                name: field.full_name,
+                field: field.object,
+            });
+        } else {
+            collect_signatures(field.kids, out)?;
+        }
+    }
+    Ok(())
+}
+
+impl PdfDocument {
+    /// The signature fields of the form, signed or not.
+    pub fn signatures(&self) -> Result<Vec<Signature>, Error> {
+        let mut signatures = Vec::new();
+        collect_signatures(self.form_fields()?, &mut signatures)?;
+        Ok(signatures)
+    }
+
+    /// The changes the certification signature of the document allows, `None` if it is not
+    /// certified.
+    pub fn certification(&self) -> Result<Option<MdpPermissions>, Error> {
+        let Some(perms) = self.catalog()?.get_dict("Perms")? else {
+            return Ok(None);
+        };
+        match perms.get_dict("DocMDP")? {
+            Some(value) => docmdp_permissions(&value),
+            None => Ok(None),
+        }
+    }
+
+    /// The validation data in the document security store.
+    pub fn validation_data(&self) -> Result<ValidationData, Error> {
+        let mut data = ValidationData::default();
+        let Some(dss) = self.catalog()?.get_dict("DSS")? else {
+            return Ok(data);
+        };
+        for (key, list) in [
+            ("Certs", &mut data.certificates),
+            ("OCSPs", &mut data.ocsp_responses),
+            ("CRLs", &mut data.crls),
+        ] {
+            let Some(streams) = dss.get_dict(key)? else {
+                continue;
+            };
+            for i in 0..streams.len()? as i32 {
+                if let Some(stream) = streams.get_array(i)? {
+                    list.push(stream.read_stream()?);
+                }
+            }
+        }
+        Ok(data)
+    }
+
+    /// Add validation data to the document security store, keeping what it holds already.
+    /// Save the document incrementally afterwards, or signatures become invalid.
+    pub fn add_validation_data(&mut self, data: &ValidationData) -> Result<(), Error> {
+        let mut catalog = self.catalog()?;
+        let mut dss = match catalog.get_dict("DSS")? {
+            Some(dss) => dss,
+            None => {
+                let dss = self.add_object(&self.new_dict()?)?;
+                catalog.dict_put("DSS", dss.clone())?;
+                dss
+            }
+        };
+        for (key, list) in [
+            ("Certs", &data.certificates),
+            ("OCSPs", &data.ocsp_responses),
+            ("CRLs", &data.crls),
+        ] {
+            if list.is_empty() {
+                continue;
+            }
+            let mut streams = match dss.get_dict(key)? {
+                Some(streams) => streams,
+                None => {
+                    dss.dict_put(key, self.new_array()?)?;
+                    dss.get_dict(key)?.ok_or(Error::UnexpectedNullPtr)?
+                }
+            };
+            for bytes in list {
+                let mut stream = self.add_object(&self.new_dict()?)?;
+                stream.write_stream_buffer(&Buffer::from_bytes(bytes)?)?;
+                streams.array_push(stream)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl PdfAnnotation {
+    /// Sign a signature widget with `signer`, e.g. one created by
+    /// [`PdfPage::create_field`](crate::pdf::PdfPage::create_field). This generates the
+    /// appearance of the signature and reserves room for it. The signature itself is created
+    /// when the document is saved, so the document has to be saved before it is changed
+    /// further.
+    pub fn sign<S: PdfSigner + 'static>(
+        &mut self,
+        signer: S,
+        options: &SignatureOptions,
+    ) -> Result<(), Error> {
+        let field = self.field()?;
+        match field.get_dict_inheritable("FT")? {
+            Some(ft) if ft.as_name()? == b"Sig" => {}
+            _ => return Err(Error::InvalidAnnotation("not a signature field".to_owned())),
+        }
+        let doc = field.document().ok_or(Error::InvalidPdfDocument)?;
+        if options.certification.is_some() {
+            for signature in doc.signatures()? {
+                if signature.is_signed()? {
+                    return Err(Error::InvalidAnnotation(
+                        "only the first signature can certify a document".to_owned(),
+                    ));
+                }
+            }
+        }
+
+        let name = CString::new(signer.name())?;
+        let reason = options.reason.as_deref().map(CString::new).transpose()?;
+        let location = options.location.as_deref().map(CString::new).transpose()?;
+        let max_size = signer.max_signature_size();
+        let user = Box::into_raw(Box::new(Box::new(signer) as BoxedSigner));
+        // MuPDF owns the signer from here on, dropping it on failure too
+        unsafe {
+            ffi_try!(mupdf_pdf_sign_signature(
+                context(),
+                self.inner,
+                name.as_ptr(),
+                max_size,
+                Some(signer_sign),
+                Some(signer_drop),
+                user.cast(),
+                reason.as_ref().map_or(ptr::null(), |s| s.as_ptr()),
+                location.as_ref().map_or(ptr::null(), |s| s.as_ptr())
+            ))
+        }?;
+
+        let mut value = field.get_dict("V")?.ok_or(Error::UnexpectedNullPtr)?;
+        if options.pades {
+            value.dict_put("SubFilter", PdfObject::new_name("ETSI.CAdES.detached")?)?;
+        }
+        if let Some(permissions) = options.certification {
+            let mut params = doc.new_dict()?;
+            params.dict_put("Type", PdfObject::new_name("TransformParams")?)?;
+            params.dict_put("P", PdfObject::new_int(permissions as i32)?)?;
+            params.dict_put("V", PdfObject::new_name("1.2")?)?;
+            let mut reference = doc.new_dict()?;
+            reference.dict_put("Type", PdfObject::new_name("SigRef")?)?;
+            reference.dict_put("TransformMethod", PdfObject::new_name("DocMDP")?)?;
+            reference.dict_put("TransformParams", params)?;
+            let mut references = doc.new_array()?;
+            references.array_push(reference)?;
+            value.dict_put("Reference", references)?;
+
+            let mut catalog = doc.catalog()?;
+            let mut perms = match catalog.get_dict("Perms")? {
+                Some(perms) => perms,
+                None => doc.new_dict()?,
+            };
+            perms.dict_put("DocMDP", value)?;
+            catalog.dict_put("Perms", perms)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        Cms, MdpPermissions, PdfSigner, SignatureOptions, TimestampedSigner, ValidationData,
+    };
+    use crate::pdf::der;
+    use crate::pdf::{FieldKind, PdfDocument};
+    use crate::{Error, Rect};
+
+    /// Stands in for a CMS signature, which this crate leaves to the signer.
+    struct FakeSigner;
+
+    impl PdfSigner for FakeSigner {
+        fn name(&self) -> String {
+            "Jane Doe".to_owned()
+        }
+
+        fn max_signature_size(&self) -> usize {
+            1024
+        }
+
+        fn sign(&mut self, data: &[u8]) -> Result<Vec<u8>, Error> {
+            assert!(data.starts_with(b"%PDF"));
+            Ok(b"fake signature".to_vec())
+        }
+    }
+
+    #[test]
+    fn test_certification_signature() {
+        let mut doc = PdfDocument::new();
+        let mut page = doc.new_page((300.0, 400.0)).unwrap();
+        let mut widget = page
+            .create_field(
+                FieldKind::Signature,
+                "author",
+                Rect::new(10.0, 10.0, 210.0, 60.0),
+            )
+            .unwrap();
+        page.create_field(
+            FieldKind::Signature,
+            "approval",
+            Rect::new(10.0, 70.0, 210.0, 120.0),
+        )
+        .unwrap();
+        assert!(!doc.signatures().unwrap()[0].is_signed().unwrap());
+
+        let mut options = SignatureOptions::new();
+        options
+            .set_reason("I am the author")
+            .set_certification(Some(MdpPermissions::FormFilling));
+        widget.sign(FakeSigner, &options).unwrap();
+        let mut buf = Vec::new();
+        doc.write_to(&mut buf).unwrap();
+
+        let doc = PdfDocument::from_bytes(&buf).unwrap();
+        assert_eq!(
+            doc.certification().unwrap(),
+            Some(MdpPermissions::FormFilling)
+        );
+        let signatures = doc.signatures().unwrap();
+        assert_eq!(signatures.len(), 2);
+        let author = &signatures[0];
+        assert_eq!(author.name(), "author");
+        assert!(author.is_signed().unwrap());
+        assert_eq!(author.reason().unwrap().as_deref(), Some("I am the author"));
+        assert!(author.contents().unwrap().starts_with(b"fake signature"));
+        assert_eq!(author.byte_range().unwrap().len(), 2);
+        assert_eq!(
+            author.certification().unwrap(),
+            Some(MdpPermissions::FormFilling)
+        );
+        assert!(!author.is_modified_since_signing().unwrap());
+        assert!(!signatures[1].is_signed().unwrap());
+        assert_eq!(signatures[1].certification().unwrap(), None);
+    }
+
+    /// A CMS signature reduced to what `Cms` looks at.
+    fn fake_cms(signature: &[u8]) -> Vec<u8> {
+        let signer = der::encode_children(
+            der::SEQUENCE,
+            &[
+                &der::encode(der::INTEGER, &[1]),
+                &der::encode(der::SEQUENCE, &[]),
+                &der::encode(der::OCTET_STRING, signature),
+            ],
+        );
+        let signed_data = der::encode_children(
+            der::SEQUENCE,
+            &[
+                &der::encode(der::INTEGER, &[1]),
+                &der::encode(der::SET, &[]),
+                &der::encode(der::SET, &signer),
+            ],
+        );
+        der::encode_children(
+            der::SEQUENCE,
+            &[
+                &der::encode(der::OID, &[0x2a]),
+                &der::encode(der::context(0), &signed_data),
+            ],
+        )
+    }
+
+    struct CmsSigner;
+
+    impl PdfSigner for CmsSigner {
+        fn name(&self) -> String {
+            "Jane Doe".to_owned()
+        }
+
+        fn sign(&mut self, _data: &[u8]) -> Result<Vec<u8>, Error> {
+            Ok(fake_cms(b"signature value"))
+        }
+    }
+
+    #[test]
+    fn test_timestamped_signer() {
+        let mut signer = TimestampedSigner::new(CmsSigner, |value: &[u8]| {
+            assert_eq!(value, b"signature value");
+            Ok(b"token".to_vec())
+        });
+        assert_eq!(signer.max_signature_size(), 16384 + 8192);
+        let cms = signer.sign(b"data").unwrap();
+        let cms = Cms::parse(&cms).unwrap();
+        assert_eq!(cms.signature_value().unwrap(), b"signature value");
+        let unsigned = cms.signer.last().unwrap();
+        assert_eq!(unsigned.tag, der::context(1));
+        let attribute = &unsigned.children().unwrap()[0].children().unwrap();
+        assert_eq!(attribute[0].contents, super::TIMESTAMP_TOKEN_OID);
+        assert_eq!(attribute[1].contents, b"token");
+    }
+
+    #[test]
+    fn test_validation_data() {
+        let mut doc = PdfDocument::open("tests/files/dummy.pdf").unwrap();
+        let data = ValidationData {
+            certificates: vec![b"certificate".to_vec(), b"issuer".to_vec()],
+            ocsp_responses: vec![b"ocsp".to_vec()],
+            crls: Vec::new(),
+        };
+        doc.add_validation_data(&data).unwrap();
+        let mut buf = Vec::new();
+        doc.write_to(&mut buf).unwrap();
+
+        let mut doc = PdfDocument::from_bytes(&buf).unwrap();
+        assert_eq!(doc.validation_data().unwrap(), data);
+        let crl = ValidationData {
+            crls: vec![b"crl".to_vec()],
+            ..Default::default()
+        };
+        doc.add_validation_data(&crl).unwrap();
+        let all = doc.validation_data().unwrap();
+        assert_eq!(all.certificates.len(), 2);
+        assert_eq!(all.crls, crl.crls);
+    }
+}
@@ -0,0 +1,509 @@
+use std::fmt;
+
+use crate::pdf::{PdfALevel, PdfDocument, PdfObject};
+use crate::Error;
+
+/// What [`PdfDocument::validate`] checks a document against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValidationProfile {
+    /// Requirements for accepting documents from untrusted sources: all fonts are embedded, and
+    /// there is no encryption, JavaScript or action that launches applications or imports data.
+    Basic,
+    /// The requirements of a PDF/A level that can be checked on the structure of the document,
+    /// without interpreting content streams or font programs.
+    PdfA(PdfALevel),
+}
+
+/// A requirement a document does not meet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    /// Identifier of the requirement, such as `font-not-embedded`.
+    pub rule: &'static str,
+    pub message: String,
+    /// The number of the offending object, if it is an indirect object.
+    pub object: Option<i32>,
+}
+
+impl Violation {
+    fn new(rule: &'static str, message: String, object: Option<i32>) -> Self {
+        Self {
+            rule,
+            message,
+            object,
+        }
+    }
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.rule, self.message)?;
+        if let Some(num) = self.object {
+            write!(f, " (object {})", num)?;
+        }
+        Ok(())
+    }
+}
+
+/// The value of a name entry of a dictionary.
+pub(crate) fn name_of(obj: &PdfObject, key: &str) -> Result<Option<Vec<u8>>, Error> {
+    match obj.get_dict(key)? {
+        Some(value) if value.is_name()? => Ok(Some(value.as_name()?.to_vec())),
+        _ => Ok(None),
+    }
+}
+
+fn reference(obj: &PdfObject) -> Result<Option<i32>, Error> {
+    if obj.is_indirect()? {
+        Ok(Some(obj.as_indirect()?))
+    } else {
+        Ok(None)
+    }
+}
+
+/// The indirect objects of a document that are dictionaries or streams, with their numbers.
+pub(crate) fn dict_objects(doc: &PdfDocument) -> Result<Vec<(i32, PdfObject)>, Error> {
+    let mut objects = Vec::new();
+    for num in 1..doc.count_objects()? as i32 {
+        let obj = doc.new_indirect(num, 0)?;
+        if obj.is_dict()? {
+            objects.push((num, obj));
+        }
+    }
+    Ok(objects)
+}
+
+/// The graphics states in the direct resources of a page or form, or of a resource dictionary.
+/// Indirect graphics states are found among the objects of the document.
+pub(crate) fn graphics_states(obj: &PdfObject) -> Result<Vec<PdfObject>, Error> {
+    let mut states = Vec::new();
+    let resources = match obj.get_dict("Resources")? {
+        Some(resources) if !resources.is_indirect()? => resources,
+        _ => obj.clone(),
+    };
+    if let Some(gstates) = resources.get_dict("ExtGState")? {
+        for i in 0..gstates.dict_len()? as i32 {
+            if let Some(gstate) = gstates.get_dict_val(i)? {
+                if gstate.is_dict()? && !gstate.is_indirect()? {
+                    states.push(gstate);
+                }
+            }
+        }
+    }
+    Ok(states)
+}
+
+pub(crate) fn font_name(font: &PdfObject) -> Result<String, Error> {
+    let name = name_of(font, "BaseFont")?.unwrap_or_default();
+    Ok(String::from_utf8_lossy(&name).into_owned())
+}
+
+/// Whether a dictionary is a font that has to embed its font program. The CID fonts of
+/// composite fonts are checked with their composite font.
+pub(crate) fn is_font(obj: &PdfObject) -> Result<bool, Error> {
+    Ok(name_of(obj, "Type")?.as_deref() == Some(b"Font")
+        && !matches!(
+            name_of(obj, "Subtype")?.as_deref(),
+            Some(b"CIDFontType0" | b"CIDFontType2")
+        ))
+}
+
+/// Whether a font dictionary, or the CID font of a composite font, embeds its font program.
+/// Type 3 fonts consist of content streams and are always embedded.
+pub(crate) fn is_font_embedded(font: &PdfObject) -> Result<bool, Error> {
+    let font = match name_of(font, "Subtype")?.as_deref() {
+        Some(b"Type3") => return Ok(true),
+        Some(b"Type0") => match font
+            .get_dict("DescendantFonts")?
+            .and_then(|fonts| fonts.get_array(0).transpose())
+            .transpose()?
+        {
+            Some(font) => font,
+            None => return Ok(false),
+        },
+        _ => font.clone(),
+    };
+    let Some(descriptor) = font.get_dict("FontDescriptor")? else {
+        return Ok(false);
+    };
+    for key in ["FontFile", "FontFile2", "FontFile3"] {
+        if descriptor.get_dict(key)?.is_some() {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// The kind of an action dictionary, `None` for destinations.
+fn action_kind(action: &PdfObject) -> Result<Option<Vec<u8>>, Error> {
+    if action.is_dict()? {
+        name_of(action, "S")
+    } else {
+        Ok(None)
+    }
+}
+
+/// Whether an action is one `profile` forbids. JavaScript is always forbidden.
+pub(crate) fn is_forbidden_action(
+    action: &PdfObject,
+    profile: ValidationProfile,
+) -> Result<bool, Error> {
+    let Some(kind) = action_kind(action)? else {
+        return Ok(false);
+    };
+    let level = match profile {
+        ValidationProfile::Basic => {
+            return Ok(matches!(
+                &kind[..],
+                b"JavaScript" | b"Launch" | b"ImportData"
+            ))
+        }
+        ValidationProfile::PdfA(level) => level,
+    };
+    Ok(match &kind[..] {
+        b"Launch" | b"Sound" | b"Movie" | b"ResetForm" | b"ImportData" | b"JavaScript" => true,
+        b"Hide" | b"SetOCGState" | b"Rendition" | b"Trans" | b"GoTo3DView" => {
+            level != PdfALevel::A1b
+        }
+        b"Named" => !matches!(
+            name_of(action, "N")?.as_deref(),
+            Some(b"NextPage" | b"PrevPage" | b"FirstPage" | b"LastPage")
+        ),
+        _ => false,
+    })
+}
+
+/// Whether a graphics state, annotation or form uses transparency, which PDF/A-1 forbids.
+pub(crate) fn uses_transparency(obj: &PdfObject) -> Result<bool, Error> {
+    if let Some(group) = obj.get_dict("Group")? {
+        if name_of(&group, "S")?.as_deref() == Some(b"Transparency") {
+            return Ok(true);
+        }
+    }
+    for key in ["CA", "ca"] {
+        // The MK dictionary of widgets has a caption string named CA
+        if let Some(alpha) = obj.get_dict(key)? {
+            if alpha.is_number()? && alpha.as_float()? != 1.0 {
+                return Ok(true);
+            }
+        }
+    }
+    if let Some(blend_mode) = name_of(obj, "BM")? {
+        if blend_mode != b"Normal" && blend_mode != b"Compatible" {
+            return Ok(true);
+        }
+    }
+    let is_image = name_of(obj, "Subtype")?.as_deref() == Some(b"Image");
+    match obj.get_dict("SMask")? {
+        Some(_) if is_image => return Ok(true),
+        Some(_) if name_of(obj, "SMask")?.as_deref() != Some(b"None") => return Ok(true),
+        _ => {}
+    }
+    Ok(is_image
+        && obj
+            .get_dict("SMaskInData")?
+            .is_some_and(|s| s.as_int().is_ok_and(|s| s != 0)))
+}
+
+/// Whether a stream is compressed with LZW, which PDF/A forbids for its patent history.
+pub(crate) fn uses_lzw(obj: &PdfObject) -> Result<bool, Error> {
+    let Some(filter) = obj.get_dict("Filter")? else {
+        return Ok(false);
+    };
+    if filter.is_array()? {
+        for i in 0..filter.len()? as i32 {
+            if let Some(filter) = filter.get_array(i)? {
+                if filter.is_name()? && filter.as_name()? == b"LZWDecode" {
+                    return Ok(true);
+                }
+            }
+        }
+        Ok(false)
+    } else {
+        Ok(filter.is_name()? && filter.as_name()? == b"LZWDecode")
+    }
+}
+
+/// Annotation flags PDF/A requires to be set and to be cleared.
+pub(crate) const ANNOT_PRINT: i32 = 4;
+pub(crate) const ANNOT_HIDING: i32 = 1 | 2 | 32 | 256;
+
+/// Whether a dictionary is an annotation that PDF/A requires to be printed.
+pub(crate) fn is_printed_annotation(obj: &PdfObject) -> Result<bool, Error> {
+    let is_annotation = name_of(obj, "Type")?.as_deref() == Some(b"Annot")
+        || (obj.get_dict("Rect")?.is_some() && obj.get_dict("Subtype")?.is_some());
+    Ok(is_annotation && !obj.is_stream()? && name_of(obj, "Subtype")?.as_deref() != Some(b"Popup"))
+}
+
+/// The PDF/A part declared by XMP metadata, from either of the forms XMP properties take.
+fn declared_part(xmp: &str) -> Option<u8> {
+    let start = if let Some(i) = xmp.find("pdfaid:part>") {
+        i + "pdfaid:part>".len()
+    } else if let Some(i) = xmp.find("pdfaid:part=\"") {
+        i + "pdfaid:part=\"".len()
+    } else {
+        xmp.find("pdfaid:part='")? + "pdfaid:part='".len()
+    };
+    xmp[start..].trim_start().get(..1)?.parse().ok()
+}
+
+impl PdfDocument {
+    /// Check the document against `profile`, returning the requirements it does not meet in
+    /// the order they were found. Rules that apply to objects are reported for each object.
+    ///
+    /// The rules are `encryption`, `javascript`, `forbidden-action`, `font-not-embedded`, and
+    /// for PDF/A `output-intent`, `metadata`, `file-id`, `embedded-file`, `transparency`,
+    /// `lzw-filter`, `image-interpolation`, `annotation-flags` and `forbidden-annotation`.
+    pub fn validate(&self, profile: ValidationProfile) -> Result<Vec<Violation>, Error> {
+        let mut violations = Vec::new();
+        if self.is_encrypted()? {
+            violations.push(Violation::new(
+                "encryption",
+                "document is encrypted".to_owned(),
+                None,
+            ));
+        }
+        let catalog = self.catalog()?;
+        if let Some(names) = catalog.get_dict("Names")? {
+            if let Some(js) = names.get_dict("JavaScript")? {
+                violations.push(Violation::new(
+                    "javascript",
+                    "document has document level JavaScript".to_owned(),
+                    reference(&js)?,
+                ));
+            }
+            if let ValidationProfile::PdfA(level @ (PdfALevel::A1b | PdfALevel::A2b)) = profile {
+                if let Some(files) = names.get_dict("EmbeddedFiles")? {
+                    violations.push(Violation::new(
+                        "embedded-file",
+                        format!("PDF/A-{} restricts embedded files", level.part()),
+                        reference(&files)?,
+                    ));
+                }
+            }
+        }
+        if let ValidationProfile::PdfA(level) = profile {
+            self.validate_pdfa_catalog(level, &mut violations)?;
+        }
+        for (num, obj) in dict_objects(self)? {
+            validate_object(num, &obj, profile, &mut violations)?;
+        }
+        Ok(violations)
+    }
+
+    fn validate_pdfa_catalog(
+        &self,
+        level: PdfALevel,
+        violations: &mut Vec<Violation>,
+    ) -> Result<(), Error> {
+        let catalog = self.catalog()?;
+        let mut has_intent = false;
+        if let Some(intents) = catalog.get_dict("OutputIntents")? {
+            for i in 0..intents.len()? as i32 {
+                if let Some(intent) = intents.get_array(i)? {
+                    has_intent |= name_of(&intent, "S")?.as_deref() == Some(b"GTS_PDFA1")
+                        && intent.get_dict("DestOutputProfile")?.is_some();
+                }
+            }
+        }
+        if !has_intent {
+            violations.push(Violation::new(
+                "output-intent",
+                "no PDF/A output intent with an ICC profile".to_owned(),
+                None,
+            ));
+        }
+
+        let metadata = match catalog.get_dict("Metadata")? {
+            Some(metadata) if metadata.is_stream()? => Some(metadata.read_stream()?),
+            _ => None,
+        };
+        match metadata.as_deref().map(String::from_utf8_lossy) {
+            None => violations.push(Violation::new(
+                "metadata",
+                "no XMP metadata".to_owned(),
+                None,
+            )),
+            Some(xmp) => match declared_part(&xmp) {
+                Some(part) if part == level.part() => {}
+                Some(part) => violations.push(Violation::new(
+                    "metadata",
+                    format!("XMP metadata declares PDF/A-{}", part),
+                    None,
+                )),
+                None => violations.push(Violation::new(
+                    "metadata",
+                    "XMP metadata declares no PDF/A part".to_owned(),
+                    None,
+                )),
+            },
+        }
+
+        if self.trailer()?.get_dict("ID")?.is_none() {
+            violations.push(Violation::new(
+                "file-id",
+                "trailer has no file identifier".to_owned(),
+                None,
+            ));
+        }
+        Ok(())
+    }
+}
+
+fn validate_object(
+    num: i32,
+    obj: &PdfObject,
+    profile: ValidationProfile,
+    violations: &mut Vec<Violation>,
+) -> Result<(), Error> {
+    let mut report =
+        |rule, message: String| violations.push(Violation::new(rule, message, Some(num)));
+
+    if is_font(obj)? && !is_font_embedded(obj)? {
+        report(
+            "font-not-embedded",
+            format!("font {} is not embedded", font_name(obj)?),
+        );
+    }
+    for key in ["A", "OpenAction"] {
+        if let Some(action) = obj.get_dict(key)? {
+            if is_forbidden_action(&action, profile)? {
+                let kind = action_kind(&action)?.unwrap_or_default();
+                let kind = String::from_utf8_lossy(&kind);
+                if kind == "JavaScript" {
+                    report("javascript", "JavaScript action".to_owned());
+                } else {
+                    report("forbidden-action", format!("{} action", kind));
+                }
+            }
+        }
+    }
+    if let Some(actions) = obj.get_dict("AA")? {
+        let mut has_js = false;
+        for i in 0..actions.dict_len()? as i32 {
+            if let Some(action) = actions.get_dict_val(i)? {
+                has_js |= action_kind(&action)?.as_deref() == Some(b"JavaScript");
+            }
+        }
+        if has_js {
+            report("javascript", "JavaScript additional action".to_owned());
+        } else if profile != ValidationProfile::Basic {
+            report("forbidden-action", "additional actions".to_owned());
+        }
+    }
+
+    let ValidationProfile::PdfA(level) = profile else {
+        return Ok(());
+    };
+    if level == PdfALevel::A1b {
+        let mut transparent = uses_transparency(obj)?;
+        for gstate in graphics_states(obj)? {
+            transparent |= uses_transparency(&gstate)?;
+        }
+        if transparent {
+            report("transparency", "PDF/A-1 forbids transparency".to_owned());
+        }
+    }
+    if obj.is_stream()? && uses_lzw(obj)? {
+        report("lzw-filter", "stream is compressed with LZW".to_owned());
+    }
+    if obj
+        .get_dict("Interpolate")?
+        .is_some_and(|i| i.as_bool().unwrap_or(false))
+    {
+        report("image-interpolation", "image is interpolated".to_owned());
+    }
+    if is_printed_annotation(obj)? {
+        let subtype = name_of(obj, "Subtype")?.unwrap_or_default();
+        let forbidden: &[&[u8]] = match level {
+            PdfALevel::A1b => &[b"FileAttachment", b"Sound", b"Movie"],
+            _ => &[b"Sound", b"Movie", b"Screen", b"3D", b"RichMedia"],
+        };
+        if forbidden.contains(&&subtype[..]) {
+            report(
+                "forbidden-annotation",
+                format!("{} annotation", String::from_utf8_lossy(&subtype)),
+            );
+        }
+        let flags = match obj.get_dict("F")? {
+            Some(flags) => flags.as_int()?,
+            None => 0,
+        };
+        if flags & ANNOT_PRINT == 0 || flags & ANNOT_HIDING != 0 {
+            report(
+                "annotation-flags",
+                "annotation is not printed or is hidden".to_owned(),
+            );
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{declared_part, ValidationProfile};
+    use crate::pdf::{PdfALevel, PdfDocument};
+
+    #[test]
+    fn test_declared_part() {
+        assert_eq!(declared_part("<pdfaid:part>2</pdfaid:part>"), Some(2));
+        assert_eq!(
+            declared_part("<rdf:Description pdfaid:part=\"1\"/>"),
+            Some(1)
+        );
+        assert_eq!(declared_part("<x:xmpmeta/>"), None);
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut doc = PdfDocument::new();
+        doc.new_page((300.0, 400.0)).unwrap();
+        let font = doc
+            .new_object_from_str("<< /Type /Font /Subtype /TrueType /BaseFont /Arial >>")
+            .unwrap();
+        let font = doc.add_object(&font).unwrap();
+        let annot = doc
+            .new_object_from_str(
+                "<< /Type /Annot /Subtype /Link /Rect [0 0 10 10] \
+                 /A << /S /Launch /F (calc.exe) >> >>",
+            )
+            .unwrap();
+        let annot = doc.add_object(&annot).unwrap();
+        let mut annots = doc.new_array().unwrap();
+        annots.array_push(annot.clone()).unwrap();
+        doc.find_page(0)
+            .unwrap()
+            .dict_put("Annots", annots)
+            .unwrap();
+
+        let violations = doc.validate(ValidationProfile::Basic).unwrap();
+        let rules: Vec<_> = violations.iter().map(|v| (v.rule, v.object)).collect();
+        assert_eq!(
+            rules,
+            [
+                ("font-not-embedded", Some(font.as_indirect().unwrap())),
+                ("forbidden-action", Some(annot.as_indirect().unwrap())),
+            ]
+        );
+        assert_eq!(
+            violations[1].to_string(),
+            format!(
+                "forbidden-action: Launch action (object {})",
+                annot.as_indirect().unwrap()
+            )
+        );
+
+        let violations = doc
+            .validate(ValidationProfile::PdfA(PdfALevel::A2b))
+            .unwrap();
+        let rules: Vec<_> = violations.iter().map(|v| v.rule).collect();
+        for rule in [
+            "output-intent",
+            "metadata",
+            "font-not-embedded",
+            "forbidden-action",
+            "annotation-flags",
+        ] {
+            assert!(rules.contains(&rule), "{} missing from {:?}", rule, rules);
+        }
+    }
+}
@@ -0,0 +1,338 @@
+use crate::pdf::{PdfDocument, PdfObject};
+use crate::{Destination, DestinationKind, Error};
+
+/// What a viewer shows next to the pages when it opens the document.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PageMode {
+    /// Only the pages.
+    #[default]
+    UseNone,
+    /// The outline.
+    UseOutlines,
+    /// Page thumbnails.
+    UseThumbs,
+    /// The pages full screen, without menus or other windows.
+    FullScreen,
+    /// The optional content groups.
+    UseOC,
+    /// The attachments.
+    UseAttachments,
+}
+
+impl PageMode {
+    const ALL: [(PageMode, &'static str); 6] = [
+        (PageMode::UseNone, "UseNone"),
+        (PageMode::UseOutlines, "UseOutlines"),
+        (PageMode::UseThumbs, "UseThumbs"),
+        (PageMode::FullScreen, "FullScreen"),
+        (PageMode::UseOC, "UseOC"),
+        (PageMode::UseAttachments, "UseAttachments"),
+    ];
+}
+
+/// How a viewer lays out the pages when it opens the document.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PageLayout {
+    /// One page at a time.
+    #[default]
+    SinglePage,
+    /// The pages in a column.
+    OneColumn,
+    /// The pages in two columns, with odd pages on the left.
+    TwoColumnLeft,
+    /// The pages in two columns, with odd pages on the right.
+    TwoColumnRight,
+    /// Two pages at a time, with odd pages on the left.
+    TwoPageLeft,
+    /// Two pages at a time, with odd pages on the right.
+    TwoPageRight,
+}
+
+impl PageLayout {
+    const ALL: [(PageLayout, &'static str); 6] = [
+        (PageLayout::SinglePage, "SinglePage"),
+        (PageLayout::OneColumn, "OneColumn"),
+        (PageLayout::TwoColumnLeft, "TwoColumnLeft"),
+        (PageLayout::TwoColumnRight, "TwoColumnRight"),
+        (PageLayout::TwoPageLeft, "TwoPageLeft"),
+        (PageLayout::TwoPageRight, "TwoPageRight"),
+    ];
+}
+
+/// The paper handling preselected in print dialogs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Duplex {
+    Simplex,
+    /// Print on both sides, flipping on the short edge.
+    FlipShortEdge,
+    /// Print on both sides, flipping on the long edge.
+    FlipLongEdge,
+}
+
+impl Duplex {
+    const ALL: [(Duplex, &'static str); 3] = [
+        (Duplex::Simplex, "Simplex"),
+        (Duplex::FlipShortEdge, "DuplexFlipShortEdge"),
+        (Duplex::FlipLongEdge, "DuplexFlipLongEdge"),
+    ];
+}
+
+fn from_name<T: Copy>(all: &[(T, &'static str)], name: &[u8]) -> Option<T> {
+    all.iter()
+        .find(|(_, n)| n.as_bytes() == name)
+        .map(|&(value, _)| value)
+}
+
+fn to_name<T: Copy + PartialEq>(all: &[(T, &'static str)], value: T) -> &'static str {
+    all.iter().find(|(v, _)| *v == value).unwrap().1
+}
+
+/// How a viewer presents the document, from the `/ViewerPreferences` of the catalog.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ViewerPreferences {
+    pub hide_toolbar: bool,
+    pub hide_menubar: bool,
+    /// Hide scroll bars, navigation controls and the like, leaving only the pages.
+    pub hide_window_ui: bool,
+    /// Resize the window to fit the first page.
+    pub fit_window: bool,
+    pub center_window: bool,
+    /// Show the title of the document rather than its file name in the title bar.
+    pub display_doc_title: bool,
+    pub duplex: Option<Duplex>,
+    /// The pages preselected in print dialogs, as ranges of first and last page number.
+    pub print_page_ranges: Vec<(i32, i32)>,
+    /// The number of copies preselected in print dialogs.
+    pub num_copies: Option<i32>,
+}
+
+const FLAGS: [&str; 6] = [
+    "HideToolbar",
+    "HideMenubar",
+    "HideWindowUI",
+    "FitWindow",
+    "CenterWindow",
+    "DisplayDocTitle",
+];
+
+impl ViewerPreferences {
+    fn flags(&self) -> [bool; 6] {
+        [
+            self.hide_toolbar,
+            self.hide_menubar,
+            self.hide_window_ui,
+            self.fit_window,
+            self.center_window,
+            self.display_doc_title,
+        ]
+    }
+
+    fn flags_mut(&mut self) -> [&mut bool; 6] {
+        [
+            &mut self.hide_toolbar,
+            &mut self.hide_menubar,
+            &mut self.hide_window_ui,
+            &mut self.fit_window,
+            &mut self.center_window,
+            &mut self.display_doc_title,
+        ]
+    }
+}
+
+impl PdfDocument {
+    /// The natural language of the document's text, such as `en-US`.
+    pub fn language(&self) -> Result<Option<String>, Error> {
+        self.catalog()?.path_string("Lang")
+    }
+
+    pub fn set_language(&mut self, language: Option<&str>) -> Result<(), Error> {
+        let mut catalog = self.catalog()?;
+        match language {
+            Some(language) => catalog.dict_put("Lang", PdfObject::new_string(language)?),
+            None => catalog.dict_delete("Lang"),
+        }
+    }
+
+    pub fn page_mode(&self) -> Result<PageMode, Error> {
+        let name = self.catalog()?.path_name("PageMode")?.unwrap_or_default();
+        Ok(from_name(&PageMode::ALL, name.as_bytes()).unwrap_or_default())
+    }
+
+    pub fn set_page_mode(&mut self, mode: PageMode) -> Result<(), Error> {
+        self.catalog()?.dict_put(
+            "PageMode",
+            PdfObject::new_name(to_name(&PageMode::ALL, mode))?,
+        )
+    }
+
+    pub fn page_layout(&self) -> Result<PageLayout, Error> {
+        let name = self.catalog()?.path_name("PageLayout")?.unwrap_or_default();
+        Ok(from_name(&PageLayout::ALL, name.as_bytes()).unwrap_or_default())
+    }
+
+    pub fn set_page_layout(&mut self, layout: PageLayout) -> Result<(), Error> {
+        self.catalog()?.dict_put(
+            "PageLayout",
+            PdfObject::new_name(to_name(&PageLayout::ALL, layout))?,
+        )
+    }
+
+    /// The page and view the document opens at, `None` if it opens at the first page or runs
+    /// an action other than going to a destination in the document.
+    pub fn open_action(&self) -> Result<Option<(i32, DestinationKind)>, Error> {
+        let Some(mut dest) = self.catalog()?.get_dict("OpenAction")? else {
+            return Ok(None);
+        };
+        if dest.is_dict()? {
+            match dest.get_dict("D")? {
+                Some(d) if dest.path_name("S")?.as_deref() == Some("GoTo") => dest = d,
+                _ => return Ok(None),
+            }
+        }
+        if !dest.is_array()? {
+            return Ok(None);
+        }
+        let Some(page) = dest.get_array(0)? else {
+            return Ok(None);
+        };
+        let page_no = if page.is_int()? {
+            Some(page.as_int()?)
+        } else {
+            self.page_number(&page)?
+        };
+        match (page_no, DestinationKind::decode(&dest)?) {
+            (Some(page_no), Some(kind)) => Ok(Some((page_no, kind))),
+            _ => Ok(None),
+        }
+    }
+
+    /// Open the document at page `page_no` with the view `kind`.
+    pub fn set_open_action(&mut self, page_no: i32, kind: DestinationKind) -> Result<(), Error> {
+        let page = self.find_page(page_no)?;
+        let mut dest = self.new_array()?;
+        Destination::new(page, kind).encode_into(&mut dest)?;
+        self.catalog()?.dict_put("OpenAction", dest)
+    }
+
+    /// Remove the open action, so the document opens at the first page.
+    pub fn clear_open_action(&mut self) -> Result<(), Error> {
+        self.catalog()?.dict_delete("OpenAction")
+    }
+
+    pub fn viewer_preferences(&self) -> Result<ViewerPreferences, Error> {
+        let mut preferences = ViewerPreferences::default();
+        let Some(dict) = self.catalog()?.get_dict("ViewerPreferences")? else {
+            return Ok(preferences);
+        };
+        for (flag, key) in preferences.flags_mut().into_iter().zip(FLAGS) {
+            *flag = dict.path_bool(key)?.unwrap_or(false);
+        }
+        preferences.duplex = dict
+            .path_name("Duplex")?
+            .and_then(|name| from_name(&Duplex::ALL, name.as_bytes()));
+        if let Some(ranges) = dict.get_dict("PrintPageRange")? {
+            for i in (0..ranges.len()? as i32 - 1).step_by(2) {
+                if let (Some(first), Some(last)) = (ranges.get_array(i)?, ranges.get_array(i + 1)?)
+                {
+                    // Page numbers count from 1 in the file
+                    preferences
+                        .print_page_ranges
+                        .push((first.as_int()? - 1, last.as_int()? - 1));
+                }
+            }
+        }
+        preferences.num_copies = dict.path_int("NumCopies")?;
+        Ok(preferences)
+    }
+
+    /// Replace the viewer preferences this crate knows about, keeping any others.
+    pub fn set_viewer_preferences(&mut self, preferences: &ViewerPreferences) -> Result<(), Error> {
+        let mut catalog = self.catalog()?;
+        let mut dict = match catalog.get_dict("ViewerPreferences")? {
+            Some(dict) => dict,
+            None => {
+                catalog.dict_put("ViewerPreferences", self.new_dict()?)?;
+                catalog
+                    .get_dict("ViewerPreferences")?
+                    .ok_or(Error::UnexpectedNullPtr)?
+            }
+        };
+        for (flag, key) in preferences.flags().into_iter().zip(FLAGS) {
+            if flag {
+                dict.dict_put(key, PdfObject::new_bool(true))?;
+            } else {
+                dict.dict_delete(key)?;
+            }
+        }
+        match preferences.duplex {
+            Some(duplex) => dict.dict_put(
+                "Duplex",
+                PdfObject::new_name(to_name(&Duplex::ALL, duplex))?,
+            )?,
+            None => dict.dict_delete("Duplex")?,
+        }
+        if preferences.print_page_ranges.is_empty() {
+            dict.dict_delete("PrintPageRange")?;
+        } else {
+            let mut ranges = self.new_array()?;
+            for &(first, last) in &preferences.print_page_ranges {
+                ranges.array_push(PdfObject::new_int(first + 1)?)?;
+                ranges.array_push(PdfObject::new_int(last + 1)?)?;
+            }
+            dict.dict_put("PrintPageRange", ranges)?;
+        }
+        match preferences.num_copies {
+            Some(copies) => dict.dict_put("NumCopies", PdfObject::new_int(copies)?),
+            None => dict.dict_delete("NumCopies"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Duplex, PageLayout, PageMode, ViewerPreferences};
+    use crate::pdf::PdfDocument;
+    use crate::DestinationKind;
+
+    #[test]
+    fn test_presentation() {
+        let mut doc = PdfDocument::open("tests/files/dummy.pdf").unwrap();
+        assert_eq!(doc.page_mode().unwrap(), PageMode::UseNone);
+        assert_eq!(doc.open_action().unwrap(), None);
+
+        doc.set_language(Some("nl-NL")).unwrap();
+        doc.set_page_mode(PageMode::UseOutlines).unwrap();
+        doc.set_page_layout(PageLayout::TwoPageRight).unwrap();
+        let view = DestinationKind::XYZ {
+            left: Some(0.0),
+            top: Some(842.0),
+            zoom: None,
+        };
+        doc.set_open_action(0, view.clone()).unwrap();
+        let preferences = ViewerPreferences {
+            hide_toolbar: true,
+            display_doc_title: true,
+            duplex: Some(Duplex::FlipLongEdge),
+            print_page_ranges: vec![(0, 0)],
+            num_copies: Some(2),
+            ..Default::default()
+        };
+        doc.set_viewer_preferences(&preferences).unwrap();
+
+        let mut buf = Vec::new();
+        doc.write_to(&mut buf).unwrap();
+        let doc = PdfDocument::from_bytes(&buf).unwrap();
+        assert_eq!(doc.language().unwrap().as_deref(), Some("nl-NL"));
+        assert_eq!(doc.page_mode().unwrap(), PageMode::UseOutlines);
+        assert_eq!(doc.page_layout().unwrap(), PageLayout::TwoPageRight);
+        assert_eq!(doc.open_action().unwrap(), Some((0, view)));
+        assert_eq!(doc.viewer_preferences().unwrap(), preferences);
+        assert_eq!(
+            doc.resolve_path("Root/ViewerPreferences/PrintPageRange")
+                .unwrap()
+                .unwrap()
+                .to_string(),
+            "[1 1]"
+        );
+    }
+}
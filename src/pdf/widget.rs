@@ -0,0 +1,347 @@
+use mupdf_sys::*;
+
+use crate::pdf::{FieldFlags, PdfAnnotation, PdfObject, PdfPage};
+use crate::{context, Error};
+
+/// What a user does with a form widget, for viewers that drive forms themselves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(i32)]
+pub enum WidgetEvent {
+    /// The pointer moved onto the widget.
+    Enter = 0,
+    /// The pointer left the widget.
+    Exit = 1,
+    /// A mouse button was pressed on the widget.
+    Down = 2,
+    /// A mouse button was released on the widget, running its action.
+    Up = 3,
+    /// The widget got the keyboard focus.
+    Focus = 4,
+    /// The widget lost the keyboard focus.
+    Blur = 5,
+}
+
+/// A page being shown or hidden by a viewer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PageEvent {
+    Open,
+    Close,
+}
+
+impl PdfAnnotation {
+    /// Run the actions of a widget for `event`, returning whether they changed any widget of
+    /// its page so that it needs to be redrawn. The JavaScript of actions only runs when
+    /// enabled with [`PdfDocument::enable_js`](crate::pdf::PdfDocument::enable_js).
+    pub fn event(&mut self, event: WidgetEvent) -> Result<bool, Error> {
+        unsafe { ffi_try!(mupdf_pdf_annot_event(context(), self.inner, event as i32)) }
+    }
+}
+
+impl PdfPage {
+    /// Run the open or close actions of the page, returning whether they changed any of its
+    /// widgets.
+    pub fn event(&mut self, event: PageEvent) -> Result<bool, Error> {
+        unsafe {
+            ffi_try!(mupdf_pdf_page_event(
+                context(),
+                self.as_mut_ptr(),
+                event == PageEvent::Open
+            ))
+        }
+    }
+}
+
+/// An option of a combo box or list box.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChoiceOption {
+    /// The value the field takes when the option is chosen.
+    pub export_value: String,
+    /// The text shown for the option.
+    pub label: String,
+}
+
+impl ChoiceOption {
+    pub fn new(export_value: &str, label: &str) -> Self {
+        Self {
+            export_value: export_value.to_owned(),
+            label: label.to_owned(),
+        }
+    }
+}
+
+impl From<&str> for ChoiceOption {
+    fn from(value: &str) -> Self {
+        Self::new(value, value)
+    }
+}
+
+fn string_of(obj: &PdfObject) -> Result<String, Error> {
+    if obj.is_string()? {
+        Ok(obj.as_string()?.to_owned())
+    } else {
+        Ok(String::new())
+    }
+}
+
+impl PdfAnnotation {
+    /// The dictionary of the field a widget belongs to, which holds the value of the field.
+    /// Widgets of a field with a single widget are usually merged with their field.
+    pub(crate) fn field(&self) -> Result<PdfObject, Error> {
+        let obj = self.object();
+        match obj.get_dict("Parent")? {
+            Some(parent) if obj.get_dict("T")?.is_none() => Ok(parent),
+            _ => Ok(obj),
+        }
+    }
+
+    fn check_choice(&self) -> Result<(), Error> {
+        let is_choice = match self.object().get_dict_inheritable("FT")? {
+            Some(ft) => ft.as_name()? == b"Ch",
+            None => false,
+        };
+        if is_choice {
+            Ok(())
+        } else {
+            Err(Error::InvalidAnnotation("not a choice field".to_owned()))
+        }
+    }
+
+    /// Whether a choice field is a combo box, or else a list box.
+    pub fn is_combo_box(&self) -> Result<bool, Error> {
+        self.check_choice()?;
+        Ok(self.field_flags()?.contains(FieldFlags::COMBO))
+    }
+
+    /// Whether more than one option of a list box can be chosen.
+    pub fn is_multi_select(&self) -> Result<bool, Error> {
+        self.check_choice()?;
+        Ok(self.field_flags()?.contains(FieldFlags::MULTI_SELECT))
+    }
+
+    pub fn set_multi_select(&mut self, multi_select: bool) -> Result<(), Error> {
+        self.check_choice()?;
+        let mut flags = self.field_flags()?;
+        flags.set(FieldFlags::MULTI_SELECT, multi_select);
+        self.set_field_flags(flags)
+    }
+
+    /// The options of a combo box or list box, in the order they are shown.
+    pub fn choice_options(&self) -> Result<Vec<ChoiceOption>, Error> {
+        self.check_choice()?;
+        let Some(opt) = self.object().get_dict_inheritable("Opt")? else {
+            return Ok(Vec::new());
+        };
+        let mut options = Vec::with_capacity(opt.len()?);
+        for i in 0..opt.len()? as i32 {
+            let Some(option) = opt.get_array(i)? else {
+                continue;
+            };
+            if option.is_array()? {
+                let part = |i| match option.get_array(i)? {
+                    Some(s) => string_of(&s),
+                    None => Ok(String::new()),
+                };
+                options.push(ChoiceOption {
+                    export_value: part(0)?,
+                    label: part(1)?,
+                });
+            } else {
+                let value = string_of(&option)?;
+                options.push(ChoiceOption::new(&value, &value));
+            }
+        }
+        Ok(options)
+    }
+
+    /// Replace the options of a combo box or list box, regenerating its appearance. Options
+    /// whose label is their export value are stored as a single string.
+    pub fn set_choice_options(&mut self, options: &[ChoiceOption]) -> Result<(), Error> {
+        self.check_choice()?;
+        let mut field = self.field()?;
+        let doc = field.document().ok_or(Error::InvalidPdfDocument)?;
+        let mut opt = doc.new_array()?;
+        for option in options {
+            if option.export_value == option.label {
+                opt.array_push(PdfObject::new_string(&option.label)?)?;
+            } else {
+                let mut pair = doc.new_array()?;
+                pair.array_push(PdfObject::new_string(&option.export_value)?)?;
+                pair.array_push(PdfObject::new_string(&option.label)?)?;
+                opt.array_push(pair)?;
+            }
+        }
+        field.dict_put("Opt", opt)?;
+        // Indices of chosen options are no longer valid
+        field.dict_delete("I")?;
+        self.update()?;
+        Ok(())
+    }
+
+    /// The export values of the chosen options, or the text typed into an editable combo box.
+    pub fn choice_values(&self) -> Result<Vec<String>, Error> {
+        self.check_choice()?;
+        let Some(value) = self.object().get_dict_inheritable("V")? else {
+            return Ok(Vec::new());
+        };
+        if value.is_array()? {
+            (0..value.len()? as i32)
+                .filter_map(|i| value.get_array(i).transpose())
+                .map(|v| string_of(&v?))
+                .collect()
+        } else {
+            Ok(vec![string_of(&value)?])
+        }
+    }
+
+    /// Choose the options with the given export values, regenerating the appearance. More than
+    /// one value needs a multi-select list box, and values other than those of the options an
+    /// editable combo box.
+    pub fn set_choice_values(&mut self, values: &[&str]) -> Result<(), Error> {
+        let flags = self.field_flags()?;
+        if values.len() > 1 && !flags.contains(FieldFlags::MULTI_SELECT) {
+            return Err(Error::InvalidAnnotation(
+                "choice field allows a single value".to_owned(),
+            ));
+        }
+        let options = self.choice_options()?;
+        let mut indices = Vec::with_capacity(values.len());
+        for value in values {
+            match options.iter().position(|o| o.export_value == *value) {
+                Some(i) => indices.push(i as i32),
+                None if flags.contains(FieldFlags::COMBO | FieldFlags::EDIT) => {}
+                None => {
+                    return Err(Error::InvalidAnnotation(format!(
+                        "{} is not an option of the field",
+                        value
+                    )))
+                }
+            }
+        }
+        indices.sort_unstable();
+
+        let mut field = self.field()?;
+        let doc = field.document().ok_or(Error::InvalidPdfDocument)?;
+        match values {
+            [] => field.dict_delete("V")?,
+            [value] => field.dict_put("V", PdfObject::new_string(value)?)?,
+            values => {
+                let mut array = doc.new_array()?;
+                for value in values {
+                    array.array_push(PdfObject::new_string(value)?)?;
+                }
+                field.dict_put("V", array)?;
+            }
+        }
+        if flags.contains(FieldFlags::MULTI_SELECT) && !indices.is_empty() {
+            let mut array = doc.new_array()?;
+            for i in indices {
+                array.array_push(PdfObject::new_int(i)?)?;
+            }
+            field.dict_put("I", array)?;
+        } else {
+            field.dict_delete("I")?;
+        }
+        self.update()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ChoiceOption, PageEvent, WidgetEvent};
+    use crate::pdf::{PdfDocument, PdfObject, PdfPage};
+
+    /// Add fields with a single widget each to the first page, returning their dictionaries.
+    fn add_fields(doc: &mut PdfDocument, fields: &[&str]) -> Vec<PdfObject> {
+        let mut annots = doc.new_array().unwrap();
+        let mut objects = Vec::new();
+        for field in fields {
+            let obj = doc.new_object_from_str(field).unwrap();
+            let obj = doc.add_object(&obj).unwrap();
+            annots.array_push(obj.clone()).unwrap();
+            objects.push(obj);
+        }
+        let mut form = doc.new_dict().unwrap();
+        form.dict_put("Fields", annots.clone()).unwrap();
+        doc.find_page(0)
+            .unwrap()
+            .dict_put("Annots", annots)
+            .unwrap();
+        doc.catalog().unwrap().dict_put("AcroForm", form).unwrap();
+        objects
+    }
+
+    #[test]
+    fn test_widget_events() {
+        let mut doc = PdfDocument::open("tests/files/dummy.pdf").unwrap();
+        let fields = add_fields(
+            &mut doc,
+            &[
+                "<< /Type /Annot /Subtype /Widget /FT /Tx /T (name) /V (Jane) \
+                 /DA (/Helv 12 Tf 0 g) /Rect [100 700 300 720] >>",
+                "<< /Type /Annot /Subtype /Widget /FT /Btn /Ff 65536 /T (reset) \
+                 /Rect [100 650 200 680] /A << /S /ResetForm >> >>",
+            ],
+        );
+
+        let mut page = PdfPage::try_from(doc.load_page(0).unwrap()).unwrap();
+        assert_eq!(page.annotations().count(), 0);
+        let mut widgets: Vec<_> = page.widgets().collect();
+        assert_eq!(widgets.len(), 2);
+        page.event(PageEvent::Open).unwrap();
+
+        let button = &mut widgets[1];
+        for event in [WidgetEvent::Enter, WidgetEvent::Down, WidgetEvent::Focus] {
+            button.event(event).unwrap();
+        }
+        assert!(fields[0].get_dict("V").unwrap().is_some());
+        button.event(WidgetEvent::Up).unwrap();
+        assert!(fields[0].get_dict("V").unwrap().is_none());
+        button.event(WidgetEvent::Blur).unwrap();
+        button.event(WidgetEvent::Exit).unwrap();
+        page.event(PageEvent::Close).unwrap();
+    }
+
+    #[test]
+    fn test_choice_options() {
+        let mut doc = PdfDocument::open("tests/files/dummy.pdf").unwrap();
+        add_fields(
+            &mut doc,
+            &["<< /Type /Annot /Subtype /Widget /FT /Ch /T (fruit) \
+                 /Opt [(Apple) [(pear) (Pear)]] /V (Apple) \
+                 /DA (/Helv 12 Tf 0 g) /Rect [100 600 300 700] >>"],
+        );
+        let page = PdfPage::try_from(doc.load_page(0).unwrap()).unwrap();
+        let mut list = page.widgets().next().unwrap();
+
+        assert!(!list.is_combo_box().unwrap());
+        assert_eq!(
+            list.choice_options().unwrap(),
+            [
+                ChoiceOption::from("Apple"),
+                ChoiceOption::new("pear", "Pear")
+            ]
+        );
+        assert_eq!(list.choice_values().unwrap(), ["Apple"]);
+
+        let options = [
+            ChoiceOption::from("Apple"),
+            ChoiceOption::new("pear", "Pear"),
+            ChoiceOption::new("plum", "Plum"),
+        ];
+        list.set_choice_options(&options).unwrap();
+        assert_eq!(list.choice_options().unwrap(), options);
+
+        assert!(list.set_choice_values(&["plum", "Apple"]).is_err());
+        list.set_multi_select(true).unwrap();
+        list.set_choice_values(&["plum", "Apple"]).unwrap();
+        assert_eq!(list.choice_values().unwrap(), ["plum", "Apple"]);
+        assert_eq!(
+            list.object().get_dict("I").unwrap().unwrap().to_string(),
+            "[0 2]"
+        );
+        assert!(list.set_choice_values(&["kiwi"]).is_err());
+        list.set_choice_values(&[]).unwrap();
+        assert!(list.choice_values().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,631 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::io::Write as _;
+
+use crate::pdf::annotation_data::{
+    annotation_name, invalid, name_or_default, read_annotation, rect_numbers, AnnotationData,
+    PopupData,
+};
+use crate::pdf::{LineEndingStyle, PdfAnnotationType, PdfDocument, PdfObject};
+use crate::xml::{XmlDocument, XmlNode};
+use crate::{Error, Point, Quad, Rect};
+
+const XFDF_NAMESPACE: &str = "http://ns.adobe.com/xfdf/";
+
+/// Names of the annotation flags in XFDF, by bit.
+const FLAGS: [&str; 10] = [
+    "invisible",
+    "hidden",
+    "print",
+    "nozoom",
+    "norotate",
+    "noview",
+    "readonly",
+    "locked",
+    "togglenoview",
+    "lockedcontents",
+];
+
+/// An element of an XFDF document being written.
+#[derive(Debug, Default)]
+struct Element {
+    name: String,
+    attributes: Vec<(String, String)>,
+    children: Vec<Element>,
+    text: String,
+}
+
+impl Element {
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_owned(),
+            ..Default::default()
+        }
+    }
+
+    fn set(&mut self, name: &str, value: impl Into<String>) {
+        self.attributes.push((name.to_owned(), value.into()));
+    }
+
+    fn write(&self, out: &mut String) {
+        out.push('<');
+        out.push_str(&self.name);
+        for (name, value) in &self.attributes {
+            let _ = write!(out, " {}=\"{}\"", name, escape(value));
+        }
+        if self.children.is_empty() && self.text.is_empty() {
+            out.push_str("/>\n");
+            return;
+        }
+        out.push('>');
+        if !self.children.is_empty() {
+            out.push('\n');
+        }
+        out.push_str(&escape(&self.text));
+        for child in &self.children {
+            child.write(out);
+        }
+        let _ = writeln!(out, "</{}>", self.name);
+    }
+}
+
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '&' => out.push_str("&amp;"),
+            '"' => out.push_str("&quot;"),
+            '\r' => out.push_str("&#13;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+fn format_numbers(values: impl IntoIterator<Item = f32>) -> String {
+    values
+        .into_iter()
+        .map(|v| v.to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn parse_numbers(text: &str) -> Result<Vec<f32>, Error> {
+    text.split(|c: char| c == ',' || c == ';' || c.is_whitespace())
+        .filter(|s| !s.is_empty())
+        .map(|s| {
+            s.parse()
+                .map_err(|_| invalid(format!("invalid number {:?} in XFDF", s)))
+        })
+        .collect()
+}
+
+fn parse_rect(text: &str) -> Result<Rect, Error> {
+    match *parse_numbers(text)? {
+        [x0, y0, x1, y1] => Ok(Rect::new(x0.min(x1), y0.min(y1), x0.max(x1), y0.max(y1))),
+        _ => Err(invalid(format!("invalid rect {:?} in XFDF", text))),
+    }
+}
+
+fn parse_point(text: &str) -> Result<Point, Error> {
+    match *parse_numbers(text)? {
+        [x, y] => Ok(Point::new(x, y)),
+        _ => Err(invalid(format!("invalid point {:?} in XFDF", text))),
+    }
+}
+
+fn parse_points(text: &str) -> Result<Vec<Point>, Error> {
+    Ok(parse_numbers(text)?
+        .chunks_exact(2)
+        .map(|p| Point::new(p[0], p[1]))
+        .collect())
+}
+
+fn format_points(points: &[Point]) -> String {
+    points
+        .iter()
+        .map(|p| format!("{},{}", p.x, p.y))
+        .collect::<Vec<_>>()
+        .join(";")
+}
+
+/// XFDF colors are RGB, so gray and CMYK colors are converted.
+fn format_color(color: &[f32]) -> Option<String> {
+    let (r, g, b) = match *color {
+        [gray] => (gray, gray, gray),
+        [r, g, b] => (r, g, b),
+        [c, m, y, k] => (
+            (1.0 - c) * (1.0 - k),
+            (1.0 - m) * (1.0 - k),
+            (1.0 - y) * (1.0 - k),
+        ),
+        _ => return None,
+    };
+    let byte = |v: f32| (v.clamp(0.0, 1.0) * 255.0).round() as u8;
+    Some(format!("#{:02X}{:02X}{:02X}", byte(r), byte(g), byte(b)))
+}
+
+fn parse_color(text: &str) -> Result<Vec<f32>, Error> {
+    let hex = text.strip_prefix('#').unwrap_or(text);
+    let value = u32::from_str_radix(hex, 16)
+        .ok()
+        .filter(|_| hex.len() == 6)
+        .ok_or_else(|| invalid(format!("invalid color {:?} in XFDF", text)))?;
+    Ok([16, 8, 0]
+        .iter()
+        .map(|shift| ((value >> shift) & 0xff) as f32 / 255.0)
+        .collect())
+}
+
+fn format_flags(flags: i32) -> String {
+    FLAGS
+        .iter()
+        .enumerate()
+        .filter(|(bit, _)| flags & (1 << bit) != 0)
+        .map(|(_, name)| *name)
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn parse_flags(text: &str) -> i32 {
+    text.split(',')
+        .filter_map(|name| FLAGS.iter().position(|f| f == &name.trim()))
+        .fold(0, |flags, bit| flags | (1 << bit))
+}
+
+fn to_element(data: &AnnotationData) -> Option<Element> {
+    let mut element = Element::new(&data.subtype.pdf_name()?.to_lowercase());
+    element.set("page", data.page.to_string());
+    element.set("rect", format_numbers(rect_values(data.rect)));
+    element.set("name", data.name.clone());
+    element.set("flags", format_flags(data.flags));
+    let texts = [
+        ("title", &data.author),
+        ("subject", &data.subject),
+        ("date", &data.modified),
+        ("creationdate", &data.created),
+        ("icon", &data.icon),
+        ("inreplyto", &data.in_reply_to),
+        ("replyType", &data.reply_type),
+        ("state", &data.state),
+        ("statemodel", &data.state_model),
+    ];
+    for (name, value) in texts {
+        if let Some(value) = value {
+            let value = match name {
+                "replyType" if value == "Group" => "group",
+                "replyType" => "reply",
+                _ => value.as_str(),
+            };
+            element.set(name, value);
+        }
+    }
+    if let Some(color) = format_color(&data.color) {
+        element.set("color", color);
+    }
+    if let Some(color) = format_color(&data.interior_color) {
+        element.set("interior-color", color);
+    }
+    if let Some(opacity) = data.opacity {
+        element.set("opacity", opacity.to_string());
+    }
+    if let Some(width) = data.border_width {
+        element.set("width", width.to_string());
+    }
+    if let Some(q) = data.justification {
+        element.set(
+            "justification",
+            ["left", "centered", "right"][q.clamp(0, 2) as usize],
+        );
+    }
+    if !data.quad_points.is_empty() {
+        let coords = data
+            .quad_points
+            .iter()
+            .flat_map(|q| [q.ul, q.ur, q.ll, q.lr])
+            .flat_map(|p| [p.x, p.y]);
+        element.set("coords", format_numbers(coords));
+    }
+    if let Some([start, end]) = data.line {
+        element.set("start", format!("{},{}", start.x, start.y));
+        element.set("end", format!("{},{}", end.x, end.y));
+    }
+    if let Some([head, tail]) = data.line_endings {
+        element.set("head", head.pdf_name());
+        element.set("tail", tail.pdf_name());
+    }
+
+    if let Some(contents) = &data.contents {
+        let mut child = Element::new("contents");
+        child.text = contents.clone();
+        element.children.push(child);
+    }
+    if let Some(popup) = &data.popup {
+        let mut child = Element::new("popup");
+        child.set("page", data.page.to_string());
+        child.set("rect", format_numbers(rect_values(popup.rect)));
+        child.set("open", if popup.open { "yes" } else { "no" });
+        element.children.push(child);
+    }
+    if !data.ink_list.is_empty() {
+        let mut list = Element::new("inklist");
+        for stroke in &data.ink_list {
+            let mut gesture = Element::new("gesture");
+            gesture.text = format_points(stroke);
+            list.children.push(gesture);
+        }
+        element.children.push(list);
+    }
+    if !data.vertices.is_empty() {
+        let mut child = Element::new("vertices");
+        child.text = format_points(&data.vertices);
+        element.children.push(child);
+    }
+    if let Some(da) = &data.default_appearance {
+        let mut child = Element::new("defaultappearance");
+        child.text = da.clone();
+        element.children.push(child);
+    }
+    Some(element)
+}
+
+fn rect_values(rect: Rect) -> [f32; 4] {
+    [rect.x0, rect.y0, rect.x1, rect.y1]
+}
+
+fn from_element(element: XmlNode) -> Result<AnnotationData, Error> {
+    let element_name = element.name().unwrap_or_default();
+    let subtype = SUBTYPE_NAMES
+        .iter()
+        .copied()
+        .find(|t| t.pdf_name().map(str::to_lowercase).as_deref() == Some(element_name))
+        .ok_or_else(|| invalid(format!("unsupported XFDF annotation {}", element_name)))?;
+    let text = |name: &str| element.attribute(name).map(str::to_owned);
+    let page = element
+        .attribute("page")
+        .ok_or_else(|| invalid(format!("{} without page in XFDF", element_name)))?;
+    let mut data = AnnotationData {
+        page: page
+            .parse()
+            .map_err(|_| invalid(format!("invalid page {:?} in XFDF", page)))?,
+        subtype,
+        name: text("name").unwrap_or_default(),
+        rect: parse_rect(element.attribute("rect").unwrap_or_default())?,
+        contents: element.child("contents").map(|c| c.text()),
+        author: text("title"),
+        subject: text("subject"),
+        modified: text("date"),
+        created: text("creationdate"),
+        flags: element.attribute("flags").map_or(0, parse_flags),
+        icon: text("icon"),
+        in_reply_to: text("inreplyto"),
+        reply_type: element.attribute("replyType").map(|t| {
+            if t.eq_ignore_ascii_case("group") {
+                "Group".to_owned()
+            } else {
+                "R".to_owned()
+            }
+        }),
+        state: text("state"),
+        state_model: text("statemodel"),
+        default_appearance: element.child("defaultappearance").map(|c| c.text()),
+        ..Default::default()
+    };
+    if let Some(color) = element.attribute("color") {
+        data.color = parse_color(color)?;
+    }
+    if let Some(color) = element.attribute("interior-color") {
+        data.interior_color = parse_color(color)?;
+    }
+    if let Some(opacity) = element.attribute("opacity") {
+        data.opacity = Some(parse_numbers(opacity)?.first().copied().unwrap_or(1.0));
+    }
+    if let Some(width) = element.attribute("width") {
+        data.border_width = parse_numbers(width)?.first().copied();
+    }
+    data.justification = element.attribute("justification").map(|j| match j {
+        "centered" => 1,
+        "right" => 2,
+        _ => 0,
+    });
+    if let Some(coords) = element.attribute("coords") {
+        data.quad_points = parse_numbers(coords)?
+            .chunks_exact(8)
+            .map(|q| {
+                Quad::new(
+                    Point::new(q[0], q[1]),
+                    Point::new(q[2], q[3]),
+                    Point::new(q[4], q[5]),
+                    Point::new(q[6], q[7]),
+                )
+            })
+            .collect();
+    }
+    if let (Some(start), Some(end)) = (element.attribute("start"), element.attribute("end")) {
+        data.line = Some([parse_point(start)?, parse_point(end)?]);
+    }
+    let ending = |name| {
+        element
+            .attribute(name)
+            .and_then(LineEndingStyle::from_pdf_name)
+            .unwrap_or(LineEndingStyle::None)
+    };
+    if element.attribute("head").is_some() || element.attribute("tail").is_some() {
+        data.line_endings = Some([ending("head"), ending("tail")]);
+    }
+    if let Some(popup) = element.child("popup") {
+        data.popup = Some(PopupData {
+            rect: parse_rect(popup.attribute("rect").unwrap_or_default())?,
+            open: matches!(popup.attribute("open"), Some("yes" | "true")),
+        });
+    }
+    if let Some(list) = element.child("inklist") {
+        data.ink_list = list
+            .children()
+            .filter(|c| c.name() == Some("gesture"))
+            .map(|c| parse_points(&c.text()))
+            .collect::<Result<_, Error>>()?;
+    }
+    if let Some(vertices) = element.child("vertices") {
+        data.vertices = parse_points(&vertices.text())?;
+    }
+    Ok(data)
+}
+
+/// The subtypes with an XFDF element, which is their PDF name in lowercase.
+const SUBTYPE_NAMES: [PdfAnnotationType; 17] = [
+    PdfAnnotationType::Text,
+    PdfAnnotationType::FreeText,
+    PdfAnnotationType::Line,
+    PdfAnnotationType::Square,
+    PdfAnnotationType::Circle,
+    PdfAnnotationType::Polygon,
+    PdfAnnotationType::PloyLine,
+    PdfAnnotationType::Highlight,
+    PdfAnnotationType::Underline,
+    PdfAnnotationType::Squiggly,
+    PdfAnnotationType::StrikeOut,
+    PdfAnnotationType::Redact,
+    PdfAnnotationType::Stamp,
+    PdfAnnotationType::Caret,
+    PdfAnnotationType::Ink,
+    PdfAnnotationType::FileAttachment,
+    PdfAnnotationType::Sound,
+];
+
+/// Serialize annotations to an XFDF document.
+pub fn annotations_to_xfdf(annotations: &[AnnotationData]) -> String {
+    let mut annots = Element::new("annots");
+    annots.children = annotations
+        .iter()
+        .filter(|a| SUBTYPE_NAMES.contains(&a.subtype))
+        .filter_map(to_element)
+        .collect();
+    let mut root = Element::new("xfdf");
+    root.set("xmlns", XFDF_NAMESPACE);
+    root.set("xml:space", "preserve");
+    root.children.push(annots);
+
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    root.write(&mut out);
+    out
+}
+
+/// Parse the annotations of an XFDF document. Form field values in it are ignored.
+pub fn annotations_from_xfdf(xfdf: &str) -> Result<Vec<AnnotationData>, Error> {
+    let doc = XmlDocument::parse(xfdf.as_bytes())?;
+    let root = doc.root().ok_or_else(|| invalid("empty XFDF document"))?;
+    if root.name() != Some("xfdf") {
+        return Err(invalid(format!(
+            "expected xfdf element, found {}",
+            root.name().unwrap_or_default()
+        )));
+    }
+    match root.child("annots") {
+        Some(annots) => annots.children().map(from_element).collect(),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// Serialize annotations to an FDF file, the older format that stores annotations in PDF
+/// syntax.
+pub fn annotations_to_fdf(annotations: &[AnnotationData]) -> Result<Vec<u8>, Error> {
+    // The objects are made in a scratch document and printed from there
+    let mut doc = PdfDocument::new();
+    let mut objects = Vec::new();
+    let mut by_name = HashMap::new();
+    for data in annotations {
+        let Some(subtype) = data.subtype.pdf_name() else {
+            continue;
+        };
+        if !SUBTYPE_NAMES.contains(&data.subtype) {
+            continue;
+        }
+        let mut obj = doc.new_dict()?;
+        obj.dict_put("Type", PdfObject::new_name("Annot")?)?;
+        obj.dict_put("Subtype", PdfObject::new_name(subtype)?)?;
+        obj.dict_put("Page", PdfObject::new_int(data.page)?)?;
+        doc.write_annotation(&mut obj, data)?;
+        if let Some(popup_data) = &data.popup {
+            let mut popup = doc.new_dict()?;
+            popup.dict_put("Type", PdfObject::new_name("Annot")?)?;
+            popup.dict_put("Subtype", PdfObject::new_name("Popup")?)?;
+            popup.dict_put("Rect", doc.number_array(&rect_numbers(popup_data.rect))?)?;
+            popup.dict_put("Open", PdfObject::new_bool(popup_data.open))?;
+            obj.dict_put("Popup", popup)?;
+        }
+        let obj = doc.add_object(&obj)?;
+        if !data.name.is_empty() {
+            by_name.insert((data.page, data.name.as_str()), obj.try_clone()?);
+        }
+        objects.push((obj, data));
+    }
+    for (obj, data) in &mut objects {
+        let Some(target) = &data.in_reply_to else {
+            continue;
+        };
+        if let Some(parent) = by_name.get(&(data.page, target.as_str())) {
+            obj.dict_put("IRT", parent.try_clone()?)?;
+        }
+    }
+
+    let mut annots = doc.new_array()?;
+    for (obj, _) in &objects {
+        annots.array_push(obj.try_clone()?)?;
+    }
+    let mut fdf = doc.new_dict()?;
+    fdf.dict_put("Annots", annots)?;
+    let mut root = doc.new_dict()?;
+    root.dict_put("FDF", fdf)?;
+    let root = doc.add_object(&root)?;
+
+    let mut out = b"%FDF-1.2\n%\xe2\xe3\xcf\xd3\n".to_vec();
+    for obj in objects.iter().map(|(obj, _)| obj).chain([&root]) {
+        let value = obj.resolve()?.ok_or(Error::UnexpectedNullPtr)?;
+        writeln!(out, "{} 0 obj", obj.as_indirect()?)?;
+        writeln!(out, "{}", value.print(false, true)?)?;
+        writeln!(out, "endobj")?;
+    }
+    writeln!(out, "trailer")?;
+    writeln!(out, "<< /Root {} 0 R >>", root.as_indirect()?)?;
+    writeln!(out, "%%EOF")?;
+    Ok(out)
+}
+
+/// Parse the annotations of an FDF file. Form field values in it are ignored.
+pub fn annotations_from_fdf(fdf: &[u8]) -> Result<Vec<AnnotationData>, Error> {
+    // Apart from its header an FDF file is PDF syntax, which MuPDF reads given a PDF header
+    let mut data = fdf.to_vec();
+    if data.starts_with(b"%FDF-") {
+        data[1..4].copy_from_slice(b"PDF");
+    }
+    let doc = PdfDocument::from_bytes(&data)?;
+    let Some(annots) = doc.trailer()?.resolve_path("Root/FDF/Annots")? else {
+        return Ok(Vec::new());
+    };
+    let mut objects = Vec::new();
+    for i in 0..annots.len()? as i32 {
+        if let Some(obj) = annots.get_array(i)? {
+            let page = match obj.get_dict("Page")? {
+                Some(page) => page.as_int()?,
+                None => 0,
+            };
+            objects.push((obj, page));
+        }
+    }
+    // Replies refer to their parent through an indirect reference
+    let mut names = HashMap::new();
+    for (index, (obj, page)) in objects.iter().enumerate() {
+        if obj.is_indirect()? {
+            names.insert(obj.as_indirect()?, annotation_name(obj, *page, index)?);
+        }
+    }
+    let mut annotations = Vec::new();
+    for (index, (obj, page)) in objects.iter().enumerate() {
+        let subtype = name_or_default(obj, "Subtype")?;
+        if matches!(subtype.as_str(), "Link" | "Widget" | "Popup") {
+            continue;
+        }
+        let name = annotation_name(obj, *page, index)?;
+        annotations.push(read_annotation(obj, *page, name, &names)?);
+    }
+    Ok(annotations)
+}
+
+impl PdfDocument {
+    /// Export the annotations of all pages as XFDF, the XML format Acrobat and other viewers
+    /// exchange comments in, so reviews can be stored or merged without the PDF itself.
+    ///
+    /// Links, form fields and 3D or multimedia annotations aren't included.
+    pub fn export_xfdf(&self) -> Result<String, Error> {
+        Ok(annotations_to_xfdf(&self.annotations_data()?))
+    }
+
+    /// Import the annotations of an XFDF document, replacing existing annotations with the same
+    /// name on the same page, and return how many were imported.
+    pub fn import_xfdf(&mut self, xfdf: &str) -> Result<usize, Error> {
+        let annotations = annotations_from_xfdf(xfdf)?;
+        self.import_annotations(&annotations)
+    }
+
+    /// Export the annotations of all pages as FDF, as [`PdfDocument::export_xfdf`] does as XFDF.
+    pub fn export_fdf(&self) -> Result<Vec<u8>, Error> {
+        annotations_to_fdf(&self.annotations_data()?)
+    }
+
+    /// Import the annotations of an FDF file, as [`PdfDocument::import_xfdf`] does for XFDF.
+    pub fn import_fdf(&mut self, fdf: &[u8]) -> Result<usize, Error> {
+        let annotations = annotations_from_fdf(fdf)?;
+        self.import_annotations(&annotations)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{annotations_from_fdf, annotations_from_xfdf};
+    use crate::pdf::{PdfAnnotationType, PdfDocument};
+
+    #[test]
+    fn test_xfdf_round_trip() {
+        let xfdf = r##"<?xml version="1.0" encoding="UTF-8"?>
+<xfdf xmlns="http://ns.adobe.com/xfdf/" xml:space="preserve">
+  <annots>
+    <highlight page="0" rect="56,750,116,775" name="hl-1" title="Reviewer" color="#FFFF00"
+      flags="print" coords="56,775,116,775,56,750,116,750">
+      <contents>Check &amp; fix</contents>
+      <popup page="0" rect="200,700,400,800" open="yes"/>
+    </highlight>
+    <text page="0" rect="0,0,20,20" name="reply-1" inreplyto="hl-1" replyType="reply"
+      state="Accepted" statemodel="Review">
+      <contents>Done</contents>
+    </text>
+    <ink page="0" rect="100,100,200,200" name="ink-1" width="2">
+      <inklist><gesture>110,110;150,160;190,190</gesture></inklist>
+    </ink>
+  </annots>
+</xfdf>"##;
+        let parsed = annotations_from_xfdf(xfdf).unwrap();
+        assert_eq!(parsed.len(), 3);
+        assert_eq!(parsed[0].color, vec![1.0, 1.0, 0.0]);
+        assert_eq!(parsed[0].flags, 4);
+
+        let mut doc = PdfDocument::open("tests/files/dummy.pdf").unwrap();
+        assert_eq!(doc.import_xfdf(xfdf).unwrap(), 3);
+        let exported = doc.export_xfdf().unwrap();
+
+        let mut copy = PdfDocument::open("tests/files/dummy.pdf").unwrap();
+        copy.import_xfdf(&exported).unwrap();
+        let data = copy.annotations_data().unwrap();
+        assert_eq!(data.len(), 3);
+        let highlight = data.iter().find(|a| a.name == "hl-1").unwrap();
+        assert_eq!(highlight.subtype, PdfAnnotationType::Highlight);
+        assert_eq!(highlight.contents.as_deref(), Some("Check & fix"));
+        assert_eq!(highlight.author.as_deref(), Some("Reviewer"));
+        assert_eq!(highlight.quad_points, parsed[0].quad_points);
+        assert!(highlight.popup.as_ref().unwrap().open);
+        let reply = data.iter().find(|a| a.name == "reply-1").unwrap();
+        assert_eq!(reply.in_reply_to.as_deref(), Some("hl-1"));
+        assert_eq!(reply.state.as_deref(), Some("Accepted"));
+        let ink = data.iter().find(|a| a.name == "ink-1").unwrap();
+        assert_eq!(ink.ink_list, parsed[2].ink_list);
+
+        let fdf = copy.export_fdf().unwrap();
+        assert!(fdf.starts_with(b"%FDF-1.2"));
+        let from_fdf = annotations_from_fdf(&fdf).unwrap();
+        assert_eq!(from_fdf.len(), 3);
+        let highlight = from_fdf.iter().find(|a| a.name == "hl-1").unwrap();
+        assert_eq!(highlight.contents.as_deref(), Some("Check & fix"));
+        assert_eq!(highlight.quad_points, parsed[0].quad_points);
+        let mut copy = PdfDocument::open("tests/files/dummy.pdf").unwrap();
+        assert_eq!(copy.import_fdf(&fdf).unwrap(), 3);
+        let reply = copy
+            .annotations_data()
+            .unwrap()
+            .into_iter()
+            .find(|a| a.name == "reply-1")
+            .unwrap();
+        assert_eq!(reply.in_reply_to.as_deref(), Some("hl-1"));
+    }
+}
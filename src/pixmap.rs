@@ -1,10 +1,10 @@
 use std::ffi::CString;
 use std::io::{self, Write};
-use std::slice;
+use std::{mem, slice};
 
 use mupdf_sys::*;
 
-use crate::{context, Buffer, Colorspace, Error, IRect};
+use crate::{context, stats, Buffer, Colorspace, Error, IRect};
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 #[repr(C)]
@@ -28,9 +28,18 @@ pub struct Pixmap {
 
 impl Pixmap {
     pub(crate) unsafe fn from_raw(pixmap: *mut fz_pixmap) -> Self {
+        if !pixmap.is_null() {
+            stats::pixmap_acquire(unsafe { Self::byte_size(pixmap) });
+        }
         Self { inner: pixmap }
     }
 
+    unsafe fn byte_size(pixmap: *mut fz_pixmap) -> usize {
+        let stride = unsafe { fz_pixmap_stride(context(), pixmap) } as usize;
+        let height = unsafe { fz_pixmap_height(context(), pixmap) } as usize;
+        stride * height
+    }
+
     /// Create an empty pixmap of size and origin.
     ///
     /// Note that the image area is not initialized and will contain crap data
@@ -43,7 +52,7 @@ impl Pixmap {
         alpha: bool,
     ) -> Result<Self, Error> {
         unsafe { ffi_try!(mupdf_new_pixmap(context(), cs.inner, x, y, w, h, alpha)) }
-            .map(|inner| Self { inner })
+            .map(|inner| unsafe { Self::from_raw(inner) })
     }
 
     /// Create an empty pixmap of size and origin given by the rectangle.
@@ -57,6 +66,21 @@ impl Pixmap {
         Self::new(cs, x, y, w, h, alpha)
     }
 
+    /// A pixmap covering `rect` of this one and sharing its samples, so that several threads can
+    /// render into disjoint parts of the same pixmap.
+    ///
+    /// The caller must make sure the samples aren't accessed through both pixmaps at once.
+    pub(crate) fn sub_pixmap(&self, rect: IRect) -> Result<Self, Error> {
+        unsafe {
+            ffi_try!(mupdf_new_pixmap_from_pixmap(
+                context(),
+                self.inner,
+                rect.into()
+            ))
+        }
+        .map(|inner| unsafe { Self::from_raw(inner) })
+    }
+
     /// Create an empty pixmap of size with origin set to `(0, 0)`.
     ///
     /// Note that the image area is not initialized and will contain crap data
@@ -104,6 +128,7 @@ impl Pixmap {
     }
 
     /// Indicates whether the pixmap contains transparency information.
+    /// The color components are premultiplied by alpha, see [`Pixmap::unpremultiply`].
     pub fn alpha(&self) -> bool {
         unsafe { (*self.inner).alpha > 0 }
     }
@@ -117,7 +142,7 @@ impl Pixmap {
             if ptr.is_null() {
                 return None;
             }
-            Some(Colorspace::from_raw(ptr))
+            Some(Colorspace::from_raw_kept(ptr))
         }
     }
 
@@ -195,6 +220,18 @@ impl Pixmap {
         unsafe { ffi_try!(mupdf_invert_pixmap(context(), self.inner)) }
     }
 
+    /// Invert the part of the pixmap inside `rect`, in the same coordinates as [`Pixmap::rect`],
+    /// e.g. to highlight a selection.
+    pub fn invert_rect(&mut self, rect: IRect) -> Result<(), Error> {
+        unsafe { ffi_try!(mupdf_invert_pixmap_rect(context(), self.inner, rect.into())) }
+    }
+
+    /// Invert the luminance while keeping the hue, so that a page turns white on black but
+    /// colored text and images keep their colors, for a night mode.
+    pub fn invert_luminance(&mut self) -> Result<(), Error> {
+        unsafe { ffi_try!(mupdf_invert_pixmap_luminance(context(), self.inner)) }
+    }
+
     /// Apply a gamma factor to a pixmap, i.e. lighten or darken it.
     ///
     /// Pixmaps with no colorspace are ignored
@@ -206,7 +243,11 @@ impl Pixmap {
         unsafe { ffi_try!(mupdf_gamma_pixmap(context(), self.inner, gamma)) }
     }
 
-    /// Tint pixmap with color
+    /// Tint pixmap with color: black becomes `black` and white becomes `white`, both given as
+    /// `0xRRGGBB`, with the shades in between. For example black `0x5b4636` and white `0xf4ecd8`
+    /// give a sepia theme.
+    ///
+    /// Only gray and RGB pixmaps can be tinted.
     pub fn tint(&mut self, black: i32, white: i32) -> Result<(), Error> {
         unsafe { ffi_try!(mupdf_tint_pixmap(context(), self.inner, black, white)) }
     }
@@ -222,24 +263,268 @@ impl Pixmap {
         .map(|inner| unsafe { Buffer::from_raw(inner) })
     }
 
+    /// JPEG encoded samples, `quality` going from 0 to 100.
+    pub(crate) fn to_jpeg(&self, quality: u8) -> Result<Buffer, Error> {
+        unsafe {
+            ffi_try!(mupdf_pixmap_get_jpeg_data(
+                context(),
+                self.inner,
+                quality.min(100) as i32
+            ))
+        }
+        .map(|inner| unsafe { Buffer::from_raw(inner) })
+    }
+
     pub fn write_to<W: Write>(&self, w: &mut W, format: ImageFormat) -> Result<u64, Error> {
         let mut buf = self.get_image_data(format)?;
         Ok(io::copy(&mut buf, w)?)
     }
 
+    /// Divide the color components by alpha, for toolkits that want straight (unassociated)
+    /// alpha. MuPDF renders with color components premultiplied by alpha, and expects pixmaps
+    /// passed to it to be premultiplied, so call [`Pixmap::premultiply`] before handing the
+    /// pixmap back. Does nothing for pixmaps without alpha.
+    pub fn unpremultiply(&mut self) {
+        if !self.alpha() {
+            return;
+        }
+        let n = self.n() as usize;
+        for pixel in self.samples_mut().chunks_exact_mut(n) {
+            let a = pixel[n - 1] as u32;
+            if a == 0 || a == 255 {
+                continue;
+            }
+            for c in &mut pixel[..n - 1] {
+                *c = ((*c as u32 * 255 + a / 2) / a).min(255) as u8;
+            }
+        }
+    }
+
+    /// Multiply the color components by alpha, undoing [`Pixmap::unpremultiply`] or converting
+    /// samples with straight alpha written with [`Pixmap::samples_mut`].
+    pub fn premultiply(&mut self) {
+        if !self.alpha() {
+            return;
+        }
+        let n = self.n() as usize;
+        for pixel in self.samples_mut().chunks_exact_mut(n) {
+            let a = pixel[n - 1] as u32;
+            if a == 255 {
+                continue;
+            }
+            for c in &mut pixel[..n - 1] {
+                *c = ((*c as u32 * a + 127) / 255) as u8;
+            }
+        }
+    }
+
+    /// A new pixmap of `width` x `height` with the colorspace, separations, origin and
+    /// resolution of this one.
+    fn new_like(&self, width: usize, height: usize, alpha: bool) -> Result<Self, Error> {
+        unsafe {
+            ffi_try!(mupdf_new_pixmap_like(
+                context(),
+                self.inner,
+                width as i32,
+                height as i32,
+                alpha
+            ))
+        }
+        .map(|inner| unsafe { Self::from_raw(inner) })
+    }
+
+    /// A copy without alpha, composited over the solid `background` color, e.g. `[255, 255,
+    /// 255]` for white behind an RGB pixmap. This avoids the seams GUI toolkits leave when they
+    /// blend premultiplied pixmaps as if they had straight alpha.
+    ///
+    /// Fails if `background` doesn't have one value per color component, spots included.
+    pub fn composite_over(&self, background: &[u8]) -> Result<Self, Error> {
+        let n = self.n() as usize;
+        let alpha = self.alpha() as usize;
+        if background.len() != n - alpha {
+            return Err(Error::InvalidInput(format!(
+                "background has {} values for {} color components",
+                background.len(),
+                n - alpha
+            )));
+        }
+        if alpha == 0 {
+            return self.try_clone();
+        }
+        let mut dst = self.new_like(self.width() as usize, self.height() as usize, false)?;
+        let out = dst.samples_mut();
+        for (src, dst) in self
+            .samples()
+            .chunks_exact(n)
+            .zip(out.chunks_exact_mut(n - 1))
+        {
+            let a = src[n - 1] as u32;
+            for ((d, &c), &bg) in dst.iter_mut().zip(&src[..n - 1]).zip(background) {
+                *d = (c as u32 + (bg as u32 * (255 - a) + 127) / 255).min(255) as u8;
+            }
+        }
+        Ok(dst)
+    }
+
+    /// A copy scaled to `width` x `height` pixels, with the same origin.
+    pub fn scale(&self, width: f32, height: f32) -> Result<Self, Error> {
+        self.scale_with_clip(self.x() as f32, self.y() as f32, width, height, None)
+    }
+
+    /// A copy scaled to cover `width` x `height` pixels from `(x, y)`, keeping only the part
+    /// inside `clip` if given. Clipping to a viewport is much faster than scaling the whole
+    /// pixmap.
+    pub fn scale_with_clip(
+        &self,
+        x: f32,
+        y: f32,
+        width: f32,
+        height: f32,
+        clip: Option<IRect>,
+    ) -> Result<Self, Error> {
+        unsafe {
+            ffi_try!(mupdf_scale_pixmap(
+                context(),
+                self.inner,
+                x,
+                y,
+                width,
+                height,
+                clip.unwrap_or_default().into(),
+                clip.is_some()
+            ))
+        }
+        .map(|inner| unsafe { Self::from_raw(inner) })
+    }
+
+    /// A copy of the part of the pixmap inside `rect`, in the same coordinates as
+    /// [`Pixmap::rect`]. The result is empty if `rect` doesn't overlap the pixmap.
+    pub fn crop(&self, rect: IRect) -> Result<Self, Error> {
+        let bounds = self.rect();
+        let x0 = rect.x0.max(bounds.x0);
+        let y0 = rect.y0.max(bounds.y0);
+        let x1 = rect.x1.min(bounds.x1).max(x0);
+        let y1 = rect.y1.min(bounds.y1).max(y0);
+        // The sub pixmap shares the samples, cloning copies them
+        self.sub_pixmap(IRect::new(x0, y0, x1, y1))?.try_clone()
+    }
+
+    /// A copy rotated clockwise by `degrees`, keeping the origin. Fails unless `degrees` is a
+    /// multiple of 90.
+    pub fn rotate(&self, degrees: i32) -> Result<Self, Error> {
+        if degrees % 90 != 0 {
+            return Err(Error::InvalidInput(format!(
+                "cannot rotate by {} degrees, only by multiples of 90",
+                degrees
+            )));
+        }
+        let quarter_turns = degrees.rem_euclid(360) / 90;
+        let (w, h) = (self.width() as usize, self.height() as usize);
+        let (dw, dh) = if quarter_turns % 2 == 1 {
+            (h, w)
+        } else {
+            (w, h)
+        };
+        let mut dst = self.new_like(dw, dh, self.alpha())?;
+        let n = self.n() as usize;
+        let src = self.samples();
+        let out = dst.samples_mut();
+        for y in 0..h {
+            for x in 0..w {
+                let (dx, dy) = match quarter_turns {
+                    0 => (x, y),
+                    1 => (h - 1 - y, x),
+                    2 => (w - 1 - x, h - 1 - y),
+                    _ => (y, w - 1 - x),
+                };
+                let i = (y * w + x) * n;
+                let o = (dy * dw + dx) * n;
+                out[o..o + n].copy_from_slice(&src[i..i + n]);
+            }
+        }
+        Ok(dst)
+    }
+
+    /// The MD5 digest of the samples, the same as `mutool draw -5` prints. Resolution and origin
+    /// are not included.
+    pub fn digest(&self) -> [u8; 16] {
+        let mut digest = [0; 16];
+        unsafe { fz_md5_pixmap(context(), self.inner, digest.as_mut_ptr()) };
+        digest
+    }
+
+    /// [`Pixmap::digest`] as lowercase hex, for golden tests.
+    pub fn md5(&self) -> String {
+        hex(&self.digest())
+    }
+
+    /// A digest that only depends on how the pixmap looks: gray is expanded to RGB and alpha is
+    /// composited onto white, so the same rendering to pixmaps with or without alpha, or to gray
+    /// versus RGB for gray content, hashes the same. The width and height are included.
+    ///
+    /// White is no ink for CMYK and spot colorants, so compositing leaves those as they are.
+    pub fn normalized_digest(&self) -> [u8; 16] {
+        let n = self.n() as usize;
+        let alpha = self.alpha() as usize;
+        let colorants = n - alpha;
+        // Only the colorants of additive colorspaces, such as gray and RGB, gain white
+        let additive = match self.color_space() {
+            Some(cs) if !cs.is_subtractive() => cs.n() as usize,
+            _ => 0,
+        };
+        let mut md5: fz_md5 = unsafe { mem::zeroed() };
+        unsafe { fz_md5_init(&mut md5) };
+        let mut update =
+            |data: &[u8]| unsafe { fz_md5_update(&mut md5, data.as_ptr(), data.len()) };
+        update(&self.width().to_be_bytes());
+        update(&self.height().to_be_bytes());
+        let mut out = Vec::with_capacity(colorants.max(3));
+        for pixel in self.samples().chunks_exact(n) {
+            let a = if alpha == 1 { pixel[n - 1] } else { 255 };
+            out.clear();
+            // Samples are premultiplied, so compositing onto white adds the missing coverage
+            out.extend(pixel[..additive].iter().map(|&c| c.saturating_add(255 - a)));
+            out.extend_from_slice(&pixel[additive..colorants]);
+            if colorants == 0 {
+                out.push(255 - a);
+            }
+            if out.len() == 1 {
+                out.resize(3, out[0]);
+            }
+            update(&out);
+        }
+        let mut digest = [0; 16];
+        unsafe { fz_md5_final(&mut md5, digest.as_mut_ptr()) };
+        digest
+    }
+
+    /// [`Pixmap::normalized_digest`] as lowercase hex.
+    pub fn normalized_md5(&self) -> String {
+        hex(&self.normalized_digest())
+    }
+
     pub fn try_clone(&self) -> Result<Self, Error> {
-        unsafe { ffi_try!(mupdf_clone_pixmap(context(), self.inner)) }.map(|inner| Self { inner })
+        unsafe { ffi_try!(mupdf_clone_pixmap(context(), self.inner)) }
+            .map(|inner| unsafe { Self::from_raw(inner) })
     }
 }
 
+fn hex(digest: &[u8]) -> String {
+    digest.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
 impl Drop for Pixmap {
     fn drop(&mut self) {
         if !self.inner.is_null() {
+            stats::pixmap_release(unsafe { Self::byte_size(self.inner) });
             unsafe { fz_drop_pixmap(context(), self.inner) };
         }
     }
 }
 
+// Reference counting goes through the locks shared by all contexts
+unsafe impl Send for Pixmap {}
+
 impl Clone for Pixmap {
     fn clone(&self) -> Pixmap {
         self.try_clone().unwrap()
@@ -303,6 +588,25 @@ mod test {
         pixmap.tint(0, 255).unwrap();
     }
 
+    #[test]
+    fn test_pixmap_invert_rect() {
+        let cs = Colorspace::device_gray();
+        let mut pixmap = Pixmap::new(&cs, 10, 10, 4, 1, false).unwrap();
+        pixmap.clear_with(255).unwrap();
+        pixmap.invert_rect(IRect::new(11, 10, 13, 11)).unwrap();
+        assert_eq!(pixmap.samples(), [255, 0, 0, 255]);
+
+        let mut pixmap = Pixmap::new_with_w_h(&Colorspace::device_rgb(), 1, 1, false).unwrap();
+        pixmap.samples_mut().copy_from_slice(&[255, 255, 255]);
+        pixmap.invert_luminance().unwrap();
+        assert!(pixmap.samples().iter().all(|&v| v < 8));
+
+        let mut pixmap = Pixmap::new_with_w_h(&Colorspace::device_rgb(), 1, 1, false).unwrap();
+        pixmap.samples_mut().copy_from_slice(&[0, 0, 0]);
+        pixmap.tint(0x5b4636, 0xf4ecd8).unwrap();
+        assert_eq!(pixmap.samples(), [0x5b, 0x46, 0x36]);
+    }
+
     #[test]
     fn test_pixmap_pixels() {
         let cs = Colorspace::device_rgb();
@@ -319,4 +623,84 @@ mod test {
         let pixels = pixmap.pixels();
         assert!(pixels.is_some());
     }
+
+    #[test]
+    fn test_pixmap_digest() {
+        let rgb = Colorspace::device_rgb();
+        let mut white = Pixmap::new_with_w_h(&rgb, 10, 10, false).unwrap();
+        white.clear_with(255).unwrap();
+        assert_eq!(white.md5(), "1bd7fcea47685c0520aeb5cdf3a8cab6");
+
+        let mut transparent = Pixmap::new_with_w_h(&rgb, 10, 10, true).unwrap();
+        transparent.clear().unwrap();
+        assert_ne!(transparent.md5(), white.md5());
+        assert_eq!(transparent.normalized_md5(), white.normalized_md5());
+
+        let mut gray = Pixmap::new_with_w_h(&Colorspace::device_gray(), 10, 10, false).unwrap();
+        gray.clear_with(255).unwrap();
+        assert_eq!(gray.normalized_digest(), white.normalized_digest());
+
+        let mut black = white.clone();
+        black.clear_with(0).unwrap();
+        assert_ne!(black.normalized_digest(), white.normalized_digest());
+
+        // Transparent CMYK is no ink, the same as white CMYK
+        let cmyk = Colorspace::device_cmyk();
+        let mut paper = Pixmap::new_with_w_h(&cmyk, 10, 10, false).unwrap();
+        paper.clear().unwrap();
+        let mut transparent = Pixmap::new_with_w_h(&cmyk, 10, 10, true).unwrap();
+        transparent.clear().unwrap();
+        assert_eq!(transparent.normalized_digest(), paper.normalized_digest());
+        let mut ink = paper.clone();
+        ink.clear_with(255).unwrap();
+        assert_ne!(ink.normalized_digest(), paper.normalized_digest());
+    }
+
+    #[test]
+    fn test_pixmap_alpha() {
+        let cs = Colorspace::device_rgb();
+        let mut pixmap = Pixmap::new_with_w_h(&cs, 2, 1, true).unwrap();
+        // Half transparent red, and fully transparent
+        pixmap
+            .samples_mut()
+            .copy_from_slice(&[128, 0, 0, 128, 0, 0, 0, 0]);
+
+        let over_white = pixmap.composite_over(&[255, 255, 255]).unwrap();
+        assert!(!over_white.alpha());
+        assert_eq!(over_white.samples(), [255, 127, 127, 255, 255, 255]);
+        assert!(pixmap.composite_over(&[255]).is_err());
+
+        pixmap.unpremultiply();
+        assert_eq!(pixmap.samples(), [255, 0, 0, 128, 0, 0, 0, 0]);
+        pixmap.premultiply();
+        assert_eq!(pixmap.samples(), [128, 0, 0, 128, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_pixmap_transform() {
+        let cs = Colorspace::device_gray();
+        let mut pixmap = Pixmap::new_with_w_h(&cs, 3, 2, false).unwrap();
+        pixmap.samples_mut().copy_from_slice(&[1, 2, 3, 4, 5, 6]);
+
+        let rotated = pixmap.rotate(90).unwrap();
+        assert_eq!((rotated.width(), rotated.height()), (2, 3));
+        assert_eq!(rotated.samples(), [4, 1, 5, 2, 6, 3]);
+        assert_eq!(pixmap.rotate(180).unwrap().samples(), [6, 5, 4, 3, 2, 1]);
+        assert_eq!(pixmap.rotate(-90).unwrap().samples(), [3, 6, 2, 5, 1, 4]);
+        assert!(pixmap.rotate(45).is_err());
+
+        let cropped = pixmap.crop(IRect::new(1, 0, 10, 1)).unwrap();
+        assert_eq!(cropped.rect(), IRect::new(1, 0, 3, 1));
+        assert_eq!(cropped.samples(), [2, 3]);
+
+        let mut white = Pixmap::new_with_w_h(&cs, 100, 50, false).unwrap();
+        white.clear_with(255).unwrap();
+        let scaled = white.scale(40.0, 20.0).unwrap();
+        assert_eq!((scaled.width(), scaled.height()), (40, 20));
+        assert!(scaled.samples().iter().all(|&v| v == 255));
+        let clipped = white
+            .scale_with_clip(0.0, 0.0, 40.0, 20.0, Some(IRect::new(0, 0, 10, 10)))
+            .unwrap();
+        assert_eq!((clipped.width(), clipped.height()), (10, 10));
+    }
 }
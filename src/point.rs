@@ -9,6 +9,7 @@ use crate::{impl_ffi_traits, Matrix};
     Debug, Clone, Copy, PartialEq, zerocopy::FromBytes, zerocopy::IntoBytes, zerocopy::Immutable,
 )]
 #[repr(C)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub struct Point {
     pub x: f32,
     pub y: f32,
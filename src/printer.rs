@@ -0,0 +1,349 @@
+use std::ffi::{c_char, CStr, CString};
+use std::mem;
+use std::ptr;
+
+use mupdf_sys::*;
+
+use crate::{context, Buffer, Colorspace, Error, Pixmap};
+
+/// Options for PostScript output.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PsOptions {
+    resolution: i32,
+    gray: bool,
+}
+
+impl Default for PsOptions {
+    fn default() -> Self {
+        Self {
+            resolution: 300,
+            gray: false,
+        }
+    }
+}
+
+impl PsOptions {
+    pub fn resolution(&self) -> i32 {
+        self.resolution
+    }
+
+    pub fn set_resolution(&mut self, dpi: i32) -> &mut Self {
+        self.resolution = dpi;
+        self
+    }
+
+    pub fn gray(&self) -> bool {
+        self.gray
+    }
+
+    pub fn set_gray(&mut self, value: bool) -> &mut Self {
+        self.gray = value;
+        self
+    }
+
+    pub(crate) fn colorspace(&self) -> Colorspace {
+        if self.gray {
+            Colorspace::device_gray()
+        } else {
+            Colorspace::device_rgb()
+        }
+    }
+}
+
+/// Options for PCL output.
+///
+/// The printer features are taken from a preset, on top of which the job options such as
+/// duplexing and the paper tray can be set.
+#[derive(Clone, Copy)]
+pub struct PclOptions {
+    pub(crate) inner: fz_pcl_options,
+    resolution: i32,
+    mono: bool,
+}
+
+impl Default for PclOptions {
+    fn default() -> Self {
+        Self::from_preset("generic").unwrap()
+    }
+}
+
+impl PclOptions {
+    /// Initialize the options from one of the printer presets known to MuPDF, e.g. `generic`,
+    /// `ljet4`, `dj500`, `fs600`, `lj`, `lj2`, `lj3`, `lj3d`, `lj4`, `lj4pl`, `lj4d`, `lp2563b`
+    /// or `oce9050`.
+    pub fn from_preset(preset: &str) -> Result<Self, Error> {
+        let c_preset = CString::new(preset)?;
+        let mut inner: fz_pcl_options = unsafe { mem::zeroed() };
+        unsafe { ffi_try!(mupdf_pcl_preset(context(), &mut inner, c_preset.as_ptr())) }?;
+        Ok(Self {
+            inner,
+            resolution: 300,
+            mono: false,
+        })
+    }
+
+    pub fn resolution(&self) -> i32 {
+        self.resolution
+    }
+
+    pub fn set_resolution(&mut self, dpi: i32) -> &mut Self {
+        self.resolution = dpi;
+        self
+    }
+
+    /// Whether the page is written as a monochrome bitmap instead of a color raster.
+    pub fn mono(&self) -> bool {
+        self.mono
+    }
+
+    pub fn set_mono(&mut self, value: bool) -> &mut Self {
+        self.mono = value;
+        self
+    }
+
+    pub fn duplex(&self) -> Option<bool> {
+        (self.inner.duplex_set != 0).then_some(self.inner.duplex != 0)
+    }
+
+    pub fn set_duplex(&mut self, value: bool) -> &mut Self {
+        self.inner.duplex_set = 1;
+        self.inner.duplex = if value { 1 } else { 0 };
+        self
+    }
+
+    /// Whether duplex pages are flipped along the short edge.
+    pub fn tumble(&self) -> bool {
+        self.inner.tumble != 0
+    }
+
+    pub fn set_tumble(&mut self, value: bool) -> &mut Self {
+        self.inner.tumble = if value { 1 } else { 0 };
+        self
+    }
+
+    pub fn manual_feed(&self) -> Option<bool> {
+        (self.inner.manual_feed_set != 0).then_some(self.inner.manual_feed != 0)
+    }
+
+    pub fn set_manual_feed(&mut self, value: bool) -> &mut Self {
+        self.inner.manual_feed_set = 1;
+        self.inner.manual_feed = if value { 1 } else { 0 };
+        self
+    }
+
+    /// The input tray (PCL media position) to feed paper from.
+    pub fn media_position(&self) -> Option<i32> {
+        (self.inner.media_position_set != 0).then_some(self.inner.media_position)
+    }
+
+    pub fn set_media_position(&mut self, tray: i32) -> &mut Self {
+        self.inner.media_position_set = 1;
+        self.inner.media_position = tray;
+        self
+    }
+
+    pub fn orientation(&self) -> i32 {
+        self.inner.orientation
+    }
+
+    pub fn set_orientation(&mut self, value: i32) -> &mut Self {
+        self.inner.orientation = value;
+        self
+    }
+
+    pub(crate) fn colorspace(&self) -> Colorspace {
+        if self.mono {
+            Colorspace::device_gray()
+        } else {
+            Colorspace::device_rgb()
+        }
+    }
+}
+
+/// Options for PWG raster output, as consumed by CUPS and IPP Everywhere printers.
+#[derive(Clone, Copy)]
+pub struct PwgOptions {
+    pub(crate) inner: fz_pwg_options,
+    resolution: i32,
+    gray: bool,
+}
+
+impl Default for PwgOptions {
+    fn default() -> Self {
+        Self {
+            inner: unsafe { mem::zeroed() },
+            resolution: 300,
+            gray: false,
+        }
+    }
+}
+
+fn copy_c_str(dst: &mut [c_char], value: &str) -> Result<(), Error> {
+    let c_value = CString::new(value)?;
+    let bytes = c_value.as_bytes_with_nul();
+    let len = bytes.len().min(dst.len());
+    unsafe {
+        ptr::copy_nonoverlapping(c_value.as_ptr(), dst.as_mut_ptr(), len);
+    }
+    // Always keep the field null-terminated, even if the value got truncated
+    if let Some(last) = dst.last_mut() {
+        *last = 0;
+    }
+    Ok(())
+}
+
+impl PwgOptions {
+    pub fn resolution(&self) -> i32 {
+        self.resolution
+    }
+
+    pub fn set_resolution(&mut self, dpi: i32) -> &mut Self {
+        self.resolution = dpi;
+        self
+    }
+
+    pub fn gray(&self) -> bool {
+        self.gray
+    }
+
+    pub fn set_gray(&mut self, value: bool) -> &mut Self {
+        self.gray = value;
+        self
+    }
+
+    pub fn duplex(&self) -> bool {
+        self.inner.duplex != 0
+    }
+
+    pub fn set_duplex(&mut self, value: bool) -> &mut Self {
+        self.inner.duplex = if value { 1 } else { 0 };
+        self
+    }
+
+    pub fn tumble(&self) -> bool {
+        self.inner.tumble != 0
+    }
+
+    pub fn set_tumble(&mut self, value: bool) -> &mut Self {
+        self.inner.tumble = if value { 1 } else { 0 };
+        self
+    }
+
+    pub fn manual_feed(&self) -> bool {
+        self.inner.manual_feed != 0
+    }
+
+    pub fn set_manual_feed(&mut self, value: bool) -> &mut Self {
+        self.inner.manual_feed = if value { 1 } else { 0 };
+        self
+    }
+
+    /// The input tray to feed paper from.
+    pub fn media_position(&self) -> u32 {
+        self.inner.media_position
+    }
+
+    pub fn set_media_position(&mut self, tray: u32) -> &mut Self {
+        self.inner.media_position = tray;
+        self
+    }
+
+    pub fn num_copies(&self) -> u32 {
+        self.inner.num_copies
+    }
+
+    pub fn set_num_copies(&mut self, copies: u32) -> &mut Self {
+        self.inner.num_copies = copies;
+        self
+    }
+
+    pub fn output_face_up(&self) -> bool {
+        self.inner.output_face_up != 0
+    }
+
+    pub fn set_output_face_up(&mut self, value: bool) -> &mut Self {
+        self.inner.output_face_up = if value { 1 } else { 0 };
+        self
+    }
+
+    pub fn media_type(&self) -> &str {
+        let c_str = unsafe { CStr::from_ptr(self.inner.media_type.as_ptr()) };
+        c_str.to_str().unwrap_or_default()
+    }
+
+    /// Set the media type, e.g. `stationery` or `photographic`. Values longer than 63 bytes are
+    /// truncated.
+    pub fn set_media_type(&mut self, value: &str) -> Result<&mut Self, Error> {
+        copy_c_str(&mut self.inner.media_type, value)?;
+        Ok(self)
+    }
+
+    pub fn media_color(&self) -> &str {
+        let c_str = unsafe { CStr::from_ptr(self.inner.media_color.as_ptr()) };
+        c_str.to_str().unwrap_or_default()
+    }
+
+    pub fn set_media_color(&mut self, value: &str) -> Result<&mut Self, Error> {
+        copy_c_str(&mut self.inner.media_color, value)?;
+        Ok(self)
+    }
+
+    pub(crate) fn colorspace(&self) -> Colorspace {
+        if self.gray {
+            Colorspace::device_gray()
+        } else {
+            Colorspace::device_rgb()
+        }
+    }
+}
+
+pub(crate) fn pixmap_to_ps(pixmap: &Pixmap) -> Result<Buffer, Error> {
+    unsafe { ffi_try!(mupdf_pixmap_to_ps(context(), pixmap.inner)) }
+        .map(|inner| unsafe { Buffer::from_raw(inner) })
+}
+
+pub(crate) fn pixmap_to_pcl(pixmap: &Pixmap, options: &PclOptions) -> Result<Buffer, Error> {
+    // `fz_write_pixmap_as_pcl` updates the page counter, so hand it a copy
+    let mut opts = options.inner;
+    unsafe {
+        ffi_try!(mupdf_pixmap_to_pcl(
+            context(),
+            pixmap.inner,
+            &mut opts,
+            options.mono
+        ))
+    }
+    .map(|inner| unsafe { Buffer::from_raw(inner) })
+}
+
+pub(crate) fn pixmap_to_pwg(pixmap: &Pixmap, options: &PwgOptions) -> Result<Buffer, Error> {
+    unsafe { ffi_try!(mupdf_pixmap_to_pwg(context(), pixmap.inner, &options.inner)) }
+        .map(|inner| unsafe { Buffer::from_raw(inner) })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{PclOptions, PwgOptions};
+
+    #[test]
+    fn test_pcl_options() {
+        let mut options = PclOptions::default();
+        assert_eq!(options.duplex(), None);
+        options.set_duplex(true).set_media_position(2);
+        assert_eq!(options.duplex(), Some(true));
+        assert_eq!(options.media_position(), Some(2));
+
+        assert!(PclOptions::from_preset("ljet4").is_ok());
+        assert!(PclOptions::from_preset("no-such-printer").is_err());
+    }
+
+    #[test]
+    fn test_pwg_options() {
+        let mut options = PwgOptions::default();
+        options.set_media_type("stationery").unwrap();
+        assert_eq!(options.media_type(), "stationery");
+        options.set_duplex(true).set_num_copies(2);
+        assert!(options.duplex());
+        assert_eq!(options.num_copies(), 2);
+    }
+}
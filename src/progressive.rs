@@ -0,0 +1,223 @@
+use std::ffi::CString;
+use std::ops::Range;
+
+use mupdf_sys::*;
+
+use crate::{context, Cookie, Device, DisplayList, Document, Error, ErrorKind, Matrix, Page};
+
+/// Granularity at which received data is tracked.
+const BLOCK_SIZE: usize = 4096;
+
+/// A document opened before all of its data has arrived, e.g. while a linearized PDF is being
+/// downloaded.
+///
+/// Data is supplied with [`ProgressiveDocument::write`] as it arrives, in any order. Operations
+/// needing data that isn't there yet return `Ok(None)`, and [`ProgressiveDocument::needed_range`]
+/// then tells which bytes to fetch next, e.g. with an HTTP range request.
+#[derive(Debug)]
+pub struct ProgressiveDocument {
+    stream: *mut fz_stream,
+    magic: CString,
+    len: usize,
+    // Received byte ranges, sorted and merged
+    received: Vec<Range<usize>>,
+    doc: Option<Document>,
+}
+
+fn try_later<T>(res: Result<T, Error>) -> Result<Option<T>, Error> {
+    match res {
+        Ok(value) => Ok(Some(value)),
+        Err(err) if err.kind() == Some(ErrorKind::TryLater) => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+impl ProgressiveDocument {
+    /// Prepare to receive a file of `len` bytes, `magic` being its file name or MIME type.
+    pub fn new(len: usize, magic: &str) -> Result<Self, Error> {
+        let magic = CString::new(magic)?;
+        let stream =
+            unsafe { ffi_try!(mupdf_open_progressive_stream(context(), len, BLOCK_SIZE)) }?;
+        Ok(Self {
+            stream,
+            magic,
+            len,
+            received: Vec::new(),
+            doc: None,
+        })
+    }
+
+    /// Supply `data` found at `offset` in the file. Data reaching past the length of the file
+    /// is rejected with [`Error::InvalidInput`].
+    pub fn write(&mut self, offset: usize, data: &[u8]) -> Result<(), Error> {
+        let end = match offset.checked_add(data.len()) {
+            Some(end) if end <= self.len => end,
+            _ => {
+                return Err(Error::InvalidInput(format!(
+                    "{} bytes at {} are past the end of the {} byte file",
+                    data.len(),
+                    offset,
+                    self.len
+                )))
+            }
+        };
+        let start = self.received.partition_point(|r| r.end < offset);
+        let overlapping = self.received[start..]
+            .iter()
+            .take_while(|r| r.start <= end)
+            .count();
+        let merged = self.received[start..start + overlapping]
+            .iter()
+            .fold(offset..end, |m, r| m.start.min(r.start)..m.end.max(r.end));
+
+        // Blocks entirely within the merged range, the last one possibly being short
+        let first_block = merged.start.div_ceil(BLOCK_SIZE);
+        let end_block = if merged.end >= self.len {
+            self.len / BLOCK_SIZE + 1
+        } else {
+            merged.end / BLOCK_SIZE
+        };
+        unsafe {
+            ffi_try!(mupdf_progressive_stream_write(
+                context(),
+                self.stream,
+                offset,
+                data.as_ptr(),
+                data.len(),
+                first_block,
+                end_block
+            ))
+        }?;
+        self.received.splice(start..start + overlapping, [merged]);
+        Ok(())
+    }
+
+    /// Number of bytes received so far.
+    pub fn bytes_received(&self) -> usize {
+        self.received.iter().map(|r| r.len()).sum()
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.len == 0 || self.received.first() == Some(&(0..self.len))
+    }
+
+    /// The bytes to fetch for the operation that last returned `Ok(None)` to make progress, up
+    /// to the next data already received.
+    pub fn needed_range(&self) -> Option<Range<usize>> {
+        let missing = unsafe { mupdf_progressive_stream_missing(self.stream) };
+        let start = usize::try_from(missing).ok()?;
+        let end = self
+            .received
+            .iter()
+            .map(|r| r.start)
+            .find(|&s| s > start)
+            .unwrap_or(self.len);
+        Some(start..end)
+    }
+
+    /// The document, once enough data arrived to open it.
+    pub fn document(&mut self) -> Result<Option<&Document>, Error> {
+        if self.doc.is_none() {
+            let res = unsafe {
+                ffi_try!(mupdf_open_document_with_stream(
+                    context(),
+                    self.magic.as_ptr(),
+                    self.stream
+                ))
+            }
+            .map(|inner| unsafe { Document::from_raw(inner) });
+            self.doc = try_later(res)?;
+        }
+        Ok(self.doc.as_ref())
+    }
+
+    /// Number of pages, which a linearized PDF tells before the rest of the file arrives.
+    pub fn page_count(&mut self) -> Result<Option<i32>, Error> {
+        match self.document()? {
+            Some(doc) => try_later(doc.page_count()),
+            None => Ok(None),
+        }
+    }
+
+    /// Load a page, once the data it needs has arrived.
+    ///
+    /// Rendering a page loaded before the whole file arrived may still miss fonts or images, in
+    /// which case [`Cookie::incomplete`] is set and the page should be rendered again later.
+    pub fn load_page(&mut self, page_no: i32) -> Result<Option<Page>, Error> {
+        match self.document()? {
+            Some(doc) => try_later(doc.load_page(page_no)),
+            None => Ok(None),
+        }
+    }
+
+    /// Record a page into a display list as far as the data received allows, returning whether
+    /// it is complete.
+    pub fn display_list(&mut self, page_no: i32) -> Result<Option<(DisplayList, bool)>, Error> {
+        let Some(page) = self.load_page(page_no)? else {
+            return Ok(None);
+        };
+        let cookie = Cookie::new()?;
+        let list = DisplayList::new(page.bounds()?)?;
+        let finished = {
+            let device = Device::from_display_list(&list)?;
+            try_later(page.run_with_cookie(&device, &Matrix::IDENTITY, &cookie))?.is_some()
+        };
+        Ok(Some((list, finished && !cookie.incomplete())))
+    }
+}
+
+impl Drop for ProgressiveDocument {
+    fn drop(&mut self) {
+        // The document keeps its own reference to the stream
+        self.doc = None;
+        unsafe { fz_drop_stream(context(), self.stream) };
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ProgressiveDocument;
+
+    #[test]
+    fn test_progressive_document() {
+        let data = std::fs::read("tests/files/dummy.pdf").unwrap();
+        let mut doc = ProgressiveDocument::new(data.len(), "application/pdf").unwrap();
+        let mut steps = 0;
+        let page = loop {
+            if let Some(page) = doc.load_page(0).unwrap() {
+                break page;
+            }
+            let range = doc.needed_range().unwrap_or(0..data.len());
+            doc.write(range.start, &data[range]).unwrap();
+            steps += 1;
+            assert!(steps < 100);
+        };
+        assert_eq!(page.bounds().unwrap().x1, 595.0);
+    }
+
+    #[test]
+    fn test_progressive_document_write_out_of_bounds() {
+        use crate::Error;
+
+        let data = std::fs::read("tests/files/dummy.pdf").unwrap();
+        let len = data.len();
+        let mut doc = ProgressiveDocument::new(len, "application/pdf").unwrap();
+        assert!(matches!(
+            doc.write(len - 10, &data[..20]),
+            Err(Error::InvalidInput(_))
+        ));
+        assert!(matches!(
+            doc.write(usize::MAX, &data[..1]),
+            Err(Error::InvalidInput(_))
+        ));
+        assert_eq!(doc.bytes_received(), 0);
+
+        doc.write(len / 2, &data[len / 2..]).unwrap();
+        assert_eq!(doc.bytes_received(), len - len / 2);
+        assert!(!doc.is_complete());
+        doc.write(0, &data[..len / 2 + 1]).unwrap();
+        assert_eq!(doc.bytes_received(), len);
+        assert!(doc.is_complete());
+        assert!(doc.load_page(0).unwrap().is_some());
+    }
+}
@@ -1,6 +1,9 @@
-use mupdf_sys::fz_quad;
+use mupdf_sys::{
+    fz_is_point_inside_quad, fz_is_quad_inside_quad, fz_is_quad_intersecting_quad, fz_quad,
+    fz_quad_from_rect, fz_transform_quad,
+};
 
-use crate::{impl_ffi_traits, Point};
+use crate::{impl_ffi_traits, Matrix, Point, Rect};
 
 /// A representation for a region defined by 4 points
 /// This is marked `repr(c)` to ensure compatibility with the FFI analogue, [`fz_quad`], so that
@@ -9,6 +12,7 @@ use crate::{impl_ffi_traits, Point};
     Debug, Clone, PartialEq, zerocopy::FromBytes, zerocopy::IntoBytes, zerocopy::Immutable,
 )]
 #[repr(C)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub struct Quad {
     pub ul: Point,
     pub ur: Point,
@@ -20,6 +24,53 @@ impl Quad {
     pub fn new(ul: Point, ur: Point, ll: Point, lr: Point) -> Self {
         Self { ul, ur, ll, lr }
     }
+
+    pub fn from_rect(rect: Rect) -> Self {
+        unsafe { fz_quad_from_rect(rect.into()) }.into()
+    }
+
+    pub fn transform(&self, matrix: &Matrix) -> Self {
+        unsafe { fz_transform_quad(self.clone().into(), matrix.into()) }.into()
+    }
+
+    /// Whether `point` lies inside the quad, which may be rotated or skewed.
+    pub fn contains_point(&self, point: Point) -> bool {
+        unsafe { fz_is_point_inside_quad(point.into(), self.clone().into()) != 0 }
+    }
+
+    /// Whether `other` lies entirely inside the quad.
+    pub fn contains_quad(&self, other: &Quad) -> bool {
+        unsafe { fz_is_quad_inside_quad(other.clone().into(), self.clone().into()) != 0 }
+    }
+
+    pub fn intersects(&self, other: &Quad) -> bool {
+        unsafe { fz_is_quad_intersecting_quad(self.clone().into(), other.clone().into()) != 0 }
+    }
+}
+
+impl From<Rect> for Quad {
+    fn from(rect: Rect) -> Self {
+        Self::from_rect(rect)
+    }
 }
 
 impl_ffi_traits!(Quad, fz_quad);
+
+#[cfg(test)]
+mod test {
+    use super::Quad;
+    use crate::{Matrix, Point, Rect};
+
+    #[test]
+    fn test_quad_containment() {
+        let quad =
+            Quad::from_rect(Rect::new(0.0, 0.0, 10.0, 10.0)).transform(&Matrix::new_rotate(45.0));
+        assert!(quad.contains_point(Point::new(0.0, 7.0)));
+        assert!(!quad.contains_point(Point::new(5.0, 1.0)));
+        let inner = Quad::from_rect(Rect::new(-1.0, 5.0, 1.0, 7.0));
+        assert!(quad.contains_quad(&inner));
+        assert!(!inner.contains_quad(&quad));
+        assert!(quad.intersects(&Quad::from_rect(Rect::new(-20.0, 5.0, 20.0, 6.0))));
+        assert!(!quad.intersects(&Quad::from_rect(Rect::new(20.0, 0.0, 30.0, 10.0))));
+    }
+}
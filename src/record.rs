@@ -0,0 +1,587 @@
+use std::num::NonZero;
+
+use crate::{
+    BlendMode, ColorParams, Colorspace, Device, DisplayList, Error, Function, Image, LineCap,
+    LineJoin, Matrix, NativeDevice, Page, Path, PathWalker, Point, Rect, Shade, StrokeState, Text,
+};
+
+/// A piece of a recorded path.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub enum PathSegment {
+    MoveTo(Point),
+    LineTo(Point),
+    /// A cubic Bézier curve through two control points to an end point.
+    CurveTo(Point, Point, Point),
+    Close,
+}
+
+/// A color and the name of its colorspace.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct DrawColor {
+    pub colorspace: String,
+    pub components: Vec<f32>,
+    pub alpha: f32,
+}
+
+/// The stroke parameters of a recorded path or text.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct StrokeStyle {
+    pub line_width: f32,
+    pub start_cap: LineCap,
+    pub dash_cap: LineCap,
+    pub end_cap: LineCap,
+    pub line_join: LineJoin,
+    pub miter_limit: f32,
+    pub dash_phase: f32,
+    pub dashes: Vec<f32>,
+}
+
+/// A glyph of a recorded text span.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct RecordedGlyph {
+    pub gid: i32,
+    /// The character the glyph stands for, -1 for the glyphs after the first of a ligature.
+    pub ucs: i32,
+    pub x: f32,
+    pub y: f32,
+}
+
+/// A run of glyphs in one font.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct RecordedTextSpan {
+    pub font_name: String,
+    /// The text rendering matrix without the translation to each glyph.
+    pub trm: Matrix,
+    pub glyphs: Vec<RecordedGlyph>,
+}
+
+/// The properties of a recorded image, without its samples.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct RecordedImage {
+    pub width: u32,
+    pub height: u32,
+    pub bits_per_component: u8,
+    /// The name of the colorspace, `None` for image masks.
+    pub colorspace: Option<String>,
+}
+
+/// A drawing operation, the calls a [`Device`] receives.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub enum DrawCommand {
+    FillPath {
+        path: Vec<PathSegment>,
+        even_odd: bool,
+        ctm: Matrix,
+        color: DrawColor,
+    },
+    StrokePath {
+        path: Vec<PathSegment>,
+        stroke: StrokeStyle,
+        ctm: Matrix,
+        color: DrawColor,
+    },
+    ClipPath {
+        path: Vec<PathSegment>,
+        even_odd: bool,
+        ctm: Matrix,
+    },
+    ClipStrokePath {
+        path: Vec<PathSegment>,
+        stroke: StrokeStyle,
+        ctm: Matrix,
+    },
+    FillText {
+        text: Vec<RecordedTextSpan>,
+        ctm: Matrix,
+        color: DrawColor,
+    },
+    StrokeText {
+        text: Vec<RecordedTextSpan>,
+        stroke: StrokeStyle,
+        ctm: Matrix,
+        color: DrawColor,
+    },
+    ClipText {
+        text: Vec<RecordedTextSpan>,
+        ctm: Matrix,
+    },
+    ClipStrokeText {
+        text: Vec<RecordedTextSpan>,
+        stroke: StrokeStyle,
+        ctm: Matrix,
+    },
+    /// Invisible text, such as the text layer of a scan.
+    IgnoreText {
+        text: Vec<RecordedTextSpan>,
+        ctm: Matrix,
+    },
+    FillShade {
+        ctm: Matrix,
+        alpha: f32,
+    },
+    FillImage {
+        image: RecordedImage,
+        ctm: Matrix,
+        alpha: f32,
+    },
+    FillImageMask {
+        image: RecordedImage,
+        ctm: Matrix,
+        color: DrawColor,
+    },
+    ClipImageMask {
+        image: RecordedImage,
+        ctm: Matrix,
+    },
+    PopClip,
+    BeginMask {
+        area: Rect,
+        luminosity: bool,
+    },
+    EndMask,
+    BeginGroup {
+        area: Rect,
+        isolated: bool,
+        knockout: bool,
+        blend_mode: BlendMode,
+        alpha: f32,
+    },
+    EndGroup,
+    BeginTile {
+        area: Rect,
+        view: Rect,
+        x_step: f32,
+        y_step: f32,
+        ctm: Matrix,
+    },
+    EndTile,
+    BeginLayer {
+        name: String,
+    },
+    EndLayer,
+}
+
+/// The drawing operations of a page or display list, in drawing order, for analyzing or
+/// comparing the structure of documents, or converting them with backends of your own.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct RecordedCommands {
+    pub commands: Vec<DrawCommand>,
+}
+
+impl RecordedCommands {
+    /// Record the operations drawing `page`, annotations and widgets included, transformed by
+    /// `ctm`.
+    pub fn from_page(page: &Page, ctm: &Matrix) -> Result<Self, Error> {
+        let mut recorded = Self::default();
+        {
+            let device = Device::from_native(&mut recorded)?;
+            page.run(&device, ctm)?;
+        }
+        Ok(recorded)
+    }
+
+    /// Record the operations of `list`, transformed by `ctm`.
+    pub fn from_display_list(list: &DisplayList, ctm: &Matrix) -> Result<Self, Error> {
+        let mut recorded = Self::default();
+        {
+            let device = Device::from_native(&mut recorded)?;
+            list.run(&device, ctm, Rect::INF)?;
+        }
+        Ok(recorded)
+    }
+}
+
+impl IntoIterator for RecordedCommands {
+    type Item = DrawCommand;
+    type IntoIter = std::vec::IntoIter<DrawCommand>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.commands.into_iter()
+    }
+}
+
+#[derive(Default)]
+struct SegmentCollector(Vec<PathSegment>);
+
+impl PathWalker for SegmentCollector {
+    fn move_to(&mut self, x: f32, y: f32) {
+        self.0.push(PathSegment::MoveTo(Point::new(x, y)));
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        self.0.push(PathSegment::LineTo(Point::new(x, y)));
+    }
+
+    fn curve_to(&mut self, cx1: f32, cy1: f32, cx2: f32, cy2: f32, ex: f32, ey: f32) {
+        self.0.push(PathSegment::CurveTo(
+            Point::new(cx1, cy1),
+            Point::new(cx2, cy2),
+            Point::new(ex, ey),
+        ));
+    }
+
+    fn close(&mut self) {
+        self.0.push(PathSegment::Close);
+    }
+}
+
+fn segments(path: &Path) -> Vec<PathSegment> {
+    let mut collector = SegmentCollector::default();
+    // Walking only fails on allocation failure, which leaves a truncated path
+    let _ = path.walk(&mut collector);
+    collector.0
+}
+
+fn draw_color(cs: &Colorspace, color: &[f32], alpha: f32) -> DrawColor {
+    DrawColor {
+        colorspace: cs.name().to_owned(),
+        components: color.to_vec(),
+        alpha,
+    }
+}
+
+fn stroke_style(stroke: &StrokeState) -> StrokeStyle {
+    StrokeStyle {
+        line_width: stroke.line_width(),
+        start_cap: stroke.start_cap(),
+        dash_cap: stroke.dash_cap(),
+        end_cap: stroke.end_cap(),
+        line_join: stroke.line_join(),
+        miter_limit: stroke.miter_limit(),
+        dash_phase: stroke.dash_phase(),
+        dashes: stroke.dashes(),
+    }
+}
+
+fn text_spans(text: &Text) -> Vec<RecordedTextSpan> {
+    text.spans()
+        .map(|span| RecordedTextSpan {
+            font_name: span.font().name().to_owned(),
+            trm: span.trm(),
+            glyphs: span
+                .items()
+                .map(|item| RecordedGlyph {
+                    gid: item.gid(),
+                    ucs: item.ucs(),
+                    x: item.x(),
+                    y: item.y(),
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+fn image(img: &Image) -> RecordedImage {
+    let has_colorspace = unsafe { !(*img.inner).colorspace.is_null() };
+    RecordedImage {
+        width: img.width(),
+        height: img.height(),
+        bits_per_component: img.bits_per_components(),
+        colorspace: has_colorspace.then(|| img.color_space().name().to_owned()),
+    }
+}
+
+impl NativeDevice for RecordedCommands {
+    fn fill_path(
+        &mut self,
+        path: &Path,
+        even_odd: bool,
+        cmt: Matrix,
+        color_space: &Colorspace,
+        color: &[f32],
+        alpha: f32,
+        _cp: ColorParams,
+    ) {
+        self.commands.push(DrawCommand::FillPath {
+            path: segments(path),
+            even_odd,
+            ctm: cmt,
+            color: draw_color(color_space, color, alpha),
+        });
+    }
+
+    fn stroke_path(
+        &mut self,
+        path: &Path,
+        stroke_state: &StrokeState,
+        cmt: Matrix,
+        color_space: &Colorspace,
+        color: &[f32],
+        alpha: f32,
+        _cp: ColorParams,
+    ) {
+        self.commands.push(DrawCommand::StrokePath {
+            path: segments(path),
+            stroke: stroke_style(stroke_state),
+            ctm: cmt,
+            color: draw_color(color_space, color, alpha),
+        });
+    }
+
+    fn clip_path(&mut self, path: &Path, even_odd: bool, cmt: Matrix, _scissor: Rect) {
+        self.commands.push(DrawCommand::ClipPath {
+            path: segments(path),
+            even_odd,
+            ctm: cmt,
+        });
+    }
+
+    fn clip_stroke_path(
+        &mut self,
+        path: &Path,
+        stroke_state: &StrokeState,
+        cmt: Matrix,
+        _scissor: Rect,
+    ) {
+        self.commands.push(DrawCommand::ClipStrokePath {
+            path: segments(path),
+            stroke: stroke_style(stroke_state),
+            ctm: cmt,
+        });
+    }
+
+    fn fill_text(
+        &mut self,
+        text: &Text,
+        cmt: Matrix,
+        color_space: &Colorspace,
+        color: &[f32],
+        alpha: f32,
+        _cp: ColorParams,
+    ) {
+        self.commands.push(DrawCommand::FillText {
+            text: text_spans(text),
+            ctm: cmt,
+            color: draw_color(color_space, color, alpha),
+        });
+    }
+
+    fn stroke_text(
+        &mut self,
+        text: &Text,
+        stroke_state: &StrokeState,
+        cmt: Matrix,
+        color_space: &Colorspace,
+        color: &[f32],
+        alpha: f32,
+        _cp: ColorParams,
+    ) {
+        self.commands.push(DrawCommand::StrokeText {
+            text: text_spans(text),
+            stroke: stroke_style(stroke_state),
+            ctm: cmt,
+            color: draw_color(color_space, color, alpha),
+        });
+    }
+
+    fn clip_text(&mut self, text: &Text, cmt: Matrix, _scissor: Rect) {
+        self.commands.push(DrawCommand::ClipText {
+            text: text_spans(text),
+            ctm: cmt,
+        });
+    }
+
+    fn clip_stroke_text(
+        &mut self,
+        text: &Text,
+        stroke_state: &StrokeState,
+        cmt: Matrix,
+        _scissor: Rect,
+    ) {
+        self.commands.push(DrawCommand::ClipStrokeText {
+            text: text_spans(text),
+            stroke: stroke_style(stroke_state),
+            ctm: cmt,
+        });
+    }
+
+    fn ignore_text(&mut self, text: &Text, cmt: Matrix) {
+        self.commands.push(DrawCommand::IgnoreText {
+            text: text_spans(text),
+            ctm: cmt,
+        });
+    }
+
+    fn fill_shade(&mut self, _shade: &Shade, cmt: Matrix, alpha: f32, _cp: ColorParams) {
+        self.commands
+            .push(DrawCommand::FillShade { ctm: cmt, alpha });
+    }
+
+    fn fill_image(&mut self, img: &Image, cmt: Matrix, alpha: f32, _cp: ColorParams) {
+        self.commands.push(DrawCommand::FillImage {
+            image: image(img),
+            ctm: cmt,
+            alpha,
+        });
+    }
+
+    fn fill_image_mask(
+        &mut self,
+        img: &Image,
+        cmt: Matrix,
+        color_space: &Colorspace,
+        color: &[f32],
+        alpha: f32,
+        _cp: ColorParams,
+    ) {
+        self.commands.push(DrawCommand::FillImageMask {
+            image: image(img),
+            ctm: cmt,
+            color: draw_color(color_space, color, alpha),
+        });
+    }
+
+    fn clip_image_mask(&mut self, img: &Image, cmt: Matrix, _scissor: Rect) {
+        self.commands.push(DrawCommand::ClipImageMask {
+            image: image(img),
+            ctm: cmt,
+        });
+    }
+
+    fn pop_clip(&mut self) {
+        self.commands.push(DrawCommand::PopClip);
+    }
+
+    fn begin_mask(
+        &mut self,
+        area: Rect,
+        luminosity: bool,
+        _color_space: &Colorspace,
+        _color: &[f32],
+        _cp: ColorParams,
+    ) {
+        self.commands
+            .push(DrawCommand::BeginMask { area, luminosity });
+    }
+
+    fn end_mask(&mut self, _f: &Function) {
+        self.commands.push(DrawCommand::EndMask);
+    }
+
+    fn begin_group(
+        &mut self,
+        area: Rect,
+        _cs: &Colorspace,
+        isolated: bool,
+        knockout: bool,
+        blendmode: BlendMode,
+        alpha: f32,
+    ) {
+        self.commands.push(DrawCommand::BeginGroup {
+            area,
+            isolated,
+            knockout,
+            blend_mode: blendmode,
+            alpha,
+        });
+    }
+
+    fn end_group(&mut self) {
+        self.commands.push(DrawCommand::EndGroup);
+    }
+
+    fn begin_tile(
+        &mut self,
+        area: Rect,
+        view: Rect,
+        x_step: f32,
+        y_step: f32,
+        ctm: Matrix,
+        _id: Option<NonZero<i32>>,
+    ) -> Option<NonZero<i32>> {
+        self.commands.push(DrawCommand::BeginTile {
+            area,
+            view,
+            x_step,
+            y_step,
+            ctm,
+        });
+        // Have the tile content drawn, and recorded, every time
+        None
+    }
+
+    fn end_tile(&mut self) {
+        self.commands.push(DrawCommand::EndTile);
+    }
+
+    fn begin_layer(&mut self, name: &str) {
+        self.commands.push(DrawCommand::BeginLayer {
+            name: name.to_owned(),
+        });
+    }
+
+    fn end_layer(&mut self) {
+        self.commands.push(DrawCommand::EndLayer);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{DrawCommand, PathSegment, RecordedCommands};
+    use crate::{Colorspace, Device, DisplayList, Document, Matrix, Path, Rect, StrokeState};
+
+    #[test]
+    fn test_record_page() {
+        let doc = Document::open("tests/files/dummy.pdf").unwrap();
+        let page = doc.load_page(0).unwrap();
+        let recorded = RecordedCommands::from_page(&page, &Matrix::IDENTITY).unwrap();
+        let text: String = recorded
+            .commands
+            .iter()
+            .filter_map(|command| match command {
+                DrawCommand::FillText { text, .. } => Some(text),
+                _ => None,
+            })
+            .flatten()
+            .flat_map(|span| &span.glyphs)
+            .filter_map(|glyph| char::from_u32(glyph.ucs as u32))
+            .filter(|c| *c != ' ')
+            .collect();
+        assert_eq!(text, "DummyPDFfile");
+    }
+
+    #[test]
+    fn test_record_display_list() {
+        let list = DisplayList::new(Rect::new(0.0, 0.0, 100.0, 100.0)).unwrap();
+        {
+            let device = Device::from_display_list(&list).unwrap();
+            let mut clip = Path::new().unwrap();
+            clip.rect(0.0, 0.0, 50.0, 100.0).unwrap();
+            let mut path = Path::new().unwrap();
+            path.move_to(10.0, 10.0).unwrap();
+            path.line_to(90.0, 90.0).unwrap();
+            let stroke = StrokeState::default();
+            let cs = Colorspace::device_gray();
+            device.clip_path(&clip, false, &Matrix::IDENTITY).unwrap();
+            device
+                .stroke_path(
+                    &path,
+                    &stroke,
+                    &Matrix::IDENTITY,
+                    &cs,
+                    &[0.5],
+                    1.0,
+                    Default::default(),
+                )
+                .unwrap();
+            device.pop_clip().unwrap();
+        }
+        let recorded = RecordedCommands::from_display_list(&list, &Matrix::IDENTITY).unwrap();
+        let [DrawCommand::ClipPath { .. }, DrawCommand::StrokePath { path, color, .. }, DrawCommand::PopClip] =
+            recorded.commands.as_slice()
+        else {
+            panic!("{:?}", recorded.commands);
+        };
+        assert_eq!(path.len(), 2);
+        assert!(matches!(path[1], PathSegment::LineTo(p) if p.x == 90.0));
+        assert_eq!(color.components, [0.5]);
+    }
+}
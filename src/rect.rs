@@ -1,4 +1,5 @@
 use std::fmt;
+use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign};
 
 use mupdf_sys::*;
 
@@ -9,6 +10,7 @@ const FZ_MAX_INF_RECT: i32 = 0x7fffff80u32 as i32;
 
 /// A rectangle using integers instead of floats
 #[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub struct IRect {
     pub x0: i32,
     pub y0: i32,
@@ -78,6 +80,18 @@ impl IRect {
         }
         self
     }
+
+    /// Shrink to the area covered by both `self` and `other`, empty if they don't overlap.
+    pub fn intersect(&mut self, other: IRect) -> &mut Self {
+        let r: IRect = unsafe { fz_intersect_irect((*self).into(), other.into()) }.into();
+        // MuPDF's empty result is inverted, which `is_empty` doesn't recognize
+        *self = if r.x0 >= r.x1 || r.y0 >= r.y1 {
+            IRect::default()
+        } else {
+            r
+        };
+        self
+    }
 }
 
 impl fmt::Display for IRect {
@@ -102,6 +116,7 @@ impl From<IRect> for fz_irect {
 
 /// A rectangle represented by two diagonally opposite corners at arbitrary coordinates
 #[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub struct Rect {
     pub x0: f32,
     pub y0: f32,
@@ -172,6 +187,35 @@ impl Rect {
         self
     }
 
+    /// Shrink to the area covered by both `self` and `other`, empty if they don't overlap.
+    pub fn intersect(&mut self, other: Rect) -> &mut Self {
+        let r: Rect = unsafe { fz_intersect_rect((*self).into(), other.into()) }.into();
+        // MuPDF's empty result is inverted, which `is_empty` doesn't recognize
+        *self = if r.x0 >= r.x1 || r.y0 >= r.y1 {
+            Rect::default()
+        } else {
+            r
+        };
+        self
+    }
+
+    /// The bounding box of the rectangle transformed by `matrix`.
+    pub fn transform(&self, matrix: &Matrix) -> Self {
+        unsafe { fz_transform_rect((*self).into(), matrix.into()) }.into()
+    }
+
+    /// Round to integer coordinates, snapping edges that are within 0.001 of an integer to it
+    /// and rounding the others outwards. This is how MuPDF sizes pixmaps, so that
+    /// floating point errors don't add a row or column of pixels.
+    pub fn round(&self) -> IRect {
+        unsafe { fz_round_rect((*self).into()) }.into()
+    }
+
+    /// The smallest integer rectangle containing the rectangle.
+    pub fn round_out(&self) -> IRect {
+        unsafe { fz_irect_from_rect((*self).into()) }.into()
+    }
+
     pub fn adjust_for_stroke(&self, stroke: &StrokeState, ctm: &Matrix) -> Result<Self, Error> {
         let r = (*self).into();
         unsafe {
@@ -192,6 +236,38 @@ impl fmt::Display for Rect {
     }
 }
 
+/// The intersection of two rectangles, see [`Rect::intersect`].
+impl BitAnd for Rect {
+    type Output = Rect;
+
+    fn bitand(mut self, rhs: Rect) -> Rect {
+        self.intersect(rhs);
+        self
+    }
+}
+
+impl BitAndAssign for Rect {
+    fn bitand_assign(&mut self, rhs: Rect) {
+        self.intersect(rhs);
+    }
+}
+
+/// The union of two rectangles, see [`Rect::union`].
+impl BitOr for Rect {
+    type Output = Rect;
+
+    fn bitor(mut self, rhs: Rect) -> Rect {
+        self.r#union(rhs);
+        self
+    }
+}
+
+impl BitOrAssign for Rect {
+    fn bitor_assign(&mut self, rhs: Rect) {
+        self.r#union(rhs);
+    }
+}
+
 impl From<IRect> for Rect {
     fn from(it: IRect) -> Rect {
         Rect {
@@ -227,3 +303,60 @@ impl From<Rect> for fz_rect {
         fz_rect { x0, y0, x1, y1 }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::{IRect, Rect};
+    use crate::Matrix;
+
+    #[test]
+    fn test_rect_ops() {
+        let a = Rect::new(0.0, 0.0, 10.0, 10.0);
+        let b = Rect::new(5.0, 5.0, 20.0, 20.0);
+        assert_eq!(a & b, Rect::new(5.0, 5.0, 10.0, 10.0));
+        assert_eq!(a | b, Rect::new(0.0, 0.0, 20.0, 20.0));
+        assert!((a & Rect::new(10.0, 0.0, 20.0, 10.0)).is_empty());
+        assert_eq!(
+            *IRect::new(0, 0, 10, 10).intersect(IRect::new(5, -5, 20, 5)),
+            IRect::new(5, 0, 10, 5)
+        );
+        assert!(IRect::new(0, 0, 10, 10)
+            .intersect(IRect::new(10, 0, 20, 10))
+            .is_empty());
+
+        let m = Matrix::new_scale(2.0, 2.0) * Matrix::new_translate(1.0, 0.0);
+        assert_eq!(m, Matrix::new(2.0, 0.0, 0.0, 2.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn test_rect_transform_round() {
+        let r = Rect::new(0.0, 0.0, 10.0, 20.0).transform(&Matrix::new_rotate(90.0));
+        assert_eq!(r, Rect::new(-20.0, 0.0, 0.0, 10.0));
+
+        let r = Rect::new(0.9995, 0.5, 10.0005, 100.2);
+        assert_eq!(r.round(), IRect::new(1, 0, 10, 101));
+        assert_eq!(r.round_out(), IRect::new(0, 0, 11, 101));
+        assert_eq!(
+            Rect::new(-0.2, 0.0, 1.0, 1.0).round_out(),
+            IRect::new(-1, 0, 1, 1)
+        );
+    }
+
+    #[test]
+    fn test_matrix_decompose() {
+        let mut m = Matrix::new_scale(2.0, 2.0);
+        m.concat(Matrix::new_rotate(30.0));
+        m.concat(Matrix::new_translate(5.0, 7.0));
+        assert!((m.expansion() - 2.0).abs() < 1e-5);
+        assert!((m.rotation() - 30.0).abs() < 1e-4);
+        assert!(!m.is_rectilinear());
+        assert!(Matrix::new_rotate(90.0).is_rectilinear());
+
+        let inverse = m.invert().unwrap();
+        let p = crate::Point::new(3.0, 4.0)
+            .transform(&m)
+            .transform(&inverse);
+        assert!((p.x - 3.0).abs() < 1e-4 && (p.y - 4.0).abs() < 1e-4);
+        assert_eq!(Matrix::new_scale(0.0, 1.0).invert(), None);
+    }
+}
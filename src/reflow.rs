@@ -0,0 +1,110 @@
+use std::fmt::Write;
+
+use crate::{Context, Error};
+
+/// Typography settings for laying out reflowable documents such as EPUB, HTML and FB2, as
+/// e-readers offer them.
+///
+/// The settings are applied as a user stylesheet, so styles of a document with more specific
+/// selectors take precedence, unless the styles of documents are turned off with
+/// [`Context::set_use_document_css`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ReflowStyle {
+    hyphenate: Option<bool>,
+    justify: Option<bool>,
+    line_height: Option<f32>,
+}
+
+impl ReflowStyle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether words may be broken with a hyphen at the end of a line, as the document says by
+    /// default.
+    pub fn hyphenate(&self) -> Option<bool> {
+        self.hyphenate
+    }
+
+    pub fn set_hyphenate(&mut self, value: bool) -> &mut Self {
+        self.hyphenate = Some(value);
+        self
+    }
+
+    /// Whether paragraphs are justified rather than aligned left, as the document says by
+    /// default.
+    pub fn justify(&self) -> Option<bool> {
+        self.justify
+    }
+
+    pub fn set_justify(&mut self, value: bool) -> &mut Self {
+        self.justify = Some(value);
+        self
+    }
+
+    /// The height of lines as a multiple of the font size, as the document says by default.
+    ///
+    /// This is a fixed CSS `line-height`, not a minimum: lines with larger text or inline
+    /// images aren't made taller.
+    pub fn line_height(&self) -> Option<f32> {
+        self.line_height
+    }
+
+    /// Heights below 1 are raised to 1, so lines of text never overlap.
+    pub fn set_line_height(&mut self, value: f32) -> &mut Self {
+        self.line_height = Some(value.max(1.0));
+        self
+    }
+
+    /// The stylesheet applying the settings.
+    pub fn to_css(&self) -> String {
+        let mut css = String::new();
+        if let Some(hyphenate) = self.hyphenate {
+            let hyphens = if hyphenate { "auto" } else { "none" };
+            let _ = write!(css, "hyphens:{};", hyphens);
+        }
+        if let Some(justify) = self.justify {
+            let align = if justify { "justify" } else { "left" };
+            let _ = write!(css, "text-align:{};", align);
+        }
+        if let Some(height) = self.line_height {
+            let _ = write!(css, "line-height:{};", height);
+        }
+        if css.is_empty() {
+            return css;
+        }
+        format!("p,li,blockquote,div{{{}}}", css)
+    }
+}
+
+impl Context {
+    /// Lay out reflowable documents opened from now on with `style`. Documents already open keep
+    /// their style until they are opened again.
+    ///
+    /// This replaces the whole user stylesheet, including rules set earlier with
+    /// [`Context::set_user_css`]. To keep them, pass them together with
+    /// [`ReflowStyle::to_css`] to [`Context::set_user_css`] instead.
+    pub fn set_reflow_style(&mut self, style: &ReflowStyle) -> Result<(), Error> {
+        self.set_user_css(&style.to_css())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ReflowStyle;
+
+    #[test]
+    fn test_reflow_style_css() {
+        assert_eq!(ReflowStyle::new().to_css(), "");
+        let mut style = ReflowStyle::new();
+        style
+            .set_hyphenate(true)
+            .set_justify(false)
+            .set_line_height(0.5);
+        assert_eq!(style.line_height(), Some(1.0));
+        assert_eq!(
+            style.to_css(),
+            "p,li,blockquote,div{hyphens:auto;text-align:left;line-height:1;}"
+        );
+    }
+}
@@ -0,0 +1,194 @@
+use mupdf_sys::*;
+
+use crate::{context, Colorspace, Error, IRect, Matrix, Pixmap, Rect, SkipContent};
+
+/// How to render a page: scale, rotation, output format, the area to render and anti-aliasing.
+#[derive(Debug, Clone)]
+pub struct RenderOptions {
+    zoom: f32,
+    rotation: f32,
+    colorspace: Colorspace,
+    alpha: bool,
+    annotations: bool,
+    skip: SkipContent,
+    clip: Option<IRect>,
+    aa_level: Option<i32>,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            zoom: 1.0,
+            rotation: 0.0,
+            colorspace: Colorspace::device_rgb(),
+            alpha: false,
+            annotations: true,
+            skip: SkipContent::empty(),
+            clip: None,
+            aa_level: None,
+        }
+    }
+}
+
+impl RenderOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Scale factor, `1.0` rendering one pixel per point (72 dpi).
+    pub fn set_zoom(&mut self, zoom: f32) -> &mut Self {
+        self.zoom = zoom;
+        self
+    }
+
+    /// Resolution in dots per inch, the same as a zoom of `dpi / 72`.
+    pub fn set_dpi(&mut self, dpi: f32) -> &mut Self {
+        self.zoom = dpi / 72.0;
+        self
+    }
+
+    /// Clockwise rotation in degrees, applied after scaling.
+    pub fn set_rotation(&mut self, degrees: f32) -> &mut Self {
+        self.rotation = degrees;
+        self
+    }
+
+    pub fn set_colorspace(&mut self, colorspace: &Colorspace) -> &mut Self {
+        self.colorspace = colorspace.clone();
+        self
+    }
+
+    pub fn set_alpha(&mut self, alpha: bool) -> &mut Self {
+        self.alpha = alpha;
+        self
+    }
+
+    /// Whether to render annotations and form widgets, `true` by default.
+    pub fn set_annotations(&mut self, annotations: bool) -> &mut Self {
+        self.annotations = annotations;
+        self
+    }
+
+    /// Kinds of content to leave out, none by default. Annotations are left out with
+    /// [`RenderOptions::set_annotations`].
+    pub fn set_skip(&mut self, skip: SkipContent) -> &mut Self {
+        self.skip = skip;
+        self
+    }
+
+    /// Only render the part of the page inside `clip`, in device coordinates, e.g. the visible
+    /// part of a zoomed in page in a viewer. The pixmap covers the intersection of `clip` and
+    /// the page.
+    pub fn set_clip(&mut self, clip: IRect) -> &mut Self {
+        self.clip = Some(clip);
+        self
+    }
+
+    /// Number of bits of anti-aliasing, from 0 for none to 8 for the best, instead of the
+    /// level of the [`Context`](crate::Context).
+    pub fn set_aa_level(&mut self, bits: i32) -> &mut Self {
+        self.aa_level = Some(bits.clamp(0, 8));
+        self
+    }
+
+    pub fn zoom(&self) -> f32 {
+        self.zoom
+    }
+
+    pub fn rotation(&self) -> f32 {
+        self.rotation
+    }
+
+    pub fn colorspace(&self) -> &Colorspace {
+        &self.colorspace
+    }
+
+    pub fn alpha(&self) -> bool {
+        self.alpha
+    }
+
+    pub fn annotations(&self) -> bool {
+        self.annotations
+    }
+
+    pub fn skip(&self) -> SkipContent {
+        self.skip
+    }
+
+    pub fn dpi(&self) -> f32 {
+        self.zoom * 72.0
+    }
+
+    pub fn clip(&self) -> Option<IRect> {
+        self.clip
+    }
+
+    pub fn aa_level(&self) -> Option<i32> {
+        self.aa_level
+    }
+
+    /// The page to device transform these options describe.
+    pub fn matrix(&self) -> Matrix {
+        let mut ctm = Matrix::new_scale(self.zoom, self.zoom);
+        ctm.concat(Matrix::new_rotate(self.rotation));
+        ctm
+    }
+
+    /// The area in device space a rendering of content with the page `bounds` covers.
+    pub(crate) fn area(&self, bounds: Rect) -> IRect {
+        let mut area = bounds.transform(&self.matrix()).round();
+        if let Some(clip) = self.clip {
+            area.intersect(clip);
+        }
+        area
+    }
+
+    /// A cleared pixmap for rendering content with the page `bounds`, white unless the options
+    /// ask for alpha.
+    pub(crate) fn blank_pixmap(&self, bounds: Rect) -> Result<Pixmap, Error> {
+        let area = self.area(bounds);
+        let mut pixmap = Pixmap::new_with_rect(&self.colorspace, area, self.alpha)?;
+        if self.alpha {
+            pixmap.clear()?;
+        } else {
+            pixmap.clear_with(255)?;
+        }
+        Ok(pixmap)
+    }
+
+    /// Call `f` with the anti-aliasing level of the options, restoring the level of the context
+    /// afterwards. Draw devices take the level when they are created.
+    pub(crate) fn with_aa_level<T>(&self, f: impl FnOnce() -> T) -> T {
+        let Some(bits) = self.aa_level else {
+            return f();
+        };
+        let _restore = AaLevels::set(bits);
+        f()
+    }
+}
+
+/// The anti-aliasing levels of the context before [`AaLevels::set`], restored on drop so they
+/// are restored even if rendering panics.
+struct AaLevels {
+    text: i32,
+    graphics: i32,
+}
+
+impl AaLevels {
+    fn set(bits: i32) -> Self {
+        let ctx = context();
+        let (text, graphics) = unsafe { (fz_text_aa_level(ctx), fz_graphics_aa_level(ctx)) };
+        unsafe { fz_set_aa_level(ctx, bits) };
+        Self { text, graphics }
+    }
+}
+
+impl Drop for AaLevels {
+    fn drop(&mut self) {
+        let ctx = context();
+        unsafe {
+            fz_set_text_aa_level(ctx, self.text);
+            fz_set_graphics_aa_level(ctx, self.graphics);
+        }
+    }
+}
@@ -0,0 +1,529 @@
+use crate::pdf::{PdfDocument, PdfPage};
+use crate::{Bitmap, Colorspace, Document, Error, Matrix, Pixmap, Size};
+
+/// Percentile of a tile's samples taken as its background level, high enough to skip the text.
+const BACKGROUND_PERCENTILE: f32 = 0.9;
+
+/// How to clean up the background of a scanned page, see [`Pixmap::normalize_background`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BackgroundOptions {
+    tile_size: u32,
+    white_point: u8,
+    threshold: Option<u8>,
+}
+
+impl Default for BackgroundOptions {
+    fn default() -> Self {
+        Self {
+            tile_size: 32,
+            white_point: 224,
+            threshold: None,
+        }
+    }
+}
+
+impl BackgroundOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Size in pixels of the squares the background level is estimated over. It should be a few
+    /// times the height of a line of text, `32` by default suits scans at 150 to 300 dpi.
+    pub fn set_tile_size(&mut self, pixels: u32) -> &mut Self {
+        self.tile_size = pixels.max(1);
+        self
+    }
+
+    /// Pixels at least this light after normalization become pure white, `224` by default.
+    pub fn set_white_point(&mut self, value: u8) -> &mut Self {
+        self.white_point = value;
+        self
+    }
+
+    /// Binarize the page: pixels darker than `threshold` after normalization become black and
+    /// all others white. As the background is leveled first this amounts to an adaptive
+    /// threshold. Off by default.
+    pub fn set_threshold(&mut self, threshold: Option<u8>) -> &mut Self {
+        self.threshold = threshold;
+        self
+    }
+
+    pub fn tile_size(&self) -> u32 {
+        self.tile_size
+    }
+
+    pub fn white_point(&self) -> u8 {
+        self.white_point
+    }
+
+    pub fn threshold(&self) -> Option<u8> {
+        self.threshold
+    }
+}
+
+/// Background level of every channel of every tile, in row major tile order.
+fn tile_levels(samples: &[u8], w: usize, h: usize, n: usize, colors: usize, ts: usize) -> Vec<f32> {
+    let (tw, th) = (w.div_ceil(ts), h.div_ceil(ts));
+    let mut levels = vec![0.0; tw * th * colors];
+    let mut histogram = [0u32; 256];
+    for ty in 0..th {
+        for tx in 0..tw {
+            let (x0, x1) = (tx * ts, ((tx + 1) * ts).min(w));
+            let (y0, y1) = (ty * ts, ((ty + 1) * ts).min(h));
+            let count = ((x1 - x0) * (y1 - y0)) as f32;
+            for c in 0..colors {
+                histogram.fill(0);
+                for y in y0..y1 {
+                    for x in x0..x1 {
+                        histogram[samples[(y * w + x) * n + c] as usize] += 1;
+                    }
+                }
+                let target = (count * BACKGROUND_PERCENTILE).ceil() as u32;
+                let mut seen = 0;
+                let level = histogram
+                    .iter()
+                    .position(|&k| {
+                        seen += k;
+                        seen >= target
+                    })
+                    .unwrap_or(255);
+                levels[(ty * tw + tx) * colors + c] = level as f32;
+            }
+        }
+    }
+
+    // Tiles much darker than the page are pictures or dark borders, not paper: level them with
+    // the page instead of blowing them up to white
+    for c in 0..colors {
+        let mut page: Vec<f32> = levels.iter().skip(c).step_by(colors).copied().collect();
+        page.sort_by(f32::total_cmp);
+        let median = page[page.len() / 2].max(1.0);
+        for level in levels.iter_mut().skip(c).step_by(colors) {
+            if *level < median * 0.5 {
+                *level = median;
+            }
+        }
+    }
+    levels
+}
+
+/// Interpolation position between tile centers for pixel `i`: lower tile, upper tile, weight.
+fn tile_position(i: usize, ts: usize, tiles: usize) -> (usize, usize, f32) {
+    let f = ((i as f32 + 0.5) / ts as f32 - 0.5).clamp(0.0, (tiles - 1) as f32);
+    let lo = f.floor() as usize;
+    (lo, (lo + 1).min(tiles - 1), f - lo as f32)
+}
+
+impl Pixmap {
+    /// Even out the paper of a scanned page: estimate the background level over the page,
+    /// divide it out so the paper becomes white whatever its tint and lighting, then push light
+    /// pixels to pure white or binarize the page as `options` say.
+    ///
+    /// Flat, clean backgrounds help OCR and compress much better, whether to JPEG or to a
+    /// bilevel format. Only gray and RGB pixmaps are supported, alpha is left untouched.
+    pub fn normalize_background(&mut self, options: &BackgroundOptions) -> Result<(), Error> {
+        let n = self.n() as usize;
+        let colors = n - self.alpha() as usize;
+        if colors != 1 && colors != 3 {
+            return Err(Error::InvalidPixmap(format!(
+                "expected a gray or RGB pixmap, got {} color components",
+                colors
+            )));
+        }
+        let (w, h) = (self.width() as usize, self.height() as usize);
+        if w == 0 || h == 0 {
+            return Ok(());
+        }
+        let ts = options.tile_size as usize;
+        let (tw, th) = (w.div_ceil(ts), h.div_ceil(ts));
+        let levels = tile_levels(self.samples(), w, h, n, colors, ts);
+        let level = |tx: usize, ty: usize, c: usize| levels[(ty * tw + tx) * colors + c];
+
+        let columns: Vec<_> = (0..w).map(|x| tile_position(x, ts, tw)).collect();
+        let samples = self.samples_mut();
+        let mut normalized = [0.0f32; 3];
+        for y in 0..h {
+            let (ty0, ty1, fy) = tile_position(y, ts, th);
+            for (x, &(tx0, tx1, fx)) in columns.iter().enumerate() {
+                let pixel = &mut samples[(y * w + x) * n..][..colors];
+                for c in 0..colors {
+                    let top = level(tx0, ty0, c) * (1.0 - fx) + level(tx1, ty0, c) * fx;
+                    let bottom = level(tx0, ty1, c) * (1.0 - fx) + level(tx1, ty1, c) * fx;
+                    let background = (top * (1.0 - fy) + bottom * fy).max(1.0);
+                    normalized[c] = (pixel[c] as f32 * 255.0 / background).min(255.0);
+                }
+                let luminance = if colors == 1 {
+                    normalized[0]
+                } else {
+                    0.3 * normalized[0] + 0.59 * normalized[1] + 0.11 * normalized[2]
+                };
+                match options.threshold {
+                    Some(threshold) => {
+                        let value = if luminance < threshold as f32 { 0 } else { 255 };
+                        pixel.fill(value);
+                    }
+                    None if luminance >= options.white_point as f32 => pixel.fill(255),
+                    None => {
+                        for (value, normalized) in pixel.iter_mut().zip(normalized) {
+                            *value = normalized.round() as u8;
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// How to compress scanned pages into layered PDF pages, see [`PdfDocument::add_mrc_page`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MrcOptions {
+    dpi: u32,
+    grayscale: bool,
+    background: Option<BackgroundOptions>,
+    threshold: u8,
+    background_downsample: u32,
+    jpeg_quality: u8,
+}
+
+impl Default for MrcOptions {
+    fn default() -> Self {
+        Self {
+            dpi: 300,
+            grayscale: false,
+            background: Some(BackgroundOptions::default()),
+            threshold: 128,
+            background_downsample: 3,
+            jpeg_quality: 40,
+        }
+    }
+}
+
+impl MrcOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Resolution pages are rendered at by [`Document::optimize_scans`], `300` by default.
+    pub fn set_dpi(&mut self, dpi: u32) -> &mut Self {
+        self.dpi = dpi.max(1);
+        self
+    }
+
+    /// Render pages in gray rather than color in [`Document::optimize_scans`].
+    pub fn set_grayscale(&mut self, grayscale: bool) -> &mut Self {
+        self.grayscale = grayscale;
+        self
+    }
+
+    /// Clean up the background before separating the text from it, on by default with
+    /// [`BackgroundOptions::default`].
+    pub fn set_background(&mut self, background: Option<BackgroundOptions>) -> &mut Self {
+        self.background = background;
+        self
+    }
+
+    /// Pixels darker than this go to the bilevel text layer, `128` by default.
+    pub fn set_threshold(&mut self, threshold: u8) -> &mut Self {
+        self.threshold = threshold;
+        self
+    }
+
+    /// Factor the background layer is downsampled by, `3` by default.
+    pub fn set_background_downsample(&mut self, factor: u32) -> &mut Self {
+        self.background_downsample = factor.max(1);
+        self
+    }
+
+    /// JPEG quality of the background layer from 0 to 100, `40` by default.
+    pub fn set_jpeg_quality(&mut self, quality: u8) -> &mut Self {
+        self.jpeg_quality = quality.min(100);
+        self
+    }
+
+    pub fn dpi(&self) -> u32 {
+        self.dpi
+    }
+
+    pub fn grayscale(&self) -> bool {
+        self.grayscale
+    }
+
+    pub fn background(&self) -> Option<BackgroundOptions> {
+        self.background
+    }
+
+    pub fn threshold(&self) -> u8 {
+        self.threshold
+    }
+
+    pub fn background_downsample(&self) -> u32 {
+        self.background_downsample
+    }
+
+    pub fn jpeg_quality(&self) -> u8 {
+        self.jpeg_quality
+    }
+}
+
+fn luminance(pixel: &[u8]) -> u8 {
+    match *pixel {
+        [r, g, b] => ((30 * r as u32 + 59 * g as u32 + 11 * b as u32) / 100) as u8,
+        [v, ..] => v,
+        [] => 0,
+    }
+}
+
+/// The two layers of a scanned page.
+struct MrcLayers {
+    /// Gray pixmap, black where there is ink.
+    mask: Pixmap,
+    /// Average color of the ink.
+    ink: [u8; 3],
+    /// The page without the ink, at a reduced resolution.
+    background: Pixmap,
+}
+
+fn separate(scan: &Pixmap, colors: usize, options: &MrcOptions) -> Result<MrcLayers, Error> {
+    let n = scan.n() as usize;
+    let (w, h) = (scan.width() as usize, scan.height() as usize);
+    let samples = scan.samples();
+    let is_ink = |i: usize| luminance(&samples[i * n..][..colors]) < options.threshold;
+
+    let mut mask = Pixmap::new_with_w_h(&Colorspace::device_gray(), w as i32, h as i32, false)?;
+    let mut ink = [0u64; 3];
+    let mut paper = [0u64; 3];
+    let mut ink_count = 0;
+    for (i, value) in mask.samples_mut().iter_mut().enumerate() {
+        let sums = if is_ink(i) {
+            ink_count += 1;
+            *value = 0;
+            &mut ink
+        } else {
+            *value = 255;
+            &mut paper
+        };
+        for (c, sum) in sums.iter_mut().enumerate() {
+            *sum += samples[i * n + c.min(colors - 1)] as u64;
+        }
+    }
+    let average = |sums: [u64; 3], count: usize| sums.map(|s| (s / count.max(1) as u64) as u8);
+    let ink = average(ink, ink_count);
+    let paper = average(paper, w * h - ink_count);
+
+    // Average each block of the background over its paper pixels, blocks that are all ink
+    // taking the color of the whole page's paper
+    let d = options.background_downsample as usize;
+    let (bw, bh) = (w.div_ceil(d), h.div_ceil(d));
+    let cs = if colors == 1 {
+        Colorspace::device_gray()
+    } else {
+        Colorspace::device_rgb()
+    };
+    let mut background = Pixmap::new_with_w_h(&cs, bw as i32, bh as i32, false)?;
+    let out = background.samples_mut();
+    for by in 0..bh {
+        for bx in 0..bw {
+            let mut sums = [0u64; 3];
+            let mut count = 0;
+            for y in by * d..((by + 1) * d).min(h) {
+                for x in bx * d..((bx + 1) * d).min(w) {
+                    let i = y * w + x;
+                    if !is_ink(i) {
+                        count += 1;
+                        for (c, sum) in sums.iter_mut().take(colors).enumerate() {
+                            *sum += samples[i * n + c] as u64;
+                        }
+                    }
+                }
+            }
+            let color = if count == 0 {
+                paper
+            } else {
+                average(sums, count)
+            };
+            out[(by * bw + bx) * colors..][..colors].copy_from_slice(&color[..colors]);
+        }
+    }
+    Ok(MrcLayers {
+        mask,
+        ink,
+        background,
+    })
+}
+
+impl PdfDocument {
+    /// Append a scanned page split into two layers, as mixed raster content (MRC) does: the
+    /// text and line art as a CCITT Group 4 compressed bilevel mask painted in the average ink
+    /// color, over a downsampled JPEG of the rest of the page.
+    ///
+    /// This keeps text sharp at a fraction of the size of a full resolution image of the page.
+    /// The page is sized from the resolution of `scan`, which must be a gray or RGB pixmap.
+    pub fn add_mrc_page(&mut self, scan: &Pixmap, options: &MrcOptions) -> Result<PdfPage, Error> {
+        let colors = scan.n() as usize - scan.alpha() as usize;
+        if colors != 1 && colors != 3 {
+            return Err(Error::InvalidPixmap(format!(
+                "expected a gray or RGB pixmap, got {} color components",
+                colors
+            )));
+        }
+        let cleaned;
+        let scan = match &options.background {
+            Some(background) => {
+                let mut pixmap = scan.try_clone()?;
+                pixmap.normalize_background(background)?;
+                cleaned = pixmap;
+                &cleaned
+            }
+            None => scan,
+        };
+        let layers = separate(scan, colors, options)?;
+
+        let (x_res, y_res) = scan.resolution();
+        let width = scan.width() as f32 * 72.0 / x_res.max(1) as f32;
+        let height = scan.height() as f32 * 72.0 / y_res.max(1) as f32;
+        let page = self.new_page(Size::new(width, height))?;
+
+        let colorspace = if colors == 1 {
+            "DeviceGray"
+        } else {
+            "DeviceRGB"
+        };
+        let background = self.new_object_from_str(&format!(
+            "<< /Type /XObject /Subtype /Image /Width {} /Height {} /ColorSpace /{} \
+             /BitsPerComponent 8 /Filter /DCTDecode >>",
+            layers.background.width(),
+            layers.background.height(),
+            colorspace,
+        ))?;
+        let mut background = self.add_object(&background)?;
+        background.write_raw_stream_buffer(&layers.background.to_jpeg(options.jpeg_quality)?)?;
+
+        let (w, h) = (scan.width(), scan.height());
+        let mask = self.new_object_from_str(&format!(
+            "<< /Type /XObject /Subtype /Image /Width {w} /Height {h} /ImageMask true \
+             /BitsPerComponent 1 /Filter /CCITTFaxDecode \
+             /DecodeParms << /K -1 /Columns {w} /Rows {h} >> >>"
+        ))?;
+        let mut mask = self.add_object(&mask)?;
+        mask.write_raw_stream_buffer(&Bitmap::from_pixmap(&layers.mask)?.to_ccitt_g4()?)?;
+
+        let mut xobjects = self.new_dict()?;
+        xobjects.dict_put("Bg", background)?;
+        xobjects.dict_put("Fg", mask)?;
+        let mut page_obj = page.object();
+        let mut resources = page_obj
+            .get_dict("Resources")?
+            .ok_or(Error::UnexpectedNullPtr)?;
+        resources.dict_put("XObject", xobjects)?;
+
+        let [r, g, b] = layers.ink.map(|c| c as f32 / 255.0);
+        let contents = self.new_dict()?;
+        let mut contents = self.add_object(&contents)?;
+        contents.write_stream_string(&format!(
+            "q {width} 0 0 {height} 0 0 cm /Bg Do Q\n\
+             q {r:.3} {g:.3} {b:.3} rg {width} 0 0 {height} 0 0 cm /Fg Do Q\n"
+        ))?;
+        page_obj.dict_put("Contents", contents)?;
+        Ok(page)
+    }
+}
+
+impl Document {
+    /// Rasterize every page and recompress it with [`PdfDocument::add_mrc_page`], shrinking
+    /// scanned documents considerably.
+    ///
+    /// Any vector content or real text on the pages ends up in the image layers too, so only use
+    /// this on documents made of scans.
+    pub fn optimize_scans(&self, options: &MrcOptions) -> Result<PdfDocument, Error> {
+        let mut out = PdfDocument::new();
+        let scale = options.dpi as f32 / 72.0;
+        let cs = if options.grayscale {
+            Colorspace::device_gray()
+        } else {
+            Colorspace::device_rgb()
+        };
+        for page in self.pages()? {
+            let page = page?;
+            let mut pixmap = page.to_pixmap(&Matrix::new_scale(scale, scale), &cs, false, true)?;
+            pixmap.set_resolution(options.dpi as i32, options.dpi as i32);
+            out.add_mrc_page(&pixmap, options)?;
+        }
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{BackgroundOptions, MrcOptions};
+    use crate::{Colorspace, Document, Pixmap};
+
+    /// A gray page lit unevenly from the left with a dark square on it.
+    fn scan() -> Pixmap {
+        let mut pixmap = Pixmap::new_with_w_h(&Colorspace::device_gray(), 128, 96, false).unwrap();
+        let samples = pixmap.samples_mut();
+        for y in 0..96 {
+            for x in 0..128 {
+                let dark = (40..60).contains(&x) && (40..60).contains(&y);
+                samples[y * 128 + x] = if dark { 30 } else { 200 - (x / 4) as u8 };
+            }
+        }
+        pixmap
+    }
+
+    #[test]
+    fn test_normalize_background() {
+        let mut pixmap = scan();
+        pixmap
+            .normalize_background(&BackgroundOptions::new())
+            .unwrap();
+        let samples = pixmap.samples();
+        assert_eq!(samples[10 * 128 + 5], 255);
+        assert_eq!(samples[10 * 128 + 120], 255);
+        assert!(samples[50 * 128 + 50] < 64);
+
+        let mut pixmap = scan();
+        pixmap
+            .normalize_background(BackgroundOptions::new().set_threshold(Some(128)))
+            .unwrap();
+        assert!(pixmap.samples().iter().all(|&v| v == 0 || v == 255));
+        assert_eq!(pixmap.samples()[50 * 128 + 50], 0);
+
+        let mut cmyk = Pixmap::new_with_w_h(&Colorspace::device_cmyk(), 8, 8, false).unwrap();
+        assert!(cmyk
+            .normalize_background(&BackgroundOptions::new())
+            .is_err());
+    }
+
+    #[test]
+    fn test_mrc_page() {
+        let mut scan = scan();
+        scan.set_resolution(144, 144);
+        let mut pdf = crate::pdf::PdfDocument::new();
+        pdf.add_mrc_page(&scan, &MrcOptions::new()).unwrap();
+        let mut out = Vec::new();
+        pdf.write_to(&mut out).unwrap();
+
+        let doc = Document::from_bytes(&out, "application/pdf").unwrap();
+        let page = doc.load_page(0).unwrap();
+        let bounds = page.bounds().unwrap();
+        assert_eq!((bounds.width(), bounds.height()), (64.0, 48.0));
+        let pixmap = page
+            .to_pixmap(
+                &crate::Matrix::new_scale(2.0, 2.0),
+                &Colorspace::device_gray(),
+                false,
+                false,
+            )
+            .unwrap();
+        let samples = pixmap.samples();
+        assert!(samples[50 * 128 + 50] < 64);
+        assert!(samples[10 * 128 + 100] > 192);
+
+        let doc = Document::open("tests/files/dummy.pdf").unwrap();
+        let optimized = doc
+            .optimize_scans(MrcOptions::new().set_dpi(72).set_grayscale(true))
+            .unwrap();
+        assert_eq!(optimized.page_count().unwrap(), 1);
+    }
+}
@@ -0,0 +1,216 @@
+use std::collections::VecDeque;
+
+use crate::text_page::SearchHitResponse;
+use crate::{Cookie, Document, Error, Location, Quad, TextPageOptions};
+
+/// Options for [`Document::search_all`].
+#[derive(Debug, Clone)]
+pub struct SearchOptions {
+    start: Location,
+    text_options: TextPageOptions,
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        Self {
+            start: Location {
+                chapter: 0,
+                page: 0,
+            },
+            text_options: TextPageOptions::empty(),
+        }
+    }
+}
+
+impl SearchOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The page to start searching at, the first page by default. Pages before it aren't
+    /// searched, so "find next" in a viewer starts at the page after the current hit.
+    pub fn start(&self) -> Location {
+        self.start
+    }
+
+    pub fn set_start(&mut self, value: Location) -> &mut Self {
+        self.start = value;
+        self
+    }
+
+    /// Options for extracting the text of the pages searched, none by default.
+    pub fn text_options(&self) -> TextPageOptions {
+        self.text_options
+    }
+
+    pub fn set_text_options(&mut self, value: TextPageOptions) -> &mut Self {
+        self.text_options = value;
+        self
+    }
+}
+
+/// An occurrence of the text searched for.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct SearchHit {
+    pub location: Location,
+    /// The quads covering the hit, more than one if it spans several lines.
+    pub quads: Vec<Quad>,
+}
+
+/// An iterator over the hits of [`Document::search_all`], searching a page at a time.
+#[derive(Debug)]
+pub struct SearchIter<'a> {
+    doc: &'a Document,
+    needle: String,
+    text_options: TextPageOptions,
+    cookie: Option<&'a Cookie>,
+    next: Location,
+    /// The number of pages of `next.chapter`.
+    chapter_pages: i32,
+    chapter_count: i32,
+    hits: VecDeque<SearchHit>,
+    done: bool,
+}
+
+impl SearchIter<'_> {
+    /// Search the next page, returning `false` at the end of the document.
+    fn search_next_page(&mut self) -> Result<bool, Error> {
+        while self.next.page >= self.chapter_pages {
+            self.next = Location {
+                chapter: self.next.chapter + 1,
+                page: 0,
+            };
+            if self.next.chapter >= self.chapter_count {
+                return Ok(false);
+            }
+            self.chapter_pages = self.doc.chapter_page_count(self.next.chapter)?;
+        }
+        let location = self.next;
+        self.next.page += 1;
+        let text_page = self
+            .doc
+            .load_location(location)?
+            .to_text_page(self.text_options)?;
+        text_page.search_cb(&self.needle, &mut self.hits, |hits, quads| {
+            hits.push_back(SearchHit {
+                location,
+                quads: quads.to_vec(),
+            });
+            SearchHitResponse::ContinueSearch
+        })?;
+        Ok(true)
+    }
+}
+
+impl Iterator for SearchIter<'_> {
+    type Item = Result<SearchHit, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            if let Some(hit) = self.hits.pop_front() {
+                return Some(Ok(hit));
+            }
+            if self.cookie.is_some_and(Cookie::is_aborted) {
+                self.done = true;
+                return Some(Err(Error::Aborted));
+            }
+            match self.search_next_page() {
+                Ok(true) => {}
+                Ok(false) => self.done = true,
+                // The failed page is skipped, the search goes on if the caller wants it to
+                Err(err) => return Some(Err(err)),
+            }
+        }
+        None
+    }
+}
+
+impl Document {
+    /// Lazily search the pages of the document for `needle`, chapter by chapter, starting at
+    /// [`SearchOptions::start`]. Pages are only loaded when the hits of the pages before them
+    /// have all been taken, so taking the first hit is cheap.
+    ///
+    /// A page that fails to load yields an error, and the search goes on with the next page.
+    pub fn search_all(&self, needle: &str, options: &SearchOptions) -> Result<SearchIter, Error> {
+        let start = Location {
+            chapter: options.start.chapter.max(0),
+            page: options.start.page.max(0),
+        };
+        let chapter_count = self.chapter_count()?;
+        let chapter_pages = if start.chapter < chapter_count {
+            self.chapter_page_count(start.chapter)?
+        } else {
+            0
+        };
+        Ok(SearchIter {
+            doc: self,
+            needle: needle.to_owned(),
+            text_options: options.text_options,
+            cookie: None,
+            next: start,
+            chapter_pages,
+            chapter_count,
+            hits: VecDeque::new(),
+            done: false,
+        })
+    }
+
+    /// Like [`Document::search_all`], stopping with [`Error::Aborted`] once the search is
+    /// stopped through `cookie`.
+    pub fn search_all_with_cookie<'a>(
+        &'a self,
+        needle: &str,
+        options: &SearchOptions,
+        cookie: &'a Cookie,
+    ) -> Result<SearchIter<'a>, Error> {
+        let mut iter = self.search_all(needle, options)?;
+        iter.cookie = Some(cookie);
+        Ok(iter)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::SearchOptions;
+    use crate::{Cookie, Document, Error, Location};
+
+    #[test]
+    fn test_search_all() {
+        let doc = Document::open("tests/files/dummy.pdf").unwrap();
+        let mut hits = doc.search_all("dummy", &SearchOptions::new()).unwrap();
+        let hit = hits.next().unwrap().unwrap();
+        assert_eq!(
+            hit.location,
+            Location {
+                chapter: 0,
+                page: 0
+            }
+        );
+        assert_eq!(hit.quads.len(), 1);
+        assert!((56.0..58.0).contains(&hit.quads[0].ul.x));
+        assert!(hits.next().is_none());
+
+        assert_eq!(
+            doc.search_all("not found", &SearchOptions::new())
+                .unwrap()
+                .count(),
+            0
+        );
+
+        let mut options = SearchOptions::new();
+        options.set_start(Location {
+            chapter: 0,
+            page: 1,
+        });
+        assert_eq!(doc.search_all("dummy", &options).unwrap().count(), 0);
+
+        let cookie = Cookie::new().unwrap();
+        cookie.abort();
+        let mut hits = doc
+            .search_all_with_cookie("dummy", &SearchOptions::new(), &cookie)
+            .unwrap();
+        assert!(matches!(hits.next(), Some(Err(Error::Aborted))));
+        assert!(hits.next().is_none());
+    }
+}
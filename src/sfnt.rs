@@ -0,0 +1,57 @@
+pub(crate) struct Reader<'a>(pub(crate) &'a [u8]);
+
+impl<'a> Reader<'a> {
+    pub(crate) fn bytes(&self, offset: usize, len: usize) -> Option<&'a [u8]> {
+        self.0.get(offset..offset.checked_add(len)?)
+    }
+
+    pub(crate) fn u16(&self, offset: usize) -> Option<u16> {
+        let b = self.bytes(offset, 2)?;
+        Some(u16::from_be_bytes([b[0], b[1]]))
+    }
+
+    pub(crate) fn u32(&self, offset: usize) -> Option<u32> {
+        let b = self.bytes(offset, 4)?;
+        Some(u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    /// A 16.16 fixed point number
+    pub(crate) fn fixed(&self, offset: usize) -> Option<f32> {
+        Some(self.u32(offset)? as i32 as f32 / 65536.0)
+    }
+}
+
+/// Offset of the table directory of the font at `index` in a font file or collection.
+fn directory(r: &Reader, index: u32) -> Option<usize> {
+    if r.bytes(0, 4)? == b"ttcf" {
+        if index >= r.u32(8)? {
+            return None;
+        }
+        return Some(r.u32(12 + 4 * index as usize)? as usize);
+    }
+    (index == 0).then_some(0)
+}
+
+/// Whether `data` looks like an OpenType/TrueType font (collection) with a font at `index`.
+pub(crate) fn is_font(data: &[u8], index: u32) -> bool {
+    let r = Reader(data);
+    directory(&r, index)
+        .and_then(|dir| r.u16(dir + 4))
+        .is_some()
+}
+
+/// Find a table of the font at `index` in a font file or collection.
+pub(crate) fn find_table<'a>(data: &'a [u8], index: u32, tag: &[u8; 4]) -> Option<&'a [u8]> {
+    let r = Reader(data);
+    let dir = directory(&r, index)?;
+    let num_tables = r.u16(dir + 4)? as usize;
+    for i in 0..num_tables {
+        let record = dir + 12 + 16 * i;
+        if r.bytes(record, 4)? == tag {
+            let offset = r.u32(record + 8)? as usize;
+            let len = r.u32(record + 12)? as usize;
+            return r.bytes(offset, len);
+        }
+    }
+    None
+}
@@ -1,6 +1,7 @@
 use crate::Rect;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub struct Size {
     pub width: f32,
     pub height: f32,
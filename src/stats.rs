@@ -0,0 +1,72 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::{MemoryUsage, StoreUsage};
+
+/// Number of live wrappers of one kind of MuPDF handle.
+#[derive(Debug)]
+pub(crate) struct Counter(AtomicUsize);
+
+impl Counter {
+    const fn new() -> Self {
+        Self(AtomicUsize::new(0))
+    }
+
+    pub(crate) fn acquire(&self) {
+        self.0.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn release(&self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    fn get(&self) -> usize {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+pub(crate) static DOCUMENTS: Counter = Counter::new();
+pub(crate) static PAGES: Counter = Counter::new();
+pub(crate) static PIXMAPS: Counter = Counter::new();
+
+static PIXMAP_BYTES: AtomicUsize = AtomicUsize::new(0);
+static PEAK_PIXMAP_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+pub(crate) fn pixmap_acquire(bytes: usize) {
+    PIXMAPS.acquire();
+    let total = PIXMAP_BYTES.fetch_add(bytes, Ordering::Relaxed) + bytes;
+    PEAK_PIXMAP_BYTES.fetch_max(total, Ordering::Relaxed);
+}
+
+pub(crate) fn pixmap_release(bytes: usize) {
+    PIXMAPS.release();
+    PIXMAP_BYTES.fetch_sub(bytes, Ordering::Relaxed);
+}
+
+/// A snapshot of the resources held by MuPDF across all threads.
+///
+/// Handles are counted per Rust wrapper, so a cloned [`Document`](crate::Document) counts twice
+/// even though both refer to the same underlying document.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResourceStats {
+    pub documents: usize,
+    pub pages: usize,
+    pub pixmaps: usize,
+    /// Bytes of sample data held by live pixmaps.
+    pub pixmap_bytes: usize,
+    /// Highest value `pixmap_bytes` has reached.
+    pub peak_pixmap_bytes: usize,
+    pub store: StoreUsage,
+    pub memory: MemoryUsage,
+}
+
+pub(crate) fn snapshot(store: StoreUsage, memory: MemoryUsage) -> ResourceStats {
+    ResourceStats {
+        documents: DOCUMENTS.get(),
+        pages: PAGES.get(),
+        pixmaps: PIXMAPS.get(),
+        pixmap_bytes: PIXMAP_BYTES.load(Ordering::Relaxed),
+        peak_pixmap_bytes: PEAK_PIXMAP_BYTES.load(Ordering::Relaxed),
+        store,
+        memory,
+    }
+}
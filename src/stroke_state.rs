@@ -6,6 +6,7 @@ use num_enum::TryFromPrimitive;
 use crate::{context, Error};
 
 #[derive(Debug, Clone, Copy, PartialEq, TryFromPrimitive)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 #[repr(u32)]
 pub enum LineCap {
     Butt = 0,
@@ -21,6 +22,7 @@ impl Default for LineCap {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, TryFromPrimitive)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 #[repr(u32)]
 pub enum LineJoin {
     Miter = 0,
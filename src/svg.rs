@@ -0,0 +1,205 @@
+use std::ffi::c_int;
+use std::io::Read;
+use std::ptr;
+
+use mupdf_sys::*;
+
+use crate::{context, Buffer, Error, FFIWrapper, Matrix, Page};
+
+/// How text is written to SVG.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SvgText {
+    /// Glyph outlines, which look exactly like the page everywhere but can't be selected,
+    /// searched or read out.
+    #[default]
+    Path,
+    /// `<text>` elements, selectable and accessible but drawn with whatever fonts the viewer has.
+    Text,
+}
+
+/// Unit of the width and height of the `<svg>` element, i.e. its physical size.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SvgUnits {
+    #[default]
+    Points,
+    /// CSS pixels, 96 to the inch.
+    Pixels,
+    Millimeters,
+    Inches,
+    /// No unit, so the image takes the size its container gives it.
+    None,
+}
+
+/// Options for [`Page::to_svg_with_options`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct SvgOptions {
+    text: SvgText,
+    reuse_images: bool,
+    id_prefix: String,
+    resolution: f32,
+    units: SvgUnits,
+}
+
+impl Default for SvgOptions {
+    fn default() -> Self {
+        Self {
+            text: SvgText::Path,
+            reuse_images: true,
+            id_prefix: String::new(),
+            resolution: 72.0,
+            units: SvgUnits::Points,
+        }
+    }
+}
+
+impl SvgOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_text(&mut self, text: SvgText) -> &mut Self {
+        self.text = text;
+        self
+    }
+
+    /// Write images used more than once a single time and refer to them, `true` by default.
+    pub fn set_reuse_images(&mut self, reuse: bool) -> &mut Self {
+        self.reuse_images = reuse;
+        self
+    }
+
+    /// Prefix for the `id` attributes of the clip paths, masks, images and glyphs the SVG
+    /// defines, so that several pages can be inlined into one HTML document without their ids
+    /// clashing.
+    pub fn set_id_prefix(&mut self, prefix: &str) -> &mut Self {
+        self.id_prefix = prefix.to_owned();
+        self
+    }
+
+    /// Drawing units per inch, `72.0` by default making one unit a point. This sets the scale of
+    /// the coordinates and `viewBox`, not the physical size of the image.
+    pub fn set_resolution(&mut self, dpi: f32) -> &mut Self {
+        self.resolution = dpi;
+        self
+    }
+
+    pub fn set_units(&mut self, units: SvgUnits) -> &mut Self {
+        self.units = units;
+        self
+    }
+
+    pub fn text(&self) -> SvgText {
+        self.text
+    }
+
+    pub fn reuse_images(&self) -> bool {
+        self.reuse_images
+    }
+
+    pub fn id_prefix(&self) -> &str {
+        &self.id_prefix
+    }
+
+    pub fn resolution(&self) -> f32 {
+        self.resolution
+    }
+
+    pub fn units(&self) -> SvgUnits {
+        self.units
+    }
+}
+
+/// Format a length given in points in `units`.
+fn length(points: f32, units: SvgUnits) -> String {
+    match units {
+        SvgUnits::Points => format!("{}pt", points),
+        SvgUnits::Pixels => format!("{}px", points * 96.0 / 72.0),
+        SvgUnits::Millimeters => format!("{}mm", points * 25.4 / 72.0),
+        SvgUnits::Inches => format!("{}in", points / 72.0),
+        SvgUnits::None => format!("{}", points),
+    }
+}
+
+/// Replace the value of `attr` in the first `<svg>` tag.
+fn set_root_attribute(svg: &mut String, attr: &str, value: &str) {
+    let Some(start) = svg.find("<svg") else {
+        return;
+    };
+    let end = svg[start..].find('>').map_or(svg.len(), |i| start + i);
+    let needle = format!(" {}=\"", attr);
+    if let Some(i) = svg[start..end].find(&needle) {
+        let value_start = start + i + needle.len();
+        if let Some(len) = svg[value_start..end].find('"') {
+            svg.replace_range(value_start..value_start + len, value);
+        }
+    }
+}
+
+impl Page {
+    /// Convert the page to SVG, choosing between fidelity and accessibility for the text and
+    /// controlling ids, coordinate scale and physical size.
+    pub fn to_svg_with_options(&self, options: &SvgOptions) -> Result<String, Error> {
+        let scale = options.resolution / 72.0;
+        let text_format = match options.text {
+            SvgText::Path => FZ_SVG_TEXT_AS_PATH,
+            SvgText::Text => FZ_SVG_TEXT_AS_TEXT,
+        };
+        let inner = unsafe {
+            ffi_try!(mupdf_page_to_svg(
+                context(),
+                self.as_ptr() as *mut _,
+                Matrix::new_scale(scale, scale).into(),
+                text_format as c_int,
+                options.reuse_images as c_int,
+                ptr::null_mut()
+            ))
+        }?;
+        let mut buf = unsafe { Buffer::from_raw(inner) };
+        let mut svg = String::new();
+        buf.read_to_string(&mut svg)?;
+
+        let bounds = self.bounds()?;
+        set_root_attribute(&mut svg, "width", &length(bounds.width(), options.units));
+        set_root_attribute(&mut svg, "height", &length(bounds.height(), options.units));
+
+        let prefix = &options.id_prefix;
+        if !prefix.is_empty() {
+            svg = svg
+                .replace(" id=\"", &format!(" id=\"{}", prefix))
+                .replace("href=\"#", &format!("href=\"#{}", prefix))
+                .replace("url(#", &format!("url(#{}", prefix));
+        }
+        Ok(svg)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{SvgOptions, SvgText, SvgUnits};
+    use crate::Document;
+
+    #[test]
+    fn test_page_to_svg_with_options() {
+        let doc = Document::open("tests/files/dummy.pdf").unwrap();
+        let page = doc.load_page(0).unwrap();
+
+        let svg = page.to_svg_with_options(&SvgOptions::new()).unwrap();
+        assert!(svg.contains("width=\"595pt\""));
+        assert!(!svg.contains("<text"));
+
+        let svg = page
+            .to_svg_with_options(
+                SvgOptions::new()
+                    .set_text(SvgText::Text)
+                    .set_id_prefix("p1-")
+                    .set_resolution(144.0)
+                    .set_units(SvgUnits::Inches),
+            )
+            .unwrap();
+        assert!(svg.contains("<text"));
+        assert!(svg.contains("Dummy"));
+        assert!(svg.contains("viewBox=\"0 0 1190 1684\""));
+        assert!(svg.contains(&format!("width=\"{}in\"", 595.0 / 72.0)));
+        assert!(!svg.contains(" id=\"clip"));
+    }
+}
@@ -0,0 +1,508 @@
+use std::collections::HashSet;
+use std::ops::Range;
+
+use crate::layout::{body_size, text_lines, CharInfo, Line};
+use crate::text_page::TextBlockType;
+use crate::{Rect, TextPage};
+
+/// Rules thinner than this, in points, are lines rather than boxes.
+const RULE_WIDTH: f32 = 3.0;
+
+/// Distance in points within which rules are considered to touch or line up.
+const RULE_TOLERANCE: f32 = 2.0;
+
+/// Horizontal gap between two runs of a line, in body text sizes, that makes them table cells.
+const CELL_GAP: f32 = 1.5;
+
+/// Tables whose cells average more words than this are more likely columns of prose.
+const MAX_WORDS_PER_CELL: f32 = 6.0;
+
+/// A cell of a [`Table`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct TableCell {
+    /// The area of the cell: its column's width by its row's height.
+    pub rect: Rect,
+    /// The text of the cell, lines joined with spaces.
+    pub text: String,
+    pub row: usize,
+    pub col: usize,
+}
+
+/// A table found by [`TextPage::tables`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct Table {
+    pub bounds: Rect,
+    pub rows: usize,
+    pub cols: usize,
+    /// The cells, row by row. Every row has `cols` cells, empty ones included.
+    pub cells: Vec<TableCell>,
+    /// Whether the table was found from its ruling lines rather than the alignment of its text.
+    pub ruled: bool,
+}
+
+impl Table {
+    pub fn cell(&self, row: usize, col: usize) -> Option<&TableCell> {
+        if row >= self.rows || col >= self.cols {
+            return None;
+        }
+        self.cells.get(row * self.cols + col)
+    }
+
+    /// The text of the cells, row by row.
+    pub fn to_rows(&self) -> Vec<Vec<String>> {
+        self.cells
+            .chunks(self.cols.max(1))
+            .map(|row| row.iter().map(|cell| cell.text.clone()).collect())
+            .collect()
+    }
+}
+
+/// The glyphs of a cell, line by line.
+pub(crate) type CellGlyphs<'a> = Vec<Vec<&'a CharInfo>>;
+
+/// A table and the lines of text, as (block, line) indices, it was made of.
+pub(crate) struct FoundTable<'a> {
+    pub table: Table,
+    pub lines: Vec<(usize, usize)>,
+    /// The glyphs of the cells, in the order of [`Table::cells`].
+    pub glyphs: Vec<CellGlyphs<'a>>,
+}
+
+impl TextPage {
+    /// Find the tables on the page.
+    ///
+    /// Tables drawn with ruling lines are found first, from a grid of horizontal and vertical
+    /// lines, which requires the text page to have been created with
+    /// [`TextPageOptions::COLLECT_VECTORS`](crate::TextPageOptions::COLLECT_VECTORS). The
+    /// rest of the text is then searched for tables without rules: consecutive lines whose
+    /// runs, separated by wide gaps, line up in the same columns. Cells spanning several
+    /// columns or rows are not recognized as such.
+    pub fn tables(&self) -> Vec<Table> {
+        let blocks = text_lines(self);
+        let body = body_size(&blocks);
+        find_tables(self, &blocks, body)
+            .into_iter()
+            .map(|found| found.table)
+            .collect()
+    }
+}
+
+pub(crate) fn find_tables<'a>(
+    page: &TextPage,
+    blocks: &'a [Vec<Line>],
+    body: f32,
+) -> Vec<FoundTable<'a>> {
+    let mut found = ruled_tables(page, blocks);
+    let used: HashSet<(usize, usize)> = found.iter().flat_map(|t| t.lines.clone()).collect();
+    found.extend(aligned_tables(blocks, body, &used));
+    found
+}
+
+/// The horizontal and vertical rules drawn on the page, as thin rectangles.
+fn rules(page: &TextPage) -> (Vec<Rect>, Vec<Rect>) {
+    let (mut horizontal, mut vertical) = (Vec::new(), Vec::new());
+    for block in page.blocks() {
+        if block.r#type() != TextBlockType::Vector {
+            continue;
+        }
+        let r = block.bounds();
+        if r.height() <= RULE_WIDTH && r.width() > RULE_WIDTH {
+            horizontal.push(r);
+        } else if r.width() <= RULE_WIDTH && r.height() > RULE_WIDTH {
+            vertical.push(r);
+        } else if block.is_stroked() && block.is_rectangle() {
+            horizontal.push(Rect::new(r.x0, r.y0, r.x1, r.y0));
+            horizontal.push(Rect::new(r.x0, r.y1, r.x1, r.y1));
+            vertical.push(Rect::new(r.x0, r.y0, r.x0, r.y1));
+            vertical.push(Rect::new(r.x1, r.y0, r.x1, r.y1));
+        }
+    }
+    (horizontal, vertical)
+}
+
+fn touch(a: &Rect, b: &Rect) -> bool {
+    a.x0 - RULE_TOLERANCE <= b.x1
+        && b.x0 - RULE_TOLERANCE <= a.x1
+        && a.y0 - RULE_TOLERANCE <= b.y1
+        && b.y0 - RULE_TOLERANCE <= a.y1
+}
+
+/// Sorted positions, merging those closer than the tolerance.
+fn cluster(mut positions: Vec<f32>) -> Vec<f32> {
+    positions.sort_by(f32::total_cmp);
+    let mut clusters: Vec<(f32, usize)> = Vec::new();
+    for p in positions {
+        match clusters.last_mut() {
+            Some((sum, n)) if p - *sum / *n as f32 <= RULE_TOLERANCE => {
+                *sum += p;
+                *n += 1;
+            }
+            _ => clusters.push((p, 1)),
+        }
+    }
+    clusters
+        .into_iter()
+        .map(|(sum, n)| sum / n as f32)
+        .collect()
+}
+
+/// Index of the interval between consecutive `edges` containing `p`.
+fn interval(edges: &[f32], p: f32) -> Option<usize> {
+    edges.windows(2).position(|w| p >= w[0] && p < w[1])
+}
+
+fn cell_text(glyphs: &CellGlyphs) -> String {
+    let text: Vec<String> = glyphs
+        .iter()
+        .map(|line| line.iter().map(|g| g.c).collect())
+        .collect();
+    text.join(" ")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn ruled_tables<'a>(page: &TextPage, blocks: &'a [Vec<Line>]) -> Vec<FoundTable<'a>> {
+    let (horizontal, vertical) = rules(page);
+    let rules: Vec<(Rect, bool)> = horizontal
+        .into_iter()
+        .map(|r| (r, true))
+        .chain(vertical.into_iter().map(|r| (r, false)))
+        .collect();
+
+    // Group the rules into grids of connected lines
+    let mut group: Vec<usize> = (0..rules.len()).collect();
+    fn root(group: &mut [usize], mut i: usize) -> usize {
+        while group[i] != i {
+            group[i] = group[group[i]];
+            i = group[i];
+        }
+        i
+    }
+    for i in 0..rules.len() {
+        for j in i + 1..rules.len() {
+            if touch(&rules[i].0, &rules[j].0) {
+                let (a, b) = (root(&mut group, i), root(&mut group, j));
+                group[a] = b;
+            }
+        }
+    }
+    let mut grids: Vec<(usize, Vec<f32>, Vec<f32>)> = Vec::new();
+    for (i, &(r, is_horizontal)) in rules.iter().enumerate() {
+        let g = root(&mut group, i);
+        let index = match grids.iter().position(|(id, _, _)| *id == g) {
+            Some(index) => index,
+            None => {
+                grids.push((g, Vec::new(), Vec::new()));
+                grids.len() - 1
+            }
+        };
+        if is_horizontal {
+            grids[index].1.push((r.y0 + r.y1) / 2.0);
+        } else {
+            grids[index].2.push((r.x0 + r.x1) / 2.0);
+        }
+    }
+
+    let mut tables = Vec::new();
+    for (_, ys, xs) in grids {
+        let (ys, xs) = (cluster(ys), cluster(xs));
+        if ys.len() < 2 || xs.len() < 2 || (ys.len() - 1) * (xs.len() - 1) < 2 {
+            continue;
+        }
+        let (rows, cols) = (ys.len() - 1, xs.len() - 1);
+        let mut glyphs: Vec<CellGlyphs> = vec![Vec::new(); rows * cols];
+        let mut lines = Vec::new();
+        for (b, block) in blocks.iter().enumerate() {
+            for (l, line) in block.iter().enumerate() {
+                let mut touched: Vec<(usize, Vec<&CharInfo>)> = Vec::new();
+                for g in &line.glyphs {
+                    let r = Rect::from(g.quad.clone());
+                    let (x, y) = ((r.x0 + r.x1) / 2.0, (r.y0 + r.y1) / 2.0);
+                    if let (Some(row), Some(col)) = (interval(&ys, y), interval(&xs, x)) {
+                        let cell = row * cols + col;
+                        match touched.iter_mut().find(|(c, _)| *c == cell) {
+                            Some((_, cell_glyphs)) => cell_glyphs.push(g),
+                            None => touched.push((cell, vec![g])),
+                        }
+                    }
+                }
+                if !touched.is_empty() {
+                    lines.push((b, l));
+                }
+                for (cell, cell_glyphs) in touched {
+                    glyphs[cell].push(cell_glyphs);
+                }
+            }
+        }
+        let texts: Vec<String> = glyphs.iter().map(cell_text).collect();
+        if texts.iter().all(|t| t.is_empty()) {
+            // A frame around something else
+            continue;
+        }
+        let cells = texts
+            .into_iter()
+            .enumerate()
+            .map(|(i, text)| {
+                let (row, col) = (i / cols, i % cols);
+                TableCell {
+                    rect: Rect::new(xs[col], ys[row], xs[col + 1], ys[row + 1]),
+                    text,
+                    row,
+                    col,
+                }
+            })
+            .collect();
+        tables.push(FoundTable {
+            table: Table {
+                bounds: Rect::new(xs[0], ys[0], xs[cols], ys[rows]),
+                rows,
+                cols,
+                cells,
+                ruled: true,
+            },
+            lines,
+            glyphs,
+        });
+    }
+    tables
+}
+
+/// A run of a line separated from the rest by a wide gap.
+struct Run<'a> {
+    glyphs: &'a [CharInfo],
+    bounds: Rect,
+}
+
+impl Run<'_> {
+    fn overlaps(&self, other: &Run) -> bool {
+        self.bounds.x0 <= other.bounds.x1 && other.bounds.x0 <= self.bounds.x1
+    }
+
+    fn text(&self) -> String {
+        let text: String = self.glyphs.iter().map(|g| g.c).collect();
+        text.split_whitespace().collect::<Vec<_>>().join(" ")
+    }
+}
+
+fn runs(line: &Line, gap: f32) -> Vec<Run> {
+    let mut runs = Vec::new();
+    let mut start = None;
+    let mut bounds = Rect::default();
+    let mut end = 0;
+    for (i, g) in line.glyphs.iter().enumerate() {
+        if g.c.is_whitespace() {
+            continue;
+        }
+        let r = Rect::from(g.quad.clone());
+        match start {
+            Some(s) if r.x0 - bounds.x1 > gap => {
+                runs.push(Run {
+                    glyphs: &line.glyphs[s..end],
+                    bounds,
+                });
+                start = Some(i);
+                bounds = r;
+            }
+            Some(_) => {
+                bounds.union(r);
+            }
+            None => {
+                start = Some(i);
+                bounds = r;
+            }
+        }
+        end = i + 1;
+    }
+    if let Some(s) = start {
+        runs.push(Run {
+            glyphs: &line.glyphs[s..end],
+            bounds,
+        });
+    }
+    runs
+}
+
+/// Lines sharing a baseline, as (block, line) indices, and their runs from left to right.
+struct Row<'a> {
+    lines: Vec<(usize, usize)>,
+    runs: Vec<Run<'a>>,
+}
+
+impl Row<'_> {
+    fn aligned_with(&self, other: &Row) -> bool {
+        self.runs.len() == other.runs.len()
+            && self
+                .runs
+                .iter()
+                .zip(&other.runs)
+                .all(|(a, b)| a.overlaps(b))
+    }
+}
+
+fn baseline(line: &Line) -> f32 {
+    line.glyphs
+        .iter()
+        .find(|g| !g.c.is_whitespace())
+        .map_or(line.bounds.y1, |g| g.origin.y)
+}
+
+fn rows<'a>(blocks: &'a [Vec<Line>], body: f32, used: &HashSet<(usize, usize)>) -> Vec<Row<'a>> {
+    let mut order: Vec<(usize, usize)> = blocks
+        .iter()
+        .enumerate()
+        .flat_map(|(b, lines)| (0..lines.len()).map(move |l| (b, l)))
+        .filter(|line| !used.contains(line))
+        .collect();
+    order.sort_by(|&(b1, l1), &(b2, l2)| {
+        baseline(&blocks[b1][l1]).total_cmp(&baseline(&blocks[b2][l2]))
+    });
+
+    let mut rows: Vec<Row> = Vec::new();
+    let mut last = f32::NAN;
+    for (b, l) in order {
+        let y = baseline(&blocks[b][l]);
+        match rows.last_mut() {
+            Some(row) if (y - last).abs() <= body * 0.5 => row.lines.push((b, l)),
+            _ => rows.push(Row {
+                lines: vec![(b, l)],
+                runs: Vec::new(),
+            }),
+        }
+        last = y;
+    }
+    for row in &mut rows {
+        row.runs = row
+            .lines
+            .iter()
+            .flat_map(|&(b, l)| runs(&blocks[b][l], body * CELL_GAP))
+            .collect();
+        row.runs.sort_by(|a, b| a.bounds.x0.total_cmp(&b.bounds.x0));
+    }
+    rows
+}
+
+/// Runs of at least two consecutive rows with the same columns.
+fn aligned_ranges(rows: &[Row]) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    let mut start = 0;
+    while start < rows.len() {
+        let mut end = start + 1;
+        if rows[start].runs.len() >= 2 {
+            while end < rows.len() && rows[end].aligned_with(&rows[end - 1]) {
+                end += 1;
+            }
+        }
+        let runs: Vec<&Run> = rows[start..end].iter().flat_map(|r| &r.runs).collect();
+        let words: usize = runs.iter().map(|r| r.text().split(' ').count()).sum();
+        if end - start >= 2 && words as f32 / runs.len() as f32 <= MAX_WORDS_PER_CELL {
+            ranges.push(start..end);
+        }
+        start = end;
+    }
+    ranges
+}
+
+fn aligned_tables<'a>(
+    blocks: &'a [Vec<Line>],
+    body: f32,
+    used: &HashSet<(usize, usize)>,
+) -> Vec<FoundTable<'a>> {
+    let rows = rows(blocks, body, used);
+    aligned_ranges(&rows)
+        .into_iter()
+        .map(|range| {
+            let rows = &rows[range];
+            let cols = rows[0].runs.len();
+            let mut col_x = vec![(f32::INFINITY, f32::NEG_INFINITY); cols];
+            let mut row_y = vec![(f32::INFINITY, f32::NEG_INFINITY); rows.len()];
+            for (r, row) in rows.iter().enumerate() {
+                for (c, run) in row.runs.iter().enumerate() {
+                    col_x[c] = (col_x[c].0.min(run.bounds.x0), col_x[c].1.max(run.bounds.x1));
+                    row_y[r] = (row_y[r].0.min(run.bounds.y0), row_y[r].1.max(run.bounds.y1));
+                }
+            }
+            let cells = rows
+                .iter()
+                .enumerate()
+                .flat_map(|(r, row)| {
+                    let (col_x, row_y) = (&col_x, &row_y);
+                    row.runs.iter().enumerate().map(move |(c, run)| TableCell {
+                        rect: Rect::new(col_x[c].0, row_y[r].0, col_x[c].1, row_y[r].1),
+                        text: run.text(),
+                        row: r,
+                        col: c,
+                    })
+                })
+                .collect();
+            FoundTable {
+                table: Table {
+                    bounds: Rect::new(
+                        col_x[0].0,
+                        row_y[0].0,
+                        col_x[cols - 1].1,
+                        row_y[rows.len() - 1].1,
+                    ),
+                    rows: rows.len(),
+                    cols,
+                    cells,
+                    ruled: false,
+                },
+                lines: rows.iter().flat_map(|row| row.lines.clone()).collect(),
+                glyphs: rows
+                    .iter()
+                    .flat_map(|row| &row.runs)
+                    .map(|run| vec![run.glyphs.iter().collect()])
+                    .collect(),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::cluster;
+    use crate::pdf::{PageOptions, PdfDocument};
+    use crate::TextPageOptions;
+
+    #[test]
+    fn test_cluster() {
+        assert_eq!(
+            cluster(vec![10.0, 50.0, 11.0, 30.0]),
+            vec![10.5, 30.0, 50.0]
+        );
+    }
+
+    #[test]
+    fn test_text_page_tables() {
+        let html = "<p>Statement of income</p>\
+            <table>\
+            <tr><td>Item</td><td>2023</td><td>2024</td></tr>\
+            <tr><td>Revenue</td><td>100</td><td>120</td></tr>\
+            <tr><td>Costs</td><td>70</td><td>80</td></tr>\
+            </table>";
+        let css = "td { padding: 2pt 3em }";
+        for (css, ruled) in [
+            (css.to_owned(), false),
+            (format!("{} td {{ border: 1pt solid black }}", css), true),
+        ] {
+            let doc = PdfDocument::from_html(html, &css, &PageOptions::new()).unwrap();
+            let tables = doc
+                .load_page(0)
+                .unwrap()
+                .to_text_page(TextPageOptions::COLLECT_VECTORS)
+                .unwrap()
+                .tables();
+            assert_eq!(tables.len(), 1);
+            let table = &tables[0];
+            assert_eq!(table.ruled, ruled);
+            assert_eq!((table.rows, table.cols), (3, 3));
+            assert_eq!(table.to_rows()[1], vec!["Revenue", "100", "120"]);
+            let cell = table.cell(2, 2).unwrap();
+            assert_eq!((cell.text.as_str(), cell.row, cell.col), ("80", 2, 2));
+            assert!(table.cell(3, 0).is_none());
+        }
+    }
+}
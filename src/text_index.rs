@@ -0,0 +1,240 @@
+use crate::{Document, Error, Location, Quad, Rect, SearchHit, TextPageOptions};
+
+/// Start of the binary format of [`TextIndex::to_bytes`].
+const MAGIC: &[u8; 4] = b"MTXI";
+const VERSION: u8 = 1;
+
+/// A word of a page and where it is.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct IndexedWord {
+    pub text: String,
+    pub bbox: Rect,
+}
+
+/// The words of a page, in reading order.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct PageWords {
+    pub location: Location,
+    pub words: Vec<IndexedWord>,
+}
+
+/// The words of every page of a document with their positions, to be stored next to the
+/// document and searched without extracting its text again.
+///
+/// An index is stored with [`TextIndex::to_bytes`], or as JSON through `serde` with the `serde`
+/// feature.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct TextIndex {
+    pub pages: Vec<PageWords>,
+}
+
+impl TextIndex {
+    /// Extract the words of every page of `doc`, chapter by chapter.
+    pub fn from_document(doc: &Document) -> Result<Self, Error> {
+        let mut pages = Vec::new();
+        for chapter in 0..doc.chapter_count()? {
+            for page in 0..doc.chapter_page_count(chapter)? {
+                let location = Location { chapter, page };
+                let text_page = doc
+                    .load_location(location)?
+                    .to_text_page(TextPageOptions::empty())?;
+                let mut words = Vec::new();
+                for block in text_page.blocks() {
+                    for line in block.lines() {
+                        let mut word: Option<IndexedWord> = None;
+                        for ch in line.chars() {
+                            let c = ch.char().unwrap_or(char::REPLACEMENT_CHARACTER);
+                            if c.is_whitespace() {
+                                words.extend(word.take());
+                                continue;
+                            }
+                            let bbox = Rect::from(ch.quad());
+                            match word.as_mut() {
+                                Some(word) => {
+                                    word.text.push(c);
+                                    word.bbox.r#union(bbox);
+                                }
+                                None => {
+                                    word = Some(IndexedWord {
+                                        text: c.to_string(),
+                                        bbox,
+                                    })
+                                }
+                            }
+                        }
+                        words.extend(word);
+                    }
+                }
+                pages.push(PageWords { location, words });
+            }
+        }
+        Ok(Self { pages })
+    }
+
+    /// Find `needle` in the index, ignoring case. A needle of several words matches runs of
+    /// words of a page that start with the end of its first word, end with the start of its
+    /// last word and have its other words in between, like the search of a text page does.
+    pub fn search(&self, needle: &str) -> Vec<SearchHit> {
+        let needle: Vec<String> = needle.split_whitespace().map(str::to_lowercase).collect();
+        let mut hits = Vec::new();
+        if needle.is_empty() {
+            return hits;
+        }
+        for page in &self.pages {
+            let words: Vec<String> = page.words.iter().map(|w| w.text.to_lowercase()).collect();
+            for start in 0..words.len().saturating_sub(needle.len() - 1) {
+                let candidate = &words[start..start + needle.len()];
+                if phrase_matches(candidate, &needle) {
+                    hits.push(SearchHit {
+                        location: page.location,
+                        quads: page.words[start..start + needle.len()]
+                            .iter()
+                            .map(|w| Quad::from(w.bbox))
+                            .collect(),
+                    });
+                }
+            }
+        }
+        hits
+    }
+
+    /// Encode the index in a compact binary format, read back by [`TextIndex::from_bytes`].
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(MAGIC);
+        out.push(VERSION);
+        out.extend_from_slice(&(self.pages.len() as u32).to_le_bytes());
+        for page in &self.pages {
+            out.extend_from_slice(&page.location.chapter.to_le_bytes());
+            out.extend_from_slice(&page.location.page.to_le_bytes());
+            out.extend_from_slice(&(page.words.len() as u32).to_le_bytes());
+            for word in &page.words {
+                out.extend_from_slice(&(word.text.len() as u32).to_le_bytes());
+                out.extend_from_slice(word.text.as_bytes());
+                for v in [word.bbox.x0, word.bbox.y0, word.bbox.x1, word.bbox.y1] {
+                    out.extend_from_slice(&v.to_le_bytes());
+                }
+            }
+        }
+        out
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let mut reader = Reader { bytes };
+        if reader.take(MAGIC.len())? != MAGIC {
+            return Err(Error::InvalidTextIndex("not a text index".to_owned()));
+        }
+        let version = reader.take(1)?[0];
+        if version != VERSION {
+            return Err(Error::InvalidTextIndex(format!(
+                "unsupported version {}",
+                version
+            )));
+        }
+        let page_count = reader.u32()?;
+        let mut pages = Vec::new();
+        for _ in 0..page_count {
+            let location = Location {
+                chapter: reader.i32()?,
+                page: reader.i32()?,
+            };
+            let word_count = reader.u32()?;
+            let mut words = Vec::new();
+            for _ in 0..word_count {
+                let len = reader.u32()? as usize;
+                let text = std::str::from_utf8(reader.take(len)?)
+                    .map_err(|_| Error::InvalidUtf8)?
+                    .to_owned();
+                let bbox = Rect::new(reader.f32()?, reader.f32()?, reader.f32()?, reader.f32()?);
+                words.push(IndexedWord { text, bbox });
+            }
+            pages.push(PageWords { location, words });
+        }
+        if !reader.bytes.is_empty() {
+            return Err(Error::InvalidTextIndex(
+                "trailing data after the index".to_owned(),
+            ));
+        }
+        Ok(Self { pages })
+    }
+}
+
+fn phrase_matches(words: &[String], needle: &[String]) -> bool {
+    match (words, needle) {
+        ([word], [n]) => word.contains(n.as_str()),
+        ([first, middle @ .., last], [n_first, n_middle @ .., n_last]) => {
+            first.ends_with(n_first.as_str())
+                && middle == n_middle
+                && last.starts_with(n_last.as_str())
+        }
+        _ => false,
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        if self.bytes.len() < len {
+            return Err(Error::InvalidTextIndex("truncated data".to_owned()));
+        }
+        let (head, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(head)
+    }
+
+    fn array(&mut self) -> Result<[u8; 4], Error> {
+        Ok(self.take(4)?.try_into().unwrap())
+    }
+
+    fn u32(&mut self) -> Result<u32, Error> {
+        self.array().map(u32::from_le_bytes)
+    }
+
+    fn i32(&mut self) -> Result<i32, Error> {
+        self.array().map(i32::from_le_bytes)
+    }
+
+    fn f32(&mut self) -> Result<f32, Error> {
+        self.array().map(f32::from_le_bytes)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::TextIndex;
+    use crate::{Document, Error};
+
+    #[test]
+    fn test_text_index() {
+        let doc = Document::open("tests/files/dummy.pdf").unwrap();
+        let index = TextIndex::from_document(&doc).unwrap();
+        assert_eq!(index.pages.len(), 1);
+        let words: Vec<&str> = index.pages[0]
+            .words
+            .iter()
+            .map(|w| w.text.as_str())
+            .collect();
+        assert_eq!(words, ["Dummy", "PDF", "file"]);
+
+        let bytes = index.to_bytes();
+        let read = TextIndex::from_bytes(&bytes).unwrap();
+        assert_eq!(read, index);
+        assert!(matches!(
+            TextIndex::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(Error::InvalidTextIndex(_))
+        ));
+
+        let hits = read.search("my pdf F");
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].quads.len(), 3);
+        assert!((56.0..58.0).contains(&hits[0].quads[0].ul.x));
+        assert_eq!(read.search("pdf").len(), 1);
+        assert!(read.search("file pdf").is_empty());
+    }
+}
@@ -13,11 +13,13 @@ use num_enum::TryFromPrimitive;
 
 use crate::FFIAnalogue;
 use crate::{
-    context, rust_slice_to_ffi_ptr, Buffer, Error, Image, Matrix, Point, Quad, Rect, WriteMode,
+    context, rust_slice_to_ffi_ptr, Buffer, Error, Font, Image, Matrix, Point, Quad, Rect,
+    WriteMode,
 };
 
 bitflags! {
     /// Options for creating a pixmap and draw device.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
     pub struct TextPageOptions: u32 {
         const BLOCK_IMAGE = FZ_STEXT_BLOCK_IMAGE as _;
         const BLOCK_TEXT = FZ_STEXT_BLOCK_TEXT as _;
@@ -25,9 +27,84 @@ bitflags! {
         const PRESERVE_IMAGES = FZ_STEXT_PRESERVE_IMAGES as _;
         const PRESERVE_LIGATURES = FZ_STEXT_PRESERVE_LIGATURES as _;
         const PRESERVE_WHITESPACE = FZ_STEXT_PRESERVE_WHITESPACE as _;
+        /// Add a [`TextBlockType::Vector`] block for every path drawn on the page, which is how
+        /// [`TextPage::tables`] finds ruling lines.
+        const COLLECT_VECTORS = FZ_STEXT_COLLECT_VECTORS as _;
     }
 }
 
+bitflags! {
+    /// Styling of a [`TextChar`].
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct TextCharFlags: u32 {
+        const STRIKEOUT = FZ_STEXT_STRIKEOUT as _;
+        const UNDERLINE = FZ_STEXT_UNDERLINE as _;
+        /// Not in the document but inserted by MuPDF, e.g. a space for a gap between words.
+        const SYNTHETIC = FZ_STEXT_SYNTHETIC as _;
+        /// Set in a bold font, or emboldened by stroking the outline along with filling it.
+        const BOLD = FZ_STEXT_BOLD as _;
+        const FILLED = FZ_STEXT_FILLED as _;
+        const STROKED = FZ_STEXT_STROKED as _;
+        /// Used as a clipping path.
+        const CLIPPED = FZ_STEXT_CLIPPED as _;
+    }
+}
+
+/// How a character is painted, like the PDF text rendering mode (`Tr`) without clipping, which
+/// is given by [`TextCharFlags::CLIPPED`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub enum TextRenderMode {
+    Fill,
+    Stroke,
+    FillStroke,
+    /// Neither filled nor stroked, like the text layer OCR software puts over scanned pages.
+    Invisible,
+}
+
+/// Position of a character relative to the baseline of its line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub enum CharPosition {
+    Baseline,
+    Superscript,
+    Subscript,
+}
+
+/// How finely [`TextPage::text_runs`] splits the text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuadGranularity {
+    Char,
+    Word,
+    Line,
+}
+
+/// A piece of text with a single quad covering it.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct TextRun {
+    pub text: String,
+    pub quad: Quad,
+}
+
+/// A run of characters of a line in the same font, size, color and rendering mode.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct StyledSpan {
+    pub text: String,
+    pub bounds: Rect,
+    pub font_name: String,
+    pub size: f32,
+    /// Fill color as `0xAARRGGBB`.
+    pub argb: u32,
+    pub render_mode: TextRenderMode,
+    /// Whether the text is neither filled nor stroked, i.e. drawn with PDF rendering mode 3 or 7.
+    pub invisible: bool,
+    /// Whether the span is likely part of a text layer added by OCR software: invisible text
+    /// over an image, or text in the GlyphLessFont Tesseract and MuPDF use for OCR output.
+    pub likely_ocr: bool,
+}
+
 /// A text page is a list of blocks, together with an overall bounding box
 #[derive(Debug)]
 pub struct TextPage {
@@ -39,6 +116,13 @@ impl TextPage {
         Self { inner: ptr }
     }
 
+    /// An empty text page covering `media_box`, to be filled by running a device created with
+    /// [`Device::from_text_page`](crate::Device::from_text_page).
+    pub fn new(media_box: Rect) -> Result<Self, Error> {
+        unsafe { ffi_try!(mupdf_new_stext_page(context(), media_box.into())) }
+            .map(|inner| Self { inner })
+    }
+
     pub fn to_text(&self) -> Result<String, Error> {
         let inner = unsafe { ffi_try!(mupdf_stext_page_to_text(context(), self.inner)) }?;
         let mut buf = unsafe { Buffer::from_raw(inner) };
@@ -47,6 +131,10 @@ impl TextPage {
         Ok(text)
     }
 
+    pub fn bounds(&self) -> Rect {
+        unsafe { (*self.inner).mediabox }.into()
+    }
+
     pub fn blocks(&self) -> TextBlockIter {
         TextBlockIter {
             next: unsafe { (*self.inner).first_block },
@@ -54,6 +142,127 @@ impl TextPage {
         }
     }
 
+    /// The text of the page as words or lines, each with one quad instead of one per character,
+    /// e.g. to build a much smaller text layer for a web viewer.
+    ///
+    /// Consecutive characters of a line are only merged if the gap between them is at most
+    /// `tolerance` times the font size, so that columns sharing a baseline stay apart. Words don't
+    /// include the whitespace separating them.
+    pub fn text_runs(&self, granularity: QuadGranularity, tolerance: f32) -> Vec<TextRun> {
+        let mut runs: Vec<TextRun> = self
+            .line_runs(granularity, tolerance)
+            .into_iter()
+            .flatten()
+            .collect();
+        if granularity == QuadGranularity::Line {
+            runs.retain(|run| !run.text.trim().is_empty());
+        }
+        runs
+    }
+
+    /// [`TextPage::text_runs`] grouped by the line they are on.
+    pub(crate) fn line_runs(
+        &self,
+        granularity: QuadGranularity,
+        tolerance: f32,
+    ) -> Vec<Vec<TextRun>> {
+        let mut lines = Vec::new();
+        for block in self.blocks() {
+            for line in block.lines() {
+                let mut runs = Vec::new();
+                let mut current: Option<TextRun> = None;
+                for ch in line.chars() {
+                    let c = ch.char().unwrap_or(char::REPLACEMENT_CHARACTER);
+                    if granularity == QuadGranularity::Word && c.is_whitespace() {
+                        runs.extend(current.take());
+                        continue;
+                    }
+                    let quad = ch.quad();
+                    if let Some(run) = current.as_mut() {
+                        let (dx, dy) = (quad.ul.x - run.quad.ur.x, quad.ul.y - run.quad.ur.y);
+                        let gap = (dx * dx + dy * dy).sqrt();
+                        if granularity != QuadGranularity::Char
+                            && gap <= tolerance * ch.size().max(1.0)
+                        {
+                            run.text.push(c);
+                            run.quad.ur = quad.ur;
+                            run.quad.lr = quad.lr;
+                            continue;
+                        }
+                    }
+                    runs.extend(current.replace(TextRun {
+                        text: c.to_string(),
+                        quad,
+                    }));
+                }
+                runs.extend(current);
+                lines.push(runs);
+            }
+        }
+        lines
+    }
+
+    /// The text of the page split into runs of uniform style, telling visible text apart from
+    /// hidden text such as the invisible layer of scanned and OCRed pages.
+    ///
+    /// Invisible text is only recognized as OCR output by the image under it if the text page
+    /// was created with [`TextPageOptions::PRESERVE_IMAGES`].
+    pub fn styled_spans(&self) -> Vec<StyledSpan> {
+        let images: Vec<Rect> = self
+            .blocks()
+            .filter(|block| block.r#type() == TextBlockType::Image)
+            .map(|block| block.bounds())
+            .collect();
+        let paint = TextCharFlags::FILLED | TextCharFlags::STROKED | TextCharFlags::CLIPPED;
+
+        let mut spans = Vec::new();
+        for block in self.blocks() {
+            for line in block.lines() {
+                let mut current: Option<(StyledSpan, *mut fz_font, TextCharFlags)> = None;
+                for ch in line.chars() {
+                    let c = ch.char().unwrap_or(char::REPLACEMENT_CHARACTER);
+                    let quad = Rect::from(ch.quad());
+                    let flags = ch.flags() & paint;
+                    if let Some((span, font, span_flags)) = current.as_mut() {
+                        // Spaces inserted by MuPDF have no style of their own
+                        if ch.flags().contains(TextCharFlags::SYNTHETIC)
+                            || (*font == ch.inner.font
+                                && span.size == ch.size()
+                                && span.argb == ch.argb()
+                                && *span_flags == flags)
+                        {
+                            span.text.push(c);
+                            span.bounds.union(quad);
+                            continue;
+                        }
+                    }
+                    let render_mode = ch.render_mode();
+                    let span = StyledSpan {
+                        text: c.to_string(),
+                        bounds: quad,
+                        font_name: ch.font().name().to_owned(),
+                        size: ch.size(),
+                        argb: ch.argb(),
+                        render_mode,
+                        invisible: render_mode == TextRenderMode::Invisible,
+                        likely_ocr: false,
+                    };
+                    spans.extend(current.replace((span, ch.inner.font, flags)).map(|c| c.0));
+                }
+                spans.extend(current.map(|c| c.0));
+            }
+        }
+        for span in &mut spans {
+            let (x, y) = (
+                (span.bounds.x0 + span.bounds.x1) / 2.0,
+                (span.bounds.y0 + span.bounds.y1) / 2.0,
+            );
+            span.likely_ocr = span.font_name.contains("GlyphLess")
+                || (span.invisible && images.iter().any(|image| image.contains(x, y)));
+        }
+        spans
+    }
+
     pub fn search(&self, needle: &str) -> Result<Vec<Quad>, Error> {
         let mut vec = Vec::new();
         self.search_cb(needle, &mut vec, |v, quads| {
@@ -161,6 +370,33 @@ impl TextPage {
         .map(|count| count as u32)
     }
 
+    /// The quads covering the text selected by dragging from `a` to `b`, one per line of
+    /// text, as used for text markup annotations.
+    pub fn selection_quads(&self, a: Point, b: Point) -> Result<Vec<Quad>, Error> {
+        const MAX_QUADS: usize = 1024;
+        let mut quads = vec![
+            fz_quad {
+                ul: fz_point { x: 0.0, y: 0.0 },
+                ur: fz_point { x: 0.0, y: 0.0 },
+                ll: fz_point { x: 0.0, y: 0.0 },
+                lr: fz_point { x: 0.0, y: 0.0 },
+            };
+            MAX_QUADS
+        ];
+        let count = unsafe {
+            ffi_try!(mupdf_highlight_selection(
+                context(),
+                self.inner,
+                a.into(),
+                b.into(),
+                quads.as_mut_ptr(),
+                MAX_QUADS as i32
+            ))
+        }?;
+        quads.truncate(count.max(0) as usize);
+        Ok(quads.into_iter().map(Quad::from).collect())
+    }
+
     pub fn highlight_selection(
         &mut self,
         a: Point,
@@ -198,11 +434,17 @@ pub enum SearchHitResponse {
     AbortSearch = 1,
 }
 
+/// The kind of a [`TextBlock`]. MuPDF adds kinds of blocks over time, so matches on this need a
+/// wildcard arm.
 #[derive(Debug, Clone, Copy, PartialEq, TryFromPrimitive)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 #[repr(u32)]
+#[non_exhaustive]
 pub enum TextBlockType {
     Text = FZ_STEXT_BLOCK_TEXT as u32,
     Image = FZ_STEXT_BLOCK_IMAGE as u32,
+    /// The bounds of a path, collected with [`TextPageOptions::COLLECT_VECTORS`].
+    Vector = FZ_STEXT_BLOCK_VECTOR as u32,
 }
 
 /// A text block is a list of lines of text (typically a paragraph), or an image.
@@ -243,6 +485,25 @@ impl TextBlock<'_> {
         None
     }
 
+    /// Whether this is a vector block of a stroked, rather than filled, path.
+    pub fn is_stroked(&self) -> bool {
+        self.vector_flags() & FZ_STEXT_VECTOR_IS_STROKED as u32 != 0
+    }
+
+    /// Whether this is a vector block of an axis-aligned rectangle.
+    pub fn is_rectangle(&self) -> bool {
+        self.vector_flags() & FZ_STEXT_VECTOR_IS_RECTANGLE as u32 != 0
+    }
+
+    fn vector_flags(&self) -> u32 {
+        unsafe {
+            if self.inner.type_ == FZ_STEXT_BLOCK_VECTOR as i32 {
+                return self.inner.u.v.flags as u32;
+            }
+        }
+        0
+    }
+
     pub fn image(&self) -> Option<Image> {
         unsafe {
             if self.inner.type_ == FZ_STEXT_BLOCK_IMAGE as i32 {
@@ -295,6 +556,42 @@ impl TextLine<'_> {
             _marker: PhantomData,
         }
     }
+
+    /// Unit vector of the direction of the text.
+    pub fn dir(&self) -> Point {
+        self.inner.dir.into()
+    }
+
+    /// Whether each character sits on the baseline or is a superscript or subscript, i.e. set
+    /// smaller than the largest characters of the line and raised or lowered from their
+    /// baseline.
+    pub fn char_positions(&self) -> Vec<CharPosition> {
+        let Some(reference) = self
+            .chars()
+            .filter(|ch| ch.char().is_some_and(|c| !c.is_whitespace()))
+            .max_by(|a, b| a.size().total_cmp(&b.size()))
+        else {
+            return self.chars().map(|_| CharPosition::Baseline).collect();
+        };
+        let (size, base) = (reference.size(), reference.origin());
+        let dir = self.dir();
+        self.chars()
+            .map(|ch| {
+                let origin = ch.origin();
+                // Distance from the reference baseline, downwards in page coordinates
+                let offset = (origin.y - base.y) * dir.x - (origin.x - base.x) * dir.y;
+                if ch.size() >= size * 0.85 {
+                    CharPosition::Baseline
+                } else if offset < -size * 0.15 {
+                    CharPosition::Superscript
+                } else if offset > size * 0.1 {
+                    CharPosition::Subscript
+                } else {
+                    CharPosition::Baseline
+                }
+            })
+            .collect()
+    }
 }
 
 #[derive(Debug)]
@@ -339,6 +636,71 @@ impl TextChar<'_> {
     pub fn quad(&self) -> Quad {
         self.inner.quad.into()
     }
+
+    pub fn font(&self) -> Font {
+        unsafe {
+            fz_keep_font(context(), self.inner.font);
+            Font::from_raw(self.inner.font)
+        }
+    }
+
+    pub fn flags(&self) -> TextCharFlags {
+        TextCharFlags::from_bits_truncate(self.inner.flags as u32)
+    }
+
+    /// Fill color as `0xAARRGGBB`.
+    pub fn argb(&self) -> u32 {
+        self.inner.argb as u32
+    }
+
+    pub fn render_mode(&self) -> TextRenderMode {
+        let flags = self.flags();
+        match (
+            flags.contains(TextCharFlags::FILLED),
+            flags.contains(TextCharFlags::STROKED),
+        ) {
+            (true, false) => TextRenderMode::Fill,
+            (false, true) => TextRenderMode::Stroke,
+            (true, true) => TextRenderMode::FillStroke,
+            (false, false) => TextRenderMode::Invisible,
+        }
+    }
+
+    /// Whether the character is bold, either set in a bold font or emboldened.
+    pub fn is_bold(&self) -> bool {
+        self.flags().contains(TextCharFlags::BOLD) || self.font().is_bold()
+    }
+
+    /// Whether the character is emboldened by stroking a regular font, which PDF producers do
+    /// when no bold variant of the font is available.
+    pub fn is_synthetic_bold(&self) -> bool {
+        self.flags().contains(TextCharFlags::BOLD) && !self.font().is_bold()
+    }
+
+    /// Whether the character is italic, either set in an italic font or slanted.
+    pub fn is_italic(&self) -> bool {
+        self.font().is_italic() || self.is_slanted()
+    }
+
+    /// Whether the character is a regular font slanted to look italic.
+    pub fn is_synthetic_italic(&self) -> bool {
+        !self.font().is_italic() && self.is_slanted()
+    }
+
+    /// Whether the glyph is sheared, its vertical sides leaning relative to its baseline.
+    fn is_slanted(&self) -> bool {
+        let Quad { ul, ur, ll, .. } = self.quad();
+        let (bx, by) = (ur.x - ul.x, ur.y - ul.y);
+        let (sx, sy) = (ul.x - ll.x, ul.y - ll.y);
+        let (base, side) = ((bx * bx + by * by).sqrt(), (sx * sx + sy * sy).sqrt());
+        if base == 0.0 || side == 0.0 {
+            return false;
+        }
+        // Sine of the angle between the side and the normal of the baseline, about 0.2 for a
+        // typical 12 degree slant
+        let sin = (bx * sx + by * sy) / (base * side);
+        sin > 0.1
+    }
 }
 
 #[derive(Debug)]
@@ -399,6 +761,110 @@ mod test {
         assert_eq!(hits.len(), 0);
     }
 
+    #[test]
+    fn test_text_page_text_runs() {
+        use crate::text_page::QuadGranularity;
+
+        let doc = Document::open("tests/files/dummy.pdf").unwrap();
+        let page0 = doc.load_page(0).unwrap();
+        let text_page = page0.to_text_page(TextPageOptions::empty()).unwrap();
+        let chars = text_page.text_runs(QuadGranularity::Char, 0.5);
+        let words = text_page.text_runs(QuadGranularity::Word, 0.5);
+        let lines = text_page.text_runs(QuadGranularity::Line, 0.5);
+        assert!(chars.len() > words.len() && words.len() >= lines.len());
+        let dummy = words.iter().find(|run| run.text == "Dummy").unwrap();
+        assert!((dummy.quad.ul.x - 56.8).abs() < 0.01);
+        assert!((dummy.quad.ur.x - 115.85159).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_text_char_style() {
+        use crate::pdf::{PageOptions, PdfDocument};
+        use crate::text_page::{CharPosition, TextCharFlags, TextRenderMode};
+
+        let doc = Document::open("tests/files/dummy.pdf").unwrap();
+        let text_page = doc
+            .load_page(0)
+            .unwrap()
+            .to_text_page(TextPageOptions::empty())
+            .unwrap();
+        let block = text_page.blocks().next().unwrap();
+        let line = block.lines().next().unwrap();
+        let ch = line.chars().next().unwrap();
+        assert_eq!(ch.render_mode(), TextRenderMode::Fill);
+        assert_eq!(ch.argb() & 0xffffff, 0);
+        assert!(ch.is_bold() && !ch.is_synthetic_bold());
+        assert!(!ch.is_italic());
+        assert!(!ch.flags().contains(TextCharFlags::CLIPPED));
+        assert!(line
+            .char_positions()
+            .iter()
+            .all(|&p| p == CharPosition::Baseline));
+
+        let doc = PdfDocument::from_html(
+            "<p>E = mc<sup>2</sup> and H<sub>2</sub>O are <i>famous</i></p>",
+            "",
+            &PageOptions::new(),
+        )
+        .unwrap();
+        let text_page = doc
+            .load_page(0)
+            .unwrap()
+            .to_text_page(TextPageOptions::empty())
+            .unwrap();
+        let mut found = Vec::new();
+        for block in text_page.blocks() {
+            for line in block.lines() {
+                for (ch, position) in line.chars().zip(line.char_positions()) {
+                    found.push((ch.char().unwrap(), position, ch.is_italic()));
+                }
+            }
+        }
+        assert!(found.contains(&('2', CharPosition::Superscript, false)));
+        assert!(found.contains(&('2', CharPosition::Subscript, false)));
+        assert!(found.contains(&('f', CharPosition::Baseline, true)));
+        assert!(found.contains(&('E', CharPosition::Baseline, false)));
+    }
+
+    #[test]
+    fn test_text_page_styled_spans() {
+        use crate::text_page::TextRenderMode;
+
+        let doc = Document::open("tests/files/dummy.pdf").unwrap();
+        let text_page = doc
+            .load_page(0)
+            .unwrap()
+            .to_text_page(TextPageOptions::PRESERVE_IMAGES)
+            .unwrap();
+        let spans = text_page.styled_spans();
+        assert_eq!(spans.len(), 1);
+        assert_eq!(spans[0].text, "Dummy PDF file");
+        assert!(spans[0].font_name.contains("Arial"));
+        assert_eq!(spans[0].render_mode, TextRenderMode::Fill);
+        assert!(!spans[0].invisible && !spans[0].likely_ocr);
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_text_page_json() {
+        use crate::text_page::{QuadGranularity, TextRun};
+        use crate::Quad;
+
+        let doc = Document::open("tests/files/dummy.pdf").unwrap();
+        let page0 = doc.load_page(0).unwrap();
+        let hits = page0.search("Dummy", 1).unwrap();
+        let json = serde_json::to_string(&hits).unwrap();
+        let quads: Vec<Quad> = serde_json::from_str(&json).unwrap();
+        assert_eq!(quads, &*hits);
+
+        let text_page = page0.to_text_page(TextPageOptions::empty()).unwrap();
+        let runs = text_page.text_runs(QuadGranularity::Line, 0.5);
+        let json = serde_json::to_string(&runs).unwrap();
+        assert!(json.starts_with(r#"[{"text":"Dummy PDF file","quad":{"ul":{"x":"#));
+        let parsed: Vec<TextRun> = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, runs);
+    }
+
     #[test]
     fn test_text_page_cb_search() {
         let doc = Document::open("tests/files/dummy.pdf").unwrap();
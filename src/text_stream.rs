@@ -0,0 +1,151 @@
+use crate::{
+    ColorParams, Colorspace, Device, DisplayList, Error, Matrix, NativeDevice, Page, StrokeState,
+    Text,
+};
+
+/// A run of text in one font, as drawn by a page.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct StreamedSpan {
+    pub text: String,
+    pub font_name: String,
+    /// Font size in device space.
+    pub size: f32,
+    /// The text rendering matrix of the first character, from glyph space to device space.
+    pub matrix: Matrix,
+    /// Fill or stroke color as `0xAARRGGBB`, 0 for invisible text.
+    pub argb: u32,
+}
+
+/// A device passing the text drawn to it on to a callback, span by span.
+struct SpanDevice<F> {
+    callback: F,
+}
+
+impl<F: FnMut(StreamedSpan)> SpanDevice<F> {
+    fn emit(&mut self, text: &Text, ctm: Matrix, argb: u32) {
+        for span in text.spans() {
+            let mut chars = String::new();
+            let mut origin = None;
+            for item in span.items() {
+                origin.get_or_insert((item.x(), item.y()));
+                // Ligatures map one glyph to several characters, the glyphs after the first
+                // have no character of their own
+                if let Some(c) = u32::try_from(item.ucs()).ok().and_then(char::from_u32) {
+                    chars.push(c);
+                }
+            }
+            let Some((x, y)) = origin else {
+                continue;
+            };
+            let trm = span.trm();
+            let matrix = Matrix::new(trm.a, trm.b, trm.c, trm.d, x, y) * ctm.clone();
+            (self.callback)(StreamedSpan {
+                text: chars,
+                font_name: span.font().name().to_owned(),
+                size: (matrix.a * matrix.d - matrix.b * matrix.c).abs().sqrt(),
+                matrix,
+                argb,
+            });
+        }
+    }
+}
+
+/// `color` in `cs` as `0xAARRGGBB`.
+fn argb(cs: &Colorspace, color: &[f32], alpha: f32, cp: ColorParams) -> u32 {
+    let rgb = cs
+        .convert_color(color, &Colorspace::device_rgb(), None, cp)
+        .unwrap_or_else(|_| vec![0.0; 3]);
+    let byte = |v: f32| (v.clamp(0.0, 1.0) * 255.0).round() as u32;
+    (byte(alpha) << 24) | (byte(rgb[0]) << 16) | (byte(rgb[1]) << 8) | byte(rgb[2])
+}
+
+impl<F: FnMut(StreamedSpan)> NativeDevice for SpanDevice<F> {
+    fn fill_text(
+        &mut self,
+        text: &Text,
+        cmt: Matrix,
+        color_space: &Colorspace,
+        color: &[f32],
+        alpha: f32,
+        cp: ColorParams,
+    ) {
+        self.emit(text, cmt, argb(color_space, color, alpha, cp));
+    }
+
+    fn stroke_text(
+        &mut self,
+        text: &Text,
+        _stroke_state: &StrokeState,
+        cmt: Matrix,
+        color_space: &Colorspace,
+        color: &[f32],
+        alpha: f32,
+        cp: ColorParams,
+    ) {
+        self.emit(text, cmt, argb(color_space, color, alpha, cp));
+    }
+
+    fn ignore_text(&mut self, text: &Text, cmt: Matrix) {
+        self.emit(text, cmt, 0);
+    }
+}
+
+impl Page {
+    /// Call `f` with every span of text as the page draws it, transformed by `ctm`.
+    ///
+    /// Unlike [`Page::to_text_page`] this doesn't collect the text of the page into blocks and
+    /// lines, so it takes little memory even for huge pages, but spans come in drawing order,
+    /// which need not be reading order, and a word may be split across spans.
+    pub fn stream_text_spans(
+        &self,
+        ctm: &Matrix,
+        f: impl FnMut(StreamedSpan),
+    ) -> Result<(), Error> {
+        let device = Device::from_native(SpanDevice { callback: f })?;
+        self.run(&device, ctm)
+    }
+}
+
+impl DisplayList {
+    /// Like [`Page::stream_text_spans`], for the text recorded in the list.
+    pub fn stream_text_spans(
+        &self,
+        ctm: &Matrix,
+        f: impl FnMut(StreamedSpan),
+    ) -> Result<(), Error> {
+        let device = Device::from_native(SpanDevice { callback: f })?;
+        self.run(&device, ctm, self.bounds())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{Document, Matrix};
+
+    #[test]
+    fn test_stream_text_spans() {
+        let doc = Document::open("tests/files/dummy.pdf").unwrap();
+        let page = doc.load_page(0).unwrap();
+        let mut spans = Vec::new();
+        page.stream_text_spans(&Matrix::IDENTITY, |span| spans.push(span))
+            .unwrap();
+        let text: String = spans.iter().map(|span| span.text.as_str()).collect();
+        // Whether spaces are drawn as glyphs depends on the producer
+        assert_eq!(text.replace(' ', ""), "DummyPDFfile");
+        let first = &spans[0];
+        assert!(first.size > 1.0, "{:?}", first);
+        assert!((56.0..58.0).contains(&first.matrix.e), "{:?}", first);
+        assert_eq!(first.argb, 0xff000000);
+
+        let mut count = 0;
+        page.to_display_list(false)
+            .unwrap()
+            .stream_text_spans(&Matrix::new_scale(2.0, 2.0), |span| {
+                assert!(span.matrix.e > 100.0);
+                count += 1;
+            })
+            .unwrap();
+        assert_eq!(count, spans.len());
+    }
+}
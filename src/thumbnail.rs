@@ -0,0 +1,97 @@
+use mupdf_sys::*;
+
+use crate::pdf::PdfDocument;
+use crate::{context, Colorspace, Error, FFIWrapper, Image, Matrix, Page, Pixmap};
+
+/// The size of a `width` x `height` area scaled down to fit in `max_dim` x `max_dim`.
+fn fit(width: u32, height: u32, max_dim: u32) -> (u32, u32) {
+    let largest = width.max(height);
+    if largest <= max_dim {
+        return (width, height);
+    }
+    let scale = |v: u32| ((v as u64 * max_dim as u64 + largest as u64 / 2) / largest as u64) as u32;
+    (scale(width).max(1), scale(height).max(1))
+}
+
+impl Page {
+    /// A thumbnail of the page, at most `max_dim` pixels wide and high.
+    ///
+    /// The `/Thumb` image of PDF pages is used when there is one, scaled down if it is too
+    /// large, in its own colorspace. Other pages are rendered in RGB.
+    pub fn thumbnail(&self, max_dim: u32) -> Result<Pixmap, Error> {
+        let image = unsafe {
+            ffi_try!(mupdf_load_page_thumbnail(
+                context(),
+                self.as_ptr() as *mut _
+            ))
+        }?;
+        if image.is_null() {
+            return self.render_thumbnail(max_dim);
+        }
+        let pixmap = unsafe { Image::from_raw(image) }.to_pixmap()?;
+        let (width, height) = fit(pixmap.width(), pixmap.height(), max_dim);
+        if (width, height) == (pixmap.width(), pixmap.height()) {
+            return Ok(pixmap);
+        }
+        pixmap.scale(width as f32, height as f32)
+    }
+
+    /// Render a thumbnail at twice its size, and scale that down for smooth edges.
+    fn render_thumbnail(&self, max_dim: u32) -> Result<Pixmap, Error> {
+        let bounds = self.bounds()?;
+        let largest = bounds.width().max(bounds.height());
+        let scale = if largest > 0.0 {
+            2.0 * max_dim as f32 / largest
+        } else {
+            1.0
+        };
+        let ctm = Matrix::new_scale(scale, scale);
+        let pixmap = self.to_pixmap(&ctm, &Colorspace::device_rgb(), false, true)?;
+        let (width, height) = fit(pixmap.width(), pixmap.height(), max_dim);
+        pixmap.scale(width as f32, height as f32)
+    }
+}
+
+impl PdfDocument {
+    /// Render a thumbnail of at most `max_dim` pixels wide and high for every page and store it
+    /// as the `/Thumb` image of the page, replacing existing thumbnails.
+    pub fn embed_thumbnails(&mut self, max_dim: u32) -> Result<(), Error> {
+        for page_no in 0..self.page_count()? {
+            let pixmap = self.load_page(page_no)?.render_thumbnail(max_dim)?;
+            let image = self.add_image(&Image::from_pixmap(&pixmap)?)?;
+            self.find_page(page_no)?.dict_put("Thumb", image)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::fit;
+    use crate::pdf::PdfDocument;
+
+    #[test]
+    fn test_fit() {
+        assert_eq!(fit(595, 842, 100), (71, 100));
+        assert_eq!(fit(842, 595, 100), (100, 71));
+        assert_eq!(fit(50, 60, 100), (50, 60));
+        assert_eq!(fit(1000, 1, 100), (100, 1));
+    }
+
+    #[test]
+    fn test_thumbnail() {
+        let mut doc = PdfDocument::open("tests/files/dummy.pdf").unwrap();
+        let rendered = doc.load_page(0).unwrap().thumbnail(128).unwrap();
+        assert_eq!((rendered.width(), rendered.height()), (91, 128));
+
+        doc.embed_thumbnails(64).unwrap();
+        let thumb = doc.find_page(0).unwrap().get_dict("Thumb").unwrap();
+        assert!(thumb.unwrap().is_stream().unwrap());
+
+        // The embedded thumbnail is reused, and not scaled up
+        let embedded = doc.load_page(0).unwrap().thumbnail(128).unwrap();
+        assert_eq!((embedded.width(), embedded.height()), (46, 64));
+        let smaller = doc.load_page(0).unwrap().thumbnail(32).unwrap();
+        assert_eq!((smaller.width(), smaller.height()), (23, 32));
+    }
+}
@@ -0,0 +1,412 @@
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
+
+use mupdf_sys::*;
+
+use crate::{context, Bitmap, Buffer, Colorspace, Error, Pixmap};
+
+const SHORT: u16 = 3;
+const LONG: u16 = 4;
+const RATIONAL: u16 = 5;
+
+const NEW_SUBFILE_TYPE: u16 = 254;
+const IMAGE_WIDTH: u16 = 256;
+const IMAGE_LENGTH: u16 = 257;
+const BITS_PER_SAMPLE: u16 = 258;
+const COMPRESSION: u16 = 259;
+const PHOTOMETRIC: u16 = 262;
+const STRIP_OFFSETS: u16 = 273;
+const SAMPLES_PER_PIXEL: u16 = 277;
+const ROWS_PER_STRIP: u16 = 278;
+const STRIP_BYTE_COUNTS: u16 = 279;
+const X_RESOLUTION: u16 = 282;
+const Y_RESOLUTION: u16 = 283;
+const PLANAR_CONFIGURATION: u16 = 284;
+const RESOLUTION_UNIT: u16 = 296;
+const PAGE_NUMBER: u16 = 297;
+const EXTRA_SAMPLES: u16 = 338;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TiffCompression {
+    None,
+    /// zlib compression, lossless and widely supported.
+    #[default]
+    Deflate,
+    /// Lempel-Ziv-Welch compression, lossless and supported by baseline TIFF readers.
+    Lzw,
+}
+
+struct Entry {
+    tag: u16,
+    kind: u16,
+    count: u32,
+    data: Vec<u8>,
+}
+
+impl Entry {
+    fn short(tag: u16, values: &[u16]) -> Self {
+        Self {
+            tag,
+            kind: SHORT,
+            count: values.len() as u32,
+            data: values.iter().flat_map(|v| v.to_le_bytes()).collect(),
+        }
+    }
+
+    fn long(tag: u16, value: u32) -> Self {
+        Self {
+            tag,
+            kind: LONG,
+            count: 1,
+            data: value.to_le_bytes().to_vec(),
+        }
+    }
+
+    fn rational(tag: u16, value: u32) -> Self {
+        Self {
+            tag,
+            kind: RATIONAL,
+            count: 1,
+            data: [value.to_le_bytes(), 1u32.to_le_bytes()].concat(),
+        }
+    }
+}
+
+/// An image file directory whose pointer to the next one is not known yet.
+struct PendingIfd {
+    data: Vec<u8>,
+    next_offset_at: usize,
+}
+
+/// Writes pages to a multi-page TIFF file, as used by scanners and for fax and archival.
+///
+/// Pages are written out as they are added, only the directory of the last page is held back
+/// until the next page or [`TiffWriter::finish`].
+pub struct TiffWriter<W: Write> {
+    out: W,
+    written: u32,
+    pages: u16,
+    pending: Option<PendingIfd>,
+}
+
+impl<W: Write> TiffWriter<W> {
+    pub fn new(out: W) -> Self {
+        Self {
+            out,
+            written: 0,
+            pages: 0,
+            pending: None,
+        }
+    }
+
+    /// Add a gray, RGB or CMYK page, with or without alpha.
+    pub fn add_pixmap(
+        &mut self,
+        pixmap: &Pixmap,
+        compression: TiffCompression,
+    ) -> Result<(), Error> {
+        let colors = pixmap.n() as usize - pixmap.alpha() as usize;
+        let photometric = match colors {
+            1 => 1,
+            3 if pixmap.color_space() != Some(Colorspace::device_bgr()) => 2,
+            4 => 5,
+            _ => {
+                return Err(Error::InvalidPixmap(
+                    "expected a gray, RGB or CMYK pixmap".to_owned(),
+                ))
+            }
+        };
+        let strip = match compression {
+            TiffCompression::None => pixmap.samples().to_vec(),
+            TiffCompression::Deflate => deflate(pixmap.samples())?,
+            TiffCompression::Lzw => lzw(pixmap.samples()),
+        };
+        let mut entries = vec![
+            Entry::long(IMAGE_WIDTH, pixmap.width()),
+            Entry::long(IMAGE_LENGTH, pixmap.height()),
+            Entry::short(BITS_PER_SAMPLE, &vec![8; pixmap.n() as usize]),
+            Entry::short(
+                COMPRESSION,
+                &[match compression {
+                    TiffCompression::None => 1,
+                    TiffCompression::Deflate => 8,
+                    TiffCompression::Lzw => 5,
+                }],
+            ),
+            Entry::short(PHOTOMETRIC, &[photometric]),
+            Entry::short(SAMPLES_PER_PIXEL, &[pixmap.n() as u16]),
+            Entry::short(PLANAR_CONFIGURATION, &[1]),
+        ];
+        if pixmap.alpha() {
+            // MuPDF's pixmaps are premultiplied, that is associated alpha
+            entries.push(Entry::short(EXTRA_SAMPLES, &[1]));
+        }
+        self.add_page(entries, pixmap.height(), pixmap.resolution(), strip)
+    }
+
+    /// Add a bilevel page, compressed with CCITT Group 4 as fax images are.
+    pub fn add_bitmap(&mut self, bitmap: &Bitmap) -> Result<(), Error> {
+        if bitmap.n() != 1 {
+            return Err(Error::InvalidPixmap(
+                "expected a single component bitmap".to_owned(),
+            ));
+        }
+        let strip = read_buffer(bitmap.to_ccitt_g4()?)?;
+        let entries = vec![
+            Entry::long(IMAGE_WIDTH, bitmap.width()),
+            Entry::long(IMAGE_LENGTH, bitmap.height()),
+            Entry::short(BITS_PER_SAMPLE, &[1]),
+            Entry::short(COMPRESSION, &[4]),
+            // Set bits are ink
+            Entry::short(PHOTOMETRIC, &[0]),
+            Entry::short(SAMPLES_PER_PIXEL, &[1]),
+        ];
+        self.add_page(entries, bitmap.height(), bitmap.resolution(), strip)
+    }
+
+    /// Write the directory of the last page and return the output.
+    pub fn finish(mut self) -> Result<W, Error> {
+        let Some(pending) = self.pending.take() else {
+            return Err(Error::Io(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "a TIFF file needs at least one page",
+            )));
+        };
+        self.out.write_all(&pending.data)?;
+        self.out.flush()?;
+        Ok(self.out)
+    }
+
+    pub fn page_count(&self) -> usize {
+        self.pages as usize
+    }
+
+    fn add_page(
+        &mut self,
+        mut entries: Vec<Entry>,
+        height: u32,
+        (x_res, y_res): (i32, i32),
+        mut strip: Vec<u8>,
+    ) -> Result<(), Error> {
+        let strip_len = u32::try_from(strip.len())?;
+        if strip.len() % 2 == 1 {
+            // Directories must start on a word boundary
+            strip.push(0);
+        }
+        let strip_offset = match &self.pending {
+            Some(pending) => self.position(pending.data.len())?,
+            None => 8,
+        };
+        let ifd_offset = strip_offset
+            .checked_add(u32::try_from(strip.len())?)
+            .ok_or_else(too_large)?;
+
+        entries.extend([
+            // A page of a multi-page document
+            Entry::long(NEW_SUBFILE_TYPE, 2),
+            Entry::long(ROWS_PER_STRIP, height),
+            Entry::long(STRIP_OFFSETS, strip_offset),
+            Entry::long(STRIP_BYTE_COUNTS, strip_len),
+            Entry::rational(X_RESOLUTION, x_res.max(1) as u32),
+            Entry::rational(Y_RESOLUTION, y_res.max(1) as u32),
+            Entry::short(RESOLUTION_UNIT, &[2]),
+            // The page count isn't known yet, 0 leaves it unspecified
+            Entry::short(PAGE_NUMBER, &[self.pages, 0]),
+        ]);
+        let ifd = encode_ifd(entries, ifd_offset)?;
+
+        match self.pending.take() {
+            Some(mut pending) => {
+                let at = pending.next_offset_at;
+                pending.data[at..at + 4].copy_from_slice(&ifd_offset.to_le_bytes());
+                self.write(&pending.data)?;
+            }
+            None => {
+                self.write(b"II*\0")?;
+                self.write(&ifd_offset.to_le_bytes())?;
+            }
+        }
+        self.write(&strip)?;
+        self.pending = Some(ifd);
+        self.pages = self.pages.checked_add(1).ok_or_else(too_large)?;
+        Ok(())
+    }
+
+    fn position(&self, extra: usize) -> Result<u32, Error> {
+        self.written
+            .checked_add(u32::try_from(extra)?)
+            .ok_or_else(too_large)
+    }
+
+    fn write(&mut self, data: &[u8]) -> Result<(), Error> {
+        self.out.write_all(data)?;
+        self.written = self.position(data.len())?;
+        Ok(())
+    }
+}
+
+fn too_large() -> Error {
+    Error::Io(io::Error::new(
+        io::ErrorKind::InvalidInput,
+        "TIFF files are limited to 4 GiB",
+    ))
+}
+
+/// Lay out a directory at `offset`, followed by the values too large to fit in their entries.
+fn encode_ifd(mut entries: Vec<Entry>, offset: u32) -> Result<PendingIfd, Error> {
+    entries.sort_by_key(|e| e.tag);
+    let next_offset_at = 2 + 12 * entries.len();
+    let mut data = Vec::with_capacity(next_offset_at + 4);
+    let mut values = Vec::new();
+    let values_offset = offset
+        .checked_add(u32::try_from(next_offset_at + 4)?)
+        .ok_or_else(too_large)?;
+    data.extend((entries.len() as u16).to_le_bytes());
+    for entry in &entries {
+        data.extend(entry.tag.to_le_bytes());
+        data.extend(entry.kind.to_le_bytes());
+        data.extend(entry.count.to_le_bytes());
+        if entry.data.len() <= 4 {
+            let mut value = [0; 4];
+            value[..entry.data.len()].copy_from_slice(&entry.data);
+            data.extend(value);
+        } else {
+            let at = values_offset
+                .checked_add(u32::try_from(values.len())?)
+                .ok_or_else(too_large)?;
+            data.extend(at.to_le_bytes());
+            values.extend(&entry.data);
+            if values.len() % 2 == 1 {
+                values.push(0);
+            }
+        }
+    }
+    data.extend(0u32.to_le_bytes());
+    data.extend(values);
+    Ok(PendingIfd {
+        data,
+        next_offset_at,
+    })
+}
+
+fn deflate(data: &[u8]) -> Result<Vec<u8>, Error> {
+    let inner = unsafe { ffi_try!(mupdf_deflate(context(), data.as_ptr(), data.len())) }?;
+    read_buffer(unsafe { Buffer::from_raw(inner) })
+}
+
+/// TIFF's flavor of LZW: codes of 9 to 12 bits, most significant bit first, switching to wider
+/// codes one code early.
+fn lzw(data: &[u8]) -> Vec<u8> {
+    const CLEAR: u16 = 256;
+    const END: u16 = 257;
+    const FIRST: u16 = 258;
+    const LAST: u16 = 4094;
+
+    let mut out = Vec::with_capacity(data.len() / 2);
+    let mut bits = 0u32;
+    let mut pending = 0;
+    let mut put = |code: u16, width: u32| {
+        bits = (bits << width) | code as u32;
+        pending += width;
+        while pending >= 8 {
+            pending -= 8;
+            out.push((bits >> pending) as u8);
+        }
+    };
+
+    let mut table = HashMap::new();
+    let mut next = FIRST;
+    let mut width = 9;
+    put(CLEAR, width);
+    if let Some((&first, rest)) = data.split_first() {
+        let mut prefix = first as u16;
+        for &byte in rest {
+            if let Some(&code) = table.get(&(prefix, byte)) {
+                prefix = code;
+                continue;
+            }
+            put(prefix, width);
+            if next == LAST {
+                put(CLEAR, width);
+                table.clear();
+                next = FIRST;
+                width = 9;
+            } else {
+                table.insert((prefix, byte), next);
+                next += 1;
+                if next >= 1 << width {
+                    width += 1;
+                }
+            }
+            prefix = byte as u16;
+        }
+        put(prefix, width);
+        // The reader adds an entry for the last code too
+        next += 1;
+        if next == LAST {
+            put(CLEAR, width);
+            width = 9;
+        } else if next >= 1 << width {
+            width += 1;
+        }
+    }
+    put(END, width);
+    if pending > 0 {
+        out.push((bits << (8 - pending)) as u8);
+    }
+    out
+}
+
+fn read_buffer(mut buf: Buffer) -> Result<Vec<u8>, Error> {
+    let mut data = Vec::with_capacity(buf.len());
+    buf.read_to_end(&mut data)?;
+    Ok(data)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{TiffCompression, TiffWriter};
+    use crate::{Bitmap, Colorspace, Pixmap};
+
+    #[test]
+    fn test_tiff_writer() {
+        let mut gray = Pixmap::new_with_w_h(&Colorspace::device_gray(), 200, 100, false).unwrap();
+        gray.clear_with(200).unwrap();
+        gray.set_resolution(144, 144);
+        let mut rgb = Pixmap::new_with_w_h(&Colorspace::device_rgb(), 30, 20, true).unwrap();
+        rgb.clear().unwrap();
+        let bitmap = Bitmap::from_pixmap(&gray).unwrap();
+
+        let mut writer = TiffWriter::new(Vec::new());
+        writer.add_pixmap(&gray, TiffCompression::Deflate).unwrap();
+        writer.add_pixmap(&rgb, TiffCompression::None).unwrap();
+        writer.add_bitmap(&bitmap).unwrap();
+        writer.add_pixmap(&gray, TiffCompression::Lzw).unwrap();
+        assert_eq!(writer.page_count(), 4);
+        let tiff = writer.finish().unwrap();
+        assert_eq!(&tiff[..4], b"II*\0");
+
+        #[cfg(feature = "img")]
+        {
+            let doc = crate::Document::open_image(&tiff, "image/tiff").unwrap();
+            assert_eq!(doc.page_count().unwrap(), 4);
+            let bounds = doc.load_page(0).unwrap().bounds().unwrap();
+            assert_eq!((bounds.width(), bounds.height()), (100.0, 50.0));
+            for page_no in [0, 3] {
+                let pixmap = doc
+                    .load_page(page_no)
+                    .unwrap()
+                    .to_pixmap(
+                        &crate::Matrix::new_scale(2.0, 2.0),
+                        &Colorspace::device_gray(),
+                        false,
+                        false,
+                    )
+                    .unwrap();
+                assert!(pixmap.samples().iter().all(|&v| v.abs_diff(200) <= 2));
+            }
+        }
+
+        assert!(TiffWriter::new(Vec::new()).finish().is_err());
+    }
+}
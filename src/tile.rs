@@ -0,0 +1,93 @@
+use std::sync::Arc;
+
+use crate::{Colorspace, Device, DisplayList, Error, IRect, Matrix, Pixmap};
+
+/// A region of a page rendered at some scale, keeping the page's recorded display list so other
+/// regions and scales can be rendered without interpreting the page again.
+///
+/// Cloning is cheap, the display list is shared.
+#[derive(Debug, Clone)]
+pub struct TileInfo {
+    list: Arc<DisplayList>,
+    scale: f32,
+    viewport: IRect,
+    colorspace: Colorspace,
+    alpha: bool,
+}
+
+impl TileInfo {
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+
+    /// The rendered area, in device pixels at [`TileInfo::scale`].
+    pub fn viewport(&self) -> IRect {
+        self.viewport
+    }
+
+    pub fn display_list(&self) -> &DisplayList {
+        &self.list
+    }
+
+    /// Map the viewport to the equivalent area at `new_scale`, e.g. to keep the same part of the
+    /// page in view while zooming.
+    pub fn viewport_at(&self, new_scale: f32) -> IRect {
+        let f = new_scale / self.scale;
+        IRect::new(
+            (self.viewport.x0 as f32 * f).floor() as i32,
+            (self.viewport.y0 as f32 * f).floor() as i32,
+            (self.viewport.x1 as f32 * f).ceil() as i32,
+            (self.viewport.y1 as f32 * f).ceil() as i32,
+        )
+    }
+
+    pub(crate) fn render(
+        list: Arc<DisplayList>,
+        scale: f32,
+        viewport: IRect,
+        cs: &Colorspace,
+        alpha: bool,
+    ) -> Result<(Pixmap, Self), Error> {
+        let mut pixmap = Pixmap::new(
+            cs,
+            viewport.x0,
+            viewport.y0,
+            viewport.width(),
+            viewport.height(),
+            alpha,
+        )?;
+        if alpha {
+            pixmap.clear()?;
+        } else {
+            pixmap.clear_with(255)?;
+        }
+        {
+            let device = Device::from_pixmap(&pixmap)?;
+            // Only the parts of the list inside the viewport are drawn
+            list.run(&device, &Matrix::new_scale(scale, scale), viewport.into())?;
+        }
+        let info = Self {
+            list,
+            scale,
+            viewport,
+            colorspace: cs.clone(),
+            alpha,
+        };
+        Ok((pixmap, info))
+    }
+
+    /// Render only `viewport` at `new_scale` from the display list recorded for the tile,
+    /// without interpreting the page again.
+    ///
+    /// This is cheap enough to call every frame of a pinch-zoom gesture, with the viewport
+    /// typically computed by [`TileInfo::viewport_at`].
+    pub fn render_patch(&self, new_scale: f32, viewport: IRect) -> Result<(Pixmap, Self), Error> {
+        Self::render(
+            self.list.clone(),
+            new_scale,
+            viewport,
+            &self.colorspace,
+            self.alpha,
+        )
+    }
+}
@@ -0,0 +1,116 @@
+use std::ffi::{c_char, CStr, CString};
+use std::marker::PhantomData;
+
+use mupdf_sys::*;
+
+use crate::{context, Error};
+
+/// An XML document parsed by MuPDF, whitespace preserved.
+///
+/// Namespace prefixes are dropped from element names, so `<x:a>` is an element named `a`.
+#[derive(Debug)]
+pub(crate) struct XmlDocument {
+    inner: *mut fz_xml,
+}
+
+impl XmlDocument {
+    pub(crate) fn parse(xml: &[u8]) -> Result<Self, Error> {
+        unsafe { ffi_try!(mupdf_parse_xml(context(), xml.as_ptr(), xml.len())) }
+            .map(|inner| Self { inner })
+    }
+
+    /// The document element.
+    pub(crate) fn root(&self) -> Option<XmlNode<'_>> {
+        XmlNode::from_raw(unsafe { fz_xml_root(self.inner) })
+    }
+}
+
+impl Drop for XmlDocument {
+    fn drop(&mut self) {
+        if !self.inner.is_null() {
+            unsafe { fz_drop_xml(context(), self.inner) };
+        }
+    }
+}
+
+/// An element or run of text of an [`XmlDocument`].
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct XmlNode<'a> {
+    inner: *mut fz_xml,
+    _doc: PhantomData<&'a XmlDocument>,
+}
+
+fn to_str<'a>(s: *const c_char) -> Option<&'a str> {
+    if s.is_null() {
+        return None;
+    }
+    unsafe { CStr::from_ptr(s) }.to_str().ok()
+}
+
+impl<'a> XmlNode<'a> {
+    fn from_raw(inner: *mut fz_xml) -> Option<Self> {
+        (!inner.is_null()).then_some(Self {
+            inner,
+            _doc: PhantomData,
+        })
+    }
+
+    /// The name of an element, `None` for text.
+    pub(crate) fn name(&self) -> Option<&'a str> {
+        to_str(unsafe { fz_xml_tag(self.inner) })
+    }
+
+    pub(crate) fn attribute(&self, name: &str) -> Option<&'a str> {
+        let name = CString::new(name).ok()?;
+        to_str(unsafe { fz_xml_att(self.inner, name.as_ptr()) })
+    }
+
+    /// The text directly inside an element, or the text of a text node.
+    pub(crate) fn text(&self) -> String {
+        if let Some(text) = to_str(unsafe { fz_xml_text(self.inner) }) {
+            return text.to_owned();
+        }
+        self.nodes()
+            .filter(|node| node.name().is_none())
+            .map(|node| node.text())
+            .collect()
+    }
+
+    /// The elements and text nodes inside an element.
+    pub(crate) fn nodes(&self) -> impl Iterator<Item = XmlNode<'a>> {
+        let first = XmlNode::from_raw(unsafe { fz_xml_down(self.inner) });
+        std::iter::successors(first, |node| {
+            XmlNode::from_raw(unsafe { fz_xml_next(node.inner) })
+        })
+    }
+
+    /// The elements inside an element.
+    pub(crate) fn children(&self) -> impl Iterator<Item = XmlNode<'a>> {
+        self.nodes().filter(|node| node.name().is_some())
+    }
+
+    /// The first element inside an element named `name`.
+    pub(crate) fn child(&self, name: &str) -> Option<XmlNode<'a>> {
+        self.children().find(|node| node.name() == Some(name))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::XmlDocument;
+
+    #[test]
+    fn test_parse_xml() {
+        let doc = XmlDocument::parse(
+            b"<?xml version=\"1.0\"?>\n<!-- comment -->\n\
+              <x:a xmlns:x=\"urn:x\" b='1 &amp; 2'><c>t&lt;<![CDATA[<d>]]></c><e/></x:a>",
+        )
+        .unwrap();
+        let root = doc.root().unwrap();
+        assert_eq!(root.name(), Some("a"));
+        assert_eq!(root.attribute("b"), Some("1 & 2"));
+        assert_eq!(root.child("c").unwrap().text(), "t<<d>");
+        assert!(root.child("e").is_some());
+        assert_eq!(root.children().count(), 2);
+    }
+}
@@ -0,0 +1,22 @@
+use mupdf::{Context, Document};
+
+// The store is shared by every context in the process, so this is the only test in its binary
+#[test]
+fn test_store_shrink() {
+    let mut ctx = Context::new_with_store_limit(64 << 20).unwrap();
+    let empty = ctx.store_usage().unwrap();
+    assert_eq!(empty.max, 64 << 20);
+
+    Document::open("tests/files/multiple-images.pdf")
+        .unwrap()
+        .prefetch(0..1)
+        .unwrap();
+    let full = ctx.store_usage().unwrap();
+    assert!(full.size > empty.size);
+    assert!(full.size <= full.max);
+
+    assert!(ctx.shrink_store(50));
+    assert!(ctx.store_usage().unwrap().size <= full.size / 2);
+    ctx.empty_store();
+    assert_eq!(ctx.store_usage().unwrap().size, 0);
+}